@@ -0,0 +1,327 @@
+use std::ops::{Add, Mul};
+
+use glam::{Quat, Vec3};
+
+#[allow(unused_imports, reason = "used in doc")]
+use crate::nodes::animation_player::AnimationPlayer3D;
+
+/// how the values between two keyframes are found
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Interpolation {
+    /// keep the value of the previous keyframe until the next one
+    Step,
+    /// blend straight from one keyframe to the next, rotations are slerped
+    #[default]
+    Linear,
+    /// a hermite spline through the keyframes
+    ///
+    /// every keyframe is stored as three values, the in tangent, the value and the out tangent,
+    /// the same as gltf
+    CubicSpline,
+}
+
+/// the values of a [`Channel`] at each of its keyframe times
+#[derive(Clone, Debug, PartialEq)]
+pub enum Keyframes {
+    Translation(Vec<Vec3>),
+    Rotation(Vec<Quat>),
+    Scale(Vec<Vec3>),
+    /// the weight of every morph target of the meshes at the target for each keyframe
+    Weights(Vec<Vec<f32>>),
+}
+
+/// a value sampled from a [`Channel`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChannelValue {
+    Translation(Vec3),
+    Rotation(Quat),
+    Scale(Vec3),
+    Weights(Vec<f32>),
+}
+
+/// animates one part of the transform of one node or the morph weights of its meshes
+#[derive(Clone, Debug, PartialEq)]
+pub struct Channel {
+    /// `/` separated names from the [`AnimationPlayer3D`] to the animated node, empty animates
+    /// the player itself
+    pub target: String,
+    /// the time in seconds of each keyframe in increasing order
+    pub times: Vec<f32>,
+    pub keyframes: Keyframes,
+    pub interpolation: Interpolation,
+}
+
+impl Channel {
+    /// a linear channel moving `target`
+    pub fn translation(target: impl Into<String>, times: Vec<f32>, values: Vec<Vec3>) -> Self {
+        Self::new(target, times, Keyframes::Translation(values))
+    }
+
+    /// a linear channel rotating `target`
+    pub fn rotation(target: impl Into<String>, times: Vec<f32>, values: Vec<Quat>) -> Self {
+        Self::new(target, times, Keyframes::Rotation(values))
+    }
+
+    /// a linear channel scaling `target`
+    pub fn scale(target: impl Into<String>, times: Vec<f32>, values: Vec<Vec3>) -> Self {
+        Self::new(target, times, Keyframes::Scale(values))
+    }
+
+    /// a linear channel blending the morph targets of `target`, see
+    /// [`MeshInstance3D::morph_weights`](crate::prelude::MeshInstance3D::morph_weights)
+    ///
+    /// `target` can be a mesh instance or a node whose children are mesh instances
+    pub fn weights(target: impl Into<String>, times: Vec<f32>, values: Vec<Vec<f32>>) -> Self {
+        Self::new(target, times, Keyframes::Weights(values))
+    }
+
+    fn new(target: impl Into<String>, times: Vec<f32>, keyframes: Keyframes) -> Self {
+        Self {
+            target: target.into(),
+            times,
+            keyframes,
+            interpolation: Interpolation::Linear,
+        }
+    }
+
+    /// use a different interpolation, see [`Interpolation::CubicSpline`] for how its keyframes
+    /// are laid out
+    pub fn with_interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    /// the time of the last keyframe
+    pub fn duration(&self) -> f32 {
+        self.times.last().copied().unwrap_or(0.0)
+    }
+
+    /// the value at `time` seconds. before the first and after the last keyframe the value of
+    /// that keyframe is kept
+    ///
+    /// `None` if the channel has no keyframes or fewer values than times
+    pub fn sample(&self, time: f32) -> Option<ChannelValue> {
+        let (times, interpolation) = (&self.times, self.interpolation);
+        match &self.keyframes {
+            Keyframes::Translation(values) => {
+                sample(times, values, interpolation, time).map(ChannelValue::Translation)
+            }
+            Keyframes::Rotation(values) => {
+                sample(times, values, interpolation, time).map(ChannelValue::Rotation)
+            }
+            Keyframes::Scale(values) => {
+                sample(times, values, interpolation, time).map(ChannelValue::Scale)
+            }
+            // every weight is sampled on its own
+            Keyframes::Weights(values) => (0..values.first()?.len())
+                .map(|target| {
+                    let weights: Vec<f32> = values
+                        .iter()
+                        .map(|keyframe| keyframe.get(target).copied().unwrap_or(0.0))
+                        .collect();
+                    sample(times, &weights, interpolation, time)
+                })
+                .collect::<Option<Vec<f32>>>()
+                .map(ChannelValue::Weights),
+        }
+    }
+}
+
+/// a named set of channels played together by an [`AnimationPlayer3D`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AnimationClip {
+    pub name: String,
+    pub channels: Vec<Channel>,
+}
+
+impl AnimationClip {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            channels: Vec::new(),
+        }
+    }
+
+    /// add a channel to the clip
+    pub fn with_channel(mut self, channel: Channel) -> Self {
+        self.channels.push(channel);
+        self
+    }
+
+    /// how long the clip is in seconds, the end of its longest channel
+    pub fn duration(&self) -> f32 {
+        self.channels
+            .iter()
+            .map(Channel::duration)
+            .fold(0.0, f32::max)
+    }
+}
+
+trait Keyframe: Copy + Add<Output = Self> + Mul<f32, Output = Self> {
+    fn interpolate(self, other: Self, t: f32) -> Self;
+
+    /// fix up a value made by adding keyframes together
+    fn finish(self) -> Self {
+        self
+    }
+}
+
+impl Keyframe for f32 {
+    fn interpolate(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Keyframe for Vec3 {
+    fn interpolate(self, other: Self, t: f32) -> Self {
+        self.lerp(other, t)
+    }
+}
+
+impl Keyframe for Quat {
+    fn interpolate(self, other: Self, t: f32) -> Self {
+        self.slerp(other, t)
+    }
+
+    fn finish(self) -> Self {
+        self.normalize()
+    }
+}
+
+fn sample<T: Keyframe>(
+    times: &[f32],
+    values: &[T],
+    interpolation: Interpolation,
+    time: f32,
+) -> Option<T> {
+    let stride = match interpolation {
+        Interpolation::CubicSpline => 3,
+        _ => 1,
+    };
+    // the value is in the middle of a cubic spline keyframe
+    let value = |key: usize| values.get(key * stride + stride / 2).copied();
+    let last = times.len().checked_sub(1)?;
+
+    let next = times.partition_point(|key_time| *key_time <= time);
+    if next == 0 {
+        return value(0);
+    }
+    if next > last {
+        return value(last);
+    }
+
+    let previous = next - 1;
+    let span = times[next] - times[previous];
+    let t = match span > 0.0 {
+        true => (time - times[previous]) / span,
+        false => 0.0,
+    };
+
+    match interpolation {
+        Interpolation::Step => value(previous),
+        Interpolation::Linear => Some(value(previous)?.interpolate(value(next)?, t).finish()),
+        Interpolation::CubicSpline => {
+            let (start, end) = (value(previous)?, value(next)?);
+            // tangents are per second so they are scaled to the length of the span
+            let out_tangent = *values.get(previous * 3 + 2)? * span;
+            let in_tangent = *values.get(next * 3)? * span;
+
+            let (t2, t3) = (t * t, t * t * t);
+            let blended = start * (2.0 * t3 - 3.0 * t2 + 1.0)
+                + out_tangent * (t3 - 2.0 * t2 + t)
+                + end * (-2.0 * t3 + 3.0 * t2)
+                + in_tangent * (t3 - t2);
+            Some(blended.finish())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translation(interpolation: Interpolation, values: Vec<Vec3>) -> Channel {
+        Channel::translation("bone", vec![0.0, 1.0, 2.0], values).with_interpolation(interpolation)
+    }
+
+    fn sampled(channel: &Channel, time: f32) -> Vec3 {
+        match channel.sample(time) {
+            Some(ChannelValue::Translation(value)) => value,
+            other => panic!("expected a translation, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_step_and_linear() {
+        let values = vec![Vec3::ZERO, Vec3::X, Vec3::Y];
+
+        let step = translation(Interpolation::Step, values.clone());
+        assert_eq!(sampled(&step, 0.5), Vec3::ZERO);
+        assert_eq!(sampled(&step, 1.5), Vec3::X);
+
+        let linear = translation(Interpolation::Linear, values);
+        assert_eq!(sampled(&linear, 0.5), Vec3::X * 0.5);
+        assert_eq!(sampled(&linear, 1.5), Vec3::new(0.5, 0.5, 0.0));
+        // the ends are held
+        assert_eq!(sampled(&linear, -1.0), Vec3::ZERO);
+        assert_eq!(sampled(&linear, 5.0), Vec3::Y);
+    }
+
+    #[test]
+    fn test_cubic_spline_hits_keyframes() {
+        // in tangent, value, out tangent for each keyframe
+        let values = vec![
+            Vec3::ZERO,
+            Vec3::ZERO,
+            Vec3::X * 3.0,
+            Vec3::X,
+            Vec3::X,
+            Vec3::ZERO,
+            Vec3::ZERO,
+            Vec3::ZERO,
+            Vec3::ZERO,
+        ];
+        let cubic = translation(Interpolation::CubicSpline, values);
+
+        assert_eq!(sampled(&cubic, 0.0), Vec3::ZERO);
+        assert_eq!(sampled(&cubic, 1.0), Vec3::X);
+        // the out tangent pushes the curve past a straight line early in the span
+        assert!(sampled(&cubic, 0.25).x > 0.25);
+        assert_eq!(sampled(&cubic, 9.0), Vec3::ZERO);
+    }
+
+    #[test]
+    fn test_weights_sampled_per_target() {
+        let weights =
+            Channel::weights("face", vec![0.0, 1.0], vec![vec![0.0, 1.0], vec![1.0, 0.0]]);
+        assert_eq!(
+            weights.sample(0.25),
+            Some(ChannelValue::Weights(vec![0.25, 0.75]))
+        );
+        assert_eq!(
+            weights.sample(2.0),
+            Some(ChannelValue::Weights(vec![1.0, 0.0]))
+        );
+    }
+
+    #[test]
+    fn test_rotation_stays_normalized() {
+        let rotation = Channel::rotation(
+            "bone",
+            vec![0.0, 1.0],
+            vec![
+                Quat::IDENTITY,
+                Quat::from_rotation_y(std::f32::consts::FRAC_PI_2),
+            ],
+        );
+        let Some(ChannelValue::Rotation(value)) = rotation.sample(0.5) else {
+            panic!("expected a rotation");
+        };
+        assert!(value.is_normalized());
+        assert!(value.abs_diff_eq(Quat::from_rotation_y(std::f32::consts::FRAC_PI_4), 1e-5));
+        assert_eq!(
+            AnimationClip::new("turn").with_channel(rotation).duration(),
+            1.0
+        );
+    }
+}
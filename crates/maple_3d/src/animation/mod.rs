@@ -0,0 +1,35 @@
+//! skeletal and keyframe animation
+//!
+//! an [`AnimationClip`] is a set of [`Channel`]s that each move, rotate or scale one node found
+//! by its path from an [`AnimationPlayer3D`]. the player samples the clip it is playing every
+//! frame and writes the values into the transforms of the targets.
+//!
+//! meshes with a [`Skin`] are bent by their joint nodes in the vertex shader, so animating the
+//! joints animates the mesh. [`Channel::weights`] blends the morph targets of meshes instead,
+//! for things like faces. gltf files with skins, morph targets or animations load them all.
+//!
+//! # Example
+//! ```no_run
+//! # use maple_engine::prelude::*;
+//! # use maple_3d::prelude::*;
+//! # use glam::Vec3;
+//! # let scene = Scene::default();
+//! let bob = AnimationClip::new("bob").with_channel(Channel::translation(
+//!     "body",
+//!     vec![0.0, 0.5, 1.0],
+//!     vec![Vec3::ZERO, Vec3::Y, Vec3::ZERO],
+//! ));
+//!
+//! scene
+//!     .spawn(AnimationPlayer3D::builder().clip(bob).autoplay("bob"))
+//!     .spawn_child_with_name("body", Empty::default());
+//! ```
+
+mod clip;
+mod skin;
+
+pub use clip::*;
+pub use skin::*;
+
+#[allow(unused_imports, reason = "used in doc")]
+use crate::nodes::animation_player::AnimationPlayer3D;
@@ -0,0 +1,200 @@
+use std::sync::Arc;
+
+use glam::Mat4;
+use maple_engine::{GameContext, Scene, scene::NodeId};
+
+use crate::nodes::{animation_player::AnimationPlayer3D, mesh_instance::MeshInstance3D};
+
+/// the joints that deform a skinned [`MeshInstance3D`]
+///
+/// the joints of each vertex index into this list. a joint's matrix moves a vertex from the
+/// mesh's bind pose to where the joint node is now, so moving the joint nodes, usually with an
+/// [`AnimationPlayer3D`], bends the mesh
+#[derive(Clone, Debug, Default)]
+pub struct Skin {
+    /// names from the nearest [`AnimationPlayer3D`] above the mesh to each joint
+    paths: Option<Arc<[String]>>,
+    joints: Vec<NodeId>,
+    inverse_bind_matrices: Arc<[Mat4]>,
+}
+
+impl Skin {
+    /// a skin moved by nodes already in the scene
+    ///
+    /// the node ids are kept when the mesh is instanced from a scene asset, use
+    /// [`Skin::from_paths`] for skins in assets
+    pub fn new(joints: Vec<NodeId>, inverse_bind_matrices: impl Into<Arc<[Mat4]>>) -> Self {
+        Self {
+            paths: None,
+            joints,
+            inverse_bind_matrices: inverse_bind_matrices.into(),
+        }
+    }
+
+    /// a skin whose joints are found by their `/` separated path from the nearest
+    /// [`AnimationPlayer3D`] above the mesh, or from a root node if there is none
+    ///
+    /// the joints are looked up once the mesh is added to a scene
+    pub fn from_paths(
+        paths: impl Into<Arc<[String]>>,
+        inverse_bind_matrices: impl Into<Arc<[Mat4]>>,
+    ) -> Self {
+        Self {
+            paths: Some(paths.into()),
+            joints: Vec::new(),
+            inverse_bind_matrices: inverse_bind_matrices.into(),
+        }
+    }
+
+    /// the joint nodes, empty until the paths of a [`Skin::from_paths`] skin are found
+    pub fn joints(&self) -> &[NodeId] {
+        &self.joints
+    }
+
+    pub fn inverse_bind_matrices(&self) -> &[Mat4] {
+        &self.inverse_bind_matrices
+    }
+
+    /// true once every joint has a node
+    pub fn is_resolved(&self) -> bool {
+        match &self.paths {
+            Some(paths) => self.joints.len() == paths.len(),
+            None => true,
+        }
+    }
+
+    /// the matrix of each joint in the space of the mesh
+    ///
+    /// `mesh` is the render space matrix of the mesh the skin belongs to. `None` if the skin
+    /// isn't resolved or a joint was despawned. the mesh must not be locked while this runs
+    pub fn joint_matrices(&self, scene: &Scene, mesh: &Mat4) -> Option<Vec<Mat4>> {
+        if !self.is_resolved() {
+            return None;
+        }
+
+        let to_mesh = mesh.inverse();
+        self.joints
+            .iter()
+            .enumerate()
+            .map(|(index, joint)| {
+                let joint =
+                    scene.with_transform(*joint, |transform| *transform.render_space().matrix())?;
+                let inverse_bind = self
+                    .inverse_bind_matrices
+                    .get(index)
+                    .copied()
+                    .unwrap_or(Mat4::IDENTITY);
+                Some(to_mesh * joint * inverse_bind)
+            })
+            .collect()
+    }
+
+    fn resolve(&mut self, scene: &Scene, mesh: NodeId) {
+        let Some(paths) = self.paths.clone() else {
+            return;
+        };
+
+        let mut ancestor = scene.parent_id(mesh);
+        let player = loop {
+            match ancestor {
+                Some(id) if scene.get::<AnimationPlayer3D>(id).is_some() => break Some(id),
+                Some(id) => ancestor = scene.parent_id(id),
+                None => break None,
+            }
+        };
+
+        let joints: Option<Vec<NodeId>> = paths
+            .iter()
+            .map(|path| match player {
+                Some(player) => scene.get_child_id_by_path(player, path),
+                None => scene.get_id_by_path(path),
+            })
+            .collect();
+        if let Some(joints) = joints {
+            self.joints = joints;
+        }
+    }
+}
+
+/// find the joint nodes of skins that were added to the scene by path
+pub(crate) fn update_skins(ctx: &GameContext) {
+    // resolving only reads the hierarchy so the mesh can stay locked
+    ctx.scene
+        .for_each_with_id(&mut |id, instance: &mut MeshInstance3D| {
+            if let Some(skin) = &mut instance.skin
+                && !skin.is_resolved()
+            {
+                skin.resolve(&ctx.scene, id);
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+    use maple_engine::{Buildable, Builder, nodes::Empty};
+
+    use super::*;
+
+    #[test]
+    fn test_bind_pose_is_identity() {
+        let ctx = GameContext::new();
+        let player = ctx
+            .scene
+            .spawn_with_name("rig", AnimationPlayer3D::builder().build())
+            .id();
+        let root = ctx.scene.spawn_as_child_with_name(
+            "root",
+            Empty::builder().position(Vec3::Y).build(),
+            player,
+        );
+        let root_id = root.id();
+        root.spawn_child_with_name("tip", Empty::builder().position(Vec3::Y).build());
+
+        // the inverse bind matrices undo where the joints are when the mesh was bound
+        let inverse_binds = vec![
+            Mat4::from_translation(-Vec3::Y),
+            Mat4::from_translation(-Vec3::Y * 2.0),
+        ];
+        let mesh = ctx
+            .scene
+            .spawn_as_child(
+                MeshInstance3D::builder()
+                    .skin(Skin::from_paths(
+                        vec!["root".to_string(), "root/tip".to_string()],
+                        inverse_binds,
+                    ))
+                    .build(),
+                player,
+            )
+            .id();
+
+        ctx.scene.sync_world_transform();
+        update_skins(&ctx);
+
+        let skin = ctx
+            .scene
+            .get::<MeshInstance3D>(mesh)
+            .and_then(|mesh| mesh.read().skin.clone())
+            .unwrap();
+        assert!(skin.is_resolved());
+        assert_eq!(skin.joints()[0], root_id);
+
+        let matrices = skin.joint_matrices(&ctx.scene, &Mat4::IDENTITY).unwrap();
+        for matrix in matrices {
+            assert!(matrix.abs_diff_eq(Mat4::IDENTITY, 1e-5));
+        }
+
+        // moving a joint moves the vertices bound to it
+        ctx.scene.with_transform(root_id, |transform| {
+            transform.position = Vec3::new(1.0, 1.0, 0.0);
+        });
+        ctx.scene.sync_world_transform();
+        let matrices = skin.joint_matrices(&ctx.scene, &Mat4::IDENTITY).unwrap();
+        assert!(
+            matrices[1]
+                .transform_point3(Vec3::Y * 2.0)
+                .abs_diff_eq(Vec3::new(1.0, 2.0, 0.0), 1e-5)
+        );
+    }
+}
@@ -3,7 +3,10 @@ use std::{
     collections::HashMap,
     marker::PhantomData,
     ops::{Deref, DerefMut},
-    sync::{Arc, OnceLock},
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicBool, Ordering},
+    },
 };
 
 use maple_engine::{
@@ -42,6 +45,56 @@ pub trait GpuMateiral: SendSync + AsAnyGpu {
     fn descriptor_set(&self) -> DescriptorSet;
 }
 
+/// one draw of every mesh using a material, see [`MaterialInstance::passes`]
+///
+/// the `None` fields use the material's own shaders and state
+#[derive(Debug, Clone, Copy)]
+pub struct MaterialPass {
+    /// passes with the same label share pipelines so passes with different shaders need
+    /// different labels
+    pub label: &'static str,
+    pub vertex_shader: Option<ShaderSource>,
+    pub fragment_shader: Option<ShaderSource>,
+    pub cull_mode: Option<CullMode>,
+    pub alpha_mode: Option<AlphaMode>,
+    /// `None` writes depth unless the pass blends
+    pub depth_write: Option<bool>,
+    /// how many times the pass is drawn, each draw gets its index and this count as `pass_index`
+    /// and `pass_count` of the mesh data in the shader
+    pub repeat: u32,
+    /// whether the meshes drawn by this pass are drawn into shadow maps
+    pub casts_shadows: bool,
+}
+
+impl MaterialPass {
+    /// the material drawn once with its own shaders and state
+    pub const BASE: Self = Self {
+        label: "base",
+        vertex_shader: None,
+        fragment_shader: None,
+        cull_mode: None,
+        alpha_mode: None,
+        depth_write: None,
+        repeat: 1,
+        casts_shadows: true,
+    };
+
+    /// a pass drawn once with the material's shaders and state that doesn't cast shadows
+    pub fn new(label: &'static str) -> Self {
+        Self {
+            label,
+            casts_shadows: false,
+            ..Self::BASE
+        }
+    }
+}
+
+impl Default for MaterialPass {
+    fn default() -> Self {
+        Self::BASE
+    }
+}
+
 /// provides an interface to get data from the material implementations
 ///
 /// see: [`super::materials::PbrMaterial`]
@@ -55,6 +108,17 @@ where
     fn fragment_shader() -> ShaderSource
     where
         Self: Sized;
+    /// the fragment shader writing the material into the g-buffer of
+    /// [`maple_renderer::types::render_config::RenderPath::Deferred`], it outputs the
+    /// `GBufferOutput` of `#include "maple_3d/gbuffer.wgsl"`
+    ///
+    /// materials without one, with blending or with more than their base pass are drawn forward
+    fn gbuffer_shader() -> Option<ShaderSource>
+    where
+        Self: Sized,
+    {
+        None
+    }
     fn alpha_mode(&self) -> AlphaMode;
     fn casts_shadows(&self) -> bool {
         true
@@ -71,41 +135,61 @@ where
     fn cull_mode(&self) -> CullMode {
         CullMode::Back
     }
+
+    /// the draws of each mesh using the material in the order they are drawn
+    ///
+    /// e.g. an inverted hull outline that pushes the vertices out along their normal in its own
+    /// vertex shader before the material itself:
+    ///
+    /// ```rust, ignore
+    /// fn passes(&self) -> Vec<MaterialPass> {
+    ///     vec![
+    ///         MaterialPass {
+    ///             vertex_shader: Some(include_str!("outline.vert.wgsl").into()),
+    ///             fragment_shader: Some(include_str!("outline.frag.wgsl").into()),
+    ///             cull_mode: Some(CullMode::Front),
+    ///             ..MaterialPass::new("outline")
+    ///         },
+    ///         MaterialPass::BASE,
+    ///     ]
+    /// }
+    /// ```
+    ///
+    /// or 16 shells for fur that the shader pushes out by `pass_index / pass_count`:
+    /// `MaterialPass { alpha_mode: Some(AlphaMode::Blend), repeat: 16, ..MaterialPass::new("fur") }`
+    ///
+    /// every pass of every mesh is drawn before the next pass
+    fn passes(&self) -> Vec<MaterialPass> {
+        vec![MaterialPass::BASE]
+    }
+
     fn pipeline(
         &self,
         rcx: &RenderContext,
         pass_info: &PassInfo,
         pipeline_layout: PipelineLayout,
         shader: GraphicsShader,
+        pass: &MaterialPass,
     ) -> RenderPipeline {
-        let (blend_mode, pipeline_alpha_mode) = match self.alpha_mode() {
-            AlphaMode::Opaque | AlphaMode::Mask => (
-                DepthMode::Texture(DepthStencilOptions {
-                    format: TextureFormat::Depth32,
-                    compare: DepthCompare::Less,
-                    write_enabled: true,
-                    depth_bias: None,
-                }),
-                PipelineAlphaMode::Opaque,
-            ),
-            AlphaMode::Blend => (
-                DepthMode::Texture(DepthStencilOptions {
-                    format: TextureFormat::Depth32,
-                    compare: DepthCompare::Less,
-                    write_enabled: false,
-                    depth_bias: None,
-                }),
-                PipelineAlphaMode::Blend,
-            ),
+        let alpha_mode = pass.alpha_mode.unwrap_or(self.alpha_mode());
+        let pipeline_alpha_mode = match alpha_mode {
+            AlphaMode::Opaque | AlphaMode::Mask => PipelineAlphaMode::Opaque,
+            AlphaMode::Blend => PipelineAlphaMode::Blend,
         };
+        let depth = DepthMode::Texture(DepthStencilOptions {
+            format: TextureFormat::Depth32,
+            compare: DepthCompare::Less,
+            write_enabled: pass.depth_write.unwrap_or(alpha_mode != AlphaMode::Blend),
+            depth_bias: None,
+        });
         rcx.device()
             .create_pipeline(maple_renderer::core::PipelineCreateInfo {
                 label: Some(self.label()),
                 layout: pipeline_layout,
                 shader,
                 color_formats: &pass_info.color_formats,
-                depth: blend_mode,
-                cull_mode: self.cull_mode(),
+                depth,
+                cull_mode: pass.cull_mode.unwrap_or(self.cull_mode()),
                 alpha_mode: pipeline_alpha_mode,
                 sample_count: pass_info.sample_count,
                 vertex_buffer_layout: Some(Vertex::buffer_layout()),
@@ -218,12 +302,14 @@ bitflags::bitflags! {
         const TRANSPARENT = 0x1;
         const CULL_BACK = 0x2;
         const CULL_FRONT = 0x4;
+        const NO_DEPTH_WRITE = 0x8;
     }
 }
 
 #[derive(Default)]
 pub struct MaterialPipelineCache {
-    pub pipelines: HashMap<TypeId, HashMap<MaterialPipelineKey, RenderPipeline>>,
+    /// the pipelines of each material type by pass label and key
+    pub pipelines: HashMap<TypeId, HashMap<(&'static str, MaterialPipelineKey), RenderPipeline>>,
 
     pub shadow_descrptor: HashMap<AssetId, DescriptorSet>,
 }
@@ -238,8 +324,11 @@ pub struct Material {
     // store buffer data in a way that allows the most freedom for material implementations
     instance: Box<dyn MaterialInstance>,
     gpu_material: OnceLock<Arc<dyn GpuMateiral>>,
+    /// the instance was borrowed mutably since its buffer was last written
+    changed: AtomicBool,
     vertex_shader: ShaderSource,
     fragment_shader: ShaderSource,
+    gbuffer_shader: Option<ShaderSource>,
 }
 
 impl Material {
@@ -247,8 +336,10 @@ impl Material {
         Self {
             instance: Box::new(instance),
             gpu_material: OnceLock::new(),
+            changed: AtomicBool::new(false),
             vertex_shader: T::vertex_shader(),
             fragment_shader: T::fragment_shader(),
+            gbuffer_shader: T::gbuffer_shader(),
         }
     }
 
@@ -260,29 +351,41 @@ impl Material {
         self.instance.as_any().downcast_ref()
     }
 
+    /// the material's buffer is written again the next time it is drawn
     pub fn get_instance_mut<T: MaterialInstance + 'static>(&mut self) -> Option<&mut T> {
-        self.instance.as_any_mut().downcast_mut()
+        let instance = self.instance.as_any_mut().downcast_mut();
+        if instance.is_some() {
+            *self.changed.get_mut() = true;
+        }
+        instance
     }
 
     pub fn material_key(&self) -> TypeId {
         self.instance.type_id()
     }
 
-    pub fn pipeline_key(&self) -> MaterialPipelineKey {
+    /// the state of the pipeline that draws `pass` of the material
+    pub fn pipeline_key(&self, pass: &MaterialPass) -> MaterialPipelineKey {
         let mut key = MaterialPipelineKey::default();
+        let cull_mode = self.pass_cull_mode(pass);
+        let alpha_mode = self.pass_alpha_mode(pass);
 
-        if self.instance.cull_mode() == CullMode::Back {
+        if cull_mode == CullMode::Back {
             key |= MaterialPipelineKey::CULL_BACK;
         }
 
-        if self.instance.cull_mode() == CullMode::Front {
+        if cull_mode == CullMode::Front {
             key |= MaterialPipelineKey::CULL_FRONT;
         }
 
-        if self.instance.alpha_mode() == AlphaMode::Blend {
+        if alpha_mode == AlphaMode::Blend {
             key |= MaterialPipelineKey::TRANSPARENT;
         }
 
+        if !pass.depth_write.unwrap_or(alpha_mode != AlphaMode::Blend) {
+            key |= MaterialPipelineKey::NO_DEPTH_WRITE;
+        }
+
         key
     }
 
@@ -294,10 +397,26 @@ impl Material {
         self.fragment_shader
     }
 
+    pub fn gbuffer_shader(&self) -> Option<ShaderSource> {
+        self.gbuffer_shader
+    }
+
     pub fn alpha_mode(&self) -> AlphaMode {
         self.instance.alpha_mode()
     }
 
+    pub fn passes(&self) -> Vec<MaterialPass> {
+        self.instance.passes()
+    }
+
+    pub fn pass_alpha_mode(&self, pass: &MaterialPass) -> AlphaMode {
+        pass.alpha_mode.unwrap_or(self.alpha_mode())
+    }
+
+    pub fn pass_cull_mode(&self, pass: &MaterialPass) -> CullMode {
+        pass.cull_mode.unwrap_or(self.cull_mode())
+    }
+
     pub fn alpha_info(&self) -> Option<MaterialAlphaInfo> {
         self.instance.alpha_info()
     }
@@ -316,9 +435,10 @@ impl Material {
         pass_info: &PassInfo,
         pipeline_layout: PipelineLayout,
         shader: GraphicsShader,
+        pass: &MaterialPass,
     ) -> RenderPipeline {
         self.instance
-            .pipeline(rcx, pass_info, pipeline_layout, shader)
+            .pipeline(rcx, pass_info, pipeline_layout, shader, pass)
     }
 
     pub fn descriptor_set(
@@ -344,9 +464,17 @@ impl Material {
 
     pub fn update_buffer(&self, rcx: &RenderContext) {
         if let Some(gpu_material) = self.gpu_material.get() {
+            self.changed.store(false, Ordering::Relaxed);
             self.instance.update(rcx, gpu_material.deref());
         }
     }
+
+    /// write the buffer again only if the instance changed since it was last written
+    pub fn update_changed_buffer(&self, rcx: &RenderContext) {
+        if self.changed.load(Ordering::Relaxed) {
+            self.update_buffer(rcx);
+        }
+    }
 }
 
 impl Asset for Material {
@@ -366,3 +494,68 @@ impl MaterialLoader {
         Self { device }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::materials::PbrMaterial;
+
+    #[test]
+    fn test_passes_override_the_pipeline_key() {
+        let material = Material::new(PbrMaterial::default());
+        let passes = material.passes();
+        assert_eq!(passes.len(), 1);
+        assert_eq!(
+            material.pipeline_key(&passes[0]),
+            MaterialPipelineKey::CULL_BACK
+        );
+
+        let outline = MaterialPass {
+            cull_mode: Some(CullMode::Front),
+            ..MaterialPass::new("outline")
+        };
+        assert_eq!(
+            material.pipeline_key(&outline),
+            MaterialPipelineKey::CULL_FRONT
+        );
+        assert!(!outline.casts_shadows);
+
+        // blending passes don't write depth unless asked to
+        let shells = MaterialPass {
+            alpha_mode: Some(AlphaMode::Blend),
+            repeat: 16,
+            ..MaterialPass::new("fur")
+        };
+        assert_eq!(
+            material.pipeline_key(&shells),
+            MaterialPipelineKey::CULL_BACK
+                | MaterialPipelineKey::TRANSPARENT
+                | MaterialPipelineKey::NO_DEPTH_WRITE
+        );
+        let written = MaterialPass {
+            depth_write: Some(true),
+            ..shells
+        };
+        assert!(
+            !material
+                .pipeline_key(&written)
+                .contains(MaterialPipelineKey::NO_DEPTH_WRITE)
+        );
+    }
+
+    #[test]
+    fn test_mutable_instances_mark_the_material_changed() {
+        let mut material = Material::new(PbrMaterial::default());
+        assert!(!material.changed.load(Ordering::Relaxed));
+
+        material
+            .get_instance_mut::<PbrMaterial>()
+            .unwrap()
+            .normal_scale = 0.5;
+        assert!(material.changed.load(Ordering::Relaxed));
+        assert_eq!(
+            material.get_instance::<PbrMaterial>().unwrap().normal_scale,
+            0.5
+        );
+    }
+}
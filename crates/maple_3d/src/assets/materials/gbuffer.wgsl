@@ -0,0 +1,31 @@
+// how surfaces are packed into the g-buffer of the deferred path, written by the g-buffer shaders
+// of materials and read by the deferred lighting pass
+//
+// - albedo  rgba8   rgb albedo, a how much of the indirect light reaches the surface
+// - normal  rgba16f xy the shading normal, zw the vertex normal, both octahedron encoded
+// - surface rgba8   r metallic, g roughness, b 1 if the surface is lit
+// - emission rgba16f rgb emitted light, unlit surfaces put their whole color here
+
+struct GBufferOutput {
+    @location(0) albedo: vec4<f32>,
+    @location(1) normal: vec4<f32>,
+    @location(2) surface: vec4<f32>,
+    @location(3) emission: vec4<f32>,
+}
+
+// a unit vector folded onto the octahedron and flattened into -1..1
+fn octahedron_encode(n: vec3<f32>) -> vec2<f32> {
+    let folded = n.xy / (abs(n.x) + abs(n.y) + abs(n.z));
+    if n.z >= 0.0 {
+        return folded;
+    }
+    return (1.0 - abs(folded.yx)) * select(vec2<f32>(-1.0), vec2<f32>(1.0), folded >= vec2<f32>(0.0));
+}
+
+fn octahedron_decode(e: vec2<f32>) -> vec3<f32> {
+    var n = vec3<f32>(e, 1.0 - abs(e.x) - abs(e.y));
+    let t = saturate(-n.z);
+    n.x += select(t, -t, n.x >= 0.0);
+    n.y += select(t, -t, n.y >= 0.0);
+    return normalize(n);
+}
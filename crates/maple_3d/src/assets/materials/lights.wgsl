@@ -0,0 +1,431 @@
+// the lights the render graph collects, their group 2 bindings and shadow lookups shared by the
+// forward pbr shader and the deferred lighting pass
+//
+// `camera` has to be declared by the shader including this for the cascade selection
+
+#include "maple/common.wgsl"
+#include "maple/shadow.wgsl"
+
+struct DirectLight {
+    color: vec4<f32>,
+    direction: vec4<f32>,
+    intensity: f32,
+    shadow_index: i32,
+    cascade_level: i32,
+    bias: f32,
+    cascade_split: vec4<f32>,
+    light_space_matrices: array<mat4x4<f32>, 4>,
+    cascade_texel_size: array<f32, 4>,
+    // angular diameter in degrees
+    size: f32,
+    normal_bias: f32,
+    shadow_filter: u32,
+    // the part of the shadow map width the light was rendered into
+    shadow_scale: f32,
+}
+
+struct PointLight {
+    color: vec4<f32>,
+    pos: vec4<f32>,
+    intensity: f32,
+    shadow_index: i32,
+    far_plane: f32,
+    bias: f32,
+    // the part of each cube face the shadow was rendered into
+    shadow_scale: f32,
+    shadow_filter: u32,
+    size: f32,
+}
+
+struct SpotLight {
+    color: vec4<f32>,
+    pos: vec4<f32>,
+    direction: vec4<f32>,
+    light_space_matrix: mat4x4<f32>,
+    intensity: f32,
+    range: f32,
+    // cosines of the cone angles
+    inner_cos: f32,
+    outer_cos: f32,
+    shadow_index: i32,
+    bias: f32,
+    near_plane: f32,
+    size: f32,
+    shadow_filter: u32,
+    // the part of the shadow map width the light was rendered into
+    shadow_scale: f32,
+}
+
+const AREA_LIGHT_DISK: u32 = 1u;
+
+struct AreaLight {
+    color: vec4<f32>,
+    pos: vec4<f32>,
+    // half the size of the shape along its axes
+    right: vec4<f32>,
+    up: vec4<f32>,
+    intensity: f32,
+    shape: u32,
+    two_sided: u32,
+}
+
+struct DirectLightBuffer {
+    len: i32,
+    lights: array<DirectLight>,
+}
+
+struct PointLightBuffer {
+    len: i32,
+    lights: array<PointLight>,
+}
+
+struct SpotLightBuffer {
+    len: i32,
+    lights: array<SpotLight>,
+}
+
+struct AreaLightBuffer {
+    len: i32,
+    lights: array<AreaLight>,
+}
+
+@group(2) @binding(0) var<storage, read> direct_light_buffer: DirectLightBuffer;
+@group(2) @binding(1) var<storage, read> point_light_buffer: PointLightBuffer;
+@group(2) @binding(2) var directional_shadow_maps: texture_depth_2d_array;
+@group(2) @binding(3) var point_shadow_maps: texture_depth_cube_array;
+@group(2) @binding(4) var shadow_sampler: sampler_comparison;
+@group(2) @binding(5) var shadow_sampler_linear: sampler;
+@group(2) @binding(6) var<storage, read> spot_light_buffer: SpotLightBuffer;
+@group(2) @binding(7) var spot_shadow_maps: texture_depth_2d_array;
+@group(2) @binding(8) var<storage, read> area_light_buffer: AreaLightBuffer;
+
+fn get_cascade_data(light: DirectLight, cascade_index: i32) -> mat4x4<f32> {
+    switch cascade_index {
+        case 0: { return light.light_space_matrices[0]; }
+        case 1: { return light.light_space_matrices[1]; }
+        case 2: { return light.light_space_matrices[2]; }
+        default: { return light.light_space_matrices[3]; }
+    }
+}
+
+fn get_cascade_split(light: DirectLight, cascade_index: i32) -> f32 {
+    switch cascade_index {
+        case 0: { return light.cascade_split[0]; }
+        case 1: { return light.cascade_split[1]; }
+        case 2: { return light.cascade_split[2]; }
+        default: { return light.cascade_split[3]; }
+    }
+}
+
+// the widest pcss searches and blurs go in texels, further taps miss small blockers
+const PCSS_MAX_RADIUS: f32 = 32.0;
+
+// filter a 2d shadow map with one of the SHADOW_FILTER_ values. pcss blurs by `pcss_radius` in uv
+fn filter_shadow_map(
+    shadow_map: texture_depth_2d_array,
+    uv: vec2<f32>,
+    depth: f32,
+    layer: i32,
+    shadow_filter: u32,
+    pcss_radius: f32,
+    rotation: mat2x2<f32>,
+) -> f32 {
+    switch shadow_filter {
+        case SHADOW_FILTER_HARD: {
+            return sample_shadow_hardware(shadow_map, shadow_sampler, uv, depth, layer);
+        }
+        case SHADOW_FILTER_PCF_3X3: {
+            return sample_shadow_pcf(shadow_map, shadow_sampler, uv, depth, layer, 1.0);
+        }
+        case SHADOW_FILTER_PCSS: {
+            return sample_shadow_poisson(shadow_map, shadow_sampler, uv, depth, layer, pcss_radius, rotation);
+        }
+        default: {
+            return sample_shadow_castano_thirteen(shadow_map, shadow_sampler, uv, depth, layer);
+        }
+    }
+}
+
+// sample a cascade texture
+fn sample_cascade_shadow(
+    light: DirectLight,
+    world_pos: vec3<f32>,
+    cascade_index: i32,
+    frag_coord: vec2<f32>,
+    surface_normal: vec3<f32>,
+) -> f32 {
+    // Transform to light space
+    let light_space_matrix = light.light_space_matrices[cascade_index];
+    let light_dir = normalize(-light.direction.xyz);
+
+    // Normal + depth offset bias applied in world space before projection
+    let normal_offset = light.normal_bias * light.cascade_texel_size[cascade_index] * surface_normal.xyz;
+    let depth_offset = light.bias * light_dir.xyz;
+    let offset_position = world_pos.xyz + normal_offset + depth_offset;
+
+    let light_space_pos = light_space_matrix * vec4<f32>(offset_position, 1.0);
+    var proj_coords = light_space_pos.xyz / light_space_pos.w;
+
+    // Transform XY to [0, 1] range for texture sampling
+    proj_coords.x = proj_coords.x * 0.5 + 0.5;
+    proj_coords.y = proj_coords.y * 0.5 + 0.5;
+    // Flip Y (shadow maps were upside down)
+    proj_coords.y = 1.0 - proj_coords.y;
+
+    if proj_coords.x < 0.0 || proj_coords.x > 1.0 || proj_coords.y < 0.0 || proj_coords.y > 1.0 || proj_coords.z > 1.0 {
+        return 1.0;
+    }
+
+    // the light only rendered into the top left of its layer
+    let shadow_uv = proj_coords.xy * light.shadow_scale;
+    let depth = proj_coords.z;
+    let shadow_layer = light.shadow_index * 4 + cascade_index;
+    let texel = 1.0 / f32(textureDimensions(directional_shadow_maps).x);
+    let rotation = poisson_rotation(frag_coord);
+
+    var pcss_radius = texel;
+    if light.shadow_filter == SHADOW_FILTER_PCSS {
+        // world units covered by the whole map and by its whole depth range
+        let uv_size = light.cascade_texel_size[cascade_index] / texel;
+        let depth_size = 1.0 / length(vec3<f32>(light_space_matrix[0].z, light_space_matrix[1].z, light_space_matrix[2].z));
+        let light_tan = tan(radians(light.size) * 0.5);
+
+        // the furthest a blocker can be and still hide part of the light is at the near plane
+        let search_radius = clamp(depth * depth_size * light_tan / uv_size, texel, texel * PCSS_MAX_RADIUS);
+        let blocker = find_shadow_blockers(
+            directional_shadow_maps,
+            shadow_sampler_linear,
+            shadow_uv,
+            depth,
+            shadow_layer,
+            search_radius,
+            rotation,
+        );
+        if blocker < 0.0 {
+            return 1.0;
+        }
+
+        let penumbra = (depth - blocker) * depth_size * light_tan / uv_size;
+        pcss_radius = clamp(penumbra, texel, texel * PCSS_MAX_RADIUS);
+    }
+
+    return filter_shadow_map(
+        directional_shadow_maps,
+        shadow_uv,
+        depth,
+        shadow_layer,
+        light.shadow_filter,
+        pcss_radius,
+        rotation,
+    );
+}
+
+// Calculate shadow factor for directional lights with cascade blending
+fn calculate_directional_shadow(light: DirectLight, world_pos: vec3<f32>, normal: vec3<f32>, frag_coord: vec2<f32>) -> f32 {
+    if light.shadow_index < 0 {
+        return 1.0; // No shadow
+    }
+
+    // Select cascade based on depth
+    let view_pos = camera.view * vec4<f32>(world_pos, 1.0);
+    let depth = abs(view_pos.z);
+
+    var cascade_index = light.cascade_level - 1;
+    var blend_factor = 0.0;
+
+    // Find which cascade we're in and calculate blend factor
+    for (var i = 0; i < light.cascade_level; i++) {
+        if depth < light.cascade_split[i] {
+            cascade_index = i;
+
+            if i > 0 {
+                let prev_split = select(0.0, light.cascade_split[i - 1], i > 0);
+                let cascade_range = light.cascade_split[i] - prev_split;
+                let blend_range = cascade_range * 0.1; // 10% transition zone
+                let distance_to_end = light.cascade_split[i] - depth;
+
+                if distance_to_end < blend_range {
+                    blend_factor = 1.0 - (distance_to_end / blend_range);
+                }
+            }
+            break;
+        }
+    }
+
+    let shadow = sample_cascade_shadow(light, world_pos, cascade_index, frag_coord, normal);
+
+    // Blend with next cascade if in transition zone
+    if blend_factor > 0.0 && cascade_index < light.cascade_level - 1 {
+        let next_shadow = sample_cascade_shadow(light, world_pos, cascade_index + 1, frag_coord, normal);
+        return mix(shadow, next_shadow, blend_factor);
+    }
+
+    return shadow;
+}
+
+fn calculate_point_shadow(light: PointLight, world_pos: vec3<f32>, frag_coord: vec2<f32>) -> f32 {
+    if light.shadow_index < 0 {
+        return 1.0; // No shadow
+    }
+
+    // Get vector from light to fragment
+    let light_to_frag = world_pos - light.pos.xyz;
+
+    let current_depth = length(light_to_frag);
+    let normalized_depth = current_depth / light.far_plane;
+
+    if normalized_depth > 1.0 {
+        return 1.0; // Beyond shadow range
+    }
+
+    // shadow maps are upside down
+    let flipped_dir = light_to_frag * vec3<f32>(1.0, -1.0, 1.0);
+    let face_size = f32(textureDimensions(point_shadow_maps).x);
+
+    let compare_depth = saturate(normalized_depth - light.bias);
+
+    if light.shadow_filter == SHADOW_FILTER_HARD {
+        let sample_dir = scale_point_shadow_direction(flipped_dir, light.shadow_scale, face_size);
+        return sample_point_shadow(
+            point_shadow_maps,
+            shadow_sampler,
+            sample_dir,
+            compare_depth,
+            light.shadow_index,
+        );
+    }
+
+    // offsets are angles from the light, a texel is about this wide at the middle of a face
+    let texel = 2.0 / (face_size * light.shadow_scale);
+    let rotation = poisson_rotation(frag_coord);
+
+    var radius = 2.5 * texel;
+    if light.shadow_filter == SHADOW_FILTER_PCF_3X3 {
+        radius = 1.5 * texel;
+    } else if light.shadow_filter == SHADOW_FILTER_PCSS {
+        // blockers right next to the bulb can hide it from far away so the widest area is searched
+        let blocker = find_point_shadow_blockers(
+            point_shadow_maps,
+            shadow_sampler_linear,
+            flipped_dir,
+            compare_depth,
+            light.shadow_index,
+            texel * PCSS_MAX_RADIUS,
+            rotation,
+            light.shadow_scale,
+            face_size,
+        );
+        if blocker < 0.0 {
+            return 1.0;
+        }
+
+        let blocker_depth = max(blocker * light.far_plane, 0.001);
+        let penumbra = 0.5 * light.size * (current_depth - blocker_depth) / (blocker_depth * current_depth);
+        radius = clamp(penumbra, texel, texel * PCSS_MAX_RADIUS);
+    }
+
+    return sample_point_shadow_poisson(
+        point_shadow_maps,
+        shadow_sampler,
+        flipped_dir,
+        compare_depth,
+        light.shadow_index,
+        radius,
+        rotation,
+        light.shadow_scale,
+        face_size,
+    );
+}
+
+// distance from a spot light along its direction for a depth in its shadow map
+fn linearize_spot_depth(light: SpotLight, depth: f32) -> f32 {
+    let near = light.near_plane;
+    let far = light.range;
+    return near * far / (far - depth * (far - near));
+}
+
+fn calculate_spot_shadow(light: SpotLight, world_pos: vec3<f32>, surface_normal: vec3<f32>, frag_coord: vec2<f32>) -> f32 {
+    if light.shadow_index < 0 {
+        return 1.0; // No shadow
+    }
+
+    // texels get bigger further from the light so the offset does too
+    let distance = length(light.pos.xyz - world_pos);
+    let offset_position = world_pos + surface_normal * light.bias * distance;
+
+    let light_space_pos = light.light_space_matrix * vec4<f32>(offset_position, 1.0);
+    var proj_coords = light_space_pos.xyz / light_space_pos.w;
+    // shadow maps are upside down
+    proj_coords.x = proj_coords.x * 0.5 + 0.5;
+    proj_coords.y = 0.5 - proj_coords.y * 0.5;
+
+    if light_space_pos.w <= 0.0 || proj_coords.x < 0.0 || proj_coords.x > 1.0 || proj_coords.y < 0.0 || proj_coords.y > 1.0 || proj_coords.z > 1.0 {
+        return 1.0;
+    }
+
+    // the light only rendered into the top left of its layer
+    let shadow_uv = proj_coords.xy * light.shadow_scale;
+    let texel = 1.0 / f32(textureDimensions(spot_shadow_maps).x);
+    let rotation = poisson_rotation(frag_coord);
+
+    var pcss_radius = texel;
+    if light.shadow_filter == SHADOW_FILTER_PCSS {
+        // the map is 2 * tan(outer angle) wide in uv at 1 unit from the light
+        let tan_outer = sqrt(1.0 - light.outer_cos * light.outer_cos) / light.outer_cos;
+        let light_uv = light.size * light.shadow_scale / (2.0 * tan_outer);
+        let receiver = linearize_spot_depth(light, proj_coords.z);
+
+        // the furthest a blocker can be and still hide part of the light is at the near plane
+        let search_radius = 0.5 * light_uv * (receiver - light.near_plane) / (receiver * light.near_plane);
+        let blocker = find_shadow_blockers(
+            spot_shadow_maps,
+            shadow_sampler_linear,
+            shadow_uv,
+            proj_coords.z,
+            light.shadow_index,
+            clamp(search_radius, texel, texel * PCSS_MAX_RADIUS),
+            rotation,
+        );
+        if blocker < 0.0 {
+            return 1.0;
+        }
+
+        let blocker_distance = linearize_spot_depth(light, blocker);
+        let penumbra = 0.5 * light_uv * (receiver - blocker_distance) / (blocker_distance * receiver);
+        pcss_radius = clamp(penumbra, texel, texel * PCSS_MAX_RADIUS);
+    }
+
+    return filter_shadow_map(
+        spot_shadow_maps,
+        shadow_uv,
+        proj_coords.z,
+        light.shadow_index,
+        light.shadow_filter,
+        pcss_radius,
+        rotation,
+    );
+}
+
+fn area_light_area(light: AreaLight) -> f32 {
+    let rect = 4.0 * length(light.right.xyz) * length(light.up.xyz);
+    return select(rect, rect * PI * 0.25, light.shape == AREA_LIGHT_DISK);
+}
+
+// the point of an area light's shape closest to `point` once it is projected onto the light's plane
+fn closest_point_on_area_light(light: AreaLight, point: vec3<f32>) -> vec3<f32> {
+    let half_size = vec2<f32>(length(light.right.xyz), length(light.up.xyz));
+    let right = light.right.xyz / max(half_size.x, 0.0001);
+    let up = light.up.xyz / max(half_size.y, 0.0001);
+
+    let local = point - light.pos.xyz;
+    var offset = vec2<f32>(dot(local, right), dot(local, up));
+    if light.shape == AREA_LIGHT_DISK {
+        // unevenly scaled disks are ellipses
+        let radius = length(offset / max(half_size, vec2<f32>(0.0001)));
+        offset = offset / max(radius, 1.0);
+    } else {
+        offset = clamp(offset, -half_size, half_size);
+    }
+
+    return light.pos.xyz + right * offset.x + up * offset.y;
+}
@@ -1,3 +1,15 @@
 mod pbr_material;
 
 pub use pbr_material::*;
+
+use maple_renderer::shader_include::register_include;
+
+/// make the chunks the pbr, g-buffer and deferred lighting shaders share includable
+pub(crate) fn register_shader_includes() {
+    register_include("maple_3d/lights.wgsl", include_str!("lights.wgsl"));
+    register_include(
+        "maple_3d/pbr_surface.wgsl",
+        include_str!("pbr_surface.wgsl"),
+    );
+    register_include("maple_3d/gbuffer.wgsl", include_str!("gbuffer.wgsl"));
+}
@@ -13,11 +13,17 @@ struct CameraData {
 struct MeshData {
     model: mat4x4<f32>,
     normal_matrix: mat4x4<f32>,
+    joint_offset: u32,
+    joint_count: u32,
+    // the draw of the material pass, see MaterialPass::repeat
+    pass_index: u32,
+    pass_count: u32,
 }
 
 @group(0) @binding(0) var<uniform> scene: SceneData;
 @group(0) @binding(1) var<uniform> camera: CameraData;
 @group(1) @binding(0) var<storage, read> mesh_data: array<MeshData>;
+@group(1) @binding(1) var<storage, read> joints: array<mat4x4<f32>>;
 
 struct VertexInput {
     @builtin(instance_index) instance_index: u32,
@@ -26,6 +32,8 @@ struct VertexInput {
     @location(2) tex_uv: vec2<f32>,
     @location(3) tangent: vec3<f32>,
     @location(4) bitangent: vec3<f32>,
+    @location(5) joints: vec4<u32>,
+    @location(6) weights: vec4<f32>,
 }
 
 struct VertexOutput {
@@ -39,20 +47,41 @@ struct VertexOutput {
     @location(6) tangent_frag_pos: vec3<f32>,
 }
 
+// the joint matrices blended by the vertex weights, identity for meshes without a skin
+fn skin_matrix(data: MeshData, ids: vec4<u32>, weights: vec4<f32>) -> mat4x4<f32> {
+    if (data.joint_count == 0u) {
+        return mat4x4<f32>(
+            vec4<f32>(1.0, 0.0, 0.0, 0.0),
+            vec4<f32>(0.0, 1.0, 0.0, 0.0),
+            vec4<f32>(0.0, 0.0, 1.0, 0.0),
+            vec4<f32>(0.0, 0.0, 0.0, 1.0),
+        );
+    }
+
+    // out of range joints are clamped so bad data can't read another mesh's joints
+    let last = data.joint_count - 1u;
+    return joints[data.joint_offset + min(ids.x, last)] * weights.x
+        + joints[data.joint_offset + min(ids.y, last)] * weights.y
+        + joints[data.joint_offset + min(ids.z, last)] * weights.z
+        + joints[data.joint_offset + min(ids.w, last)] * weights.w;
+}
+
 @vertex
 fn main(input: VertexInput) -> VertexOutput {
     let mesh = mesh_data[input.instance_index];
+    let skin = skin_matrix(mesh, input.joints, input.weights);
+    let position = skin * vec4<f32>(input.position, 1.0);
 
     // Transform position to world space
-    let world_pos = (mesh.model * vec4<f32>(input.position, 1.0)).xyz;
+    let world_pos = (mesh.model * position).xyz;
 
     // Transform position to clip space
-    let clip_position = camera.VP * mesh.model * vec4<f32>(input.position, 1.0);
+    let clip_position = camera.VP * mesh.model * position;
 
     // Transform normals
-    let normal = normalize((mesh.normal_matrix * vec4<f32>(input.normal, 0.0)).xyz);
-    let tangent = normalize((mesh.normal_matrix * vec4<f32>(input.tangent, 0.0)).xyz);
-    let bitangent = normalize((mesh.normal_matrix * vec4<f32>(input.bitangent, 0.0)).xyz);
+    let normal = normalize((mesh.normal_matrix * skin * vec4<f32>(input.normal, 0.0)).xyz);
+    let tangent = normalize((mesh.normal_matrix * skin * vec4<f32>(input.tangent, 0.0)).xyz);
+    let bitangent = normalize((mesh.normal_matrix * skin * vec4<f32>(input.bitangent, 0.0)).xyz);
 
     // TBN
     let TBN = transpose(mat3x3<f32>(tangent, bitangent, normal));
@@ -0,0 +1,69 @@
+// the pbr material written into the g-buffer of the deferred path, the deferred lighting pass
+// shades it the same way pbr.frag.wgsl does
+
+#include "maple_3d/pbr_surface.wgsl"
+#include "maple_3d/gbuffer.wgsl"
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_pos: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) tex_coord: vec2<f32>,
+    @location(3) tangent: vec3<f32>,
+    @location(4) bitangent: vec3<f32>,
+    @location(5) tangent_view_pos: vec3<f32>,
+    @location(6) tangent_frag_pos: vec3<f32>,
+}
+
+@fragment
+fn main(in: VertexOutput) -> GBufferOutput {
+    // View direction in tangent space
+    let V = normalize(in.tangent_view_pos - in.tangent_frag_pos);
+
+    var tex_coords = in.tex_coord * material.texture_scale + material.texture_offset;
+    if material.parallax_steps > 0u && material.parallax_scale > 0.0 {
+        tex_coords = parallax_occlusion(tex_coords, V);
+    }
+
+    let base_color = sample_color(base_color_texture, base_color_sampler, tex_coords, SRGB_BASE_COLOR) * material.base_color_factor;
+    if material.alpha_mode == ALPHA_MODE_MASK && base_color.a < material.alpha_cutoff {
+        discard;
+    }
+
+    let emissive = sample_color(emissive_texture, emissive_sampler, tex_coords, SRGB_EMISSIVE).rgb * material.emissive_factor.rgb;
+    let N_geom = normalize(in.normal);
+
+    if material.unlit == 1u {
+        let encoded = octahedron_encode(N_geom);
+        return GBufferOutput(
+            vec4<f32>(base_color.rgb, 1.0),
+            vec4<f32>(encoded, encoded),
+            vec4<f32>(0.0, 1.0, 0.0, 1.0),
+            vec4<f32>(base_color.rgb + emissive, 1.0),
+        );
+    }
+
+    let metallic_roughness = textureSample(metallic_roughness_texture, metallic_roughness_sampler, tex_coords);
+    let metallic = metallic_roughness.b * material.metallic_factor;
+    let roughness = metallic_roughness.g * material.roughness_factor;
+
+    let tangent_normal = textureSample(normal_texture, normal_sampler, tex_coords).rgb * 2.0 - 1.0;
+    let N = normalize(vec3<f32>(tangent_normal.x * material.normal_scale, -tangent_normal.y * material.normal_scale, tangent_normal.z));
+    let TBN_world = mat3x3<f32>(normalize(in.tangent), normalize(in.bitangent), N_geom);
+    let world_normal = normalize(TBN_world * N);
+
+    // the same specular anti aliasing as the forward shader, the lighting pass can't take
+    // derivatives of the normal
+    let roughness_aa = length(fwidth(world_normal)) * 0.5;
+    let adjusted_roughness = clamp(roughness + roughness_aa, 0.045, 1.0);
+
+    let ao = textureSample(ambient_occlusion_texture, ambient_occlusion_sampler, tex_coords).r;
+    let ao_factor = mix(1.0, ao, material.ambient_occlusion_strength);
+
+    return GBufferOutput(
+        vec4<f32>(base_color.rgb, ao_factor),
+        vec4<f32>(octahedron_encode(world_normal), octahedron_encode(N_geom)),
+        vec4<f32>(metallic, adjusted_roughness, 1.0, 1.0),
+        vec4<f32>(emissive, 1.0),
+    );
+}
@@ -20,6 +20,11 @@ use crate::prelude::{Material, MaterialInstance};
 /// [`Self::metallic_factor`] and [`Self::roughness_factor`] based on glTF 2.0 metallic-roughness
 /// model
 ///
+/// lights are shaded with a Cook-Torrance BRDF (GGX distribution, Smith geometry and Schlick
+/// fresnel) and the environment of [`crate::nodes::environment::Environment`] adds image based ambient light.
+/// without an environment the scene's ambient is used as a uniform one. occlusion only darkens
+/// the ambient light
+///
 /// This is the engines default material used and can be made directly by a [`Color`] or [`AssetHandle<Texture>`] through the asset system
 ///
 /// # Example
@@ -37,7 +42,8 @@ use crate::prelude::{Material, MaterialInstance};
 /// ```
 #[derive(Debug, Clone)]
 pub struct PbrMaterial {
-    /// the color the material appears
+    /// the color the material appears in linear space like gltf's factors, use
+    /// [`Color::to_linear`] for colors picked in srgb
     ///
     /// the alpha channel meaning depends on [`Self::alpha_mode`]
     /// - [`AlphaMode::Opaque`] : ignored
@@ -52,6 +58,8 @@ pub struct PbrMaterial {
 
     /// Texture used for the base color of the material. see [`Self::base_color_factor`]
     ///
+    /// its colors are read as srgb and its alpha as linear
+    ///
     /// Default: [`Option::None`]
     pub base_color_texture: Option<AssetHandle<Texture>>,
 
@@ -72,12 +80,14 @@ pub struct PbrMaterial {
 
     /// texture used for metallic and roughness multipied by the factors for each
     ///
-    /// metallic is on blue channel and roughness is on green.
+    /// metallic is on blue channel and roughness is on green. read as linear like the normal and
+    /// occlusion textures
     ///
     /// Default: [`Option::None`]
     pub metallic_roughness_texture: Option<AssetHandle<Texture>>,
 
-    /// scale the object normals are scaled by usually unused
+    /// how strongly [`Self::normal_texture`] bends the surface normal, `0.0` flattens it and
+    /// values above `1.0` exaggerate it
     ///
     /// Default: `1.0`
     pub normal_scale: f32,
@@ -91,6 +101,26 @@ pub struct PbrMaterial {
     /// Default: [`Option::None`]
     pub normal_texture: Option<AssetHandle<Texture>>,
 
+    /// height map used for parallax occlusion mapping, white is the top of the surface and black
+    /// is [`Self::parallax_scale`] below it. only the red channel is read
+    ///
+    /// texture coordinates are shifted by the height seen from the camera so bumps hide what is
+    /// behind them. the mesh isn't changed so its outline and shadows stay flat
+    ///
+    /// Default: [`Option::None`]
+    pub height_texture: Option<AssetHandle<Texture>>,
+
+    /// how deep [`Self::height_texture`] goes in texture coordinates, `0.0` turns parallax off
+    ///
+    /// Default: `0.05`
+    pub parallax_scale: f32,
+
+    /// the most steps taken through [`Self::height_texture`] for each pixel. surfaces seen head on
+    /// take a quarter of these, more steps remove layering on steep views but cost more
+    ///
+    /// Default: `16`
+    pub parallax_steps: u32,
+
     /// how much ambient light this material is exposed to
     ///
     /// more occluded sections may appear darker so this provides a almost free way for more light
@@ -104,7 +134,7 @@ pub struct PbrMaterial {
     /// Default: [`Option::None`]
     pub occlusion_texture: Option<AssetHandle<Texture>>,
 
-    /// Color that it emitted to the camera
+    /// Color that it emitted to the camera in linear space
     ///
     /// this color is added to the materials output color after lighting calculations.
     ///
@@ -113,6 +143,8 @@ pub struct PbrMaterial {
 
     /// Texture used for material Emission. see [`Self::emissive_factor`]
     ///
+    /// its colors are read as srgb
+    ///
     /// Default: [`Option::None`]
     pub emissive_texture: Option<AssetHandle<Texture>>,
 
@@ -124,6 +156,14 @@ pub struct PbrMaterial {
     /// Default: [`Vec2::ONE`]
     pub texture_scale: math::Vec2,
 
+    /// offset added to the meshes texture cordinates after [`Self::texture_scale`]
+    ///
+    /// together with the scale this can show one region of a texture such as a frame of a
+    /// [`crate::assets::texture_atlas::TextureAtlas`].
+    ///
+    /// Default: [`Vec2::ZERO`]
+    pub texture_offset: math::Vec2,
+
     /// whether the objects renders both sides
     ///
     /// Default: `false`
@@ -148,6 +188,12 @@ pub struct PbrMaterial {
     ///
     /// Default: [`CullMode::Back`]
     pub cull_mode: CullMode,
+
+    /// skip lighting and show the base color and emission as they are, like gltf's
+    /// `KHR_materials_unlit`
+    ///
+    /// Default: `false`
+    pub unlit: bool,
 }
 
 impl Default for PbrMaterial {
@@ -160,16 +206,21 @@ impl Default for PbrMaterial {
             metallic_roughness_texture: None,
             normal_scale: 1.0,
             normal_texture: None,
+            height_texture: None,
+            parallax_scale: 0.05,
+            parallax_steps: 16,
             ambient_occlusion_strength: 1.0,
             occlusion_texture: None,
             emissive_factor: Color::BLACK,
             emissive_texture: None,
             texture_scale: Vec2::ONE,
+            texture_offset: Vec2::ZERO,
             double_sided: false,
             alpha_mode: AlphaMode::Opaque,
             alpha_cutoff: 0.5,
             cast_shadows: true,
             cull_mode: CullMode::Back,
+            unlit: false,
         }
     }
 }
@@ -213,6 +264,8 @@ impl IntoAsset<Material> for AssetHandle<Texture> {
 pub struct GpuPbrMaterial {
     uniform: Buffer<MaterialBufferData>,
     descriptor: DescriptorSet,
+    /// see [`MaterialBufferData::srgb_textures`]
+    srgb_textures: u32,
 }
 
 impl GpuMateiral for GpuPbrMaterial {
@@ -230,6 +283,10 @@ impl MaterialInstance for PbrMaterial {
         include_str!("pbr.frag.wgsl").into()
     }
 
+    fn gbuffer_shader() -> Option<maple_renderer::shader_asset::ShaderSource> {
+        Some(include_str!("pbr_gbuffer.frag.wgsl").into())
+    }
+
     fn alpha_mode(&self) -> AlphaMode {
         self.alpha_mode
     }
@@ -283,6 +340,9 @@ impl MaterialInstance for PbrMaterial {
                 // normal
                 DescriptorBindingType::TextureView { filterable: true },
                 DescriptorBindingType::Sampler { filtering: true },
+                // height
+                DescriptorBindingType::TextureView { filterable: true },
+                DescriptorBindingType::Sampler { filtering: true },
             ],
         })
     }
@@ -317,6 +377,7 @@ impl MaterialInstance for PbrMaterial {
             (&self.occlusion_texture, &defaults.white),
             (&self.emissive_texture, &defaults.white),
             (&self.normal_texture, &defaults.normal),
+            (&self.height_texture, &defaults.white),
         ];
 
         let resolved: Option<Vec<Texture>> = slots
@@ -327,30 +388,47 @@ impl MaterialInstance for PbrMaterial {
         let Some(resolved) = resolved else {
             return None;
         };
-        let [base_color, metallic_roughness, occlusion, emissive, normal]: [Texture; 5] =
-            resolved.try_into().unwrap();
-
-        let uniform = self.get_buffer();
+        let [
+            base_color,
+            metallic_roughness,
+            occlusion,
+            emissive,
+            normal,
+            height,
+        ]: [Texture; 6] = resolved.try_into().unwrap();
+
+        // color textures the gpu doesn't decode when sampling are decoded in the shader
+        let srgb_textures = [(&base_color, SRGB_BASE_COLOR), (&emissive, SRGB_EMISSIVE)]
+            .into_iter()
+            .filter(|(texture, _)| !texture.format().is_srgb())
+            .fold(0, |flags, (_, flag)| flags | flag);
+
+        let uniform = self.get_buffer(srgb_textures);
         let uniform_buffer = rcx.device().create_uniform_buffer(&uniform);
+        // each texture is sampled the way it asks for, e.g. by its gltf sampler
+        let sampler = |texture: &Texture| rcx.get_or_create_sampler(texture.sampling());
 
         let descriptor = rcx.device().build_descriptor_set(
             DescriptorSet::builder(&layout)
                 .uniform(0, &uniform_buffer)
                 .texture_view(1, &base_color.create_view())
-                .sampler(2, &defaults.sampler)
+                .sampler(2, &sampler(&base_color))
                 .texture_view(3, &metallic_roughness.create_view())
-                .sampler(4, &defaults.sampler)
+                .sampler(4, &sampler(&metallic_roughness))
                 .texture_view(5, &occlusion.create_view())
-                .sampler(6, &defaults.sampler)
+                .sampler(6, &sampler(&occlusion))
                 .texture_view(7, &emissive.create_view())
-                .sampler(8, &defaults.sampler)
+                .sampler(8, &sampler(&emissive))
                 .texture_view(9, &normal.create_view())
-                .sampler(10, &defaults.sampler),
+                .sampler(10, &sampler(&normal))
+                .texture_view(11, &height.create_view())
+                .sampler(12, &sampler(&height)),
         );
 
         Some(Arc::new(GpuPbrMaterial {
             uniform: uniform_buffer,
             descriptor: descriptor,
+            srgb_textures,
         }))
     }
 
@@ -359,11 +437,18 @@ impl MaterialInstance for PbrMaterial {
             return;
         };
 
-        rcx.queue()
-            .write_buffer(&gpu_material.uniform, &self.get_buffer());
+        rcx.queue().write_buffer(
+            &gpu_material.uniform,
+            &self.get_buffer(gpu_material.srgb_textures),
+        );
     }
 }
 
+/// the base color texture has to be decoded from srgb in the shader
+const SRGB_BASE_COLOR: u32 = 1 << 0;
+/// the emissive texture has to be decoded from srgb in the shader
+const SRGB_EMISSIVE: u32 = 1 << 1;
+
 /// buffer data for the uniform std430
 #[derive(Debug, Clone, Copy, Pod, Default, Zeroable)]
 #[repr(C)]
@@ -376,14 +461,36 @@ pub struct MaterialBufferData {
     pub emissive_factor: [f32; 4],
     pub alpha_cutoff: f32,
     pub parallax_scale: f32,
-    pub alpha_mode: u32,         // 0 opaque, 1 mask, 2 blend
-    pub unlit: u32,              // 0 lit, 1 unlit
-    pub texture_scale: [f32; 2], // UV scale for all textures
-    _padding: [f32; 2],          // Padding for alignment
+    pub alpha_mode: u32,          // 0 opaque, 1 mask, 2 blend
+    pub unlit: u32,               // 0 lit, 1 unlit
+    pub texture_scale: [f32; 2],  // UV scale for all textures
+    pub texture_offset: [f32; 2], // UV offset for all textures
+    /// which textures are srgb encoded but sampled without conversion
+    pub srgb_textures: u32,
+    /// 0 without a height texture
+    pub parallax_steps: u32,
+    pub _padding: [u32; 2],
 }
 
 impl PbrMaterial {
-    fn get_buffer(&self) -> MaterialBufferData {
+    /// change every material in `materials` that is a pbr material, queued for the ones that are
+    /// still loading
+    pub(crate) fn modify_all<'a>(
+        assets: &AssetLibrary,
+        materials: impl IntoIterator<Item = &'a AssetHandle<Material>>,
+        f: impl Fn(&mut PbrMaterial) + Clone + Send + 'static,
+    ) {
+        for material in materials {
+            let f = f.clone();
+            assets.modify(material, move |material| {
+                if let Some(pbr) = material.get_instance_mut::<PbrMaterial>() {
+                    f(pbr);
+                }
+            });
+        }
+    }
+
+    fn get_buffer(&self, srgb_textures: u32) -> MaterialBufferData {
         MaterialBufferData {
             base_color_factor: self.base_color_factor.into(),
             metallic_factor: self.metallic_factor,
@@ -392,15 +499,21 @@ impl PbrMaterial {
             ambient_occlusion_strength: self.ambient_occlusion_strength,
             emissive_factor: self.emissive_factor.into(),
             texture_scale: self.texture_scale.into(),
+            texture_offset: self.texture_offset.into(),
             alpha_cutoff: self.alpha_cutoff,
-            parallax_scale: 1.0,
+            parallax_scale: self.parallax_scale.max(0.0),
             alpha_mode: match self.alpha_mode {
                 AlphaMode::Opaque => 0u32,
                 AlphaMode::Mask => 1u32,
                 AlphaMode::Blend => 2u32,
             },
-            unlit: 0,
-            _padding: Zeroable::zeroed(),
+            unlit: self.unlit as u32,
+            srgb_textures,
+            parallax_steps: match self.height_texture {
+                Some(_) => self.parallax_steps,
+                None => 0,
+            },
+            _padding: [0; 2],
         }
     }
 }
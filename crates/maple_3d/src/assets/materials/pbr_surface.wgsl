@@ -0,0 +1,95 @@
+// the pbr material's uniform, its group 3 textures and the helpers sampling them, shared by the
+// forward and g-buffer shaders
+
+#include "maple/tonemap.wgsl"
+
+const ALPHA_MODE_OPAQUE: u32 = 0u;
+const ALPHA_MODE_MASK: u32 = 1u;
+const ALPHA_MODE_BLEND: u32 = 2u;
+
+struct MaterialData {
+    base_color_factor: vec4<f32>,
+    metallic_factor: f32,
+    roughness_factor: f32,
+    normal_scale: f32,
+    ambient_occlusion_strength: f32,
+    emissive_factor: vec4<f32>,
+    alpha_cutoff: f32,
+    parallax_scale: f32,
+    alpha_mode: u32,
+    unlit: u32,
+    texture_scale: vec2<f32>,
+    texture_offset: vec2<f32>,
+    // bit 0 base color, bit 1 emissive: srgb textures sampled without conversion
+    srgb_textures: u32,
+    // 0 without a height texture
+    parallax_steps: u32,
+}
+
+const SRGB_BASE_COLOR: u32 = 1u;
+const SRGB_EMISSIVE: u32 = 2u;
+
+@group(3) @binding(0) var<uniform> material: MaterialData;
+@group(3) @binding(1) var base_color_texture: texture_2d<f32>;
+@group(3) @binding(2) var base_color_sampler: sampler;
+@group(3) @binding(3) var metallic_roughness_texture: texture_2d<f32>;
+@group(3) @binding(4) var metallic_roughness_sampler: sampler;
+@group(3) @binding(5) var ambient_occlusion_texture: texture_2d<f32>;
+@group(3) @binding(6) var ambient_occlusion_sampler: sampler;
+@group(3) @binding(7) var emissive_texture: texture_2d<f32>;
+@group(3) @binding(8) var emissive_sampler: sampler;
+@group(3) @binding(9) var normal_texture: texture_2d<f32>;
+@group(3) @binding(10) var normal_sampler: sampler;
+@group(3) @binding(11) var height_texture: texture_2d<f32>;
+@group(3) @binding(12) var height_sampler: sampler;
+
+// parallax occlusion mapping, steps along the view ray through the height texture until it is
+// under the surface then returns where between the last two steps it crossed
+fn parallax_occlusion(uv: vec2<f32>, view_dir: vec3<f32>) -> vec2<f32> {
+    // head on views need fewer steps than grazing ones
+    let max_steps = f32(material.parallax_steps);
+    let steps = max(ceil(mix(max_steps, max_steps * 0.25, abs(view_dir.z))), 1.0);
+    let step_depth = 1.0 / steps;
+    // how far the uv moves for the whole depth, limited so grazing angles don't smear
+    let shift = view_dir.xy / max(abs(view_dir.z), 0.1) * material.parallax_scale;
+    let step_shift = shift * step_depth;
+
+    // the loop isn't uniform so the derivatives are taken before it
+    let dx = dpdx(uv);
+    let dy = dpdy(uv);
+
+    var current_uv = uv;
+    var layer_depth = 0.0;
+    var surface_depth = 1.0 - textureSampleGrad(height_texture, height_sampler, uv, dx, dy).r;
+    var previous_uv = uv;
+    var previous_surface = surface_depth;
+    var taken = 0.0;
+    loop {
+        if layer_depth >= surface_depth || taken >= steps {
+            break;
+        }
+        previous_uv = current_uv;
+        previous_surface = surface_depth;
+        current_uv -= step_shift;
+        layer_depth += step_depth;
+        surface_depth = 1.0 - textureSampleGrad(height_texture, height_sampler, current_uv, dx, dy).r;
+        taken += 1.0;
+    }
+
+    let after = surface_depth - layer_depth;
+    let before = previous_surface - (layer_depth - step_depth);
+    let gap = after - before;
+    if abs(gap) < 0.00001 {
+        return current_uv;
+    }
+    return mix(current_uv, previous_uv, after / gap);
+}
+
+// a color texture in linear space, srgb formats are already decoded by the sampler
+fn sample_color(t: texture_2d<f32>, s: sampler, uv: vec2<f32>, flag: u32) -> vec4<f32> {
+    let color = textureSample(t, s, uv);
+    if (material.srgb_textures & flag) != 0u {
+        return vec4<f32>(srgb_to_linear(color.rgb), color.a);
+    }
+    return color;
+}
@@ -1,10 +1,18 @@
+use std::{collections::HashMap, sync::Arc};
+
+use glam::{IVec3, Mat3, Mat4, Vec2, Vec3};
 use maple_engine::{
-    asset::{Asset, AssetLoader},
+    asset::{Asset, AssetLibrary, AssetLoader, IntoAsset, LoadErr},
     prelude::node_transform::WorldTransform,
+    utils::ray::Ray,
+};
+use maple_renderer::{
+    core::{Buffer, RenderDevice},
+    render_graph::memory::MemoryKind,
 };
-use maple_renderer::core::{Buffer, RenderDevice};
 use rayon::iter::{
-    IndexedParallelIterator, IntoParallelIterator, IntoParallelRefMutIterator, ParallelIterator,
+    IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator,
+    IntoParallelRefMutIterator, ParallelIterator,
 };
 
 use crate::math::{AABB, Vertex};
@@ -200,6 +208,120 @@ impl Mesh3DLoader {
     }
 }
 
+/// the offsets one blend shape of a [`Mesh3D`] moves each vertex by at a weight of 1
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MorphTarget {
+    pub name: Option<String>,
+    /// one offset per vertex, empty if the target doesn't move positions
+    pub positions: Vec<Vec3>,
+    /// one offset per vertex, empty if the target doesn't change normals
+    pub normals: Vec<Vec3>,
+}
+
+/// the blend shapes of a [`Mesh3D`]
+///
+/// meshes are blended on the cpu by the weights of each [`crate::prelude::MeshInstance3D`]
+/// before skinning, so a copy of the vertices is kept
+#[derive(Debug, Clone)]
+pub struct MorphTargets {
+    base: Arc<[Vertex]>,
+    targets: Arc<[MorphTarget]>,
+}
+
+impl MorphTargets {
+    /// the blend shapes of a mesh made from `vertices`
+    pub fn new(vertices: &[Vertex], targets: Vec<MorphTarget>) -> Self {
+        Self {
+            base: vertices.into(),
+            targets: targets.into(),
+        }
+    }
+
+    pub fn targets(&self) -> &[MorphTarget] {
+        &self.targets
+    }
+
+    /// the index of the target called `name`, for setting its weight
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.targets
+            .iter()
+            .position(|target| target.name.as_deref() == Some(name))
+    }
+
+    /// how many vertices each target moves
+    pub fn vertex_count(&self) -> usize {
+        self.base.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// the vertices with the offsets of each target added `weights` of the way
+    ///
+    /// targets without a weight aren't added
+    pub fn blend(&self, weights: &[f32]) -> Vec<Vertex> {
+        let active: Vec<(&MorphTarget, f32)> = self
+            .targets
+            .iter()
+            .zip(weights.iter().copied())
+            .filter(|(_, weight)| *weight != 0.0)
+            .collect();
+
+        self.base
+            .par_iter()
+            .enumerate()
+            .map(|(index, vertex)| {
+                let mut position = Vec3::from(vertex.position);
+                let mut normal = Vec3::from(vertex.normal);
+                for (target, weight) in &active {
+                    if let Some(offset) = target.positions.get(index) {
+                        position += *offset * *weight;
+                    }
+                    if let Some(offset) = target.normals.get(index) {
+                        normal += *offset * *weight;
+                    }
+                }
+                let normal = normal.normalize_or(Vec3::from(vertex.normal));
+
+                // keep the tangent frame at right angles to the blended normal
+                let (tangent, bitangent) =
+                    (Vec3::from(vertex.tangent), Vec3::from(vertex.bitangent));
+                let handedness = match normal.cross(tangent).dot(bitangent) < 0.0 {
+                    true => -1.0,
+                    false => 1.0,
+                };
+                let tangent = (tangent - normal * normal.dot(tangent)).normalize_or(tangent);
+
+                Vertex {
+                    position: position.into(),
+                    normal: normal.into(),
+                    tangent: tangent.into(),
+                    bitangent: (normal.cross(tangent) * handedness).into(),
+                    ..*vertex
+                }
+            })
+            .collect()
+    }
+
+    /// a box around every shape the targets can blend `aabb` into with weights from 0 to 1
+    fn bounds(&self, aabb: &AABB) -> AABB {
+        let (mut min, mut max) = (aabb.min, aabb.max);
+        for target in self.targets.iter() {
+            let offsets = target.positions.iter();
+            min += offsets
+                .clone()
+                .fold(Vec3::ZERO, |min, offset| min.min(*offset));
+            max += offsets.fold(Vec3::ZERO, |max, offset| max.max(*offset));
+        }
+        AABB::new(min, max)
+    }
+}
+
 /// Mesh3D is a [`Asset`] that reprensents an objects shape on the gpu
 ///
 /// it contains a refrence to vertices and indices
@@ -210,6 +332,12 @@ pub struct Mesh3D {
     index_buffer: Buffer<[u32]>,
 
     aabb: AABB,
+    /// a cpu copy of the triangles for ray casts, empty if the mesh was made from buffers
+    positions: Arc<[Vec3]>,
+    indices: Arc<[u32]>,
+    /// a cpu copy of the vertices for baking static batches, empty if made from buffers
+    vertices: Arc<[Vertex]>,
+    morph_targets: Option<MorphTargets>,
 }
 
 impl Asset for Mesh3D {
@@ -218,7 +346,7 @@ impl Asset for Mesh3D {
 
 impl Mesh3D {
     pub fn new(device: &RenderDevice, vertices: &[Vertex], indices: &[u32]) -> Self {
-        let aabb = AABB::from_vertices(&vertices);
+        let aabb = AABB::from_points(vertices.iter().map(|vertex| Vec3::from(vertex.position)));
 
         Self {
             // transform: NodeTransform::default(),
@@ -226,6 +354,13 @@ impl Mesh3D {
             index_buffer: device.create_index_buffer(&indices),
             // material: MaterialProperties::default(),
             aabb,
+            positions: vertices
+                .iter()
+                .map(|vertex| Vec3::from(vertex.position))
+                .collect(),
+            indices: indices.into(),
+            vertices: vertices.into(),
+            morph_targets: None,
         }
     }
 
@@ -241,9 +376,25 @@ impl Mesh3D {
             index_buffer,
 
             aabb,
+            positions: Arc::new([]),
+            indices: Arc::new([]),
+            vertices: Arc::new([]),
+            morph_targets: None,
         }
     }
 
+    /// give the mesh blend shapes, its bounding box grows to fit every shape they blend into
+    pub fn with_morph_targets(mut self, morph_targets: MorphTargets) -> Self {
+        self.aabb = morph_targets.bounds(&self.aabb);
+        self.morph_targets = Some(morph_targets);
+        self
+    }
+
+    /// the blend shapes of the mesh, weighted by [`crate::prelude::MeshInstance3D::morph_weights`]
+    pub fn morph_targets(&self) -> Option<&MorphTargets> {
+        self.morph_targets.as_ref()
+    }
+
     /// grabs the meshes vertices if they have been created if not it creates them with the
     /// renderer
     pub fn get_vertex_buffer(&self) -> &Buffer<[Vertex]> {
@@ -256,8 +407,438 @@ impl Mesh3D {
         &self.index_buffer
     }
 
+    /// the bounding box of the vertices in the meshes local space
+    /// the vertex and index buffers on the gpu
+    pub fn gpu_memory(&self) -> MemoryKind {
+        MemoryKind::Mesh {
+            vertex_bytes: self.vertex_buffer.byte_size(),
+            index_bytes: self.index_buffer.byte_size(),
+        }
+    }
+
+    /// the cpu copy of the vertex positions and indices, empty if the mesh was made from buffers
+    pub(crate) fn triangles(&self) -> (&[Vec3], &[u32]) {
+        (&self.positions, &self.indices)
+    }
+
+    /// the cpu copy of the vertices, empty if the mesh was made from buffers
+    pub(crate) fn vertices(&self) -> &[Vertex] {
+        &self.vertices
+    }
+
+    pub fn aabb(&self) -> &AABB {
+        &self.aabb
+    }
+
     // get the bounding box in world space
     pub fn world_aabb(&self, transform: WorldTransform) -> AABB {
-        self.aabb.transform(&transform.matrix)
+        self.aabb.transform(transform.matrix())
+    }
+
+    /// the distance to the closest triangle a ray in the meshes local space hits
+    ///
+    /// meshes made with [`Mesh3D::from_buffers`] have no triangles on the cpu so their bounding
+    /// box is hit instead
+    pub fn raycast(&self, ray: &Ray) -> Option<f32> {
+        if self.indices.is_empty() {
+            return ray.intersect_aabb(&self.aabb);
+        }
+        ray.intersect_aabb(&self.aabb)?;
+
+        self.indices
+            .chunks_exact(3)
+            .filter_map(|triangle| {
+                let [a, b, c] = [0, 1, 2].map(|i| self.positions[triangle[i] as usize]);
+                ray.intersect_triangle(a, b, c)
+            })
+            .min_by(f32::total_cmp)
+    }
+}
+
+/// builds a [`Mesh3D`] out of vertices and triangles made at runtime for procedural terrain,
+/// voxels or debug shapes, add it to the asset library like a primitive
+///
+/// normals are smoothed from the triangles around each vertex when none are given, tangents are
+/// always computed when the mesh is made. [`crate::model::ModelData::from_mesh`] wraps one in a
+/// [`crate::model::Model`] with a material
+///
+/// ```rust,ignore
+/// let mut builder = MeshBuilder::new();
+/// for (x, z) in grid {
+///     builder.vertex([x, height(x, z), z], [x / size, z / size]);
+/// }
+/// for (a, b, c, d) in cells {
+///     builder.quad(a, b, c, d);
+/// }
+/// let terrain = ctx.assets.add(builder);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MeshBuilder {
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+    /// set once a vertex is given a normal, the rest are computed
+    has_normals: bool,
+}
+
+impl MeshBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// a mesh from arrays of vertices and triangle indices, normals are computed if every
+    /// vertex's normal is zero
+    pub fn from_vertices(vertices: impl Into<Vec<Vertex>>, indices: impl Into<Vec<u32>>) -> Self {
+        let vertices = vertices.into();
+        let has_normals = vertices.iter().any(|vertex| vertex.normal != [0.0; 3]);
+        Self {
+            vertices,
+            indices: indices.into(),
+            has_normals,
+        }
+    }
+
+    /// the vertices and triangles of a loaded mesh to build on, `None` for meshes made with
+    /// [`Mesh3D::from_buffers`] since their vertices aren't on the cpu
+    pub fn from_mesh(mesh: &Mesh3D) -> Option<Self> {
+        if mesh.vertices().is_empty() {
+            return None;
+        }
+        Some(Self::from_vertices(
+            mesh.vertices().to_vec(),
+            mesh.triangles().1.to_vec(),
+        ))
+    }
+
+    /// add a vertex without a normal, returns its index
+    pub fn vertex(&mut self, position: impl Into<Vec3>, uv: impl Into<Vec2>) -> u32 {
+        self.vertices.push(Vertex {
+            position: position.into().to_array(),
+            tex_uv: uv.into().to_array(),
+            ..Default::default()
+        });
+        self.vertices.len() as u32 - 1
+    }
+
+    /// add a vertex with a normal, returns its index
+    pub fn vertex_with_normal(
+        &mut self,
+        position: impl Into<Vec3>,
+        normal: impl Into<Vec3>,
+        uv: impl Into<Vec2>,
+    ) -> u32 {
+        let index = self.vertex(position, uv);
+        self.vertices[index as usize].normal = normal.into().normalize_or_zero().to_array();
+        self.has_normals = true;
+        index
+    }
+
+    /// add a triangle, counter clockwise corners face the camera
+    pub fn triangle(&mut self, a: u32, b: u32, c: u32) {
+        self.indices.extend([a, b, c]);
+    }
+
+    /// add a quad as two triangles, the corners go around it counter clockwise
+    pub fn quad(&mut self, a: u32, b: u32, c: u32, d: u32) {
+        self.indices.extend([a, b, c, a, c, d]);
+    }
+
+    /// add every vertex and triangle of `other` moved by `transform`, for merging voxel chunks
+    /// or debug shapes into one mesh
+    pub fn append(&mut self, other: &MeshBuilder, transform: Mat4) {
+        let offset = self.vertices.len() as u32;
+        let normal_matrix = Mat3::from_mat4(transform).inverse().transpose();
+
+        self.vertices.extend(other.vertices.iter().map(|vertex| {
+            Vertex {
+                position: transform
+                    .transform_point3(Vec3::from(vertex.position))
+                    .to_array(),
+                normal: (normal_matrix * Vec3::from(vertex.normal))
+                    .normalize_or_zero()
+                    .to_array(),
+                ..*vertex
+            }
+        }));
+        self.indices
+            .extend(other.indices.iter().map(|index| index + offset));
+        self.has_normals |= other.has_normals;
+    }
+
+    /// smooth every vertex's normal from the triangles around it, replacing the given ones
+    pub fn compute_normals(&mut self) {
+        let missing = vec![true; self.vertices.len()];
+        fill_normals(&mut self.vertices, &self.indices, &missing);
+        self.has_normals = true;
+    }
+
+    /// give every triangle its own vertices facing the way it does, for hard edges like voxels
+    /// and low poly shapes
+    pub fn compute_flat_normals(&mut self) {
+        let mut vertices = Vec::with_capacity(self.indices.len());
+        for triangle in self.indices.chunks_exact(3) {
+            let corners = [0, 1, 2].map(|i| self.vertices[triangle[i] as usize]);
+            let [a, b, c] = corners.map(|vertex| Vec3::from(vertex.position));
+            let normal = (b - a).cross(c - a).normalize_or(Vec3::Y).to_array();
+            vertices.extend(corners.map(|vertex| Vertex { normal, ..vertex }));
+        }
+
+        self.indices = (0..vertices.len() as u32).collect();
+        self.vertices = vertices;
+        self.has_normals = true;
+    }
+
+    /// a coarser copy where the vertices in the same `cell_size` cube facing the same way are
+    /// merged into one, triangles that collapse are dropped. used for
+    /// [levels of detail](crate::lod)
+    pub fn decimated(&self, cell_size: f32) -> MeshBuilder {
+        let cell_size = cell_size.max(f32::EPSILON);
+        // vertices facing different ways are kept apart so hard edges stay hard
+        let key = |vertex: &Vertex| {
+            let normal = Vec3::from(vertex.normal);
+            let axis = normal.abs().max_position();
+            let facing = (axis as i32 + 1) * normal[axis].signum() as i32;
+            let cell = (Vec3::from(vertex.position) / cell_size).floor().as_ivec3();
+            (cell, facing)
+        };
+
+        let mut merged: HashMap<(IVec3, i32), u32> = HashMap::new();
+        let mut sums: Vec<(Vertex, Vec3, Vec3, Vec2, f32)> = Vec::new();
+        let remap: Vec<u32> = self
+            .vertices
+            .iter()
+            .map(|vertex| {
+                let index = *merged.entry(key(vertex)).or_insert_with(|| {
+                    sums.push((*vertex, Vec3::ZERO, Vec3::ZERO, Vec2::ZERO, 0.0));
+                    sums.len() as u32 - 1
+                });
+                let (_, position, normal, uv, count) = &mut sums[index as usize];
+                *position += Vec3::from(vertex.position);
+                *normal += Vec3::from(vertex.normal);
+                *uv += Vec2::from(vertex.tex_uv);
+                *count += 1.0;
+                index
+            })
+            .collect();
+
+        let vertices = sums
+            .into_iter()
+            .map(|(vertex, position, normal, uv, count)| Vertex {
+                position: (position / count).to_array(),
+                normal: normal.normalize_or_zero().to_array(),
+                tex_uv: (uv / count).to_array(),
+                ..vertex
+            })
+            .collect();
+        let mut indices = Vec::new();
+        for triangle in self.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| remap[triangle[i] as usize]);
+            if a != b && b != c && a != c {
+                indices.extend([a, b, c]);
+            }
+        }
+
+        MeshBuilder {
+            vertices,
+            indices,
+            has_normals: self.has_normals,
+        }
+    }
+
+    pub fn vertices(&self) -> &[Vertex] {
+        &self.vertices
+    }
+
+    /// edit vertices in place, for example to move terrain
+    pub fn vertices_mut(&mut self) -> &mut [Vertex] {
+        &mut self.vertices
+    }
+
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    /// true if nothing has been added
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// an error if there are no triangles, a triangle is missing a corner or uses a vertex that
+    /// doesn't exist
+    pub fn validate(&self) -> Result<(), LoadErr> {
+        validate_triangles(&self.indices, self.vertices.len())
+    }
+
+    /// the checked vertices and indices with normals filled in, tangents are left to the mesh
+    pub(crate) fn finish(mut self) -> Result<(Vec<Vertex>, Vec<u32>), LoadErr> {
+        self.validate()?;
+        if !self.has_normals {
+            self.compute_normals();
+        }
+        Ok((self.vertices, self.indices))
+    }
+}
+
+impl IntoAsset<Mesh3D> for MeshBuilder {
+    fn into_asset(
+        self,
+        loader: &<Mesh3D as Asset>::Loader,
+        _library: &AssetLibrary,
+    ) -> Result<Mesh3D, LoadErr> {
+        let (mut vertices, indices) = self.finish()?;
+        Ok(loader.create_mesh(&mut vertices, &indices))
+    }
+}
+
+/// see [`MeshBuilder::validate`]
+pub(crate) fn validate_triangles(indices: &[u32], vertex_count: usize) -> Result<(), LoadErr> {
+    if indices.is_empty() {
+        return Err(LoadErr::IntoAsset("the mesh has no triangles".into()));
+    }
+    if !indices.len().is_multiple_of(3) {
+        return Err(LoadErr::IntoAsset(format!(
+            "{} indices don't make whole triangles",
+            indices.len()
+        )));
+    }
+    if let Some(index) = indices
+        .iter()
+        .find(|index| **index as usize >= vertex_count)
+    {
+        return Err(LoadErr::IntoAsset(format!(
+            "a triangle uses vertex {index} of {vertex_count}"
+        )));
+    }
+    Ok(())
+}
+
+/// smooth normals for vertices that don't have one from the faces around them
+pub(crate) fn fill_normals(vertices: &mut [Vertex], indices: &[u32], missing: &[bool]) {
+    if !missing.contains(&true) {
+        return;
+    }
+
+    let mut normals = vec![Vec3::ZERO; vertices.len()];
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| Vec3::from(vertices[triangle[i] as usize].position));
+        // not normalized so bigger faces count for more
+        let normal = (b - a).cross(c - a);
+        for index in triangle {
+            normals[*index as usize] += normal;
+        }
+    }
+
+    for ((vertex, normal), missing) in vertices.iter_mut().zip(normals).zip(missing) {
+        if *missing {
+            vertex.normal = normal.normalize_or(Vec3::Y).into();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_morph_targets_blend_by_weight() {
+        let base = [Vertex {
+            normal: [0.0, 0.0, 1.0],
+            tangent: [1.0, 0.0, 0.0],
+            bitangent: [0.0, 1.0, 0.0],
+            ..Default::default()
+        }; 2];
+        let morph = MorphTargets::new(
+            &base,
+            vec![
+                MorphTarget {
+                    name: Some("raise".to_string()),
+                    positions: vec![Vec3::Y, Vec3::Y * 2.0],
+                    normals: Vec::new(),
+                },
+                MorphTarget {
+                    name: Some("tilt".to_string()),
+                    positions: Vec::new(),
+                    normals: vec![Vec3::new(0.0, -1.0, -1.0); 2],
+                },
+            ],
+        );
+        assert_eq!(morph.index_of("tilt"), Some(1));
+
+        let blended = morph.blend(&[0.5]);
+        assert_eq!(blended[0].position, [0.0, 0.5, 0.0]);
+        assert_eq!(blended[1].position, [0.0, 1.0, 0.0]);
+        assert_eq!(blended[0].normal, [0.0, 0.0, 1.0]);
+
+        // blended normals are normalized and the tangent frame follows them
+        let blended = morph.blend(&[0.0, 0.5]);
+        let normal = Vec3::from(blended[0].normal);
+        assert!(normal.abs_diff_eq(Vec3::new(0.0, -1.0, 1.0).normalize(), 1e-5));
+        assert!(normal.dot(Vec3::from(blended[0].tangent)).abs() < 1e-5);
+        assert!(normal.dot(Vec3::from(blended[0].bitangent)).abs() < 1e-5);
+
+        let aabb = morph.bounds(&AABB::new(Vec3::ZERO, Vec3::ZERO));
+        assert_eq!(aabb.max, Vec3::Y * 2.0);
+    }
+
+    #[test]
+    fn test_mesh_builder() {
+        let mut builder = MeshBuilder::new();
+        let corners = [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [0.0, 1.0, 0.0],
+        ];
+        let [a, b, c, d] = corners.map(|corner| builder.vertex(corner, [0.0, 0.0]));
+        builder.quad(a, b, c, d);
+
+        let mut merged = builder.clone();
+        merged.append(&builder, Mat4::from_translation(Vec3::Z));
+        assert_eq!(merged.vertices().len(), 8);
+        assert_eq!(merged.indices()[6..9], [4, 5, 6]);
+        assert_eq!(merged.vertices()[4].position, [0.0, 0.0, 1.0]);
+
+        // normals are smoothed when none were given
+        let (vertices, indices) = builder.clone().finish().unwrap();
+        assert_eq!(indices.len(), 6);
+        assert!(
+            vertices
+                .iter()
+                .all(|vertex| vertex.normal == [0.0, 0.0, 1.0])
+        );
+
+        builder.compute_flat_normals();
+        assert_eq!(builder.vertices().len(), 6);
+        assert_eq!(builder.indices(), &[0, 1, 2, 3, 4, 5]);
+
+        builder.triangle(0, 1, 9);
+        assert!(builder.validate().is_err());
+        assert!(MeshBuilder::new().validate().is_err());
+    }
+
+    #[test]
+    fn test_decimated_mesh_merges_close_vertices() {
+        // a strip of quads along x, 0.25 apart
+        let mut builder = MeshBuilder::new();
+        for i in 0..=4 {
+            let x = i as f32 * 0.25;
+            builder.vertex_with_normal([x, 0.0, 0.0], Vec3::Z, [x, 0.0]);
+            builder.vertex_with_normal([x, 1.0, 0.0], Vec3::Z, [x, 1.0]);
+        }
+        for i in 0..4 {
+            let a = i * 2;
+            builder.quad(a, a + 2, a + 3, a + 1);
+        }
+
+        let same = builder.decimated(0.1);
+        assert_eq!(same.vertices().len(), 10);
+        assert_eq!(same.indices().len(), 24);
+
+        // cells of 2 collapse the strip onto one edge
+        assert!(builder.decimated(2.0).is_empty());
+
+        let coarse = builder.decimated(0.5);
+        assert!(coarse.vertices().len() < 10);
+        assert!(!coarse.is_empty());
+        assert!(coarse.validate().is_ok());
     }
 }
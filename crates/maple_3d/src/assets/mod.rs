@@ -2,3 +2,4 @@ pub mod material;
 pub mod materials;
 pub mod mesh;
 pub mod primitives;
+pub mod texture_atlas;
@@ -78,8 +78,7 @@ impl IntoAsset<Mesh3D> for Cuboid {
                 position: *p,
                 normal: *n,
                 tex_uv: *uv,
-                tangent: [0.0, 0.0, 0.0],
-                bitangent: [0.0, 0.0, 0.0],
+                ..Default::default()
             })
             .collect();
 
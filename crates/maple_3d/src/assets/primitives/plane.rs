@@ -59,8 +59,7 @@ impl IntoAsset<Mesh3D> for Plane {
                     normal: self.normal.to_array(),
                     tex_uv: [tx, tz],
                     // tangent and bitangent are calculated on creation of mesh
-                    tangent: [0.0, 0.0, 0.0],
-                    bitangent: [0.0, 0.0, 0.0],
+                    ..Default::default()
                 })
             }
         }
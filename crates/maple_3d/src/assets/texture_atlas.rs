@@ -0,0 +1,125 @@
+//! a texture atlas packs many images such as the frames of an animation into one texture
+
+use glam::Vec2;
+use maple_engine::asset::AssetHandle;
+use maple_renderer::core::{CullMode, texture::Texture};
+
+use crate::assets::{material::AlphaMode, materials::PbrMaterial};
+
+/// a region of an atlas in texture coordinates
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AtlasRegion {
+    /// top left corner
+    pub min: Vec2,
+    /// bottom right corner
+    pub max: Vec2,
+}
+
+impl AtlasRegion {
+    /// the [`PbrMaterial::texture_scale`] that maps a mesh's 0 to 1 texture coordinates onto
+    /// this region
+    pub fn scale(&self) -> Vec2 {
+        self.max - self.min
+    }
+
+    /// the [`PbrMaterial::texture_offset`] that maps a mesh's 0 to 1 texture coordinates onto
+    /// this region
+    pub fn offset(&self) -> Vec2 {
+        self.min
+    }
+}
+
+/// a texture split into regions
+///
+/// # Example
+/// ```no_run
+/// # use maple_3d::prelude::*;
+/// # use maple_engine::prelude::*;
+/// # let assets = AssetLibrary::default();
+/// // a sprite sheet with 8 frames in 2 rows
+/// let atlas = TextureAtlas::grid(assets.load("res/player.png"), 4, 2);
+/// let material = assets.add(atlas.material());
+/// ```
+#[derive(Clone)]
+pub struct TextureAtlas {
+    pub texture: AssetHandle<Texture>,
+    pub regions: Vec<AtlasRegion>,
+}
+
+impl TextureAtlas {
+    /// create an atlas with no regions
+    pub fn new(texture: AssetHandle<Texture>) -> Self {
+        Self {
+            texture,
+            regions: Vec::new(),
+        }
+    }
+
+    /// split the texture into a grid of equally sized regions ordered left to right then top to
+    /// bottom
+    pub fn grid(texture: AssetHandle<Texture>, columns: u32, rows: u32) -> Self {
+        let columns = columns.max(1);
+        let rows = rows.max(1);
+        let size = Vec2::new(1.0 / columns as f32, 1.0 / rows as f32);
+
+        let regions = (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (column, row)))
+            .map(|(column, row)| {
+                let min = Vec2::new(column as f32, row as f32) * size;
+                AtlasRegion {
+                    min,
+                    max: min + size,
+                }
+            })
+            .collect();
+
+        Self { texture, regions }
+    }
+
+    /// add a region given in pixels of a texture that is `texture_size` pixels large. returns the
+    /// index of the region
+    pub fn add_region(&mut self, texture_size: Vec2, position: Vec2, size: Vec2) -> usize {
+        let texture_size = texture_size.max(Vec2::ONE);
+        self.regions.push(AtlasRegion {
+            min: position / texture_size,
+            max: (position + size) / texture_size,
+        });
+        self.regions.len() - 1
+    }
+
+    /// get a region by its index
+    pub fn region(&self, index: usize) -> Option<AtlasRegion> {
+        self.regions.get(index).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    /// a double sided alpha masked material showing the first region of the atlas
+    ///
+    /// every sprite that is animated on its own needs its own material since the region is
+    /// stored in the material
+    pub fn material(&self) -> PbrMaterial {
+        let region = self.region(0).unwrap_or(AtlasRegion {
+            min: Vec2::ZERO,
+            max: Vec2::ONE,
+        });
+
+        PbrMaterial {
+            base_color_texture: Some(self.texture.clone()),
+            texture_scale: region.scale(),
+            texture_offset: region.offset(),
+            roughness_factor: 1.0,
+            alpha_mode: AlphaMode::Mask,
+            double_sided: true,
+            cast_shadows: false,
+            cull_mode: CullMode::None,
+            ..Default::default()
+        }
+    }
+}
@@ -1,27 +1,35 @@
 use std::{collections::HashMap, path::Path};
 
-use glam::{Quat, Vec3, Vec4};
+use glam::{Mat4, Quat, Vec3, Vec4};
 use gltf::{Document, buffer::Data, image as gltf_image};
 use maple_engine::{
     Scene,
     asset::{Asset, AssetHandle, AssetLibrary, AssetLoader, FileLoader, LoadErr},
+    color::Color,
     nodes::{Buildable, Builder, Empty},
     scene::{InstancableScene, InstanceId, NodeId, SceneAsset},
 };
 use maple_renderer::core::{
     RenderDevice, RenderQueue,
     mipmap_generator::MipmapGenerator,
-    texture::{Texture, TextureCreateInfo, TextureFormat, TextureUsage},
+    texture::{
+        FilterMode, Texture, TextureCreateInfo, TextureFormat, TextureMode, TextureSampling,
+        TextureUsage,
+    },
 };
 
 use crate::{
+    animation::{AnimationClip, Channel, Interpolation, Keyframes, Skin},
     assets::{
         material::AlphaMode,
         materials::PbrMaterial,
-        mesh::{Mesh3D, Mesh3DLoader},
+        mesh::{Mesh3D, Mesh3DLoader, MorphTarget, MorphTargets},
     },
     math::Vertex,
-    nodes::mesh_instance::MeshInstance3D,
+    nodes::{
+        animation_player::AnimationPlayer3D, camera::Camera3D, directional_light::DirectionalLight,
+        mesh_instance::MeshInstance3D, point_light::PointLight, spot_light::SpotLight,
+    },
     prelude::Material,
 };
 
@@ -41,6 +49,19 @@ pub struct PrimitiveKey {
 
 /// represents a Gltf Scene as an [`Asset`]
 ///
+/// the file's node tree is kept when it is merged into a scene. every gltf node becomes an
+/// [`Empty`] with the node's name and local transform under its gltf parent, including nodes
+/// without a mesh, and each mesh primitive is a [`MeshInstance3D`] child called `primitive_<n>`.
+/// names that appear more than once in the file get `_<node index>` added. parts can be found with
+/// [`Scene::get_id_by_path`], animated like any other node and pulled off with [`Scene::detach`]
+///
+/// files with skins or animations are loaded under an [`AnimationPlayer3D`] called
+/// `AnimationPlayer` that has the file's animations as its clips. meshes keep their morph targets
+/// and start with the weights of their node
+///
+/// cameras and `KHR_lights_punctual` lights in the file are only imported when the loader is set
+/// to with [`GltfSceneLoader::import_cameras`] and [`GltfSceneLoader::import_lights`]
+///
 /// # Example
 /// ```no_run
 /// # use maple_3d::prelude::*;
@@ -49,6 +70,11 @@ pub struct PrimitiveKey {
 /// # let scene = Scene::default();
 /// let gltf = assets.load::<GltfScene>("path/to/scene.gltf");
 /// scene.merge_asset(gltf);
+///
+/// // once the scene has loaded
+/// if let Some(door) = scene.get_id_by_path("car/body/door_left") {
+///     scene.detach(door);
+/// }
 /// ```
 pub struct GltfScene {
     /// Preprocessed meshes
@@ -71,6 +97,7 @@ impl GltfScene {
         self.preprocessed_meshes.get(&key).cloned()
     }
 
+    /// a texture by its index in the file's textures, sampled the way its gltf sampler says
     pub fn get_texture(&self, key: usize) -> Option<AssetHandle<Texture>> {
         self.texture_handles.get(&key).cloned()
     }
@@ -86,12 +113,22 @@ impl GltfScene {
 
         self.material_handles.get(id).cloned()
     }
+
+    /// override how strongly the normal maps of every material bend the surface instead of the
+    /// scale in the file, see [`PbrMaterial::normal_scale`]
+    pub fn set_normal_scale(&self, assets: &AssetLibrary, scale: f32) {
+        PbrMaterial::modify_all(assets, self.material_handles.values(), move |material| {
+            material.normal_scale = scale
+        });
+    }
 }
 
 pub struct GltfSceneLoader {
     pub(crate) device: RenderDevice,
     pub(crate) queue: RenderQueue,
     pub(crate) mipmap_generator: MipmapGenerator,
+    cameras: bool,
+    lights: bool,
 }
 
 impl GltfSceneLoader {
@@ -104,10 +141,48 @@ impl GltfSceneLoader {
             device,
             queue,
             mipmap_generator,
+            cameras: false,
+            lights: false,
         }
     }
+
+    /// turn the file's perspective cameras into [`Camera3D`] children called `camera` of their
+    /// nodes. they are active with priority 0 like any other new camera. Default: false
+    ///
+    /// orthographic cameras are skipped
+    ///
+    /// ```rust,ignore
+    /// // in a plugin's setup after Core3D, replacing the default loader
+    /// let context = &app.renderer().context;
+    /// let loader = GltfSceneLoader::new(
+    ///     context.device().clone(),
+    ///     context.queue().clone(),
+    ///     context.mipmap_generator().clone(),
+    /// )
+    /// .import_cameras(true)
+    /// .import_lights(true);
+    /// app.context_mut().assets.register_loader(loader);
+    /// ```
+    pub fn import_cameras(mut self, import: bool) -> Self {
+        self.cameras = import;
+        self
+    }
+
+    /// turn `KHR_lights_punctual` lights into [`DirectionalLight`], [`PointLight`] and
+    /// [`SpotLight`] children called `light` of their nodes. Default: false
+    ///
+    /// blender exports light power as photometric units, lux for
+    /// sun lights and candela for the rest, which are turned back into the watts the engine's
+    /// intensities are close to
+    pub fn import_lights(mut self, import: bool) -> Self {
+        self.lights = import;
+        self
+    }
 }
 
+/// lumens per watt blender uses to turn light power into photometric units
+const LUMENS_PER_WATT: f32 = 683.0;
+
 impl AssetLoader for GltfSceneLoader {
     type Asset = GltfScene;
 }
@@ -123,10 +198,39 @@ impl FileLoader for GltfSceneLoader {
             LoadErr::Import(format!("Failed to load GLTF: {}", e))
         })?;
 
+        self.build(path, document, buffers, images, library)
+    }
+
+    /// fetched files have nowhere to read other files from so only .glb and gltf with embedded
+    /// buffers and images load this way
+    fn load_bytes(
+        &self,
+        path: &Path,
+        bytes: Vec<u8>,
+        library: &AssetLibrary,
+    ) -> Result<Self::Asset, LoadErr> {
+        log::info!("Loading GLTF from {:?}", path);
+        let (document, buffers, images) = gltf::import_slice(&bytes)
+            .map_err(|e| LoadErr::Import(format!("Failed to load GLTF: {}", e)))?;
+
+        self.build(path, document, buffers, images, library)
+    }
+}
+
+impl GltfSceneLoader {
+    fn build(
+        &self,
+        path: &Path,
+        document: Document,
+        buffers: Vec<Data>,
+        images: Vec<gltf_image::Data>,
+        library: &AssetLibrary,
+    ) -> Result<GltfScene, LoadErr> {
         log::debug!("GLTF import successful, {} images found", images.len());
 
         // List of extensions we support
         const SUPPORTED_EXTENSIONS: &[&str] = &[
+            "KHR_lights_punctual",
             "KHR_materials_unlit",
             "KHR_materials_pbrSpecularGlossiness",
             "KHR_materials_emissive_strength",
@@ -172,6 +276,7 @@ impl FileLoader for GltfSceneLoader {
             &self.device,
             &self.queue,
             &self.mipmap_generator,
+            &document,
             &images,
             library,
         );
@@ -184,8 +289,22 @@ impl FileLoader for GltfSceneLoader {
 
         log::info!("Finished loading GLTF from {:?}", path);
 
+        let names = node_names(&document);
+        let paths = node_paths(&document, &names);
+        let skins = load_skins(&document, &buffers, &paths);
+        let clips = load_animations(&document, &buffers, &paths);
+
         let scene = InstancableScene::new();
 
+        // skins and animations find their nodes by path from the player
+        let player = match skins.is_empty() && clips.is_empty() {
+            true => None,
+            false => Some(scene.spawn(
+                "AnimationPlayer",
+                AnimationPlayer3D::builder().clips(clips).build(),
+            )),
+        };
+
         // Load all scenes from the GLTF (usually just one)
         for gltf_scene in document.scenes() {
             for node in gltf_scene.nodes() {
@@ -193,10 +312,12 @@ impl FileLoader for GltfSceneLoader {
                     self,
                     &node,
                     &scene,
-                    None,
+                    player,
                     &texture_handles,
                     &material_handles,
                     &preprocessed_meshes,
+                    &names,
+                    &skins,
                 );
             }
         }
@@ -215,12 +336,13 @@ fn preload_textures(
     device: &RenderDevice,
     queue: &RenderQueue,
     mipmap_generator: &MipmapGenerator,
+    document: &gltf::Document,
     images: &[gltf_image::Data],
     assets: &AssetLibrary,
 ) -> HashMap<usize, AssetHandle<Texture>> {
-    let mut texture_handles = HashMap::new();
+    let mut image_textures = Vec::with_capacity(images.len());
 
-    for (image_index, image) in images.iter().enumerate() {
+    for image in images {
         let (pixels, format) = match image.format {
             gltf::image::Format::R8 => {
                 // R8 (grayscale) -> RGBA8: R -> (R, R, R, 255)
@@ -297,12 +419,50 @@ fn preload_textures(
         queue.write_texture(&texture, &pixels);
         mipmap_generator.generate_mipmaps(&texture);
 
-        let handle = assets.register(texture);
-
-        texture_handles.insert(image_index, handle);
+        image_textures.push(texture);
     }
 
-    texture_handles
+    // textures that share an image share its gpu memory
+    document
+        .textures()
+        .filter_map(|texture| {
+            let image = image_textures.get(texture.source().index())?;
+            let sampled = image.clone().with_sampling(sampling(&texture.sampler()));
+            Some((texture.index(), assets.register(sampled)))
+        })
+        .collect()
+}
+
+/// how a gltf sampler samples its texture, unset filters are trilinear
+fn sampling(sampler: &gltf::texture::Sampler) -> TextureSampling {
+    use gltf::texture::{MagFilter, MinFilter, WrappingMode};
+
+    let mode = |wrap: WrappingMode| match wrap {
+        WrappingMode::ClampToEdge => TextureMode::ClampToEdge,
+        WrappingMode::MirroredRepeat => TextureMode::MirrorRepeat,
+        WrappingMode::Repeat => TextureMode::Repeat,
+    };
+    let (min_filter, mipmap_filter) = match sampler.min_filter() {
+        Some(MinFilter::Nearest) => (FilterMode::Nearest, None),
+        Some(MinFilter::Linear) => (FilterMode::Linear, None),
+        Some(MinFilter::NearestMipmapNearest) => (FilterMode::Nearest, Some(FilterMode::Nearest)),
+        Some(MinFilter::LinearMipmapNearest) => (FilterMode::Linear, Some(FilterMode::Nearest)),
+        Some(MinFilter::NearestMipmapLinear) => (FilterMode::Nearest, Some(FilterMode::Linear)),
+        Some(MinFilter::LinearMipmapLinear) | None => {
+            (FilterMode::Linear, Some(FilterMode::Linear))
+        }
+    };
+
+    TextureSampling {
+        mode_u: mode(sampler.wrap_s()),
+        mode_v: mode(sampler.wrap_t()),
+        mag_filter: match sampler.mag_filter() {
+            Some(MagFilter::Nearest) => FilterMode::Nearest,
+            Some(MagFilter::Linear) | None => FilterMode::Linear,
+        },
+        min_filter,
+        mipmap_filter,
+    }
 }
 
 /// Preprocess all meshes in the GLTF document
@@ -320,6 +480,7 @@ fn preprocess_meshes(
 
     for mesh in document.meshes() {
         let mesh_index = mesh.index();
+        let target_names = morph_target_names(&mesh);
 
         for (primitive_index, primitive) in mesh.primitives().enumerate() {
             let key = PrimitiveKey {
@@ -366,6 +527,7 @@ fn preprocess_meshes(
                             tex_uv: tex_coords[j],
                             tangent: tangent_vec3.into(),
                             bitangent: bitangent.into(),
+                            ..Default::default()
                         }
                     })
                     .collect()
@@ -377,12 +539,34 @@ fn preprocess_meshes(
                         position: pos,
                         normal: normals[j],
                         tex_uv: tex_coords[j],
-                        tangent: [0.0, 0.0, 0.0],
-                        bitangent: [0.0, 0.0, 0.0],
+                        ..Default::default()
                     })
                     .collect()
             };
 
+            // joints and weights of skinned meshes
+            if let Some(joints) = reader.read_joints(0) {
+                for (vertex, joints) in vertices.iter_mut().zip(joints.into_u16()) {
+                    vertex.joints = joints.map(u32::from);
+                }
+            }
+            if let Some(weights) = reader.read_weights(0) {
+                for (vertex, weights) in vertices.iter_mut().zip(weights.into_f32()) {
+                    vertex.weights = weights;
+                }
+            }
+
+            let morph_targets: Vec<MorphTarget> = reader
+                .read_morph_targets()
+                .enumerate()
+                .map(|(index, (positions, normals, _))| MorphTarget {
+                    name: target_names.get(index).cloned(),
+                    positions: positions
+                        .map_or_else(Vec::new, |iter| iter.map(Vec3::from).collect()),
+                    normals: normals.map_or_else(Vec::new, |iter| iter.map(Vec3::from).collect()),
+                })
+                .collect();
+
             // Read indices
             let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
             let indices: Vec<u32> = reader
@@ -394,16 +578,38 @@ fn preprocess_meshes(
                 Mesh3DLoader::calculate_tangents(&mut vertices, &indices);
             }
 
-            preprocessed.insert(
-                key,
-                assets.add(Mesh3D::new(&loader.device, &vertices, &indices)),
-            );
+            let mut mesh_3d = Mesh3D::new(&loader.device, &vertices, &indices);
+            if !morph_targets.is_empty() {
+                mesh_3d = mesh_3d.with_morph_targets(MorphTargets::new(&vertices, morph_targets));
+            }
+            preprocessed.insert(key, assets.add(mesh_3d));
         }
     }
 
     preprocessed
 }
 
+/// the names of a mesh's morph targets, gltf has no field for them so exporters put them in the
+/// mesh's extras as `targetNames`
+fn morph_target_names(mesh: &gltf::Mesh) -> Vec<String> {
+    let Some(extras) = mesh.extras() else {
+        return Vec::new();
+    };
+    let Ok(extras) = serde_json::from_str::<serde_json::Value>(extras.get()) else {
+        return Vec::new();
+    };
+    extras
+        .get("targetNames")
+        .and_then(serde_json::Value::as_array)
+        .map(|names| {
+            names
+                .iter()
+                .map(|name| name.as_str().unwrap_or_default().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 fn preprocess_materials(
     assets: &AssetLibrary,
     texture_handles: &HashMap<usize, AssetHandle<Texture>>,
@@ -508,7 +714,142 @@ fn perceive_brightness(color: Vec3) -> f32 {
     0.299 * color.x + 0.587 * color.y + 0.114 * color.z
 }
 
+/// a name for every node that is unique in the file so paths to nodes are unique
+///
+/// nodes keep their own name unless another node has it too
+fn node_names(document: &Document) -> Vec<String> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for name in document.nodes().filter_map(|node| node.name()) {
+        *counts.entry(name).or_default() += 1;
+    }
+
+    document
+        .nodes()
+        .map(|node| match node.name() {
+            Some(name) if counts[name] == 1 => name.to_string(),
+            Some(name) => format!("{name}_{}", node.index()),
+            None => format!("node_{}", node.index()),
+        })
+        .collect()
+}
+
+/// the `/` separated path from a root node to every node
+fn node_paths(document: &Document, names: &[String]) -> HashMap<usize, String> {
+    let mut paths = HashMap::new();
+    let mut stack: Vec<(gltf::Node, String)> = document
+        .scenes()
+        .flat_map(|scene| scene.nodes())
+        .map(|node| (node.clone(), names[node.index()].clone()))
+        .collect();
+
+    while let Some((node, path)) = stack.pop() {
+        for child in node.children() {
+            let child_path = format!("{path}/{}", names[child.index()]);
+            stack.push((child, child_path));
+        }
+        paths.entry(node.index()).or_insert(path);
+    }
+    paths
+}
+
+fn load_skins(
+    document: &Document,
+    buffers: &[Data],
+    paths: &HashMap<usize, String>,
+) -> HashMap<usize, Skin> {
+    let mut skins = HashMap::new();
+    for skin in document.skins() {
+        let joints: Vec<String> = skin
+            .joints()
+            .map(|joint| paths.get(&joint.index()).cloned().unwrap_or_default())
+            .collect();
+
+        // without inverse bind matrices the joints are bound where they are
+        let reader = skin.reader(|buffer| Some(&buffers[buffer.index()]));
+        let inverse_bind_matrices: Vec<Mat4> = match reader.read_inverse_bind_matrices() {
+            Some(matrices) => matrices
+                .map(|matrix| Mat4::from_cols_array_2d(&matrix))
+                .collect(),
+            None => vec![Mat4::IDENTITY; joints.len()],
+        };
+
+        skins.insert(
+            skin.index(),
+            Skin::from_paths(joints, inverse_bind_matrices),
+        );
+    }
+    skins
+}
+
+fn load_animations(
+    document: &Document,
+    buffers: &[Data],
+    paths: &HashMap<usize, String>,
+) -> Vec<AnimationClip> {
+    use gltf::animation::util::ReadOutputs;
+
+    let mut clips = Vec::new();
+    for animation in document.animations() {
+        let name = animation
+            .name()
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("animation_{}", animation.index()));
+        let mut clip = AnimationClip::new(name);
+
+        for channel in animation.channels() {
+            let Some(target) = paths.get(&channel.target().node().index()) else {
+                continue;
+            };
+            let reader = channel.reader(|buffer| Some(&buffers[buffer.index()]));
+            let (Some(times), Some(outputs)) = (reader.read_inputs(), reader.read_outputs()) else {
+                continue;
+            };
+
+            let interpolation = match channel.sampler().interpolation() {
+                gltf::animation::Interpolation::Step => Interpolation::Step,
+                gltf::animation::Interpolation::Linear => Interpolation::Linear,
+                gltf::animation::Interpolation::CubicSpline => Interpolation::CubicSpline,
+            };
+            let stride = match interpolation {
+                Interpolation::CubicSpline => 3,
+                _ => 1,
+            };
+            let times: Vec<f32> = times.collect();
+
+            let keyframes = match outputs {
+                ReadOutputs::Translations(values) => {
+                    Keyframes::Translation(values.map(Vec3::from).collect())
+                }
+                ReadOutputs::Rotations(values) => {
+                    Keyframes::Rotation(values.into_f32().map(Quat::from_array).collect())
+                }
+                ReadOutputs::Scales(values) => Keyframes::Scale(values.map(Vec3::from).collect()),
+                // every keyframe has a weight for each morph target, one after another
+                ReadOutputs::MorphTargetWeights(values) => {
+                    let values: Vec<f32> = values.into_f32().collect();
+                    let keyframes = times.len() * stride;
+                    if keyframes == 0 || !values.len().is_multiple_of(keyframes) {
+                        continue;
+                    }
+                    let targets = (values.len() / keyframes).max(1);
+                    Keyframes::Weights(values.chunks(targets).map(<[f32]>::to_vec).collect())
+                }
+            };
+
+            clip = clip.with_channel(Channel {
+                target: target.clone(),
+                times,
+                keyframes,
+                interpolation,
+            });
+        }
+        clips.push(clip);
+    }
+    clips
+}
+
 /// Recursively process a gltf node and its children
+#[allow(clippy::too_many_arguments)]
 fn process_node(
     loader: &GltfSceneLoader,
     node: &gltf::Node,
@@ -517,6 +858,8 @@ fn process_node(
     texture_handles: &HashMap<usize, AssetHandle<Texture>>,
     material_handles: &HashMap<usize, AssetHandle<Material>>,
     preprocessed_meshes: &HashMap<PrimitiveKey, AssetHandle<Mesh3D>>,
+    names: &[String],
+    skins: &HashMap<usize, Skin>,
 ) {
     let (translation, rotation, scale) = node.transform().decomposed();
 
@@ -524,7 +867,8 @@ fn process_node(
     let rotation: Quat = Quat::from_array(rotation);
     let scale: Vec3 = scale.into();
 
-    let node_name = node.name().unwrap_or("unnamed_node");
+    let node_name = &names[node.index()];
+    let skin = node.skin().and_then(|skin| skins.get(&skin.index()));
 
     // Create an Empty node for this gltf node to hold the transform
     let empty_node = Empty::builder()
@@ -542,6 +886,12 @@ fn process_node(
     // If this node has a mesh, create Mesh3D nodes for each primitive
     if let Some(mesh) = node.mesh() {
         let mesh_index = mesh.index();
+        // the node's weights override the mesh's defaults
+        let morph_weights = node
+            .weights()
+            .or_else(|| mesh.weights())
+            .unwrap_or_default()
+            .to_vec();
 
         for (primitive_index, primitive) in mesh.primitives().enumerate() {
             let key = PrimitiveKey {
@@ -564,10 +914,15 @@ fn process_node(
                 .get(&material_index)
                 .expect("material should have been preloaded");
 
-            let mesh_instance = MeshInstance3D::builder()
+            let mut mesh_instance = MeshInstance3D::builder()
                 .mesh(mesh_3d.clone())
                 .material(material.clone())
-                .build();
+                .morph_weights(morph_weights.clone());
+            // skinned vertices are moved outside of the mesh's bounding box
+            if let Some(skin) = skin {
+                mesh_instance = mesh_instance.skin(skin.clone()).frustum_culled(false);
+            }
+            let mesh_instance = mesh_instance.build();
 
             let primitive_name = format!("primitive_{}", primitive_index);
             // Add mesh as child of the empty node
@@ -575,6 +930,64 @@ fn process_node(
         }
     }
 
+    if loader.cameras
+        && let Some(camera) = node.camera()
+    {
+        match camera.projection() {
+            gltf::camera::Projection::Perspective(perspective) => {
+                let camera = Camera3D::builder()
+                    .fov(perspective.yfov().to_degrees())
+                    .near_plane(perspective.znear())
+                    .far_plane(perspective.zfar().unwrap_or(1000.0))
+                    .build();
+                scene.spawn_as_child("camera", camera, empty_handle);
+            }
+            gltf::camera::Projection::Orthographic(_) => {
+                log::warn!("skipped orthographic gltf camera on {node_name}");
+            }
+        }
+    }
+
+    // lights shine down -Z like gltf lights so they need no rotation of their own
+    if loader.lights
+        && let Some(light) = node.light()
+    {
+        let color = Color::from(light.color());
+        match light.kind() {
+            gltf::khr_lights_punctual::Kind::Directional => {
+                let light = DirectionalLight::builder()
+                    .color(color)
+                    .intensity(light.intensity() / LUMENS_PER_WATT)
+                    .build();
+                scene.spawn_as_child("light", light, empty_handle);
+            }
+            gltf::khr_lights_punctual::Kind::Point => {
+                let light = PointLight::builder()
+                    .color(color)
+                    .intensity(light.intensity() * 4.0 * std::f32::consts::PI / LUMENS_PER_WATT)
+                    .build();
+                scene.spawn_as_child("light", light, empty_handle);
+            }
+            gltf::khr_lights_punctual::Kind::Spot {
+                inner_cone_angle,
+                outer_cone_angle,
+            } => {
+                let intensity = light.intensity() * 4.0 * std::f32::consts::PI / LUMENS_PER_WATT;
+                let light = SpotLight::builder()
+                    .color(color)
+                    .intensity(intensity)
+                    .cone(inner_cone_angle.to_degrees(), outer_cone_angle.to_degrees())
+                    .range(
+                        light
+                            .range()
+                            .unwrap_or_else(|| PointLight::calculate_far_plane(intensity, 0.01)),
+                    )
+                    .build();
+                scene.spawn_as_child("light", light, empty_handle);
+            }
+        }
+    }
+
     // Recursively process children - pass this node's ID as parent
     for child_node in node.children() {
         process_node(
@@ -585,6 +998,8 @@ fn process_node(
             texture_handles,
             material_handles,
             preprocessed_meshes,
+            names,
+            skins,
         );
     }
 }
@@ -624,7 +1039,7 @@ fn build_material<'a>(
             |m| {
                 m.pbr_specular_glossiness()
                     .and_then(|sg| sg.diffuse_texture())
-                    .map(|t| t.texture().source().index())
+                    .map(|t| t.texture().index())
             },
             texture_handles,
         );
@@ -635,7 +1050,7 @@ fn build_material<'a>(
             |m| {
                 m.pbr_specular_glossiness()
                     .and_then(|sg| sg.specular_glossiness_texture())
-                    .map(|t| t.texture().source().index())
+                    .map(|t| t.texture().index())
             },
             texture_handles,
         );
@@ -656,7 +1071,7 @@ fn build_material<'a>(
             |m| {
                 m.pbr_metallic_roughness()
                     .base_color_texture()
-                    .map(|t| t.texture().source().index())
+                    .map(|t| t.texture().index())
             },
             texture_handles,
         );
@@ -666,7 +1081,7 @@ fn build_material<'a>(
             |m| {
                 m.pbr_metallic_roughness()
                     .metallic_roughness_texture()
-                    .map(|t| t.texture().source().index())
+                    .map(|t| t.texture().index())
             },
             texture_handles,
         );
@@ -683,19 +1098,19 @@ fn build_material<'a>(
     // Load common textures (same for both workflows)
     let normal_texture = load_texture(
         material_model,
-        |m| m.normal_texture().map(|t| t.texture().source().index()),
+        |m| m.normal_texture().map(|t| t.texture().index()),
         texture_handles,
     );
 
     let occlusion_texture = load_texture(
         material_model,
-        |m| m.occlusion_texture().map(|f| f.texture().source().index()),
+        |m| m.occlusion_texture().map(|f| f.texture().index()),
         texture_handles,
     );
 
     let emissive_texture = load_texture(
         material_model,
-        |m| m.emissive_texture().map(|t| t.texture().source().index()),
+        |m| m.emissive_texture().map(|t| t.texture().index()),
         texture_handles,
     );
 
@@ -706,9 +1121,6 @@ fn build_material<'a>(
         gltf::material::AlphaMode::Blend => AlphaMode::Blend,
     };
 
-    // Check for unlit extension
-    let _is_unlit = material_model.unlit();
-
     let mut material = PbrMaterial {
         base_color_factor: base_color_factor.into(),
         base_color_texture,
@@ -726,6 +1138,7 @@ fn build_material<'a>(
         double_sided: material_model.double_sided(),
         alpha_mode: gltf_alpha_mode,
         alpha_cutoff: material_model.alpha_cutoff().unwrap_or(0.5),
+        unlit: material_model.unlit(),
         ..Default::default()
     };
 
@@ -2,32 +2,70 @@
 //!
 //! contains nodes, assets, materials, pipelines, and tools for rendering 3d scenes in maple
 
+pub mod animation;
 pub mod assets;
 pub mod gltf;
+pub mod lod;
 pub mod math;
+pub mod model;
 pub mod nodes;
 pub mod plugin;
 pub mod render_passes;
+pub mod static_batch;
+pub mod tilemap;
+pub mod visibility;
+
+/// the version of this crate
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 pub mod prelude {
     pub use crate::nodes::{
+        animation_player::{AnimationFinished, AnimationPlayer3D, AnimationPlayer3DBuilder},
+        area_light::{AreaLight, AreaLightBuilder, AreaLightShape},
         camera::{Camera3D, Camera3DBuilder},
         directional_light::{DirectionalLight, DirectionalLightBuilder},
-        environment::{Environment, ResolutionScale},
-        mesh_instance::{MeshInstance3D, MeshInstance3DBuilder},
+        environment::{Environment, EnvironmentSource, ResolutionScale},
+        instanced_model::{InstancedModel, InstancedModelBuilder},
+        mesh_instance::{MeshInstance3D, MeshInstance3DBuilder, raycast_meshes},
         point_light::{PointLight, PointLightBuilder},
+        rts_camera::{
+            DragSelection, RtsCameraController, RtsCameraControllerBuilder, SelectionRect,
+            select_in_rect,
+        },
+        spot_light::{SpotLight, SpotLightBuilder},
+        sprite_animation::{
+            SpriteAnimation, SpriteAnimationBuilder, SpriteAnimationFinished, SpriteFrameChanged,
+        },
     };
 
+    pub use crate::animation::{AnimationClip, Channel, Interpolation, Keyframes, Skin};
+
     pub use crate::assets::materials::PbrMaterial;
 
     pub use crate::gltf::GltfScene;
 
+    pub use crate::model::{Model, ModelData, ModelMaterial, ModelMesh};
+
+    pub use crate::tilemap::{TileLayer, TileObject, TiledMap, Tilemap};
+
+    pub use crate::visibility::{OnScreen, VisibilityChanged};
+
+    pub use crate::lod::{LodLevel, MeshLod};
+
+    pub use crate::static_batch::{StaticBatchExt, StaticBatchStats};
+
+    pub use crate::render_passes::shadow_filter::ShadowFilter;
+    pub use crate::render_passes::shadow_lod::ShadowLod;
+    pub use crate::render_passes::transparency::TransparencySort;
+
     pub use crate::assets::material::{
         AlphaMode, Material, MaterialInstance, MaterialInstanceMut, MaterialInstanceRef,
+        MaterialPass,
     };
 
-    pub use crate::assets::mesh::Mesh3D;
+    pub use crate::assets::mesh::{Mesh3D, MeshBuilder, MorphTarget, MorphTargets};
     pub use crate::assets::primitives::*;
+    pub use crate::assets::texture_atlas::{AtlasRegion, TextureAtlas};
 
     pub use crate::plugin::Core3D;
 }
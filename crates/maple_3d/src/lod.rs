@@ -0,0 +1,214 @@
+//! lower detail meshes drawn in place of a mesh while it is small on screen
+//!
+//! a [`MeshInstance3D`] with a [`MeshLod`] draws its own mesh up close and switches to the levels
+//! of the lod as it covers less of the screen. the level is picked once a frame for the active
+//! camera from how much of the screen's height the bounding sphere of the mesh covers, shadows are
+//! drawn with the same level:
+//!
+//! ```rust, ignore
+//! ctx.scene.spawn(
+//!     MeshInstance3D::builder()
+//!         .mesh(rock)
+//!         .material(stone)
+//!         .lod(
+//!             MeshLod::new()
+//!                 .level(rock_medium, 0.3)
+//!                 .level(rock_low, 0.1)
+//!                 .culled_below(0.01),
+//!         ),
+//! );
+//! ```
+//!
+//! levels can be made from a loaded mesh with [`MeshLod::generate`], which merges nearby
+//! vertices with [`MeshBuilder::decimated`]. hand made levels look better for the meshes that
+//! are seen the most.
+
+use maple_engine::{
+    GameContext,
+    asset::{AssetHandle, AssetLibrary},
+};
+
+use crate::{
+    assets::mesh::{Mesh3D, MeshBuilder},
+    nodes::{camera::Camera3D, mesh_instance::MeshInstance3D},
+    visibility::active_camera,
+};
+
+/// a mesh drawn while the instance covers less of the screen than `screen_size`
+#[derive(Debug, Clone)]
+pub struct LodLevel {
+    /// `None` draws nothing
+    pub mesh: Option<AssetHandle<Mesh3D>>,
+    /// the fraction of the screen's height the bounding sphere of the mesh covers below which
+    /// the level is drawn
+    pub screen_size: f32,
+}
+
+/// the levels of detail of a [`MeshInstance3D`], see the [module docs](self)
+#[derive(Debug, Clone, Default)]
+pub struct MeshLod {
+    /// ordered from the largest screen size to the smallest
+    levels: Vec<LodLevel>,
+    /// 0 is the mesh of the instance, the levels follow
+    current: usize,
+}
+
+impl MeshLod {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// draw `mesh` once the instance covers less than `screen_size` of the screen's height
+    pub fn level(self, mesh: AssetHandle<Mesh3D>, screen_size: f32) -> Self {
+        self.with_level(LodLevel {
+            mesh: Some(mesh),
+            screen_size,
+        })
+    }
+
+    /// draw nothing once the instance covers less than `screen_size` of the screen's height
+    pub fn culled_below(self, screen_size: f32) -> Self {
+        self.with_level(LodLevel {
+            mesh: None,
+            screen_size,
+        })
+    }
+
+    pub fn with_level(mut self, level: LodLevel) -> Self {
+        self.levels.push(level);
+        self.levels
+            .sort_by(|a, b| b.screen_size.total_cmp(&a.screen_size));
+        self
+    }
+
+    /// `levels` coarser copies of `mesh` each drawn below half the screen size of the one before,
+    /// starting at half of the screen. meshes made with [`Mesh3D::from_buffers`] get no levels
+    pub fn generate(assets: &AssetLibrary, mesh: &Mesh3D, levels: usize) -> Self {
+        let mut lod = Self::new();
+        let Some(builder) = MeshBuilder::from_mesh(mesh) else {
+            return lod;
+        };
+
+        let size = (mesh.aabb().max - mesh.aabb().min).max_element();
+        let mut screen_size = 0.5;
+        for level in 0..levels {
+            // the cells double in size every level, starting at a 64th of the mesh
+            let cell_size = size / 64.0 * 2f32.powi(level as i32);
+            let decimated = builder.decimated(cell_size);
+            if decimated.is_empty() {
+                break;
+            }
+            lod = lod.level(assets.add(decimated), screen_size);
+            screen_size *= 0.5;
+        }
+        lod
+    }
+
+    pub fn levels(&self) -> &[LodLevel] {
+        &self.levels
+    }
+
+    /// the level drawn this frame, `None` if the instance's own mesh is drawn
+    pub fn current(&self) -> Option<&LodLevel> {
+        self.current
+            .checked_sub(1)
+            .and_then(|index| self.levels.get(index))
+    }
+
+    /// the level for an instance covering `screen_size` of the screen's height, 0 for the mesh of
+    /// the instance
+    pub fn select(&self, screen_size: f32) -> usize {
+        self.levels
+            .iter()
+            .take_while(|level| screen_size < level.screen_size)
+            .count()
+    }
+
+    /// the mesh to draw in place of `mesh`
+    pub(crate) fn mesh<'a>(
+        &'a self,
+        mesh: Option<&'a AssetHandle<Mesh3D>>,
+    ) -> Option<&'a AssetHandle<Mesh3D>> {
+        match self.current() {
+            Some(level) => level.mesh.as_ref(),
+            None => mesh,
+        }
+    }
+}
+
+/// the fraction of the screen's height a sphere `distance` away covers, `tan_half_fov` is the
+/// tangent of half the camera's vertical field of view
+pub fn screen_size(radius: f32, distance: f32, tan_half_fov: f32) -> f32 {
+    if distance <= radius {
+        return 1.0;
+    }
+    radius / (distance * tan_half_fov.max(f32::EPSILON))
+}
+
+/// pick the level of every mesh instance with a lod for the active camera
+pub(crate) fn update_lods(ctx: &GameContext) {
+    let Some(camera) = active_camera(&ctx.scene).and_then(|id| ctx.scene.get::<Camera3D>(id))
+    else {
+        return;
+    };
+    let (eye, tan_half_fov) = {
+        let camera = camera.read();
+        (
+            camera.transform.world_space().position(),
+            (camera.fov.to_radians() * 0.5).tan(),
+        )
+    };
+
+    ctx.scene.for_each(&mut |instance: &mut MeshInstance3D| {
+        let Some(lod) = &mut instance.lod else {
+            return;
+        };
+        let Some(mesh) = instance.mesh.as_ref().and_then(|mesh| ctx.assets.get(mesh)) else {
+            return;
+        };
+        let aabb = mesh.world_aabb(*instance.transform.world_space());
+        let radius = (aabb.max - aabb.min).length() * 0.5;
+        let size = screen_size(radius, aabb.center().distance(eye), tan_half_fov);
+
+        let selected = lod.select(size);
+        // the level drawn now stays until the next one has loaded
+        let loaded = match selected.checked_sub(1).map(|index| &lod.levels[index]) {
+            Some(LodLevel {
+                mesh: Some(mesh), ..
+            }) => ctx.assets.is_loaded(mesh),
+            _ => true,
+        };
+        if loaded {
+            lod.current = selected;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels_are_picked_by_screen_size() {
+        let level = |screen_size| LodLevel {
+            mesh: None,
+            screen_size,
+        };
+        let lod = MeshLod::new()
+            .culled_below(0.01)
+            .with_level(level(0.5))
+            .with_level(level(0.1));
+
+        // sorted from the largest screen size
+        let sizes: Vec<f32> = lod.levels().iter().map(|level| level.screen_size).collect();
+        assert_eq!(sizes, [0.5, 0.1, 0.01]);
+        assert_eq!(lod.select(0.8), 0);
+        assert_eq!(lod.select(0.3), 1);
+        assert_eq!(lod.select(0.05), 2);
+        assert_eq!(lod.select(0.001), 3);
+
+        // a unit sphere 10 units away with a 90 degree field of view
+        assert!((screen_size(1.0, 10.0, 1.0) - 0.1).abs() < 1e-6);
+        assert_eq!(screen_size(1.0, 0.5, 1.0), 1.0);
+    }
+}
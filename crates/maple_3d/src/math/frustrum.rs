@@ -119,4 +119,10 @@ impl Frustum {
 
         true
     }
+
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.distance_to_point(point) >= 0.0)
+    }
 }
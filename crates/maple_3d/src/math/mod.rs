@@ -1,7 +1,6 @@
-mod aabb;
 mod frustrum;
 mod vertex;
 
-pub use aabb::*;
 pub use frustrum::*;
+pub use maple_engine::utils::aabb::AABB;
 pub use vertex::*;
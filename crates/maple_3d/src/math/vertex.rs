@@ -13,6 +13,12 @@ pub struct Vertex {
     pub tangent: [f32; 3],
 
     pub bitangent: [f32; 3],
+
+    /// indices into the mesh's [`crate::animation::Skin`] joints
+    pub joints: [u32; 4],
+
+    /// how much each of the joints moves the vertex, all zero for meshes without a skin
+    pub weights: [f32; 4],
 }
 
 impl VertexLayout for Vertex {
@@ -22,5 +28,7 @@ impl VertexLayout for Vertex {
         2 => Float32x2, // tex_uv
         3 => Float32x3, // tangent
         4 => Float32x3, // bitangent
+        5 => Uint32x4,  // joints
+        6 => Float32x4, // weights
     ];
 }
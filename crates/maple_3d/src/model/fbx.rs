@@ -0,0 +1,1079 @@
+//! parser for binary autodesk `.fbx` models
+//!
+//! reads the mesh models of the file with their node tree, local transforms and materials.
+//! polygons are triangulated as fans and split into a primitive per material, normals and the
+//! first uv set are read with any mapping the file uses. skins, animations, cameras, lights and
+//! embedded textures are skipped, textures are loaded from their relative file name.
+//!
+//! the file's up axis and unit scale are turned into the root's rotation and scale so models
+//! exported in centimeters or with z up come out the right size and way up. ascii fbx files
+//! can't be read, export them as binary.
+
+use std::{
+    collections::HashMap,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use glam::{EulerRot, Mat3, Quat, Vec2, Vec3};
+use maple_engine::{asset::LoadErr, color::Color};
+
+use super::{ModelData, ModelMaterial, ModelNode, ModelPrimitive};
+use crate::{assets::mesh::fill_normals, math::Vertex};
+
+const MAGIC: &[u8] = b"Kaydara FBX Binary  \0";
+
+/// a value stored on an fbx node
+#[derive(Debug, Clone, PartialEq)]
+pub enum Property {
+    Bool(bool),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+    Bools(Vec<bool>),
+    I32s(Vec<i32>),
+    I64s(Vec<i64>),
+    F32s(Vec<f32>),
+    F64s(Vec<f64>),
+    String(String),
+    Raw(Vec<u8>),
+}
+
+impl Property {
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Property::Bool(value) => Some(*value as u8 as f64),
+            Property::I16(value) => Some(*value as f64),
+            Property::I32(value) => Some(*value as f64),
+            Property::I64(value) => Some(*value as f64),
+            Property::F32(value) => Some(*value as f64),
+            Property::F64(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Property::I16(value) => Some(*value as i64),
+            Property::I32(value) => Some(*value as i64),
+            Property::I64(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Property::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// float arrays of either precision
+    pub fn as_f64s(&self) -> Option<Vec<f64>> {
+        match self {
+            Property::F32s(values) => Some(values.iter().map(|value| *value as f64).collect()),
+            Property::F64s(values) => Some(values.clone()),
+            _ => None,
+        }
+    }
+
+    /// int arrays of either size
+    pub fn as_i32s(&self) -> Option<Vec<i32>> {
+        match self {
+            Property::I32s(values) => Some(values.clone()),
+            Property::I64s(values) => Some(values.iter().map(|value| *value as i32).collect()),
+            _ => None,
+        }
+    }
+}
+
+/// a node of the fbx document tree
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FbxNode {
+    pub name: String,
+    pub properties: Vec<Property>,
+    pub children: Vec<FbxNode>,
+}
+
+impl FbxNode {
+    pub fn child(&self, name: &str) -> Option<&FbxNode> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a FbxNode> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    /// the first property of the child `name`
+    fn value(&self, name: &str) -> Option<&Property> {
+        self.child(name)?.properties.first()
+    }
+
+    /// the `P` entries of the node's `Properties70` by name, each with the values after its
+    /// name, type, label and flags
+    fn properties70(&self) -> HashMap<&str, &[Property]> {
+        self.child("Properties70")
+            .map(|properties| {
+                properties
+                    .children_named("P")
+                    .filter_map(|p| Some((p.properties.first()?.as_str()?, p.properties.get(4..)?)))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// the object's name without the class fbx stores after it
+    fn object_name(&self) -> String {
+        let name = self
+            .properties
+            .get(1)
+            .and_then(Property::as_str)
+            .unwrap_or("");
+        let name = name.split('\0').next().unwrap_or(name);
+        // older files write `Model::name`
+        let name = name.split_once("::").map_or(name, |(_, name)| name);
+        name.to_string()
+    }
+}
+
+fn invalid(message: &str) -> LoadErr {
+    LoadErr::Import(format!("invalid fbx: {message}"))
+}
+
+/// read the node tree of a binary fbx file
+pub fn parse_document(bytes: &[u8]) -> Result<Vec<FbxNode>, LoadErr> {
+    if !bytes.starts_with(MAGIC) {
+        return match bytes.starts_with(b"; FBX") {
+            true => Err(LoadErr::Import(
+                "ascii fbx files can't be loaded, export it as binary".into(),
+            )),
+            false => Err(invalid("not an fbx file")),
+        };
+    }
+
+    let mut reader = Reader {
+        bytes,
+        position: MAGIC.len() + 2,
+    };
+    let version = reader.u32()?;
+    let wide = version >= 7500;
+
+    let mut nodes = Vec::new();
+    // the footer after the top level nodes is left alone
+    while reader.position < bytes.len() {
+        match read_node(&mut reader, wide)? {
+            Some(node) => nodes.push(node),
+            None => break,
+        }
+    }
+    Ok(nodes)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], LoadErr> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| invalid("the file ends early"))?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], LoadErr> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, LoadErr> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, LoadErr> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn offset(&mut self, wide: bool) -> Result<u64, LoadErr> {
+        match wide {
+            true => Ok(u64::from_le_bytes(self.array()?)),
+            false => Ok(self.u32()? as u64),
+        }
+    }
+}
+
+/// `None` for the empty record that ends a list of nodes
+fn read_node(reader: &mut Reader, wide: bool) -> Result<Option<FbxNode>, LoadErr> {
+    let end = reader.offset(wide)? as usize;
+    let property_count = reader.offset(wide)?;
+    let _property_len = reader.offset(wide)?;
+    let name_len = reader.u8()? as usize;
+    if end == 0 {
+        return Ok(None);
+    }
+    if end > reader.bytes.len() || end < reader.position {
+        return Err(invalid("a node ends outside of the file"));
+    }
+
+    let name = String::from_utf8_lossy(reader.take(name_len)?).into_owned();
+    let mut properties = Vec::new();
+    for _ in 0..property_count {
+        properties.push(read_property(reader)?);
+    }
+
+    let mut children = Vec::new();
+    while reader.position < end {
+        match read_node(reader, wide)? {
+            Some(child) => children.push(child),
+            None => break,
+        }
+    }
+    reader.position = end;
+
+    Ok(Some(FbxNode {
+        name,
+        properties,
+        children,
+    }))
+}
+
+fn read_property(reader: &mut Reader) -> Result<Property, LoadErr> {
+    Ok(match reader.u8()? {
+        b'C' => Property::Bool(reader.u8()? != 0),
+        b'Y' => Property::I16(i16::from_le_bytes(reader.array()?)),
+        b'I' => Property::I32(i32::from_le_bytes(reader.array()?)),
+        b'L' => Property::I64(i64::from_le_bytes(reader.array()?)),
+        b'F' => Property::F32(f32::from_le_bytes(reader.array()?)),
+        b'D' => Property::F64(f64::from_le_bytes(reader.array()?)),
+        b'S' => {
+            let len = reader.u32()? as usize;
+            Property::String(String::from_utf8_lossy(reader.take(len)?).into_owned())
+        }
+        b'R' => {
+            let len = reader.u32()? as usize;
+            Property::Raw(reader.take(len)?.to_vec())
+        }
+        kind @ (b'b' | b'i' | b'l' | b'f' | b'd') => {
+            let (data, count) = read_array(reader, element_size(kind))?;
+            let values = data.chunks_exact(element_size(kind)).take(count);
+            match kind {
+                b'b' => Property::Bools(values.map(|value| value[0] != 0).collect()),
+                b'i' => Property::I32s(
+                    values
+                        .map(|value| i32::from_le_bytes(value.try_into().unwrap()))
+                        .collect(),
+                ),
+                b'l' => Property::I64s(
+                    values
+                        .map(|value| i64::from_le_bytes(value.try_into().unwrap()))
+                        .collect(),
+                ),
+                b'f' => Property::F32s(
+                    values
+                        .map(|value| f32::from_le_bytes(value.try_into().unwrap()))
+                        .collect(),
+                ),
+                _ => Property::F64s(
+                    values
+                        .map(|value| f64::from_le_bytes(value.try_into().unwrap()))
+                        .collect(),
+                ),
+            }
+        }
+        kind => {
+            return Err(invalid(&format!(
+                "unknown property type {:?}",
+                kind as char
+            )));
+        }
+    })
+}
+
+fn element_size(kind: u8) -> usize {
+    match kind {
+        b'b' => 1,
+        b'i' | b'f' => 4,
+        _ => 8,
+    }
+}
+
+/// the bytes of an array property, inflated if they are compressed
+fn read_array(reader: &mut Reader, element_size: usize) -> Result<(Vec<u8>, usize), LoadErr> {
+    let count = reader.u32()? as usize;
+    let encoding = reader.u32()?;
+    let len = reader.u32()? as usize;
+    let data = reader.take(len)?;
+
+    let data = match encoding {
+        0 => data.to_vec(),
+        1 => {
+            let mut inflated = Vec::with_capacity(count * element_size);
+            flate2::read::ZlibDecoder::new(data)
+                .read_to_end(&mut inflated)
+                .map_err(|e| invalid(&format!("failed to inflate an array: {e}")))?;
+            inflated
+        }
+        _ => return Err(invalid("unknown array encoding")),
+    };
+    if data.len() < count * element_size {
+        return Err(invalid("an array is shorter than its length"));
+    }
+    Ok((data, count))
+}
+
+/// read a binary fbx model, texture paths are made relative to `base_dir`
+pub fn parse(bytes: &[u8], base_dir: &Path) -> Result<ModelData, LoadErr> {
+    let document = parse_document(bytes)?;
+    let find = |name: &str| document.iter().find(|node| node.name == name);
+
+    let mut objects: HashMap<i64, &FbxNode> = HashMap::new();
+    if let Some(list) = find("Objects") {
+        for object in &list.children {
+            if let Some(id) = object.properties.first().and_then(Property::as_i64) {
+                objects.insert(id, object);
+            }
+        }
+    }
+
+    // (child, parent, property) in the order the file lists them, material slots follow it
+    let connections: Vec<(i64, i64, Option<&str>)> = find("Connections")
+        .map(|connections| {
+            connections
+                .children_named("C")
+                .filter_map(|c| {
+                    let child = c.properties.get(1)?.as_i64()?;
+                    let parent = c.properties.get(2)?.as_i64()?;
+                    Some((
+                        child,
+                        parent,
+                        c.properties.get(3).and_then(Property::as_str),
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let is = |id: i64, kind: &str| objects.get(&id).is_some_and(|object| object.name == kind);
+    let parents_of = |child: i64| {
+        connections
+            .iter()
+            .filter(move |(c, _, _)| *c == child)
+            .map(|(_, parent, _)| *parent)
+    };
+    let children_of = |parent: i64, kind: &'static str| {
+        connections
+            .iter()
+            .filter(move |(c, p, _)| *p == parent && is(*c, kind))
+            .map(|(child, _, property)| (*child, *property))
+    };
+
+    // materials
+    let mut material_ids: Vec<i64> = objects
+        .iter()
+        .filter(|(_, object)| object.name == "Material")
+        .map(|(id, _)| *id)
+        .collect();
+    material_ids.sort_unstable();
+    let materials: Vec<ModelMaterial> = material_ids
+        .iter()
+        .map(|id| {
+            let textures = children_of(*id, "Texture")
+                .filter_map(|(texture, property)| {
+                    let path = texture_path(objects[&texture], base_dir)?;
+                    Some((property.unwrap_or(""), path))
+                })
+                .collect();
+            material(objects[id], textures)
+        })
+        .collect();
+    let material_index: HashMap<i64, usize> = material_ids
+        .iter()
+        .enumerate()
+        .map(|(index, id)| (*id, index))
+        .collect();
+
+    // models, sorted by id so parents are found before the order is fixed below
+    let mut model_ids: Vec<i64> = objects
+        .iter()
+        .filter(|(_, object)| object.name == "Model")
+        .map(|(id, _)| *id)
+        .collect();
+    model_ids.sort_unstable();
+    let model_parent: HashMap<i64, i64> = model_ids
+        .iter()
+        .filter_map(|id| {
+            let parent = parents_of(*id).find(|parent| is(*parent, "Model"))?;
+            Some((*id, parent))
+        })
+        .collect();
+
+    // parents before children
+    let mut ordered: Vec<i64> = Vec::with_capacity(model_ids.len());
+    fn visit(id: i64, parents: &HashMap<i64, i64>, ordered: &mut Vec<i64>, depth: usize) {
+        if ordered.contains(&id) || depth > 256 {
+            return;
+        }
+        if let Some(parent) = parents.get(&id) {
+            visit(*parent, parents, ordered, depth + 1);
+        }
+        if !ordered.contains(&id) {
+            ordered.push(id);
+        }
+    }
+    for id in &model_ids {
+        visit(*id, &model_parent, &mut ordered, 0);
+    }
+    let node_index: HashMap<i64, usize> = ordered
+        .iter()
+        .enumerate()
+        .map(|(index, id)| (*id, index))
+        .collect();
+
+    let mut nodes = Vec::with_capacity(ordered.len());
+    for id in &ordered {
+        let model = objects[id];
+        let mut node = ModelNode::new(model.object_name());
+        node.parent = model_parent
+            .get(id)
+            .and_then(|parent| node_index.get(parent).copied());
+
+        let properties = model.properties70();
+        let vec3 = |name: &str, default: Vec3| {
+            properties
+                .get(name)
+                .and_then(|values| {
+                    let [x, y, z] = [0, 1, 2].map(|i| values.get(i).and_then(Property::as_f64));
+                    Some(Vec3::new(x? as f32, y? as f32, z? as f32))
+                })
+                .unwrap_or(default)
+        };
+        let euler = |degrees: Vec3| {
+            let radians = degrees * std::f32::consts::PI / 180.0;
+            // fbx's default xyz order turns around x first
+            Quat::from_euler(EulerRot::ZYX, radians.z, radians.y, radians.x)
+        };
+        node.position = vec3("Lcl Translation", Vec3::ZERO);
+        node.rotation =
+            euler(vec3("PreRotation", Vec3::ZERO)) * euler(vec3("Lcl Rotation", Vec3::ZERO));
+        node.scale = vec3("Lcl Scaling", Vec3::ONE);
+
+        // the model's materials in the order of the geometry's material indices
+        let slots: Vec<usize> = children_of(*id, "Material")
+            .filter_map(|(material, _)| material_index.get(&material).copied())
+            .collect();
+        for (geometry, _) in children_of(*id, "Geometry") {
+            node.primitives
+                .extend(geometry_primitives(objects[&geometry], &slots)?);
+        }
+        nodes.push(node);
+    }
+
+    let (rotation, scale) = find("GlobalSettings").map_or((Quat::IDENTITY, 1.0), axes);
+
+    Ok(ModelData {
+        rotation,
+        scale,
+        nodes,
+        materials,
+    })
+}
+
+/// the rotation and scale from the file's axes and units to y up meters
+fn axes(settings: &FbxNode) -> (Quat, f32) {
+    let properties = settings.properties70();
+    let int = |name: &str, default: i64| {
+        properties
+            .get(name)
+            .and_then(|values| values.first()?.as_i64())
+            .unwrap_or(default)
+    };
+    let axis = |name: &str, sign: &str, default: i64| {
+        let mut axis = Vec3::ZERO;
+        axis[int(name, default).clamp(0, 2) as usize] = int(sign, 1).signum() as f32;
+        axis
+    };
+
+    // the file's right, up and front in its own coordinates
+    let right = axis("CoordAxis", "CoordAxisSign", 0);
+    let up = axis("UpAxis", "UpAxisSign", 1);
+    let front = axis("FrontAxis", "FrontAxisSign", 2);
+    let to_engine = Mat3::from_cols(right, up, front).transpose();
+
+    // a mirrored file can't be turned into a rotation
+    let rotation = match to_engine.determinant() > 0.0 {
+        true => Quat::from_mat3(&to_engine),
+        false => {
+            log::warn!("fbx axes are mirrored, the model is loaded without turning them");
+            Quat::IDENTITY
+        }
+    };
+
+    // fbx counts in centimeters
+    let unit_scale = properties
+        .get("UnitScaleFactor")
+        .and_then(|values| values.first()?.as_f64())
+        .unwrap_or(1.0);
+
+    (rotation, unit_scale as f32 / 100.0)
+}
+
+fn material(material: &FbxNode, textures: Vec<(&str, PathBuf)>) -> ModelMaterial {
+    let properties = material.properties70();
+    let float = |name: &str| {
+        properties
+            .get(name)
+            .and_then(|values| values.first()?.as_f64())
+            .map(|value| value as f32)
+    };
+    let color = |name: &str| {
+        let values = properties.get(name)?;
+        let [r, g, b] = [0, 1, 2].map(|i| values.get(i).and_then(Property::as_f64));
+        Some(Vec3::new(r? as f32, g? as f32, b? as f32))
+    };
+
+    let mut out = ModelMaterial {
+        name: material.object_name(),
+        ..Default::default()
+    };
+
+    let diffuse =
+        color("DiffuseColor").unwrap_or(Vec3::ONE) * float("DiffuseFactor").unwrap_or(1.0);
+    let opacity = float("Opacity")
+        .or_else(|| float("TransparencyFactor").map(|transparency| 1.0 - transparency))
+        .unwrap_or(1.0)
+        .clamp(0.0, 1.0);
+    out.base_color = Color::from_normalized(diffuse.x, diffuse.y, diffuse.z, opacity);
+
+    if let Some(emissive) = color("EmissiveColor") {
+        let emissive = emissive * float("EmissiveFactor").unwrap_or(1.0);
+        out.emissive = Color::from_normalized(emissive.x, emissive.y, emissive.z, 1.0);
+    }
+    if let Some(shininess) = float("ShininessExponent").or_else(|| float("Shininess")) {
+        // blinn phong exponent to roughness
+        out.roughness = (2.0 / (shininess.max(0.0) + 2.0)).sqrt();
+    }
+    // blender writes metallic as the reflection factor
+    if let Some(metallic) = float("ReflectionFactor") {
+        out.metallic = metallic.clamp(0.0, 1.0);
+    }
+
+    for (property, path) in textures {
+        match property {
+            "DiffuseColor" | "Maya|baseColor" => out.base_color_texture = Some(path),
+            "NormalMap" | "Bump" | "Maya|normalCamera" => out.normal_texture = Some(path),
+            "EmissiveColor" | "EmissiveFactor" => out.emissive_texture = Some(path),
+            _ => {}
+        }
+    }
+
+    out
+}
+
+fn texture_path(texture: &FbxNode, base_dir: &Path) -> Option<PathBuf> {
+    let relative = texture
+        .value("RelativeFilename")
+        .and_then(Property::as_str)
+        .filter(|path| !path.is_empty());
+    // absolute paths from the artist's machine are looked for next to the model
+    let file = texture
+        .value("FileName")
+        .and_then(Property::as_str)
+        .and_then(|path| {
+            path.replace('\\', "/")
+                .rsplit('/')
+                .next()
+                .map(str::to_string)
+        });
+
+    let path = match relative {
+        Some(relative) => relative.replace('\\', "/"),
+        None => file.filter(|file| !file.is_empty())?,
+    };
+    Some(base_dir.join(path))
+}
+
+/// how a layer element's values are spread over the mesh
+struct LayerElement {
+    mapping: String,
+    values: Vec<f64>,
+    /// indices into `values` when the reference is `IndexToDirect`
+    indices: Option<Vec<i32>>,
+}
+
+impl LayerElement {
+    fn read(element: Option<&FbxNode>, values: &str, indices: &str) -> Option<Self> {
+        let element = element?;
+        let mapping = element
+            .value("MappingInformationType")
+            .and_then(Property::as_str)
+            .unwrap_or("ByPolygonVertex")
+            .to_string();
+        let reference = element
+            .value("ReferenceInformationType")
+            .and_then(Property::as_str)
+            .unwrap_or("Direct");
+
+        Some(Self {
+            mapping,
+            values: element.value(values)?.as_f64s()?,
+            indices: match reference {
+                "IndexToDirect" | "Index" => element.value(indices)?.as_i32s(),
+                _ => None,
+            },
+        })
+    }
+
+    /// the element's value of `width` floats for a polygon vertex
+    fn get(
+        &self,
+        width: usize,
+        polygon_vertex: usize,
+        control_point: usize,
+        polygon: usize,
+    ) -> Option<&[f64]> {
+        let index = match self.mapping.as_str() {
+            "ByVertice" | "ByVertex" | "ByControlPoint" => control_point,
+            "ByPolygon" => polygon,
+            "AllSame" => 0,
+            _ => polygon_vertex,
+        };
+        let index = match &self.indices {
+            Some(indices) => usize::try_from(*indices.get(index)?).ok()?,
+            None => index,
+        };
+        self.values.get(index * width..(index + 1) * width)
+    }
+}
+
+fn geometry_primitives(
+    geometry: &FbxNode,
+    slots: &[usize],
+) -> Result<Vec<ModelPrimitive>, LoadErr> {
+    let (Some(positions), Some(polygon_vertices)) = (
+        geometry.value("Vertices").and_then(Property::as_f64s),
+        geometry
+            .value("PolygonVertexIndex")
+            .and_then(Property::as_i32s),
+    ) else {
+        // lines and nurbs have no polygons
+        return Ok(Vec::new());
+    };
+
+    let normals = LayerElement::read(
+        geometry.child("LayerElementNormal"),
+        "Normals",
+        "NormalsIndex",
+    );
+    let uvs = LayerElement::read(geometry.child("LayerElementUV"), "UV", "UVIndex");
+    let material_layer = geometry.child("LayerElementMaterial");
+    let material_mapping = material_layer
+        .and_then(|layer| layer.value("MappingInformationType"))
+        .and_then(Property::as_str);
+    let polygon_materials = material_layer
+        .and_then(|layer| layer.value("Materials"))
+        .and_then(Property::as_i32s)
+        .unwrap_or_default();
+
+    let mut builders: Vec<(Option<usize>, Builder)> = Vec::new();
+    let mut polygon = Vec::new();
+    let mut polygon_index = 0;
+    for (polygon_vertex, index) in polygon_vertices.iter().enumerate() {
+        // the last vertex of a polygon is stored as -index - 1
+        let (control_point, last) = match *index < 0 {
+            true => ((-index - 1) as usize, true),
+            false => (*index as usize, false),
+        };
+        let position = positions
+            .get(control_point * 3..control_point * 3 + 3)
+            .ok_or_else(|| invalid("a polygon uses a missing vertex"))?;
+        let normal = normals
+            .as_ref()
+            .and_then(|normals| normals.get(3, polygon_vertex, control_point, polygon_index))
+            .map(|n| Vec3::new(n[0] as f32, n[1] as f32, n[2] as f32));
+        let uv = uvs
+            .as_ref()
+            .and_then(|uvs| uvs.get(2, polygon_vertex, control_point, polygon_index))
+            // v counts up from the bottom of the image
+            .map(|uv| Vec2::new(uv[0] as f32, 1.0 - uv[1] as f32));
+        polygon.push((
+            Vec3::new(position[0] as f32, position[1] as f32, position[2] as f32),
+            normal,
+            uv,
+        ));
+
+        if !last {
+            continue;
+        }
+
+        let slot = match material_mapping {
+            Some("AllSame") => polygon_materials.first(),
+            _ => polygon_materials.get(polygon_index),
+        };
+        let material = slot.and_then(|slot| slots.get(usize::try_from(*slot).ok()?).copied());
+        let builder = match builders.iter().position(|(m, _)| *m == material) {
+            Some(index) => &mut builders[index].1,
+            None => {
+                builders.push((material, Builder::default()));
+                &mut builders.last_mut().unwrap().1
+            }
+        };
+
+        let corners: Vec<u32> = polygon
+            .drain(..)
+            .map(|(position, normal, uv)| builder.vertex(position, normal, uv))
+            .collect();
+        for i in 1..corners.len().saturating_sub(1) {
+            builder
+                .indices
+                .extend([corners[0], corners[i], corners[i + 1]]);
+        }
+        polygon_index += 1;
+    }
+
+    Ok(builders
+        .into_iter()
+        .filter(|(_, builder)| !builder.indices.is_empty())
+        .map(|(material, mut builder)| {
+            fill_normals(
+                &mut builder.vertices,
+                &builder.indices,
+                &builder.missing_normals,
+            );
+            ModelPrimitive {
+                vertices: builder.vertices,
+                indices: builder.indices,
+                material,
+            }
+        })
+        .collect())
+}
+
+#[derive(Default)]
+struct Builder {
+    vertices: Vec<Vertex>,
+    missing_normals: Vec<bool>,
+    indices: Vec<u32>,
+    lookup: HashMap<[u32; 8], u32>,
+}
+
+impl Builder {
+    fn vertex(&mut self, position: Vec3, normal: Option<Vec3>, uv: Option<Vec2>) -> u32 {
+        let uv = uv.unwrap_or_default();
+        let key = [
+            position.x,
+            position.y,
+            position.z,
+            uv.x,
+            uv.y,
+            normal.map_or(f32::NAN, |n| n.x),
+            normal.map_or(f32::NAN, |n| n.y),
+            normal.map_or(f32::NAN, |n| n.z),
+        ]
+        .map(f32::to_bits);
+
+        *self.lookup.entry(key).or_insert_with(|| {
+            self.vertices.push(Vertex {
+                position: position.into(),
+                normal: normal.unwrap_or_default().normalize_or_zero().into(),
+                tex_uv: uv.into(),
+                ..Default::default()
+            });
+            self.missing_normals.push(normal.is_none());
+            self.vertices.len() as u32 - 1
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    /// writes a 7400 binary file, the only version the tests need
+    fn write(nodes: &[FbxNode]) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        out.extend([0x1a, 0x00]);
+        out.extend(7400u32.to_le_bytes());
+        for node in nodes {
+            write_node(&mut out, node);
+        }
+        out.extend([0; 13]);
+        out
+    }
+
+    fn write_node(out: &mut Vec<u8>, node: &FbxNode) {
+        let start = out.len();
+        out.extend([0; 12]);
+        out.push(node.name.len() as u8);
+        out.extend(node.name.as_bytes());
+
+        let properties_start = out.len();
+        for property in &node.properties {
+            write_property(out, property);
+        }
+        let properties_len = (out.len() - properties_start) as u32;
+
+        for child in &node.children {
+            write_node(out, child);
+        }
+        if !node.children.is_empty() {
+            out.extend([0; 13]);
+        }
+
+        let end = out.len() as u32;
+        out[start..start + 4].copy_from_slice(&end.to_le_bytes());
+        out[start + 4..start + 8].copy_from_slice(&(node.properties.len() as u32).to_le_bytes());
+        out[start + 8..start + 12].copy_from_slice(&properties_len.to_le_bytes());
+    }
+
+    fn write_property(out: &mut Vec<u8>, property: &Property) {
+        match property {
+            Property::I32(value) => {
+                out.push(b'I');
+                out.extend(value.to_le_bytes());
+            }
+            Property::I64(value) => {
+                out.push(b'L');
+                out.extend(value.to_le_bytes());
+            }
+            Property::F64(value) => {
+                out.push(b'D');
+                out.extend(value.to_le_bytes());
+            }
+            Property::String(value) => {
+                out.push(b'S');
+                out.extend((value.len() as u32).to_le_bytes());
+                out.extend(value.as_bytes());
+            }
+            Property::I32s(values) => {
+                out.push(b'i');
+                out.extend((values.len() as u32).to_le_bytes());
+                out.extend(0u32.to_le_bytes());
+                out.extend((values.len() as u32 * 4).to_le_bytes());
+                values
+                    .iter()
+                    .for_each(|value| out.extend(value.to_le_bytes()));
+            }
+            // doubles are compressed to cover inflating arrays
+            Property::F64s(values) => {
+                let mut encoder =
+                    flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+                values
+                    .iter()
+                    .for_each(|value| encoder.write_all(&value.to_le_bytes()).unwrap());
+                let compressed = encoder.finish().unwrap();
+
+                out.push(b'd');
+                out.extend((values.len() as u32).to_le_bytes());
+                out.extend(1u32.to_le_bytes());
+                out.extend((compressed.len() as u32).to_le_bytes());
+                out.extend(compressed);
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn node(name: &str, properties: Vec<Property>, children: Vec<FbxNode>) -> FbxNode {
+        FbxNode {
+            name: name.into(),
+            properties,
+            children,
+        }
+    }
+
+    fn string(value: &str) -> Property {
+        Property::String(value.into())
+    }
+
+    fn p(name: &str, values: &[f64]) -> FbxNode {
+        let mut properties = vec![string(name), string(""), string(""), string("")];
+        properties.extend(values.iter().map(|value| Property::F64(*value)));
+        node("P", properties, vec![])
+    }
+
+    fn p_int(name: &str, value: i32) -> FbxNode {
+        let properties = vec![string(name), string("int"), string(""), string("")];
+        node(
+            "P",
+            [properties, vec![Property::I32(value)]].concat(),
+            vec![],
+        )
+    }
+
+    fn object(kind: &str, id: i64, name: &str, children: Vec<FbxNode>) -> FbxNode {
+        let properties = vec![
+            Property::I64(id),
+            string(&format!("{name}\0\x01{kind}")),
+            string(""),
+        ];
+        node(kind, properties, children)
+    }
+
+    fn connect(child: i64, parent: i64, property: Option<&str>) -> FbxNode {
+        let mut properties = vec![
+            string(if property.is_some() { "OP" } else { "OO" }),
+            Property::I64(child),
+            Property::I64(parent),
+        ];
+        properties.extend(property.map(string));
+        node("C", properties, vec![])
+    }
+
+    /// a quad and a triangle with a material each, a cube model with a child and z up centimeters
+    fn document() -> Vec<FbxNode> {
+        let settings = node(
+            "GlobalSettings",
+            vec![],
+            vec![node(
+                "Properties70",
+                vec![],
+                vec![
+                    p_int("UpAxis", 2),
+                    p_int("UpAxisSign", 1),
+                    p_int("FrontAxis", 1),
+                    p_int("FrontAxisSign", -1),
+                    p_int("CoordAxis", 0),
+                    p_int("CoordAxisSign", 1),
+                    p("UnitScaleFactor", &[1.0]),
+                ],
+            )],
+        );
+
+        let geometry = object(
+            "Geometry",
+            10,
+            "Mesh",
+            vec![
+                node(
+                    "Vertices",
+                    vec![Property::F64s(vec![
+                        0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0,
+                    ])],
+                    vec![],
+                ),
+                node(
+                    "PolygonVertexIndex",
+                    vec![Property::I32s(vec![0, 1, 2, -4, 0, 1, -3])],
+                    vec![],
+                ),
+                node(
+                    "LayerElementMaterial",
+                    vec![Property::I32(0)],
+                    vec![
+                        node("MappingInformationType", vec![string("ByPolygon")], vec![]),
+                        node("Materials", vec![Property::I32s(vec![0, 1])], vec![]),
+                    ],
+                ),
+            ],
+        );
+        let cube = object(
+            "Model",
+            20,
+            "Cube",
+            vec![node(
+                "Properties70",
+                vec![],
+                vec![p("Lcl Translation", &[1.0, 2.0, 3.0])],
+            )],
+        );
+        let lid = object("Model", 21, "Lid", vec![]);
+        let wood = object(
+            "Material",
+            30,
+            "Wood",
+            vec![node(
+                "Properties70",
+                vec![],
+                vec![p("DiffuseColor", &[1.0, 0.0, 0.0])],
+            )],
+        );
+        let metal = object(
+            "Material",
+            31,
+            "Metal",
+            vec![node("Properties70", vec![], vec![p("Opacity", &[0.5])])],
+        );
+        let texture = object(
+            "Texture",
+            40,
+            "WoodTexture",
+            vec![node(
+                "RelativeFilename",
+                vec![string("textures\\wood.png")],
+                vec![],
+            )],
+        );
+
+        vec![
+            settings,
+            node(
+                "Objects",
+                vec![],
+                vec![geometry, cube, lid, wood, metal, texture],
+            ),
+            node(
+                "Connections",
+                vec![],
+                vec![
+                    connect(20, 0, None),
+                    connect(21, 20, None),
+                    connect(10, 20, None),
+                    // metal is the model's first material slot
+                    connect(31, 20, None),
+                    connect(30, 20, None),
+                    connect(40, 30, Some("DiffuseColor")),
+                ],
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_parse_fbx() {
+        let bytes = write(&document());
+        assert_eq!(parse_document(&bytes).unwrap(), document());
+
+        let model = parse(&bytes, Path::new("res/models")).unwrap();
+        assert!((model.scale - 0.01).abs() < 1e-6);
+        // z up becomes y up
+        assert!((model.rotation * Vec3::Z).abs_diff_eq(Vec3::Y, 1e-6));
+
+        assert_eq!(model.nodes.len(), 2);
+        let cube = &model.nodes[0];
+        assert_eq!(cube.name, "Cube");
+        assert_eq!(cube.position, Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(model.nodes[1].name, "Lid");
+        assert_eq!(model.nodes[1].parent, Some(0));
+
+        // polygons are split by material and the slots follow the connections
+        assert_eq!(cube.primitives.len(), 2);
+        let quad = &cube.primitives[0];
+        assert_eq!(quad.material, Some(1));
+        assert_eq!(quad.indices, vec![0, 1, 2, 0, 2, 3]);
+        assert_eq!(cube.primitives[1].material, Some(0));
+        assert_eq!(quad.vertices[0].normal, [0.0, 0.0, 1.0]);
+
+        let wood = &model.materials[0];
+        assert_eq!(wood.name, "Wood");
+        assert_eq!(wood.base_color, Color::from_normalized(1.0, 0.0, 0.0, 1.0));
+        assert_eq!(
+            wood.base_color_texture,
+            Some(PathBuf::from("res/models/textures/wood.png"))
+        );
+        assert_eq!(model.materials[1].base_color.a, 0.5);
+    }
+
+    #[test]
+    fn test_ascii_fbx_is_rejected() {
+        let ascii = b"; FBX 7.4.0 project file\nFBXHeaderExtension:  {\n}\n";
+        let Err(LoadErr::Import(message)) = parse(ascii, Path::new("")) else {
+            panic!("ascii fbx should fail to load");
+        };
+        assert!(message.contains("ascii"));
+    }
+}
@@ -0,0 +1,413 @@
+//! models in formats other than gltf, wavefront `.obj` and autodesk `.fbx`
+//!
+//! a [`Model`] asset loads either format by its extension and spawns a node tree for it like
+//! [`crate::gltf::GltfScene`] does:
+//! - an [`Empty`] root named after the file that turns the file's axes and units into the
+//!   engine's y up meters
+//! - an [`Empty`] for each object in the file with its name and local transform under its parent
+//! - a [`MeshInstance3D`] child called `primitive_<n>` for each material of the object's mesh
+//!
+//! materials become [`PbrMaterial`]s and their textures are loaded from paths relative to the
+//! model. see [`obj`] and [`fbx`] for what each format supports.
+//!
+//! ```rust, ignore
+//! let model = ctx.assets.load::<Model>("res/models/crate.obj");
+//! ctx.scene.merge_asset(model);
+//!
+//! // once the model has loaded
+//! let lid = ctx.scene.get_id_by_path("crate/lid");
+//! ```
+
+pub mod fbx;
+pub mod obj;
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use glam::{Mat4, Quat, Vec3};
+use maple_engine::{
+    Scene,
+    asset::{Asset, AssetHandle, AssetLibrary, AssetLoader, FileLoader, IntoAsset, LoadErr},
+    color::Color,
+    nodes::{Buildable, Builder, Empty},
+    scene::{InstancableScene, NodeId, SceneAsset},
+};
+use maple_renderer::core::{RenderDevice, texture::Texture};
+
+use crate::{
+    assets::{
+        material::AlphaMode,
+        materials::PbrMaterial,
+        mesh::{Mesh3D, Mesh3DLoader, MeshBuilder, validate_triangles},
+    },
+    math::Vertex,
+    nodes::mesh_instance::MeshInstance3D,
+    prelude::Material,
+};
+
+/// a model read from a file or built with [`ModelData::from_mesh`] before anything is uploaded
+///
+/// add one to the asset library to get a [`Model`]
+#[derive(Debug, Clone)]
+pub struct ModelData {
+    /// turns the file's axes and units into the engine's
+    pub rotation: Quat,
+    pub scale: f32,
+    /// parents come before their children
+    pub nodes: Vec<ModelNode>,
+    pub materials: Vec<ModelMaterial>,
+}
+
+impl Default for ModelData {
+    fn default() -> Self {
+        Self {
+            rotation: Quat::IDENTITY,
+            scale: 1.0,
+            nodes: Vec::new(),
+            materials: Vec::new(),
+        }
+    }
+}
+
+impl ModelData {
+    /// a model with one node called `mesh` that draws a mesh built at runtime with `material`
+    ///
+    /// ```rust,ignore
+    /// let rock = ModelData::from_mesh(builder, ModelMaterial::default())?;
+    /// ctx.scene.merge_asset(ctx.assets.add(rock));
+    /// ```
+    pub fn from_mesh(mesh: MeshBuilder, material: ModelMaterial) -> Result<Self, LoadErr> {
+        let (vertices, indices) = mesh.finish()?;
+        let mut node = ModelNode::new("mesh");
+        node.primitives.push(ModelPrimitive {
+            vertices,
+            indices,
+            material: Some(0),
+        });
+
+        Ok(Self {
+            nodes: vec![node],
+            materials: vec![material],
+            ..Default::default()
+        })
+    }
+}
+
+impl IntoAsset<Model> for ModelData {
+    fn into_asset(self, loader: &ModelLoader, library: &AssetLibrary) -> Result<Model, LoadErr> {
+        for (index, node) in self.nodes.iter().enumerate() {
+            if node.parent.is_some_and(|parent| parent >= index) {
+                return Err(LoadErr::IntoAsset(format!(
+                    "node {} comes before its parent",
+                    node.name
+                )));
+            }
+            for primitive in &node.primitives {
+                validate_triangles(&primitive.indices, primitive.vertices.len())?;
+            }
+        }
+
+        Ok(loader.build("model", self, library))
+    }
+}
+
+/// an object in a model file
+#[derive(Debug, Clone)]
+pub struct ModelNode {
+    pub name: String,
+    /// index into [`ModelData::nodes`]
+    pub parent: Option<usize>,
+    pub position: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+    pub primitives: Vec<ModelPrimitive>,
+}
+
+impl ModelNode {
+    pub(crate) fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            parent: None,
+            position: Vec3::ZERO,
+            rotation: Quat::IDENTITY,
+            scale: Vec3::ONE,
+            primitives: Vec::new(),
+        }
+    }
+}
+
+/// the faces of an object with one material
+#[derive(Debug, Clone, Default)]
+pub struct ModelPrimitive {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+    /// index into [`ModelData::materials`], `None` uses a plain white material
+    pub material: Option<usize>,
+}
+
+/// the parts of a model file's material that map onto a [`PbrMaterial`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelMaterial {
+    pub name: String,
+    pub base_color: Color,
+    pub base_color_texture: Option<PathBuf>,
+    pub metallic: f32,
+    pub roughness: f32,
+    pub normal_texture: Option<PathBuf>,
+    pub emissive: Color,
+    pub emissive_texture: Option<PathBuf>,
+}
+
+impl Default for ModelMaterial {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            base_color: Color::WHITE,
+            base_color_texture: None,
+            metallic: 0.0,
+            roughness: 0.5,
+            normal_texture: None,
+            emissive: Color::BLACK,
+            emissive_texture: None,
+        }
+    }
+}
+
+/// a mesh of a [`Model`] with where it's drawn relative to the model's root
+#[derive(Clone)]
+pub struct ModelMesh {
+    pub transform: Mat4,
+    pub mesh: AssetHandle<Mesh3D>,
+    pub material: AssetHandle<Material>,
+}
+
+/// a model loaded from an `.obj` or `.fbx` file, see the [module docs](self)
+pub struct Model {
+    pub data: ModelData,
+    materials: Vec<AssetHandle<Material>>,
+    meshes: Vec<ModelMesh>,
+    scene: InstancableScene,
+}
+
+impl Asset for Model {
+    type Loader = ModelLoader;
+}
+
+impl Model {
+    /// the materials of the model in the order of [`ModelData::materials`]
+    pub fn materials(&self) -> &[AssetHandle<Material>] {
+        &self.materials
+    }
+
+    /// every mesh the model draws, used by [`crate::nodes::instanced_model::InstancedModel`] to
+    /// draw it without spawning its nodes
+    pub fn meshes(&self) -> &[ModelMesh] {
+        &self.meshes
+    }
+
+    pub fn get_material_by_name(&self, name: &str) -> Option<AssetHandle<Material>> {
+        let index = self
+            .data
+            .materials
+            .iter()
+            .position(|material| material.name == name)?;
+        self.materials.get(index).cloned()
+    }
+
+    /// override how strongly the normal maps of every material bend the surface, see
+    /// [`PbrMaterial::normal_scale`]
+    pub fn set_normal_scale(&self, assets: &AssetLibrary, scale: f32) {
+        PbrMaterial::modify_all(assets, &self.materials, move |material| {
+            material.normal_scale = scale
+        });
+    }
+}
+
+impl SceneAsset for Model {
+    fn load(&self, scene: &Scene, parent: Option<NodeId>) {
+        match parent {
+            Some(node) => scene.merge_as_child(self.scene.instance(), node),
+            None => scene.merge(self.scene.instance()),
+        };
+    }
+}
+
+/// loader for [`Model`] assets
+pub struct ModelLoader {
+    device: RenderDevice,
+}
+
+impl ModelLoader {
+    pub(crate) fn new(device: RenderDevice) -> Self {
+        Self { device }
+    }
+}
+
+impl AssetLoader for ModelLoader {
+    type Asset = Model;
+}
+
+impl FileLoader for ModelLoader {
+    fn load_path(&self, path: &Path, library: &AssetLibrary) -> Result<Self::Asset, LoadErr> {
+        let bytes = std::fs::read(path).map_err(|e| LoadErr::Import(e.to_string()))?;
+        self.load_bytes(path, bytes, library)
+    }
+
+    /// external `.mtl` files of fetched `.obj` models can't be read so they load white
+    fn load_bytes(
+        &self,
+        path: &Path,
+        bytes: Vec<u8>,
+        library: &AssetLibrary,
+    ) -> Result<Self::Asset, LoadErr> {
+        log::info!("Loading model from {:?}", path);
+        let base_dir = path.parent().unwrap_or(Path::new(""));
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+
+        let data = match extension.as_deref() {
+            Some("obj") => {
+                let source = String::from_utf8_lossy(&bytes);
+                obj::parse(&source, base_dir, |path| std::fs::read_to_string(path))?
+            }
+            Some("fbx") => fbx::parse(&bytes, base_dir)?,
+            _ => {
+                return Err(LoadErr::Import(format!(
+                    "{} isn't an .obj or .fbx model",
+                    path.display()
+                )));
+            }
+        };
+
+        let name = path
+            .file_stem()
+            .and_then(|name| name.to_str())
+            .unwrap_or("model");
+        let model = self.build(name, data, library);
+        log::info!("Finished loading model from {:?}", path);
+        Ok(model)
+    }
+}
+
+impl ModelLoader {
+    fn build(&self, name: &str, mut data: ModelData, library: &AssetLibrary) -> Model {
+        let materials: Vec<AssetHandle<Material>> = data
+            .materials
+            .iter()
+            .map(|material| library.add(pbr_material(material, library)))
+            .collect();
+        let default_material = library.add(PbrMaterial::default());
+
+        let root_transform = Mat4::from_scale_rotation_translation(
+            Vec3::splat(data.scale),
+            data.rotation,
+            Vec3::ZERO,
+        );
+        let mut meshes = Vec::new();
+        // relative to the root, parents come first
+        let mut transforms: Vec<Mat4> = Vec::with_capacity(data.nodes.len());
+
+        let scene = InstancableScene::new();
+        let root = scene.spawn(
+            name,
+            Empty::builder()
+                .rotation(data.rotation)
+                .scale(Vec3::splat(data.scale))
+                .build(),
+        );
+
+        // names that appear more than once get their index so paths stay unique
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for node in &data.nodes {
+            *counts.entry(node.name.clone()).or_default() += 1;
+        }
+
+        let mut ids = Vec::with_capacity(data.nodes.len());
+        for (index, node) in data.nodes.iter_mut().enumerate() {
+            let node_name = match counts[&node.name] {
+                1 => node.name.clone(),
+                _ => format!("{}_{index}", node.name),
+            };
+            let parent = node.parent.map_or(root, |parent| ids[parent]);
+            let id = scene.spawn_as_child(
+                &node_name,
+                Empty::builder()
+                    .position(node.position)
+                    .rotation(node.rotation)
+                    .scale(node.scale)
+                    .build(),
+                parent,
+            );
+            ids.push(id);
+            let transform = node
+                .parent
+                .map_or(root_transform, |parent| transforms[parent])
+                * Mat4::from_scale_rotation_translation(node.scale, node.rotation, node.position);
+            transforms.push(transform);
+
+            for (primitive_index, primitive) in node.primitives.iter_mut().enumerate() {
+                Mesh3DLoader::calculate_tangents(&mut primitive.vertices, &primitive.indices);
+                let mesh = library.add(Mesh3D::new(
+                    &self.device,
+                    &primitive.vertices,
+                    &primitive.indices,
+                ));
+                let material = primitive
+                    .material
+                    .and_then(|material| materials.get(material))
+                    .unwrap_or(&default_material)
+                    .clone();
+
+                meshes.push(ModelMesh {
+                    transform,
+                    mesh: mesh.clone(),
+                    material: material.clone(),
+                });
+                scene.spawn_as_child(
+                    format!("primitive_{primitive_index}"),
+                    MeshInstance3D::builder()
+                        .mesh(mesh)
+                        .material(material)
+                        .build(),
+                    id,
+                );
+            }
+
+            // the meshes are on the gpu now
+            node.primitives.clear();
+        }
+
+        Model {
+            data,
+            materials,
+            meshes,
+            scene,
+        }
+    }
+}
+
+fn pbr_material(material: &ModelMaterial, library: &AssetLibrary) -> PbrMaterial {
+    let texture = |path: &Option<PathBuf>| {
+        path.as_ref()
+            .map(|path| library.load::<Texture>(path.clone()))
+    };
+
+    PbrMaterial {
+        base_color_factor: material.base_color,
+        base_color_texture: texture(&material.base_color_texture),
+        metallic_factor: material.metallic,
+        roughness_factor: material.roughness,
+        normal_texture: texture(&material.normal_texture),
+        emissive_factor: material.emissive,
+        emissive_texture: texture(&material.emissive_texture),
+        alpha_mode: match material.base_color.a < 1.0 {
+            true => AlphaMode::Blend,
+            false => AlphaMode::Opaque,
+        },
+        ..Default::default()
+    }
+}
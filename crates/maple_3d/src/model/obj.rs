@@ -0,0 +1,498 @@
+//! parser for wavefront `.obj` models and their `.mtl` material libraries
+//!
+//! every `o` and `g` in the file is a node of its own and its faces are split into a primitive
+//! per `usemtl`. polygons are triangulated as fans and vertices without a normal are given a
+//! smooth one from the faces around them. lines, points, curves and smoothing groups are skipped.
+//!
+//! materials read the diffuse color and texture, opacity, emission and the pbr extension's
+//! `Pr`, `Pm` and `norm`. without `Pr` the roughness comes from the specular exponent the way
+//! blender writes it, and `map_Bump` is used as a normal map since that is what blender and most
+//! other exporters put there.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use glam::{Vec2, Vec3};
+use maple_engine::{asset::LoadErr, color::Color};
+
+use super::{ModelData, ModelMaterial, ModelNode, ModelPrimitive};
+use crate::{assets::mesh::fill_normals, math::Vertex};
+
+/// read an obj model, `read` opens the material libraries it uses
+pub fn parse<E: std::fmt::Display>(
+    source: &str,
+    base_dir: &Path,
+    read: impl Fn(&Path) -> Result<String, E>,
+) -> Result<ModelData, LoadErr> {
+    let mut positions: Vec<Vec3> = Vec::new();
+    let mut uvs: Vec<Vec2> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+
+    let mut materials: Vec<ModelMaterial> = Vec::new();
+    let mut nodes: Vec<ObjectBuilder> = Vec::new();
+    let mut material: Option<usize> = None;
+
+    for (number, line) in source.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let Some((keyword, rest)) = split_keyword(line) else {
+            continue;
+        };
+        let error = |message: &str| invalid(number + 1, message);
+
+        match keyword {
+            "v" => positions.push(Vec3::from(floats::<3>(rest).ok_or_else(|| error("bad v"))?)),
+            "vt" => {
+                // v is optional and counts up from the bottom of the image
+                let mut values = rest.split_whitespace().map(str::parse::<f32>);
+                let u = values
+                    .next()
+                    .and_then(Result::ok)
+                    .ok_or_else(|| error("bad vt"))?;
+                let v = values.next().and_then(Result::ok).unwrap_or(0.0);
+                uvs.push(Vec2::new(u, 1.0 - v));
+            }
+            "vn" => normals.push(Vec3::from(
+                floats::<3>(rest).ok_or_else(|| error("bad vn"))?,
+            )),
+            "o" | "g" => {
+                let name = match rest.is_empty() {
+                    true => "default",
+                    false => rest,
+                };
+                match nodes.last_mut() {
+                    // `g` inside an `o` without faces yet is the same object, exporters write
+                    // both
+                    Some(node) if node.is_empty() && (keyword == "o" || !node.named_by_object) => {
+                        node.name = name.to_string();
+                        node.named_by_object |= keyword == "o";
+                    }
+                    _ => nodes.push(ObjectBuilder::new(name, keyword == "o")),
+                }
+            }
+            "usemtl" => {
+                material = materials.iter().position(|material| material.name == rest);
+                if material.is_none() {
+                    log::warn!("obj material {rest} isn't in any of its material libraries");
+                }
+            }
+            "mtllib" => {
+                // the file names can have spaces so only split on `.mtl`
+                for library in split_libraries(rest) {
+                    let path = base_dir.join(library);
+                    match read(&path) {
+                        Ok(source) => materials.extend(parse_mtl(&source, base_dir)),
+                        Err(e) => log::warn!("failed to read {}: {e}", path.display()),
+                    }
+                }
+            }
+            "f" => {
+                if nodes.is_empty() {
+                    nodes.push(ObjectBuilder::new("default", false));
+                }
+                let node = nodes.last_mut().unwrap();
+                let primitive = node.primitive(material);
+
+                let mut corners = Vec::new();
+                for corner in rest.split_whitespace() {
+                    let mut parts = corner.split('/');
+                    let position = parts.next().and_then(|index| {
+                        resolve(index, positions.len()).map(|index| positions[index])
+                    });
+                    let Some(position) = position else {
+                        return Err(error("face has a missing vertex"));
+                    };
+                    let uv = parts
+                        .next()
+                        .and_then(|index| resolve(index, uvs.len()))
+                        .map(|index| uvs[index]);
+                    let normal = parts
+                        .next()
+                        .and_then(|index| resolve(index, normals.len()))
+                        .map(|index| normals[index]);
+                    corners.push(primitive.vertex(position, uv, normal));
+                }
+                if corners.len() < 3 {
+                    return Err(error("face has less than 3 vertices"));
+                }
+
+                for i in 1..corners.len() - 1 {
+                    primitive
+                        .indices
+                        .extend([corners[0], corners[i], corners[i + 1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let nodes = nodes
+        .into_iter()
+        .filter(|node| !node.is_empty())
+        .map(ObjectBuilder::build)
+        .collect();
+
+    Ok(ModelData {
+        rotation: Default::default(),
+        scale: 1.0,
+        nodes,
+        materials,
+    })
+}
+
+fn invalid(line: usize, message: &str) -> LoadErr {
+    LoadErr::Import(format!("invalid obj on line {line}: {message}"))
+}
+
+fn split_keyword(line: &str) -> Option<(&str, &str)> {
+    if line.is_empty() {
+        return None;
+    }
+    Some(match line.split_once(char::is_whitespace) {
+        Some((keyword, rest)) => (keyword, rest.trim()),
+        None => (line, ""),
+    })
+}
+
+fn floats<const N: usize>(values: &str) -> Option<[f32; N]> {
+    let mut values = values.split_whitespace().map(str::parse::<f32>);
+    let mut out = [0.0; N];
+    for value in &mut out {
+        *value = values.next()?.ok()?;
+    }
+    Some(out)
+}
+
+/// a 1 based or negative from the end obj index into a list of `len`
+fn resolve(index: &str, len: usize) -> Option<usize> {
+    let index: isize = index.parse().ok()?;
+    let index = match index {
+        0 => return None,
+        index if index > 0 => index as usize - 1,
+        index => len.checked_sub(index.unsigned_abs())?,
+    };
+    (index < len).then_some(index)
+}
+
+fn split_libraries(names: &str) -> Vec<&str> {
+    let mut libraries = Vec::new();
+    let mut rest = names;
+    while let Some(end) = rest.to_ascii_lowercase().find(".mtl") {
+        libraries.push(rest[..end + 4].trim());
+        rest = &rest[end + 4..];
+    }
+    if !rest.trim().is_empty() {
+        libraries.push(rest.trim());
+    }
+    libraries
+}
+
+struct ObjectBuilder {
+    name: String,
+    named_by_object: bool,
+    primitives: Vec<PrimitiveBuilder>,
+}
+
+impl ObjectBuilder {
+    fn new(name: &str, named_by_object: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            named_by_object,
+            primitives: Vec::new(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.primitives
+            .iter()
+            .all(|primitive| primitive.indices.is_empty())
+    }
+
+    fn primitive(&mut self, material: Option<usize>) -> &mut PrimitiveBuilder {
+        let index = match self
+            .primitives
+            .iter()
+            .position(|primitive| primitive.material == material)
+        {
+            Some(index) => index,
+            None => {
+                self.primitives.push(PrimitiveBuilder::new(material));
+                self.primitives.len() - 1
+            }
+        };
+        &mut self.primitives[index]
+    }
+
+    fn build(self) -> ModelNode {
+        let mut node = ModelNode::new(self.name);
+        node.primitives = self
+            .primitives
+            .into_iter()
+            .filter(|primitive| !primitive.indices.is_empty())
+            .map(PrimitiveBuilder::build)
+            .collect();
+        node
+    }
+}
+
+struct PrimitiveBuilder {
+    material: Option<usize>,
+    vertices: Vec<Vertex>,
+    missing_normals: Vec<bool>,
+    indices: Vec<u32>,
+    /// corners that were already added by their position, uv and normal bits
+    lookup: HashMap<[u32; 8], u32>,
+}
+
+impl PrimitiveBuilder {
+    fn new(material: Option<usize>) -> Self {
+        Self {
+            material,
+            vertices: Vec::new(),
+            missing_normals: Vec::new(),
+            indices: Vec::new(),
+            lookup: HashMap::new(),
+        }
+    }
+
+    fn vertex(&mut self, position: Vec3, uv: Option<Vec2>, normal: Option<Vec3>) -> u32 {
+        let uv = uv.unwrap_or_default();
+        let key = [
+            position.x,
+            position.y,
+            position.z,
+            uv.x,
+            uv.y,
+            normal.map_or(f32::NAN, |n| n.x),
+            normal.map_or(f32::NAN, |n| n.y),
+            normal.map_or(f32::NAN, |n| n.z),
+        ]
+        .map(f32::to_bits);
+
+        *self.lookup.entry(key).or_insert_with(|| {
+            self.vertices.push(Vertex {
+                position: position.into(),
+                normal: normal.unwrap_or_default().normalize_or_zero().into(),
+                tex_uv: uv.into(),
+                ..Default::default()
+            });
+            self.missing_normals.push(normal.is_none());
+            self.vertices.len() as u32 - 1
+        })
+    }
+
+    fn build(mut self) -> ModelPrimitive {
+        fill_normals(&mut self.vertices, &self.indices, &self.missing_normals);
+        ModelPrimitive {
+            vertices: self.vertices,
+            indices: self.indices,
+            material: self.material,
+        }
+    }
+}
+
+/// the materials of an `.mtl` file, texture paths are made relative to `base_dir`
+pub fn parse_mtl(source: &str, base_dir: &Path) -> Vec<ModelMaterial> {
+    let mut materials: Vec<ModelMaterial> = Vec::new();
+    // roughness from the specular exponent unless the file has `Pr`
+    let mut exponents: Vec<Option<f32>> = Vec::new();
+    let mut has_roughness: Vec<bool> = Vec::new();
+
+    for line in source.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let Some((keyword, rest)) = split_keyword(line) else {
+            continue;
+        };
+        if keyword == "newmtl" {
+            materials.push(ModelMaterial {
+                name: rest.to_string(),
+                ..Default::default()
+            });
+            exponents.push(None);
+            has_roughness.push(false);
+            continue;
+        }
+        let Some(material) = materials.last_mut() else {
+            continue;
+        };
+        let float = || rest.split_whitespace().next()?.parse::<f32>().ok();
+        let color = || floats::<3>(rest).map(|[r, g, b]| Color::from_normalized(r, g, b, 1.0));
+
+        match keyword.to_ascii_lowercase().as_str() {
+            "kd" => {
+                if let Some(color) = color() {
+                    material.base_color = color.with_alpha(material.base_color.a);
+                }
+            }
+            "d" => {
+                if let Some(alpha) = float() {
+                    material.base_color.a = alpha.clamp(0.0, 1.0);
+                }
+            }
+            "tr" => {
+                if let Some(transparency) = float() {
+                    material.base_color.a = (1.0 - transparency).clamp(0.0, 1.0);
+                }
+            }
+            "ke" => {
+                if let Some(color) = color() {
+                    material.emissive = color;
+                }
+            }
+            "ns" => *exponents.last_mut().unwrap() = float(),
+            "pr" => {
+                if let Some(roughness) = float() {
+                    material.roughness = roughness.clamp(0.0, 1.0);
+                    *has_roughness.last_mut().unwrap() = true;
+                }
+            }
+            "pm" => {
+                if let Some(metallic) = float() {
+                    material.metallic = metallic.clamp(0.0, 1.0);
+                }
+            }
+            "map_kd" => material.base_color_texture = texture_path(rest, base_dir),
+            "map_ke" => material.emissive_texture = texture_path(rest, base_dir),
+            "norm" | "map_bump" | "bump" => material.normal_texture = texture_path(rest, base_dir),
+            _ => {}
+        }
+    }
+
+    for ((material, exponent), has_roughness) in
+        materials.iter_mut().zip(exponents).zip(has_roughness)
+    {
+        if let (Some(exponent), false) = (exponent, has_roughness) {
+            // blender writes the exponent as (1 - roughness)² * 1000
+            material.roughness = 1.0 - (exponent.clamp(0.0, 1000.0) / 1000.0).sqrt();
+        }
+    }
+
+    materials
+}
+
+/// the file of a texture statement after its options
+fn texture_path(statement: &str, base_dir: &Path) -> Option<PathBuf> {
+    let mut tokens = statement.split_whitespace().peekable();
+    while let Some(token) = tokens.peek().copied() {
+        if !token.starts_with('-') {
+            break;
+        }
+        tokens.next();
+        match token {
+            // options with a single word after them
+            "-blendu" | "-blendv" | "-clamp" | "-cc" | "-imfchan" | "-type" => {
+                tokens.next();
+            }
+            // the rest take up to three numbers
+            _ => {
+                for _ in 0..3 {
+                    match tokens.peek().map(|value| value.parse::<f32>()) {
+                        Some(Ok(_)) => {
+                            tokens.next();
+                        }
+                        _ => break,
+                    }
+                }
+            }
+        }
+    }
+
+    let file = tokens.collect::<Vec<_>>().join(" ");
+    // windows exporters write backslashes
+    let file = file.replace('\\', "/");
+    (!file.is_empty()).then(|| base_dir.join(file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OBJ: &str = "\
+# a quad and a triangle
+mtllib crate.mtl
+o crate
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+usemtl wood
+f 1/1/1 2/2/1 3/3/1 4/4/1
+o roof
+usemtl metal
+f -4 -3 -2
+";
+
+    const MTL: &str = "\
+newmtl wood
+Kd 0.8 0.5 0.2
+Ns 250
+map_Kd -s 2 2 1 textures/wood.png
+map_Bump -bm 1.0 textures/wood_normal.png
+newmtl metal
+Kd 0.5 0.5 0.5
+d 0.5
+Pr 0.2
+Pm 1
+";
+
+    #[test]
+    fn test_parse_obj_with_materials() {
+        let model = parse(OBJ, Path::new("res/models"), |path| {
+            assert_eq!(path, Path::new("res/models/crate.mtl"));
+            Ok::<_, String>(MTL.to_string())
+        })
+        .unwrap();
+
+        assert_eq!(model.nodes.len(), 2);
+        let quad = &model.nodes[0];
+        assert_eq!(quad.name, "crate");
+        assert_eq!(quad.primitives.len(), 1);
+        let primitive = &quad.primitives[0];
+        assert_eq!(primitive.material, Some(0));
+        assert_eq!(primitive.vertices.len(), 4);
+        assert_eq!(primitive.indices, vec![0, 1, 2, 0, 2, 3]);
+        // v counts up from the bottom of the image
+        assert_eq!(primitive.vertices[3].tex_uv, [0.0, 0.0]);
+
+        // faces without normals are given one
+        let roof = &model.nodes[1].primitives[0];
+        assert_eq!(roof.material, Some(1));
+        assert_eq!(roof.vertices[0].normal, [0.0, 0.0, 1.0]);
+
+        let wood = &model.materials[0];
+        assert_eq!(wood.base_color, Color::from_normalized(0.8, 0.5, 0.2, 1.0));
+        assert!((wood.roughness - 0.5).abs() < 1e-3);
+        assert_eq!(
+            wood.base_color_texture.as_deref(),
+            Some(Path::new("res/models/textures/wood.png"))
+        );
+        assert_eq!(
+            wood.normal_texture.as_deref(),
+            Some(Path::new("res/models/textures/wood_normal.png"))
+        );
+
+        let metal = &model.materials[1];
+        assert_eq!(metal.base_color.a, 0.5);
+        assert_eq!((metal.metallic, metal.roughness), (1.0, 0.2));
+    }
+
+    #[test]
+    fn test_invalid_faces() {
+        let no_mtl = |_: &Path| Err::<String, _>("no files");
+        assert!(parse("v 0 0 0\nf 1 2 3", Path::new(""), no_mtl).is_err());
+        assert!(parse("v 0 0 0\nv 1 0 0\nf 1 2", Path::new(""), no_mtl).is_err());
+        // the material library failing to open isn't an error
+        let model = parse(
+            "mtllib a.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3",
+            Path::new(""),
+            no_mtl,
+        );
+        assert_eq!(model.unwrap().nodes[0].name, "default");
+    }
+}
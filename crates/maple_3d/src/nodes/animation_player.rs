@@ -0,0 +1,433 @@
+//! plays [`AnimationClip`]s on the nodes below it
+//!
+//! see the [animation module](crate::animation) for how clips target nodes
+
+use std::{collections::HashMap, sync::Arc};
+
+use maple_engine::{
+    Buildable, Builder, GameContext, Node, Scene,
+    nodes::node_builder::NodePrototype,
+    prelude::{EventLabel, NodeTransform},
+    scene::NodeId,
+};
+
+use crate::{
+    animation::{AnimationClip, ChannelValue},
+    nodes::mesh_instance::MeshInstance3D,
+};
+
+/// emitted to an [`AnimationPlayer3D`] that doesn't loop when its clip reaches the end
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AnimationFinished {
+    /// the name of the clip that finished
+    pub clip: String,
+}
+impl EventLabel for AnimationFinished {}
+
+/// samples the clip it is playing every frame and writes it into the transforms of the nodes the
+/// clip's channels target
+pub struct AnimationPlayer3D {
+    pub transform: NodeTransform,
+    /// multiplier for how fast clips play, negative plays them backwards
+    pub speed: f32,
+    /// start the clip over when it reaches the end
+    pub looping: bool,
+    clips: Arc<[AnimationClip]>,
+    current: Option<usize>,
+    time: f32,
+    playing: bool,
+    /// the clip and time last written to the targets
+    applied: Option<(usize, f32)>,
+    /// the nodes channel targets were found at
+    targets: HashMap<String, NodeId>,
+}
+
+// the found targets belong to the scene this player is in, not to its copies
+impl Clone for AnimationPlayer3D {
+    fn clone(&self) -> Self {
+        Self {
+            transform: self.transform,
+            speed: self.speed,
+            looping: self.looping,
+            clips: self.clips.clone(),
+            current: self.current,
+            time: self.time,
+            playing: self.playing,
+            applied: None,
+            targets: HashMap::new(),
+        }
+    }
+}
+
+impl Node for AnimationPlayer3D {
+    fn get_transform(&mut self) -> &mut NodeTransform {
+        &mut self.transform
+    }
+}
+
+impl AnimationPlayer3D {
+    pub fn clips(&self) -> &[AnimationClip] {
+        &self.clips
+    }
+
+    pub fn clip(&self, name: &str) -> Option<&AnimationClip> {
+        self.clips.iter().find(|clip| clip.name == name)
+    }
+
+    /// the clip being played or paused
+    pub fn current_clip(&self) -> Option<&AnimationClip> {
+        self.clips.get(self.current?)
+    }
+
+    /// play the clip called `name` from the start, false if there is no such clip
+    ///
+    /// playing the clip that is already playing does nothing
+    pub fn play(&mut self, name: &str) -> bool {
+        match self.clips.iter().position(|clip| clip.name == name) {
+            Some(index) => self.play_index(index),
+            None => false,
+        }
+    }
+
+    /// like [`AnimationPlayer3D::play`] but picks the clip by its index in
+    /// [`AnimationPlayer3D::clips`]
+    pub fn play_index(&mut self, index: usize) -> bool {
+        if index >= self.clips.len() {
+            return false;
+        }
+        if self.current != Some(index) || !self.playing {
+            self.current = Some(index);
+            self.time = self.start_time();
+        }
+        self.playing = true;
+        true
+    }
+
+    /// stop advancing keeping the current pose
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// continue playing the current clip from where it was paused
+    pub fn resume(&mut self) {
+        self.playing = self.current.is_some();
+    }
+
+    /// stop advancing and go back to the start of the clip
+    pub fn stop(&mut self) {
+        self.playing = false;
+        self.time = self.start_time();
+    }
+
+    /// true if the current clip is advancing
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// seconds into the current clip
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// jump to `time` seconds into the current clip, clamped to the clip
+    pub fn seek(&mut self, time: f32) {
+        let duration = self.current_clip().map_or(0.0, AnimationClip::duration);
+        self.time = time.clamp(0.0, duration);
+    }
+
+    /// where a clip starts, backwards clips start at their end
+    fn start_time(&self) -> f32 {
+        match self.speed < 0.0 {
+            true => self.current_clip().map_or(0.0, AnimationClip::duration),
+            false => 0.0,
+        }
+    }
+
+    /// advance the current clip by `dt` seconds, returns true if it reached its end
+    ///
+    /// this is done by the engine every frame so it is only needed to drive a player by hand
+    pub fn advance(&mut self, dt: f32) -> bool {
+        let Some(duration) = self.current_clip().map(AnimationClip::duration) else {
+            return false;
+        };
+        if !self.playing {
+            return false;
+        }
+
+        self.time += dt * self.speed;
+        if self.looping {
+            self.time = match duration > 0.0 {
+                true => self.time.rem_euclid(duration),
+                false => 0.0,
+            };
+            return false;
+        }
+
+        if (0.0..duration).contains(&self.time) {
+            return false;
+        }
+        self.time = self.time.clamp(0.0, duration);
+        self.playing = false;
+        true
+    }
+}
+
+/// advances every enabled [`AnimationPlayer3D`] and writes its pose into its targets
+pub(crate) fn update_animations(ctx: &GameContext, dt: f32) {
+    for player in ctx.scene.collect::<AnimationPlayer3D>() {
+        let id = player.id();
+        if !ctx.scene.is_enabled(id) {
+            continue;
+        }
+
+        // the player isn't locked while its targets are written since a channel can target it
+        let (clips, index, time, finished, mut targets) = {
+            let mut player = player.write();
+            let finished = player.advance(dt);
+            // morph weights change the mesh without moving a node
+            if player.is_playing() {
+                ctx.request_redraw();
+            }
+            let Some(index) = player.current else {
+                continue;
+            };
+            if !finished && player.applied == Some((index, player.time)) {
+                continue;
+            }
+            let targets = std::mem::take(&mut player.targets);
+            (player.clips.clone(), index, player.time, finished, targets)
+        };
+
+        let clip = &clips[index];
+        for channel in &clip.channels {
+            let target = match targets.get(&channel.target) {
+                Some(target) => *target,
+                None => match ctx.scene.get_child_id_by_path(id, &channel.target) {
+                    Some(target) => *targets.entry(channel.target.clone()).or_insert(target),
+                    None => continue,
+                },
+            };
+            let Some(value) = channel.sample(time) else {
+                continue;
+            };
+
+            let written = ctx.scene.with_transform(target, |transform| match &value {
+                ChannelValue::Translation(position) => transform.position = *position,
+                ChannelValue::Rotation(rotation) => transform.rotation = *rotation,
+                ChannelValue::Scale(scale) => transform.scale = *scale,
+                ChannelValue::Weights(_) => {}
+            });
+            if let ChannelValue::Weights(weights) = value {
+                set_morph_weights(&ctx.scene, target, weights);
+            }
+            // look the target up again next time if it was despawned
+            if written.is_none() {
+                targets.remove(&channel.target);
+            }
+        }
+
+        {
+            let mut player = player.write();
+            player.targets = targets;
+            player.applied = Some((index, time));
+        }
+
+        if finished {
+            let event = AnimationFinished {
+                clip: clip.name.clone(),
+            };
+            ctx.scene.emit_to(id, &event, ctx);
+        }
+    }
+}
+
+/// weights channels from gltf target the node holding a mesh, which has a mesh instance child
+/// for each primitive
+fn set_morph_weights(scene: &Scene, target: NodeId, weights: Vec<f32>) {
+    match scene.get::<MeshInstance3D>(target) {
+        Some(mesh) => mesh.write().morph_weights = weights,
+        None => {
+            for mesh in scene.children::<MeshInstance3D>(target) {
+                mesh.write().morph_weights = weights.clone();
+            }
+        }
+    }
+}
+
+pub struct AnimationPlayer3DBuilder {
+    prototype: NodePrototype,
+    clips: Vec<AnimationClip>,
+    autoplay: Option<String>,
+    looping: bool,
+    speed: f32,
+}
+
+impl Default for AnimationPlayer3DBuilder {
+    fn default() -> Self {
+        Self {
+            prototype: NodePrototype::default(),
+            clips: Vec::new(),
+            autoplay: None,
+            looping: true,
+            speed: 1.0,
+        }
+    }
+}
+
+impl Buildable for AnimationPlayer3D {
+    type Builder = AnimationPlayer3DBuilder;
+    fn builder() -> Self::Builder {
+        AnimationPlayer3DBuilder::default()
+    }
+}
+
+impl Builder for AnimationPlayer3DBuilder {
+    type Node = AnimationPlayer3D;
+    fn prototype(&mut self) -> &mut NodePrototype {
+        &mut self.prototype
+    }
+
+    fn build(self) -> Self::Node {
+        let mut player = AnimationPlayer3D {
+            transform: self.prototype.transform,
+            speed: self.speed,
+            looping: self.looping,
+            clips: self.clips.into(),
+            current: None,
+            time: 0.0,
+            playing: false,
+            applied: None,
+            targets: HashMap::new(),
+        };
+        if let Some(name) = self.autoplay
+            && !player.play(&name)
+        {
+            log::warn!("animation player has no clip called {name}");
+        }
+        player
+    }
+}
+
+impl AnimationPlayer3DBuilder {
+    /// add a clip the player can play
+    pub fn clip(mut self, clip: AnimationClip) -> Self {
+        self.clips.push(clip);
+        self
+    }
+
+    /// add multiple clips the player can play
+    pub fn clips(mut self, clips: impl IntoIterator<Item = AnimationClip>) -> Self {
+        self.clips.extend(clips);
+        self
+    }
+
+    /// start playing the clip called `name` right away
+    pub fn autoplay(mut self, name: impl Into<String>) -> Self {
+        self.autoplay = Some(name.into());
+        self
+    }
+
+    /// start clips over when they reach the end. Default: `true`
+    pub fn looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// multiplier for how fast clips play. Default: `1.0`
+    pub fn speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+    use maple_engine::nodes::Empty;
+
+    use super::*;
+    use crate::animation::Channel;
+
+    fn slide() -> AnimationClip {
+        AnimationClip::new("slide").with_channel(Channel::translation(
+            "arm/hand",
+            vec![0.0, 1.0],
+            vec![Vec3::ZERO, Vec3::X * 2.0],
+        ))
+    }
+
+    #[test]
+    fn test_player_moves_target_by_path() {
+        let ctx = GameContext::new();
+        let player = ctx.scene.spawn(
+            AnimationPlayer3D::builder()
+                .clip(slide())
+                .autoplay("slide")
+                .looping(false),
+        );
+        let hand = player
+            .spawn_child_with_name("arm", Empty::default())
+            .spawn_child_with_name("hand", Empty::default())
+            .id();
+        let player = player.id();
+
+        let position = || {
+            ctx.scene
+                .with_transform(hand, |transform| transform.position)
+                .unwrap()
+        };
+
+        update_animations(&ctx, 0.5);
+        assert_eq!(position(), Vec3::X);
+
+        update_animations(&ctx, 1.0);
+        assert_eq!(position(), Vec3::X * 2.0);
+        let player = ctx.scene.get::<AnimationPlayer3D>(player).unwrap();
+        assert!(!player.read().is_playing());
+
+        // a paused player leaves its targets alone
+        ctx.scene
+            .with_transform(hand, |transform| transform.position = Vec3::ZERO);
+        update_animations(&ctx, 0.5);
+        assert_eq!(position(), Vec3::ZERO);
+
+        player.write().seek(0.25);
+        update_animations(&ctx, 0.5);
+        assert_eq!(position(), Vec3::X * 0.5);
+    }
+
+    #[test]
+    fn test_weights_reach_mesh_children() {
+        let ctx = GameContext::new();
+        let player = ctx.scene.spawn(
+            AnimationPlayer3D::builder()
+                .clip(AnimationClip::new("smile").with_channel(Channel::weights(
+                    "face",
+                    vec![0.0, 1.0],
+                    vec![vec![0.0, 0.0], vec![1.0, 0.5]],
+                )))
+                .autoplay("smile"),
+        );
+        let primitive = player
+            .spawn_child_with_name("face", Empty::default())
+            .spawn_child(MeshInstance3D::default())
+            .id();
+
+        update_animations(&ctx, 0.5);
+        let mesh = ctx.scene.get::<MeshInstance3D>(primitive).unwrap();
+        assert_eq!(mesh.read().morph_weights, vec![0.5, 0.25]);
+    }
+
+    #[test]
+    fn test_looping_wraps_time() {
+        let mut player = AnimationPlayer3D::builder()
+            .clip(slide())
+            .autoplay("slide")
+            .build();
+
+        assert!(!player.advance(1.5));
+        assert!((player.time() - 0.5).abs() < 1e-6);
+        assert!(player.is_playing());
+        assert!(!player.play("missing"));
+    }
+}
@@ -0,0 +1,306 @@
+//! Area lights emit light from a flat rectangle or disk, like a window or a screen
+//!
+//! the light leaves the front of the shape, the node's -Z side, and gets softer the bigger the
+//! shape is compared to how far away it is. the pbr shader lights each surface from the point of
+//! the shape closest to it for diffuse and from the point closest to its reflection for specular
+//! (representative point lighting) so highlights take the shape of the light.
+//!
+//! area lights don't cast shadows or draw anything themselves, pair one with a mesh using an
+//! emissive material to see the light source.
+//!
+//! ```rust,ignore
+//! ctx.scene.spawn(
+//!     AreaLight::builder()
+//!         .position((0.0, 2.0, -4.0))
+//!         .rect(3.0, 2.0)
+//!         .color(Color::from_hex(0xcfe6ff))
+//!         .intensity(4.0)
+//!         .build(),
+//! );
+//! ```
+
+const MAX_LIGHTS: usize = 100;
+
+use bytemuck::{Pod, Zeroable};
+use glam::{Quat, Vec3};
+use maple_engine::{
+    Buildable, Builder, Node, color::Color, nodes::node_builder::NodePrototype,
+    prelude::NodeTransform,
+};
+
+/// used to pass data to the shader buffer
+///
+/// the data on the gpu follows this format in this order:
+/// ```c
+/// struct AreaLight {
+///     vec4 color;
+///     vec4 pos;
+///     vec4 right; // half the width along the light's x axis
+///     vec4 up; // half the height along the light's y axis
+///     float intensity;
+///     uint shape;
+///     uint two_sided;
+///     float _padding;
+/// };
+/// ```
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, Pod, Zeroable)]
+pub struct AreaLightBufferData {
+    color: [f32; 4],
+    position: [f32; 4],
+    right: [f32; 4],
+    up: [f32; 4],
+    intensity: f32,
+    shape: u32,
+    two_sided: u32,
+    _padding: f32, //ssbo is 16 byte aligned
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct AreaLightBuffer {
+    pub length: i32,
+    _padding: [i32; 3],
+    pub data: [AreaLightBufferData; MAX_LIGHTS],
+}
+
+impl AreaLightBuffer {
+    pub fn from_lights(lights: &[AreaLightBufferData]) -> Self {
+        let mut buffer = AreaLightBuffer {
+            length: lights.len().min(MAX_LIGHTS) as i32,
+            _padding: [0; 3],
+            data: [AreaLightBufferData::default(); MAX_LIGHTS],
+        };
+
+        let copy_count = lights.len().min(MAX_LIGHTS);
+        buffer.data[..copy_count].copy_from_slice(&lights[..copy_count]);
+
+        buffer
+    }
+}
+
+/// the shape light is emitted from, in the light's local x and y axes before scaling
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AreaLightShape {
+    Rect { width: f32, height: f32 },
+    Disk { radius: f32 },
+}
+
+impl AreaLightShape {
+    /// half the size of the shape along the x and y axes
+    pub fn half_extents(&self) -> (f32, f32) {
+        match *self {
+            Self::Rect { width, height } => (width * 0.5, height * 0.5),
+            Self::Disk { radius } => (radius, radius),
+        }
+    }
+
+    /// the area light is emitted from
+    pub fn area(&self) -> f32 {
+        match *self {
+            Self::Rect { width, height } => width * height,
+            Self::Disk { radius } => std::f32::consts::PI * radius * radius,
+        }
+    }
+}
+
+/// area light nodes represent area lights in the Scene, see the [module docs](self)
+#[derive(Debug, Clone)]
+pub struct AreaLight {
+    /// transform component for area light, the light shines out of its -Z side and is scaled by
+    /// its x and y scale
+    pub transform: NodeTransform,
+
+    /// how bright the surface of the light is, the light received grows with its area
+    pub intensity: f32,
+
+    /// the light color default is White
+    pub color: Color,
+
+    pub shape: AreaLightShape,
+
+    /// emit light from the back of the shape too
+    pub two_sided: bool,
+}
+
+impl Node for AreaLight {
+    fn get_transform(&mut self) -> &mut NodeTransform {
+        &mut self.transform
+    }
+}
+
+impl Default for AreaLight {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl AreaLight {
+    /// create a 1 by 1 rect light
+    pub fn new() -> AreaLight {
+        Self::default()
+    }
+
+    /// the direction light leaves the front of the shape
+    pub fn direction(&self) -> Vec3 {
+        self.transform.render_space().rotation() * Vec3::NEG_Z
+    }
+
+    /// point the front of the light in a direction
+    pub fn set_direction(&mut self, direction: impl Into<Vec3>) -> &mut Self {
+        let direction = direction.into().normalize();
+        self.transform
+            .set_rotation(Quat::from_rotation_arc(Vec3::NEG_Z, direction));
+        self
+    }
+
+    /// set the light color
+    pub fn set_color(&mut self, color: impl Into<Color>) -> &mut Self {
+        self.color = color.into();
+        self
+    }
+
+    /// returns the formatted buffer data
+    pub fn get_buffered_data(&self) -> AreaLightBufferData {
+        let transform = self.transform.render_space();
+        let scale = transform.scale();
+        let (half_width, half_height) = self.shape.half_extents();
+
+        let right = transform.rotation() * Vec3::X * (half_width * scale.x.abs());
+        let up = transform.rotation() * Vec3::Y * (half_height * scale.y.abs());
+
+        AreaLightBufferData {
+            color: self.color.into(),
+            position: transform.position().extend(0.0).to_array(),
+            right: right.extend(0.0).to_array(),
+            up: up.extend(0.0).to_array(),
+            intensity: self.intensity,
+            shape: match self.shape {
+                AreaLightShape::Rect { .. } => 0,
+                AreaLightShape::Disk { .. } => 1,
+            },
+            two_sided: self.two_sided as u32,
+            _padding: 0.0,
+        }
+    }
+}
+
+impl Buildable for AreaLight {
+    type Builder = AreaLightBuilder;
+    fn builder() -> Self::Builder {
+        Self::Builder {
+            prototype: NodePrototype::default(),
+            intensity: 1.0,
+            color: Color::WHITE,
+            shape: AreaLightShape::Rect {
+                width: 1.0,
+                height: 1.0,
+            },
+            two_sided: false,
+        }
+    }
+}
+
+/// area light specific builder
+pub struct AreaLightBuilder {
+    prototype: NodePrototype,
+    intensity: f32,
+    color: Color,
+    shape: AreaLightShape,
+    two_sided: bool,
+}
+
+impl Builder for AreaLightBuilder {
+    type Node = AreaLight;
+    fn prototype(&mut self) -> &mut NodePrototype {
+        &mut self.prototype
+    }
+
+    fn build(self) -> Self::Node {
+        Self::Node {
+            transform: self.prototype.transform,
+            intensity: self.intensity,
+            color: self.color,
+            shape: self.shape,
+            two_sided: self.two_sided,
+        }
+    }
+}
+
+impl AreaLightBuilder {
+    /// set the intensity of the light
+    pub fn intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// set the color of the light
+    pub fn color(mut self, color: impl Into<Color>) -> Self {
+        self.color = color.into();
+        self
+    }
+
+    /// the direction light leaves the front of the shape
+    pub fn direction(mut self, direction: impl Into<Vec3>) -> Self {
+        let direction = direction.into().normalize();
+        self.prototype()
+            .transform
+            .set_rotation(Quat::from_rotation_arc(Vec3::NEG_Z, direction));
+        self
+    }
+
+    /// emit light from a rectangle along the light's x and y axes
+    ///
+    /// default value: 1 by 1
+    pub fn rect(mut self, width: f32, height: f32) -> Self {
+        self.shape = AreaLightShape::Rect {
+            width: width.max(0.0),
+            height: height.max(0.0),
+        };
+        self
+    }
+
+    /// emit light from a disk facing the light's -Z axis
+    pub fn disk(mut self, radius: f32) -> Self {
+        self.shape = AreaLightShape::Disk {
+            radius: radius.max(0.0),
+        };
+        self
+    }
+
+    /// emit light from the back of the shape too
+    ///
+    /// default value: false
+    pub fn two_sided(mut self, two_sided: bool) -> Self {
+        self.two_sided = two_sided;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use maple_engine::components::node_transform::WorldTransform;
+
+    use super::*;
+
+    #[test]
+    fn test_buffer_axes_follow_the_shape_and_scale() {
+        let mut light = AreaLight::builder()
+            .rect(4.0, 2.0)
+            .scale((2.0, 1.0, 1.0))
+            .build();
+        light.transform.get_world_space(WorldTransform::default());
+
+        let data = light.get_buffered_data();
+        assert_eq!(data.right, [4.0, 0.0, 0.0, 0.0]);
+        assert_eq!(data.up, [0.0, 1.0, 0.0, 0.0]);
+        assert_eq!(data.shape, 0);
+
+        let disk = AreaLightShape::Disk { radius: 2.0 };
+        assert_eq!(disk.half_extents(), (2.0, 2.0));
+        assert!((disk.area() - 4.0 * std::f32::consts::PI).abs() < 1e-5);
+
+        // the gpu layout has to match the wgsl struct
+        assert_eq!(size_of::<AreaLightBufferData>(), 80);
+    }
+}
@@ -8,7 +8,7 @@ extern crate glam as math;
 
 use bytemuck::{Pod, Zeroable};
 use glam::{
-    Mat4, Vec3,
+    Mat4, Vec2, Vec3,
     camera::rh::{proj::directx::perspective, view::look_at_mat4},
 };
 use maple_engine::{
@@ -16,8 +16,11 @@ use maple_engine::{
     nodes::node_builder::NodePrototype,
     prelude::{EventCtx, NodeTransform, Update},
     resources::{Input, KeyCode},
+    utils::ray::Ray,
 };
 
+use crate::nodes::rts_camera::screen_ray;
+
 #[derive(Default, Debug, Clone, Copy, Pod, Zeroable)]
 #[repr(C)]
 pub struct Camera3DBufferData {
@@ -27,9 +30,12 @@ pub struct Camera3DBufferData {
     vp: [[f32; 4]; 4],
     far_plane: f32,
     padding: [f32; 3],
+    /// turns clip space back into world space for passes that rebuild positions from depth
+    inverse_vp: [[f32; 4]; 4],
 }
 
 /// A 3D camera that can be use in a 3d environment.
+#[derive(Clone)]
 pub struct Camera3D {
     /// the NodeTransform of the camera (every node has this)
     pub transform: NodeTransform,
@@ -231,10 +237,10 @@ impl Camera3D {
     /// The view matrix of the camera
     pub fn get_view_matrix(&self) -> math::Mat4 {
         //let world_position = parent_transform + self.transform;
-        let world_position = self.transform.world_space();
-        let target = world_position.position + self.transform.get_forward_vector();
+        let world_position = self.transform.render_space();
+        let target = world_position.position() + self.transform.get_forward_vector();
         look_at_mat4(
-            world_position.position,
+            world_position.position(),
             target,
             math::vec3(0.0, 1.0, 0.0), //up vector
         )
@@ -268,8 +274,27 @@ impl Camera3D {
         self.get_projection_matrix(aspect_ratio) * self.get_view_matrix()
     }
 
+    /// the world space ray from the camera through a pixel on the screen
+    ///
+    /// pass [`Input::cursor_position`] and [`Input::screen_size_pixels`] to pick what is under
+    /// the mouse with [`maple_engine::Scene::raycast`]
+    pub fn screen_to_ray(&self, screen_position: Vec2, screen_size: Vec2) -> Ray {
+        let aspect_ratio = screen_size.x / screen_size.y.max(1.0);
+        let (origin, direction) = screen_ray(
+            self.get_vp_matrix(aspect_ratio),
+            screen_position,
+            screen_size,
+        );
+        Ray::new(origin, direction)
+    }
+
     pub fn get_buffer_data(&self, aspect_ratio: f32) -> Camera3DBufferData {
-        let position = self.transform.world_space().position.extend(1.0).to_array();
+        let position = self
+            .transform
+            .render_space()
+            .position()
+            .extend(1.0)
+            .to_array();
 
         let view = self.get_view_matrix();
         let projection = self.get_projection_matrix(aspect_ratio);
@@ -282,6 +307,7 @@ impl Camera3D {
             vp: vp.to_cols_array_2d(),
             far_plane: self.far,
             padding: [0.0; 3],
+            inverse_vp: vp.inverse().to_cols_array_2d(),
         }
     }
 
@@ -500,6 +526,22 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_screen_to_ray_through_center() {
+        let mut camera = create_test_camera();
+        camera.set_orientation_vector(Vec3::new(0.0, 0.0, -1.0));
+
+        let screen_size = glam::Vec2::new(800.0, 600.0);
+        let ray = camera.screen_to_ray(screen_size / 2.0, screen_size);
+
+        assert!(ray.direction.distance(Vec3::NEG_Z) < 1e-4);
+        // the ray starts on the near plane in front of the camera
+        assert!(ray.origin.distance(Vec3::new(0.0, 0.0, -0.1)) < 1e-3);
+
+        let corner = camera.screen_to_ray(glam::Vec2::ZERO, screen_size);
+        assert!(corner.direction.x < 0.0 && corner.direction.y > 0.0);
+    }
+
     #[test]
     fn test_camera_view_matrix_calculation() {
         let mut camera = create_test_camera();
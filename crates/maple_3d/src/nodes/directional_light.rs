@@ -17,7 +17,13 @@ use maple_engine::{
     prelude::NodeTransform,
 };
 
-use crate::nodes::camera::Camera3D;
+use crate::{
+    nodes::camera::Camera3D,
+    render_passes::{
+        shadow_filter::ShadowFilter,
+        shadow_resource::{DIRECTIONAL_SHADOW_SIZE, MAX_SHADOW_SIZE},
+    },
+};
 
 /// used to pass data to the shader buffer
 ///
@@ -48,7 +54,9 @@ pub struct DirectionalLightBufferData {
     cascade_texel_sizes: [f32; 4],
     light_size: f32,
     normal_bias: f32,
-    _padding: [f32; 2],
+    shadow_filter: u32,
+    /// the part of the shadow map width the light was rendered into
+    pub shadow_scale: f32,
 }
 
 #[repr(C)]
@@ -88,6 +96,8 @@ pub struct DirectionalLight {
     /// The intensity of the directional light.
     pub intensity: f32,
 
+    /// how wide the light looks in the sky in degrees, only softens [`ShadowFilter::Pcss`]
+    /// shadows. the sun is about 0.5
     pub size: f32,
 
     far_plane: f32,
@@ -101,6 +111,15 @@ pub struct DirectionalLight {
     pub bias: f32,
 
     pub normal_bias: f32,
+
+    /// how the edges of the shadow are softened
+    pub shadow_filter: ShadowFilter,
+
+    /// render shadow maps for this light
+    pub cast_shadows: bool,
+
+    /// width and height of each cascade's shadow map in texels
+    pub shadow_resolution: u32,
 }
 
 impl Node for DirectionalLight {
@@ -121,6 +140,9 @@ impl Default for DirectionalLight {
             size: 0.0,
             bias: 0.015,
             normal_bias: 0.015,
+            shadow_filter: ShadowFilter::default(),
+            cast_shadows: true,
+            shadow_resolution: DIRECTIONAL_SHADOW_SIZE,
             cascade_factors,
             transform: NodeTransform::default(),
         }
@@ -181,6 +203,9 @@ impl DirectionalLight {
             cascade_factors,
             bias: 0.015,
             normal_bias: 0.015,
+            shadow_filter: ShadowFilter::default(),
+            cast_shadows: true,
+            shadow_resolution: DIRECTIONAL_SHADOW_SIZE,
         }
     }
 
@@ -195,6 +220,9 @@ impl DirectionalLight {
             cascade_factors: self.cascade_factors.clone(),
             bias: self.bias,
             normal_bias: self.normal_bias,
+            shadow_filter: self.shadow_filter,
+            cast_shadows: self.cast_shadows,
+            shadow_resolution: self.shadow_resolution,
         }
     }
 
@@ -234,7 +262,7 @@ impl DirectionalLight {
     /// world-space texel size (needed for shadow filtering / bias scaling on the GPU)
     pub fn view_projection(&self, camera: &Camera3D, aspect_ratio: f32) -> Vec<(Mat4, f32)> {
         // Shadow map resolution for texel snapping
-        const SHADOW_MAP_SIZE: f32 = 4096.0;
+        let shadow_map_size = self.shadow_resolution.clamp(1, MAX_SHADOW_SIZE) as f32;
 
         let mut matrices = Vec::with_capacity(self.num_cascades);
 
@@ -259,8 +287,8 @@ impl DirectionalLight {
                 &camera.get_view_matrix(),
             );
 
-            let view = self.get_view(&corners, SHADOW_MAP_SIZE);
-            let (proj, texel_size) = Self::get_proj(&corners, &view, SHADOW_MAP_SIZE);
+            let view = self.get_view(&corners, shadow_map_size);
+            let (proj, texel_size) = Self::get_proj(&corners, &view, shadow_map_size);
 
             matrices.push((proj * view, texel_size));
 
@@ -414,7 +442,7 @@ impl DirectionalLight {
     }
 
     pub fn direction(&self) -> Vec3 {
-        self.transform.world_space().rotation * Vec3::NEG_Z
+        self.transform.render_space().rotation() * Vec3::NEG_Z
     }
     /// sets the color of the light
     pub fn set_color(&mut self, color: impl Into<Color>) -> &mut Self {
@@ -428,11 +456,12 @@ impl DirectionalLight {
         self
     }
 
+    /// returns the formatted buffer data, `shadow_index` is `None` for lights without a shadow
     pub fn to_buffer_data(
         &self,
         camera: &Camera3D,
         aspect_ratio: f32,
-        shadow_index: usize,
+        shadow_index: Option<usize>,
     ) -> DirectionalLightBufferData {
         let vp_matrices = self.view_projection(camera, aspect_ratio);
 
@@ -463,7 +492,7 @@ impl DirectionalLight {
             color: self.color.into(),
             direction: self.direction().extend(0.0).to_array(),
             intensity: self.intensity,
-            shadow_index: shadow_index as i32,
+            shadow_index: shadow_index.map_or(-1, |index| index as i32),
             cascade_level: self.num_cascades as i32,
             cascade_texel_sizes,
             bias: self.bias,
@@ -471,7 +500,8 @@ impl DirectionalLight {
             light_space_matrices,
             light_size: self.size,
             normal_bias: self.normal_bias,
-            _padding: Zeroable::zeroed(),
+            shadow_filter: self.shadow_filter.gpu_value(),
+            shadow_scale: 1.0,
         }
     }
 
@@ -518,6 +548,9 @@ impl Buildable for DirectionalLight {
             bias: 0.015,
             normal_bias: 0.015,
             size: 0.0,
+            shadow_filter: ShadowFilter::default(),
+            cast_shadows: true,
+            shadow_resolution: DIRECTIONAL_SHADOW_SIZE,
         }
     }
 }
@@ -532,6 +565,9 @@ pub struct DirectionalLightBuilder {
     bias: f32,
     normal_bias: f32,
     size: f32,
+    shadow_filter: ShadowFilter,
+    cast_shadows: bool,
+    shadow_resolution: u32,
 }
 
 impl Builder for DirectionalLightBuilder {
@@ -554,6 +590,9 @@ impl Builder for DirectionalLightBuilder {
             bias: self.bias,
             normal_bias: self.normal_bias,
             size: self.size,
+            shadow_filter: self.shadow_filter,
+            cast_shadows: self.cast_shadows,
+            shadow_resolution: self.shadow_resolution.max(1),
         }
     }
 }
@@ -583,6 +622,10 @@ impl DirectionalLightBuilder {
         self
     }
 
+    /// how wide the light looks in the sky in degrees, bigger lights give softer
+    /// [`ShadowFilter::Pcss`] shadows
+    ///
+    /// default value: 0
     pub fn size(mut self, size: f32) -> Self {
         self.size = size;
         self
@@ -613,6 +656,30 @@ impl DirectionalLightBuilder {
         self.num_cascades = level;
         self
     }
+
+    /// how the edges of the shadow are softened, see [`ShadowFilter`]
+    ///
+    /// default value: [`ShadowFilter::Pcf5x5`]
+    pub fn shadow_filter(mut self, shadow_filter: ShadowFilter) -> Self {
+        self.shadow_filter = shadow_filter;
+        self
+    }
+
+    /// render shadow maps for this light
+    ///
+    /// default value: true
+    pub fn cast_shadows(mut self, cast_shadows: bool) -> Self {
+        self.cast_shadows = cast_shadows;
+        self
+    }
+
+    /// width and height of each cascade's shadow map in texels
+    ///
+    /// default value: 2048
+    pub fn shadow_resolution(mut self, resolution: u32) -> Self {
+        self.shadow_resolution = resolution;
+        self
+    }
 }
 
 #[cfg(test)]
@@ -1158,7 +1225,7 @@ mod tests {
         let mut light = light;
         light.intensity = 10.0;
 
-        let buffer_data = light.to_buffer_data(&camera, 16.0 / 9.0, 0);
+        let buffer_data = light.to_buffer_data(&camera, 16.0 / 9.0, Some(0));
 
         // Verify shader buffer data is valid
         assert_eq!(buffer_data.cascade_level, 4, "Should have 4 cascades");
@@ -30,10 +30,35 @@ impl ResolutionScale {
     }
 }
 
+/// the images the sky of an [`Environment`] is made from
+#[derive(Clone)]
+pub enum EnvironmentSource {
+    /// an equirectangular panorama, usually an `.hdr` for proper lighting
+    Equirect(AssetHandle<Texture>),
+    /// the six square faces of a cube in the order +x, -x, +y, -y, +z, -z, laid out like the
+    /// faces of a ktx or opengl cubemap
+    Faces([AssetHandle<Texture>; 6]),
+}
+
+/// the sky of the scene
+///
+/// the source images are drawn as the background behind everything else and turned into the
+/// irradiance and prefiltered specular maps the main pass uses as ambient light. without one the
+/// background is the clear color and ambient light is uniform.
+///
+/// all of this is generated once from the first environment in the scene
+///
+/// ```rust, ignore
+/// ctx.scene.spawn(Environment::new(ctx.assets.load("res/sky.hdr")));
+///
+/// let faces = ["px", "nx", "py", "ny", "pz", "nz"]
+///     .map(|face| ctx.assets.load(format!("res/sky/{face}.png")));
+/// ctx.scene.spawn(Environment::from_faces(faces).with_ibl_strength(0.5));
+/// ```
 pub struct Environment {
     pub transform: NodeTransform,
 
-    hdri_source: AssetHandle<Texture>,
+    source: EnvironmentSource,
     ibl_strength: f32,
 
     cubemap_scale: ResolutionScale,
@@ -49,7 +74,17 @@ impl Node for Environment {
 }
 
 impl Environment {
+    /// an environment from an equirectangular panorama
     pub fn new(hdr: AssetHandle<Texture>) -> Self {
+        Self::from_source(EnvironmentSource::Equirect(hdr))
+    }
+
+    /// an environment from the six faces of a cube, see [`EnvironmentSource::Faces`]
+    pub fn from_faces(faces: [AssetHandle<Texture>; 6]) -> Self {
+        Self::from_source(EnvironmentSource::Faces(faces))
+    }
+
+    pub fn from_source(source: EnvironmentSource) -> Self {
         // Automatically determine base resolution from source HDR dimensions
         // For equirectangular maps, width is typically 2x height, so we use height
         // as the base cubemap resolution
@@ -57,7 +92,7 @@ impl Environment {
         // most of this is handled by the rendergraph
         Self {
             transform: NodeTransform::default(),
-            hdri_source: hdr,
+            source,
             ibl_strength: 1.0, // Default strength
             cubemap_scale: ResolutionScale::Full,
             irradiance_resolution: 32,
@@ -66,8 +101,25 @@ impl Environment {
         }
     }
 
+    pub fn source(&self) -> &EnvironmentSource {
+        &self.source
+    }
+
+    /// the panorama once it loaded, `None` for an environment made from faces
     pub fn get_hdri_texture(&self, assets: &AssetLibrary) -> Option<AssetRef<Texture>> {
-        assets.get::<Texture>(&self.hdri_source)
+        match &self.source {
+            EnvironmentSource::Equirect(hdr) => assets.get::<Texture>(hdr),
+            EnvironmentSource::Faces(_) => None,
+        }
+    }
+
+    /// the faces in the order of [`EnvironmentSource::Faces`] once all of them loaded, `None` for
+    /// an environment made from a panorama
+    pub fn get_face_textures(&self, assets: &AssetLibrary) -> Option<Vec<AssetRef<Texture>>> {
+        match &self.source {
+            EnvironmentSource::Equirect(_) => None,
+            EnvironmentSource::Faces(faces) => faces.iter().map(|face| assets.get(face)).collect(),
+        }
     }
 
     pub fn ibl_strength(&self) -> f32 {
@@ -91,12 +143,12 @@ impl Environment {
 
     /// Get the actual cubemap resolution after applying scale
     pub fn get_cubemap_resolution(&self, assets: &AssetLibrary) -> Option<u32> {
-        // Get the HDRI texture to determine base resolution
-        let texture = self.get_hdri_texture(assets)?;
-
-        // For equirectangular maps, width is typically 2x height
-        // Use height as the base cubemap resolution
-        let base_resolution = texture.height();
+        let base_resolution = match &self.source {
+            // For equirectangular maps, width is typically 2x height
+            // so half the height covers a cube face
+            EnvironmentSource::Equirect(_) => self.get_hdri_texture(assets)?.height() / 2,
+            EnvironmentSource::Faces(_) => self.get_face_textures(assets)?.first()?.width(),
+        };
 
         // Apply the resolution scale factor
         Some(self.cubemap_scale.apply(base_resolution))
@@ -0,0 +1,154 @@
+use glam::{Mat4, Quat, Vec3};
+use maple_engine::{
+    Buildable, Builder, GameContext, Node, asset::AssetHandle, nodes::node_builder::NodePrototype,
+    prelude::NodeTransform,
+};
+
+use crate::model::Model;
+
+#[allow(unused_imports, reason = "used in doc")]
+use crate::nodes::mesh_instance::MeshInstance3D;
+
+/// draws many copies of a [`Model`] from one node
+///
+/// the meshes of the model aren't spawned as [`MeshInstance3D`]s, each copy is a transform
+/// relative to the node that is drawn with one draw call per mesh of the model for all copies.
+/// the copies are culled one by one but can't be moved or picked on their own, use mesh instances
+/// for that
+///
+/// # Example
+/// ```rust, ignore
+/// let trees = (0..1000).map(|i| {
+///     let position = Vec3::new((i % 40) as f32 * 4.0, 0.0, (i / 40) as f32 * 4.0);
+///     Mat4::from_translation(position)
+/// });
+/// ctx.scene.spawn_with_name(
+///     "forest",
+///     InstancedModel::builder()
+///         .model(ctx.assets.load::<Model>("res/models/tree.obj"))
+///         .instances(trees),
+/// );
+/// ```
+#[derive(Clone)]
+pub struct InstancedModel {
+    /// Transform of the node
+    pub transform: NodeTransform,
+
+    /// the model drawn at each instance
+    pub model: Option<AssetHandle<Model>>,
+
+    /// where each copy is drawn relative to the node
+    pub instances: Vec<Mat4>,
+
+    /// skip drawing the copies whose bounding box is outside the camera or a light's frustum
+    pub frustum_culled: bool,
+}
+
+impl Default for InstancedModel {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl InstancedModel {
+    /// add a copy at `position` relative to the node
+    pub fn add_instance(&mut self, position: Vec3, rotation: Quat, scale: Vec3) {
+        self.instances.push(Mat4::from_scale_rotation_translation(
+            scale, rotation, position,
+        ));
+    }
+}
+
+impl Node for InstancedModel {
+    fn get_transform(&mut self) -> &mut NodeTransform {
+        &mut self.transform
+    }
+}
+
+/// give every instanced model the bounding box of all its copies once the model has loaded
+pub(crate) fn update_instanced_bounds(ctx: &GameContext) {
+    ctx.scene.for_each(&mut |instanced: &mut InstancedModel| {
+        let aabb = instanced
+            .model
+            .as_ref()
+            .and_then(|model| ctx.assets.get(model))
+            .and_then(|model| {
+                let mut bounds = None;
+                for model_mesh in model.meshes() {
+                    let Some(mesh) = ctx.assets.get(&model_mesh.mesh) else {
+                        continue;
+                    };
+                    for instance in &instanced.instances {
+                        let aabb = mesh.aabb().transform(&(*instance * model_mesh.transform));
+                        bounds = Some(match bounds {
+                            Some(bounds) => aabb.merge(&bounds),
+                            None => aabb,
+                        });
+                    }
+                }
+                bounds
+            });
+        instanced.transform.set_local_aabb(aabb);
+    });
+}
+
+pub struct InstancedModelBuilder {
+    prototype: NodePrototype,
+    model: Option<AssetHandle<Model>>,
+    instances: Vec<Mat4>,
+    frustum_culled: bool,
+}
+
+impl Default for InstancedModelBuilder {
+    fn default() -> Self {
+        Self {
+            prototype: NodePrototype::default(),
+            model: None,
+            instances: Vec::new(),
+            frustum_culled: true,
+        }
+    }
+}
+
+impl Buildable for InstancedModel {
+    type Builder = InstancedModelBuilder;
+    fn builder() -> Self::Builder {
+        InstancedModelBuilder::default()
+    }
+}
+
+impl Builder for InstancedModelBuilder {
+    type Node = InstancedModel;
+    fn prototype(&mut self) -> &mut NodePrototype {
+        &mut self.prototype
+    }
+
+    fn build(self) -> Self::Node {
+        Self::Node {
+            transform: self.prototype.transform,
+            model: self.model,
+            instances: self.instances,
+            frustum_culled: self.frustum_culled,
+        }
+    }
+}
+
+impl InstancedModelBuilder {
+    pub fn model(mut self, model: AssetHandle<Model>) -> Self {
+        self.model = Some(model);
+        self
+    }
+
+    /// the transforms of the copies relative to the node
+    pub fn instances(mut self, instances: impl IntoIterator<Item = Mat4>) -> Self {
+        self.instances.extend(instances);
+        self
+    }
+
+    /// set if copies are skipped when they're off screen. see
+    /// [`InstancedModel::frustum_culled`]
+    pub fn frustum_culled(mut self, frustum_culled: bool) -> Self {
+        self.frustum_culled = frustum_culled;
+        self
+    }
+}
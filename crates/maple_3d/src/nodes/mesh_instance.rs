@@ -2,14 +2,17 @@ use std::marker::PhantomData;
 
 use bytemuck::{Pod, Zeroable};
 use maple_engine::{
-    Buildable, Builder, Node,
+    Buildable, Builder, GameContext, Node,
     asset::{AssetHandle, AssetLibrary},
     nodes::node_builder::NodePrototype,
-    prelude::NodeTransform,
+    prelude::{NodeTransform, RayHit},
+    utils::ray::Ray,
 };
 
 use crate::{
+    animation::Skin,
     assets::mesh::Mesh3D,
+    lod::MeshLod,
     prelude::{Material, MaterialInstance, MaterialInstanceMut, MaterialInstanceRef},
 };
 
@@ -18,6 +21,14 @@ use crate::{
 pub struct Mesh3DUniformBufferData {
     pub model: [[f32; 4]; 4],
     pub normal_matrix: [[f32; 4]; 4],
+    /// where the joint matrices of the mesh's skin start in the joint buffer
+    pub joint_offset: u32,
+    /// 0 for meshes that aren't skinned
+    pub joint_count: u32,
+    /// which draw of its [`crate::assets::material::MaterialPass`] this is
+    pub pass_index: u32,
+    /// how many times the pass is drawn
+    pub pass_count: u32,
 }
 
 #[allow(unused_imports, reason = "used in doc")]
@@ -44,7 +55,7 @@ use maple_engine::prelude::Scene;
 ///     }))
 /// );
 /// ```
-#[derive(Clone, Default)]
+#[derive(Clone)]
 pub struct MeshInstance3D {
     /// Transform of the node
     pub transform: NodeTransform,
@@ -56,15 +67,47 @@ pub struct MeshInstance3D {
     ///
     /// **Meshes with no material will not be rendered**
     pub material: Option<AssetHandle<Material>>,
+
+    /// skip drawing the mesh when its bounding box is outside the camera or a light's frustum
+    ///
+    /// turn this off for meshes moved in the vertex shader whose bounding box doesn't cover where
+    /// they are drawn
+    pub frustum_culled: bool,
+
+    /// the joints that bend the mesh, the mesh's vertices need joints and weights
+    pub skin: Option<Skin>,
+
+    /// how much of each of the mesh's
+    /// [`MorphTargets`](crate::assets::mesh::MorphTargets) is blended in, by target index
+    ///
+    /// meshes whose weights are all zero are drawn without blending
+    pub morph_weights: Vec<f32>,
+
+    /// lower detail meshes drawn in place of [`MeshInstance3D::mesh`] while it's small on screen
+    pub lod: Option<MeshLod>,
+}
+
+impl Default for MeshInstance3D {
+    fn default() -> Self {
+        Self {
+            transform: NodeTransform::default(),
+            mesh: None,
+            material: None,
+            frustum_culled: true,
+            skin: None,
+            morph_weights: Vec::new(),
+            lod: None,
+        }
+    }
 }
 
 impl MeshInstance3D {
     pub fn get_uniform(&self) -> Mesh3DUniformBufferData {
-        let model = self.transform.world_space().matrix.to_cols_array_2d();
+        let model = self.transform.render_space().matrix().to_cols_array_2d();
         let normal_matrix = self
             .transform
-            .world_space()
-            .matrix
+            .render_space()
+            .matrix()
             .inverse()
             .transpose()
             .to_cols_array_2d();
@@ -72,9 +115,32 @@ impl MeshInstance3D {
         Mesh3DUniformBufferData {
             model,
             normal_matrix,
+            ..Default::default()
+        }
+    }
+
+    /// set the weight of one morph target, see [`MeshInstance3D::morph_weights`]
+    pub fn set_morph_weight(&mut self, index: usize, weight: f32) {
+        if self.morph_weights.len() <= index {
+            self.morph_weights.resize(index + 1, 0.0);
+        }
+        self.morph_weights[index] = weight;
+    }
+
+    /// the mesh drawn this frame, the level of [`MeshInstance3D::lod`] picked for the active
+    /// camera if there is one
+    pub fn drawn_mesh(&self) -> Option<&AssetHandle<Mesh3D>> {
+        match &self.lod {
+            Some(lod) => lod.mesh(self.mesh.as_ref()),
+            None => self.mesh.as_ref(),
         }
     }
 
+    /// true if any morph target is blended in
+    pub fn is_morphed(&self) -> bool {
+        self.morph_weights.iter().any(|weight| *weight != 0.0)
+    }
+
     pub fn get_material<T: MaterialInstance>(
         &self,
         assets: &AssetLibrary,
@@ -108,17 +174,86 @@ impl MeshInstance3D {
     }
 }
 
+/// give every mesh instance the bounding box of its mesh once the mesh has loaded
+pub(crate) fn update_mesh_bounds(ctx: &GameContext) {
+    ctx.scene.for_each(&mut |instance: &mut MeshInstance3D| {
+        let aabb = instance
+            .mesh
+            .as_ref()
+            .and_then(|mesh| ctx.assets.get(mesh))
+            .map(|mesh| *mesh.aabb());
+        instance.transform.set_local_aabb(aabb);
+    });
+}
+
+/// like [`Scene::raycast`] but mesh instances are hit with the triangles of their mesh instead of
+/// their bounding box. other nodes are hit with their bounding box
+pub fn raycast_meshes(scene: &Scene, assets: &AssetLibrary, ray: &Ray) -> Option<RayHit> {
+    let mut closest: Option<RayHit> = None;
+
+    // the bounding box is never further than the triangles inside it
+    for mut hit in scene.raycast_all(ray) {
+        if closest
+            .as_ref()
+            .is_some_and(|closest| closest.distance <= hit.distance)
+        {
+            break;
+        }
+
+        if let Some(instance) = scene.get::<MeshInstance3D>(hit.node) {
+            let instance = instance.read();
+            let Some(mesh) = instance.mesh.as_ref().and_then(|mesh| assets.get(mesh)) else {
+                continue;
+            };
+
+            // the local ray keeps world distances since its direction isn't normalized
+            let model = instance.transform.world_space().matrix();
+            let Some(distance) = mesh.raycast(&ray.transform(&model.inverse())) else {
+                continue;
+            };
+            hit.distance = distance;
+            hit.point = ray.at(distance);
+        }
+
+        if closest
+            .as_ref()
+            .is_none_or(|closest| hit.distance < closest.distance)
+        {
+            closest = Some(hit);
+        }
+    }
+
+    closest
+}
+
 impl Node for MeshInstance3D {
     fn get_transform(&mut self) -> &mut NodeTransform {
         &mut self.transform
     }
 }
 
-#[derive(Default)]
 pub struct MeshInstance3DBuilder {
     prototype: NodePrototype,
     mesh: Option<AssetHandle<Mesh3D>>,
     material: Option<AssetHandle<Material>>,
+    frustum_culled: bool,
+    skin: Option<Skin>,
+    morph_weights: Vec<f32>,
+    lod: Option<MeshLod>,
+}
+
+impl Default for MeshInstance3DBuilder {
+    fn default() -> Self {
+        Self {
+            prototype: NodePrototype::default(),
+            mesh: None,
+            material: None,
+            frustum_culled: true,
+            skin: None,
+            morph_weights: Vec::new(),
+            lod: None,
+        }
+    }
 }
 
 impl Buildable for MeshInstance3D {
@@ -139,6 +274,10 @@ impl Builder for MeshInstance3DBuilder {
             transform: self.prototype.transform,
             mesh: self.mesh,
             material: self.material,
+            frustum_culled: self.frustum_culled,
+            skin: self.skin,
+            morph_weights: self.morph_weights,
+            lod: self.lod,
         }
     }
 }
@@ -153,4 +292,29 @@ impl MeshInstance3DBuilder {
         self.material = Some(material);
         self
     }
+
+    /// set if the mesh is skipped when it's off screen. see [`MeshInstance3D::frustum_culled`]
+    pub fn frustum_culled(mut self, frustum_culled: bool) -> Self {
+        self.frustum_culled = frustum_culled;
+        self
+    }
+
+    /// bend the mesh with joint nodes. skinned meshes move outside of their bounding box so
+    /// [`MeshInstance3DBuilder::frustum_culled`] should usually be turned off
+    pub fn skin(mut self, skin: Skin) -> Self {
+        self.skin = Some(skin);
+        self
+    }
+
+    /// the starting weight of each of the mesh's morph targets
+    pub fn morph_weights(mut self, weights: impl Into<Vec<f32>>) -> Self {
+        self.morph_weights = weights.into();
+        self
+    }
+
+    /// draw lower detail meshes while the mesh is small on screen, see [`MeshLod`]
+    pub fn lod(mut self, lod: MeshLod) -> Self {
+        self.lod = Some(lod);
+        self
+    }
 }
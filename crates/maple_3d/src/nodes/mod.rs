@@ -1,5 +1,11 @@
+pub mod animation_player;
+pub mod area_light;
 pub mod camera;
 pub mod directional_light;
 pub mod environment;
+pub mod instanced_model;
 pub mod mesh_instance;
 pub mod point_light;
+pub mod rts_camera;
+pub mod spot_light;
+pub mod sprite_animation;
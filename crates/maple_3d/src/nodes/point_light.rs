@@ -15,6 +15,8 @@ use maple_engine::{
     prelude::NodeTransform,
 };
 
+use crate::render_passes::{shadow_filter::ShadowFilter, shadow_resource::POINT_SHADOW_SIZE};
+
 /// used to pass data to the shader buffer
 ///
 /// the data on the gpu follows this format in this order:
@@ -25,7 +27,11 @@ use maple_engine::{
 ///     float intensity;
 ///     int shadowIndex;
 ///     float far_plane;
-///     int _padding;
+///     float bias;
+///     float shadow_scale;
+///     uint shadow_filter;
+///     float size;
+///     float _padding;
 /// };
 /// ```
 #[repr(C)]
@@ -36,7 +42,12 @@ pub struct PointLightBufferData {
     intensity: f32,
     shadow_index: i32,
     far_plane: f32,
-    bias: f32, //ssbo is 16 byte aligned
+    bias: f32,
+    /// the part of each cube face the shadow was rendered into, see [`crate::render_passes::shadow_lod`]
+    pub shadow_scale: f32,
+    shadow_filter: u32,
+    size: f32,
+    _padding: f32, //ssbo is 16 byte aligned
 }
 
 #[repr(C)]
@@ -87,6 +98,19 @@ pub struct PointLight {
     near_plane: f32,
 
     pub bias: f32,
+
+    /// how the edges of the shadow are softened
+    pub shadow_filter: ShadowFilter,
+
+    /// diameter of the bulb, only softens [`ShadowFilter::Pcss`] shadows
+    pub size: f32,
+
+    /// render a shadow map for this light
+    pub cast_shadows: bool,
+
+    /// width of each shadow cube face in texels, [`crate::render_passes::shadow_lod`] can render
+    /// less of it
+    pub shadow_resolution: u32,
 }
 
 impl Node for PointLight {
@@ -116,21 +140,29 @@ impl PointLight {
             transform,
             color: Color::WHITE,
             bias: 0.001,
+            shadow_filter: ShadowFilter::default(),
+            size: 0.1,
+            cast_shadows: true,
+            shadow_resolution: POINT_SHADOW_SIZE,
         }
     }
 
-    /// returns the formatted buffer data
-    pub fn get_buffered_data(&self, index: usize) -> PointLightBufferData {
-        let position: [f32; 3] = self.transform.world_space().position.into();
+    /// returns the formatted buffer data, `shadow_index` is `None` for lights without a shadow
+    pub fn get_buffered_data(&self, shadow_index: Option<usize>) -> PointLightBufferData {
+        let position: [f32; 3] = self.transform.render_space().position().into();
         let sized_positon = [position[0], position[1], position[2], 0.0];
 
         PointLightBufferData {
             color: self.color.into(),
             position: sized_positon,
             intensity: self.intensity,
-            shadow_index: index as i32,
+            shadow_index: shadow_index.map_or(-1, |index| index as i32),
             far_plane: self.far_plane,
             bias: self.bias,
+            shadow_scale: 1.0,
+            shadow_filter: self.shadow_filter.gpu_value(),
+            size: self.size,
+            _padding: 0.0,
         }
     }
 
@@ -169,8 +201,8 @@ impl PointLight {
     }
 
     pub fn get_shadow_transformations(&self) -> [Mat4; 6] {
-        let transform = self.transform.world_space();
-        let pos = transform.position;
+        let transform = self.transform.render_space();
+        let pos = transform.position();
         let shadow_proj = self.projection;
 
         [
@@ -206,6 +238,10 @@ impl Buildable for PointLight {
             color: Color::WHITE.into(),
             near_plane: 0.1,
             bias: 0.001,
+            shadow_filter: ShadowFilter::default(),
+            size: 0.1,
+            cast_shadows: true,
+            shadow_resolution: POINT_SHADOW_SIZE,
         }
     }
 }
@@ -217,6 +253,10 @@ pub struct PointLightBuilder {
     color: Color,
     near_plane: f32,
     bias: f32,
+    shadow_filter: ShadowFilter,
+    size: f32,
+    cast_shadows: bool,
+    shadow_resolution: u32,
 }
 
 impl Builder for PointLightBuilder {
@@ -235,6 +275,10 @@ impl Builder for PointLightBuilder {
             far_plane,
             projection: Mat4::default(),
             bias: self.bias,
+            shadow_filter: self.shadow_filter,
+            size: self.size.max(0.0),
+            cast_shadows: self.cast_shadows,
+            shadow_resolution: self.shadow_resolution.max(1),
         };
 
         light.update_shadow_projection();
@@ -268,4 +312,36 @@ impl PointLightBuilder {
         self.bias = bias;
         self
     }
+
+    /// how the edges of the shadow are softened, see [`ShadowFilter`]
+    ///
+    /// default value: [`ShadowFilter::Pcf5x5`]
+    pub fn shadow_filter(mut self, shadow_filter: ShadowFilter) -> Self {
+        self.shadow_filter = shadow_filter;
+        self
+    }
+
+    /// diameter of the bulb, bigger lights give softer [`ShadowFilter::Pcss`] shadows
+    ///
+    /// default value: 0.1
+    pub fn size(mut self, size: f32) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// render a shadow map for this light
+    ///
+    /// default value: true
+    pub fn cast_shadows(mut self, cast_shadows: bool) -> Self {
+        self.cast_shadows = cast_shadows;
+        self
+    }
+
+    /// width of each shadow cube face in texels
+    ///
+    /// default value: 256
+    pub fn shadow_resolution(mut self, resolution: u32) -> Self {
+        self.shadow_resolution = resolution;
+        self
+    }
 }
@@ -0,0 +1,650 @@
+//! a top down strategy camera and helpers for selecting nodes with a box drawn by the mouse
+//!
+//! a [`RtsCameraController`] is placed on the ground at the point the camera looks at and moves
+//! its child [`Camera3D`] around that point. the engine updates every enabled controller each
+//! frame:
+//! - moving the cursor to the edge of the window scrolls the camera. the cursor is confined to
+//!   the window so it can't leave while scrolling, and released once no enabled controller
+//!   confines it
+//! - scrolling the mouse wheel zooms towards the point under the cursor
+//! - dragging with the rotate button or holding `Q`/`E` rotates around the focus point
+//!
+//! # Example
+//! ```no_run
+//! # use maple_engine::prelude::*;
+//! # use maple_3d::prelude::*;
+//! # let scene = Scene::default();
+//! scene
+//!     .spawn(RtsCameraController::builder().distance(20.0).pitch(55.0))
+//!     .spawn_child(Camera3D::builder().far_plane(500.0));
+//! ```
+//!
+//! boxes drawn with [`DragSelection`] are tested against the screen space bounds of every visible
+//! [`MeshInstance3D`] with [`select_in_rect`]
+
+use glam::{Mat4, Quat, Vec2, Vec3, camera::rh::view::look_at_mat4};
+use maple_engine::{
+    Buildable, Builder, GameContext, Node, Scene,
+    asset::AssetLibrary,
+    context::Resource,
+    nodes::node_builder::NodePrototype,
+    prelude::{NodeId, NodeTransform},
+    resources::{CursorMode, Input, KeyCode, MouseButton},
+};
+
+use crate::nodes::{camera::Camera3D, mesh_instance::MeshInstance3D};
+
+/// a strategy camera that orbits its child [`Camera3D`] around a point on the ground
+///
+/// the position of the node is the point the camera looks at. the node should be a root node
+/// and its rotation is not used, the direction the camera faces is set with [`Self::yaw`].
+pub struct RtsCameraController {
+    pub transform: NodeTransform,
+
+    /// rotation around the world up axis in radians
+    pub yaw: f32,
+    /// angle the camera looks down at the ground in radians
+    pub pitch: f32,
+    /// distance from the camera to the focus point
+    pub distance: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+
+    /// how fast the camera scrolls in units per second at a distance of 10
+    ///
+    /// scrolling is scaled by the distance so it feels the same at any zoom
+    pub pan_speed: f32,
+    /// scroll when the cursor is within this many pixels of the edge of the window
+    pub edge_margin: f32,
+    /// scroll when the cursor is near the edge of the window
+    pub edge_scrolling: bool,
+    /// keep the cursor inside the window while the controller is enabled
+    pub confine_cursor: bool,
+
+    /// how much of the distance is zoomed per line scrolled
+    pub zoom_speed: f32,
+
+    /// radians turned per pixel the mouse moves while the rotate button is held
+    pub rotate_sensitivity: f32,
+    /// radians per second turned while `Q` or `E` is held
+    pub rotate_speed: f32,
+    pub rotate_button: MouseButton,
+}
+
+impl Node for RtsCameraController {
+    fn get_transform(&mut self) -> &mut NodeTransform {
+        &mut self.transform
+    }
+}
+
+impl RtsCameraController {
+    /// the direction along the ground the camera faces
+    pub fn forward(&self) -> Vec3 {
+        Quat::from_rotation_y(self.yaw) * Vec3::NEG_Z
+    }
+
+    /// the direction along the ground to the right of the camera
+    pub fn right(&self) -> Vec3 {
+        Quat::from_rotation_y(self.yaw) * Vec3::X
+    }
+
+    /// where the camera is relative to the focus point
+    pub fn camera_offset(&self) -> Vec3 {
+        let offset = Vec3::new(0.0, self.pitch.sin(), self.pitch.cos()) * self.distance;
+        Quat::from_rotation_y(self.yaw) * offset
+    }
+
+    /// where the camera is in the world
+    pub fn camera_position(&self) -> Vec3 {
+        self.transform.position + self.camera_offset()
+    }
+
+    /// the view matrix of the camera with the controller's current position
+    pub fn view_matrix(&self) -> Mat4 {
+        look_at_mat4(self.camera_position(), self.transform.position, Vec3::Y)
+    }
+
+    /// move the focus point along the ground. `x` moves right and `y` moves forward
+    pub fn pan(&mut self, offset: impl Into<Vec2>) {
+        let offset = offset.into();
+        self.transform
+            .translate(self.right() * offset.x + self.forward() * offset.y);
+    }
+
+    /// turn the camera around the focus point by `radians`
+    pub fn rotate(&mut self, radians: f32) {
+        self.yaw = (self.yaw + radians).rem_euclid(std::f32::consts::TAU);
+    }
+
+    /// multiply the distance by `factor` while keeping `target` at the same place on screen
+    ///
+    /// the target should be on the ground plane of the focus point, see [`Self::ground_point`]
+    pub fn zoom_towards(&mut self, factor: f32, target: Vec3) {
+        let distance = (self.distance * factor).clamp(self.min_distance, self.max_distance);
+        // scaling everything around the target keeps it on the same ray from the camera
+        let scale = distance / self.distance;
+        let focus = target + (self.transform.position - target) * scale;
+
+        self.distance = distance;
+        self.transform.set_position(focus);
+    }
+
+    /// the point on the ground plane of the focus point that is under a screen position
+    pub fn ground_point(
+        &self,
+        camera: &Camera3D,
+        screen_position: Vec2,
+        screen_size: Vec2,
+    ) -> Option<Vec3> {
+        let aspect_ratio = screen_size.x / screen_size.y.max(1.0);
+        let view_projection = camera.get_projection_matrix(aspect_ratio) * self.view_matrix();
+        let (origin, direction) = screen_ray(view_projection, screen_position, screen_size);
+
+        let height = self.transform.position.y;
+        if direction.y.abs() < f32::EPSILON {
+            return None;
+        }
+        let t = (height - origin.y) / direction.y;
+        (t > 0.0).then(|| origin + direction * t)
+    }
+
+    /// move a camera to where the controller would place it
+    pub fn apply_to(&self, camera: &mut Camera3D) {
+        camera
+            .transform
+            .set_position(self.camera_offset())
+            .looking_at(Vec3::ZERO);
+    }
+
+    /// scroll, zoom and rotate from this frame's input
+    pub fn handle_input(&mut self, input: &Input, camera: &Camera3D, dt: f32) {
+        let screen_size = input.screen_size_pixels();
+        let cursor = input.cursor_position;
+
+        if self.edge_scrolling && !input.cursor_exit {
+            let mut direction = Vec2::ZERO;
+            if cursor.x <= self.edge_margin {
+                direction.x -= 1.0;
+            }
+            if cursor.x >= screen_size.x - self.edge_margin {
+                direction.x += 1.0;
+            }
+            if cursor.y <= self.edge_margin {
+                direction.y += 1.0;
+            }
+            if cursor.y >= screen_size.y - self.edge_margin {
+                direction.y -= 1.0;
+            }
+
+            if direction != Vec2::ZERO {
+                let speed = self.pan_speed * self.distance / 10.0 * dt;
+                self.pan(direction.normalize() * speed);
+            }
+        }
+
+        if input.mouse_buttons.contains(&self.rotate_button) {
+            self.rotate(-input.mouse_delta.x * self.rotate_sensitivity);
+        }
+        if input.keys.contains(&KeyCode::KeyQ) {
+            self.rotate(-self.rotate_speed * dt);
+        }
+        if input.keys.contains(&KeyCode::KeyE) {
+            self.rotate(self.rotate_speed * dt);
+        }
+
+        let scroll = input.scroll_delta_lines.y;
+        if scroll != 0.0 {
+            let factor = (1.0 - self.zoom_speed).max(0.01).powf(scroll);
+            let target = self
+                .ground_point(camera, cursor, screen_size)
+                .unwrap_or(self.transform.position);
+            self.zoom_towards(factor, target);
+        }
+    }
+}
+
+/// if the cursor was confined by the [`RtsCameraController`]s
+///
+/// the cursor is only confined once so game code can set it back to normal, and it is released
+/// when the controllers that wanted it are disabled or despawned
+#[derive(Default, Debug)]
+pub(crate) struct RtsCursorConfinement {
+    confined: bool,
+}
+
+impl Resource for RtsCursorConfinement {}
+
+/// updates every enabled [`RtsCameraController`] and places its child cameras
+pub(crate) fn update_rts_cameras(ctx: &GameContext, dt: f32) {
+    let mut confine = false;
+
+    for controller in ctx.scene.collect::<RtsCameraController>() {
+        if !ctx.scene.is_enabled(controller.id()) {
+            continue;
+        }
+
+        let cameras = controller.children::<Camera3D>();
+        confine |= controller.read().confine_cursor;
+
+        let mut node = controller.write();
+        if let Some(camera) = cameras.first() {
+            let input = ctx.get_resource::<Input>();
+            node.handle_input(&input, &camera.read(), dt);
+        }
+
+        for camera in cameras {
+            node.apply_to(&mut camera.write());
+        }
+    }
+
+    update_cursor_confinement(ctx, confine);
+}
+
+fn update_cursor_confinement(ctx: &GameContext, confine: bool) {
+    if !ctx.has_resource::<RtsCursorConfinement>() || !ctx.has_resource::<Input>() {
+        return;
+    }
+    let mut confinement = ctx.get_resource_mut::<RtsCursorConfinement>();
+    if confinement.confined == confine {
+        return;
+    }
+
+    let mut input = ctx.get_resource_mut::<Input>();
+    match confine {
+        true if input.cursor_mode() == CursorMode::Normal => {
+            input.set_cursor_mode(CursorMode::Confined);
+        }
+        // leave modes other code set since the controllers confined it
+        false if input.cursor_mode() == CursorMode::Confined => {
+            input.set_cursor_mode(CursorMode::Normal);
+        }
+        _ => {}
+    }
+    confinement.confined = confine;
+}
+
+impl Buildable for RtsCameraController {
+    type Builder = RtsCameraControllerBuilder;
+    fn builder() -> Self::Builder {
+        RtsCameraControllerBuilder {
+            prototype: NodePrototype::default(),
+            yaw: 0.0,
+            pitch: 60.0,
+            distance: 20.0,
+            min_distance: 5.0,
+            max_distance: 100.0,
+            pan_speed: 10.0,
+            edge_margin: 10.0,
+            edge_scrolling: true,
+            confine_cursor: true,
+            zoom_speed: 0.1,
+            rotate_sensitivity: 0.005,
+            rotate_speed: 1.5,
+            rotate_button: MouseButton::Middle,
+        }
+    }
+}
+
+/// builder implementation for RtsCameraController
+pub struct RtsCameraControllerBuilder {
+    prototype: NodePrototype,
+    yaw: f32,
+    pitch: f32,
+    distance: f32,
+    min_distance: f32,
+    max_distance: f32,
+    pan_speed: f32,
+    edge_margin: f32,
+    edge_scrolling: bool,
+    confine_cursor: bool,
+    zoom_speed: f32,
+    rotate_sensitivity: f32,
+    rotate_speed: f32,
+    rotate_button: MouseButton,
+}
+
+impl Builder for RtsCameraControllerBuilder {
+    type Node = RtsCameraController;
+    fn prototype(&mut self) -> &mut NodePrototype {
+        &mut self.prototype
+    }
+
+    fn build(self) -> Self::Node {
+        let min_distance = self.min_distance.min(self.max_distance);
+        RtsCameraController {
+            transform: self.prototype.transform,
+            yaw: self.yaw.to_radians(),
+            pitch: self.pitch.clamp(1.0, 89.0).to_radians(),
+            distance: self.distance.clamp(min_distance, self.max_distance),
+            min_distance,
+            max_distance: self.max_distance,
+            pan_speed: self.pan_speed,
+            edge_margin: self.edge_margin,
+            edge_scrolling: self.edge_scrolling,
+            confine_cursor: self.confine_cursor,
+            zoom_speed: self.zoom_speed,
+            rotate_sensitivity: self.rotate_sensitivity,
+            rotate_speed: self.rotate_speed,
+            rotate_button: self.rotate_button,
+        }
+    }
+}
+
+impl RtsCameraControllerBuilder {
+    /// the starting rotation around the up axis in degrees. Default: `0.0`
+    pub fn yaw(mut self, degrees: f32) -> Self {
+        self.yaw = degrees;
+        self
+    }
+
+    /// how far the camera looks down in degrees. Default: `60.0`
+    pub fn pitch(mut self, degrees: f32) -> Self {
+        self.pitch = degrees;
+        self
+    }
+
+    /// the starting distance from the focus point. Default: `20.0`
+    pub fn distance(mut self, distance: f32) -> Self {
+        self.distance = distance;
+        self
+    }
+
+    /// how close and far the camera can zoom. Default: `5.0..100.0`
+    pub fn zoom_range(mut self, min: f32, max: f32) -> Self {
+        self.min_distance = min;
+        self.max_distance = max;
+        self
+    }
+
+    /// scroll speed in units per second at a distance of 10. Default: `10.0`
+    pub fn pan_speed(mut self, speed: f32) -> Self {
+        self.pan_speed = speed;
+        self
+    }
+
+    /// how close to the edge of the window in pixels the cursor has to be to scroll.
+    /// Default: `10.0`
+    pub fn edge_margin(mut self, pixels: f32) -> Self {
+        self.edge_margin = pixels;
+        self
+    }
+
+    /// scroll when the cursor is at the edge of the window. Default: `true`
+    pub fn edge_scrolling(mut self, enabled: bool) -> Self {
+        self.edge_scrolling = enabled;
+        self
+    }
+
+    /// confine the cursor to the window. Default: `true`
+    pub fn confine_cursor(mut self, confine: bool) -> Self {
+        self.confine_cursor = confine;
+        self
+    }
+
+    /// how much of the distance is zoomed per line scrolled. Default: `0.1`
+    pub fn zoom_speed(mut self, speed: f32) -> Self {
+        self.zoom_speed = speed;
+        self
+    }
+
+    /// the mouse button dragged to rotate and how many radians a pixel turns.
+    /// Default: middle mouse and `0.005`
+    pub fn rotate_button(mut self, button: MouseButton, sensitivity: f32) -> Self {
+        self.rotate_button = button;
+        self.rotate_sensitivity = sensitivity;
+        self
+    }
+
+    /// radians per second turned with `Q` and `E`. Default: `1.5`
+    pub fn rotate_speed(mut self, speed: f32) -> Self {
+        self.rotate_speed = speed;
+        self
+    }
+}
+
+/// the world space ray through a pixel on the screen as an origin and a normalized direction
+pub(crate) fn screen_ray(
+    view_projection: Mat4,
+    screen_position: Vec2,
+    screen_size: Vec2,
+) -> (Vec3, Vec3) {
+    let ndc = screen_position / screen_size.max(Vec2::ONE) * 2.0 - Vec2::ONE;
+    let ndc = Vec2::new(ndc.x, -ndc.y);
+
+    let inverse = view_projection.inverse();
+    let near = inverse.project_point3(ndc.extend(0.0));
+    let far = inverse.project_point3(ndc.extend(1.0));
+
+    (near, (far - near).normalize_or_zero())
+}
+
+/// the pixel a world position is drawn at or `None` if it is behind the camera
+pub(crate) fn world_to_screen(
+    view_projection: Mat4,
+    position: Vec3,
+    screen_size: Vec2,
+) -> Option<Vec2> {
+    let clip = view_projection * position.extend(1.0);
+    if clip.w <= 0.0 {
+        return None;
+    }
+
+    let ndc = clip.truncate().truncate() / clip.w;
+    Some(Vec2::new(ndc.x + 1.0, 1.0 - ndc.y) * 0.5 * screen_size)
+}
+
+/// a rectangle on the screen in pixels
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SelectionRect {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl SelectionRect {
+    /// the rectangle between two corners in any order
+    pub fn from_corners(a: Vec2, b: Vec2) -> Self {
+        Self {
+            min: a.min(b),
+            max: a.max(b),
+        }
+    }
+
+    pub fn size(&self) -> Vec2 {
+        self.max - self.min
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+
+    pub fn intersects(&self, other: &SelectionRect) -> bool {
+        self.min.cmple(other.max).all() && other.min.cmple(self.max).all()
+    }
+}
+
+/// tracks a box dragged with the mouse
+///
+/// # Example
+/// ```no_run
+/// # use maple_engine::prelude::*;
+/// # use maple_3d::prelude::*;
+/// # fn example(ctx: &GameContext, selection: &mut DragSelection, camera: &Camera3D) {
+/// let input = ctx.get_resource::<Input>();
+/// if let Some(rect) = selection.update(&input, MouseButton::Left) {
+///     let selected = select_in_rect(&ctx.scene, &ctx.assets, camera, input.screen_size_pixels(), rect);
+/// }
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DragSelection {
+    start: Option<Vec2>,
+    current: Vec2,
+}
+
+impl DragSelection {
+    /// drags shorter than this many pixels are treated as clicks
+    pub const CLICK_SIZE: f32 = 4.0;
+
+    /// follow the cursor and return the finished rectangle the frame the button is released
+    pub fn update(&mut self, input: &Input, button: MouseButton) -> Option<SelectionRect> {
+        self.current = input.cursor_position;
+
+        if input.mouse_button_just_pressed.contains(&button) {
+            self.start = Some(self.current);
+        }
+
+        if input.mouse_button_just_released.contains(&button) {
+            let rect = self.rect();
+            self.start = None;
+            return rect;
+        }
+
+        None
+    }
+
+    /// the rectangle being dragged, useful for drawing it
+    pub fn rect(&self) -> Option<SelectionRect> {
+        self.start
+            .map(|start| SelectionRect::from_corners(start, self.current))
+    }
+
+    /// true while the mouse is held and has moved far enough to not be a click
+    pub fn is_dragging(&self) -> bool {
+        self.rect()
+            .is_some_and(|rect| rect.size().max_element() >= Self::CLICK_SIZE)
+    }
+
+    /// stop the current drag without returning a rectangle
+    pub fn cancel(&mut self) {
+        self.start = None;
+    }
+}
+
+/// the visible [`MeshInstance3D`] nodes whose screen space bounds touch a rectangle
+///
+/// nodes are tested with the bounding box of their mesh. nodes without a loaded mesh are tested
+/// with their position. nodes are returned in scene order
+pub fn select_in_rect(
+    scene: &Scene,
+    assets: &AssetLibrary,
+    camera: &Camera3D,
+    screen_size: Vec2,
+    rect: SelectionRect,
+) -> Vec<NodeId> {
+    let aspect_ratio = screen_size.x / screen_size.y.max(1.0);
+    let view_projection = camera.get_vp_matrix(aspect_ratio);
+
+    scene
+        .collect_visible::<MeshInstance3D>()
+        .into_iter()
+        .filter(|mesh| {
+            let node = mesh.read();
+            let world = *node.transform.render_space();
+
+            let corners = match node.mesh.as_ref().and_then(|handle| assets.get(handle)) {
+                Some(mesh) => mesh.world_aabb(world).corners().to_vec(),
+                None => vec![world.position()],
+            };
+
+            let points: Vec<Vec2> = corners
+                .into_iter()
+                .filter_map(|corner| world_to_screen(view_projection, corner, screen_size))
+                .collect();
+            if points.is_empty() {
+                return false;
+            }
+
+            let bounds = points.iter().fold(
+                SelectionRect::from_corners(points[0], points[0]),
+                |rect, point| {
+                    SelectionRect::from_corners(rect.min.min(*point), rect.max.max(*point))
+                },
+            );
+            rect.intersects(&bounds)
+        })
+        .map(|mesh| mesh.id())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_is_released_when_controllers_stop_confining_it() {
+        let mut ctx = GameContext::new();
+        ctx.insert_resource(Input::headless());
+        ctx.insert_resource(RtsCursorConfinement::default());
+        let first = ctx.scene.spawn(RtsCameraController::builder()).id();
+        let second = ctx.scene.spawn(RtsCameraController::builder()).id();
+        let cursor_mode = |ctx: &GameContext| ctx.get_resource::<Input>().cursor_mode();
+
+        update_rts_cameras(&ctx, 0.0);
+        assert_eq!(cursor_mode(&ctx), CursorMode::Confined);
+
+        // another controller still confines it
+        ctx.scene.set_enabled(first, false);
+        update_rts_cameras(&ctx, 0.0);
+        assert_eq!(cursor_mode(&ctx), CursorMode::Confined);
+
+        ctx.scene.set_enabled(second, false);
+        update_rts_cameras(&ctx, 0.0);
+        assert_eq!(cursor_mode(&ctx), CursorMode::Normal);
+
+        // game code releasing the cursor isn't undone every frame
+        ctx.scene.set_enabled(second, true);
+        update_rts_cameras(&ctx, 0.0);
+        assert_eq!(cursor_mode(&ctx), CursorMode::Confined);
+        ctx.get_resource_mut::<Input>()
+            .set_cursor_mode(CursorMode::Normal);
+        update_rts_cameras(&ctx, 0.0);
+        assert_eq!(cursor_mode(&ctx), CursorMode::Normal);
+
+        // despawned controllers release it too
+        ctx.scene.set_enabled(second, false);
+        update_rts_cameras(&ctx, 0.0);
+        ctx.scene.set_enabled(first, true);
+        update_rts_cameras(&ctx, 0.0);
+        assert_eq!(cursor_mode(&ctx), CursorMode::Confined);
+        ctx.scene.despawn(first);
+        update_rts_cameras(&ctx, 0.0);
+        assert_eq!(cursor_mode(&ctx), CursorMode::Normal);
+    }
+
+    #[test]
+    fn test_zoom_keeps_target_under_cursor() {
+        let mut controller = RtsCameraController::builder()
+            .distance(20.0)
+            .pitch(45.0)
+            .build();
+        let camera = Camera3D::builder().build();
+        let screen_size = Vec2::new(800.0, 600.0);
+        let cursor = Vec2::new(600.0, 450.0);
+
+        let target = controller
+            .ground_point(&camera, cursor, screen_size)
+            .unwrap();
+        controller.zoom_towards(0.5, target);
+
+        assert!((controller.distance - 10.0).abs() < 1e-4);
+        let after = controller
+            .ground_point(&camera, cursor, screen_size)
+            .unwrap();
+        assert!(after.distance(target) < 1e-2);
+    }
+
+    #[test]
+    fn test_screen_projection_round_trip() {
+        let controller = RtsCameraController::builder().build();
+        let camera = Camera3D::builder().build();
+        let screen_size = Vec2::new(800.0, 600.0);
+        let view_projection =
+            camera.get_projection_matrix(800.0 / 600.0) * controller.view_matrix();
+
+        let center = world_to_screen(view_projection, Vec3::ZERO, screen_size).unwrap();
+        assert!(center.distance(screen_size / 2.0) < 1e-2);
+
+        let rect = SelectionRect::from_corners(Vec2::new(390.0, 310.0), Vec2::new(410.0, 290.0));
+        assert!(rect.contains(center));
+    }
+}
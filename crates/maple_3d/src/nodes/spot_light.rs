@@ -0,0 +1,447 @@
+//! Spot lights emit light from a point in space in a cone, like a flashlight or a stage light
+//!
+//! This module provides a spot light node that can be added to a scene. the light shines down the
+//! node's -Z axis. it is at full strength inside the inner cone angle and fades out towards the
+//! outer cone angle. nothing past the range is lit and the shadow is rendered out to it.
+//!
+//! ```rust,ignore
+//! ctx.scene.spawn(
+//!     SpotLight::builder()
+//!         .position((0.0, 3.0, 0.0))
+//!         .direction(Vec3::NEG_Y)
+//!         .cone(20.0, 30.0)
+//!         .range(15.0)
+//!         .intensity(40.0)
+//!         .build(),
+//! );
+//! ```
+
+pub(crate) const MAX_LIGHTS: usize = 100;
+
+use bytemuck::{Pod, Zeroable};
+use glam::{
+    Mat4, Quat, Vec3,
+    camera::rh::{proj::directx::perspective, view::look_to_mat4},
+};
+use maple_engine::{
+    Buildable, Builder, Node, color::Color, nodes::node_builder::NodePrototype,
+    prelude::NodeTransform,
+};
+
+use crate::render_passes::{shadow_filter::ShadowFilter, shadow_resource::SPOT_SHADOW_SIZE};
+
+/// used to pass data to the shader buffer
+///
+/// the data on the gpu follows this format in this order:
+/// ```c
+/// struct SpotLight {
+///     vec4 color;
+///     vec4 pos;
+///     vec4 direction;
+///     mat4 light_space_matrix;
+///     float intensity;
+///     float range;
+///     float inner_cos;
+///     float outer_cos;
+///     int shadowIndex;
+///     float bias;
+///     float near_plane;
+///     float size;
+///     uint shadow_filter;
+///     float shadow_scale;
+///     uvec2 _padding;
+/// };
+/// ```
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, Pod, Zeroable)]
+pub struct SpotLightBufferData {
+    color: [f32; 4],
+    position: [f32; 4],
+    direction: [f32; 4],
+    light_space_matrix: [[f32; 4]; 4],
+    intensity: f32,
+    range: f32,
+    inner_cos: f32,
+    outer_cos: f32,
+    shadow_index: i32,
+    bias: f32,
+    near_plane: f32,
+    size: f32,
+    shadow_filter: u32,
+    /// the part of the shadow map width the light was rendered into
+    pub shadow_scale: f32,
+    _padding: [u32; 2], //ssbo is 16 byte aligned
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct SpotLightBuffer {
+    pub length: i32,
+    _padding: [i32; 3],
+    pub data: [SpotLightBufferData; MAX_LIGHTS],
+}
+
+impl SpotLightBuffer {
+    pub fn from_lights(lights: &[SpotLightBufferData]) -> Self {
+        let mut buffer = SpotLightBuffer {
+            length: lights.len().min(MAX_LIGHTS) as i32,
+            _padding: [0; 3],
+            data: [SpotLightBufferData::default(); MAX_LIGHTS],
+        };
+
+        let copy_count = lights.len().min(MAX_LIGHTS);
+        buffer.data[..copy_count].copy_from_slice(&lights[..copy_count]);
+
+        buffer
+    }
+}
+
+/// spot light nodes represent spot lights in the Scene, see the [module docs](self)
+#[derive(Debug, Clone)]
+pub struct SpotLight {
+    /// transform component for spot light, the light shines down its -Z axis
+    pub transform: NodeTransform,
+
+    /// the light intensity (simply factors the color by a scale)
+    pub intensity: f32,
+
+    /// the light color default is White
+    pub color: Color,
+
+    /// how far the light reaches, also the far plane of its shadow
+    range: f32,
+
+    /// angle from the center of the cone in degrees where the light starts to fade out
+    inner_angle: f32,
+
+    /// angle from the center of the cone in degrees where the light is gone
+    outer_angle: f32,
+
+    near_plane: f32,
+
+    /// render a shadow map for this light
+    pub cast_shadows: bool,
+
+    /// how far surfaces are pushed out along their normal before the shadow is tested, scaled by
+    /// their distance to the light
+    pub bias: f32,
+
+    /// how the edges of the shadow are softened
+    pub shadow_filter: ShadowFilter,
+
+    /// diameter of the bulb, only softens [`ShadowFilter::Pcss`] shadows
+    pub size: f32,
+
+    /// width and height of the shadow map in texels
+    pub shadow_resolution: u32,
+}
+
+impl Node for SpotLight {
+    fn get_transform(&mut self) -> &mut NodeTransform {
+        &mut self.transform
+    }
+}
+
+impl Default for SpotLight {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl SpotLight {
+    /// the widest a cone can be, a shadow can't be projected at 180 degrees
+    pub const MAX_ANGLE: f32 = 85.0;
+
+    /// create a spot light with the default cone
+    pub fn new() -> SpotLight {
+        Self::default()
+    }
+
+    /// the direction the light shines in
+    pub fn direction(&self) -> Vec3 {
+        self.transform.render_space().rotation() * Vec3::NEG_Z
+    }
+
+    /// point the light in a direction
+    pub fn set_direction(&mut self, direction: impl Into<Vec3>) -> &mut Self {
+        let direction = direction.into().normalize();
+        self.transform
+            .set_rotation(Quat::from_rotation_arc(Vec3::NEG_Z, direction));
+        self
+    }
+
+    /// how far the light reaches
+    pub fn get_range(&self) -> f32 {
+        self.range
+    }
+
+    /// set how far the light reaches, it can't be closer than the shadow near plane
+    pub fn set_range(&mut self, range: f32) -> &mut Self {
+        self.range = range.max(self.near_plane * 2.0);
+        self
+    }
+
+    /// the inner and outer angle of the cone in degrees
+    pub fn get_cone(&self) -> (f32, f32) {
+        (self.inner_angle, self.outer_angle)
+    }
+
+    /// set the angles from the center of the cone in degrees where the light starts to fade out
+    /// and where it is gone
+    ///
+    /// the outer angle is clamped to [`SpotLight::MAX_ANGLE`] and the inner angle to the outer
+    pub fn set_cone(&mut self, inner_angle: f32, outer_angle: f32) -> &mut Self {
+        (self.inner_angle, self.outer_angle) = Self::clamp_cone(inner_angle, outer_angle);
+        self
+    }
+
+    fn clamp_cone(inner_angle: f32, outer_angle: f32) -> (f32, f32) {
+        let outer_angle = outer_angle.clamp(0.1, Self::MAX_ANGLE);
+        (inner_angle.clamp(0.0, outer_angle), outer_angle)
+    }
+
+    /// set the light color
+    pub fn set_color(&mut self, color: impl Into<Color>) -> &mut Self {
+        self.color = color.into();
+        self
+    }
+
+    /// projection of the shadow map, covers the outer cone out to the range
+    pub fn view_projection(&self) -> Mat4 {
+        let transform = self.transform.render_space();
+        let direction = self.direction();
+
+        let up = if direction.dot(Vec3::Y).abs() > 0.99 {
+            Vec3::Z
+        } else {
+            Vec3::Y
+        };
+
+        let projection = perspective(
+            (self.outer_angle * 2.0).to_radians(),
+            1.0,
+            self.near_plane,
+            self.range,
+        );
+        projection * look_to_mat4(transform.position(), direction, up)
+    }
+
+    /// returns the formatted buffer data, `shadow_index` is `None` for lights without a shadow
+    pub fn get_buffered_data(&self, shadow_index: Option<usize>) -> SpotLightBufferData {
+        let position = self.transform.render_space().position();
+
+        SpotLightBufferData {
+            color: self.color.into(),
+            position: position.extend(0.0).to_array(),
+            direction: self.direction().extend(0.0).to_array(),
+            light_space_matrix: self.view_projection().to_cols_array_2d(),
+            intensity: self.intensity,
+            range: self.range,
+            inner_cos: self.inner_angle.to_radians().cos(),
+            outer_cos: self.outer_angle.to_radians().cos(),
+            shadow_index: shadow_index.map_or(-1, |index| index as i32),
+            bias: self.bias,
+            near_plane: self.near_plane,
+            size: self.size,
+            shadow_filter: self.shadow_filter.gpu_value(),
+            shadow_scale: 1.0,
+            _padding: [0; 2],
+        }
+    }
+}
+
+impl Buildable for SpotLight {
+    type Builder = SpotLightBuilder;
+    fn builder() -> Self::Builder {
+        Self::Builder {
+            prototype: NodePrototype::default(),
+            intensity: 1.0,
+            color: Color::WHITE,
+            range: 10.0,
+            inner_angle: 20.0,
+            outer_angle: 30.0,
+            near_plane: 0.1,
+            cast_shadows: true,
+            bias: 0.001,
+            shadow_filter: ShadowFilter::default(),
+            size: 0.1,
+            shadow_resolution: SPOT_SHADOW_SIZE,
+        }
+    }
+}
+
+/// spot light specific builder
+pub struct SpotLightBuilder {
+    prototype: NodePrototype,
+    intensity: f32,
+    color: Color,
+    range: f32,
+    inner_angle: f32,
+    outer_angle: f32,
+    near_plane: f32,
+    cast_shadows: bool,
+    bias: f32,
+    shadow_filter: ShadowFilter,
+    size: f32,
+    shadow_resolution: u32,
+}
+
+impl Builder for SpotLightBuilder {
+    type Node = SpotLight;
+    fn prototype(&mut self) -> &mut NodePrototype {
+        &mut self.prototype
+    }
+
+    fn build(self) -> Self::Node {
+        let (inner_angle, outer_angle) = SpotLight::clamp_cone(self.inner_angle, self.outer_angle);
+        Self::Node {
+            transform: self.prototype.transform,
+            intensity: self.intensity,
+            color: self.color,
+            range: self.range.max(self.near_plane * 2.0),
+            inner_angle,
+            outer_angle,
+            near_plane: self.near_plane,
+            cast_shadows: self.cast_shadows,
+            bias: self.bias,
+            shadow_filter: self.shadow_filter,
+            size: self.size.max(0.0),
+            shadow_resolution: self.shadow_resolution.max(1),
+        }
+    }
+}
+
+impl SpotLightBuilder {
+    /// set the intensity of the light
+    pub fn intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// set the color of the light
+    pub fn color(mut self, color: impl Into<Color>) -> Self {
+        self.color = color.into();
+        self
+    }
+
+    /// the direction the light shines in
+    pub fn direction(mut self, direction: impl Into<Vec3>) -> Self {
+        let direction = direction.into().normalize();
+        self.prototype()
+            .transform
+            .set_rotation(Quat::from_rotation_arc(Vec3::NEG_Z, direction));
+        self
+    }
+
+    /// how far the light reaches
+    ///
+    /// default value: 10
+    pub fn range(mut self, range: f32) -> Self {
+        self.range = range;
+        self
+    }
+
+    /// angles from the center of the cone in degrees where the light starts to fade out and where
+    /// it is gone
+    ///
+    /// default value: 20 and 30
+    pub fn cone(mut self, inner_angle: f32, outer_angle: f32) -> Self {
+        self.inner_angle = inner_angle;
+        self.outer_angle = outer_angle;
+        self
+    }
+
+    /// near clipping plane of the light shadow projection
+    pub fn near_plane(mut self, near_plane: f32) -> Self {
+        self.near_plane = near_plane;
+        self
+    }
+
+    /// render a shadow map for this light
+    ///
+    /// default value: true
+    pub fn cast_shadows(mut self, cast_shadows: bool) -> Self {
+        self.cast_shadows = cast_shadows;
+        self
+    }
+
+    /// set the shadow bias, see [`SpotLight::bias`]
+    ///
+    /// default value: 0.001
+    pub fn bias(mut self, bias: f32) -> Self {
+        self.bias = bias;
+        self
+    }
+
+    /// how the edges of the shadow are softened, see [`ShadowFilter`]
+    ///
+    /// default value: [`ShadowFilter::Pcf5x5`]
+    pub fn shadow_filter(mut self, shadow_filter: ShadowFilter) -> Self {
+        self.shadow_filter = shadow_filter;
+        self
+    }
+
+    /// diameter of the bulb, bigger lights give softer [`ShadowFilter::Pcss`] shadows
+    ///
+    /// default value: 0.1
+    pub fn size(mut self, size: f32) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// width and height of the shadow map in texels
+    ///
+    /// default value: 1024
+    pub fn shadow_resolution(mut self, resolution: u32) -> Self {
+        self.shadow_resolution = resolution;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec4;
+    use maple_engine::components::node_transform::WorldTransform;
+
+    use super::*;
+
+    #[test]
+    fn test_cone_is_clamped() {
+        let light = SpotLight::builder().cone(50.0, 40.0).build();
+        assert_eq!(light.get_cone(), (40.0, 40.0));
+
+        let light = SpotLight::builder().cone(10.0, 120.0).build();
+        assert_eq!(light.get_cone(), (10.0, SpotLight::MAX_ANGLE));
+
+        // the gpu layout has to match the wgsl struct
+        assert_eq!(size_of::<SpotLightBufferData>(), 160);
+    }
+
+    #[test]
+    fn test_shadow_covers_the_cone() {
+        let mut light = SpotLight::builder()
+            .direction(Vec3::NEG_Y)
+            .cone(10.0, 30.0)
+            .range(10.0)
+            .build();
+        light.transform.get_world_space(WorldTransform::default());
+        let view_projection = light.view_projection();
+
+        let clip = |point: Vec3| {
+            let clip = view_projection * point.extend(1.0);
+            clip / clip.w
+        };
+        let inside = |clip: Vec4| {
+            clip.x.abs() <= 1.0 && clip.y.abs() <= 1.0 && (0.0..=1.0).contains(&clip.z)
+        };
+
+        assert!(inside(clip(Vec3::new(0.0, -5.0, 0.0))));
+        // just inside the outer angle
+        let edge = 5.0 * 29.0f32.to_radians().tan();
+        assert!(inside(clip(Vec3::new(edge, -5.0, 0.0))));
+        // outside the cone, behind the light and past the range
+        assert!(!inside(clip(Vec3::new(5.0, -5.0, 0.0))));
+        assert!(!inside(clip(Vec3::new(0.0, 5.0, 0.0))));
+        assert!(!inside(clip(Vec3::new(0.0, -11.0, 0.0))));
+    }
+}
@@ -0,0 +1,389 @@
+//! frame by frame animation of a sprite from a [`TextureAtlas`]
+//!
+//! a [`SpriteAnimation`] is added as a child of a [`MeshInstance3D`] whose material is a
+//! [`PbrMaterial`] using the atlas texture. the engine advances every enabled animation each frame
+//! and moves the material's texture coordinates onto the current frame.
+//!
+//! # Example
+//! ```no_run
+//! # use maple_engine::prelude::*;
+//! # use maple_3d::prelude::*;
+//! # use glam::Vec3;
+//! # let scene = Scene::default();
+//! # let assets = AssetLibrary::default();
+//! let atlas = TextureAtlas::grid(assets.load("res/player_run.png"), 8, 1);
+//!
+//! scene
+//!     .spawn(
+//!         MeshInstance3D::builder()
+//!             .mesh(assets.add(Plane {
+//!                 normal: Vec3::Z,
+//!                 ..Default::default()
+//!             }))
+//!             .material(assets.add(atlas.material())),
+//!     )
+//!     .spawn_child(
+//!         SpriteAnimation::builder()
+//!             .frames(&atlas, 0..8, 0.1)
+//!             .looping(true),
+//!     )
+//!     .on::<SpriteFrameChanged>(|ctx| {
+//!         if ctx.event.frame == 3 { /* play a footstep */ }
+//!     });
+//! ```
+
+use maple_engine::{
+    Buildable, Builder, GameContext, Node,
+    nodes::node_builder::NodePrototype,
+    prelude::{EventLabel, NodeTransform},
+};
+
+use crate::{
+    assets::{
+        materials::PbrMaterial,
+        texture_atlas::{AtlasRegion, TextureAtlas},
+    },
+    nodes::mesh_instance::MeshInstance3D,
+};
+
+#[allow(unused_imports, reason = "used in doc")]
+use crate::assets::primitives::Plane;
+
+/// emitted to a [`SpriteAnimation`] when it shows a different frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SpriteFrameChanged {
+    /// index of the frame in the animation
+    pub frame: usize,
+    /// index of the frame's region in the atlas
+    pub atlas_index: usize,
+}
+impl EventLabel for SpriteFrameChanged {}
+
+/// emitted to a [`SpriteAnimation`] that doesn't loop when it reaches the end of its last frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SpriteAnimationFinished;
+impl EventLabel for SpriteAnimationFinished {}
+
+/// a single frame of a [`SpriteAnimation`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpriteFrame {
+    /// index of the region in the atlas the frame was taken from
+    pub atlas_index: usize,
+    /// where the frame is in the texture
+    pub region: AtlasRegion,
+    /// how long the frame is shown in seconds
+    pub duration: f32,
+}
+
+/// what happened while advancing a [`SpriteAnimation`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SpriteAnimationStep {
+    /// the new frame if it changed
+    pub frame_changed: Option<usize>,
+    /// true if the animation reached its end this step
+    pub finished: bool,
+}
+
+/// plays the frames of a [`TextureAtlas`] on its parent [`MeshInstance3D`]
+#[derive(Clone)]
+pub struct SpriteAnimation {
+    pub transform: NodeTransform,
+    pub frames: Vec<SpriteFrame>,
+    /// start over after the last frame
+    pub looping: bool,
+    /// multiplier for how fast the animation plays
+    pub speed: f32,
+    playing: bool,
+    current: usize,
+    elapsed: f32,
+    /// the frame last written to the material
+    applied: Option<usize>,
+}
+
+impl Node for SpriteAnimation {
+    fn get_transform(&mut self) -> &mut NodeTransform {
+        &mut self.transform
+    }
+}
+
+impl SpriteAnimation {
+    /// true if the animation is advancing
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// true if the animation doesn't loop and has reached its end
+    pub fn is_finished(&self) -> bool {
+        !self.looping
+            && !self.playing
+            && !self.frames.is_empty()
+            && self.current == self.frames.len() - 1
+            && self.elapsed >= self.frames[self.current].duration
+    }
+
+    /// continue playing from the current frame or restart a finished animation
+    pub fn play(&mut self) {
+        if self.is_finished() {
+            self.current = 0;
+            self.elapsed = 0.0;
+        }
+        self.playing = true;
+    }
+
+    /// stop advancing keeping the current frame
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// stop advancing and go back to the first frame
+    pub fn stop(&mut self) {
+        self.playing = false;
+        self.current = 0;
+        self.elapsed = 0.0;
+    }
+
+    /// the index of the frame currently shown
+    pub fn current_frame(&self) -> usize {
+        self.current
+    }
+
+    /// jump to a frame. the index is clamped to the last frame
+    pub fn set_frame(&mut self, frame: usize) {
+        self.current = frame.min(self.frames.len().saturating_sub(1));
+        self.elapsed = 0.0;
+    }
+
+    /// the region of the texture that should be shown
+    pub fn current_region(&self) -> Option<AtlasRegion> {
+        self.frames.get(self.current).map(|frame| frame.region)
+    }
+
+    /// advance the animation by `dt` seconds
+    ///
+    /// this is done by the engine every frame so it is only needed to drive an animation by hand
+    pub fn advance(&mut self, dt: f32) -> SpriteAnimationStep {
+        let mut step = SpriteAnimationStep::default();
+        // frames with no duration would never stop advancing
+        if !self.playing || self.frames.iter().all(|frame| frame.duration <= 0.0) {
+            return step;
+        }
+
+        let start = self.current;
+        self.elapsed += dt * self.speed;
+
+        loop {
+            let duration = self.frames[self.current].duration;
+            if self.elapsed < duration {
+                break;
+            }
+
+            if self.current + 1 < self.frames.len() {
+                self.elapsed -= duration;
+                self.current += 1;
+            } else if self.looping {
+                self.elapsed -= duration;
+                self.current = 0;
+            } else {
+                self.elapsed = duration;
+                self.playing = false;
+                step.finished = true;
+                break;
+            }
+        }
+
+        if self.current != start {
+            step.frame_changed = Some(self.current);
+        }
+        step
+    }
+}
+
+/// advances every enabled [`SpriteAnimation`] and applies its frame to the parent material
+pub(crate) fn update_sprite_animations(ctx: &GameContext, dt: f32) {
+    for animation in ctx.scene.collect::<SpriteAnimation>() {
+        let id = animation.id();
+        if !ctx.scene.is_enabled(id) {
+            continue;
+        }
+
+        let (step, region) = {
+            let mut animation = animation.write();
+            let step = animation.advance(dt);
+            // frames change the material without moving a node
+            if animation.is_playing() {
+                ctx.request_redraw();
+            }
+            let region = match animation.applied != Some(animation.current) {
+                true => animation.current_region(),
+                false => None,
+            };
+            (step, region)
+        };
+
+        if let Some(region) = region
+            && let Some(parent) = animation.parent::<MeshInstance3D>()
+            && let Some(mut material) = parent.read().get_material_mut::<PbrMaterial>(&ctx.assets)
+        {
+            material.texture_scale = region.scale();
+            material.texture_offset = region.offset();
+
+            let mut animation = animation.write();
+            animation.applied = Some(animation.current);
+        }
+
+        if let Some(frame) = step.frame_changed {
+            let atlas_index = animation.read().frames[frame].atlas_index;
+            ctx.scene
+                .emit_to(id, &SpriteFrameChanged { frame, atlas_index }, ctx);
+        }
+        if step.finished {
+            ctx.scene.emit_to(id, &SpriteAnimationFinished, ctx);
+        }
+    }
+}
+
+pub struct SpriteAnimationBuilder {
+    prototype: NodePrototype,
+    frames: Vec<SpriteFrame>,
+    looping: bool,
+    speed: f32,
+    playing: bool,
+}
+
+impl Default for SpriteAnimationBuilder {
+    fn default() -> Self {
+        Self {
+            prototype: NodePrototype::default(),
+            frames: Vec::new(),
+            looping: true,
+            speed: 1.0,
+            playing: true,
+        }
+    }
+}
+
+impl Buildable for SpriteAnimation {
+    type Builder = SpriteAnimationBuilder;
+    fn builder() -> Self::Builder {
+        SpriteAnimationBuilder::default()
+    }
+}
+
+impl Builder for SpriteAnimationBuilder {
+    type Node = SpriteAnimation;
+    fn prototype(&mut self) -> &mut NodePrototype {
+        &mut self.prototype
+    }
+
+    fn build(self) -> Self::Node {
+        Self::Node {
+            transform: self.prototype.transform,
+            frames: self.frames,
+            looping: self.looping,
+            speed: self.speed,
+            playing: self.playing,
+            current: 0,
+            elapsed: 0.0,
+            applied: None,
+        }
+    }
+}
+
+impl SpriteAnimationBuilder {
+    /// add a region of the atlas as a frame shown for `duration` seconds
+    ///
+    /// indices outside of the atlas are skipped
+    pub fn frame(mut self, atlas: &TextureAtlas, index: usize, duration: f32) -> Self {
+        match atlas.region(index) {
+            Some(region) => self.frames.push(SpriteFrame {
+                atlas_index: index,
+                region,
+                duration,
+            }),
+            None => log::warn!("texture atlas has no region {index}"),
+        }
+        self
+    }
+
+    /// add multiple regions of the atlas as frames that are each shown for `duration` seconds
+    pub fn frames(
+        mut self,
+        atlas: &TextureAtlas,
+        indices: impl IntoIterator<Item = usize>,
+        duration: f32,
+    ) -> Self {
+        for index in indices {
+            self = self.frame(atlas, index, duration);
+        }
+        self
+    }
+
+    /// start over after the last frame. Default: `true`
+    pub fn looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// multiplier for how fast the animation plays. Default: `1.0`
+    pub fn speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// start paused instead of playing right away
+    pub fn paused(mut self) -> Self {
+        self.playing = false;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use super::*;
+
+    fn animation(durations: &[f32], looping: bool) -> SpriteAnimation {
+        let mut animation = SpriteAnimation::builder().looping(looping).build();
+        animation.frames = durations
+            .iter()
+            .enumerate()
+            .map(|(index, duration)| SpriteFrame {
+                atlas_index: index,
+                region: AtlasRegion {
+                    min: Vec2::ZERO,
+                    max: Vec2::ONE,
+                },
+                duration: *duration,
+            })
+            .collect();
+        animation
+    }
+
+    #[test]
+    fn test_per_frame_durations() {
+        let mut animation = animation(&[0.1, 0.3, 0.1], true);
+
+        assert_eq!(animation.advance(0.05).frame_changed, None);
+        assert_eq!(animation.advance(0.1).frame_changed, Some(1));
+        assert_eq!(animation.advance(0.2).frame_changed, None);
+        assert_eq!(animation.advance(0.1).frame_changed, Some(2));
+        // skips over frames when dt is large and wraps around
+        assert_eq!(animation.advance(0.3).frame_changed, Some(1));
+    }
+
+    #[test]
+    fn test_finishes_without_looping() {
+        let mut animation = animation(&[0.1, 0.1], false);
+
+        let step = animation.advance(1.0);
+        assert_eq!(step.frame_changed, Some(1));
+        assert!(step.finished);
+        assert!(animation.is_finished());
+        assert_eq!(animation.advance(1.0), SpriteAnimationStep::default());
+
+        animation.play();
+        assert_eq!(animation.current_frame(), 0);
+        assert!(animation.is_playing());
+    }
+}
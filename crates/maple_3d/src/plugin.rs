@@ -1,21 +1,63 @@
 use maple_app::Plugin;
+use maple_engine::{color::Color, prelude::Frame};
+use maple_renderer::{
+    render_graph::{description::GraphDescription, memory::GpuMemoryStats},
+    types::render_config::RenderPath,
+};
 
 use crate::{
+    animation::update_skins,
     assets::{
-        material::{MaterialLoader, MaterialPipelineCache},
-        mesh::Mesh3DLoader,
+        material::{Material, MaterialLoader, MaterialPipelineCache},
+        materials::{self, PbrMaterial},
+        mesh::{Mesh3D, Mesh3DLoader},
+        primitives::Cuboid,
     },
     gltf::GltfSceneLoader,
+    model::ModelLoader,
+    nodes::{
+        animation_player::update_animations,
+        instanced_model::update_instanced_bounds,
+        mesh_instance::update_mesh_bounds,
+        rts_camera::{RtsCursorConfinement, update_rts_cameras},
+        sprite_animation::update_sprite_animations,
+    },
     render_passes::{
-        bloom::BloomPass, collect_mesh::CollectMesh, composite_pass::CompositePass,
-        directional_shadow_pass::DirectionalShadowPass, environment::EnvironmentPrePass,
-        main_pass::MainPass, point_shadow_pass::PointShadowPass, scene_textures::SceneTextures,
-        shadow_resource::ShadowResource, skybox::SkyboxRender,
+        bloom::BloomPass,
+        collect_mesh::CollectMesh,
+        composite_pass::CompositePass,
+        deferred::{DeferredLightingPass, GBufferPass},
+        directional_shadow_pass::DirectionalShadowPass,
+        environment::EnvironmentPrePass,
+        main_pass::MainPass,
+        point_shadow_pass::PointShadowPass,
+        scene_textures::SceneTextures,
+        shadow_lod::ShadowLod,
+        shadow_resource::ShadowResource,
+        skybox::SkyboxRender,
+        spot_shadow_pass::SpotShadowPass,
+        transparency::TransparencySort,
     },
+    tilemap::TiledMapLoader,
+    visibility::{OnScreen, update_on_screen},
 };
 
 pub struct Core3D;
 
+/// the render graph [`Core3D`] builds when the app has no
+/// [`maple_renderer::render_graph::description`] of its own, a starting point for variants
+pub fn default_graph() -> GraphDescription {
+    GraphDescription::from_json(include_str!("render_passes/default_graph.json"))
+        .expect("the default render graph to parse")
+}
+
+/// the graph [`Core3D`] builds instead of [`default_graph`] for [`RenderPath::Deferred`], see
+/// [`crate::render_passes::deferred`]
+pub fn deferred_graph() -> GraphDescription {
+    GraphDescription::from_json(include_str!("render_passes/deferred_graph.json"))
+        .expect("the deferred render graph to parse")
+}
+
 impl Plugin for Core3D {
     fn setup(&self, app: &mut maple_app::App<maple_app::Init>) {
         // assets
@@ -28,41 +70,162 @@ impl Plugin for Core3D {
         app.context_mut()
             .assets
             .register_loader(MaterialLoader::new(device.clone()));
+        app.context_mut()
+            .assets
+            .register_loader(TiledMapLoader::new(device.clone()));
+        app.context_mut()
+            .assets
+            .register_loader(ModelLoader::new(device.clone()));
         app.context_mut()
             .assets
             .register_loader(GltfSceneLoader::new(device, queue, mipmap_generator));
 
+        // placeholders for meshes and materials that failed to load, a flat magenta unit cube
+        // stands out without breaking the rest of the scene
+        let assets = &app.context().assets;
+        let fallbacks = [
+            assets.set_fallback::<Mesh3D>(Cuboid::default()),
+            assets.set_fallback::<Material>(PbrMaterial {
+                base_color_factor: Color::BLACK,
+                emissive_factor: Color::MAGENTA,
+                double_sided: true,
+                ..Default::default()
+            }),
+        ];
+        for err in fallbacks.into_iter().filter_map(Result::err) {
+            log::warn!("failed to set a placeholder asset: {err}");
+        }
+
+        if app.context().has_resource::<GpuMemoryStats>() {
+            app.context()
+                .get_resource_mut::<GpuMemoryStats>()
+                .track::<Mesh3D>(Mesh3D::gpu_memory);
+        }
+
+        materials::register_shader_includes();
+
+        // render nodes graph descriptions can use
+        let mut graph = app.renderer_mut().graph();
+        graph.register_node::<EnvironmentPrePass>();
+        graph.register_node::<SceneTextures>();
+        graph.register_node::<CollectMesh>();
+        graph.register_node::<ShadowResource>();
+        graph.register_node::<DirectionalShadowPass>();
+        graph.register_node::<PointShadowPass>();
+        graph.register_node::<SpotShadowPass>();
+        graph.register_node::<SkyboxRender>();
+        graph.register_node::<MainPass>();
+        graph.register_node::<CompositePass>();
+        graph.register_node::<BloomPass>();
+        graph.register_node::<GBufferPass>();
+        graph.register_node::<DeferredLightingPass>();
+
         // resources
         app.context_mut()
             .insert_resource(MaterialPipelineCache::default());
+        app.context_mut().insert_resource(OnScreen::default());
+        app.context_mut().insert_resource(ShadowLod::default());
+        app.context_mut()
+            .insert_resource(RtsCursorConfinement::default());
+        app.context_mut()
+            .insert_resource(TransparencySort::default());
     }
 
     fn ready(&self, app: &mut maple_app::App<maple_app::Running>) {
+        let description = app.renderer().graph_description().cloned();
+        let fallback = match app.config().render_path {
+            RenderPath::Forward => default_graph(),
+            RenderPath::Deferred => deferred_graph(),
+        };
         let mut graph = app.renderer_mut().graph();
 
-        graph.setup_and_add_node::<EnvironmentPrePass>();
-        graph.setup_and_add_node::<SceneTextures>();
-        graph.setup_and_add_node::<CollectMesh>();
-        graph.setup_and_add_node::<ShadowResource>();
-        graph.setup_and_add_node::<DirectionalShadowPass>();
-        graph.setup_and_add_node::<PointShadowPass>();
-        graph.setup_and_add_node::<SkyboxRender>();
-        graph.setup_and_add_node::<MainPass>();
-        graph.setup_and_add_node::<CompositePass>();
-        graph.setup_and_add_node::<BloomPass>();
-
-        graph.add_edge::<CollectMesh, DirectionalShadowPass>();
-        graph.add_edge::<CollectMesh, PointShadowPass>();
-        graph.add_edge::<CollectMesh, MainPass>();
-        graph.add_edge::<EnvironmentPrePass, SkyboxRender>();
-        graph.add_edge::<SceneTextures, SkyboxRender>();
-        graph.add_edge::<ShadowResource, DirectionalShadowPass>();
-        graph.add_edge::<ShadowResource, PointShadowPass>();
-        graph.add_edge::<DirectionalShadowPass, MainPass>();
-        graph.add_edge::<PointShadowPass, MainPass>();
-        graph.add_edge::<SkyboxRender, MainPass>();
-        graph.add_edge::<MainPass, BloomPass>();
-        graph.add_edge::<BloomPass, CompositePass>();
-        graph.add_edge::<MainPass, CompositePass>();
+        // descriptions from the config can leave nodes out or use nodes of other plugins
+        if let Some(description) = description {
+            match graph.build(&description) {
+                Ok(()) => return,
+                Err(e) => log::error!("falling back to the default render graph: {e:#}"),
+            }
+        }
+        graph
+            .build(&fallback)
+            .expect("the default render graphs to only use core nodes");
+    }
+
+    fn update(&self, app: &mut maple_app::App<maple_app::Running>) {
+        let dt = app.context().get_resource::<Frame>().time_delta_f32;
+        update_sprite_animations(app.context(), dt);
+        update_animations(app.context(), dt);
+        update_skins(app.context());
+        update_rts_cameras(app.context(), dt);
+        update_mesh_bounds(app.context());
+        crate::lod::update_lods(app.context());
+        update_instanced_bounds(app.context());
+        update_on_screen(app.context());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use maple_renderer::render_graph::node::RenderNode;
+
+    use super::*;
+
+    #[test]
+    fn test_default_graph_only_uses_core_nodes() {
+        let core = [
+            EnvironmentPrePass::label(),
+            SceneTextures::label(),
+            CollectMesh::label(),
+            ShadowResource::label(),
+            DirectionalShadowPass::label(),
+            PointShadowPass::label(),
+            SpotShadowPass::label(),
+            SkyboxRender::label(),
+            MainPass::label(),
+            CompositePass::label(),
+            BloomPass::label(),
+        ];
+        let deferred = [GBufferPass::label(), DeferredLightingPass::label()];
+
+        let graph = default_graph();
+        assert_eq!(graph.nodes.len(), core.len());
+        for node in &graph.nodes {
+            assert!(
+                core.contains(&node.name.as_str()),
+                "{} isn't core",
+                node.name
+            );
+        }
+        for edge in &graph.edges {
+            assert!(edge.iter().all(|name| core.contains(&name.as_str())));
+        }
+
+        // the deferred graph lights the scene before the main pass draws what is left
+        let deferred_graph = deferred_graph();
+        assert_eq!(deferred_graph.nodes.len(), core.len() + deferred.len());
+        for node in &deferred_graph.nodes {
+            let name = node.name.as_str();
+            assert!(
+                core.contains(&name) || deferred.contains(&name),
+                "{name} isn't core"
+            );
+        }
+        assert!(
+            deferred_graph
+                .edges
+                .contains(&["Deferred Lighting".to_string(), "Main".to_string()])
+        );
+
+        // parameters survive a round trip through json
+        let bloom = graph
+            .nodes
+            .iter()
+            .find(|node| node.name == "Bloom")
+            .unwrap();
+        assert_eq!(bloom.parameters["mips"], 5);
+        assert_eq!(
+            GraphDescription::from_json(&graph.to_json()).unwrap(),
+            graph
+        );
     }
 }
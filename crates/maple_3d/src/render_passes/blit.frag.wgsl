@@ -1,39 +1,138 @@
+#include "maple/tonemap.wgsl"
+
 @group(0) @binding(0) var scene_texture: texture_2d<f32>;
 @group(0) @binding(1) var bloom_texture: texture_2d<f32>;
 @group(0) @binding(2) var tex_sampler: sampler;
+// the last frame of the old scene during a transition
+@group(0) @binding(4) var old_scene_texture: texture_2d<f32>;
 
 struct Uniforms {
     bloom_intensity: f32,
     exposure: f32,
-    _padding: vec2<f32>,
+    // the surface is linear so the image has to be gamma encoded here
+    encode_srgb: u32,
+    tonemapping: u32,
+    fxaa: u32,
+    vignette: f32,
+    // 0 none, 1 fade, 2 crossfade, 3 wipe
+    transition: u32,
+    transition_progress: f32,
+    fade_color: vec4<f32>,
+    wipe_direction: vec2<f32>,
+    wipe_softness: f32,
+    _padding: f32,
 }
 
 @group(0) @binding(3) var<uniform> uniforms: Uniforms;
 
-// ACES fitted curve (Krzysztof Narkowicz approximation)
-fn aces_tonemap(x: vec3<f32>) -> vec3<f32> {
-    let a = 2.51;
-    let b = 0.03;
-    let c = 2.43;
-    let d = 0.59;
-    let e = 0.14;
-    return saturate((x * (a * x + b)) / (x * (c * x + d) + e));
+// edges with less contrast than this are left alone
+const FXAA_EDGE_THRESHOLD: f32 = 0.125;
+const FXAA_EDGE_THRESHOLD_MIN: f32 = 0.0312;
+const FXAA_REDUCE_MUL: f32 = 0.125;
+const FXAA_REDUCE_MIN: f32 = 0.0078125;
+// the furthest along an edge that is blended in texels
+const FXAA_SPAN_MAX: f32 = 8.0;
+
+// how much of the new scene shows at a pixel during a crossfade or wipe
+fn new_scene_amount(uv: vec2<f32>) -> f32 {
+    let progress = uniforms.transition_progress;
+    if uniforms.transition == 2u {
+        return progress;
+    }
+    if uniforms.transition == 3u {
+        let direction = uniforms.wipe_direction;
+        let extent = max(abs(direction.x) + abs(direction.y), 0.0001) * 0.5;
+        // 0 where the edge starts and 1 where it ends
+        let along = dot(uv - 0.5, direction) / extent * 0.5 + 0.5;
+        let soft = uniforms.wipe_softness;
+        let edge = progress * (1.0 + soft);
+        return 1.0 - smoothstep(edge - soft, edge, along);
+    }
+    return 1.0;
+}
+
+// the scene with bloom, exposed and tonemapped
+fn ldr_at(uv: vec2<f32>) -> vec3<f32> {
+    // sampled at the top level since fxaa samples after branching on the image
+    var scene = textureSampleLevel(scene_texture, tex_sampler, uv, 0.0).rgb;
+    var bloom = textureSampleLevel(bloom_texture, tex_sampler, uv, 0.0).rgb;
+    let old_scene = textureSampleLevel(old_scene_texture, tex_sampler, uv, 0.0).rgb;
+
+    let amount = new_scene_amount(uv);
+    scene = mix(old_scene, scene, amount);
+    bloom = bloom * amount;
+
+    let hdr = (scene + bloom * uniforms.bloom_intensity) * uniforms.exposure;
+    if uniforms.tonemapping == 1u {
+        return aces_tonemap(hdr);
+    }
+    return clamp(hdr, vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+fn luma(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.299, 0.587, 0.114));
+}
+
+// blends along edges found from the contrast of the corners around a pixel
+fn fxaa(uv: vec2<f32>, texel: vec2<f32>) -> vec3<f32> {
+    let color = ldr_at(uv);
+    let luma_m = luma(color);
+    let luma_nw = luma(ldr_at(uv + vec2<f32>(-1.0, -1.0) * texel));
+    let luma_ne = luma(ldr_at(uv + vec2<f32>(1.0, -1.0) * texel));
+    let luma_sw = luma(ldr_at(uv + vec2<f32>(-1.0, 1.0) * texel));
+    let luma_se = luma(ldr_at(uv + vec2<f32>(1.0, 1.0) * texel));
+
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+    if luma_max - luma_min < max(FXAA_EDGE_THRESHOLD_MIN, luma_max * FXAA_EDGE_THRESHOLD) {
+        return color;
+    }
+
+    // the direction along the edge
+    var dir = vec2<f32>(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+    let dir_reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * FXAA_REDUCE_MUL, FXAA_REDUCE_MIN);
+    let dir_scale = 1.0 / (min(abs(dir.x), abs(dir.y)) + dir_reduce);
+    dir = clamp(dir * dir_scale, vec2<f32>(-FXAA_SPAN_MAX), vec2<f32>(FXAA_SPAN_MAX)) * texel;
+
+    let near = 0.5 * (ldr_at(uv + dir * (1.0 / 3.0 - 0.5)) + ldr_at(uv + dir * (2.0 / 3.0 - 0.5)));
+    let far = near * 0.5 + 0.25 * (ldr_at(uv - dir * 0.5) + ldr_at(uv + dir * 0.5));
+    // the far taps crossed another edge
+    let luma_far = luma(far);
+    if luma_far < luma_min || luma_far > luma_max {
+        return near;
+    }
+    return far;
 }
 
 @fragment
 fn main(@location(0) tex_coord: vec2<f32>) -> @location(0) vec4<f32> {
-    let scene = textureSample(scene_texture, tex_sampler, tex_coord).rgb;
-    let bloom = textureSample(bloom_texture, tex_sampler, tex_coord).rgb;
-
-    var hdr = scene + bloom * uniforms.bloom_intensity;
+    var ldr: vec3<f32>;
+    if uniforms.fxaa == 1u {
+        let texel = 1.0 / vec2<f32>(textureDimensions(scene_texture));
+        ldr = fxaa(tex_coord, texel);
+    } else {
+        ldr = ldr_at(tex_coord);
+    }
 
-    // Apply exposure before tonemapping
-    hdr = hdr * uniforms.exposure;
+    if uniforms.vignette > 0.0 {
+        // 0 at the center and 1 at the corners
+        let corner = length(tex_coord - 0.5) * 1.41421356;
+        ldr = ldr * (1.0 - uniforms.vignette * smoothstep(0.4, 1.0, corner));
+    }
 
-    let ldr = aces_tonemap(hdr);
+    if uniforms.transition == 1u {
+        // covered halfway through, when the scenes are swapped
+        let covered = 1.0 - abs(uniforms.transition_progress * 2.0 - 1.0);
+        ldr = mix(ldr, uniforms.fade_color.rgb, covered);
+    }
 
-    // I assume that most output is to an sRGB surface so I dont have gamma correction. 
-    // it might be nice to conditionally gamma correct based on surface in the future.
+    // srgb surfaces encode the linear color when it is written
+    if uniforms.encode_srgb == 1u {
+        ldr = linear_to_srgb(ldr);
+    }
 
     return vec4<f32>(ldr, 1.0);
 }
@@ -19,6 +19,7 @@ use maple_renderer::{
     types::Dimensions,
 };
 
+/// the mips blurred when the graph description doesn't set `mips`
 const MIP_LEVELS: u32 = 5;
 const WORKGROUP_SIZE: u32 = 8;
 
@@ -48,6 +49,8 @@ pub struct BloomPass {
 
     sampler: Sampler,
 
+    /// more mips spread the glow further, from the `mips` parameter
+    mip_levels: u32,
     mip_chain: Vec<Texture>,
     downsample_uniforms: Vec<Buffer<DownsampleUniforms>>,
     upsample_uniform: Buffer<UpsampleUniforms>,
@@ -61,7 +64,7 @@ impl BloomPass {
         let mut w = width;
         let mut h = height;
 
-        for _ in 0..MIP_LEVELS {
+        for _ in 0..self.mip_levels {
             let texture = rcx.device().create_texture(TextureCreateInfo {
                 label: Some("Bloom_mip"),
                 width: w,
@@ -99,7 +102,7 @@ impl RenderNode for BloomPass {
         Stage::PostProcess
     }
 
-    fn setup(rcx: &RenderContext, _: &mut RenderGraphContext) -> Self {
+    fn setup(rcx: &RenderContext, graph_ctx: &mut RenderGraphContext) -> Self {
         let bright_shader =
             rcx.device()
                 .create_compute_shader(maple_renderer::core::ComputeShaderSource::Wgsl(
@@ -227,6 +230,10 @@ impl RenderNode for BloomPass {
             bright_pipeline,
             bright_layout,
             sampler,
+            mip_levels: graph_ctx
+                .parameter(Self::label(), "mips")
+                .unwrap_or(MIP_LEVELS)
+                .clamp(3, 8),
             mip_chain: Vec::new(),
             downsample_uniforms: Vec::new(),
             upsample_uniform,
@@ -246,8 +253,14 @@ impl RenderNode for BloomPass {
             return;
         };
 
+        // the composite pass ignores the bloom texture while bloom is off
+        if !rcx.post_effects().bloom {
+            return;
+        }
+
         if self.mip_chain.len() <= 2 {
-            self.create_mip_chain(rcx, rcx.surface_size().width, rcx.surface_size().height);
+            let size = rcx.render_size();
+            self.create_mip_chain(rcx, size.width, size.height);
         }
 
         // bright pass
@@ -311,6 +324,7 @@ impl RenderNode for BloomPass {
                         depth_target: None,
                         clear_color, // DON'T clear - additive blend onto existing downsample data
                         clear_depth: None,
+                        discard_depth: false,
                     },
                     |mut fb| {
                         fb.use_pipeline(&self.upsample_pipeline)
@@ -324,7 +338,17 @@ impl RenderNode for BloomPass {
         graph_ctx.add_shared_resource("bloom_texture", self.mip_chain[0].clone());
     }
 
-    fn resize(&mut self, rcx: &RenderContext, dimensions: Dimensions) {
-        self.create_mip_chain(rcx, dimensions.width, dimensions.height);
+    fn resize(&mut self, rcx: &RenderContext, _dimensions: Dimensions) {
+        // the bright pass reads the scene texture texel for texel
+        let size = rcx.render_size();
+        self.create_mip_chain(rcx, size.width, size.height);
+    }
+
+    fn debug_targets(&self) -> Vec<(String, Texture)> {
+        self.mip_chain
+            .iter()
+            .enumerate()
+            .map(|(i, texture)| (format!("mip_{i}"), texture.clone()))
+            .collect()
     }
 }
@@ -1,29 +1,45 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use bytemuck::{Pod, Zeroable};
-use maple_engine::{asset::AssetId, scene::NodeId};
+use maple_engine::{
+    GameContext,
+    asset::{AssetHandle, AssetId},
+    scene::NodeId,
+};
 use maple_renderer::{
     core::{
-        Buffer, CullMode, DescriptorBindingType, DescriptorSet, DescriptorSetLayout,
-        DescriptorSetLayoutDescriptor, RenderPipeline, StageFlags, texture::SamplerOptions,
+        Buffer, ComputePipeline, ComputePipelineCreateInfo, ComputeShaderSource, CullMode,
+        DescriptorBindingType, DescriptorSet, DescriptorSetLayout, DescriptorSetLayoutDescriptor,
+        RenderContext, RenderPipeline, StageFlags, texture::SamplerOptions,
     },
     render_graph::{
         graph::{GraphResource, Stage},
         node::RenderNode,
+        stats::RenderStats,
     },
+    shader_asset::ShaderSource,
 };
 
 use crate::{
     assets::{
-        material::{MaterialAlphaInfo, MaterialPipelineCache},
+        material::{Material, MaterialAlphaInfo, MaterialPass, MaterialPipelineCache, PassInfo},
         mesh::Mesh3D,
     },
-    math::AABB,
-    nodes::mesh_instance::{Mesh3DUniformBufferData, MeshInstance3D},
+    math::{AABB, Frustum},
+    nodes::{
+        instanced_model::InstancedModel,
+        mesh_instance::{Mesh3DUniformBufferData, MeshInstance3D},
+    },
     prelude::AlphaMode,
-    render_passes::{main_pass::MainPass, shadow_resource::ShadowResource},
+    render_passes::{deferred::GBuffer, main_pass::MainPass, shadow_resource::ShadowResource},
 };
 
+/// the most joint matrices of all skinned meshes drawn in a frame
+pub const MAX_JOINTS: usize = 4096;
+
+/// the label g-buffer pipelines are cached under in [`MaterialPipelineCache`]
+const GBUFFER_PASS: &str = "g-buffer";
+
 #[repr(C)]
 #[derive(Default, Debug, Pod, Zeroable, Clone, Copy)]
 pub(crate) struct AlphaInfoGpu {
@@ -35,22 +51,99 @@ pub(crate) struct AlphaInfoGpu {
 
 #[derive(Clone)]
 pub(crate) struct MeshBundle {
+    /// the mesh instance the bundle was made from
+    pub node: NodeId,
+    /// which copy of an [`InstancedModel`] the bundle draws, 0 for mesh instances
+    pub instance: u32,
     pub mesh: Mesh3D,
     pub mesh_id: AssetId,
     pub material_id: AssetId,
     pub material_descriptor: DescriptorSet,
     pub shadow_descriptors: DescriptorSet,
     pub pipeline: RenderPipeline,
+    /// the pipeline writing the bundle into the g-buffer instead, `None` draws it forward
+    pub gbuffer_pipeline: Option<RenderPipeline>,
     pub buffer_data: Mesh3DUniformBufferData,
     pub alpha_mode: AlphaMode,
     pub cull_mode: CullMode,
     pub world_aabb: AABB,
+    /// if false the bundle is drawn even when its bounding box is outside the frustum
+    pub frustum_culled: bool,
     pub cast_shadow: bool,
+    /// the vertices are skinned or morphed so what the mesh looks like can change every frame
+    pub deformed: bool,
+    /// the position of the draw among all draws of the material's passes, bundles are drawn in
+    /// this order
+    pub draw_order: u32,
+}
+
+impl MeshBundle {
+    /// true if the bundle can be seen from inside the frustum
+    pub(crate) fn in_frustum(&self, frustum: &Frustum) -> bool {
+        !self.frustum_culled || frustum.intersects_aabb(&self.world_aabb)
+    }
+}
+
+/// a copy of a mesh blended by the morph weights of one instance
+struct MorphedMesh {
+    source: AssetId,
+    /// the weights the vertex buffer was last blended with
+    weights: Vec<f32>,
+    mesh: Mesh3D,
+    /// each copy is batched on its own
+    id: AssetId,
+}
+
+impl MorphedMesh {
+    fn new(rcx: &RenderContext, source: &Mesh3D, source_id: &AssetId, vertices: usize) -> Self {
+        let vertex_buffer = rcx.device().create_sized_vertex_buffer(vertices);
+        Self {
+            source: source_id.clone(),
+            weights: Vec::new(),
+            mesh: Mesh3D::from_buffers(
+                vertex_buffer,
+                source.get_index_buffer().clone(),
+                *source.aabb(),
+            ),
+            id: AssetId::new_id(),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Default, Debug, Pod, Zeroable, Clone, Copy)]
+struct SkinParams {
+    joint_offset: u32,
+    joint_count: u32,
+    vertex_count: u32,
+    _padding: u32,
+}
+
+/// a copy of a mesh that a compute shader skins with the joints of one instance every frame
+struct SkinnedMesh {
+    /// the mesh the copy is skinned from, a [`MorphedMesh`] for morphed instances
+    source: AssetId,
+    mesh: Mesh3D,
+    params: Buffer<SkinParams>,
+    descriptor: DescriptorSet,
+    /// each copy is batched on its own
+    id: AssetId,
 }
 
 pub struct CollectMesh {
-    mesh_cache: HashMap<NodeId, MeshBundle>,
+    /// a bundle for each draw of each mesh instance
+    mesh_cache: HashMap<NodeId, Vec<MeshBundle>>,
+    morphed: HashMap<NodeId, MorphedMesh>,
+    skinned: HashMap<NodeId, SkinnedMesh>,
+    skin_pipeline: ComputePipeline,
+    skin_layout: DescriptorSetLayout,
     shadow_descriptors: HashMap<AssetId, (Buffer<AlphaInfoGpu>, DescriptorSet)>,
+    /// [`maple_engine::asset::AssetLibrary::reloads`] when the caches were made
+    reloads: u64,
+    /// [`maple_engine::asset::AssetLibrary::collected`] when the descriptors were made, they keep
+    /// the textures of freed materials alive
+    collected: u64,
+    joint_buffer: Buffer<[[[f32; 4]; 4]]>,
     mesh_layout: DescriptorSetLayout,
     scene_layout: DescriptorSetLayout,
     light_layout: DescriptorSetLayout,
@@ -64,6 +157,363 @@ pub(crate) struct BundledMeshes {
 
 impl GraphResource for BundledMeshes {}
 
+impl CollectMesh {
+    /// add a draw call of every batch to the [`RenderStats`] of the nodes drawn by it
+    ///
+    /// does nothing if the app has no [`RenderStats`]
+    pub(crate) fn record_draws<'a>(
+        game_ctx: &GameContext,
+        pass: &str,
+        batches: impl IntoIterator<Item = (&'a Mesh3D, &'a [NodeId])>,
+    ) {
+        if !game_ctx.has_resource::<RenderStats>() {
+            return;
+        }
+
+        let mut stats = game_ctx.get_resource_mut::<RenderStats>();
+        for (mesh, nodes) in batches {
+            let triangles = mesh.get_index_buffer().len() as u64 / 3;
+            for node in nodes {
+                stats.record(pass, *node, 1, triangles);
+            }
+        }
+    }
+
+    /// the layout of the per mesh data every pass drawing meshes binds at group 1
+    pub(crate) fn mesh_layout(rcx: &RenderContext) -> DescriptorSetLayout {
+        rcx.get_or_create_layout(DescriptorSetLayoutDescriptor {
+            label: Some("Mesh"),
+            visibility: StageFlags::VERTEX,
+            layout: &[
+                DescriptorBindingType::Storage {
+                    read_only: true,
+                    has_dynamic_offset: false,
+                    min_size: None,
+                }, // transforms
+                DescriptorBindingType::Storage {
+                    read_only: true,
+                    has_dynamic_offset: false,
+                    min_size: None,
+                }, // joint matrices
+            ],
+        })
+    }
+
+    /// the buffer the joint matrices of skinned meshes are written to, made in setup
+    pub(crate) fn joint_buffer(
+        graph_ctx: &maple_renderer::render_graph::graph::RenderGraphContext,
+    ) -> Buffer<[[[f32; 4]; 4]]> {
+        graph_ctx
+            .get_shared_resource::<Buffer<[[[f32; 4]; 4]]>>("joint_matrices")
+            .cloned()
+            .expect("collect meshes is set up before the passes drawing meshes")
+    }
+
+    /// compile a pass of a material into a pipeline drawing into targets of `pass_info`
+    fn material_pipeline(
+        &self,
+        rcx: &RenderContext,
+        material: &Material,
+        pass: &MaterialPass,
+        fragment_shader: ShaderSource,
+        pass_info: &PassInfo,
+    ) -> RenderPipeline {
+        let shader = maple_renderer::core::GraphicsShader {
+            vertex: rcx
+                .device()
+                .compile_shader(pass.vertex_shader.unwrap_or(material.vertex_shader()))
+                .expect("material vertex shader compile"),
+            fragment: rcx
+                .device()
+                .compile_shader(fragment_shader)
+                .expect("material fragment shader compile"),
+        };
+        let pipeline_layout = rcx.device().create_render_pipeline_layout(&[
+            self.scene_layout.clone(),
+            self.mesh_layout.clone(),
+            self.light_layout.clone(),
+            material.layout(rcx),
+        ]);
+        material.pipeline(rcx, pass_info, pipeline_layout, shader, pass)
+    }
+
+    /// the joint matrices of a skinned mesh added to `joints`, returns the offset and count for
+    /// [`Mesh3DUniformBufferData`]
+    ///
+    /// meshes whose joints can't be found or don't fit are drawn without skinning
+    fn push_joints(
+        game_ctx: &GameContext,
+        mesh: NodeId,
+        joints: &mut Vec<[[f32; 4]; 4]>,
+    ) -> (u32, u32) {
+        // the mesh is read once and not locked while the joints are read
+        let Some((skin, model)) = game_ctx.scene.get::<MeshInstance3D>(mesh).and_then(|mesh| {
+            let mesh = mesh.read();
+            Some((mesh.skin.clone()?, *mesh.transform.render_space().matrix()))
+        }) else {
+            return (0, 0);
+        };
+        let Some(matrices) = skin.joint_matrices(&game_ctx.scene, &model) else {
+            return (0, 0);
+        };
+        if joints.len() + matrices.len() > MAX_JOINTS {
+            log::warn!("more than {MAX_JOINTS} joints are skinned this frame");
+            return (0, 0);
+        }
+
+        let offset = joints.len() as u32;
+        joints.extend(matrices.iter().map(|matrix| matrix.to_cols_array_2d()));
+        (offset, matrices.len() as u32)
+    }
+
+    /// a bundle for each draw of the passes of `material` drawing `mesh`, `None` if the material
+    /// or its textures haven't loaded yet
+    ///
+    /// the bundles have no transform or bounding box yet
+    fn material_bundles(
+        &mut self,
+        rcx: &RenderContext,
+        game_ctx: &GameContext,
+        (material_cache, deferred): (&mut MaterialPipelineCache, bool),
+        node: NodeId,
+        (mesh, mesh_id): (&Mesh3D, &AssetId),
+        material: &AssetHandle<Material>,
+    ) -> Option<Vec<MeshBundle>> {
+        let material_instance = game_ctx.assets.get(material)?;
+        let material_descriptor = material_instance.descriptor_set(rcx, &game_ctx.assets)?;
+
+        let cast_shadow = material_instance.casts_shadows();
+        let type_id = material_instance.material_key();
+        let passes = material_instance.passes();
+
+        let pipelines: Vec<RenderPipeline> = passes
+            .iter()
+            .map(|pass| {
+                let pipeline_key = material_instance.pipeline_key(pass);
+                material_cache
+                    .pipelines
+                    .entry(type_id)
+                    .or_default()
+                    .entry((pass.label, pipeline_key))
+                    .or_insert_with(|| {
+                        self.material_pipeline(
+                            rcx,
+                            &material_instance,
+                            pass,
+                            pass.fragment_shader
+                                .unwrap_or(material_instance.fragment_shader()),
+                            &MainPass::pass_info(rcx),
+                        )
+                    })
+                    .clone()
+            })
+            .collect();
+
+        // materials drawn once without blending are lit by the deferred lighting pass
+        let gbuffer_pipeline = match (material_instance.gbuffer_shader(), &passes[..]) {
+            (Some(fragment_shader), [pass])
+                if deferred
+                    && pass.repeat == 1
+                    && pass.fragment_shader.is_none()
+                    && material_instance.pass_alpha_mode(pass) != AlphaMode::Blend =>
+            {
+                let pipeline_key = material_instance.pipeline_key(pass);
+                let pipeline = material_cache
+                    .pipelines
+                    .entry(type_id)
+                    .or_default()
+                    .entry((GBUFFER_PASS, pipeline_key))
+                    .or_insert_with(|| {
+                        self.material_pipeline(
+                            rcx,
+                            &material_instance,
+                            pass,
+                            fragment_shader,
+                            &GBuffer::pass_info(),
+                        )
+                    });
+                Some(pipeline.clone())
+            }
+            _ => None,
+        };
+
+        material_instance.update_buffer(rcx);
+
+        let alpha_info = material_instance
+            .alpha_info()
+            .unwrap_or_else(|| MaterialAlphaInfo {
+                alpha_texture: None,
+                base_alpha_factor: 1.0,
+                alpha_cutoff: 0.5,
+            });
+
+        let alpha_info_gpu = AlphaInfoGpu {
+            alpha_mode: material_instance.alpha_mode().into(),
+            base_alpha_factor: alpha_info.base_alpha_factor,
+            alpha_cutoff: alpha_info.alpha_cutoff,
+            _padding: Zeroable::zeroed(),
+        };
+
+        let default_alpha_texture = &rcx.get_default_texture().white;
+
+        let alpha_texture = match &alpha_info.alpha_texture {
+            // shadow mask texture not loaded yet, skip this frame
+            Some(handle) => game_ctx.assets.get(handle)?.clone(),
+            None => default_alpha_texture.clone(),
+        };
+
+        let (buffer, descriptor) = self
+            .shadow_descriptors
+            .entry(material.id.clone())
+            .or_insert_with(|| {
+                let sampler = rcx.device().create_sampler(SamplerOptions {
+                    mode_u: maple_renderer::core::texture::TextureMode::Repeat,
+                    mode_v: maple_renderer::core::texture::TextureMode::Repeat,
+                    mode_w: maple_renderer::core::texture::TextureMode::Repeat,
+                    mag_filter: maple_renderer::core::texture::FilterMode::Linear,
+                    min_filter: maple_renderer::core::texture::FilterMode::Linear,
+                    compare: None,
+                });
+                let buffer = rcx.device().create_uniform_buffer(&alpha_info_gpu);
+                let descriptor = rcx.device().build_descriptor_set(
+                    &DescriptorSet::builder(&self.shadow_layout)
+                        .uniform(0, &buffer)
+                        .texture_view(1, &alpha_texture.create_view())
+                        .sampler(2, &sampler),
+                );
+                (buffer, descriptor)
+            });
+
+        rcx.queue().write_buffer(buffer, &alpha_info_gpu);
+
+        // each pass expands into one bundle per draw in the order they are declared
+        let mut bundles = Vec::new();
+        for (pass, pipeline) in passes.iter().zip(pipelines) {
+            let alpha_mode = material_instance.pass_alpha_mode(pass);
+            for pass_index in 0..pass.repeat {
+                bundles.push(MeshBundle {
+                    node,
+                    instance: 0,
+                    mesh: mesh.clone(),
+                    mesh_id: mesh_id.clone(),
+                    material_descriptor: material_descriptor.clone(),
+                    shadow_descriptors: descriptor.clone(),
+                    material_id: material.id.clone(),
+                    pipeline: pipeline.clone(),
+                    gbuffer_pipeline: gbuffer_pipeline.clone(),
+                    world_aabb: *mesh.aabb(),
+                    frustum_culled: true,
+                    alpha_mode,
+                    cull_mode: material_instance.pass_cull_mode(pass),
+                    buffer_data: Mesh3DUniformBufferData {
+                        pass_index,
+                        pass_count: pass.repeat,
+                        ..Default::default()
+                    },
+                    cast_shadow: cast_shadow && pass.casts_shadows,
+                    deformed: false,
+                    draw_order: bundles.len() as u32,
+                });
+            }
+        }
+        Some(bundles)
+    }
+
+    /// the mesh to draw for an instance and its batching id, instances with morph weights get
+    /// their own copy that is blended again whenever the weights change
+    fn morph(
+        morphed: &mut HashMap<NodeId, MorphedMesh>,
+        rcx: &RenderContext,
+        node: NodeId,
+        (mesh, mesh_id): (&Mesh3D, &AssetId),
+        weights: Option<Vec<f32>>,
+    ) -> (Mesh3D, AssetId) {
+        let (Some(weights), Some(targets)) = (weights, mesh.morph_targets()) else {
+            morphed.remove(&node);
+            return (mesh.clone(), mesh_id.clone());
+        };
+
+        let vertices = targets.vertex_count();
+        let entry = morphed
+            .entry(node)
+            .or_insert_with(|| MorphedMesh::new(rcx, mesh, mesh_id, vertices));
+        if entry.source != *mesh_id {
+            *entry = MorphedMesh::new(rcx, mesh, mesh_id, vertices);
+        }
+        if entry.weights != weights {
+            rcx.queue()
+                .write_buffer_slice(entry.mesh.get_vertex_buffer(), &targets.blend(&weights));
+            entry.weights = weights;
+        }
+        (entry.mesh.clone(), entry.id.clone())
+    }
+
+    /// the skinned copy of the mesh to draw for an instance and its batching id, the copy is
+    /// skinned by the compute dispatches added to `dispatches`
+    ///
+    /// instances without joints are drawn with their mesh
+    fn skin(
+        skinned_meshes: &mut HashMap<NodeId, SkinnedMesh>,
+        (skin_layout, joint_buffer): (&DescriptorSetLayout, &Buffer<[[[f32; 4]; 4]]>),
+        rcx: &RenderContext,
+        node: NodeId,
+        (mesh, mesh_id): (Mesh3D, AssetId),
+        (joint_offset, joint_count): (u32, u32),
+        dispatches: &mut Vec<(DescriptorSet, u32)>,
+    ) -> (Mesh3D, AssetId) {
+        if joint_count == 0 {
+            skinned_meshes.remove(&node);
+            return (mesh, mesh_id);
+        }
+
+        let vertex_count = mesh.get_vertex_buffer().len();
+        if skinned_meshes
+            .get(&node)
+            .is_none_or(|skinned| skinned.source != mesh_id)
+        {
+            let vertex_buffer = rcx.device().create_sized_vertex_buffer(vertex_count);
+            let params = rcx.device().create_uniform_buffer(&SkinParams::default());
+            let descriptor = rcx.device().build_descriptor_set(
+                DescriptorSet::builder(skin_layout)
+                    .storage(0, mesh.get_vertex_buffer())
+                    .storage(1, joint_buffer)
+                    .uniform(2, &params)
+                    .storage(3, &vertex_buffer),
+            );
+            skinned_meshes.insert(
+                node,
+                SkinnedMesh {
+                    source: mesh_id,
+                    mesh: Mesh3D::from_buffers(
+                        vertex_buffer,
+                        mesh.get_index_buffer().clone(),
+                        *mesh.aabb(),
+                    ),
+                    params,
+                    descriptor,
+                    id: AssetId::new_id(),
+                },
+            );
+        }
+
+        let skinned = &skinned_meshes[&node];
+        rcx.queue().write_buffer(
+            &skinned.params,
+            &SkinParams {
+                joint_offset,
+                joint_count,
+                vertex_count: vertex_count as u32,
+                _padding: 0,
+            },
+        );
+        dispatches.push((
+            skinned.descriptor.clone(),
+            (vertex_count as u32).div_ceil(64),
+        ));
+        (skinned.mesh.clone(), skinned.id.clone())
+    }
+}
+
 impl RenderNode for CollectMesh {
     fn label() -> &'static str
     where
@@ -78,22 +528,14 @@ impl RenderNode for CollectMesh {
 
     fn setup(
         rcx: &maple_renderer::core::RenderContext,
-        _graph_ctx: &mut maple_renderer::render_graph::graph::RenderGraphContext,
+        graph_ctx: &mut maple_renderer::render_graph::graph::RenderGraphContext,
     ) -> Self
     where
         Self: Sized,
     {
-        let mesh_layout = rcx.get_or_create_layout(DescriptorSetLayoutDescriptor {
-            label: Some("Mesh"),
-            visibility: StageFlags::VERTEX,
-            layout: &[
-                DescriptorBindingType::Storage {
-                    read_only: true,
-                    has_dynamic_offset: false,
-                    min_size: None,
-                }, // transforms
-            ],
-        });
+        let mesh_layout = Self::mesh_layout(rcx);
+        let joint_buffer = rcx.device().create_sized_storage_buffer(MAX_JOINTS);
+        graph_ctx.add_shared_resource("joint_matrices", joint_buffer.clone());
         let scene_layout =
             rcx.device()
                 .create_descriptor_set_layout(DescriptorSetLayoutDescriptor {
@@ -112,9 +554,56 @@ impl RenderNode for CollectMesh {
                 });
         let light_layout = ShadowResource::layout(rcx);
         let shadow_layout = ShadowResource::shadow_layout(rcx);
+
+        let skin_layout =
+            rcx.device()
+                .create_descriptor_set_layout(DescriptorSetLayoutDescriptor {
+                    label: Some("skinning layout"),
+                    visibility: StageFlags::COMPUTE,
+                    layout: &[
+                        DescriptorBindingType::Storage {
+                            read_only: true,
+                            has_dynamic_offset: false,
+                            min_size: None,
+                        }, // source vertices
+                        DescriptorBindingType::Storage {
+                            read_only: true,
+                            has_dynamic_offset: false,
+                            min_size: None,
+                        }, // joint matrices
+                        DescriptorBindingType::UniformBuffer,
+                        DescriptorBindingType::Storage {
+                            read_only: false,
+                            has_dynamic_offset: false,
+                            min_size: None,
+                        }, // skinned vertices
+                    ],
+                });
+        let skin_pipeline = rcx
+            .device()
+            .create_compute_pipeline(ComputePipelineCreateInfo {
+                label: Some("skinning"),
+                layout: rcx
+                    .device()
+                    .create_pipeline_layout(std::slice::from_ref(&skin_layout)),
+                shader: rcx
+                    .device()
+                    .create_compute_shader(ComputeShaderSource::Wgsl(include_str!(
+                        "./skinning.wgsl"
+                    ))),
+                entry_point: None,
+            });
+
         Self {
             mesh_cache: HashMap::new(),
+            morphed: HashMap::new(),
+            skinned: HashMap::new(),
+            skin_pipeline,
+            skin_layout,
             shadow_descriptors: HashMap::new(),
+            reloads: 0,
+            collected: 0,
+            joint_buffer,
             mesh_layout,
             scene_layout,
             light_layout,
@@ -125,206 +614,253 @@ impl RenderNode for CollectMesh {
     fn draw(
         &mut self,
         rcx: &maple_renderer::core::RenderContext,
-        _frame: &mut maple_renderer::core::Frame,
+        frame: &mut maple_renderer::core::Frame,
         graph_ctx: &mut maple_renderer::render_graph::graph::RenderGraphContext,
         game_ctx: &maple_engine::GameContext,
     ) {
-        let meshes = game_ctx.scene.collect::<MeshInstance3D>();
+        // the cached descriptors can point at the materials and textures of a reloaded asset
+        let reloads = game_ctx.assets.reloads();
+        if reloads != self.reloads {
+            self.reloads = reloads;
+            self.mesh_cache.clear();
+            self.shadow_descriptors.clear();
+        }
+        let collected = game_ctx.assets.collected();
+        if collected != self.collected {
+            self.collected = collected;
+            self.shadow_descriptors.clear();
+        }
+
+        let meshes = game_ctx.scene.collect_visible::<MeshInstance3D>();
         let mut material_cache = game_ctx.get_resource_mut::<MaterialPipelineCache>();
+        let deferred = graph_ctx
+            .get_shared_resource::<GBuffer>("gbuffer")
+            .is_some();
 
         let mut opaque_bundles: Vec<MeshBundle> = Vec::new();
         let mut transparent_bundles: Vec<MeshBundle> = Vec::new();
+        let mut joints: Vec<[[f32; 4]; 4]> = Vec::new();
+        let mut skin_dispatches: Vec<(DescriptorSet, u32)> = Vec::new();
+        // materials of cached meshes changed at runtime, each is only checked once
+        let mut checked_materials: HashSet<AssetId> = HashSet::new();
 
         for mesh in meshes {
-            if let Some(entry) = self.mesh_cache.get_mut(&mesh.id()) {
-                let mesh_handle = {
+            if let Some(entries) = self.mesh_cache.get_mut(&mesh.id()) {
+                // one read per cached mesh, large scenes have a lot of them
+                let (mesh_handle, material, render_space, frustum_culled, skinned, morph_weights) = {
                     let node = mesh.read();
-                    let Some(mesh) = node.mesh.clone() else {
+                    let Some(mesh) = node.drawn_mesh().cloned() else {
                         continue;
                     };
-                    mesh
+                    (
+                        mesh,
+                        node.material.clone(),
+                        *node.transform.render_space(),
+                        node.frustum_culled,
+                        node.skin.is_some(),
+                        node.is_morphed().then(|| node.morph_weights.clone()),
+                    )
                 };
                 let Some(mesh_instance) = game_ctx.assets.get(&mesh_handle) else {
                     continue;
                 };
-                entry.world_aabb = mesh_instance.world_aabb(*mesh.read().transform.world_space());
-                entry.buffer_data = Mesh3DUniformBufferData {
-                    model: mesh
-                        .read()
-                        .transform
-                        .world_space()
-                        .matrix
-                        .to_cols_array_2d(),
-                    normal_matrix: mesh
-                        .read()
-                        .transform
-                        .world_space()
-                        .matrix
-                        .inverse()
-                        .transpose()
-                        .to_cols_array_2d(),
+                if let Some(material) = material
+                    && checked_materials.insert(material.id.clone())
+                    && let Some(material) = game_ctx.assets.get(&material)
+                {
+                    material.update_changed_buffer(rcx);
+                }
+                let world_aabb = mesh_instance.world_aabb(render_space);
+                let morphed = morph_weights.is_some();
+                let (morphed_mesh, mesh_id) = Self::morph(
+                    &mut self.morphed,
+                    rcx,
+                    mesh.id(),
+                    (&mesh_instance, &mesh_handle.id),
+                    morph_weights,
+                );
+                let joint_range = match skinned {
+                    true => Self::push_joints(game_ctx, mesh.id(), &mut joints),
+                    false => (0, 0),
                 };
+                let (morphed_mesh, mesh_id) = Self::skin(
+                    &mut self.skinned,
+                    (&self.skin_layout, &self.joint_buffer),
+                    rcx,
+                    mesh.id(),
+                    (morphed_mesh, mesh_id),
+                    joint_range,
+                    &mut skin_dispatches,
+                );
+                let model = render_space.matrix().to_cols_array_2d();
+                let normal_matrix = render_space
+                    .matrix()
+                    .inverse()
+                    .transpose()
+                    .to_cols_array_2d();
 
-                match entry.alpha_mode {
-                    AlphaMode::Opaque | AlphaMode::Mask => opaque_bundles.push(entry.clone()),
-                    AlphaMode::Blend => transparent_bundles.push(entry.clone()),
+                for entry in entries {
+                    entry.world_aabb = world_aabb;
+                    entry.frustum_culled = frustum_culled;
+                    entry.mesh = morphed_mesh.clone();
+                    entry.mesh_id = mesh_id.clone();
+                    entry.deformed = morphed || joint_range.1 > 0;
+                    // the vertices are skinned already
+                    entry.buffer_data = Mesh3DUniformBufferData {
+                        model,
+                        normal_matrix,
+                        joint_offset: 0,
+                        joint_count: 0,
+                        ..entry.buffer_data
+                    };
+
+                    match entry.alpha_mode {
+                        AlphaMode::Opaque | AlphaMode::Mask => opaque_bundles.push(entry.clone()),
+                        AlphaMode::Blend => transparent_bundles.push(entry.clone()),
+                    }
                 }
             } else {
-                let (material_id, material_handle, mesh_handle) = {
+                let (material_handle, mesh_handle, morph_weights, render_space, frustum_culled) = {
                     let node = mesh.read();
                     let Some(material) = node.material.clone() else {
                         continue;
                     };
-                    let Some(mesh) = node.mesh.clone() else {
+                    let Some(mesh) = node.drawn_mesh().cloned() else {
                         continue;
                     };
-                    (material.id.clone(), material, mesh)
+                    let morph_weights = node.is_morphed().then(|| node.morph_weights.clone());
+                    (
+                        material,
+                        mesh,
+                        morph_weights,
+                        *node.transform.render_space(),
+                        node.frustum_culled,
+                    )
                 };
                 let Some(mesh_instance) = game_ctx.assets.get(&mesh_handle) else {
                     continue;
                 };
-                let world_aabb = mesh_instance.world_aabb(*mesh.read().transform.world_space());
-                let Some(material_instance) = game_ctx.assets.get(&material_handle) else {
+                let Some(bundles) = self.material_bundles(
+                    rcx,
+                    game_ctx,
+                    (&mut material_cache, deferred),
+                    mesh.id(),
+                    (&mesh_instance, &mesh_handle.id),
+                    &material_handle,
+                ) else {
                     continue;
                 };
 
-                let Some(material_descriptor) =
-                    material_instance.descriptor_set(rcx, &game_ctx.assets)
-                else {
-                    continue;
-                };
-
-                let is_opaque = matches!(
-                    material_instance.alpha_mode(),
-                    AlphaMode::Opaque | AlphaMode::Mask
+                let joint_range = Self::push_joints(game_ctx, mesh.id(), &mut joints);
+                let deformed = morph_weights.is_some() || joint_range.1 > 0;
+                let (morphed_mesh, mesh_id) = Self::morph(
+                    &mut self.morphed,
+                    rcx,
+                    mesh.id(),
+                    (&mesh_instance, &mesh_handle.id),
+                    morph_weights,
+                );
+                // the vertices are skinned before they are drawn so the passes don't skin them
+                let (morphed_mesh, mesh_id) = Self::skin(
+                    &mut self.skinned,
+                    (&self.skin_layout, &self.joint_buffer),
+                    rcx,
+                    mesh.id(),
+                    (morphed_mesh, mesh_id),
+                    joint_range,
+                    &mut skin_dispatches,
                 );
-                let cast_shadow = material_instance.casts_shadows();
-                let type_id = material_instance.material_key();
-                let pipeline_key = material_instance.pipeline_key();
+                let world_aabb = mesh_instance.world_aabb(render_space);
+                let model = render_space.matrix().to_cols_array_2d();
+                let normal_matrix = render_space
+                    .matrix()
+                    .inverse()
+                    .transpose()
+                    .to_cols_array_2d();
 
-                let pipeline = material_cache
-                    .pipelines
-                    .entry(type_id)
-                    .or_default()
-                    .entry(pipeline_key)
-                    .or_insert_with(|| {
-                        let shader = maple_renderer::core::GraphicsShader {
-                            vertex: rcx
-                                .device()
-                                .compile_shader(material_instance.vertex_shader())
-                                .expect("material vertex shader compile"),
-                            fragment: rcx
-                                .device()
-                                .compile_shader(material_instance.fragment_shader())
-                                .expect("material fragment shader compile"),
-                        };
-                        let material_layout = material_instance.layout(rcx);
-                        let pipeline_layout = rcx.device().create_render_pipeline_layout(&[
-                            self.scene_layout.clone(),
-                            self.mesh_layout.clone(),
-                            self.light_layout.clone(),
-                            material_layout,
-                        ]);
-                        material_instance.pipeline(
-                            rcx,
-                            &MainPass::pass_info(),
-                            pipeline_layout,
-                            shader,
-                        )
-                    });
+                for mut bundle in bundles {
+                    bundle.mesh = morphed_mesh.clone();
+                    bundle.mesh_id = mesh_id.clone();
+                    bundle.world_aabb = world_aabb;
+                    bundle.frustum_culled = frustum_culled;
+                    bundle.deformed = deformed;
+                    bundle.buffer_data = Mesh3DUniformBufferData {
+                        model,
+                        normal_matrix,
+                        ..bundle.buffer_data
+                    };
 
-                material_instance.update_buffer(rcx);
-
-                let buffer_data = Mesh3DUniformBufferData {
-                    model: mesh
-                        .read()
-                        .transform
-                        .world_space()
-                        .matrix
-                        .to_cols_array_2d(),
-                    normal_matrix: mesh
-                        .read()
-                        .transform
-                        .world_space()
-                        .matrix
-                        .inverse()
-                        .transpose()
-                        .to_cols_array_2d(),
-                };
+                    match bundle.alpha_mode {
+                        AlphaMode::Opaque | AlphaMode::Mask => opaque_bundles.push(bundle),
+                        AlphaMode::Blend => transparent_bundles.push(bundle),
+                    }
+                }
+            }
+        }
 
-                let alpha_info =
-                    material_instance
-                        .alpha_info()
-                        .unwrap_or_else(|| MaterialAlphaInfo {
-                            alpha_texture: None,
-                            base_alpha_factor: 1.0,
-                            alpha_cutoff: 0.5,
-                        });
-
-                let alpha_info_gpu = AlphaInfoGpu {
-                    alpha_mode: material_instance.alpha_mode().into(),
-                    base_alpha_factor: alpha_info.base_alpha_factor,
-                    alpha_cutoff: alpha_info.alpha_cutoff,
-                    _padding: Zeroable::zeroed(),
+        // every copy of an instanced model is a bundle of the same mesh so they batch into one
+        // draw per mesh of the model
+        for instanced in game_ctx.scene.collect_visible::<InstancedModel>() {
+            let (model_handle, render_space, instances, frustum_culled) = {
+                let node = instanced.read();
+                let Some(model) = node.model.clone() else {
+                    continue;
                 };
+                (
+                    model,
+                    *node.transform.render_space().matrix(),
+                    node.instances.clone(),
+                    node.frustum_culled,
+                )
+            };
+            let Some(model) = game_ctx.assets.get(&model_handle) else {
+                continue;
+            };
 
-                let default_alpha_texture = &rcx.get_default_texture().white;
-
-                let alpha_texture = match &alpha_info.alpha_texture {
-                    Some(handle) => match game_ctx.assets.get(handle) {
-                        Some(tex) => tex.clone(),
-                        None => continue, // shadow mask texture not loaded yet, skip this frame
-                    },
-                    None => default_alpha_texture.clone(),
+            for model_mesh in model.meshes() {
+                let Some(mesh_instance) = game_ctx.assets.get(&model_mesh.mesh) else {
+                    continue;
+                };
+                let Some(bundles) = self.material_bundles(
+                    rcx,
+                    game_ctx,
+                    (&mut material_cache, deferred),
+                    instanced.id(),
+                    (&mesh_instance, &model_mesh.mesh.id),
+                    &model_mesh.material,
+                ) else {
+                    continue;
                 };
 
-                let (buffer, descriptor) = self
-                    .shadow_descriptors
-                    .entry(material_id)
-                    .or_insert_with(|| {
-                        let sampler = rcx.device().create_sampler(SamplerOptions {
-                            mode_u: maple_renderer::core::texture::TextureMode::Repeat,
-                            mode_v: maple_renderer::core::texture::TextureMode::Repeat,
-                            mode_w: maple_renderer::core::texture::TextureMode::Repeat,
-                            mag_filter: maple_renderer::core::texture::FilterMode::Linear,
-                            min_filter: maple_renderer::core::texture::FilterMode::Linear,
-                            compare: None,
-                        });
-                        let buffer = rcx.device().create_uniform_buffer(&alpha_info_gpu);
-                        let descriptor = rcx.device().build_descriptor_set(
-                            &DescriptorSet::builder(&self.shadow_layout)
-                                .uniform(0, &buffer)
-                                .texture_view(1, &alpha_texture.create_view())
-                                .sampler(2, &sampler),
-                        );
-                        (buffer, descriptor)
-                    });
+                for (index, instance) in instances.iter().enumerate() {
+                    let matrix = render_space * *instance * model_mesh.transform;
+                    let world_aabb = mesh_instance.aabb().transform(&matrix);
+                    let model = matrix.to_cols_array_2d();
+                    let normal_matrix = matrix.inverse().transpose().to_cols_array_2d();
 
-                rcx.queue().write_buffer(buffer, &alpha_info_gpu);
+                    for bundle in &bundles {
+                        let mut bundle = bundle.clone();
+                        bundle.instance = index as u32;
+                        bundle.world_aabb = world_aabb;
+                        bundle.frustum_culled = frustum_culled;
+                        bundle.buffer_data = Mesh3DUniformBufferData {
+                            model,
+                            normal_matrix,
+                            ..bundle.buffer_data
+                        };
 
-                let bundle = MeshBundle {
-                    mesh: mesh_instance.clone(),
-                    mesh_id: mesh_handle.id,
-                    material_descriptor,
-                    shadow_descriptors: descriptor.clone(),
-                    material_id: material_handle.id,
-                    pipeline: pipeline.clone(),
-                    world_aabb,
-                    alpha_mode: material_instance.alpha_mode(),
-                    cull_mode: material_instance.cull_mode(),
-                    buffer_data,
-                    cast_shadow,
-                };
-                if is_opaque {
-                    opaque_bundles.push(bundle);
-                } else {
-                    transparent_bundles.push(bundle);
+                        match bundle.alpha_mode {
+                            AlphaMode::Opaque | AlphaMode::Mask => opaque_bundles.push(bundle),
+                            AlphaMode::Blend => transparent_bundles.push(bundle),
+                        }
+                    }
                 }
             }
         }
 
         opaque_bundles.sort_unstable_by_key(|bundle| {
             (
+                bundle.draw_order,
                 bundle.pipeline.id.clone(),
                 bundle.material_id.clone(),
                 bundle.mesh_id.clone(),
@@ -333,12 +869,29 @@ impl RenderNode for CollectMesh {
 
         transparent_bundles.sort_unstable_by_key(|bundle| {
             (
+                bundle.draw_order,
                 bundle.pipeline.id.clone(),
                 bundle.material_id.clone(),
                 bundle.mesh_id.clone(),
             )
         });
 
+        if !joints.is_empty() {
+            rcx.queue().write_buffer_slice(&self.joint_buffer, &joints);
+        }
+
+        // the joints are written before the frame is submitted so the dispatches see them
+        if !skin_dispatches.is_empty() {
+            let skin_pipeline = &self.skin_pipeline;
+            frame.compute(Some("skinning"), |mut cb| {
+                cb.use_pipeline(skin_pipeline);
+                for (descriptor, workgroups) in &skin_dispatches {
+                    cb.bind_descriptor_set(0, descriptor)
+                        .dispatch(*workgroups, 1, 1);
+                }
+            });
+        }
+
         opaque_bundles.append(&mut transparent_bundles);
         let mesh_bundles = BundledMeshes {
             meshes: opaque_bundles,
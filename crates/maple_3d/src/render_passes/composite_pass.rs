@@ -1,20 +1,27 @@
 use std::slice;
 
 use bytemuck::{Pod, Zeroable};
-use maple_engine::GameContext;
+use maple_engine::{
+    GameContext,
+    transition::{SceneTransitions, TransitionEffect, TransitionFrame},
+};
 use maple_renderer::{
     core::{
         Buffer, CullMode, DescriptorBindingType, DescriptorSet, DescriptorSetLayout,
-        DescriptorSetLayoutDescriptor, Frame, GraphicsShader, RenderContext, StageFlags,
+        DescriptorSetLayoutDescriptor, Frame, GraphicsShader, RenderContext, ShaderLayout,
+        StageFlags, UniformTweak,
         context::RenderOptions,
         pipeline::{AlphaMode, PipelineCreateInfo, RenderPipeline},
-        texture::{FilterMode, Sampler, SamplerOptions, Texture, TextureMode},
+        texture::{
+            FilterMode, Sampler, SamplerOptions, Texture, TextureCreateInfo, TextureMode,
+            TextureUsage,
+        },
     },
     render_graph::{
         graph::{RenderGraphContext, Stage},
         node::{DepthMode, RenderNode, RenderTarget},
     },
-    types::Dimensions,
+    types::{Dimensions, post_effects::PostEffects},
 };
 
 use crate::prelude::Camera3D;
@@ -24,7 +31,67 @@ use crate::prelude::Camera3D;
 struct CompositeUniforms {
     bloom_intensity: f32,
     exposure: f32,
-    _padding: [f32; 2],
+    /// 1 if the surface isn't srgb and the shader has to gamma encode the image
+    encode_srgb: u32,
+    tonemapping: u32,
+    fxaa: u32,
+    vignette: f32,
+    /// 0 without a transition, see [`CompositeUniforms::transition`]
+    transition: u32,
+    transition_progress: f32,
+    fade_color: [f32; 4],
+    wipe_direction: [f32; 2],
+    wipe_softness: f32,
+    _padding: f32,
+}
+
+impl CompositeUniforms {
+    fn new(effects: &PostEffects, exposure: f32, encode_srgb: bool) -> Self {
+        Self {
+            bloom_intensity: if effects.bloom {
+                effects.bloom_intensity
+            } else {
+                0.0
+            },
+            exposure,
+            encode_srgb: encode_srgb.into(),
+            tonemapping: effects.tonemapping.into(),
+            fxaa: effects.fxaa.into(),
+            vignette: effects.vignette.clamp(0.0, 1.0),
+            transition: 0,
+            transition_progress: 0.0,
+            fade_color: [0.0; 4],
+            wipe_direction: [0.0; 2],
+            wipe_softness: 0.0,
+            _padding: 0.0,
+        }
+    }
+
+    /// draw the scene transition over the frame, the frame the old scene is kept from is drawn
+    /// without it
+    fn transition(&mut self, frame: Option<TransitionFrame>) {
+        let Some(frame) = frame.filter(|frame| !frame.capture) else {
+            return;
+        };
+
+        self.transition_progress = frame.progress;
+        match frame.effect {
+            TransitionEffect::Fade(color) => {
+                let color = color.to_linear();
+                self.transition = 1;
+                self.fade_color = [color.r, color.g, color.b, color.a];
+            }
+            TransitionEffect::Crossfade => self.transition = 2,
+            TransitionEffect::Wipe {
+                direction,
+                softness,
+            } => {
+                self.transition = 3;
+                self.wipe_direction = direction.to_array();
+                self.wipe_softness = softness.max(0.0);
+            }
+        }
+    }
 }
 
 /// Post-processing pass that blits the resolved color texture to the surface
@@ -32,15 +99,23 @@ struct CompositeUniforms {
 /// This pass:
 /// - Reads the resolved color texture (after MSAA resolve)
 /// - Renders a fullscreen triangle
+/// - Applies the [`PostEffects`] set on the renderer: exposure, bloom, tonemapping, fxaa and
+///   vignette in that order
+/// - Draws the running [`SceneTransitions`] effect, keeping a copy of the old scene for the
+///   effects that show both
 /// - Outputs to the surface
-///
-/// Future post-processing effects (tone mapping, bloom, etc.) can be added here
 pub struct CompositePass {
     blit_layout: DescriptorSetLayout,
     blit_descriptor: Option<DescriptorSet>,
     sampler: Sampler,
     pipeline: RenderPipeline,
+    /// if the descriptor was built with the bloom pass's texture
+    bound_bloom: bool,
+    /// the last frame of the old scene while a transition shows both scenes
+    old_scene: Option<Texture>,
     uniform: Buffer<CompositeUniforms>,
+    /// lets the uniforms be tuned from the tweak panel
+    tweak: UniformTweak,
 }
 
 impl CompositePass {}
@@ -79,6 +154,7 @@ impl RenderNode for CompositePass {
                         DescriptorBindingType::TextureView { filterable: true }, // Binding 1: Bloom
                         DescriptorBindingType::Sampler { filtering: true }, // Binding 2: linear sampler
                         DescriptorBindingType::UniformBuffer,
+                        DescriptorBindingType::TextureView { filterable: true }, // Binding 4: the old scene of a transition
                     ],
                 });
 
@@ -112,18 +188,22 @@ impl RenderNode for CompositePass {
             sample_count: 1,
             vertex_buffer_layout: None,
         });
-        let uniform = rcx.device().create_uniform_buffer(&CompositeUniforms {
-            bloom_intensity: 0.04,
-            exposure: 0.5,
-            _padding: [0.0; 2],
-        });
+        let uniforms = CompositeUniforms::new(rcx.post_effects(), 0.5, !surface_format.is_srgb());
+        let uniform = rcx.device().create_uniform_buffer(&uniforms);
+        let block = ShaderLayout::from_source(include_str!("./blit.frag.wgsl"))
+            .and_then(|layout| layout.uniform_block(0, 3))
+            .expect("blit fragment to declare its uniforms");
+        let tweak = UniformTweak::new(Self::label(), block, &uniforms);
 
         Self {
             blit_layout,
             blit_descriptor: None,
             sampler,
             pipeline,
+            bound_bloom: false,
+            old_scene: None,
             uniform,
+            tweak,
         }
     }
 
@@ -154,21 +234,55 @@ impl RenderNode for CompositePass {
 
         let exposure = camera.read().exposure;
 
-        rcx.queue().write_buffer(
-            &self.uniform,
-            &CompositeUniforms {
-                bloom_intensity: 0.04,
-                exposure,
-                _padding: [0.0; 2],
-            },
-        );
+        // graphs without the bloom pass or with bloom off from the start have no bloom texture
+        let bloom_texture = graph_ctx.get_shared_resource::<Texture>("bloom_texture");
+        let has_bloom = bloom_texture.is_some();
+        let bloom_texture = bloom_texture.unwrap_or(&rcx.get_default_texture().white);
+
+        let mut uniforms = CompositeUniforms::new(rcx.post_effects(), exposure, false);
+        self.tweak.apply(&mut uniforms);
+        uniforms.encode_srgb = (!rcx.surface_format().is_srgb()).into();
+        if !has_bloom {
+            uniforms.bloom_intensity = 0.0;
+        }
 
-        let bloom_texture = graph_ctx
-            .get_shared_resource::<Texture>("bloom_texture")
-            .unwrap();
+        let transition = game_ctx
+            .has_resource::<SceneTransitions>()
+            .then(|| game_ctx.get_resource::<SceneTransitions>().frame())
+            .flatten();
+        uniforms.transition(transition);
+        rcx.queue().write_buffer(&self.uniform, &uniforms);
+
+        // the copy of the old scene is only bound while it's shown
+        let capture = transition.is_some_and(|frame| frame.capture);
+        if capture {
+            let size = (resolved_texture.width(), resolved_texture.height());
+            if self
+                .old_scene
+                .as_ref()
+                .is_none_or(|old_scene| (old_scene.width(), old_scene.height()) != size)
+            {
+                self.old_scene = Some(rcx.device().create_texture(TextureCreateInfo {
+                    label: Some("transition_old_scene"),
+                    width: size.0,
+                    height: size.1,
+                    format: resolved_texture.format(),
+                    usage: TextureUsage::TEXTURE_BINDING | TextureUsage::COPY_DST,
+                    sample_count: 1,
+                    mip_level: 1,
+                }));
+            }
+            if let Some(old_scene) = &self.old_scene {
+                frame.copy_texture(resolved_texture, old_scene);
+            }
+            self.blit_descriptor = None;
+        } else if transition.is_none() && self.old_scene.take().is_some() {
+            self.blit_descriptor = None;
+        }
 
         // Build descriptor once (invalidated on resize)
-        if self.blit_descriptor.is_none() {
+        if self.blit_descriptor.is_none() || self.bound_bloom != has_bloom {
+            self.bound_bloom = has_bloom;
             let layout = &self.blit_layout;
 
             self.blit_descriptor = Some(
@@ -177,7 +291,15 @@ impl RenderNode for CompositePass {
                         .texture_view(0, &resolved_texture.create_view())
                         .texture_view(1, &bloom_texture.create_view())
                         .sampler(2, &self.sampler)
-                        .uniform(3, &self.uniform),
+                        .uniform(3, &self.uniform)
+                        .texture_view(
+                            4,
+                            &self
+                                .old_scene
+                                .as_ref()
+                                .unwrap_or(resolved_texture)
+                                .create_view(),
+                        ),
                 ),
             );
         }
@@ -194,6 +316,7 @@ impl RenderNode for CompositePass {
                     depth_target: None,
                     clear_color: Some([0.0, 0.0, 0.0, 1.0]),
                     clear_depth: None,
+                    discard_depth: false,
                 },
                 |mut fb| {
                     fb.use_pipeline(pipeline).bind_descriptor_set(0, descriptor);
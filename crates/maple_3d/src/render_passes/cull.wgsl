@@ -0,0 +1,69 @@
+// frustum culls the instances of the main pass and packs the visible instances of each batch
+// together for its indirect draw
+
+struct CullParams {
+    // xyz is the normal and w the distance of each plane
+    planes: array<vec4<f32>, 6>,
+    instance_count: u32,
+}
+
+struct CullInstance {
+    // w is 1 if the instance can be culled
+    aabb_min: vec4<f32>,
+    aabb_max: vec4<f32>,
+    batch: u32,
+}
+
+struct MeshData {
+    model: mat4x4<f32>,
+    normal_matrix: mat4x4<f32>,
+    joint_offset: u32,
+    joint_count: u32,
+    pass_index: u32,
+    pass_count: u32,
+}
+
+struct DrawArgs {
+    index_count: u32,
+    instance_count: atomic<u32>,
+    first_index: u32,
+    base_vertex: i32,
+    first_instance: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: CullParams;
+@group(0) @binding(1) var<storage, read> instances: array<CullInstance>;
+@group(0) @binding(2) var<storage, read> mesh_in: array<MeshData>;
+@group(0) @binding(3) var<storage, read_write> mesh_out: array<MeshData>;
+@group(0) @binding(4) var<storage, read_write> draws: array<DrawArgs>;
+
+fn is_visible(instance: CullInstance) -> bool {
+    if instance.aabb_min.w == 0.0 {
+        return true;
+    }
+
+    for (var i = 0u; i < 6u; i++) {
+        let plane = params.planes[i];
+        // the corner furthest along the normal
+        let corner = select(instance.aabb_min.xyz, instance.aabb_max.xyz, plane.xyz >= vec3<f32>(0.0));
+        if dot(plane.xyz, corner) + plane.w < 0.0 {
+            return false;
+        }
+    }
+    return true;
+}
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.instance_count {
+        return;
+    }
+
+    let instance = instances[id.x];
+    if !is_visible(instance) {
+        return;
+    }
+
+    let slot = atomicAdd(&draws[instance.batch].instance_count, 1u);
+    mesh_out[draws[instance.batch].first_instance + slot] = mesh_in[id.x];
+}
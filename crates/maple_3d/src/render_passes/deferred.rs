@@ -0,0 +1,399 @@
+//! the deferred render path, picked with [`RenderPath::Deferred`]
+//!
+//! the [`GBufferPass`] draws every opaque mesh whose material has a
+//! [`MaterialInstance::gbuffer_shader`] into the targets of a [`GBuffer`] without lighting it.
+//! the [`DeferredLightingPass`] then lights each covered pixel once with every light of the scene
+//! and writes the color, normal and depth into the scene textures over the skybox. the main pass
+//! draws the meshes left over (blended ones and materials that need their own lighting) forward on
+//! top, so the cost of a light is paid per pixel instead of per mesh drawn under it.
+//!
+//! the g-buffer layout is described in `maple_3d/gbuffer.wgsl`. the lights are read from the same
+//! buffers as the forward path, only how often they are evaluated changes.
+//!
+//! ```rust, ignore
+//! App::new(Config {
+//!     render_path: RenderPath::Deferred,
+//!     ..Default::default()
+//! })
+//! .add_plugin(Core3D)
+//! ```
+//!
+//! [`RenderPath::Deferred`]: maple_renderer::types::render_config::RenderPath::Deferred
+//! [`MaterialInstance::gbuffer_shader`]: crate::assets::material::MaterialInstance::gbuffer_shader
+
+use maple_engine::GameContext;
+use maple_renderer::{
+    core::{
+        Buffer, CullMode, DepthCompare, DepthStencilOptions, DescriptorBindingType, DescriptorSet,
+        DescriptorSetLayout, DescriptorSetLayoutDescriptor, Frame, GraphicsShader, RenderContext,
+        StageFlags,
+        context::RenderOptions,
+        pipeline::{AlphaMode, PipelineCreateInfo, RenderPipeline},
+        texture::{Texture, TextureCreateInfo, TextureFormat, TextureUsage},
+    },
+    render_graph::{
+        graph::{GraphResource, RenderGraphContext, Stage},
+        node::{DepthMode, RenderNode, RenderTarget},
+    },
+    types::Dimensions,
+};
+
+use crate::{
+    assets::material::PassInfo,
+    math::Frustum,
+    nodes::{camera::Camera3D, environment::Environment, mesh_instance::Mesh3DUniformBufferData},
+    render_passes::{
+        collect_mesh::{BundledMeshes, CollectMesh},
+        main_pass::{BACKGROUND_COLOR, MAX_MESH, MainPass, SceneDescriptor},
+        scene_textures::scene_target,
+        shadow_resource::ShadowResource,
+    },
+};
+
+/// the targets the [`GBufferPass`] writes, shared as `gbuffer`
+#[derive(Clone)]
+pub(crate) struct GBuffer {
+    albedo: Texture,
+    normal: Texture,
+    surface: Texture,
+    emission: Texture,
+    depth: Texture,
+}
+
+impl GraphResource for GBuffer {}
+
+impl GBuffer {
+    /// the formats of the albedo, normal, surface and emission targets in order
+    const FORMATS: [TextureFormat; 4] = [
+        TextureFormat::RGBA8,
+        TextureFormat::RGBA16Float,
+        TextureFormat::RGBA8,
+        TextureFormat::RGBA16Float,
+    ];
+
+    /// the targets g-buffer pipelines are made for
+    pub(crate) fn pass_info() -> PassInfo {
+        PassInfo {
+            color_formats: Self::FORMATS.to_vec(),
+            sample_count: 1,
+        }
+    }
+
+    fn create(rcx: &RenderContext, dimensions: Dimensions) -> Self {
+        let target = |label, format| {
+            rcx.device().create_texture(TextureCreateInfo {
+                label: Some(label),
+                width: dimensions.width,
+                height: dimensions.height,
+                format,
+                usage: TextureUsage::RENDER_ATTACHMENT | TextureUsage::TEXTURE_BINDING,
+                sample_count: 1,
+                mip_level: 1,
+            })
+        };
+        let [albedo, normal, surface, emission] = Self::FORMATS;
+
+        Self {
+            albedo: target("gbuffer_albedo", albedo),
+            normal: target("gbuffer_normal", normal),
+            surface: target("gbuffer_surface", surface),
+            emission: target("gbuffer_emission", emission),
+            depth: target("gbuffer_depth", TextureFormat::Depth32),
+        }
+    }
+
+    fn color_targets(&self) -> [RenderTarget; 4] {
+        [&self.albedo, &self.normal, &self.surface, &self.emission]
+            .map(|texture| RenderTarget::Texture(texture.create_view()))
+    }
+}
+
+/// draws opaque meshes into the [`GBuffer`], see the [module docs](self)
+pub struct GBufferPass {
+    scene_data: SceneDescriptor,
+    gbuffer: GBuffer,
+    mesh_buffer: Buffer<[Mesh3DUniformBufferData]>,
+    mesh_descriptor: DescriptorSet,
+}
+
+impl RenderNode for GBufferPass {
+    fn label() -> &'static str
+    where
+        Self: Sized,
+    {
+        "G-Buffer"
+    }
+
+    fn stage(&self) -> Stage {
+        Stage::Opaque
+    }
+
+    fn setup(rcx: &RenderContext, gcx: &mut RenderGraphContext) -> Self {
+        let gbuffer = GBuffer::create(rcx, rcx.render_size());
+        // collect meshes makes g-buffer pipelines once it sees the g-buffer
+        gcx.add_shared_resource("gbuffer", gbuffer.clone());
+
+        let mesh_buffer = rcx.device().create_sized_storage_buffer(MAX_MESH);
+        let mesh_descriptor = rcx.device().build_descriptor_set(
+            DescriptorSet::builder(&CollectMesh::mesh_layout(rcx))
+                .storage(0, &mesh_buffer)
+                .storage(1, &CollectMesh::joint_buffer(gcx)),
+        );
+
+        Self {
+            scene_data: SceneDescriptor::new(rcx),
+            gbuffer,
+            mesh_buffer,
+            mesh_descriptor,
+        }
+    }
+
+    fn draw(
+        &mut self,
+        rcx: &RenderContext,
+        frame: &mut Frame,
+        graph_ctx: &mut RenderGraphContext,
+        game_ctx: &GameContext,
+    ) {
+        // shared again in case it was recreated during resize
+        graph_ctx.add_shared_resource("gbuffer", self.gbuffer.clone());
+
+        let scene = &game_ctx.scene;
+        let cameras = scene.collect::<Camera3D>();
+        let environments = scene.collect::<Environment>();
+        let Some(camera) = cameras
+            .iter()
+            .filter(|c| c.read().is_active)
+            .max_by_key(|c| c.read().priority)
+        else {
+            return;
+        };
+
+        let Some(light_set) = graph_ctx
+            .get_shared_resource::<DescriptorSet>("light_descriptor_set")
+            .cloned()
+        else {
+            return;
+        };
+        let scene_set = self
+            .scene_data
+            .bind(rcx, graph_ctx, &camera.read(), &environments);
+
+        let frustum = Frustum::from_view_proj(&camera.read().get_vp_matrix(rcx.aspect_ratio()));
+        let bundles = graph_ctx
+            .get_shared_resource::<BundledMeshes>("mesh_bundles")
+            .unwrap();
+        // culled on the cpu like the shadow passes
+        let (batches, buffer_data, _) = MainPass::cull_and_batch_meshes(
+            bundles
+                .meshes
+                .iter()
+                .filter_map(|bundle| Some((bundle, bundle.gbuffer_pipeline.as_ref()?))),
+            Some(&frustum),
+        );
+        rcx.queue()
+            .write_buffer_slice(&self.mesh_buffer, &buffer_data);
+
+        CollectMesh::record_draws(
+            game_ctx,
+            Self::label(),
+            batches
+                .iter()
+                .flat_map(|pipeline| &pipeline.material_batches)
+                .flat_map(|material| &material.mesh_batches)
+                .map(|batch| (&batch.mesh, batch.nodes.as_slice())),
+        );
+
+        // cleared every frame so pixels nothing covers read as the background
+        frame
+            .render(
+                RenderOptions {
+                    label: Some("G-Buffer Pass"),
+                    color_targets: &self.gbuffer.color_targets(),
+                    depth_target: Some(&self.gbuffer.depth.create_view()),
+                    clear_color: Some([0.0; 4]),
+                    clear_depth: Some(1.0),
+                    discard_depth: false,
+                },
+                |mut fb| {
+                    fb.bind_descriptor_set(0, &scene_set)
+                        .bind_descriptor_set(1, &self.mesh_descriptor)
+                        .bind_descriptor_set(2, &light_set);
+
+                    for pipeline_batch in batches {
+                        fb.use_pipeline(&pipeline_batch.pipeline);
+
+                        for material_batch in pipeline_batch.material_batches {
+                            fb.bind_descriptor_set(3, &material_batch.material_descriptor);
+
+                            for mesh_batch in material_batch.mesh_batches {
+                                fb.bind_vertex_buffer(mesh_batch.mesh.get_vertex_buffer())
+                                    .bind_index_buffer(mesh_batch.mesh.get_index_buffer())
+                                    .draw_indexed(mesh_batch.start..mesh_batch.end);
+                            }
+                        }
+                    }
+                },
+            )
+            .expect("failed to render the g-buffer");
+    }
+
+    fn resize(&mut self, rcx: &RenderContext, _dimensions: Dimensions) {
+        // lit texel for texel into the scene textures so it's drawn at their size
+        self.gbuffer = GBuffer::create(rcx, rcx.render_size());
+    }
+}
+
+/// lights the [`GBuffer`] into the scene textures, see the [module docs](self)
+pub struct DeferredLightingPass {
+    scene_data: SceneDescriptor,
+    gbuffer_layout: DescriptorSetLayout,
+    pipeline: RenderPipeline,
+}
+
+impl RenderNode for DeferredLightingPass {
+    fn label() -> &'static str
+    where
+        Self: Sized,
+    {
+        "Deferred Lighting"
+    }
+
+    fn stage(&self) -> Stage {
+        Stage::Opaque
+    }
+
+    fn setup(rcx: &RenderContext, _gcx: &mut RenderGraphContext) -> Self {
+        let scene_data = SceneDescriptor::new(rcx);
+        let gbuffer_layout =
+            rcx.device()
+                .create_descriptor_set_layout(DescriptorSetLayoutDescriptor {
+                    label: Some("gbuffer layout"),
+                    visibility: StageFlags::FRAGMENT,
+                    layout: &[
+                        DescriptorBindingType::TextureView { filterable: false }, // albedo
+                        DescriptorBindingType::TextureView { filterable: false }, // normal
+                        DescriptorBindingType::TextureView { filterable: false }, // surface
+                        DescriptorBindingType::TextureView { filterable: false }, // emission
+                        DescriptorBindingType::TextureViewDepth,
+                    ],
+                });
+
+        let shader = GraphicsShader {
+            vertex: rcx
+                .device()
+                .compile_shader(include_str!("./blit.vert.wgsl").into())
+                .expect("blit shader to compile"),
+            fragment: rcx
+                .device()
+                .compile_shader(include_str!("./deferred_lighting.frag.wgsl").into())
+                .expect("deferred lighting shader to compile"),
+        };
+
+        // drawn into the main pass targets so the forward meshes are depth tested against it
+        let pass_info = MainPass::pass_info(rcx);
+        let pipeline = rcx.device().create_pipeline(PipelineCreateInfo {
+            label: Some("Deferred Lighting"),
+            layout: rcx.device().create_pipeline_layout(&[
+                scene_data.layout.clone(),
+                gbuffer_layout.clone(),
+                ShadowResource::layout(rcx),
+            ]),
+            shader,
+            color_formats: &pass_info.color_formats,
+            depth: DepthMode::Texture(DepthStencilOptions {
+                compare: DepthCompare::Always,
+                ..DepthStencilOptions::new(TextureFormat::Depth32)
+            }),
+            cull_mode: CullMode::None,
+            alpha_mode: AlphaMode::Opaque,
+            sample_count: pass_info.sample_count,
+            vertex_buffer_layout: None,
+        });
+
+        Self {
+            scene_data,
+            gbuffer_layout,
+            pipeline,
+        }
+    }
+
+    fn draw(
+        &mut self,
+        rcx: &RenderContext,
+        frame: &mut Frame,
+        graph_ctx: &mut RenderGraphContext,
+        game_ctx: &GameContext,
+    ) {
+        let Some(gbuffer) = graph_ctx.get_shared_resource::<GBuffer>("gbuffer").cloned() else {
+            return;
+        };
+        let Some(light_set) = graph_ctx
+            .get_shared_resource::<DescriptorSet>("light_descriptor_set")
+            .cloned()
+        else {
+            return;
+        };
+        let target = |name| graph_ctx.get_shared_resource::<Texture>(name).cloned();
+        let (Some(msaa_color), Some(resolved_color), Some(msaa_normal), Some(resolved_normal)) = (
+            target("msaa_color_texture"),
+            target("resolved_color_texture"),
+            target("msaa_normal_texture"),
+            target("resolved_normal_texture"),
+        ) else {
+            return;
+        };
+        let Some(depth) = target("main_depth_texture") else {
+            return;
+        };
+
+        let scene = &game_ctx.scene;
+        let cameras = scene.collect::<Camera3D>();
+        let environments = scene.collect::<Environment>();
+        let Some(camera) = cameras
+            .iter()
+            .filter(|c| c.read().is_active)
+            .max_by_key(|c| c.read().priority)
+        else {
+            return;
+        };
+
+        let scene_set = self
+            .scene_data
+            .bind(rcx, graph_ctx, &camera.read(), &environments);
+        let gbuffer_set = rcx.device().build_descriptor_set(
+            DescriptorSet::builder(&self.gbuffer_layout)
+                .texture_view(0, &gbuffer.albedo.create_view())
+                .texture_view(1, &gbuffer.normal.create_view())
+                .texture_view(2, &gbuffer.surface.create_view())
+                .texture_view(3, &gbuffer.emission.create_view())
+                .texture_view(4, &gbuffer.depth.create_view()),
+        );
+
+        // the skybox is kept where the g-buffer is empty, without one the scene is cleared
+        let clear_color = environments.is_empty().then_some(BACKGROUND_COLOR);
+
+        frame
+            .render(
+                RenderOptions {
+                    label: Some("Deferred Lighting Pass"),
+                    color_targets: &[
+                        scene_target(&msaa_color, &resolved_color),
+                        scene_target(&msaa_normal, &resolved_normal),
+                    ],
+                    depth_target: Some(&depth.create_view()),
+                    clear_color,
+                    clear_depth: Some(1.0),
+                    discard_depth: false,
+                },
+                |mut fb| {
+                    fb.use_pipeline(&self.pipeline)
+                        .bind_descriptor_set(0, &scene_set)
+                        .bind_descriptor_set(1, &gbuffer_set)
+                        .bind_descriptor_set(2, &light_set)
+                        .draw(0..3, 0);
+                },
+            )
+            .expect("failed to render the deferred lighting");
+    }
+}
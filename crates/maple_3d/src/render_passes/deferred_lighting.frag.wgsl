@@ -0,0 +1,247 @@
+// shades every pixel of the g-buffer with all the lights of the scene, the same lighting as
+// pbr.frag.wgsl but in world space
+
+#include "maple/brdf.wgsl"
+#include "maple_3d/lights.wgsl"
+#include "maple_3d/gbuffer.wgsl"
+
+struct SceneData {
+    background_color: vec4<f32>,
+    ambient: f32,
+    ibl_strength: f32,
+}
+
+struct CameraData {
+    cam_pos: vec4<f32>,
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    VP: mat4x4<f32>,
+    far_plane: f32,
+    inverse_vp: mat4x4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> scene: SceneData;
+@group(0) @binding(1) var<uniform> camera: CameraData;
+@group(0) @binding(2) var irradiance_map: texture_cube<f32>;
+@group(0) @binding(3) var irradiance_sampler: sampler;
+@group(0) @binding(4) var prefilter_map: texture_cube<f32>;
+@group(0) @binding(5) var prefilter_sampler: sampler;
+@group(0) @binding(6) var brdf_lut: texture_2d<f32>;
+@group(0) @binding(7) var brdf_lut_sampler: sampler;
+
+@group(1) @binding(0) var gbuffer_albedo: texture_2d<f32>;
+@group(1) @binding(1) var gbuffer_normal: texture_2d<f32>;
+@group(1) @binding(2) var gbuffer_surface: texture_2d<f32>;
+@group(1) @binding(3) var gbuffer_emission: texture_2d<f32>;
+@group(1) @binding(4) var gbuffer_depth: texture_depth_2d;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coord: vec2<f32>,
+}
+
+struct FragmentOutput {
+    @location(0) color: vec4<f32>,
+    @location(1) normal: vec4<f32>,
+    // meshes drawn forward afterwards are hidden behind the shaded surfaces
+    @builtin(frag_depth) depth: f32,
+}
+
+// the cook torrance brdf of a light arriving from `L` times the cosine of its angle
+fn cook_torrance(N: vec3<f32>, V: vec3<f32>, L: vec3<f32>, albedo: vec3<f32>, metallic: f32, roughness: f32, F0: vec3<f32>) -> vec3<f32> {
+    let H = normalize(V + L);
+    let NdotL = max(dot(N, L), 0.0);
+
+    let NDF = distribution_schlick_ggx(N, H, roughness);
+    let G = geometry_smith(N, V, L, roughness);
+    let F = fresnel_schlick(max(dot(H, V), 0.0), F0);
+
+    let specular = NDF * G * F / (4.0 * max(dot(N, V), 0.0) * NdotL + 0.0001);
+    let kD = (vec3<f32>(1.0) - F) * (1.0 - metallic);
+
+    return (kD * albedo / PI + specular) * NdotL;
+}
+
+@fragment
+fn main(in: VertexOutput) -> FragmentOutput {
+    let pixel = vec2<i32>(in.position.xy);
+    let depth = textureLoad(gbuffer_depth, pixel, 0);
+    // nothing was drawn here, the skybox stays
+    if depth >= 1.0 {
+        discard;
+    }
+
+    let albedo_ao = textureLoad(gbuffer_albedo, pixel, 0);
+    let normals = textureLoad(gbuffer_normal, pixel, 0);
+    let surface = textureLoad(gbuffer_surface, pixel, 0);
+    let emission = textureLoad(gbuffer_emission, pixel, 0).rgb;
+
+    let N = octahedron_decode(normals.xy);
+    let N_geom = octahedron_decode(normals.zw);
+    let encoded_normal = vec4<f32>(N * 0.5 + 0.5, 1.0);
+
+    if surface.b < 0.5 {
+        return FragmentOutput(vec4<f32>(emission, 1.0), encoded_normal, depth);
+    }
+
+    let size = vec2<f32>(textureDimensions(gbuffer_depth));
+    let ndc = vec2<f32>(in.position.x / size.x * 2.0 - 1.0, 1.0 - in.position.y / size.y * 2.0);
+    let world = camera.inverse_vp * vec4<f32>(ndc, depth, 1.0);
+    let world_pos = world.xyz / world.w;
+
+    let albedo = albedo_ao.rgb;
+    let ao_factor = albedo_ao.a;
+    let metallic = surface.r;
+    let roughness = surface.g;
+    let V = normalize(camera.cam_pos.xyz - world_pos);
+    let F0 = mix(vec3<f32>(0.04), albedo, metallic);
+
+    var Lo = vec3<f32>(0.0);
+
+    for (var i: i32 = 0; i < direct_light_buffer.len; i++) {
+        let light = direct_light_buffer.lights[i];
+
+        // macro surface faces away skip shading (fixes light bleeding in normal maps)
+        let L = normalize(-light.direction.xyz);
+        let geom_NdotL = max(dot(N_geom, L), 0.0);
+        if geom_NdotL <= 0.0 {
+            continue;
+        }
+
+        let radiance = light.color.rgb * light.intensity;
+        let shadow = calculate_directional_shadow(light, world_pos, N_geom, in.position.xy);
+        Lo += cook_torrance(N, V, L, albedo, metallic, roughness, F0) * radiance * shadow * smoothstep(0.0, 1.0, geom_NdotL);
+    }
+
+    for (var i: i32 = 0; i < point_light_buffer.len; i++) {
+        let light = point_light_buffer.lights[i];
+
+        let to_light = light.pos.xyz - world_pos;
+        let L = normalize(to_light);
+        let geom_NdotL = max(dot(N_geom, L), 0.0);
+        if geom_NdotL <= 0.0 {
+            continue;
+        }
+
+        let attenuation = 1.0 / dot(to_light, to_light);
+        let radiance = light.color.rgb * attenuation * light.intensity;
+        let shadow = calculate_point_shadow(light, world_pos, in.position.xy);
+        Lo += cook_torrance(N, V, L, albedo, metallic, roughness, F0) * radiance * shadow * smoothstep(0.0, 1.0, geom_NdotL);
+    }
+
+    for (var i: i32 = 0; i < spot_light_buffer.len; i++) {
+        let light = spot_light_buffer.lights[i];
+
+        let to_light = light.pos.xyz - world_pos;
+        let light_distance = length(to_light);
+        if light_distance >= light.range {
+            continue;
+        }
+
+        let L = to_light / max(light_distance, 0.0001);
+        let geom_NdotL = max(dot(N_geom, L), 0.0);
+        if geom_NdotL <= 0.0 {
+            continue;
+        }
+
+        // full strength in the inner cone fading out to the outer cone
+        let cone = saturate(
+            (dot(-L, normalize(light.direction.xyz)) - light.outer_cos)
+                / max(light.inner_cos - light.outer_cos, 0.0001)
+        );
+        if cone <= 0.0 {
+            continue;
+        }
+
+        // inverse square falloff that reaches zero at the range
+        let window = saturate(1.0 - pow(light_distance / light.range, 4.0));
+        let attenuation = window * window / max(light_distance * light_distance, 0.0001);
+        let radiance = light.color.rgb * attenuation * light.intensity * cone * cone;
+
+        let shadow = calculate_spot_shadow(light, world_pos, N_geom, in.position.xy);
+        Lo += cook_torrance(N, V, L, albedo, metallic, roughness, F0) * radiance * shadow * smoothstep(0.0, 1.0, geom_NdotL);
+    }
+
+    // Area lights, lit from a representative point on the shape (karis 2013)
+    for (var i: i32 = 0; i < area_light_buffer.len; i++) {
+        let light = area_light_buffer.lights[i];
+
+        let area = area_light_area(light);
+        if area <= 0.0 {
+            continue;
+        }
+        let light_normal = normalize(cross(light.up.xyz, light.right.xyz));
+
+        // surfaces behind the light are only lit by two sided lights
+        if dot(world_pos - light.pos.xyz, light_normal) <= 0.0 && light.two_sided == 0u {
+            continue;
+        }
+
+        // diffuse comes from the closest point of the shape
+        let diffuse_vec = closest_point_on_area_light(light, world_pos) - world_pos;
+        let diffuse_dist2 = max(dot(diffuse_vec, diffuse_vec), 0.0001);
+        let L_diffuse = diffuse_vec * inverseSqrt(diffuse_dist2);
+
+        let geom_NdotL = max(dot(N_geom, L_diffuse), 0.0);
+        if geom_NdotL <= 0.0 {
+            continue;
+        }
+
+        let diffuse_scale = area * abs(dot(L_diffuse, light_normal)) / (diffuse_dist2 + area / PI);
+
+        // specular comes from the point of the shape closest to where the reflection hits its plane
+        let R = reflect(-V, N);
+        let R_dot_normal = dot(R, light_normal);
+        var hit = world_pos;
+        if abs(R_dot_normal) > 0.0001 {
+            hit += R * max(dot(light.pos.xyz - world_pos, light_normal) / R_dot_normal, 0.0);
+        }
+        let specular_vec = closest_point_on_area_light(light, hit) - world_pos;
+        let specular_dist2 = max(dot(specular_vec, specular_vec), 0.0001);
+        let L = specular_vec * inverseSqrt(specular_dist2);
+
+        // a wider lobe keeps the highlight from gaining energy as the light grows
+        let alpha = roughness * roughness;
+        let source_radius = sqrt(area / PI);
+        let alpha_wide = saturate(alpha + source_radius / (2.0 * sqrt(specular_dist2)));
+        let normalization = (alpha / alpha_wide) * (alpha / alpha_wide);
+        let specular_scale = area * abs(dot(L, light_normal)) / (specular_dist2 + area / PI) * normalization;
+
+        let H = normalize(V + L);
+        let NdotL = max(dot(N, L), 0.0);
+        let NDF = distribution_schlick_ggx(N, H, roughness);
+        let G = geometry_smith(N, V, L, roughness);
+        let F = fresnel_schlick(max(dot(H, V), 0.0), F0);
+        let specular = NDF * G * F / (4.0 * max(dot(N, V), 0.0) * NdotL + 0.0001);
+        let kD = (vec3<f32>(1.0) - F) * (1.0 - metallic);
+
+        let radiance = light.color.rgb * light.intensity;
+        let diffuse = kD * albedo / PI * max(dot(N, L_diffuse), 0.0) * diffuse_scale;
+        Lo += (diffuse + specular * NdotL * specular_scale) * radiance * smoothstep(0.0, 1.0, geom_NdotL);
+    }
+
+    let NdotV = max(dot(N, V), 0.0);
+    var ambient = vec3<f32>(0.0);
+    if scene.ibl_strength > 0.0 {
+        let R = reflect(-V, N);
+        let kS = fresnel_schlick_roughness(NdotV, F0, roughness);
+        let kD = (vec3<f32>(1.0) - kS) * (1.0 - metallic);
+
+        // a fullscreen pass has no useful derivatives so every lookup picks its level
+        let irradiance = textureSampleLevel(irradiance_map, irradiance_sampler, N, 0.0).rgb;
+        let max_reflection_lod = f32(textureNumLevels(prefilter_map) - 1);
+        let prefiltered = textureSampleLevel(prefilter_map, prefilter_sampler, R, roughness * max_reflection_lod).rgb;
+        let brdf = textureSampleLevel(brdf_lut, brdf_lut_sampler, vec2<f32>(NdotV, roughness), 0.0).rg;
+        let specular = prefiltered * (F0 * brdf.r + brdf.g);
+
+        ambient = (kD * irradiance * albedo + specular) * ao_factor * scene.ibl_strength;
+    } else {
+        // lit as if surrounded by a uniform environment of brightness scene.ambient
+        let kS = fresnel_schlick_roughness(NdotV, F0, roughness);
+        let kD = (1.0 - metallic) * (vec3<f32>(1.0) - kS);
+        let specular = env_brdf_approx(F0, NdotV, roughness);
+        ambient = (kD * albedo + specular) * scene.ambient * ao_factor;
+    }
+
+    return FragmentOutput(vec4<f32>(emission + ambient + Lo, 1.0), encoded_normal, depth);
+}
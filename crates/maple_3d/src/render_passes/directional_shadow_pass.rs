@@ -26,8 +26,9 @@ use crate::{
         mesh_instance::{Mesh3DUniformBufferData, MeshInstance3D},
     },
     render_passes::{
-        collect_mesh::BundledMeshes,
+        collect_mesh::{BundledMeshes, CollectMesh},
         main_pass::MAX_MESH,
+        shadow_lod::{ShadowLevel, ShadowSchedule},
         shadow_resource::{self, ShadowResource},
     },
 };
@@ -48,6 +49,8 @@ struct LightVPUniform {
 /// 1. Getting the light's view-projection matrices (up to 4 cascades)
 /// 2. Rendering all meshes from the light's perspective to depth layers
 /// 3. Storing depth values for shadow sampling in the main pass
+///
+/// cascades whose view and casters haven't changed since they were rendered are kept
 pub struct DirectionalShadowPass {
     // Buffer for light view-projection matrix
     light_vp_buffer: Buffer<[LightVPUniform]>,
@@ -59,8 +62,13 @@ pub struct DirectionalShadowPass {
     pipeline: HashMap<CullMode, RenderPipeline>,
 
     mesh_buffers: HashMap<u32, Buffer<[Mesh3DUniformBufferData]>>,
+    joint_buffer: Buffer<[[[f32; 4]; 4]]>,
     mesh_layout: DescriptorSetLayout,
     mesh_descriptors: HashMap<u32, DescriptorSet>,
+
+    /// the array the schedule's layers are in
+    rendered_into: Option<TextureArray>,
+    schedule: ShadowSchedule,
 }
 
 impl DirectionalShadowPass {}
@@ -77,7 +85,7 @@ impl RenderNode for DirectionalShadowPass {
         Stage::Shadow
     }
 
-    fn setup(rcx: &RenderContext, _: &mut RenderGraphContext) -> Self {
+    fn setup(rcx: &RenderContext, graph_ctx: &mut RenderGraphContext) -> Self {
         let shader = GraphicsShader {
             vertex: rcx
                 .device()
@@ -117,17 +125,8 @@ impl RenderNode for DirectionalShadowPass {
         );
 
         // Get mesh descriptor layout
-        let mesh_layout = rcx.get_or_create_layout(DescriptorSetLayoutDescriptor {
-            label: Some("Mesh"),
-            visibility: StageFlags::VERTEX,
-            layout: &[
-                DescriptorBindingType::Storage {
-                    read_only: true,
-                    has_dynamic_offset: false,
-                    min_size: None,
-                }, // transforms
-            ],
-        });
+        let mesh_layout = CollectMesh::mesh_layout(rcx);
+        let joint_buffer = CollectMesh::joint_buffer(graph_ctx);
 
         let shadow_layout = ShadowResource::shadow_layout(rcx);
 
@@ -175,8 +174,11 @@ impl RenderNode for DirectionalShadowPass {
             light_vp_descriptor,
             pipeline,
             mesh_buffers: HashMap::new(),
+            joint_buffer,
             mesh_layout,
             mesh_descriptors: HashMap::new(),
+            rendered_into: None,
+            schedule: ShadowSchedule::default(),
         }
     }
 
@@ -198,7 +200,7 @@ impl RenderNode for DirectionalShadowPass {
 
         // Get scene data
         let directional_lights = scene.collect::<DirectionalLight>();
-        let mesh_instance = scene.collect::<MeshInstance3D>();
+        let mesh_instance = scene.collect_visible::<MeshInstance3D>();
         let cameras = scene.collect::<Camera3D>();
 
         if directional_lights.is_empty() || mesh_instance.is_empty() || cameras.is_empty() {
@@ -226,15 +228,38 @@ impl RenderNode for DirectionalShadowPass {
             return;
         };
 
+        // maps made again by the shadow resource start out empty
+        if self.rendered_into.as_ref() != Some(shadow_array) {
+            self.rendered_into = Some(shadow_array.clone());
+            self.schedule.clear();
+        }
+        self.schedule.next_frame();
+
+        // lights without a shadow aren't given layers, the rest render into the top left of theirs
+        let shadow_indices = ShadowResource::shadow_indices(
+            directional_lights
+                .iter()
+                .map(|light| light.read().cast_shadows),
+        );
+        let map_size = shadow_array.width();
+        let sizes: Vec<u32> = directional_lights
+            .iter()
+            .map(|light| light.read().shadow_resolution.clamp(1, map_size))
+            .collect();
+
         // Update light buffers with current scene data
         let direct_light_data = DirectionalLightBuffer::from_lights(
             &directional_lights
                 .iter()
                 .enumerate()
                 .map(|(i, light)| {
-                    light
-                        .read()
-                        .to_buffer_data(&camera.read(), render_ctx.aspect_ratio(), i)
+                    let mut data = light.read().to_buffer_data(
+                        &camera.read(),
+                        render_ctx.aspect_ratio(),
+                        shadow_indices[i],
+                    );
+                    data.shadow_scale = sizes[i] as f32 / map_size as f32;
+                    data
                 })
                 .collect::<Vec<_>>(),
         );
@@ -247,20 +272,20 @@ impl RenderNode for DirectionalShadowPass {
         let light_vp_buffer = &self.light_vp_buffer;
         let light_vp_descriptor = &self.light_vp_descriptor;
 
+        // every light takes MAX_CASCADES uniforms whatever its cascade count
         let light_data: Vec<LightVPUniform> = directional_lights
             .iter()
-            .map(|light| {
+            .flat_map(|light| {
                 let vp = light
                     .read()
                     .view_projection(&camera.read(), render_ctx.aspect_ratio());
-                vp.iter()
-                    .map(|(mat, _)| LightVPUniform {
-                        view_projection: mat.to_cols_array_2d(),
-                        _padding: Zeroable::zeroed(),
-                    })
-                    .collect::<Vec<_>>()
+                (0..shadow_resource::MAX_CASCADES as usize).map(move |cascade| LightVPUniform {
+                    view_projection: vp
+                        .get(cascade)
+                        .map_or(Zeroable::zeroed(), |(mat, _)| mat.to_cols_array_2d()),
+                    _padding: Zeroable::zeroed(),
+                })
             })
-            .flatten()
             .collect();
 
         render_ctx
@@ -273,6 +298,15 @@ impl RenderNode for DirectionalShadowPass {
 
         // Render each directional light's cascades
         for (light_idx, light) in directional_lights.iter().enumerate() {
+            let Some(shadow_index) = shadow_indices[light_idx] else {
+                continue;
+            };
+            let size = sizes[light_idx];
+            let level = ShadowLevel {
+                scale: size as f32 / map_size as f32,
+                interval: 1,
+            };
+
             // Get view-projection matrices for all cascades
             let vp_matrices = light
                 .read()
@@ -280,8 +314,9 @@ impl RenderNode for DirectionalShadowPass {
 
             // Render each cascade
             for (cascade_idx, (vp_matrix, _)) in vp_matrices.iter().enumerate() {
-                // Calculate layer index: light_idx * 4 + cascade_idx
-                let layer = (light_idx * 4 + cascade_idx) as u32;
+                let uniform = light_idx * shadow_resource::MAX_CASCADES as usize + cascade_idx;
+                let layer =
+                    (shadow_index * shadow_resource::MAX_CASCADES as usize + cascade_idx) as u32;
 
                 let cascade_fustum = Frustum::from_view_proj(vp_matrix);
 
@@ -292,15 +327,19 @@ impl RenderNode for DirectionalShadowPass {
 
                 let (batches, data) =
                     ShadowResource::cull_and_batch_meshes(&bundles.meshes, cascade_fustum);
+                let contents = ShadowResource::contents(&light_data[uniform], &batches, &data);
+                if !self
+                    .schedule
+                    .due(layer as usize, light.id(), level, contents)
+                {
+                    continue;
+                }
+                ShadowResource::record_draws(game_ctx, Self::label(), &batches);
 
                 let buffer = self
                     .mesh_buffers
                     .entry(cascade_idx as u32)
-                    .or_insert_with(|| {
-                        render_ctx.device().create_sized_storage_buffer(
-                            size_of::<Mesh3DUniformBufferData>() * MAX_MESH,
-                        )
-                    });
+                    .or_insert_with(|| render_ctx.device().create_sized_storage_buffer(MAX_MESH));
 
                 render_ctx.queue().write_buffer_slice(buffer, &data);
 
@@ -309,7 +348,9 @@ impl RenderNode for DirectionalShadowPass {
                     .entry(cascade_idx as u32)
                     .or_insert_with(|| {
                         render_ctx.device().build_descriptor_set(
-                            DescriptorSet::builder(&self.mesh_layout).storage(0, buffer),
+                            DescriptorSet::builder(&self.mesh_layout)
+                                .storage(0, buffer)
+                                .storage(1, &self.joint_buffer),
                         )
                     });
 
@@ -325,14 +366,21 @@ impl RenderNode for DirectionalShadowPass {
                             depth_target: Some(&layer_view),
                             clear_color: None,
                             clear_depth: Some(1.0),
+                            discard_depth: false,
                         },
                         |mut fb| {
                             fb.bind_descriptor_set_with_offset(
                                 0,
                                 light_vp_descriptor,
-                                &[size_of::<LightVPUniform>() as u32 * layer],
+                                &[size_of::<LightVPUniform>() as u32 * uniform as u32],
                             )
-                            .bind_descriptor_set(1, &descriptor);
+                            .bind_descriptor_set(1, &descriptor)
+                            .set_viewport(
+                                0.0,
+                                0.0,
+                                size as f32,
+                                size as f32,
+                            );
 
                             for material_batch in batches {
                                 // fb.bind_descriptor_set(3, &material_batch.descriptor);
@@ -24,7 +24,7 @@ use maple_renderer::{
     },
 };
 
-use crate::nodes::environment::Environment;
+use crate::nodes::environment::{Environment, EnvironmentSource};
 
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Zeroable)]
@@ -33,9 +33,26 @@ struct EquirectUniforms {
     _padding: [u32; 15],
 }
 
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct FaceUniforms {
+    decode_srgb: u32,
+    _padding: [u32; 3],
+}
+
+const CUBE_FACES: [CubeFace; 6] = [
+    CubeFace::PositiveX,
+    CubeFace::NegativeX,
+    CubeFace::PositiveY,
+    CubeFace::NegativeY,
+    CubeFace::PositiveZ,
+    CubeFace::NegativeZ,
+];
+
 pub struct EnvironmentPrePass {
     // Render pipeline
     pipeline: RenderPipeline,
+    faces_pipeline: RenderPipeline,
     uniform_buffer: Buffer<EquirectUniforms>,
     sampler: Sampler,
     layout: DescriptorSetLayout,
@@ -84,6 +101,13 @@ impl RenderNode for EnvironmentPrePass {
                 .compile_shader(include_str!("./flat_to_cube.frag.wgsl").into())
                 .expect("flat_to_cube fragment to compile"),
         };
+        let faces_shader = GraphicsShader {
+            vertex: shader.vertex.clone(),
+            fragment: rcx
+                .device()
+                .compile_shader(include_str!("./face_to_cube.frag.wgsl").into())
+                .expect("face_to_cube fragment to compile"),
+        };
         let irradiance_shader = GraphicsShader {
             vertex: rcx
                 .device()
@@ -134,7 +158,7 @@ impl RenderNode for EnvironmentPrePass {
 
         let pipeline = rcx.device().create_pipeline(PipelineCreateInfo {
             label: Some("FlatToCube"),
-            layout: pipeline_layout,
+            layout: pipeline_layout.clone(),
             shader: shader.clone(),
             color_formats: &[TextureFormat::RGBA16Float],
             depth: DepthMode::None,
@@ -144,6 +168,18 @@ impl RenderNode for EnvironmentPrePass {
             vertex_buffer_layout: None,
         });
 
+        let faces_pipeline = rcx.device().create_pipeline(PipelineCreateInfo {
+            label: Some("FacesToCube"),
+            layout: pipeline_layout,
+            shader: faces_shader,
+            color_formats: &[TextureFormat::RGBA16Float],
+            depth: DepthMode::None,
+            cull_mode: CullMode::None,
+            alpha_mode: AlphaMode::Opaque,
+            sample_count: 1,
+            vertex_buffer_layout: None,
+        });
+
         let irradiance_pipeline = rcx.device().create_pipeline(PipelineCreateInfo {
             label: Some("irradiance generation"),
             layout: irradiance_pipeline_layout,
@@ -254,6 +290,7 @@ impl RenderNode for EnvironmentPrePass {
 
         Self {
             pipeline,
+            faces_pipeline,
             uniform_buffer,
             sampler,
             layout,
@@ -291,16 +328,12 @@ impl RenderNode for EnvironmentPrePass {
             return;
         };
 
-        let Some(hdri) = environment.read().get_hdri_texture(&game_ctx.assets) else {
-            // texture isnt loaded yet
-            return;
-        };
-
         let environment = environment.read();
 
-        // Use dynamic resolution from Environment configuration
-        let base_resolution = hdri.height() / 2;
-        let cubemap_resoultion = environment.get_resolution_scale().apply(base_resolution);
+        // the source textures aren't loaded yet
+        let Some(cubemap_resoultion) = environment.get_cubemap_resolution(&game_ctx.assets) else {
+            return;
+        };
         let cubemap_mip_level = f32::log2(cubemap_resoultion as f32) as u32 + 1;
 
         let cubemap = rcx.device().create_texture_cube(TextureCubeCreateInfo {
@@ -314,47 +347,74 @@ impl RenderNode for EnvironmentPrePass {
         });
         self.cubemap = Some(cubemap);
 
-        let descrptor = rcx.device().build_descriptor_set(
-            DescriptorSet::builder(&self.layout)
-                .texture_view(0, &hdri.create_view())
-                .sampler(1, &self.sampler)
-                .uniform(2, &self.uniform_buffer),
-        );
-
-        let pipeline = &self.pipeline;
         let uniform_buffer = &self.uniform_buffer;
         let cubemap = self.cubemap.as_ref().unwrap();
 
         // Share the cubemap with other render passes (like skybox)
         graph_ctx.add_shared_resource("environment_cubemap", cubemap.clone());
 
-        // cubemap generation
-        for face_idx in 0..6 {
-            let face = match face_idx {
-                0 => CubeFace::PositiveX,
-                1 => CubeFace::NegativeX,
-                2 => CubeFace::PositiveY,
-                3 => CubeFace::NegativeY,
-                4 => CubeFace::PositiveZ,
-                5 => CubeFace::NegativeZ,
-                _ => unreachable!(),
-            };
+        // cubemap generation, each face is drawn either from the panorama or its own image
+        let face_descriptors: Vec<DescriptorSet> = match environment.source() {
+            EnvironmentSource::Equirect(_) => {
+                let hdri = environment
+                    .get_hdri_texture(&game_ctx.assets)
+                    .expect("the resolution came from the loaded panorama");
+                let descriptor = rcx.device().build_descriptor_set(
+                    DescriptorSet::builder(&self.layout)
+                        .texture_view(0, &hdri.create_view())
+                        .sampler(1, &self.sampler)
+                        .uniform(2, uniform_buffer),
+                );
+                vec![descriptor; 6]
+            }
+            EnvironmentSource::Faces(_) => environment
+                .get_face_textures(&game_ctx.assets)
+                .expect("the resolution came from the loaded faces")
+                .iter()
+                .map(|face| {
+                    let decode_srgb = matches!(
+                        face.format(),
+                        TextureFormat::RGBA8
+                            | TextureFormat::BGRA8
+                            | TextureFormat::BC1
+                            | TextureFormat::BC3
+                            | TextureFormat::BC7
+                    );
+                    let uniforms = rcx.device().create_uniform_buffer(&FaceUniforms {
+                        decode_srgb: decode_srgb as u32,
+                        _padding: [0; 3],
+                    });
+                    rcx.device().build_descriptor_set(
+                        DescriptorSet::builder(&self.layout)
+                            .texture_view(0, &face.create_view())
+                            .sampler(1, &self.sampler)
+                            .uniform(2, &uniforms),
+                    )
+                })
+                .collect(),
+        };
+        let pipeline = match environment.source() {
+            EnvironmentSource::Equirect(_) => &self.pipeline,
+            EnvironmentSource::Faces(_) => &self.faces_pipeline,
+        };
 
-            let face_view = cubemap.create_face_view(face, 0);
+        for (face_idx, (face, descriptor)) in CUBE_FACES.iter().zip(&face_descriptors).enumerate() {
+            let face_view = cubemap.create_face_view(*face, 0);
 
             frame
                 .render(
                     RenderOptions {
-                        label: Some("HDRI to cubemap"),
+                        label: Some("environment to cubemap"),
                         color_targets: &[RenderTarget::Texture(face_view)],
                         depth_target: None,
                         clear_color: Some([0.0, 0.0, 0.0, 1.0]),
                         clear_depth: None,
+                        discard_depth: false,
                     },
                     |mut fb| {
                         fb.use_pipeline(pipeline)
-                            .bind_descriptor_set(0, &descrptor)
-                            .draw(0..3, face_idx);
+                            .bind_descriptor_set(0, descriptor)
+                            .draw(0..3, face_idx as u32);
                     },
                 )
                 .expect("failed to draw cubemap");
@@ -415,6 +475,7 @@ impl RenderNode for EnvironmentPrePass {
                         depth_target: None,
                         clear_color: Some([0.0, 0.0, 0.0, 1.0]),
                         clear_depth: None,
+                        discard_depth: false,
                     },
                     |mut fb| {
                         fb.use_pipeline(irradiance_pipeline)
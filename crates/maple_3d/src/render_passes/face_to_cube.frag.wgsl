@@ -0,0 +1,29 @@
+#include "maple/tonemap.wgsl"
+
+@group(0) @binding(0) var face_texture: texture_2d<f32>;
+@group(0) @binding(1) var face_sampler: sampler;
+
+struct FaceUniforms {
+    // 1 if the face is an srgb image stored in a linear format
+    decode_srgb: u32,
+}
+
+@group(0) @binding(2) var<uniform> uniforms: FaceUniforms;
+
+struct FragmentInput {
+    @location(0) local_pos: vec3<f32>,
+    @location(1) face_index: u32,
+}
+
+@fragment
+fn main(in: FragmentInput) -> @location(0) vec4<f32> {
+    // the top row of the image is the top row of the cube face
+    let uv = vec2<f32>(in.local_pos.x * 0.5 + 0.5, 0.5 - in.local_pos.y * 0.5);
+    var color = textureSampleLevel(face_texture, face_sampler, uv, 0.0).rgb;
+
+    if uniforms.decode_srgb == 1u {
+        color = srgb_to_linear(color);
+    }
+
+    return vec4<f32>(color, 1.0);
+}
@@ -1,3 +1,5 @@
+#include "maple/common.wgsl"
+
 @group(0) @binding(0) var environment_map: texture_cube<f32>;
 @group(0) @binding(1) var environment_sampler: sampler;
 
@@ -6,8 +8,6 @@ struct FragmentInput {
     @location(1) face_index: u32,
 }
 
-const PI: f32 = 3.14159265359;
-
 fn get_cube_direction(uv: vec2<f32>, face: u32) -> vec3<f32> {
     // Convert UV (-1 to 1) to 3D direction based on cube face
     let u = uv.x;
@@ -1,9 +1,14 @@
 use bytemuck::{Pod, Zeroable};
-use maple_engine::{GameContext, asset::AssetId};
+use maple_engine::{
+    GameContext,
+    asset::AssetId,
+    scene::{NodeHandle, NodeId},
+};
 use maple_renderer::{
     core::{
-        Buffer, DescriptorBindingType, DescriptorSet, DescriptorSetLayoutDescriptor, Frame,
-        RenderContext, StageFlags,
+        Buffer, ComputePipeline, ComputePipelineCreateInfo, ComputeShaderSource,
+        DescriptorBindingType, DescriptorSet, DescriptorSetLayoutDescriptor,
+        DrawIndexedIndirectArgs, Frame, RenderContext, StageFlags,
         context::RenderOptions,
         descriptor_set::DescriptorSetLayout,
         pipeline::RenderPipeline,
@@ -13,31 +18,165 @@ use maple_renderer::{
     },
     render_graph::{
         graph::{RenderGraphContext, Stage},
-        node::{RenderNode, RenderTarget},
+        node::RenderNode,
     },
     types::Dimensions,
 };
 
 use crate::{
-    assets::{material::PassInfo, mesh::Mesh3D},
+    assets::{
+        material::{AlphaMode, PassInfo},
+        mesh::Mesh3D,
+    },
     math::Frustum,
     nodes::{
         camera::{Camera3D, Camera3DBufferData},
         environment::Environment,
         mesh_instance::Mesh3DUniformBufferData,
     },
-    render_passes::collect_mesh::{BundledMeshes, MeshBundle},
+    render_passes::{
+        collect_mesh::{BundledMeshes, CollectMesh, MeshBundle},
+        deferred::GBuffer,
+        scene_textures::scene_target,
+        transparency::SortedTriangles,
+    },
 };
 
-pub const MAX_MESH: usize = 1024;
+/// the most mesh instances a pass draws in a frame, each copy of an instanced model counts
+pub const MAX_MESH: usize = 16384;
+
+/// what the scene is cleared to when there is no environment to draw a skybox
+pub(crate) const BACKGROUND_COLOR: [f32; 4] = [0.01, 0.01, 0.01, 1.0];
+
+/// group 0 of the pbr shaders, the scene, the camera and the image based lighting maps
+pub(crate) struct SceneDescriptor {
+    pub(crate) layout: DescriptorSetLayout,
+    scene_buffer: Buffer<SceneData>,
+    camera_data_buffer: Buffer<Camera3DBufferData>,
+    irradiance_sampler: Sampler,
+    prefilter_sampler: Sampler,
+    brdf_lut_sampler: Sampler,
+}
+
+impl SceneDescriptor {
+    /// the layout and buffers of group 0 of the pbr shaders
+    pub(crate) fn new(rcx: &RenderContext) -> Self {
+        let scene_layout =
+            rcx.device()
+                .create_descriptor_set_layout(DescriptorSetLayoutDescriptor {
+                    label: Some("scene layout"),
+                    visibility: StageFlags::VERTEX | StageFlags::FRAGMENT,
+                    layout: &[
+                        DescriptorBindingType::UniformBuffer,
+                        DescriptorBindingType::UniformBuffer,
+                        DescriptorBindingType::TextureViewCube { filterable: true },
+                        DescriptorBindingType::Sampler { filtering: true },
+                        DescriptorBindingType::TextureViewCube { filterable: true },
+                        DescriptorBindingType::Sampler { filtering: true },
+                        DescriptorBindingType::TextureView { filterable: false },
+                        DescriptorBindingType::Sampler { filtering: false },
+                    ],
+                });
+
+        // buffers
+        let scene_buffer = rcx
+            .device()
+            .create_uniform_buffer(&SceneData::default().ambient(1.0).ibl_strength(1.0));
+        let camera_buffer = rcx
+            .device()
+            .create_uniform_buffer(&Camera3DBufferData::default());
+
+        // Create sampler for irradiance map
+        let irradiance_sampler = rcx.device().create_sampler(SamplerOptions {
+            mode_u: TextureMode::ClampToEdge,
+            mode_v: TextureMode::ClampToEdge,
+            mode_w: TextureMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            compare: None,
+        });
+
+        let prefilter_sampler = rcx.device().create_sampler(SamplerOptions {
+            mode_u: TextureMode::ClampToEdge,
+            mode_v: TextureMode::ClampToEdge,
+            mode_w: TextureMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            compare: None,
+        });
+
+        let brdf_lut_sampler = rcx.device().create_sampler(SamplerOptions {
+            mode_u: TextureMode::ClampToEdge,
+            mode_v: TextureMode::ClampToEdge,
+            mode_w: TextureMode::ClampToEdge,
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Nearest,
+            compare: None,
+        });
+
+        Self {
+            layout: scene_layout,
+            scene_buffer,
+            camera_data_buffer: camera_buffer,
+            irradiance_sampler,
+            prefilter_sampler,
+            brdf_lut_sampler,
+        }
+    }
+
+    /// write the camera and environment of the frame and bind them with the ibl maps of the
+    /// environment pass
+    pub(crate) fn bind(
+        &self,
+        rcx: &RenderContext,
+        graph_ctx: &RenderGraphContext,
+        camera: &Camera3D,
+        environments: &[NodeHandle<'_, Environment>],
+    ) -> DescriptorSet {
+        // Get IBL strength from environment (default to 0.0 if there isnt any)
+        let ibl_strength = environments
+            .first()
+            .map(|env| env.read().ibl_strength())
+            .unwrap_or(0.0);
+
+        // Update scene buffer with current IBL strength
+        let scene_buffer_data = SceneData::default()
+            .ambient(0.01)
+            .ibl_strength(ibl_strength);
+        rcx.queue()
+            .write_buffer(&self.scene_buffer, &scene_buffer_data);
+        rcx.queue().write_buffer(
+            &self.camera_data_buffer,
+            &camera.get_buffer_data(rcx.aspect_ratio()),
+        );
+
+        // Get irradiance map from graph context, or use default black cubemap
+        let default_textures = rcx.get_default_texture();
+        let irradiance_map = graph_ctx
+            .get_shared_resource::<TextureCube>("irradiance_cubemap")
+            .unwrap_or(&default_textures.irradiance_cubemap);
 
-struct SceneDescriptor {
-    pub layout: DescriptorSetLayout,
-    pub scene_buffer: Buffer<SceneData>,
-    pub camera_data_buffer: Buffer<Camera3DBufferData>,
-    pub irradiance_sampler: Sampler,
-    pub prefilter_sampler: Sampler,
-    pub brdf_lut_sampler: Sampler,
+        let prefilter_map = graph_ctx
+            .get_shared_resource::<TextureCube>("prefilter_cubemap")
+            .unwrap_or(&default_textures.prefilter_cubemap);
+
+        let brdf_lut_map = graph_ctx
+            .get_shared_resource::<Texture>("brdf_lut")
+            .unwrap_or(&default_textures.brdf_lut);
+
+        // Build scene descriptor set with irradiance map
+        rcx.device().build_descriptor_set(
+            DescriptorSet::builder(&self.layout)
+                .uniform(0, &self.scene_buffer)
+                .uniform(1, &self.camera_data_buffer)
+                .texture_view(2, &irradiance_map.create_view())
+                .sampler(3, &self.irradiance_sampler)
+                .texture_view(4, &prefilter_map.create_view())
+                .sampler(5, &self.prefilter_sampler)
+                .texture_view(6, &brdf_lut_map.create_view())
+                .sampler(7, &self.brdf_lut_sampler),
+        )
+    }
 }
 
 #[derive(Default, Debug, Pod, Zeroable, Clone, Copy)]
@@ -61,23 +200,59 @@ impl SceneData {
     }
 }
 
-struct PipelineBatch {
-    material_batches: Vec<MaterialBatch>,
-    pipeline: RenderPipeline,
+pub(crate) struct PipelineBatch {
+    pub(crate) material_batches: Vec<MaterialBatch>,
+    pub(crate) pipeline: RenderPipeline,
     pipeline_id: AssetId,
 }
 
-struct MaterialBatch {
-    mesh_batches: Vec<MeshBatch>,
-    material_descriptor: DescriptorSet,
+pub(crate) struct MaterialBatch {
+    pub(crate) mesh_batches: Vec<MeshBatch>,
+    pub(crate) material_descriptor: DescriptorSet,
     material_id: AssetId,
 }
 
-struct MeshBatch {
-    mesh: Mesh3D,
+pub(crate) struct MeshBatch {
+    pub(crate) mesh: Mesh3D,
     mesh_id: AssetId,
-    start: u32,
-    end: u32,
+    /// the position of the batch among all mesh batches of the frame, its indirect draw
+    index: usize,
+    /// the node of each instance
+    pub(crate) nodes: Vec<NodeId>,
+    pub(crate) start: u32,
+    pub(crate) end: u32,
+}
+
+#[derive(Default, Debug, Pod, Zeroable, Clone, Copy)]
+#[repr(C)]
+struct CullParams {
+    planes: [[f32; 4]; 6],
+    instance_count: u32,
+    _padding: [u32; 3],
+}
+
+#[derive(Default, Debug, Pod, Zeroable, Clone, Copy)]
+#[repr(C)]
+pub(crate) struct CullInstance {
+    /// w is 1 if the instance can be culled
+    aabb_min: [f32; 4],
+    aabb_max: [f32; 4],
+    batch: u32,
+    _padding: [u32; 3],
+}
+
+/// frustum culling on the gpu, a compute shader writes the visible instances of each batch into
+/// [`Self::mesh_buffer`] and their count into the indirect draw of the batch
+///
+/// needs indirect draws that start at an instance, the shadow passes still cull on the cpu
+struct GpuCulling {
+    pipeline: ComputePipeline,
+    descriptor: DescriptorSet,
+    params: Buffer<CullParams>,
+    instances: Buffer<[CullInstance]>,
+    draws: Buffer<[DrawIndexedIndirectArgs]>,
+    /// the culled instances the main pass draws
+    mesh_descriptor: DescriptorSet,
 }
 
 struct TextureCache {
@@ -94,29 +269,40 @@ pub struct MainPass {
     texture_cache: Option<TextureCache>,
     mesh_buffer: Buffer<[Mesh3DUniformBufferData]>,
     mesh_descriptor: DescriptorSet,
+    gpu_culling: Option<GpuCulling>,
+    sorted_triangles: SortedTriangles,
 }
 
 impl MainPass {
-    pub fn pass_info() -> PassInfo {
+    /// the targets meshes are drawn into, the msaa samples come from the quality settings
+    pub fn pass_info(rcx: &RenderContext) -> PassInfo {
         PassInfo {
             color_formats: vec![TextureFormat::RGBA16Float, TextureFormat::RGBA8],
-            sample_count: 4,
+            sample_count: rcx.quality().msaa_samples.max(1),
         }
     }
 
-    fn cull_and_batch_meshes(
-        meshes: &Vec<MeshBundle>,
-        frustum: Frustum,
-    ) -> (Vec<PipelineBatch>, Vec<Mesh3DUniformBufferData>) {
+    /// batch the meshes inside the frustum by the pipeline each is drawn with, every mesh with
+    /// no frustum so the gpu culls them
+    pub(crate) fn cull_and_batch_meshes<'a>(
+        meshes: impl IntoIterator<Item = (&'a MeshBundle, &'a RenderPipeline)>,
+        frustum: Option<&Frustum>,
+    ) -> (
+        Vec<PipelineBatch>,
+        Vec<Mesh3DUniformBufferData>,
+        Vec<CullInstance>,
+    ) {
         let mut batch_pipelines: Vec<PipelineBatch> = Vec::new();
         let mut mesh_buffer: Vec<Mesh3DUniformBufferData> = Vec::new();
+        let mut cull_instances: Vec<CullInstance> = Vec::new();
+        let mut batch_count = 0;
 
-        for bundle in meshes {
-            if !frustum.intersects_aabb(&bundle.world_aabb) {
+        for (bundle, pipeline) in meshes {
+            if frustum.is_some_and(|frustum| !bundle.in_frustum(frustum)) {
                 continue;
             }
 
-            let pipeline_id = bundle.pipeline.id.clone();
+            let pipeline_id = pipeline.id.clone();
             let material_id = bundle.material_id.clone();
             let mesh_id = bundle.mesh_id.clone();
 
@@ -126,7 +312,7 @@ impl MainPass {
             if batch_pipelines.last().map(|b| &b.pipeline_id) != Some(&pipeline_id) {
                 batch_pipelines.push(PipelineBatch {
                     material_batches: Vec::new(),
-                    pipeline: bundle.pipeline.clone(),
+                    pipeline: pipeline.clone(),
                     pipeline_id,
                 })
             }
@@ -141,21 +327,125 @@ impl MainPass {
             }
             let bm = bp.material_batches.last_mut().unwrap();
 
+            let aabb = &bundle.world_aabb;
+            let mut cull_instance = CullInstance {
+                aabb_min: [
+                    aabb.min.x,
+                    aabb.min.y,
+                    aabb.min.z,
+                    bundle.frustum_culled as u32 as f32,
+                ],
+                aabb_max: [aabb.max.x, aabb.max.y, aabb.max.z, 0.0],
+                ..Default::default()
+            };
+
             if let Some(last) = bm.mesh_batches.last_mut() {
                 if last.mesh_id == mesh_id && last.end == instance_index {
                     last.end = instance_index + 1;
+                    last.nodes.push(bundle.node);
+                    cull_instance.batch = last.index as u32;
+                    cull_instances.push(cull_instance);
                     continue;
                 }
             }
+            cull_instance.batch = batch_count as u32;
+            cull_instances.push(cull_instance);
             bm.mesh_batches.push(MeshBatch {
                 mesh: bundle.mesh.clone(),
                 mesh_id,
+                index: batch_count,
+                nodes: vec![bundle.node],
                 start: instance_index,
                 end: instance_index + 1,
-            })
+            });
+            batch_count += 1;
+        }
+
+        (batch_pipelines, mesh_buffer, cull_instances)
+    }
+
+    fn gpu_culling(
+        rcx: &RenderContext,
+        mesh_layout: &DescriptorSetLayout,
+        mesh_buffer: &Buffer<[Mesh3DUniformBufferData]>,
+        joint_buffer: &Buffer<[[[f32; 4]; 4]]>,
+    ) -> Option<GpuCulling> {
+        if !rcx.device().supports_indirect_first_instance() {
+            log::info!("indirect draws can't start at an instance, culling meshes on the cpu");
+            return None;
         }
 
-        (batch_pipelines, mesh_buffer)
+        let layout = rcx
+            .device()
+            .create_descriptor_set_layout(DescriptorSetLayoutDescriptor {
+                label: Some("cull layout"),
+                visibility: StageFlags::COMPUTE,
+                layout: &[
+                    DescriptorBindingType::UniformBuffer,
+                    DescriptorBindingType::Storage {
+                        read_only: true,
+                        has_dynamic_offset: false,
+                        min_size: None,
+                    }, // instance bounds
+                    DescriptorBindingType::Storage {
+                        read_only: true,
+                        has_dynamic_offset: false,
+                        min_size: None,
+                    }, // every instance
+                    DescriptorBindingType::Storage {
+                        read_only: false,
+                        has_dynamic_offset: false,
+                        min_size: None,
+                    }, // visible instances
+                    DescriptorBindingType::Storage {
+                        read_only: false,
+                        has_dynamic_offset: false,
+                        min_size: None,
+                    }, // draws
+                ],
+            });
+        let pipeline = rcx
+            .device()
+            .create_compute_pipeline(ComputePipelineCreateInfo {
+                label: Some("gpu culling"),
+                layout: rcx
+                    .device()
+                    .create_pipeline_layout(std::slice::from_ref(&layout)),
+                shader: rcx
+                    .device()
+                    .create_compute_shader(ComputeShaderSource::Wgsl(include_str!("./cull.wgsl"))),
+                entry_point: None,
+            });
+
+        let params = rcx.device().create_uniform_buffer(&CullParams::default());
+        let instances = rcx.device().create_sized_storage_buffer(MAX_MESH);
+        let draws = rcx.device().create_indirect_buffer(MAX_MESH);
+        let culled_mesh_buffer = rcx
+            .device()
+            .create_sized_storage_buffer::<Mesh3DUniformBufferData>(MAX_MESH);
+
+        let descriptor = rcx.device().build_descriptor_set(
+            DescriptorSet::builder(&layout)
+                .uniform(0, &params)
+                .storage(1, &instances)
+                .storage(2, mesh_buffer)
+                .storage(3, &culled_mesh_buffer)
+                .storage(4, &draws),
+        );
+        let mesh_descriptor = rcx.device().build_descriptor_set(
+            DescriptorSet::builder(mesh_layout)
+                .storage(0, &culled_mesh_buffer)
+                .storage(1, joint_buffer),
+        );
+
+        Some(GpuCulling {
+            pipeline,
+            descriptor,
+            params,
+            instances,
+            draws,
+            mesh_descriptor,
+        })
     }
 }
 
@@ -171,93 +461,27 @@ impl RenderNode for MainPass {
         Stage::Opaque
     }
 
-    fn setup(rcx: &RenderContext, _gcx: &mut RenderGraphContext) -> Self {
+    fn setup(rcx: &RenderContext, gcx: &mut RenderGraphContext) -> Self {
         // layouts
-        let mesh_layout = rcx.get_or_create_layout(DescriptorSetLayoutDescriptor {
-            label: Some("Mesh"),
-            visibility: StageFlags::VERTEX,
-            layout: &[
-                DescriptorBindingType::Storage {
-                    read_only: true,
-                    has_dynamic_offset: false,
-                    min_size: None,
-                }, // transforms
-            ],
-        });
-        let mesh_buffer = rcx
-            .device()
-            .create_sized_storage_buffer(size_of::<Mesh3DUniformBufferData>() * MAX_MESH);
-        let mesh_descriptor = rcx
-            .device()
-            .build_descriptor_set(&DescriptorSet::builder(&mesh_layout).storage(0, &mesh_buffer));
-
-        let scene_layout =
-            rcx.device()
-                .create_descriptor_set_layout(DescriptorSetLayoutDescriptor {
-                    label: Some("scene layout"),
-                    visibility: StageFlags::VERTEX | StageFlags::FRAGMENT,
-                    layout: &[
-                        DescriptorBindingType::UniformBuffer,
-                        DescriptorBindingType::UniformBuffer,
-                        DescriptorBindingType::TextureViewCube { filterable: true },
-                        DescriptorBindingType::Sampler { filtering: true },
-                        DescriptorBindingType::TextureViewCube { filterable: true },
-                        DescriptorBindingType::Sampler { filtering: true },
-                        DescriptorBindingType::TextureView { filterable: false },
-                        DescriptorBindingType::Sampler { filtering: false },
-                    ],
-                });
-
-        // buffers
-        let scene_buffer = rcx
-            .device()
-            .create_uniform_buffer(&SceneData::default().ambient(1.0).ibl_strength(1.0));
-        let camera_buffer = rcx
-            .device()
-            .create_uniform_buffer(&Camera3DBufferData::default());
-
-        // Create sampler for irradiance map
-        let irradiance_sampler = rcx.device().create_sampler(SamplerOptions {
-            mode_u: TextureMode::ClampToEdge,
-            mode_v: TextureMode::ClampToEdge,
-            mode_w: TextureMode::ClampToEdge,
-            mag_filter: FilterMode::Linear,
-            min_filter: FilterMode::Linear,
-            compare: None,
-        });
-
-        let prefilter_sampler = rcx.device().create_sampler(SamplerOptions {
-            mode_u: TextureMode::ClampToEdge,
-            mode_v: TextureMode::ClampToEdge,
-            mode_w: TextureMode::ClampToEdge,
-            mag_filter: FilterMode::Linear,
-            min_filter: FilterMode::Linear,
-            compare: None,
-        });
-
-        let brdf_lut_sampler = rcx.device().create_sampler(SamplerOptions {
-            mode_u: TextureMode::ClampToEdge,
-            mode_v: TextureMode::ClampToEdge,
-            mode_w: TextureMode::ClampToEdge,
-            mag_filter: FilterMode::Nearest,
-            min_filter: FilterMode::Nearest,
-            compare: None,
-        });
+        let mesh_layout = CollectMesh::mesh_layout(rcx);
+        let mesh_buffer = rcx.device().create_sized_storage_buffer(MAX_MESH);
+        let joint_buffer = CollectMesh::joint_buffer(gcx);
+        let mesh_descriptor = rcx.device().build_descriptor_set(
+            DescriptorSet::builder(&mesh_layout)
+                .storage(0, &mesh_buffer)
+                .storage(1, &joint_buffer),
+        );
+        let gpu_culling = Self::gpu_culling(rcx, &mesh_layout, &mesh_buffer, &joint_buffer);
 
-        let scene_data = SceneDescriptor {
-            layout: scene_layout.clone(),
-            scene_buffer,
-            camera_data_buffer: camera_buffer,
-            irradiance_sampler,
-            prefilter_sampler,
-            brdf_lut_sampler,
-        };
+        let scene_data = SceneDescriptor::new(rcx);
 
         Self {
             scene_data,
             texture_cache: None,
             mesh_buffer,
             mesh_descriptor,
+            gpu_culling,
+            sorted_triangles: SortedTriangles::default(),
         }
     }
     fn draw(
@@ -309,54 +533,22 @@ impl RenderNode for MainPass {
             Frustum::from_view_proj(&vp)
         };
 
-        let scene_data = &self.scene_data;
-
-        // Get IBL strength from environment (default to 0.0 if there isnt any)
-        let ibl_strength = environments
-            .first()
-            .map(|env| env.read().ibl_strength())
-            .unwrap_or(0.0);
+        // the deferred lighting pass already drew the opaque meshes, only what it couldn't draw is
+        // added on top
+        let deferred = graph_ctx
+            .get_shared_resource::<GBuffer>("gbuffer")
+            .is_some();
 
         // if no environment then we need to clear the screen since no skybox was rendered
-        let clear_color = if environments.is_empty() {
-            Some([0.01, 0.01, 0.01, 1.0])
+        let clear_color = if environments.is_empty() && !deferred {
+            Some(BACKGROUND_COLOR)
         } else {
             None
         };
 
-        // Update scene buffer with current IBL strength
-        let scene_buffer_data = SceneData::default()
-            .ambient(0.01)
-            .ibl_strength(ibl_strength);
-        rcx.queue()
-            .write_buffer(&scene_data.scene_buffer, &scene_buffer_data);
-
-        // Get irradiance map from graph context, or use default black cubemap
-        let default_textures = rcx.get_default_texture();
-        let irradiance_map = graph_ctx
-            .get_shared_resource::<TextureCube>("irradiance_cubemap")
-            .unwrap_or(&default_textures.irradiance_cubemap);
-
-        let prefilter_map = graph_ctx
-            .get_shared_resource::<TextureCube>("prefilter_cubemap")
-            .unwrap_or(&default_textures.prefilter_cubemap);
-
-        let brdf_lut_map = graph_ctx
-            .get_shared_resource::<Texture>("brdf_lut")
-            .unwrap_or(&default_textures.brdf_lut);
-
-        // Build scene descriptor set with irradiance map
-        let scene_set = rcx.device().build_descriptor_set(
-            DescriptorSet::builder(&scene_data.layout)
-                .uniform(0, &scene_data.scene_buffer)
-                .uniform(1, &scene_data.camera_data_buffer)
-                .texture_view(2, &irradiance_map.create_view())
-                .sampler(3, &scene_data.irradiance_sampler)
-                .texture_view(4, &prefilter_map.create_view())
-                .sampler(5, &scene_data.prefilter_sampler)
-                .texture_view(6, &brdf_lut_map.create_view())
-                .sampler(7, &scene_data.brdf_lut_sampler),
-        );
+        let scene_set = self
+            .scene_data
+            .bind(rcx, graph_ctx, &camera.read(), &environments);
 
         let Some(light_set) =
             (match graph_ctx.get_shared_resource::<DescriptorSet>("light_descriptor_set") {
@@ -369,40 +561,103 @@ impl RenderNode for MainPass {
             return;
         };
 
-        rcx.queue().write_buffer(
-            &scene_data.camera_data_buffer,
-            &camera.read().get_buffer_data(rcx.aspect_ratio()),
-        );
-
         let bundles = graph_ctx
             .get_shared_resource::<BundledMeshes>("mesh_bundles")
             .unwrap();
-        let (batches, buffer_data) = Self::cull_and_batch_meshes(&bundles.meshes, camera_frustum);
+        let cpu_frustum = self.gpu_culling.is_none().then_some(&camera_frustum);
+        // blended meshes are drawn last, farthest first
+        let transparent = self.sorted_triangles.sort(
+            rcx,
+            game_ctx,
+            &bundles.meshes,
+            camera.read().transform.render_space().position(),
+        );
+        let forward = bundles
+            .meshes
+            .iter()
+            .filter(|bundle| bundle.gbuffer_pipeline.is_none())
+            .filter(|bundle| bundle.alpha_mode != AlphaMode::Blend)
+            .chain(&transparent)
+            .map(|bundle| (bundle, &bundle.pipeline));
+        let (batches, buffer_data, cull_instances) =
+            Self::cull_and_batch_meshes(forward, cpu_frustum);
 
         rcx.queue()
             .write_buffer_slice(&self.mesh_buffer, &buffer_data);
 
+        if let Some(culling) = &self.gpu_culling {
+            let mut planes = [[0.0; 4]; 6];
+            for (plane, frustum_plane) in planes.iter_mut().zip(&camera_frustum.planes) {
+                *plane = frustum_plane
+                    .normal
+                    .extend(frustum_plane.distance)
+                    .to_array();
+            }
+            rcx.queue().write_buffer(
+                &culling.params,
+                &CullParams {
+                    planes,
+                    instance_count: cull_instances.len() as u32,
+                    _padding: [0; 3],
+                },
+            );
+            rcx.queue()
+                .write_buffer_slice(&culling.instances, &cull_instances);
+
+            // the compute shader counts the visible instances of each batch
+            let draws: Vec<DrawIndexedIndirectArgs> = batches
+                .iter()
+                .flat_map(|pipeline| &pipeline.material_batches)
+                .flat_map(|material| &material.mesh_batches)
+                .map(|batch| DrawIndexedIndirectArgs {
+                    index_count: batch.mesh.get_index_buffer().len() as u32,
+                    first_instance: batch.start,
+                    ..Default::default()
+                })
+                .collect();
+            rcx.queue().write_buffer_slice(&culling.draws, &draws);
+
+            let workgroups = (cull_instances.len() as u32).div_ceil(64);
+            if workgroups > 0 {
+                frame.compute(Some("main pass culling"), |mut cb| {
+                    cb.use_pipeline(&culling.pipeline)
+                        .bind_descriptor_set(0, &culling.descriptor)
+                        .dispatch(workgroups, 1, 1);
+                });
+            }
+        }
+
+        // with gpu culling this counts the instances outside the frustum too
+        CollectMesh::record_draws(
+            game_ctx,
+            Self::label(),
+            batches
+                .iter()
+                .flat_map(|pipeline| &pipeline.material_batches)
+                .flat_map(|material| &material.mesh_batches)
+                .map(|batch| (&batch.mesh, batch.nodes.as_slice())),
+        );
+
         frame
             .render(
                 RenderOptions {
                     label: Some("Main Pass"),
                     color_targets: &[
-                        RenderTarget::MultiSampled {
-                            texture: targets.msaa_color.create_view(),
-                            resolve: targets.resolved_color.create_view(),
-                        },
-                        RenderTarget::MultiSampled {
-                            texture: targets.msaa_normal.create_view(),
-                            resolve: targets.resolved_normal.create_view(),
-                        },
+                        scene_target(&targets.msaa_color, &targets.resolved_color),
+                        scene_target(&targets.msaa_normal, &targets.resolved_normal),
                     ],
                     depth_target: Some(&targets.msaa_depth.create_view()),
                     clear_color,
-                    clear_depth: Some(1.0),
+                    clear_depth: (!deferred).then_some(1.0),
+                    discard_depth: false,
                 },
                 move |mut fb| {
+                    let mesh_descriptor = match &self.gpu_culling {
+                        Some(culling) => &culling.mesh_descriptor,
+                        None => &self.mesh_descriptor,
+                    };
                     fb.bind_descriptor_set(0, &scene_set)
-                        .bind_descriptor_set(1, &self.mesh_descriptor)
+                        .bind_descriptor_set(1, mesh_descriptor)
                         .bind_descriptor_set(2, light_set);
 
                     for pipeline_batch in batches {
@@ -413,8 +668,13 @@ impl RenderNode for MainPass {
 
                             for mesh_batch in material_batch.mesh_batches {
                                 fb.bind_vertex_buffer(&mesh_batch.mesh.get_vertex_buffer())
-                                    .bind_index_buffer(&mesh_batch.mesh.get_index_buffer())
-                                    .draw_indexed(mesh_batch.start..mesh_batch.end);
+                                    .bind_index_buffer(&mesh_batch.mesh.get_index_buffer());
+                                match &self.gpu_culling {
+                                    Some(culling) => {
+                                        fb.draw_indexed_indirect(&culling.draws, mesh_batch.index)
+                                    }
+                                    None => fb.draw_indexed(mesh_batch.start..mesh_batch.end),
+                                };
                             }
                         }
                     }
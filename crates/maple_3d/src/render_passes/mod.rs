@@ -1,10 +1,15 @@
 pub mod bloom;
 pub mod collect_mesh;
 pub mod composite_pass;
+pub mod deferred;
 pub mod directional_shadow_pass;
 pub mod environment;
 pub mod main_pass;
 pub mod point_shadow_pass;
 pub mod scene_textures;
+pub mod shadow_filter;
+pub mod shadow_lod;
 pub mod shadow_resource;
 pub mod skybox;
+pub mod spot_shadow_pass;
+pub mod transparency;
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use bytemuck::{Pod, Zeroable};
-use maple_engine::GameContext;
+use maple_engine::{GameContext, scene::NodeHandle};
 use maple_renderer::{
     core::{
         Buffer, CullMode, DepthBias, DepthCompare, DepthStencilOptions, DescriptorSetLayout, Frame,
@@ -21,14 +21,17 @@ use maple_renderer::{
 use crate::{
     math::{Frustum, Vertex},
     nodes::{
+        camera::Camera3D,
         mesh_instance::{Mesh3DUniformBufferData, MeshInstance3D},
         point_light::{PointLight, PointLightBuffer},
     },
     render_passes::{
-        collect_mesh,
+        collect_mesh::{self, CollectMesh},
         main_pass::MAX_MESH,
+        shadow_lod::{ShadowLevel, ShadowLod, ShadowSchedule},
         shadow_resource::{self, ShadowResource},
     },
+    visibility::active_camera,
 };
 
 /// Uniform buffer for point light shadow data
@@ -47,6 +50,9 @@ struct PointLightShadowUniform {
 /// 1. Getting the light's 6 view-projection matrices (one per cube face)
 /// 2. Rendering all meshes from each face's perspective
 /// 3. Storing depth values for shadow sampling in the main pass
+///
+/// lights that cover little of the screen render smaller shadows less often, see [`ShadowLod`].
+/// faces whose view and casters haven't changed since they were rendered are kept
 pub struct PointShadowPass {
     // Buffer for light shadow data
     light_buffer: Buffer<[PointLightShadowUniform]>,
@@ -58,11 +64,52 @@ pub struct PointShadowPass {
     pipeline: HashMap<CullMode, RenderPipeline>,
 
     mesh_buffers: HashMap<u32, Buffer<[Mesh3DUniformBufferData]>>,
+    joint_buffer: Buffer<[[[f32; 4]; 4]]>,
     mesh_layout: DescriptorSetLayout,
     mesh_descriptors: HashMap<u32, DescriptorSet>,
+
+    /// the cube array the schedule's layers are in
+    rendered_into: Option<TextureCubeArray>,
+    schedule: ShadowSchedule,
 }
 
-impl PointShadowPass {}
+impl PointShadowPass {
+    /// the level of each light and the width its shadow is rendered at in faces `map_size` wide
+    fn shadow_levels(
+        game_ctx: &GameContext,
+        lights: &[NodeHandle<'_, PointLight>],
+        map_size: u32,
+    ) -> Vec<(ShadowLevel, u32)> {
+        let lod = match game_ctx.has_resource::<ShadowLod>() {
+            true => game_ctx.get_resource::<ShadowLod>().clone(),
+            false => ShadowLod::default(),
+        };
+        let camera = active_camera(&game_ctx.scene)
+            .and_then(|camera| game_ctx.scene.get::<Camera3D>(camera))
+            .map(|camera| {
+                let camera = camera.read();
+                (camera.transform.world_space().position(), camera.fov)
+            });
+
+        lights
+            .iter()
+            .map(|light| {
+                let light = light.read();
+                let level = match camera {
+                    Some((eye, fov)) => {
+                        let radius = PointLight::calculate_far_plane(light.get_intensity(), 0.01);
+                        let center = light.transform.world_space().position();
+                        lod.level(ShadowLod::coverage(eye, fov, center, radius))
+                    }
+                    None => ShadowLevel::FULL,
+                };
+                let full = light.shadow_resolution.clamp(1, map_size);
+                let size = ((full as f32 * level.scale).round() as u32).clamp(1, full);
+                (level, size)
+            })
+            .collect()
+    }
+}
 
 impl RenderNode for PointShadowPass {
     fn label() -> &'static str
@@ -76,7 +123,7 @@ impl RenderNode for PointShadowPass {
         Stage::Shadow
     }
 
-    fn setup(rcx: &RenderContext, _gcx: &mut RenderGraphContext) -> Self {
+    fn setup(rcx: &RenderContext, gcx: &mut RenderGraphContext) -> Self {
         let shader = GraphicsShader {
             vertex: rcx
                 .device()
@@ -116,17 +163,8 @@ impl RenderNode for PointShadowPass {
         );
 
         // Get mesh descriptor layout
-        let mesh_layout = rcx.get_or_create_layout(DescriptorSetLayoutDescriptor {
-            label: Some("Mesh"),
-            visibility: StageFlags::VERTEX,
-            layout: &[
-                DescriptorBindingType::Storage {
-                    read_only: true,
-                    has_dynamic_offset: false,
-                    min_size: None,
-                }, // transforms
-            ],
-        });
+        let mesh_layout = CollectMesh::mesh_layout(rcx);
+        let joint_buffer = CollectMesh::joint_buffer(gcx);
 
         let shadow_alpha_layout = ShadowResource::shadow_layout(rcx);
 
@@ -171,8 +209,11 @@ impl RenderNode for PointShadowPass {
             light_descriptor,
             pipeline,
             mesh_descriptors: HashMap::default(),
+            joint_buffer,
             mesh_layout,
             mesh_buffers: HashMap::default(),
+            rendered_into: None,
+            schedule: ShadowSchedule::default(),
         }
     }
     fn draw(
@@ -195,7 +236,7 @@ impl RenderNode for PointShadowPass {
 
         // Get scene data
         let point_lights = scene.collect::<PointLight>();
-        let mesh_instances = scene.collect::<MeshInstance3D>();
+        let mesh_instances = scene.collect_visible::<MeshInstance3D>();
 
         if point_lights.is_empty() || mesh_instances.is_empty() {
             return;
@@ -212,11 +253,30 @@ impl RenderNode for PointShadowPass {
             return;
         };
 
+        // maps made again by the shadow resource start out empty
+        if self.rendered_into.as_ref() != Some(cube_array) {
+            self.rendered_into = Some(cube_array.clone());
+            self.schedule.clear();
+        }
+        self.schedule.next_frame();
+
+        // lights without a shadow aren't given layers
+        let shadow_indices = ShadowResource::shadow_indices(
+            point_lights.iter().map(|light| light.read().cast_shadows),
+        );
+        let map_size = cube_array.size();
+        let levels = Self::shadow_levels(game_ctx, &point_lights, map_size);
+
         let point_light_data = PointLightBuffer::from_lights(
             &point_lights
                 .iter()
+                .zip(&levels)
                 .enumerate()
-                .map(|(i, light)| light.read().get_buffered_data(i))
+                .map(|(i, (light, (_, size)))| {
+                    let mut data = light.read().get_buffered_data(shadow_indices[i]);
+                    data.shadow_scale = *size as f32 / map_size as f32;
+                    data
+                })
                 .collect::<Vec<_>>(),
         );
 
@@ -235,7 +295,7 @@ impl RenderNode for PointShadowPass {
                     .get_shadow_transformations()
                     .iter()
                     .map(|vp| {
-                        let light_pos = light.read().transform.world_space().position;
+                        let light_pos = light.read().transform.render_space().position();
                         PointLightShadowUniform {
                             view_projection: vp.to_cols_array_2d(),
                             light_pos: [light_pos.x, light_pos.y, light_pos.z, 0.0],
@@ -259,27 +319,53 @@ impl RenderNode for PointShadowPass {
 
         // Render each point light's cube map
         for (light_idx, light) in point_lights.iter().enumerate() {
+            let Some(shadow_index) = shadow_indices[light_idx] else {
+                continue;
+            };
             // Skip if light index exceeds array size
-            if (light_idx as u32) >= cube_array.array_layers() {
+            if (shadow_index as u32) >= cube_array.array_layers() {
                 break;
             }
 
+            let (level, size) = levels[light_idx];
+
             // Get view-projection matrices for all 6 cube faces
             let shadow_transforms = light.read().get_shadow_transformations();
+            let faces: Vec<_> = CubeFace::iter()
+                .zip(shadow_transforms.iter())
+                .map(|(face_idx, vp_matrix)| {
+                    let layer = light_idx as u32 * 6 + face_idx as u32;
+                    let face_frustum = Frustum::from_view_proj(vp_matrix);
+                    let (batches, data) =
+                        ShadowResource::cull_and_batch_meshes(&bundles.meshes, face_frustum);
+                    (face_idx, layer, batches, data)
+                })
+                .collect();
+
+            // the faces are kept or rendered together so they don't tear apart
+            let contents = faces
+                .iter()
+                .map(|(_, layer, batches, data)| {
+                    ShadowResource::contents(&light_data[*layer as usize], batches, data)
+                })
+                .collect::<Option<Vec<u64>>>()
+                .map(|faces| {
+                    faces
+                        .into_iter()
+                        .fold(0u64, |all, face| all.rotate_left(7) ^ face)
+                });
+            if !self.schedule.due(shadow_index, light.id(), level, contents) {
+                continue;
+            }
 
             // Render each cube face
-            for (face_idx, vp_matrix) in CubeFace::iter().zip(shadow_transforms.iter()) {
-                let layer = light_idx as u32 * 6 + face_idx as u32;
-                let face_frustum = Frustum::from_view_proj(vp_matrix);
-
-                let (batches, data) =
-                    ShadowResource::cull_and_batch_meshes(&bundles.meshes, face_frustum);
+            for (face_idx, layer, batches, data) in faces {
+                ShadowResource::record_draws(game_ctx, Self::label(), &batches);
 
-                let buffer = self.mesh_buffers.entry(face_idx as u32).or_insert_with(|| {
-                    rcx.device().create_sized_storage_buffer(
-                        size_of::<Mesh3DUniformBufferData>() * MAX_MESH,
-                    )
-                });
+                let buffer = self
+                    .mesh_buffers
+                    .entry(face_idx as u32)
+                    .or_insert_with(|| rcx.device().create_sized_storage_buffer(MAX_MESH));
 
                 rcx.queue().write_buffer_slice(buffer, &data);
 
@@ -288,12 +374,14 @@ impl RenderNode for PointShadowPass {
                         .entry(face_idx as u32)
                         .or_insert_with(|| {
                             rcx.device().build_descriptor_set(
-                                DescriptorSet::builder(&self.mesh_layout).storage(0, buffer),
+                                DescriptorSet::builder(&self.mesh_layout)
+                                    .storage(0, buffer)
+                                    .storage(1, &self.joint_buffer),
                             )
                         });
 
                 // Get depth texture for this cube face
-                let face_view = cube_array.create_face_view(light_idx as u32, face_idx);
+                let face_view = cube_array.create_face_view(shadow_index as u32, face_idx);
 
                 // Render meshes to this cube face
                 frame
@@ -304,6 +392,7 @@ impl RenderNode for PointShadowPass {
                             depth_target: Some(&face_view),
                             clear_color: None,
                             clear_depth: Some(1.0),
+                            discard_depth: false,
                         },
                         |mut fb| {
                             fb.bind_descriptor_set_with_offset(
@@ -311,7 +400,13 @@ impl RenderNode for PointShadowPass {
                                 light_descriptor,
                                 &[size_of::<PointLightShadowUniform>() as u32 * layer],
                             )
-                            .bind_descriptor_set(1, &descriptor);
+                            .bind_descriptor_set(1, &descriptor)
+                            .set_viewport(
+                                0.0,
+                                0.0,
+                                size as f32,
+                                size as f32,
+                            );
 
                             for material_batch in batches {
                                 // fb.bind_descriptor_set(3, &material_batch.descriptor);
@@ -1,4 +1,7 @@
 // Prefilter environment map for specular IBL using importance sampling
+
+#include "maple/brdf.wgsl"
+
 struct Uniforms {
     roughness: f32,
     face: u32,        // Which cubemap face (0-5)
@@ -11,50 +14,6 @@ struct Uniforms {
 @group(0) @binding(2) var output_texture: texture_storage_2d<rgba16float, write>;
 @group(0) @binding(3) var<uniform> uniforms: Uniforms;
 
-const PI: f32 = 3.14159265359;
-
-fn radical_inverse_vdc(bits_in: u32) -> f32 {
-    var bits = bits_in;
-    bits = (bits << 16u) | (bits >> 16u);
-    bits = ((bits & 0x55555555u) << 1u) | ((bits & 0xAAAAAAAAu) >> 1u);
-    bits = ((bits & 0x33333333u) << 2u) | ((bits & 0xCCCCCCCCu) >> 2u);
-    bits = ((bits & 0x0F0F0F0Fu) << 4u) | ((bits & 0xF0F0F0F0u) >> 4u);
-    bits = ((bits & 0x00FF00FFu) << 8u) | ((bits & 0xFF00FF00u) >> 8u);
-    return f32(bits) * 2.3283064365386963e-10;
-}
-
-fn hammersley(i: u32, N: u32) -> vec2<f32> {
-    return vec2<f32>(f32(i) / f32(N), radical_inverse_vdc(i));
-}
-
-fn importance_sample_ggx(xi: vec2<f32>, N: vec3<f32>, roughness: f32) -> vec3<f32> {
-    let a = roughness * roughness;
-    let phi = 2.0 * PI * xi.x;
-    let cos_theta = sqrt((1.0 - xi.y) / (1.0 + (a * a - 1.0) * xi.y));
-    let sin_theta = sqrt(1.0 - cos_theta * cos_theta);
-    
-    // Spherical to cartesian coordinates
-    var H: vec3<f32>;
-    H.x = cos(phi) * sin_theta;
-    H.y = sin(phi) * sin_theta;
-    H.z = cos_theta;
-    
-    // Tangent space to world space
-    let up = select(vec3<f32>(1.0, 0.0, 0.0), vec3<f32>(0.0, 0.0, 1.0), abs(N.z) < 0.999);
-    let tangent = normalize(cross(up, N));
-    let bitangent = cross(N, tangent);
-
-    let sample_vec = tangent * H.x + bitangent * H.y + N * H.z;
-    return normalize(sample_vec);
-}
-
-fn distribution_ggx(NdotH: f32, roughness: f32) -> f32 {
-    let a = roughness * roughness;
-    let a2 = a * a;
-    let denom = NdotH * NdotH * (a2 - 1.0) + 1.0;
-    return a2 / (PI * denom * denom);
-}
-
 // Convert UV coordinates and face index to 3D direction
 fn uv_to_direction(uv: vec2<f32>, face: u32) -> vec3<f32> {
     let u = uv.x * 2.0 - 1.0;
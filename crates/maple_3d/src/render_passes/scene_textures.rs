@@ -6,7 +6,7 @@ use maple_renderer::{
     },
     render_graph::{
         graph::{RenderGraphContext, Stage},
-        node::RenderNode,
+        node::{RenderNode, RenderTarget},
     },
     types::Dimensions,
 };
@@ -19,18 +19,21 @@ struct SceneTextureSet {
     msaa_depth: Texture,
 }
 
-impl SceneTextureSet {
-    fn create(rcx: &RenderContext, dimensions: Dimensions) -> Self {
-        let msaa_color = rcx.device().create_texture(TextureCreateInfo {
-            label: Some("scene_msaa_color"),
-            width: dimensions.width,
-            height: dimensions.height,
-            format: TextureFormat::RGBA16Float,
-            usage: TextureUsage::RENDER_ATTACHMENT,
-            sample_count: 4,
-            mip_level: 1,
-        });
+/// the target a pass draws into, `msaa` is resolved into `resolved` when msaa is on and is the
+/// same texture otherwise
+pub(crate) fn scene_target(msaa: &Texture, resolved: &Texture) -> RenderTarget {
+    if msaa.sample_count() > 1 {
+        RenderTarget::MultiSampled {
+            texture: msaa.create_view(),
+            resolve: resolved.create_view(),
+        }
+    } else {
+        RenderTarget::Texture(resolved.create_view())
+    }
+}
 
+impl SceneTextureSet {
+    fn create(rcx: &RenderContext, dimensions: Dimensions, sample_count: u32) -> Self {
         let resolved_color = rcx.device().create_texture(TextureCreateInfo {
             label: Some("scene_resolved_color"),
             width: dimensions.width,
@@ -41,16 +44,6 @@ impl SceneTextureSet {
             mip_level: 1,
         });
 
-        let msaa_normal = rcx.device().create_texture(TextureCreateInfo {
-            label: Some("scene_msaa_normal"),
-            width: dimensions.width,
-            height: dimensions.height,
-            format: TextureFormat::RGBA8,
-            usage: TextureUsage::RENDER_ATTACHMENT,
-            sample_count: 4,
-            mip_level: 1,
-        });
-
         let resolved_normal = rcx.device().create_texture(TextureCreateInfo {
             label: Some("scene_resolved_normal"),
             width: dimensions.width,
@@ -61,13 +54,39 @@ impl SceneTextureSet {
             mip_level: 1,
         });
 
+        // without msaa the passes draw straight into the resolved textures
+        let (msaa_color, msaa_normal) = if sample_count > 1 {
+            let msaa_color = rcx.device().create_texture(TextureCreateInfo {
+                label: Some("scene_msaa_color"),
+                width: dimensions.width,
+                height: dimensions.height,
+                format: TextureFormat::RGBA16Float,
+                usage: TextureUsage::RENDER_ATTACHMENT,
+                sample_count,
+                mip_level: 1,
+            });
+
+            let msaa_normal = rcx.device().create_texture(TextureCreateInfo {
+                label: Some("scene_msaa_normal"),
+                width: dimensions.width,
+                height: dimensions.height,
+                format: TextureFormat::RGBA8,
+                usage: TextureUsage::RENDER_ATTACHMENT,
+                sample_count,
+                mip_level: 1,
+            });
+            (msaa_color, msaa_normal)
+        } else {
+            (resolved_color.clone(), resolved_normal.clone())
+        };
+
         let msaa_depth = rcx.device().create_texture(TextureCreateInfo {
             label: Some("scene_msaa_depth"),
             width: dimensions.width,
             height: dimensions.height,
             format: TextureFormat::Depth32,
             usage: TextureUsage::RENDER_ATTACHMENT,
-            sample_count: 4,
+            sample_count,
             mip_level: 1,
         });
 
@@ -91,8 +110,12 @@ impl SceneTextureSet {
 
 /// Resource node that creates and manages the main scene render textures
 /// This allows other passes to share these textures without creating their own
+///
+/// the textures are the surface size scaled by the render scale of the quality settings, the
+/// msaa samples are read once in setup since pipelines are built for them
 pub struct SceneTextures {
     textures: SceneTextureSet,
+    sample_count: u32,
 }
 
 impl SceneTextures {}
@@ -110,10 +133,13 @@ impl RenderNode for SceneTextures {
     }
 
     fn setup(rcx: &RenderContext, gcx: &mut RenderGraphContext) -> Self {
-        let dimensions = rcx.surface_size();
-        let textures = SceneTextureSet::create(rcx, dimensions);
+        let sample_count = rcx.quality().msaa_samples.max(1);
+        let textures = SceneTextureSet::create(rcx, rcx.render_size(), sample_count);
         textures.share_to_graph(gcx);
-        Self { textures }
+        Self {
+            textures,
+            sample_count,
+        }
     }
 
     fn draw(
@@ -127,8 +153,8 @@ impl RenderNode for SceneTextures {
         self.textures.share_to_graph(gcx);
     }
 
-    fn resize(&mut self, rcx: &RenderContext, dimensions: Dimensions) {
-        let textures = SceneTextureSet::create(rcx, dimensions);
+    fn resize(&mut self, rcx: &RenderContext, _dimensions: Dimensions) {
+        let textures = SceneTextureSet::create(rcx, rcx.render_size(), self.sample_count);
         self.textures = textures;
     }
 }
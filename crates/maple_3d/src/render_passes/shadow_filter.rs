@@ -0,0 +1,44 @@
+//! how the edges of shadows are softened in the main pass
+//!
+//! every light that casts a shadow picks a [`ShadowFilter`]. the pcf filters compare the depth of
+//! the texels around a pixel against the shadow map and average them so the edge is blurred by a
+//! fixed amount. [`ShadowFilter::Pcss`] first searches the shadow map for what blocks the light and
+//! blurs more the further the blocker is from the surface, so shadows are sharp where objects touch
+//! the ground and soft away from it. how soft is set by the `size` of the light.
+//!
+//! pcss taps are spread over a poisson disk rotated for every pixel, trading banding for noise
+//!
+//! ```rust, ignore
+//! ctx.scene.spawn(
+//!     SpotLight::builder()
+//!         .shadow_filter(ShadowFilter::Pcss)
+//!         .size(0.3)
+//!         .build(),
+//! );
+//! ```
+
+/// the filter used on the shadow edges of a light, see the [module docs](self)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ShadowFilter {
+    /// a single compare, hard edges with visible texels
+    Hard,
+    /// average the compares over a 3 by 3 texel area
+    Pcf3x3,
+    /// average the compares over a 5 by 5 texel area
+    #[default]
+    Pcf5x5,
+    /// contact hardening soft shadows sized by the light
+    Pcss,
+}
+
+impl ShadowFilter {
+    /// the value the shader switches on, matches the `SHADOW_FILTER_` constants in shadow.wgsl
+    pub(crate) fn gpu_value(self) -> u32 {
+        match self {
+            Self::Hard => 0,
+            Self::Pcf3x3 => 1,
+            Self::Pcf5x5 => 2,
+            Self::Pcss => 3,
+        }
+    }
+}
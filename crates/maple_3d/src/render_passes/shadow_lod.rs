@@ -0,0 +1,273 @@
+//! cheaper shadows for lights that barely show up on screen
+//!
+//! a point light's screen coverage is how much of the screen height the sphere it lights takes
+//! up from the active camera. lights covering at least [`ShadowLod::full_coverage`] render their
+//! shadow at full resolution every frame. each halving of the coverage below that halves the
+//! resolution of the shadow and doubles the frames between its updates, down to
+//! [`ShadowLod::min_scale`] and up to [`ShadowLod::max_interval`]. lights with the same interval
+//! are spread out across frames so they don't all update at once.
+//!
+//! directional lights light the whole screen so they always update at their own resolution, spot
+//! lights only have one map to render so they do too
+//!
+//! a layer is not rendered again while the light and the meshes it can see are the same as when
+//! it was last rendered, so static lights over static scenes cost nothing after their first frame.
+//! skinned and morphed meshes can change without moving so a shadow that sees one is always due
+//!
+//! ```rust, ignore
+//! let mut lod = ctx.get_resource_mut::<ShadowLod>();
+//! lod.max_interval = 4;
+//! ```
+
+use glam::Vec3;
+use maple_engine::{prelude::Resource, scene::NodeId};
+
+/// how shadows of small and far away lights are reduced, see the [module docs](self)
+#[derive(Debug, Clone)]
+pub struct ShadowLod {
+    /// render every shadow at full resolution every frame when false
+    ///
+    /// Default: `true`
+    pub enabled: bool,
+    /// the fraction of the screen height a light has to cover for a full shadow
+    ///
+    /// Default: `0.5`
+    pub full_coverage: f32,
+    /// the smallest part of the shadow map a light renders into
+    ///
+    /// Default: `0.25`
+    pub min_scale: f32,
+    /// the most frames a shadow goes without being rendered
+    ///
+    /// Default: `8`
+    pub max_interval: u32,
+}
+
+impl Resource for ShadowLod {}
+
+impl Default for ShadowLod {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            full_coverage: 0.5,
+            min_scale: 0.25,
+            max_interval: 8,
+        }
+    }
+}
+
+/// the resolution and update rate of one light's shadow
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShadowLevel {
+    /// the part of the shadow map width the shadow is rendered into
+    pub scale: f32,
+    /// the shadow is rendered every this many frames
+    pub interval: u32,
+}
+
+impl ShadowLevel {
+    pub const FULL: Self = Self {
+        scale: 1.0,
+        interval: 1,
+    };
+}
+
+impl ShadowLod {
+    /// the fraction of the screen height a sphere of `radius` at `center` covers from a camera
+    /// at `eye` with a vertical field of view of `fov` degrees
+    pub fn coverage(eye: Vec3, fov: f32, center: Vec3, radius: f32) -> f32 {
+        let distance = eye.distance(center);
+        if distance <= radius {
+            return 1.0;
+        }
+        let half_height = distance * (fov.to_radians() * 0.5).tan();
+        (radius / half_height.max(f32::EPSILON)).min(1.0)
+    }
+
+    /// the level of a light covering `coverage` of the screen
+    pub fn level(&self, coverage: f32) -> ShadowLevel {
+        if !self.enabled || coverage >= self.full_coverage {
+            return ShadowLevel::FULL;
+        }
+
+        // one step per halving of the coverage
+        let steps = (self.full_coverage / coverage.max(f32::EPSILON))
+            .log2()
+            .floor()
+            .min(16.0) as u32;
+        let min_scale = self.min_scale.clamp(f32::EPSILON, 1.0);
+        ShadowLevel {
+            scale: 0.5f32.powi(steps as i32).max(min_scale),
+            interval: (1u32 << steps).clamp(1, self.max_interval.max(1)),
+        }
+    }
+}
+
+/// what one shadow map layer was last rendered with
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ScheduledLayer {
+    light: NodeId,
+    scale: f32,
+    /// see [`ShadowSchedule::due`]
+    contents: Option<u64>,
+}
+
+/// what each shadow map layer holds so only lights that are due are rendered again
+///
+/// layers whose light and casters haven't changed since they were rendered are kept as they are
+/// whatever their interval
+#[derive(Default)]
+pub(crate) struct ShadowSchedule {
+    frame: u64,
+    layers: Vec<Option<ScheduledLayer>>,
+}
+
+impl ShadowSchedule {
+    /// forget what every layer holds, when the maps were created again
+    pub(crate) fn clear(&mut self) {
+        self.layers.clear();
+    }
+
+    /// call once a frame before [`Self::due`]
+    pub(crate) fn next_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    /// true if `light` has to be rendered into `layer` this frame at `level`
+    ///
+    /// `contents` sums up everything the render depends on, see
+    /// [`super::shadow_resource::ShadowResource::contents`]. a layer rendered with the same
+    /// contents is never due again, `None` contents change every frame
+    pub(crate) fn due(
+        &mut self,
+        layer: usize,
+        light: NodeId,
+        level: ShadowLevel,
+        contents: Option<u64>,
+    ) -> bool {
+        if self.layers.len() <= layer {
+            self.layers.resize(layer + 1, None);
+        }
+
+        let current = ScheduledLayer {
+            light,
+            scale: level.scale,
+            contents,
+        };
+        let Some(scheduled) = &mut self.layers[layer] else {
+            self.layers[layer] = Some(current);
+            return true;
+        };
+
+        // the layer holds another light or resolution, it can't wait
+        if (scheduled.light, scheduled.scale) != (light, level.scale) {
+            *scheduled = current;
+            return true;
+        }
+
+        if contents.is_some() && scheduled.contents == contents {
+            return false;
+        }
+
+        // the layer offsets lights with the same interval from each other
+        let due = (self.frame + layer as u64).is_multiple_of(level.interval as u64);
+        if due {
+            scheduled.contents = contents;
+        }
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels_drop_with_coverage() {
+        let lod = ShadowLod::default();
+        assert_eq!(lod.level(0.8), ShadowLevel::FULL);
+        assert_eq!(
+            lod.level(0.2),
+            ShadowLevel {
+                scale: 0.5,
+                interval: 2
+            }
+        );
+        assert_eq!(
+            lod.level(0.01),
+            ShadowLevel {
+                scale: 0.25,
+                interval: 8
+            }
+        );
+
+        let off = ShadowLod {
+            enabled: false,
+            ..Default::default()
+        };
+        assert_eq!(off.level(0.01), ShadowLevel::FULL);
+
+        // inside the light's radius it covers everything
+        assert_eq!(ShadowLod::coverage(Vec3::ZERO, 60.0, Vec3::X, 2.0), 1.0);
+        let far = ShadowLod::coverage(Vec3::ZERO, 90.0, Vec3::new(0.0, 0.0, 100.0), 1.0);
+        assert!((far - 0.01).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_schedule_staggers_updates() {
+        let mut schedule = ShadowSchedule::default();
+        let level = ShadowLevel {
+            scale: 0.5,
+            interval: 2,
+        };
+        let (a, b) = (NodeId::new(), NodeId::new());
+
+        let mut updates = Vec::new();
+        for _ in 0..4 {
+            schedule.next_frame();
+            updates.push((
+                schedule.due(0, a, level, None),
+                schedule.due(1, b, level, None),
+            ));
+        }
+        // both render right away, then take turns
+        assert_eq!(
+            updates,
+            vec![(true, true), (true, false), (false, true), (true, false)]
+        );
+
+        // a different resolution renders on the next frame
+        schedule.next_frame();
+        assert!(schedule.due(1, b, ShadowLevel::FULL, None));
+    }
+
+    #[test]
+    fn test_schedule_keeps_unchanged_layers() {
+        let mut schedule = ShadowSchedule::default();
+        let light = NodeId::new();
+
+        let mut updates = Vec::new();
+        for contents in [1, 1, 1, 2, 2] {
+            schedule.next_frame();
+            updates.push(schedule.due(0, light, ShadowLevel::FULL, Some(contents)));
+        }
+        assert_eq!(updates, vec![true, false, false, true, false]);
+
+        // a change waits for the interval and is rendered once it's due
+        let level = ShadowLevel {
+            scale: 1.0,
+            interval: 2,
+        };
+        let mut updates = Vec::new();
+        for contents in [3, 4, 4, 4] {
+            schedule.next_frame();
+            updates.push(schedule.due(0, light, level, Some(contents)));
+        }
+        assert_eq!(updates, vec![true, false, true, false]);
+
+        // the maps were made again
+        schedule.clear();
+        schedule.next_frame();
+        assert!(schedule.due(0, light, level, Some(4)));
+    }
+}
@@ -1,4 +1,7 @@
-use maple_engine::{GameContext, asset::AssetId};
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use bytemuck::Pod;
+use maple_engine::{GameContext, asset::AssetId, scene::NodeId};
 use maple_renderer::{
     core::{
         Buffer, CullMode, DescriptorBindingType, DescriptorSet, DescriptorSetLayout,
@@ -18,33 +21,62 @@ use crate::{
     assets::mesh::Mesh3D,
     math::Frustum,
     nodes::{
+        area_light::{AreaLight, AreaLightBuffer},
         directional_light::{DirectionalLight, DirectionalLightBuffer},
         mesh_instance::Mesh3DUniformBufferData,
         point_light::{PointLight, PointLightBuffer},
+        spot_light::{SpotLight, SpotLightBuffer},
     },
-    render_passes::collect_mesh::MeshBundle,
+    render_passes::collect_mesh::{CollectMesh, MeshBundle},
 };
 
+/// the default shadow resolution of each light type
 pub const DIRECTIONAL_SHADOW_SIZE: u32 = 2048;
 pub const POINT_SHADOW_SIZE: u32 = 256;
+pub const SPOT_SHADOW_SIZE: u32 = 1024;
+/// the largest shadow resolution a light can ask for
+pub const MAX_SHADOW_SIZE: u32 = 8192;
 pub const MAX_CASCADES: u32 = 4;
 
+/// how many lights of each type cast shadows and the size of the maps they are rendered into
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct ShadowMapCounts {
+    directional_count: usize,
+    directional_size: u32,
+    point_count: usize,
+    point_size: u32,
+    spot_count: usize,
+    spot_size: u32,
+}
+
 /// Shadow resource node that manages shadow map textures and samplers
 ///
-/// This node monitors the light count each frame and recreates texture arrays
-/// when the count changes. It shares the shadow textures via the render graph
+/// This node monitors the shadow casting lights each frame and recreates texture arrays
+/// when their count or resolution changes. It shares the shadow textures via the render graph
 /// context so other passes can access them.
 struct ShadowTextureSet {
     directional_shadow_array: TextureArray,
     point_shadow_cube_array: TextureCubeArray,
+    spot_shadow_array: TextureArray,
     shadow_sampler: Sampler,
     direct_light_buffer: Buffer<DirectionalLightBuffer>,
     point_light_buffer: Buffer<PointLightBuffer>,
+    spot_light_buffer: Buffer<SpotLightBuffer>,
+    area_light_buffer: Buffer<AreaLightBuffer>,
     light_descriptor_set: DescriptorSet,
 }
 
 impl ShadowTextureSet {
-    fn create(rcx: &RenderContext, directional_count: usize, point_count: usize) -> Self {
+    fn create(rcx: &RenderContext, counts: ShadowMapCounts) -> Self {
+        let ShadowMapCounts {
+            directional_count,
+            directional_size,
+            point_count,
+            point_size,
+            spot_count,
+            spot_size,
+        } = counts;
+
         // Create shadow sampler for depth comparison
         let shadow_sampler = rcx.device().create_sampler(SamplerOptions {
             mode_u: TextureMode::ClampToEdge,
@@ -70,6 +102,12 @@ impl ShadowTextureSet {
         let point_light_buffer = rcx
             .device()
             .create_empty_storage_buffer::<PointLightBuffer>();
+        let spot_light_buffer = rcx
+            .device()
+            .create_empty_storage_buffer::<SpotLightBuffer>();
+        let area_light_buffer = rcx
+            .device()
+            .create_empty_storage_buffer::<AreaLightBuffer>();
 
         // Create directional shadow array (always at least 1 layer)
         let dir_array_layers = if directional_count > 0 {
@@ -82,11 +120,12 @@ impl ShadowTextureSet {
 
         let directional_shadow_array = rcx.device().create_texture_array(TextureArrayCreateInfo {
             label: Some("directional_shadows"),
-            width: DIRECTIONAL_SHADOW_SIZE,
-            height: DIRECTIONAL_SHADOW_SIZE,
+            width: directional_size,
+            height: directional_size,
             array_layers: dir_array_layers,
             format: TextureFormat::Depth32,
             usage: TextureUsage::RENDER_ATTACHMENT | TextureUsage::TEXTURE_BINDING,
+            mip_level: 1,
         });
 
         // Create point shadow cube array (always at least 1 layer)
@@ -100,12 +139,25 @@ impl ShadowTextureSet {
             rcx.device()
                 .create_texture_cube_array(TextureCubeArrayCreateInfo {
                     label: Some("point_shadows"),
-                    size: POINT_SHADOW_SIZE,
+                    size: point_size,
                     array_layers: point_array_layers,
                     format: TextureFormat::Depth32,
                     usage: TextureUsage::RENDER_ATTACHMENT | TextureUsage::TEXTURE_BINDING,
                 });
 
+        // Create spot shadow array, one layer per light (always at least 1 layer)
+        let spot_array_layers = spot_count.next_power_of_two().max(1) as u32;
+
+        let spot_shadow_array = rcx.device().create_texture_array(TextureArrayCreateInfo {
+            label: Some("spot_shadows"),
+            width: spot_size,
+            height: spot_size,
+            array_layers: spot_array_layers,
+            format: TextureFormat::Depth32,
+            usage: TextureUsage::RENDER_ATTACHMENT | TextureUsage::TEXTURE_BINDING,
+            mip_level: 1,
+        });
+
         // Build descriptor set
         let light_layout = ShadowResource::layout(rcx);
         let light_descriptor_set = rcx.device().build_descriptor_set(
@@ -115,15 +167,21 @@ impl ShadowTextureSet {
                 .texture_view(2, &directional_shadow_array.create_view())
                 .texture_view(3, &point_shadow_cube_array.create_view())
                 .sampler(4, &shadow_sampler)
-                .sampler(5, &shadow_sampler_linear),
+                .sampler(5, &shadow_sampler_linear)
+                .storage(6, &spot_light_buffer)
+                .texture_view(7, &spot_shadow_array.create_view())
+                .storage(8, &area_light_buffer),
         );
 
         Self {
             directional_shadow_array,
             point_shadow_cube_array,
+            spot_shadow_array,
             shadow_sampler,
             direct_light_buffer,
             point_light_buffer,
+            spot_light_buffer,
+            area_light_buffer,
             light_descriptor_set,
         }
     }
@@ -131,9 +189,11 @@ impl ShadowTextureSet {
     fn share_to_graph(&self, gcx: &mut RenderGraphContext) {
         gcx.add_shared_resource("directional_shadows", self.directional_shadow_array.clone());
         gcx.add_shared_resource("point_shadows", self.point_shadow_cube_array.clone());
+        gcx.add_shared_resource("spot_shadows", self.spot_shadow_array.clone());
         gcx.add_shared_resource("shadow_sampler", self.shadow_sampler.clone());
         gcx.add_shared_resource("direct_light_buffer", self.direct_light_buffer.clone());
         gcx.add_shared_resource("point_light_buffer", self.point_light_buffer.clone());
+        gcx.add_shared_resource("spot_light_buffer", self.spot_light_buffer.clone());
         gcx.add_shared_resource("light_descriptor_set", self.light_descriptor_set.clone());
     }
 }
@@ -148,24 +208,87 @@ pub struct MaterialBatch {
 pub struct MeshBatch {
     pub mesh: Mesh3D,
     pub mesh_id: AssetId,
+    /// see [`super::collect_mesh::MeshBundle::deformed`]
+    pub deformed: bool,
+    /// the node of each instance
+    pub nodes: Vec<NodeId>,
     pub start: u32,
     pub end: u32,
 }
 
 pub struct ShadowResource {
     textures: ShadowTextureSet,
-    prev_directional_count: usize,
-    prev_point_count: usize,
+    prev_counts: ShadowMapCounts,
 }
 
 impl ShadowResource {
+    /// the shadow map layer of each light in order, lights that don't cast shadows get none
+    pub(crate) fn shadow_indices(
+        casts_shadows: impl IntoIterator<Item = bool>,
+    ) -> Vec<Option<usize>> {
+        let mut next = 0;
+        casts_shadows
+            .into_iter()
+            .map(|casts| {
+                casts.then(|| {
+                    next += 1;
+                    next - 1
+                })
+            })
+            .collect()
+    }
+
+    /// the width of the shadow maps lights asking for `resolutions` are rendered into, 1 if
+    /// there are none
+    pub(crate) fn shadow_map_size(resolutions: impl IntoIterator<Item = u32>) -> u32 {
+        resolutions
+            .into_iter()
+            .max()
+            .map_or(1, |size| size.clamp(1, MAX_SHADOW_SIZE))
+    }
+
+    /// a hash of what a shadow layer is rendered with, the `light` uniform and the culled
+    /// `batches` with their `data`. `None` if a caster is deformed so the layer has to be
+    /// rendered every frame
+    pub(crate) fn contents(
+        light: &impl Pod,
+        batches: &[MaterialBatch],
+        data: &[Mesh3DUniformBufferData],
+    ) -> Option<u64> {
+        let mut hasher = DefaultHasher::new();
+        bytemuck::bytes_of(light).hash(&mut hasher);
+        bytemuck::cast_slice::<_, u8>(data).hash(&mut hasher);
+        for material in batches {
+            material.material_id.hash(&mut hasher);
+            material.cull_mode.hash(&mut hasher);
+            for mesh in &material.meshes {
+                if mesh.deformed {
+                    return None;
+                }
+                mesh.mesh_id.hash(&mut hasher);
+                mesh.nodes.hash(&mut hasher);
+            }
+        }
+        Some(hasher.finish())
+    }
+
+    /// how many lights cast shadows and the map size they need from whether each light casts
+    /// shadows and its resolution, no bigger than `max_size`
+    fn casters(lights: impl Iterator<Item = (bool, u32)>, max_size: u32) -> (usize, u32) {
+        let resolutions: Vec<u32> = lights
+            .filter(|(casts, _)| *casts)
+            .map(|(_, resolution)| resolution.min(max_size))
+            .collect();
+        (resolutions.len(), Self::shadow_map_size(resolutions))
+    }
+
     pub(crate) fn cull_and_batch_meshes(
         meshes: &Vec<MeshBundle>,
         fustrum: Frustum,
     ) -> (Vec<MaterialBatch>, Vec<Mesh3DUniformBufferData>) {
         let meshes: Vec<&MeshBundle> = meshes
             .iter()
-            .filter(|mesh| mesh.cast_shadow && fustrum.intersects_aabb(&mesh.world_aabb))
+            .filter(|mesh| mesh.cast_shadow && mesh.in_frustum(&fustrum))
             .collect();
 
         let mut batch_materials: Vec<MaterialBatch> = Vec::with_capacity(meshes.len());
@@ -187,13 +310,17 @@ impl ShadowResource {
 
             if let Some(last) = bm.meshes.last_mut() {
                 if last.mesh_id == mesh.mesh_id && last.end == instance_index {
+                    last.deformed |= mesh.deformed;
                     last.end = instance_index + 1;
+                    last.nodes.push(mesh.node);
                     continue;
                 }
             }
             bm.meshes.push(MeshBatch {
                 mesh: mesh.mesh.clone(),
                 mesh_id: mesh.mesh_id.clone(),
+                deformed: mesh.deformed,
+                nodes: vec![mesh.node],
                 start: instance_index,
                 end: instance_index + 1,
             })
@@ -202,6 +329,18 @@ impl ShadowResource {
         (batch_materials, mesh_buffer)
     }
 
+    /// add the draws of `batches` to the render stats of `pass`
+    pub(crate) fn record_draws(game_ctx: &GameContext, pass: &str, batches: &[MaterialBatch]) {
+        CollectMesh::record_draws(
+            game_ctx,
+            pass,
+            batches
+                .iter()
+                .flat_map(|material| &material.meshes)
+                .map(|batch| (&batch.mesh, batch.nodes.as_slice())),
+        );
+    }
+
     pub fn shadow_layout(rcx: &RenderContext) -> DescriptorSetLayout {
         rcx.device()
             .create_descriptor_set_layout(DescriptorSetLayoutDescriptor {
@@ -235,6 +374,17 @@ impl ShadowResource {
                 DescriptorBindingType::TextureViewDepthCubeArray, // Binding 3: point shadow maps
                 DescriptorBindingType::ComparisonSampler,     // Binding 4: shadow sampler
                 DescriptorBindingType::Sampler { filtering: true },
+                DescriptorBindingType::Storage {
+                    read_only: true,
+                    has_dynamic_offset: false,
+                    min_size: None,
+                }, // Binding 6: spot lights
+                DescriptorBindingType::TextureViewDepthArray, // Binding 7: spot shadow maps
+                DescriptorBindingType::Storage {
+                    read_only: true,
+                    has_dynamic_offset: false,
+                    min_size: None,
+                }, // Binding 8: area lights
             ],
         })
     }
@@ -253,13 +403,18 @@ impl RenderNode for ShadowResource {
     }
     fn setup(rcx: &RenderContext, gcx: &mut RenderGraphContext) -> Self {
         // Create initial resources with 0 lights
-        let textures = ShadowTextureSet::create(rcx, 0, 0);
+        let counts = ShadowMapCounts {
+            directional_size: 1,
+            point_size: 1,
+            spot_size: 1,
+            ..Default::default()
+        };
+        let textures = ShadowTextureSet::create(rcx, counts);
         textures.share_to_graph(gcx);
 
         Self {
             textures,
-            prev_directional_count: 0,
-            prev_point_count: 0,
+            prev_counts: counts,
         }
     }
 
@@ -271,38 +426,95 @@ impl RenderNode for ShadowResource {
         game_ctx: &GameContext,
     ) {
         let scene = &game_ctx.scene;
-        // Count lights in the scene
+        // Count shadow casting lights in the scene
         let directional_lights = scene.collect::<DirectionalLight>();
         let point_lights = scene.collect::<PointLight>();
+        let spot_lights = scene.collect::<SpotLight>();
+
+        // the passes draw each light at its resolution clamped to the map so capping the map caps
+        // every light
+        let max_size = rcx.quality().max_shadow_resolution;
+        let (directional_count, directional_size) = Self::casters(
+            directional_lights
+                .iter()
+                .map(|light| light.read())
+                .map(|light| (light.cast_shadows, light.shadow_resolution)),
+            max_size,
+        );
+        let (point_count, point_size) = Self::casters(
+            point_lights
+                .iter()
+                .map(|light| light.read())
+                .map(|light| (light.cast_shadows, light.shadow_resolution)),
+            max_size,
+        );
+        let (spot_count, spot_size) = Self::casters(
+            spot_lights
+                .iter()
+                .map(|light| light.read())
+                .map(|light| (light.cast_shadows, light.shadow_resolution)),
+            max_size,
+        );
+        let counts = ShadowMapCounts {
+            directional_count,
+            directional_size,
+            point_count,
+            point_size,
+            spot_count,
+            spot_size,
+        };
 
-        let directional_count = directional_lights.len();
-        let point_count = point_lights.len();
+        // Check if shadow maps changed - recreate if needed
+        if counts != self.prev_counts {
+            let prev = self.prev_counts;
+            if (directional_count, directional_size)
+                != (prev.directional_count, prev.directional_size)
+            {
+                log::info!(
+                    "Directional shadows changed: {} at {} -> {} at {}. Recreating shadow maps.",
+                    prev.directional_count,
+                    prev.directional_size,
+                    directional_count,
+                    directional_size
+                );
+            }
 
-        // Check if light counts changed - recreate if needed
-        if directional_count != self.prev_directional_count || point_count != self.prev_point_count
-        {
-            if directional_count != self.prev_directional_count {
+            if (point_count, point_size) != (prev.point_count, prev.point_size) {
                 log::info!(
-                    "Directional light count changed: {} -> {}. Recreating shadow maps.",
-                    self.prev_directional_count,
-                    directional_count
+                    "Point shadows changed: {} at {} -> {} at {}. Recreating shadow maps.",
+                    prev.point_count,
+                    prev.point_size,
+                    point_count,
+                    point_size
                 );
             }
 
-            if point_count != self.prev_point_count {
+            if (spot_count, spot_size) != (prev.spot_count, prev.spot_size) {
                 log::info!(
-                    "Point light count changed: {} -> {}. Recreating shadow maps.",
-                    self.prev_point_count,
-                    point_count
+                    "Spot shadows changed: {} at {} -> {} at {}. Recreating shadow maps.",
+                    prev.spot_count,
+                    prev.spot_size,
+                    spot_count,
+                    spot_size
                 );
             }
 
             // Recreate entire texture set with new light counts
-            self.textures = ShadowTextureSet::create(rcx, directional_count, point_count);
-            self.prev_directional_count = directional_count;
-            self.prev_point_count = point_count;
+            self.textures = ShadowTextureSet::create(rcx, counts);
+            self.prev_counts = counts;
         }
 
+        // area lights have no shadow pass to write them
+        let area_lights = scene.collect::<AreaLight>();
+        let area_light_data = AreaLightBuffer::from_lights(
+            &area_lights
+                .iter()
+                .map(|light| light.read().get_buffered_data())
+                .collect::<Vec<_>>(),
+        );
+        rcx.queue()
+            .write_buffer(&self.textures.area_light_buffer, &area_light_data);
+
         // Re-share resources (they might have been recreated)
         self.textures.share_to_graph(gcx);
     }
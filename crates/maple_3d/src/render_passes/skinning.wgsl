@@ -0,0 +1,66 @@
+// applies the joint matrices of a skin to the vertices of one mesh instance so the passes
+// drawing it don't have to
+
+struct SkinParams {
+    joint_offset: u32,
+    joint_count: u32,
+    vertex_count: u32,
+    _padding: u32,
+}
+
+// the vertex is read as words: position 0, normal 3, uv 6, tangent 8, bitangent 11,
+// joints 14 and weights 18
+const VERTEX_WORDS: u32 = 22u;
+
+@group(0) @binding(0) var<storage, read> source: array<u32>;
+@group(0) @binding(1) var<storage, read> joints: array<mat4x4<f32>>;
+@group(0) @binding(2) var<uniform> params: SkinParams;
+@group(0) @binding(3) var<storage, read_write> skinned: array<u32>;
+
+fn read_vec3(word: u32) -> vec3<f32> {
+    return vec3<f32>(
+        bitcast<f32>(source[word]),
+        bitcast<f32>(source[word + 1u]),
+        bitcast<f32>(source[word + 2u]),
+    );
+}
+
+fn write_vec3(word: u32, value: vec3<f32>) {
+    skinned[word] = bitcast<u32>(value.x);
+    skinned[word + 1u] = bitcast<u32>(value.y);
+    skinned[word + 2u] = bitcast<u32>(value.z);
+}
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.vertex_count {
+        return;
+    }
+    let base = id.x * VERTEX_WORDS;
+
+    // uvs, joints and weights stay the same
+    for (var word = 0u; word < VERTEX_WORDS; word++) {
+        skinned[base + word] = source[base + word];
+    }
+
+    let ids = vec4<u32>(source[base + 14u], source[base + 15u], source[base + 16u], source[base + 17u]);
+    let weights = vec4<f32>(
+        bitcast<f32>(source[base + 18u]),
+        bitcast<f32>(source[base + 19u]),
+        bitcast<f32>(source[base + 20u]),
+        bitcast<f32>(source[base + 21u]),
+    );
+
+    // out of range joints are clamped so bad data can't read another mesh's joints
+    let last = params.joint_count - 1u;
+    let skin = joints[params.joint_offset + min(ids.x, last)] * weights.x
+        + joints[params.joint_offset + min(ids.y, last)] * weights.y
+        + joints[params.joint_offset + min(ids.z, last)] * weights.z
+        + joints[params.joint_offset + min(ids.w, last)] * weights.w;
+
+    // directions are normalized by the vertex shaders
+    write_vec3(base, (skin * vec4<f32>(read_vec3(base), 1.0)).xyz);
+    write_vec3(base + 3u, (skin * vec4<f32>(read_vec3(base + 3u), 0.0)).xyz);
+    write_vec3(base + 8u, (skin * vec4<f32>(read_vec3(base + 8u), 0.0)).xyz);
+    write_vec3(base + 11u, (skin * vec4<f32>(read_vec3(base + 11u), 0.0)).xyz);
+}
@@ -12,13 +12,16 @@ use maple_renderer::{
     },
     render_graph::{
         graph::{RenderGraphContext, Stage},
-        node::{DepthMode, RenderNode, RenderTarget},
+        node::{DepthMode, RenderNode},
     },
 };
 
-use crate::nodes::{
-    camera::{Camera3D, Camera3DBufferData},
-    environment::Environment,
+use crate::{
+    nodes::{
+        camera::{Camera3D, Camera3DBufferData},
+        environment::Environment,
+    },
+    render_passes::{main_pass::MainPass, scene_textures::scene_target},
 };
 
 pub struct SkyboxRender {
@@ -97,7 +100,7 @@ impl RenderNode for SkyboxRender {
             }),
             cull_mode: CullMode::None,
             alpha_mode: AlphaMode::Opaque,
-            sample_count: 4, // TODO: Match main pass MSAA from config
+            sample_count: MainPass::pass_info(rcx).sample_count,
             vertex_buffer_layout: None,
         });
 
@@ -187,13 +190,11 @@ impl RenderNode for SkyboxRender {
             .render(
                 RenderOptions {
                     label: Some("Skybox Pass"),
-                    color_targets: &[RenderTarget::MultiSampled {
-                        texture: msaa_color_texture.create_view(),
-                        resolve: resolved_color_texture.create_view(),
-                    }],
+                    color_targets: &[scene_target(msaa_color_texture, resolved_color_texture)],
                     depth_target: Some(&depth_texture.create_view()),
                     clear_color: Some([0.1, 0.1, 0.1, 1.0]),
                     clear_depth: Some(1.0),
+                    discard_depth: false,
                 },
                 |mut fb| {
                     fb.use_pipeline(&self.pipeline)
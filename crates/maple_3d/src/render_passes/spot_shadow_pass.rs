@@ -0,0 +1,347 @@
+use std::collections::HashMap;
+
+use bytemuck::{Pod, Zeroable};
+use maple_engine::GameContext;
+use maple_renderer::{
+    core::{
+        Buffer, CullMode, DepthBias, DepthCompare, DepthStencilOptions, DescriptorSetLayout, Frame,
+        GraphicsShader, RenderContext, StageFlags,
+        context::RenderOptions,
+        descriptor_set::{DescriptorBindingType, DescriptorSet, DescriptorSetLayoutDescriptor},
+        pipeline::{AlphaMode, PipelineCreateInfo, RenderPipeline},
+        texture::{TextureArray, TextureFormat},
+    },
+    render_graph::{
+        graph::{RenderGraphContext, Stage},
+        node::{DepthMode, RenderNode},
+    },
+    types::vertex::VertexLayout,
+};
+
+use crate::{
+    math::{Frustum, Vertex},
+    nodes::{
+        mesh_instance::{Mesh3DUniformBufferData, MeshInstance3D},
+        spot_light::{self, SpotLight, SpotLightBuffer},
+    },
+    render_passes::{
+        collect_mesh::{BundledMeshes, CollectMesh},
+        main_pass::MAX_MESH,
+        shadow_lod::{ShadowLevel, ShadowSchedule},
+        shadow_resource::ShadowResource,
+    },
+};
+
+/// Uniform buffer for light view-projection matrix
+///
+/// the standard alignment is 256 bytes for offset
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct SpotLightVPUniform {
+    view_projection: [[f32; 4]; 4],
+    _padding: [u8; 192],
+}
+
+/// Spot shadow pass renders depth from spot light perspectives
+///
+/// This pass renders each spot light's shadow map by:
+/// 1. Getting the light's perspective view-projection covering its outer cone
+/// 2. Rendering all meshes from the light's perspective to its layer of the spot shadow array
+/// 3. Storing depth values for shadow sampling in the main pass
+///
+/// it uses the same shaders as the [`super::directional_shadow_pass::DirectionalShadowPass`]
+/// since both only need a view-projection. maps whose view and casters haven't changed since they
+/// were rendered are kept
+pub struct SpotShadowPass {
+    // Buffer for light view-projection matrix
+    light_vp_buffer: Buffer<[SpotLightVPUniform]>,
+
+    // Descriptor set for light VP
+    light_vp_descriptor: DescriptorSet,
+
+    // Render pipeline
+    pipeline: HashMap<CullMode, RenderPipeline>,
+
+    mesh_buffers: HashMap<u32, Buffer<[Mesh3DUniformBufferData]>>,
+    joint_buffer: Buffer<[[[f32; 4]; 4]]>,
+    mesh_layout: DescriptorSetLayout,
+    mesh_descriptors: HashMap<u32, DescriptorSet>,
+
+    /// the array the schedule's layers are in
+    rendered_into: Option<TextureArray>,
+    schedule: ShadowSchedule,
+}
+
+impl RenderNode for SpotShadowPass {
+    fn label() -> &'static str
+    where
+        Self: Sized,
+    {
+        "Spot Shadow"
+    }
+
+    fn stage(&self) -> Stage {
+        Stage::Shadow
+    }
+
+    fn setup(rcx: &RenderContext, graph_ctx: &mut RenderGraphContext) -> Self {
+        let shader = GraphicsShader {
+            vertex: rcx
+                .device()
+                .compile_shader(include_str!("./directional_shadow.vert.wgsl").into())
+                .expect("spot shadow vert shader to compile"),
+            fragment: rcx
+                .device()
+                .compile_shader(include_str!("./directional_shadow.frag.wgsl").into())
+                .expect("spot shadow frag shader to compile"),
+        };
+
+        // Create descriptor set layout for light VP matrix
+        let light_vp_layout =
+            rcx.device()
+                .create_descriptor_set_layout(DescriptorSetLayoutDescriptor {
+                    label: Some("SpotShadow_LightVP"),
+                    visibility: StageFlags::VERTEX,
+                    layout: &[DescriptorBindingType::Storage {
+                        read_only: true,
+                        has_dynamic_offset: true,
+                        min_size: Some(size_of::<SpotLightVPUniform>()),
+                    }], // Binding 0: light VP
+                });
+
+        // Create buffer for light VP matrix
+        let light_vp_buffer = rcx
+            .device()
+            .create_sized_storage_buffer(size_of::<SpotLightVPUniform>() * spot_light::MAX_LIGHTS);
+
+        // Build descriptor set
+        let light_vp_descriptor = rcx.device().build_descriptor_set(
+            DescriptorSet::builder(&light_vp_layout).storage_dynamic(
+                0,
+                &light_vp_buffer,
+                size_of::<SpotLightVPUniform>() as u64,
+            ),
+        );
+
+        // Get mesh descriptor layout
+        let mesh_layout = CollectMesh::mesh_layout(rcx);
+        let joint_buffer = CollectMesh::joint_buffer(graph_ctx);
+
+        let shadow_layout = ShadowResource::shadow_layout(rcx);
+
+        // Create pipeline
+        let pipeline_layout = rcx.device().create_pipeline_layout(&[
+            light_vp_layout.clone(),
+            mesh_layout.clone(),
+            shadow_layout,
+        ]);
+
+        let depth_mode = DepthMode::Texture(DepthStencilOptions {
+            format: TextureFormat::Depth32,
+            compare: DepthCompare::Less,
+            write_enabled: true,
+            depth_bias: Some(DepthBias {
+                constant: 2,
+                slope_scale: 2.5,
+            }),
+        });
+
+        let mut pipeline: HashMap<CullMode, RenderPipeline> = HashMap::default();
+
+        for cull_mode in [CullMode::None, CullMode::Back, CullMode::Front] {
+            pipeline.insert(
+                cull_mode,
+                rcx.device().create_pipeline(PipelineCreateInfo {
+                    label: Some("SpotShadowPass"),
+                    layout: pipeline_layout.clone(),
+                    shader: shader.clone(),
+                    color_formats: &[],
+                    depth: depth_mode.clone(),
+                    cull_mode,
+                    alpha_mode: AlphaMode::Opaque,
+                    sample_count: 1,
+                    vertex_buffer_layout: Some(Vertex::buffer_layout()),
+                }),
+            );
+        }
+
+        Self {
+            light_vp_buffer,
+            light_vp_descriptor,
+            pipeline,
+            mesh_buffers: HashMap::new(),
+            joint_buffer,
+            mesh_layout,
+            mesh_descriptors: HashMap::new(),
+            rendered_into: None,
+            schedule: ShadowSchedule::default(),
+        }
+    }
+
+    fn draw(
+        &mut self,
+        render_ctx: &RenderContext,
+        frame: &mut Frame,
+        graph_ctx: &mut RenderGraphContext,
+        game_ctx: &GameContext,
+    ) {
+        // Get shared resources (shadow resources arent created in this node)
+        let Some(shadow_array) = graph_ctx.get_shared_resource::<TextureArray>("spot_shadows")
+        else {
+            log::error!("SpotShadowPass: No spot_shadows array found");
+            return;
+        };
+        let Some(spot_light_buffer) =
+            graph_ctx.get_shared_resource::<Buffer<SpotLightBuffer>>("spot_light_buffer")
+        else {
+            return;
+        };
+
+        let scene = &game_ctx.scene;
+        let spot_lights = scene.collect::<SpotLight>();
+        if spot_lights.is_empty() {
+            return;
+        }
+
+        // lights without a shadow or past the end of the array aren't given a layer, the rest
+        // render into the top left of theirs
+        let layers = shadow_array.array_layers() as usize;
+        let shadow_indices: Vec<Option<usize>> = ShadowResource::shadow_indices(
+            spot_lights.iter().map(|light| light.read().cast_shadows),
+        )
+        .into_iter()
+        .map(|index| index.filter(|index| *index < layers))
+        .collect();
+        let map_size = shadow_array.width();
+        let sizes: Vec<u32> = spot_lights
+            .iter()
+            .map(|light| light.read().shadow_resolution.clamp(1, map_size))
+            .collect();
+
+        // the main pass needs the lights even if there is nothing to shadow
+        let spot_light_data = SpotLightBuffer::from_lights(
+            &spot_lights
+                .iter()
+                .enumerate()
+                .map(|(i, light)| {
+                    let mut data = light.read().get_buffered_data(shadow_indices[i]);
+                    data.shadow_scale = sizes[i] as f32 / map_size as f32;
+                    data
+                })
+                .collect::<Vec<_>>(),
+        );
+
+        render_ctx
+            .queue()
+            .write_buffer(spot_light_buffer, &spot_light_data);
+
+        let mesh_instances = scene.collect_visible::<MeshInstance3D>();
+        if mesh_instances.is_empty() {
+            return;
+        }
+
+        // maps made again by the shadow resource start out empty
+        if self.rendered_into.as_ref() != Some(shadow_array) {
+            self.rendered_into = Some(shadow_array.clone());
+            self.schedule.clear();
+        }
+        self.schedule.next_frame();
+
+        let view_projections: Vec<_> = spot_lights
+            .iter()
+            .take(spot_light::MAX_LIGHTS)
+            .map(|light| light.read().view_projection())
+            .collect();
+
+        let light_data: Vec<SpotLightVPUniform> = view_projections
+            .iter()
+            .map(|vp| SpotLightVPUniform {
+                view_projection: vp.to_cols_array_2d(),
+                _padding: Zeroable::zeroed(),
+            })
+            .collect();
+
+        render_ctx
+            .queue()
+            .write_buffer_slice(&self.light_vp_buffer, &light_data);
+
+        let bundles = graph_ctx
+            .get_shared_resource::<BundledMeshes>("mesh_bundles")
+            .unwrap();
+
+        for (light_idx, (light, vp_matrix)) in spot_lights.iter().zip(&view_projections).enumerate()
+        {
+            let Some(shadow_index) = shadow_indices[light_idx] else {
+                continue;
+            };
+            let size = sizes[light_idx];
+            let level = ShadowLevel {
+                scale: size as f32 / map_size as f32,
+                interval: 1,
+            };
+
+            let light_frustum = Frustum::from_view_proj(vp_matrix);
+            let (batches, data) =
+                ShadowResource::cull_and_batch_meshes(&bundles.meshes, light_frustum);
+            let contents = ShadowResource::contents(&light_data[light_idx], &batches, &data);
+            if !self.schedule.due(shadow_index, light.id(), level, contents) {
+                continue;
+            }
+            ShadowResource::record_draws(game_ctx, Self::label(), &batches);
+
+            let buffer = self
+                .mesh_buffers
+                .entry(light_idx as u32)
+                .or_insert_with(|| render_ctx.device().create_sized_storage_buffer(MAX_MESH));
+
+            render_ctx.queue().write_buffer_slice(buffer, &data);
+
+            let descriptor = self
+                .mesh_descriptors
+                .entry(light_idx as u32)
+                .or_insert_with(|| {
+                    render_ctx.device().build_descriptor_set(
+                        DescriptorSet::builder(&self.mesh_layout)
+                            .storage(0, buffer)
+                            .storage(1, &self.joint_buffer),
+                    )
+                });
+
+            // Get depth texture for this light
+            let layer_view = shadow_array.create_layer_view(shadow_index as u32);
+
+            frame
+                .render(
+                    RenderOptions {
+                        label: Some("Spot Shadow Pass"),
+                        color_targets: &[],
+                        depth_target: Some(&layer_view),
+                        clear_color: None,
+                        clear_depth: Some(1.0),
+                        discard_depth: false,
+                    },
+                    |mut fb| {
+                        fb.bind_descriptor_set_with_offset(
+                            0,
+                            &self.light_vp_descriptor,
+                            &[size_of::<SpotLightVPUniform>() as u32 * light_idx as u32],
+                        )
+                        .bind_descriptor_set(1, descriptor)
+                        .set_viewport(0.0, 0.0, size as f32, size as f32);
+
+                        for material_batch in batches {
+                            fb.use_pipeline(self.pipeline.get(&material_batch.cull_mode).unwrap());
+                            fb.bind_descriptor_set(2, &material_batch.shadow_descriptor);
+
+                            for mesh_batch in material_batch.meshes {
+                                fb.bind_vertex_buffer(mesh_batch.mesh.get_vertex_buffer())
+                                    .bind_index_buffer(mesh_batch.mesh.get_index_buffer())
+                                    .draw_indexed(mesh_batch.start..mesh_batch.end);
+                            }
+                        }
+                    },
+                )
+                .expect("failed to render spot shadow map");
+        }
+    }
+}
@@ -0,0 +1,209 @@
+//! draw order of blended meshes
+//!
+//! blended meshes are drawn after everything else, farthest from the camera first by the center
+//! of their bounding box. that is wrong for meshes that intersect or are large, so the triangles
+//! of small meshes are sorted as well: meshes with at most [`TransparencySort::max_triangles`]
+//! triangles get an index buffer of their own that is sorted back to front whenever the camera or
+//! the mesh moves.
+//!
+//! skinned and morphed meshes only keep their rest pose on the cpu so they are only sorted by
+//! their bounding box, as are meshes made with [`Mesh3D::from_buffers`].
+//!
+//! ```rust, ignore
+//! let mut sort = ctx.get_resource_mut::<TransparencySort>();
+//! sort.max_triangles = 256;
+//! ```
+
+use std::collections::HashMap;
+
+use glam::{Mat4, Vec3};
+use maple_engine::{GameContext, asset::AssetId, prelude::Resource, scene::NodeId};
+use maple_renderer::core::RenderContext;
+
+use crate::{
+    assets::{material::AlphaMode, mesh::Mesh3D},
+    render_passes::collect_mesh::MeshBundle,
+};
+
+/// how blended meshes are sorted before they are drawn, see the [module docs](self)
+#[derive(Debug, Clone)]
+pub struct TransparencySort {
+    /// sort the triangles of small meshes and not only the meshes when true
+    ///
+    /// Default: `true`
+    pub sort_triangles: bool,
+    /// the most triangles a mesh can have to sort them, sorting is done on the cpu every time
+    /// the mesh or the camera moves
+    ///
+    /// Default: `2048`
+    pub max_triangles: usize,
+}
+
+impl Resource for TransparencySort {}
+
+impl Default for TransparencySort {
+    fn default() -> Self {
+        Self {
+            sort_triangles: true,
+            max_triangles: 2048,
+        }
+    }
+}
+
+/// the indices of `indices` ordered so the triangle farthest from `eye` comes first, `model`
+/// places `positions` in the space of `eye`
+pub fn sort_triangles(positions: &[Vec3], indices: &[u32], model: &Mat4, eye: Vec3) -> Vec<u32> {
+    let mut triangles: Vec<(f32, &[u32])> = indices
+        .chunks_exact(3)
+        .map(|triangle| {
+            let center = triangle
+                .iter()
+                .map(|index| positions[*index as usize])
+                .sum::<Vec3>()
+                / 3.0;
+            (
+                model.transform_point3(center).distance_squared(eye),
+                triangle,
+            )
+        })
+        .collect();
+    triangles.sort_by(|a, b| b.0.total_cmp(&a.0));
+    triangles
+        .into_iter()
+        .flat_map(|(_, triangle)| triangle.iter().copied())
+        .collect()
+}
+
+/// an index buffer sorted for one mesh instance
+struct SortedMesh {
+    mesh: Mesh3D,
+    /// sorted meshes are batched on their own
+    id: AssetId,
+    eye: Vec3,
+    model: Mat4,
+    /// false if the mesh wasn't drawn this frame
+    used: bool,
+}
+
+/// the sorted index buffers of the blended meshes drawn last frame
+#[derive(Default)]
+pub(crate) struct SortedTriangles {
+    meshes: HashMap<(NodeId, u32, AssetId), SortedMesh>,
+}
+
+impl SortedTriangles {
+    /// the blended bundles of `bundles` farthest first from `eye`, small meshes drawn with their
+    /// triangles sorted
+    ///
+    /// bundles keep the order of their material passes so every first pass is drawn before the
+    /// second ones
+    pub(crate) fn sort<'a>(
+        &mut self,
+        rcx: &RenderContext,
+        game_ctx: &GameContext,
+        bundles: impl IntoIterator<Item = &'a MeshBundle>,
+        eye: Vec3,
+    ) -> Vec<MeshBundle> {
+        let settings = match game_ctx.has_resource::<TransparencySort>() {
+            true => game_ctx.get_resource::<TransparencySort>().clone(),
+            false => TransparencySort::default(),
+        };
+
+        let mut sorted: Vec<(f32, MeshBundle)> = bundles
+            .into_iter()
+            .filter(|bundle| bundle.alpha_mode == AlphaMode::Blend)
+            .map(|bundle| {
+                let center = (bundle.world_aabb.min + bundle.world_aabb.max) * 0.5;
+                (center.distance_squared(eye), bundle.clone())
+            })
+            .collect();
+        sorted.sort_by(|(a_distance, a), (b_distance, b)| {
+            a.draw_order
+                .cmp(&b.draw_order)
+                .then(b_distance.total_cmp(a_distance))
+        });
+
+        for sorted in self.meshes.values_mut() {
+            sorted.used = false;
+        }
+        if settings.sort_triangles {
+            for (_, bundle) in &mut sorted {
+                self.sort_bundle(rcx, bundle, eye, settings.max_triangles);
+            }
+        }
+        self.meshes.retain(|_, sorted| sorted.used);
+
+        sorted.into_iter().map(|(_, bundle)| bundle).collect()
+    }
+
+    /// draw `bundle` with its triangles sorted if it's small enough
+    fn sort_bundle(
+        &mut self,
+        rcx: &RenderContext,
+        bundle: &mut MeshBundle,
+        eye: Vec3,
+        max_triangles: usize,
+    ) {
+        let (positions, indices) = bundle.mesh.triangles();
+        if bundle.deformed || indices.is_empty() || indices.len() / 3 > max_triangles {
+            return;
+        }
+
+        let model = Mat4::from_cols_array_2d(&bundle.buffer_data.model);
+        let key = (bundle.node, bundle.instance, bundle.mesh_id.clone());
+        let sorted = self.meshes.entry(key).or_insert_with(|| SortedMesh {
+            mesh: Mesh3D::from_buffers(
+                bundle.mesh.get_vertex_buffer().clone(),
+                rcx.device().create_sized_index_buffer(indices.len()),
+                *bundle.mesh.aabb(),
+            ),
+            id: AssetId::new_id(),
+            // written below
+            eye: Vec3::NAN,
+            model: Mat4::NAN,
+            used: true,
+        });
+        sorted.used = true;
+
+        if sorted.eye != eye || sorted.model != model {
+            let order = sort_triangles(positions, indices, &model, eye);
+            rcx.queue()
+                .write_buffer_slice(sorted.mesh.get_index_buffer(), &order);
+            sorted.eye = eye;
+            sorted.model = model;
+        }
+
+        bundle.mesh = sorted.mesh.clone();
+        bundle.mesh_id = sorted.id.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_triangles_are_sorted_farthest_first() {
+        // three triangles along z, listed nearest first from an eye at z = 10
+        let positions: Vec<Vec3> = [0.0, -5.0, -10.0]
+            .into_iter()
+            .flat_map(|z| {
+                [
+                    Vec3::new(0.0, 0.0, z),
+                    Vec3::new(1.0, 0.0, z),
+                    Vec3::new(0.0, 1.0, z),
+                ]
+            })
+            .collect();
+        let indices: Vec<u32> = (0..9).collect();
+        let eye = Vec3::new(0.0, 0.0, 10.0);
+
+        let sorted = sort_triangles(&positions, &indices, &Mat4::IDENTITY, eye);
+        assert_eq!(sorted, [6, 7, 8, 3, 4, 5, 0, 1, 2]);
+
+        // turned around the nearest triangle is the farthest
+        let turned = Mat4::from_rotation_y(std::f32::consts::PI);
+        let sorted = sort_triangles(&positions, &indices, &turned, eye);
+        assert_eq!(sorted, [0, 1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+}
@@ -0,0 +1,242 @@
+//! merging level geometry that never moves into fewer draws
+//!
+//! levels built from many small parts, such as the primitives of a gltf file, are drawn with a
+//! draw call per [`MeshInstance3D`]. [`StaticBatchExt::bake_static`] merges the mesh instances of
+//! a scene that share a material into one mesh per material and per cell of the world, in world
+//! space, and draws that instead:
+//!
+//! ```rust, ignore
+//! let level = ctx.assets.load::<GltfScene>("res/levels/dungeon.gltf");
+//! ctx.scene.merge_asset(level);
+//!
+//! // once the level has loaded, for example on LoadingFinished
+//! let baked = ctx.scene.bake_static(&ctx.assets);
+//! log::info!("{} meshes drawn in {} batches", baked.meshes, baked.batches);
+//! ```
+//!
+//! every merged instance keeps its place in the tree with its mesh taken away, the batches are
+//! new root nodes. moving a merged node doesn't move what is drawn so only bake what stays put.
+//! skinned, morphed, hidden and blended meshes and meshes with a [`MeshLod`](crate::lod::MeshLod)
+//! are left alone as are meshes made with
+//! [`Mesh3D::from_buffers`] since their vertices aren't on the cpu.
+//!
+//! the merged meshes load on the worker pool so the baked parts aren't drawn for a frame or two,
+//! bake while a loading screen is shown or before the first frame of the level.
+
+use std::collections::HashMap;
+
+use glam::{IVec3, Mat3, Mat4, Vec3};
+use maple_engine::{
+    Buildable, Scene,
+    asset::{AssetHandle, AssetId, AssetLibrary},
+    scene::NodeId,
+};
+
+use crate::{
+    assets::{
+        material::{AlphaMode, Material},
+        mesh::{Mesh3D, MeshBuilder},
+    },
+    math::Vertex,
+    nodes::mesh_instance::MeshInstance3D,
+};
+
+/// the size of the cells of the world meshes are merged in by default, so the batches can still
+/// be culled
+pub const STATIC_BATCH_CELL_SIZE: f32 = 64.0;
+
+/// what [`StaticBatchExt::bake_static`] merged
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StaticBatchStats {
+    /// the meshes spawned for the merged instances
+    pub batches: usize,
+    /// the mesh instances merged into them
+    pub meshes: usize,
+}
+
+/// adds [static batching](self) to scenes
+pub trait StaticBatchExt {
+    /// merge the static mesh instances that share a material, in cells of
+    /// [`STATIC_BATCH_CELL_SIZE`]
+    fn bake_static(&self, assets: &AssetLibrary) -> StaticBatchStats {
+        self.bake_static_with(assets, STATIC_BATCH_CELL_SIZE)
+    }
+
+    /// merge the static mesh instances that share a material and whose bounding box centers are
+    /// within the same `cell_size` cube of the world, `f32::INFINITY` merges the whole scene
+    fn bake_static_with(&self, assets: &AssetLibrary, cell_size: f32) -> StaticBatchStats;
+}
+
+/// a mesh instance merged into a batch
+struct Part {
+    node: NodeId,
+    mesh: AssetHandle<Mesh3D>,
+    model: Mat4,
+}
+
+struct Batch {
+    material: AssetHandle<Material>,
+    frustum_culled: bool,
+    parts: Vec<Part>,
+}
+
+impl StaticBatchExt for Scene {
+    fn bake_static_with(&self, assets: &AssetLibrary, cell_size: f32) -> StaticBatchStats {
+        // the parts are merged where they are in the world
+        self.sync_world_transform();
+
+        let mut batches: Vec<Batch> = Vec::new();
+        let mut batch_of: HashMap<(AssetId, bool, IVec3), usize> = HashMap::new();
+        self.for_each_with_id(&mut |node, instance: &mut MeshInstance3D| {
+            if instance.skin.is_some()
+                || instance.morph_weights.iter().any(|weight| *weight != 0.0)
+                || instance.lod.is_some()
+                || !instance.transform.is_visible_in_tree()
+            {
+                return;
+            }
+            let (Some(mesh_handle), Some(material)) = (&instance.mesh, &instance.material) else {
+                return;
+            };
+            let Some(mesh) = assets.get(mesh_handle) else {
+                return;
+            };
+            if mesh.vertices().is_empty() || mesh.morph_targets().is_some() {
+                return;
+            }
+            // blended meshes are sorted one by one
+            if assets
+                .get(material)
+                .is_none_or(|material| material.alpha_mode() == AlphaMode::Blend)
+            {
+                return;
+            }
+
+            let model = *instance.transform.world_space().matrix();
+            let center = mesh.aabb().transform(&model).center();
+            let cell = match cell_size.is_finite() {
+                true => (center / cell_size).floor().as_ivec3(),
+                false => IVec3::ZERO,
+            };
+            let key = (material.id.clone(), instance.frustum_culled, cell);
+            let index = *batch_of.entry(key).or_insert_with(|| {
+                batches.push(Batch {
+                    material: material.clone(),
+                    frustum_culled: instance.frustum_culled,
+                    parts: Vec::new(),
+                });
+                batches.len() - 1
+            });
+            batches[index].parts.push(Part {
+                node,
+                mesh: mesh_handle.clone(),
+                model,
+            });
+        });
+
+        let mut stats = StaticBatchStats::default();
+        // nothing is saved by merging a mesh with itself
+        for batch in batches.into_iter().filter(|batch| batch.parts.len() > 1) {
+            let meshes: Vec<_> = batch
+                .parts
+                .iter()
+                .filter_map(|part| Some((assets.get(&part.mesh)?, part.model)))
+                .collect();
+            let (vertices, indices) = merge_meshes(
+                meshes
+                    .iter()
+                    .map(|(mesh, model)| (mesh.vertices(), mesh.triangles().1, *model)),
+            );
+            drop(meshes);
+
+            let mesh = assets.add(MeshBuilder::from_vertices(vertices, indices));
+            self.spawn(
+                MeshInstance3D::builder()
+                    .mesh(mesh)
+                    .material(batch.material)
+                    .frustum_culled(batch.frustum_culled),
+            );
+            for part in &batch.parts {
+                if let Some(instance) = self.get::<MeshInstance3D>(part.node) {
+                    instance.write().mesh = None;
+                }
+            }
+
+            stats.batches += 1;
+            stats.meshes += batch.parts.len();
+        }
+        stats
+    }
+}
+
+/// the vertices and triangles of every mesh moved by its model matrix into one mesh
+///
+/// tangents are left to the mesh loader. triangles of mirrored meshes are flipped so they keep
+/// facing outwards
+pub fn merge_meshes<'a>(
+    meshes: impl IntoIterator<Item = (&'a [Vertex], &'a [u32], Mat4)>,
+) -> (Vec<Vertex>, Vec<u32>) {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for (mesh_vertices, mesh_indices, model) in meshes {
+        let offset = vertices.len() as u32;
+        let normal_matrix = Mat3::from_mat4(model).inverse().transpose();
+
+        vertices.extend(mesh_vertices.iter().map(|vertex| {
+            Vertex {
+                position: model
+                    .transform_point3(Vec3::from(vertex.position))
+                    .to_array(),
+                normal: (normal_matrix * Vec3::from(vertex.normal))
+                    .normalize_or_zero()
+                    .to_array(),
+                ..*vertex
+            }
+        }));
+
+        let mirrored = model.determinant() < 0.0;
+        for triangle in mesh_indices.chunks_exact(3) {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|index| index + offset);
+            match mirrored {
+                true => indices.extend([a, c, b]),
+                false => indices.extend([a, b, c]),
+            }
+        }
+    }
+    (vertices, indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merged_meshes_are_moved_into_world_space() {
+        let triangle = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]].map(|position| Vertex {
+            position,
+            normal: [0.0, 0.0, 1.0],
+            ..Default::default()
+        });
+        let indices = [0, 1, 2];
+
+        let (vertices, merged) = merge_meshes([
+            (&triangle[..], &indices[..], Mat4::IDENTITY),
+            (
+                &triangle[..],
+                &indices[..],
+                Mat4::from_translation(Vec3::X * 10.0),
+            ),
+            (
+                &triangle[..],
+                &indices[..],
+                Mat4::from_scale(Vec3::new(-1.0, 1.0, 1.0)),
+            ),
+        ]);
+
+        assert_eq!(vertices.len(), 9);
+        assert_eq!(vertices[4].position, [11.0, 0.0, 0.0]);
+        assert_eq!(vertices[7].position, [-1.0, 0.0, 0.0]);
+        // the indices point at the vertices of their own mesh and the mirrored one is flipped
+        assert_eq!(merged, [0, 1, 2, 3, 4, 5, 6, 8, 7]);
+    }
+}
@@ -0,0 +1,552 @@
+//! tile maps imported from the [Tiled](https://www.mapeditor.org/) editor
+//!
+//! a [`TiledMap`] asset loads a `.tmx` file and spawns a node tree for it:
+//! - a [`Tilemap`] root holding the map size
+//! - a [`TileLayer`] for each tile layer with the tile grid and its meshes split into chunks of
+//!   [`CHUNK_SIZE`] tiles so each chunk is frustum culled on its own
+//! - an [`Empty`] for each object layer with a [`TileObject`] child for every object
+//!
+//! one world unit is one tile. the map extends along +x and -y from the [`Tilemap`] node so the
+//! top left of the map is at the origin like in the editor, and each layer is placed
+//! [`LAYER_SPACING`] above the one before it on +z.
+//!
+//! tiles are drawn with double sided alpha masked [`PbrMaterial`]s so the map can be viewed by
+//! any [`crate::nodes::camera::Camera3D`] facing -z.
+//!
+//! ```rust, ignore
+//! let map = ctx.assets.load::<TiledMap>("res/maps/level_1.tmx");
+//! ctx.scene.merge_asset(map);
+//!
+//! // objects keep their shape but don't collide on their own, `maple_physics` builds
+//! // colliders for them with its `tilemap` feature
+//! for object in ctx.scene.collect::<TileObject>() {
+//!     let object = object.read();
+//!     if object.class == "wall" { /* ... */ }
+//! }
+//! ```
+
+pub mod tiled;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+    sync::Arc,
+};
+
+use glam::{Quat, Vec2, Vec3};
+use maple_engine::{
+    Node, Scene,
+    asset::{Asset, AssetHandle, AssetLibrary, AssetLoader, FileLoader, LoadErr},
+    color::Color,
+    nodes::{Buildable, Builder, Empty},
+    prelude::NodeTransform,
+    scene::{InstancableScene, InstanceId, NodeId, SceneAsset},
+};
+use maple_renderer::core::{CullMode, RenderDevice, texture::Texture};
+
+use crate::{
+    assets::{material::AlphaMode, materials::PbrMaterial, mesh::Mesh3D},
+    math::Vertex,
+    nodes::mesh_instance::MeshInstance3D,
+    prelude::Material,
+};
+
+use tiled::{
+    TileId, TiledLayer, TiledMapData, TiledObjectLayer, TiledProperties, TiledShape, TiledTileLayer,
+};
+
+/// width and height in tiles of the meshes tile layers are split into
+pub const CHUNK_SIZE: u32 = 16;
+
+/// distance on z between layers so later layers draw over earlier ones
+pub const LAYER_SPACING: f32 = 0.01;
+
+/// root node of a spawned tile map
+#[derive(Clone, Default)]
+pub struct Tilemap {
+    pub transform: NodeTransform,
+    /// size of the map in tiles
+    pub width: u32,
+    pub height: u32,
+    /// size of a tile in pixels in the editor
+    pub tile_size: Vec2,
+    pub properties: TiledProperties,
+}
+
+impl Node for Tilemap {
+    fn get_transform(&mut self) -> &mut NodeTransform {
+        &mut self.transform
+    }
+}
+
+impl Tilemap {
+    /// position of the center of a cell relative to the map
+    pub fn cell_to_local(&self, x: u32, y: u32) -> Vec2 {
+        Vec2::new(x as f32 + 0.5, -(y as f32) - 0.5)
+    }
+
+    /// the cell at a position relative to the map or None if it is outside the map
+    pub fn local_to_cell(&self, position: Vec2) -> Option<(u32, u32)> {
+        let x = position.x.floor();
+        let y = (-position.y).floor();
+        if x < 0.0 || y < 0.0 || x >= self.width as f32 || y >= self.height as f32 {
+            return None;
+        }
+        Some((x as u32, y as u32))
+    }
+
+    /// convert pixel coordinates from the editor to a position relative to the map
+    pub fn pixels_to_local(&self, pixels: Vec2) -> Vec2 {
+        Vec2::new(pixels.x, -pixels.y) / self.tile_size.max(Vec2::ONE)
+    }
+}
+
+/// a tile layer of a [`Tilemap`]. the meshes of the layer are its children
+#[derive(Clone, Default)]
+pub struct TileLayer {
+    pub transform: NodeTransform,
+    pub name: String,
+    /// size of the layer in tiles
+    pub width: u32,
+    pub height: u32,
+    /// row major tiles starting at the top left
+    pub tiles: Arc<Vec<TileId>>,
+    pub properties: TiledProperties,
+}
+
+impl Node for TileLayer {
+    fn get_transform(&mut self) -> &mut NodeTransform {
+        &mut self.transform
+    }
+}
+
+impl TileLayer {
+    /// the tile at a cell or None if the cell is outside the layer
+    pub fn tile(&self, x: u32, y: u32) -> Option<TileId> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.tiles.get((y * self.width + x) as usize).copied()
+    }
+
+    /// true if the cell is inside the layer and has a tile
+    pub fn has_tile(&self, x: u32, y: u32) -> bool {
+        self.tile(x, y).is_some_and(|tile| !tile.is_empty())
+    }
+}
+
+/// an object from an object layer
+///
+/// the object is placed at its position in the editor. `size` and the points of `shape` are in
+/// tiles with +y up so they can be used directly to build colliders. objects don't collide on
+/// their own, see `maple_physics::tilemap` for turning them into colliders.
+#[derive(Clone)]
+pub struct TileObject {
+    pub transform: NodeTransform,
+    /// id of the object in the editor
+    pub id: u32,
+    pub name: String,
+    pub class: String,
+    pub size: Vec2,
+    pub shape: TiledShape,
+    pub properties: TiledProperties,
+}
+
+impl Node for TileObject {
+    fn get_transform(&mut self) -> &mut NodeTransform {
+        &mut self.transform
+    }
+}
+
+impl TileObject {
+    /// offset from the node to the center of the object
+    ///
+    /// rectangles and ellipses extend down from their top left corner while tile objects extend
+    /// up from their bottom left corner.
+    pub fn center(&self) -> Vec2 {
+        match self.shape {
+            TiledShape::Rectangle | TiledShape::Ellipse => {
+                Vec2::new(self.size.x, -self.size.y) * 0.5
+            }
+            TiledShape::Tile(_) => self.size * 0.5,
+            TiledShape::Point | TiledShape::Polygon(_) | TiledShape::Polyline(_) => Vec2::ZERO,
+        }
+    }
+}
+
+/// a map made in Tiled as an [`Asset`]
+///
+/// # Example
+/// ```no_run
+/// # use maple_3d::prelude::*;
+/// # use maple_engine::prelude::*;
+/// # let assets = AssetLibrary::default();
+/// # let scene = Scene::default();
+/// let map = assets.load::<TiledMap>("path/to/map.tmx");
+/// scene.merge_asset(map);
+/// ```
+pub struct TiledMap {
+    data: TiledMapData,
+    /// one texture per tileset
+    textures: Vec<AssetHandle<Texture>>,
+    scene: InstancableScene,
+}
+
+impl Asset for TiledMap {
+    type Loader = TiledMapLoader;
+}
+
+impl TiledMap {
+    /// the parsed map
+    pub fn data(&self) -> &TiledMapData {
+        &self.data
+    }
+
+    /// the texture of a tileset by its index in [`TiledMapData::tilesets`]
+    pub fn get_texture(&self, tileset: usize) -> Option<AssetHandle<Texture>> {
+        self.textures.get(tileset).cloned()
+    }
+}
+
+pub struct TiledMapLoader {
+    pub(crate) device: RenderDevice,
+}
+
+impl TiledMapLoader {
+    pub fn new(device: RenderDevice) -> Self {
+        Self { device }
+    }
+}
+
+impl AssetLoader for TiledMapLoader {
+    type Asset = TiledMap;
+}
+
+impl FileLoader for TiledMapLoader {
+    fn load_path(&self, path: &Path, library: &AssetLibrary) -> Result<Self::Asset, LoadErr> {
+        log::info!("Loading tiled map from {:?}", path);
+        let data = TiledMapData::from_file(path)?;
+
+        let textures: Vec<AssetHandle<Texture>> = data
+            .tilesets
+            .iter()
+            .map(|tileset| library.load::<Texture>(&tileset.image))
+            .collect();
+
+        let mut materials = MaterialCache {
+            textures: &textures,
+            materials: HashMap::new(),
+        };
+
+        let map_name = path
+            .file_stem()
+            .and_then(|name| name.to_str())
+            .unwrap_or("tilemap");
+
+        let scene = InstancableScene::new();
+        let root = scene.spawn(
+            map_name,
+            Tilemap {
+                transform: NodeTransform::default(),
+                width: data.width,
+                height: data.height,
+                tile_size: Vec2::new(data.tile_width as f32, data.tile_height as f32),
+                properties: data.properties.clone(),
+            },
+        );
+
+        for (index, layer) in data.layers.iter().enumerate() {
+            let position = Vec3::new(0.0, 0.0, index as f32 * LAYER_SPACING);
+            match layer {
+                TiledLayer::Tiles(layer) => spawn_tile_layer(
+                    self,
+                    library,
+                    &data,
+                    layer,
+                    position,
+                    &mut materials,
+                    &scene,
+                    root,
+                ),
+                TiledLayer::Objects(layer) => spawn_object_layer(
+                    self,
+                    library,
+                    &data,
+                    layer,
+                    position,
+                    &mut materials,
+                    &scene,
+                    root,
+                ),
+            }
+        }
+
+        log::info!("Finished loading tiled map from {:?}", path);
+
+        Ok(TiledMap {
+            data,
+            textures,
+            scene,
+        })
+    }
+}
+
+impl SceneAsset for TiledMap {
+    fn load(&self, scene: &Scene, parent: Option<NodeId>) {
+        match parent {
+            Some(node) => scene.merge_as_child(self.scene.instance(), node),
+            None => scene.merge(self.scene.instance()),
+        };
+    }
+}
+
+/// materials shared between layers by tileset and opacity
+struct MaterialCache<'a> {
+    textures: &'a [AssetHandle<Texture>],
+    materials: HashMap<(usize, u32), AssetHandle<Material>>,
+}
+
+impl MaterialCache<'_> {
+    fn get(
+        &mut self,
+        library: &AssetLibrary,
+        tileset: usize,
+        opacity: f32,
+    ) -> AssetHandle<Material> {
+        let texture = self.textures[tileset].clone();
+        self.materials
+            .entry((tileset, opacity.to_bits()))
+            .or_insert_with(|| {
+                let translucent = opacity < 1.0;
+                library.add(PbrMaterial {
+                    base_color_factor: Color::from_normalized(1.0, 1.0, 1.0, opacity),
+                    base_color_texture: Some(texture),
+                    roughness_factor: 1.0,
+                    alpha_mode: if translucent {
+                        AlphaMode::Blend
+                    } else {
+                        AlphaMode::Mask
+                    },
+                    double_sided: true,
+                    cast_shadows: false,
+                    cull_mode: CullMode::None,
+                    ..Default::default()
+                })
+            })
+            .clone()
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_tile_layer(
+    loader: &TiledMapLoader,
+    library: &AssetLibrary,
+    data: &TiledMapData,
+    layer: &TiledTileLayer,
+    position: Vec3,
+    materials: &mut MaterialCache,
+    scene: &InstancableScene,
+    root: InstanceId,
+) {
+    let offset = pixels_to_tiles(data, layer.offset);
+    let layer_id = scene.spawn_as_child(
+        &layer.name,
+        TileLayer {
+            transform: NodeTransform::new(position + offset.extend(0.0), Quat::IDENTITY, Vec3::ONE),
+            name: layer.name.clone(),
+            width: layer.width,
+            height: layer.height,
+            tiles: Arc::new(layer.tiles.clone()),
+            properties: layer.properties.clone(),
+        },
+        root,
+    );
+
+    // hidden layers are usually collision or marker layers so only their data is kept
+    if !layer.visible {
+        return;
+    }
+
+    // group tiles by chunk and tileset so each group becomes one mesh
+    let mut chunks: BTreeMap<(u32, u32, usize), TileQuads> = BTreeMap::new();
+    for y in 0..layer.height {
+        for x in 0..layer.width {
+            let Some(tile) = layer.tile(x, y).filter(|tile| !tile.is_empty()) else {
+                continue;
+            };
+            let Some(tileset) = data
+                .tilesets
+                .iter()
+                .rposition(|tileset| tileset.contains(tile.gid()))
+            else {
+                log::warn!("tile {} in layer {} has no tileset", tile.gid(), layer.name);
+                continue;
+            };
+
+            chunks
+                .entry((x / CHUNK_SIZE, y / CHUNK_SIZE, tileset))
+                .or_default()
+                .push(data, tileset, tile, Vec2::new(x as f32, -(y as f32) - 1.0));
+        }
+    }
+
+    for ((chunk_x, chunk_y, tileset), quads) in chunks {
+        let mesh = library.add(Mesh3D::new(&loader.device, &quads.vertices, &quads.indices));
+        let mesh_instance = MeshInstance3D::builder()
+            .mesh(mesh)
+            .material(materials.get(library, tileset, layer.opacity))
+            .build();
+
+        let name = format!("chunk_{}_{}_{}", chunk_x, chunk_y, tileset);
+        scene.spawn_as_child(&name, mesh_instance, layer_id);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_object_layer(
+    loader: &TiledMapLoader,
+    library: &AssetLibrary,
+    data: &TiledMapData,
+    layer: &TiledObjectLayer,
+    position: Vec3,
+    materials: &mut MaterialCache,
+    scene: &InstancableScene,
+    root: InstanceId,
+) {
+    let offset = pixels_to_tiles(data, layer.offset);
+    let layer_id = scene.spawn_as_child(
+        &layer.name,
+        Empty::builder()
+            .position(position + offset.extend(0.0))
+            .build(),
+        root,
+    );
+
+    for object in &layer.objects {
+        let to_tiles = |points: &[Vec2]| -> Vec<Vec2> {
+            points
+                .iter()
+                .map(|point| pixels_to_tiles(data, *point))
+                .collect()
+        };
+        let shape = match &object.shape {
+            TiledShape::Polygon(points) => TiledShape::Polygon(to_tiles(points)),
+            TiledShape::Polyline(points) => TiledShape::Polyline(to_tiles(points)),
+            shape => shape.clone(),
+        };
+
+        let size = object.size / Vec2::new(data.tile_width as f32, data.tile_height as f32);
+        let mut transform = NodeTransform::default();
+        transform.set_position(pixels_to_tiles(data, object.position).extend(0.0));
+        // tiled rotates clockwise
+        transform.set_rotation(Quat::from_rotation_z(-object.rotation.to_radians()));
+
+        let name = match object.name.is_empty() {
+            true => format!("object_{}", object.id),
+            false => object.name.clone(),
+        };
+
+        let object_id = scene.spawn_as_child(
+            &name,
+            TileObject {
+                transform,
+                id: object.id,
+                name: object.name.clone(),
+                class: object.class.clone(),
+                size,
+                shape: shape.clone(),
+                properties: object.properties.clone(),
+            },
+            layer_id,
+        );
+
+        // tile objects draw their tile stretched to the object size
+        if let TiledShape::Tile(tile) = shape
+            && layer.visible
+            && object.visible
+            && let Some(tileset) = data
+                .tilesets
+                .iter()
+                .rposition(|tileset| tileset.contains(tile.gid()))
+        {
+            let mut quads = TileQuads::default();
+            quads.push_sized(data, tileset, tile, Vec2::ZERO, size);
+
+            let mesh = library.add(Mesh3D::new(&loader.device, &quads.vertices, &quads.indices));
+            let mesh_instance = MeshInstance3D::builder()
+                .mesh(mesh)
+                .material(materials.get(library, tileset, 1.0))
+                .build();
+            scene.spawn_as_child("tile", mesh_instance, object_id);
+        }
+    }
+}
+
+fn pixels_to_tiles(data: &TiledMapData, pixels: Vec2) -> Vec2 {
+    let tile_size = Vec2::new(data.tile_width as f32, data.tile_height as f32).max(Vec2::ONE);
+    Vec2::new(pixels.x, -pixels.y) / tile_size
+}
+
+/// vertices of a group of tile quads facing +z
+#[derive(Default)]
+struct TileQuads {
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+}
+
+impl TileQuads {
+    /// add a tile at the size its tileset draws it with its bottom left corner at `position`
+    fn push(&mut self, data: &TiledMapData, tileset: usize, tile: TileId, position: Vec2) {
+        let set = &data.tilesets[tileset];
+        let size = Vec2::new(
+            set.tile_width as f32 / data.tile_width.max(1) as f32,
+            set.tile_height as f32 / data.tile_height.max(1) as f32,
+        );
+        self.push_sized(data, tileset, tile, position, size);
+    }
+
+    fn push_sized(
+        &mut self,
+        data: &TiledMapData,
+        tileset: usize,
+        tile: TileId,
+        position: Vec2,
+        size: Vec2,
+    ) {
+        let (uv_min, uv_max) = data.tilesets[tileset].uv_rect(tile.gid());
+
+        // corners of the quad from the top left going clockwise as (x, y) in the tile with y down
+        let corners = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+
+        let base = self.vertices.len() as u32;
+        for (x, y) in corners {
+            // undo the flips tiled applies (diagonal then horizontal then vertical) to find which
+            // part of the tile image is drawn at this corner
+            let (mut u, mut v) = (x, y);
+            if tile.flipped_vertically() {
+                v = 1.0 - v;
+            }
+            if tile.flipped_horizontally() {
+                u = 1.0 - u;
+            }
+            if tile.flipped_diagonally() {
+                (u, v) = (v, u);
+            }
+
+            let corner = position + Vec2::new(x, 1.0 - y) * size;
+            let uv = uv_min + (uv_max - uv_min) * Vec2::new(u, v);
+
+            self.vertices.push(Vertex {
+                position: [corner.x, corner.y, 0.0],
+                normal: [0.0, 0.0, 1.0],
+                tex_uv: uv.into(),
+                tangent: [1.0, 0.0, 0.0],
+                bitangent: [0.0, 1.0, 0.0],
+                ..Default::default()
+            });
+        }
+
+        self.indices
+            .extend([base, base + 3, base + 2, base, base + 2, base + 1]);
+    }
+}
@@ -0,0 +1,700 @@
+//! parser for maps made with the [Tiled](https://www.mapeditor.org/) editor
+//!
+//! supports orthogonal `.tmx` maps with embedded or external (`.tsx`) tilesets, tile layers
+//! encoded as csv, xml or base64 (optionally zlib or gzip compressed) and object layers.
+
+use std::{
+    collections::HashMap,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use base64::Engine;
+use glam::Vec2;
+use maple_engine::asset::LoadErr;
+use quick_xml::{Reader, events::Event};
+
+const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+const ROTATED_HEXAGONAL: u32 = 0x1000_0000;
+const FLIP_MASK: u32 =
+    FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY | ROTATED_HEXAGONAL;
+
+/// a tile in a layer. `gid` 0 is an empty cell
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TileId(pub u32);
+
+impl TileId {
+    pub const EMPTY: TileId = TileId(0);
+
+    /// the global tile id without the flip flags
+    pub fn gid(self) -> u32 {
+        self.0 & !FLIP_MASK
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.gid() == 0
+    }
+
+    pub fn flipped_horizontally(self) -> bool {
+        self.0 & FLIPPED_HORIZONTALLY != 0
+    }
+
+    pub fn flipped_vertically(self) -> bool {
+        self.0 & FLIPPED_VERTICALLY != 0
+    }
+
+    pub fn flipped_diagonally(self) -> bool {
+        self.0 & FLIPPED_DIAGONALLY != 0
+    }
+}
+
+/// a custom property set in the editor
+#[derive(Clone, Debug, PartialEq)]
+pub enum TiledProperty {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    /// strings, colors, files and object references are kept as text
+    String(String),
+}
+
+impl TiledProperty {
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            TiledProperty::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            TiledProperty::Int(value) => Some(*value as f64),
+            TiledProperty::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            TiledProperty::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+pub type TiledProperties = HashMap<String, TiledProperty>;
+
+/// a tileset image split into a grid of tiles
+#[derive(Clone, Debug, Default)]
+pub struct Tileset {
+    /// gid of the first tile in this tileset
+    pub first_gid: u32,
+    pub name: String,
+    /// size of a tile in pixels
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub tile_count: u32,
+    pub columns: u32,
+    pub spacing: u32,
+    pub margin: u32,
+    /// path to the image resolved relative to the map
+    pub image: PathBuf,
+    pub image_width: u32,
+    pub image_height: u32,
+    /// properties of individual tiles by their local id
+    pub tiles: HashMap<u32, TiledProperties>,
+}
+
+impl Tileset {
+    /// true if the global id belongs to this tileset
+    pub fn contains(&self, gid: u32) -> bool {
+        gid >= self.first_gid && gid < self.first_gid + self.tile_count
+    }
+
+    /// uv rectangle (min, max) of a tile in the tileset image
+    pub fn uv_rect(&self, gid: u32) -> (Vec2, Vec2) {
+        let local = gid - self.first_gid;
+        let columns = self.columns.max(1);
+        let x = self.margin + (local % columns) * (self.tile_width + self.spacing);
+        let y = self.margin + (local / columns) * (self.tile_height + self.spacing);
+
+        let size = Vec2::new(self.image_width as f32, self.image_height as f32).max(Vec2::ONE);
+        let min = Vec2::new(x as f32, y as f32) / size;
+        let max = Vec2::new((x + self.tile_width) as f32, (y + self.tile_height) as f32) / size;
+        (min, max)
+    }
+}
+
+/// a grid of tiles
+#[derive(Clone, Debug, Default)]
+pub struct TiledTileLayer {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub visible: bool,
+    pub opacity: f32,
+    /// offset in pixels
+    pub offset: Vec2,
+    /// row major tiles starting at the top left
+    pub tiles: Vec<TileId>,
+    pub properties: TiledProperties,
+}
+
+impl TiledTileLayer {
+    /// the tile at a cell or None if it is outside the layer
+    pub fn tile(&self, x: u32, y: u32) -> Option<TileId> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.tiles.get((y * self.width + x) as usize).copied()
+    }
+}
+
+/// the shape of an object
+#[derive(Clone, Debug, PartialEq)]
+pub enum TiledShape {
+    Rectangle,
+    Ellipse,
+    Point,
+    /// points relative to the object position in pixels
+    Polygon(Vec<Vec2>),
+    Polyline(Vec<Vec2>),
+    /// an object that draws a tile
+    Tile(TileId),
+}
+
+/// an object placed on an object layer
+#[derive(Clone, Debug)]
+pub struct TiledObject {
+    pub id: u32,
+    pub name: String,
+    /// the `class` (or `type` in older versions) set in the editor
+    pub class: String,
+    /// position in pixels from the top left of the map
+    pub position: Vec2,
+    /// size in pixels
+    pub size: Vec2,
+    /// clockwise rotation in degrees
+    pub rotation: f32,
+    pub visible: bool,
+    pub shape: TiledShape,
+    pub properties: TiledProperties,
+}
+
+/// a layer of objects
+#[derive(Clone, Debug, Default)]
+pub struct TiledObjectLayer {
+    pub name: String,
+    pub visible: bool,
+    pub offset: Vec2,
+    pub objects: Vec<TiledObject>,
+    pub properties: TiledProperties,
+}
+
+/// a layer of a map in drawing order
+#[derive(Clone, Debug)]
+pub enum TiledLayer {
+    Tiles(TiledTileLayer),
+    Objects(TiledObjectLayer),
+}
+
+/// a parsed `.tmx` map
+#[derive(Clone, Debug, Default)]
+pub struct TiledMapData {
+    /// size in tiles
+    pub width: u32,
+    pub height: u32,
+    /// size of a tile in pixels
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub tilesets: Vec<Tileset>,
+    pub layers: Vec<TiledLayer>,
+    pub properties: TiledProperties,
+}
+
+impl TiledMapData {
+    /// load a map and its external tilesets from a file
+    pub fn from_file(path: &Path) -> Result<Self, LoadErr> {
+        let source = std::fs::read_to_string(path).map_err(|e| LoadErr::Import(e.to_string()))?;
+        Self::parse(&source, path.parent().unwrap_or(Path::new("")))
+    }
+
+    /// parse a map. external tilesets and images are resolved relative to `base_dir`
+    pub fn parse(source: &str, base_dir: &Path) -> Result<Self, LoadErr> {
+        let root = XmlElement::parse(source)?;
+        if root.name != "map" {
+            return Err(invalid("the root element is not a map"));
+        }
+
+        let orientation = root.attr("orientation").unwrap_or("orthogonal");
+        if orientation != "orthogonal" {
+            return Err(LoadErr::Import(format!(
+                "{orientation} maps are not supported"
+            )));
+        }
+        if root.attr("infinite") == Some("1") {
+            return Err(LoadErr::Import("infinite maps are not supported".into()));
+        }
+
+        let mut map = TiledMapData {
+            width: root.parse_attr("width")?,
+            height: root.parse_attr("height")?,
+            tile_width: root.parse_attr("tilewidth")?,
+            tile_height: root.parse_attr("tileheight")?,
+            properties: parse_properties(&root),
+            ..Default::default()
+        };
+
+        for child in &root.children {
+            match child.name.as_str() {
+                "tileset" => map.tilesets.push(parse_tileset(child, base_dir)?),
+                "layer" => map.layers.push(TiledLayer::Tiles(parse_tile_layer(child)?)),
+                "objectgroup" => map
+                    .layers
+                    .push(TiledLayer::Objects(parse_object_layer(child)?)),
+                // groups are flattened
+                "group" => {
+                    for layer in &child.children {
+                        match layer.name.as_str() {
+                            "layer" => map.layers.push(TiledLayer::Tiles(parse_tile_layer(layer)?)),
+                            "objectgroup" => map
+                                .layers
+                                .push(TiledLayer::Objects(parse_object_layer(layer)?)),
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        map.tilesets.sort_by_key(|tileset| tileset.first_gid);
+
+        Ok(map)
+    }
+
+    /// the tileset a tile belongs to
+    pub fn tileset_for(&self, tile: TileId) -> Option<&Tileset> {
+        let gid = tile.gid();
+        self.tilesets
+            .iter()
+            .rev()
+            .find(|tileset| tileset.contains(gid))
+    }
+
+    /// the properties set on a tile in its tileset
+    pub fn tile_properties(&self, tile: TileId) -> Option<&TiledProperties> {
+        let tileset = self.tileset_for(tile)?;
+        tileset.tiles.get(&(tile.gid() - tileset.first_gid))
+    }
+}
+
+fn invalid(message: &str) -> LoadErr {
+    LoadErr::Import(format!("invalid tiled map: {message}"))
+}
+
+fn parse_tileset(element: &XmlElement, base_dir: &Path) -> Result<Tileset, LoadErr> {
+    let first_gid = element.parse_attr("firstgid")?;
+
+    // external tilesets only store the first gid in the map
+    if let Some(source) = element.attr("source") {
+        let path = base_dir.join(source);
+        let source = std::fs::read_to_string(&path).map_err(|e| LoadErr::Import(e.to_string()))?;
+        let external = XmlElement::parse(&source)?;
+        let mut tileset =
+            parse_tileset_contents(&external, path.parent().unwrap_or(Path::new("")))?;
+        tileset.first_gid = first_gid;
+        return Ok(tileset);
+    }
+
+    let mut tileset = parse_tileset_contents(element, base_dir)?;
+    tileset.first_gid = first_gid;
+    Ok(tileset)
+}
+
+fn parse_tileset_contents(element: &XmlElement, base_dir: &Path) -> Result<Tileset, LoadErr> {
+    let image = element
+        .child("image")
+        .ok_or_else(|| invalid("image collection tilesets are not supported"))?;
+
+    let mut tileset = Tileset {
+        first_gid: 1,
+        name: element.attr("name").unwrap_or_default().to_string(),
+        tile_width: element.parse_attr("tilewidth")?,
+        tile_height: element.parse_attr("tileheight")?,
+        tile_count: element.parse_attr("tilecount")?,
+        columns: element.parse_attr("columns")?,
+        spacing: element.parse_attr_or("spacing", 0)?,
+        margin: element.parse_attr_or("margin", 0)?,
+        image: base_dir.join(image.attr("source").unwrap_or_default()),
+        image_width: image.parse_attr("width")?,
+        image_height: image.parse_attr("height")?,
+        tiles: HashMap::new(),
+    };
+
+    for tile in element.children_named("tile") {
+        let properties = parse_properties(tile);
+        if !properties.is_empty() {
+            tileset.tiles.insert(tile.parse_attr("id")?, properties);
+        }
+    }
+
+    Ok(tileset)
+}
+
+fn parse_tile_layer(element: &XmlElement) -> Result<TiledTileLayer, LoadErr> {
+    let width: u32 = element.parse_attr("width")?;
+    let height: u32 = element.parse_attr("height")?;
+    let data = element
+        .child("data")
+        .ok_or_else(|| invalid("tile layer has no data"))?;
+
+    let tiles = match data.attr("encoding") {
+        None => data
+            .children_named("tile")
+            .map(|tile| tile.parse_attr_or("gid", 0).map(TileId))
+            .collect::<Result<Vec<_>, _>>()?,
+        Some("csv") => data
+            .text
+            .split(',')
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(|value| {
+                value
+                    .parse()
+                    .map(TileId)
+                    .map_err(|_| invalid("tile data is not a number"))
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        Some("base64") => {
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(data.text.trim())
+                .map_err(|e| LoadErr::Import(format!("invalid base64 tile data: {e}")))?;
+            let bytes = decompress(bytes, data.attr("compression"))?;
+            bytes
+                .chunks_exact(4)
+                .map(|gid| TileId(u32::from_le_bytes([gid[0], gid[1], gid[2], gid[3]])))
+                .collect()
+        }
+        Some(encoding) => {
+            return Err(LoadErr::Import(format!(
+                "unsupported tile encoding {encoding}"
+            )));
+        }
+    };
+
+    if tiles.len() != (width * height) as usize {
+        return Err(invalid("tile layer data does not match its size"));
+    }
+
+    Ok(TiledTileLayer {
+        name: element.attr("name").unwrap_or_default().to_string(),
+        width,
+        height,
+        visible: element.attr("visible") != Some("0"),
+        opacity: element.parse_attr_or("opacity", 1.0)?,
+        offset: parse_offset(element)?,
+        tiles,
+        properties: parse_properties(element),
+    })
+}
+
+fn decompress(bytes: Vec<u8>, compression: Option<&str>) -> Result<Vec<u8>, LoadErr> {
+    let mut out = Vec::new();
+    let result = match compression {
+        None => return Ok(bytes),
+        Some("zlib") => flate2::read::ZlibDecoder::new(bytes.as_slice()).read_to_end(&mut out),
+        Some("gzip") => flate2::read::GzDecoder::new(bytes.as_slice()).read_to_end(&mut out),
+        Some(compression) => {
+            return Err(LoadErr::Import(format!(
+                "unsupported tile compression {compression}"
+            )));
+        }
+    };
+    result.map_err(|e| LoadErr::Import(format!("failed to decompress tile data: {e}")))?;
+    Ok(out)
+}
+
+fn parse_object_layer(element: &XmlElement) -> Result<TiledObjectLayer, LoadErr> {
+    let mut objects = Vec::new();
+
+    for object in element.children_named("object") {
+        let shape = if object.child("ellipse").is_some() {
+            TiledShape::Ellipse
+        } else if object.child("point").is_some() {
+            TiledShape::Point
+        } else if let Some(polygon) = object.child("polygon") {
+            TiledShape::Polygon(parse_points(polygon)?)
+        } else if let Some(polyline) = object.child("polyline") {
+            TiledShape::Polyline(parse_points(polyline)?)
+        } else if let Some(gid) = object.attr("gid") {
+            TiledShape::Tile(TileId(
+                gid.parse()
+                    .map_err(|_| invalid("object gid is not a number"))?,
+            ))
+        } else {
+            TiledShape::Rectangle
+        };
+
+        objects.push(TiledObject {
+            id: object.parse_attr_or("id", 0)?,
+            name: object.attr("name").unwrap_or_default().to_string(),
+            class: object
+                .attr("class")
+                .or(object.attr("type"))
+                .unwrap_or_default()
+                .to_string(),
+            position: Vec2::new(
+                object.parse_attr_or("x", 0.0)?,
+                object.parse_attr_or("y", 0.0)?,
+            ),
+            size: Vec2::new(
+                object.parse_attr_or("width", 0.0)?,
+                object.parse_attr_or("height", 0.0)?,
+            ),
+            rotation: object.parse_attr_or("rotation", 0.0)?,
+            visible: object.attr("visible") != Some("0"),
+            shape,
+            properties: parse_properties(object),
+        });
+    }
+
+    Ok(TiledObjectLayer {
+        name: element.attr("name").unwrap_or_default().to_string(),
+        visible: element.attr("visible") != Some("0"),
+        offset: parse_offset(element)?,
+        objects,
+        properties: parse_properties(element),
+    })
+}
+
+fn parse_points(element: &XmlElement) -> Result<Vec<Vec2>, LoadErr> {
+    element
+        .attr("points")
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(|point| {
+            let (x, y) = point
+                .split_once(',')
+                .ok_or_else(|| invalid("invalid polygon point"))?;
+            let x = x.parse().map_err(|_| invalid("invalid polygon point"))?;
+            let y = y.parse().map_err(|_| invalid("invalid polygon point"))?;
+            Ok(Vec2::new(x, y))
+        })
+        .collect()
+}
+
+fn parse_offset(element: &XmlElement) -> Result<Vec2, LoadErr> {
+    Ok(Vec2::new(
+        element.parse_attr_or("offsetx", 0.0)?,
+        element.parse_attr_or("offsety", 0.0)?,
+    ))
+}
+
+fn parse_properties(element: &XmlElement) -> TiledProperties {
+    let Some(properties) = element.child("properties") else {
+        return TiledProperties::new();
+    };
+
+    properties
+        .children_named("property")
+        .filter_map(|property| {
+            let name = property.attr("name")?.to_string();
+            // long string values are stored as text
+            let value = property.attr("value").unwrap_or(property.text.as_str());
+            let value = match property.attr("type").unwrap_or("string") {
+                "bool" => TiledProperty::Bool(value == "true"),
+                "int" | "object" => value
+                    .parse()
+                    .map(TiledProperty::Int)
+                    .unwrap_or_else(|_| TiledProperty::String(value.to_string())),
+                "float" => value
+                    .parse()
+                    .map(TiledProperty::Float)
+                    .unwrap_or_else(|_| TiledProperty::String(value.to_string())),
+                _ => TiledProperty::String(value.to_string()),
+            };
+            Some((name, value))
+        })
+        .collect()
+}
+
+/// minimal xml tree since tiled files are small
+struct XmlElement {
+    name: String,
+    attributes: HashMap<String, String>,
+    children: Vec<XmlElement>,
+    text: String,
+}
+
+impl XmlElement {
+    fn parse(source: &str) -> Result<Self, LoadErr> {
+        let xml_err = |e: quick_xml::Error| LoadErr::Import(format!("invalid xml: {e}"));
+
+        let mut reader = Reader::from_str(source);
+        reader.config_mut().trim_text(true);
+
+        let mut stack: Vec<XmlElement> = Vec::new();
+
+        loop {
+            match reader.read_event().map_err(xml_err)? {
+                Event::Start(start) => stack.push(Self::from_start(&start)?),
+                Event::Empty(start) => {
+                    let element = Self::from_start(&start)?;
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(element),
+                        None => return Ok(element),
+                    }
+                }
+                Event::Text(text) => {
+                    if let Some(element) = stack.last_mut() {
+                        element.text.push_str(&text.unescape().map_err(xml_err)?);
+                    }
+                }
+                Event::CData(data) => {
+                    if let Some(element) = stack.last_mut() {
+                        element
+                            .text
+                            .push_str(&String::from_utf8_lossy(&data.into_inner()));
+                    }
+                }
+                Event::End(_) => {
+                    let element = stack.pop().ok_or_else(|| invalid("unexpected end tag"))?;
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(element),
+                        None => return Ok(element),
+                    }
+                }
+                Event::Eof => return Err(invalid("unexpected end of file")),
+                _ => {}
+            }
+        }
+    }
+
+    fn from_start(start: &quick_xml::events::BytesStart) -> Result<Self, LoadErr> {
+        let mut attributes = HashMap::new();
+        for attribute in start.attributes() {
+            let attribute =
+                attribute.map_err(|e| LoadErr::Import(format!("invalid xml attribute: {e}")))?;
+            let value = attribute
+                .unescape_value()
+                .map_err(|e| LoadErr::Import(format!("invalid xml attribute: {e}")))?;
+            attributes.insert(
+                String::from_utf8_lossy(attribute.key.as_ref()).into_owned(),
+                value.into_owned(),
+            );
+        }
+
+        Ok(Self {
+            name: String::from_utf8_lossy(start.name().as_ref()).into_owned(),
+            attributes,
+            children: Vec::new(),
+            text: String::new(),
+        })
+    }
+
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+
+    fn parse_attr<T: std::str::FromStr>(&self, name: &str) -> Result<T, LoadErr> {
+        self.attr(name)
+            .ok_or_else(|| LoadErr::Import(format!("<{}> is missing `{name}`", self.name)))?
+            .parse()
+            .map_err(|_| LoadErr::Import(format!("<{}> has an invalid `{name}`", self.name)))
+    }
+
+    fn parse_attr_or<T: std::str::FromStr>(&self, name: &str, default: T) -> Result<T, LoadErr> {
+        match self.attr(name) {
+            Some(_) => self.parse_attr(name),
+            None => Ok(default),
+        }
+    }
+
+    fn child(&self, name: &str) -> Option<&XmlElement> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a XmlElement> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="3" height="2" tilewidth="16" tileheight="16" infinite="0">
+ <tileset firstgid="1" name="terrain" tilewidth="16" tileheight="16" tilecount="4" columns="2">
+  <image source="terrain.png" width="32" height="32"/>
+  <tile id="1">
+   <properties>
+    <property name="solid" type="bool" value="true"/>
+   </properties>
+  </tile>
+ </tileset>
+ <layer id="1" name="ground" width="3" height="2">
+  <data encoding="csv">
+1,2,0,
+2147483652,0,1
+</data>
+ </layer>
+ <objectgroup id="2" name="spawns">
+  <object id="1" name="player" type="spawn" x="8" y="24"><point/></object>
+  <object id="2" name="wall" x="0" y="0" width="16" height="32"/>
+ </objectgroup>
+</map>"#;
+
+    #[test]
+    fn test_parse_tiled_map() {
+        let map = TiledMapData::parse(MAP, Path::new("res/maps")).unwrap();
+
+        assert_eq!((map.width, map.height), (3, 2));
+        assert_eq!(map.tilesets[0].image, Path::new("res/maps/terrain.png"));
+
+        let TiledLayer::Tiles(ground) = &map.layers[0] else {
+            panic!("expected a tile layer");
+        };
+        assert_eq!(ground.tile(1, 0), Some(TileId(2)));
+        assert!(ground.tile(2, 0).unwrap().is_empty());
+
+        let flipped = ground.tile(0, 1).unwrap();
+        assert!(flipped.flipped_horizontally());
+        assert_eq!(flipped.gid(), 4);
+
+        assert_eq!(
+            map.tile_properties(TileId(2)).unwrap()["solid"],
+            TiledProperty::Bool(true)
+        );
+        assert_eq!(
+            map.tilesets[0].uv_rect(4),
+            (Vec2::new(0.5, 0.5), Vec2::new(1.0, 1.0))
+        );
+
+        let TiledLayer::Objects(spawns) = &map.layers[1] else {
+            panic!("expected an object layer");
+        };
+        assert_eq!(spawns.objects[0].class, "spawn");
+        assert_eq!(spawns.objects[0].shape, TiledShape::Point);
+        assert_eq!(spawns.objects[1].size, Vec2::new(16.0, 32.0));
+    }
+
+    #[test]
+    fn test_parse_base64_tiles() {
+        let map = r#"<map orientation="orthogonal" width="2" height="1" tilewidth="16" tileheight="16">
+ <layer id="1" name="ground" width="2" height="1">
+  <data encoding="base64">
+   AQAAAAMAAAA=
+  </data>
+ </layer>
+</map>"#;
+        let map = TiledMapData::parse(map, Path::new("")).unwrap();
+        let TiledLayer::Tiles(ground) = &map.layers[0] else {
+            panic!("expected a tile layer");
+        };
+        assert_eq!(ground.tiles, vec![TileId(1), TileId(3)]);
+    }
+}
@@ -0,0 +1,187 @@
+//! which nodes the active [`Camera3D`] can see
+//!
+//! every frame after the update the [`OnScreen`] resource is filled with the nodes inside the
+//! frustum of the active camera, the same one the main pass draws with. nodes are tested with
+//! their [`NodeTransform::world_aabb`] which covers their children too, nodes without one are
+//! tested with their world position. hidden nodes are never on screen. nodes whose state changed
+//! get a [`VisibilityChanged`] event so gameplay can pause ai or skip effects off screen:
+//!
+//! ```rust, ignore
+//! ctx.scene.spawn(enemy).on::<VisibilityChanged>(|ctx| {
+//!     ctx.node_mut().thinking = ctx.event.on_screen;
+//! });
+//!
+//! if ctx.get_resource::<OnScreen>().is_on_screen(enemy) {
+//!     // ...
+//! }
+//! ```
+//!
+//! this is frustum culling only, nodes behind walls are still on screen
+//!
+//! [`NodeTransform::world_aabb`]: maple_engine::components::NodeTransform::world_aabb
+
+use std::collections::HashSet;
+
+use glam::Vec2;
+use maple_engine::{
+    GameContext, Scene,
+    prelude::{EventLabel, Resource},
+    resources::Input,
+    scene::NodeId,
+};
+
+use crate::{math::Frustum, nodes::camera::Camera3D};
+
+/// emitted to a node when it enters or leaves the view of the active camera, see the
+/// [module docs](self)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct VisibilityChanged {
+    /// true if the node just came into view
+    pub on_screen: bool,
+}
+impl EventLabel for VisibilityChanged {}
+
+/// the nodes the active camera saw at the end of the last update
+#[derive(Default, Debug)]
+pub struct OnScreen {
+    camera: Option<NodeId>,
+    nodes: HashSet<NodeId>,
+}
+
+impl Resource for OnScreen {}
+
+impl OnScreen {
+    /// true if the node was inside the frustum of the active camera
+    pub fn is_on_screen(&self, id: NodeId) -> bool {
+        self.nodes.contains(&id)
+    }
+
+    /// every node that is on screen in no particular order
+    pub fn nodes(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes.iter().copied()
+    }
+
+    /// the camera the nodes were tested against, `None` if no camera was active
+    pub fn camera(&self) -> Option<NodeId> {
+        self.camera
+    }
+
+    /// test every node in the scene against the frustum and return the nodes that came into view
+    /// (`true`) or left it (`false`)
+    pub fn update(&mut self, scene: &Scene, frustum: Option<&Frustum>) -> Vec<(NodeId, bool)> {
+        let mut nodes = HashSet::new();
+        if let Some(frustum) = frustum {
+            let mut stack = scene.root_ids();
+            while let Some(id) = stack.pop() {
+                let on_screen = scene.with_transform(id, |transform| {
+                    transform.is_visible_in_tree()
+                        && match transform.world_aabb() {
+                            Some(aabb) => frustum.intersects_aabb(aabb),
+                            None => frustum.contains_point(transform.world_space().position()),
+                        }
+                });
+                if on_screen == Some(true) {
+                    nodes.insert(id);
+                }
+                stack.extend(scene.children_ids(id));
+            }
+        }
+
+        let mut changes: Vec<(NodeId, bool)> = nodes
+            .difference(&self.nodes)
+            .map(|id| (*id, true))
+            .collect();
+        // nodes that were despawned don't hear about it
+        changes.extend(
+            self.nodes
+                .difference(&nodes)
+                .filter(|id| scene.with_transform(**id, |_| ()).is_some())
+                .map(|id| (*id, false)),
+        );
+        changes.sort_unstable();
+
+        self.nodes = nodes;
+        changes
+    }
+}
+
+/// the active camera with the highest priority, the one the main pass draws with
+pub fn active_camera(scene: &Scene) -> Option<NodeId> {
+    scene
+        .collect::<Camera3D>()
+        .into_iter()
+        .filter(|camera| camera.read().is_active)
+        .max_by_key(|camera| camera.read().priority)
+        .map(|camera| camera.id())
+}
+
+pub(crate) fn update_on_screen(ctx: &GameContext) {
+    let screen_size = match ctx.has_resource::<Input>() {
+        true => ctx.get_resource::<Input>().screen_size_pixels(),
+        false => Vec2::ONE,
+    };
+    let aspect_ratio = screen_size.x / screen_size.y.max(1.0);
+
+    let camera = active_camera(&ctx.scene);
+    let frustum = camera
+        .and_then(|camera| ctx.scene.get::<Camera3D>(camera))
+        .map(|camera| Frustum::from_view_proj(&camera.read().get_vp_matrix(aspect_ratio)));
+
+    let changes = {
+        let mut on_screen = ctx.get_resource_mut::<OnScreen>();
+        on_screen.camera = camera;
+        on_screen.update(&ctx.scene, frustum.as_ref())
+    };
+
+    for (id, on_screen) in changes {
+        ctx.scene.emit_to(id, &VisibilityChanged { on_screen }, ctx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use maple_engine::nodes::{Buildable, Builder, Empty};
+
+    use super::*;
+
+    #[test]
+    fn test_nodes_enter_and_leave_the_frustum() {
+        let scene = Scene::new();
+        let camera = Camera3D::builder().build();
+        let forward = camera.transform.get_forward_vector();
+        let frustum = Frustum::from_view_proj(&camera.get_vp_matrix(1.0));
+
+        let ahead = scene
+            .spawn(Empty::builder().position(forward * 5.0).build())
+            .id();
+        let behind = scene
+            .spawn(Empty::builder().position(-forward * 5.0).build())
+            .id();
+        scene.sync_world_transform();
+
+        let mut on_screen = OnScreen::default();
+        assert_eq!(
+            on_screen.update(&scene, Some(&frustum)),
+            vec![(ahead, true)]
+        );
+        assert!(on_screen.is_on_screen(ahead));
+        assert!(!on_screen.is_on_screen(behind));
+
+        // nothing changed so nothing is reported
+        assert!(on_screen.update(&scene, Some(&frustum)).is_empty());
+
+        scene.set_visible(ahead, false);
+        scene.sync_world_transform();
+        assert_eq!(
+            on_screen.update(&scene, Some(&frustum)),
+            vec![(ahead, false)]
+        );
+
+        scene.set_visible(ahead, true);
+        scene.sync_world_transform();
+        on_screen.update(&scene, Some(&frustum));
+        scene.despawn(ahead);
+        assert!(on_screen.update(&scene, Some(&frustum)).is_empty());
+        assert!(!on_screen.is_on_screen(ahead));
+    }
+}
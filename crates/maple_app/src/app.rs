@@ -1,10 +1,21 @@
 use anyhow::Result;
 use log::error;
-use maple_engine::{context::GameContext, prelude::Frame, scene::IntoScene};
+use maple_engine::{
+    context::GameContext,
+    loading::SceneLoader,
+    prelude::{Frame, Resumed, Suspended},
+    scene::IntoScene,
+};
 use std::{marker::PhantomData, process, rc::Rc, sync::Arc};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
 use winit::{
     application::ApplicationHandler,
     dpi::PhysicalSize,
+    error::EventLoopError,
     event::WindowEvent,
     event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
     window::{Fullscreen, Window, WindowId},
@@ -12,15 +23,13 @@ use winit::{
 
 use maple_renderer::{
     core::renderer::Renderer,
+    render_graph::description::GraphDescription,
     types::{Dimensions, render_config::RenderConfig},
 };
 
-#[cfg(target_arch = "wasm32")]
-use wasm_bindgen::prelude::*;
-
 use crate::{
     app_error::AppError,
-    config::{Config, WindowMode},
+    config::{Config, InputSampling, UpdateMode, WindowMode},
     default_plugin::DefaultPlugin,
     plugin::Plugin,
 };
@@ -38,17 +47,19 @@ pub struct App<S = Init> {
     // state: Option<AppState>,
     renderer: Renderer,
     window: Option<Arc<Window>>,
+    /// true while the app is in the background without a surface
+    suspended: bool,
+    /// true if the window got events since the last frame, see [`UpdateMode::OnDemand`]
+    input_received: bool,
+    focused: bool,
+    last_frame: Instant,
     context: GameContext,
     config: Config,
     plugins: Vec<Rc<dyn Plugin>>,
-    #[cfg(target_arch = "wasm32")]
-    pending_renderer: Option<(
-        Arc<Window>,
-        std::rc::Rc<std::cell::RefCell<Option<Renderer>>>,
-    )>,
     _marker: PhantomData<S>,
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl Default for App<Init> {
     fn default() -> Self {
         Self::new(Config::default())
@@ -57,24 +68,70 @@ impl Default for App<Init> {
 
 impl App<Init> {
     /// Creates a new app with the given configuration
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn new(config: Config) -> Self {
+        let renderer = Renderer::init_headless(Self::render_config(&config))
+            .expect("failed to initialize renderer");
+        Self::with_renderer(config, renderer)
+    }
+
+    /// Creates a new app with the given configuration
+    ///
+    /// the browser can't wait on the gpu so the app is made asynchronously on the web:
+    /// ```rust,ignore
+    /// #[wasm_bindgen(start)]
+    /// pub async fn start() {
+    ///     App::new_async(Config::default())
+    ///         .await
+    ///         .add_plugin(Core3D)
+    ///         .load_scene(scene)
+    ///         .run();
+    /// }
+    /// ```
+    #[cfg(target_arch = "wasm32")]
+    pub async fn new_async(config: Config) -> Self {
+        let renderer = Renderer::init_headless_async(Self::render_config(&config))
+            .await
+            .expect("failed to initialize renderer");
+        Self::with_renderer(config, renderer)
+    }
+
+    fn render_config(config: &Config) -> RenderConfig {
+        RenderConfig {
+            vsync: config.vsync,
+            frames_in_flight: config.frames_in_flight,
+            backend: config.backend,
+        }
+    }
+
+    fn with_renderer(config: Config, mut renderer: Renderer) -> Self {
+        if let Some(name) = config.app_name {
+            maple_engine::fs::set_app_name(name);
+        }
+
+        // a broken file falls back to the graph the plugins build themselves
+        if let Some(path) = config.render_graph {
+            match GraphDescription::load(path) {
+                Ok(description) => renderer.set_graph_description(Some(description)),
+                Err(e) => log::error!("failed to load the render graph: {e:#}"),
+            }
+        }
+
         // add core resources
         let ctx = GameContext::default();
-        let renderer_config = RenderConfig {
-            vsync: config.vsync,
-        };
-        let renderer =
-            Renderer::init_headless(renderer_config).expect("failed to initialize renderer");
+        ctx.assets.set_hot_reload(config.hot_reload);
 
         Self {
             // state: None,
             renderer,
             window: None,
+            suspended: false,
+            input_received: true,
+            focused: true,
+            last_frame: Instant::now(),
             plugins: Vec::new(),
             context: ctx,
             config,
-            #[cfg(target_arch = "wasm32")]
-            pending_renderer: None,
             _marker: PhantomData,
         }
         .add_plugin(DefaultPlugin)
@@ -91,6 +148,19 @@ impl App<Init> {
         self
     }
 
+    /// show `loading` when the app starts until the assets `scene` loads are done, then swap it for
+    /// `scene`. see [`maple_engine::loading`]
+    pub fn load_scene_with_loading_screen<L, LM, T, TM>(self, loading: L, scene: T) -> Self
+    where
+        L: IntoScene<LM> + Send + Sync + 'static,
+        T: IntoScene<TM> + Send + Sync + 'static,
+    {
+        self.context
+            .get_resource_mut::<SceneLoader>()
+            .load(loading, scene);
+        self
+    }
+
     /// Get access to the context during initialization
     pub fn context(&self) -> &GameContext {
         &self.context
@@ -121,11 +191,33 @@ impl App<Init> {
 
     /// Runs the application
     ///
-    /// This will block as long as the window is open, so call this last
+    /// This will block as long as the window is open, so call this last. on the web it returns
+    /// straight away and the browser runs a frame every time it draws the page
     pub fn run(self) {
+        self.run_event_loop(EventLoop::builder().build());
+    }
+
+    /// Runs the application on android, call it from the `android_main` of the game's library
+    ///
+    /// ```rust,ignore
+    /// #[unsafe(no_mangle)]
+    /// fn android_main(android_app: AndroidApp) {
+    ///     App::new(Config::default()).load_scene(scene()).run_android(android_app);
+    /// }
+    /// ```
+    #[cfg(target_os = "android")]
+    pub fn run_android(self, android_app: winit::platform::android::activity::AndroidApp) {
+        use winit::platform::android::EventLoopBuilderExtAndroid;
+
+        self.run_event_loop(EventLoop::builder().with_android_app(android_app).build());
+    }
+
+    fn run_event_loop(self, event_loop: Result<EventLoop<()>, EventLoopError>) {
+        // the web takes the app by value
+        #[cfg_attr(target_arch = "wasm32", allow(unused_mut))]
         let mut initialized_app = self.transition_to_running();
 
-        let event_loop = match EventLoop::new() {
+        let event_loop = match event_loop {
             Ok(event_loop) => event_loop,
             Err(e) => {
                 error!("Fatal Error: Event loop failed to initialize: {e}");
@@ -134,12 +226,35 @@ impl App<Init> {
             }
         };
 
-        event_loop.set_control_flow(ControlFlow::Poll);
+        // assets finishing on the loading threads wake an app that is waiting for a change
+        if let UpdateMode::OnDemand { .. } = initialized_app.config.update_mode {
+            let proxy = event_loop.create_proxy();
+            initialized_app.context.assets.set_waker(move || {
+                let _ = proxy.send_event(());
+            });
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            event_loop.set_control_flow(match initialized_app.config.update_mode {
+                UpdateMode::Continuous => ControlFlow::Poll,
+                UpdateMode::OnDemand { .. } => ControlFlow::Wait,
+            });
 
-        if let Err(e) = event_loop.run_app(&mut initialized_app) {
-            error!("Fatal Error: Event loop execution failed: {e}");
-            process::exit(1);
-        };
+            if let Err(e) = event_loop.run_app(&mut initialized_app) {
+                error!("Fatal Error: Event loop execution failed: {e}");
+                process::exit(1);
+            };
+        }
+
+        // redraws are requested every frame which the browser answers on its animation frames
+        #[cfg(target_arch = "wasm32")]
+        {
+            use winit::platform::web::EventLoopExtWebSys;
+
+            event_loop.set_control_flow(ControlFlow::Wait);
+            event_loop.spawn_app(initialized_app);
+        }
     }
 
     /// Transitions the app from Init to Running state
@@ -148,11 +263,13 @@ impl App<Init> {
             // state: None, // State is initialized inside of resume
             renderer: self.renderer,
             window: None,
+            suspended: false,
+            input_received: true,
+            focused: true,
+            last_frame: Instant::now(),
             plugins: self.plugins,
             context: self.context,
             config: self.config,
-            #[cfg(target_arch = "wasm32")]
-            pending_renderer: None,
             _marker: PhantomData,
         }
     }
@@ -218,7 +335,7 @@ impl App<Running> {
 
         self.plugins = plugins;
 
-        self.context().scene.sync_world_transform();
+        self.context().sync_world_transform();
     }
 
     fn update_plugins(&mut self) {
@@ -230,8 +347,11 @@ impl App<Running> {
 
         self.plugins = plugins;
 
-        // sync worlds after plugins may have changed transforms
-        self.context().scene.sync_world_transform();
+        // sync worlds after plugins, tasks and tweens may have changed transforms
+        self.context().update_tasks();
+        self.context().update_tweens();
+        self.context().sync_world_transform();
+        self.context().apply_constraints();
     }
 
     fn fixed_update_plugins(&mut self) {
@@ -243,67 +363,7 @@ impl App<Running> {
 
         self.plugins = plugins;
 
-        self.context().scene.sync_world_transform();
-    }
-
-    #[cfg(target_arch = "wasm32")]
-    fn initialize_app_state(&mut self, event_loop: &ActiveEventLoop) -> Result<(), AppError> {
-        use winit::platform::web::WindowExtWebSys;
-
-        let window = self.create_window(event_loop)?;
-        let canvas = window.canvas().expect("Failed to get canvas");
-
-        let size = window.inner_size();
-        canvas.set_width(size.width);
-        canvas.set_height(size.height);
-
-        web_sys::window()
-            .and_then(|win| win.document())
-            .and_then(|doc| doc.body())
-            .and_then(|body| body.append_child(&canvas).ok())
-            .expect("Failed to append canvas to body");
-
-        let window_clone = window.clone();
-        let vsync = self.config.vsync;
-        let dimensions = window.inner_size().into();
-
-        // Create a shared cell for the renderer
-        let renderer_cell = std::rc::Rc::new(std::cell::RefCell::new(None));
-        let renderer_cell_clone = renderer_cell.clone();
-
-        // Spawn async renderer initialization
-        wasm_bindgen_futures::spawn_local(async move {
-            let renderer_config = RenderConfig { vsync, dimensions };
-
-            match Renderer::init_async(window_clone.clone(), renderer_config).await {
-                Ok(renderer) => {
-                    log::info!("Renderer initialized successfully");
-                    *renderer_cell_clone.borrow_mut() = Some(renderer);
-                    window_clone.request_redraw();
-                }
-                Err(e) => {
-                    log::error!("Failed to initialize renderer: {}", e);
-                }
-            }
-        });
-
-        // Store window and renderer cell so we can check it later
-        self.pending_renderer = Some((window, renderer_cell));
-        Ok(())
-    }
-
-    #[cfg(target_arch = "wasm32")]
-    fn check_pending_renderer(&mut self) {
-        if let Some((window, renderer_cell)) = self.pending_renderer.take() {
-            if let Some(renderer) = renderer_cell.borrow_mut().take() {
-                // Renderer is ready, initialize state
-                self.state = Some(AppState::new(window, renderer));
-                self.initialize_plugins();
-            } else {
-                // Not ready yet, put it back
-                self.pending_renderer = Some((window, renderer_cell));
-            }
-        }
+        self.context().sync_fixed_world_transform();
     }
 
     fn draw(&mut self) {
@@ -324,6 +384,13 @@ impl App<Running> {
             attributes = attributes.with_inner_size(resolution.physical_size());
         }
 
+        // the canvas is added to the end of the page's body
+        #[cfg(target_arch = "wasm32")]
+        {
+            use winit::platform::web::WindowAttributesExtWebSys;
+            attributes = attributes.with_append(true);
+        }
+
         attributes
     }
 
@@ -341,17 +408,8 @@ impl App<Running> {
     ///
     /// called from the winit requested redraw event
     fn handle_frame(&mut self) {
-        #[cfg(target_arch = "wasm32")]
-        {
-            // Check if renderer is ready
-            self.check_pending_renderer();
-
-            // If state is still not ready, return early
-            if self.state.is_none() {
-                return;
-            }
-        }
-
+        self.input_received = false;
+        self.last_frame = Instant::now();
         self.context.begin_frame();
 
         // Run fixed update as many times as needed based on accumulated time
@@ -365,9 +423,17 @@ impl App<Running> {
 
         self.update_plugins();
 
+        self.context.flush_deferred();
+
         self.draw();
 
         self.context.end_frame();
+
+        if self.config.input_sampling == InputSampling::Late
+            && let Err(e) = self.renderer.prepare_next_frame()
+        {
+            log::error!("failed to acquire the next frame: {e}");
+        }
     }
 }
 
@@ -386,13 +452,28 @@ impl IntoDimensions for PhysicalSize<u32> {
 
 impl ApplicationHandler for App<Running> {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        match self.create_window_and_attach(event_loop) {
-            Ok(()) => {
-                // For native, initialize plugins immediately
-                // For WASM, plugins are initialized when renderer is ready
-                #[cfg(not(target_arch = "wasm32"))]
-                self.initialize_plugins();
+        // mobile platforms resume after every suspend with the same window but a new surface
+        if let Some(window) = self.window.clone() {
+            if !self.suspended {
+                return;
+            }
+            if let Err(e) = self
+                .renderer
+                .attach_surface(window.clone(), window.inner_size().dimensions())
+            {
+                log::error!("Failed to attach the window after resuming: {e}");
+                event_loop.exit();
+                return;
             }
+            self.suspended = false;
+            self.input_received = true;
+            self.context.get_resource_mut::<Frame>().skip_time();
+            self.context.emit(Resumed);
+            return;
+        }
+
+        match self.create_window_and_attach(event_loop) {
+            Ok(()) => self.initialize_plugins(),
             Err(e) => {
                 log::error!("Failed to initialize app: {e}");
                 event_loop.exit();
@@ -400,12 +481,24 @@ impl ApplicationHandler for App<Running> {
         }
     }
 
+    fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
+        if self.window.is_none() || self.suspended {
+            return;
+        }
+        log::info!("Suspended: waiting to be resumed");
+        self.suspended = true;
+        self.renderer.detach_surface();
+        self.context.emit(Suspended);
+    }
+
     fn device_event(
         &mut self,
         _event_loop: &ActiveEventLoop,
         _device_id: winit::event::DeviceId,
         event: winit::event::DeviceEvent,
     ) {
+        // raw mouse motion keeps coming while other windows are used
+        self.input_received |= self.focused;
         self.context.device_event(&event);
     }
 
@@ -415,17 +508,17 @@ impl ApplicationHandler for App<Running> {
         _window_id: WindowId,
         event: WindowEvent,
     ) {
-        #[cfg(target_arch = "wasm32")]
-        {
-            // On WASM, check if renderer is ready before processing any events
-            self.check_pending_renderer();
+        self.context.window_event(&event);
 
-            // Skip all events until state is initialized
-            if self.state.is_none() {
-                return;
+        match event {
+            WindowEvent::RedrawRequested => {}
+            WindowEvent::Focused(focused) => {
+                self.focused = focused;
+                self.input_received = true;
             }
+            // resizes, the cursor and every other event may change what is drawn
+            _ => self.input_received = true,
         }
-        self.context.window_event(&event);
 
         match event {
             WindowEvent::CloseRequested => {
@@ -436,16 +529,36 @@ impl ApplicationHandler for App<Running> {
                 log::info!("Resizing window: {size:?}");
                 self.renderer.resize(size.dimensions());
             }
-            WindowEvent::RedrawRequested => {
+            WindowEvent::RedrawRequested if !self.suspended => {
                 self.handle_frame();
             }
             _ => {}
         }
     }
 
-    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
-        if let Some(window) = &self.window {
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(window) = self.window.as_ref().filter(|_| !self.suspended) else {
+            return;
+        };
+        let UpdateMode::OnDemand { idle_interval } = self.config.update_mode else {
             window.request_redraw();
+            return;
+        };
+
+        let now = Instant::now();
+        let next = match self.input_received {
+            true => Some(now),
+            false => self.context.next_redraw(),
+        };
+        let idle = idle_interval.map(|interval| self.last_frame + interval);
+
+        match next.into_iter().chain(idle).min() {
+            Some(at) if at <= now => {
+                window.request_redraw();
+                event_loop.set_control_flow(ControlFlow::Wait);
+            }
+            Some(at) => event_loop.set_control_flow(ControlFlow::WaitUntil(at)),
+            None => event_loop.set_control_flow(ControlFlow::Wait),
         }
     }
 }
@@ -1,25 +1,95 @@
-use maple_renderer::types::render_config::VsyncMode;
+use std::time::Duration;
+
+use maple_renderer::types::render_config::{GraphicsBackend, RenderPath, VsyncMode};
 use winit::dpi::{PhysicalSize, Size};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Config {
     pub window_title: &'static str,
+    /// the folder name of [`maple_engine::fs::save_dir`] and [`maple_engine::fs::cache_dir`].
+    /// Default: the executable's name
+    pub app_name: Option<&'static str>,
+    /// load assets again when their files change, see
+    /// [`maple_engine::asset::AssetLibrary::set_hot_reload`]. Default: true in native debug builds
+    pub hot_reload: bool,
     pub resolution: Option<Resolution<u32>>,
     pub vsync: VsyncMode,
+    /// how many frames can be queued for presentation. lower values reduce input latency
+    pub frames_in_flight: u32,
+    /// the graphics api to render with, `None` picks the best one the platform has
+    pub backend: Option<GraphicsBackend>,
+    pub input_sampling: InputSampling,
+    pub update_mode: UpdateMode,
     pub window_mode: WindowMode,
     pub resizeable: bool,
     pub decorated: bool,
+    /// a json [`maple_renderer::render_graph::description`] file plugins build the render graph
+    /// from instead of their own. Default: `None`
+    pub render_graph: Option<&'static str>,
+    /// forward or deferred lighting for the graph plugins build when `render_graph` is `None`.
+    /// Default: [`RenderPath::Forward`]
+    pub render_path: RenderPath,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             window_title: "Maple Window",
+            app_name: None,
+            hot_reload: cfg!(all(debug_assertions, not(target_arch = "wasm32"))),
             resolution: None,
             vsync: VsyncMode::default(),
+            frames_in_flight: 2,
+            backend: None,
+            input_sampling: InputSampling::default(),
+            update_mode: UpdateMode::default(),
             window_mode: WindowMode::default(),
             resizeable: true,
             decorated: true,
+            render_graph: None,
+            render_path: RenderPath::default(),
+        }
+    }
+}
+
+/// when the input of a frame is collected relative to waiting on the display
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputSampling {
+    /// wait for the next swapchain image when the frame is drawn
+    #[default]
+    FrameStart,
+    /// wait for the next swapchain image at the end of the previous frame so input that
+    /// arrives during the wait is handled before the update instead of a frame later.
+    ///
+    /// this reduces input latency with vsync on but the frame is timed after the wait
+    Late,
+}
+
+/// when the app updates and draws a frame
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateMode {
+    /// draw frames one after another as fast as vsync allows
+    #[default]
+    Continuous,
+    /// only draw a frame when something changed, for tools and other apps that sit still most
+    /// of the time and shouldn't drain the battery while they do
+    ///
+    /// input, nodes moving, spawning or being hidden, tweens, animations and assets that finish
+    /// loading draw frames until the scene stops changing. anything else can ask for a frame with
+    /// [`maple_engine::context::GameContext::request_redraw`]. the delta of the frame after a
+    /// wait is the time waited so timers stay on time
+    OnDemand {
+        /// the longest the app waits between frames when nothing changed, `None` waits for the
+        /// next change
+        idle_interval: Option<Duration>,
+    },
+}
+
+impl UpdateMode {
+    /// draw on demand and at least once a second
+    pub fn on_demand() -> Self {
+        Self::OnDemand {
+            idle_interval: Some(Duration::from_secs(1)),
         }
     }
 }
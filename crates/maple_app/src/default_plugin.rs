@@ -1,6 +1,17 @@
 use maple_engine::{
+    loading::SceneLoader,
     prelude::{FixedUpdate, Frame, Update},
-    resources::Input,
+    resources::{Input, Settings, TouchInput},
+    transition::SceneTransitions,
+};
+
+use maple_renderer::{
+    core::texture::Texture,
+    render_graph::{
+        memory::{GpuMemoryStats, MemoryKind},
+        stats::RenderStats,
+    },
+    types::quality::{Quality, QualityPreset},
 };
 
 use crate::Plugin;
@@ -9,6 +20,16 @@ pub struct DefaultPlugin;
 
 impl Plugin for DefaultPlugin {
     fn setup(&self, app: &mut crate::App<crate::Init>) {
+        // logs and panics go to the browser's console
+        #[cfg(target_arch = "wasm32")]
+        {
+            console_error_panic_hook::set_once();
+            if let Err(e) = console_log::init_with_level(log::Level::Info) {
+                log::info!("Ignoring Logger: {e}");
+            }
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
         match env_logger::Builder::from_env(
             env_logger::Env::default()
                 .default_filter_or("info,wgpu_hal=warn,naga=warn,calloop=error"),
@@ -21,25 +42,104 @@ impl Plugin for DefaultPlugin {
 
         let device = app.renderer().context.device().clone();
         let queue = app.renderer().context.queue().clone();
+        let mipmap_generator = app.renderer().context.mipmap_generator().clone();
         app.context_mut().assets.register_loader(
-            maple_renderer::texture_asset::TextureAssetLoader::new(device, queue),
+            maple_renderer::texture_asset::TextureAssetLoader::new(device, queue, mipmap_generator),
         );
+
+        // missing textures show up as the magenta checkerboard
+        let error_texture = app.renderer().context.get_default_texture().error.clone();
+        if let Err(err) = app.context().assets.set_fallback(error_texture) {
+            log::warn!("failed to set the placeholder texture: {err}");
+        }
+
+        // the preset is picked from the gpu once and the user's choice is kept after that. it's
+        // applied before plugins build the render graph so the msaa samples are used
+        let mut settings = Settings::load();
+        let preset = settings
+            .get::<QualityPreset>(QualityPreset::SETTINGS_KEY)
+            .unwrap_or_else(|| {
+                let adapter = app.renderer().context.adapter_info();
+                let preset = QualityPreset::detect(&adapter);
+                log::info!("picked {} quality for {}", preset.name(), adapter.name);
+                if let Err(e) = settings.set(QualityPreset::SETTINGS_KEY, preset) {
+                    log::warn!("failed to save the quality preset: {e}");
+                }
+                preset
+            });
+        let quality = Quality::new(preset);
+        app.renderer_mut().set_quality(*quality.settings());
+
+        // plugins with gpu assets of their own track them in setup
+        let mut memory = GpuMemoryStats::default();
+        memory.track::<Texture>(MemoryKind::texture);
+        memory.set_texture_budget(Some(quality.settings().texture_budget));
+        app.context_mut().insert_resource(memory);
+        app.context_mut().insert_resource(quality);
+        app.context_mut().insert_resource(settings);
+        app.context_mut().insert_resource(SceneLoader::default());
+        app.context_mut()
+            .insert_resource(SceneTransitions::default());
     }
 
     fn ready(&self, app: &mut crate::App<crate::Running>) {
         let window = app.window().clone();
         app.context_mut().insert_resource(Frame::default());
         app.context_mut().insert_resource(Input::new(window));
+        app.context_mut().insert_resource(RenderStats::default());
 
         // sync world positions before ready (since they are synced after between update and
         // render normally)
-        app.context().scene.sync_world_transform();
+        app.context().sync_world_transform();
     }
 
     fn update(&self, app: &mut crate::App<crate::Running>) {
-        let dt = app.context().get_resource::<Frame>().time_delta_f32;
+        let (dt, unscaled_dt) = {
+            let frame = app.context().get_resource::<Frame>();
+            (frame.time_delta_f32, frame.unscaled_time_delta_f32)
+        };
+        // before the ready queue so a scene swapped in by the loader is ready this frame
+        SceneLoader::update(app.context());
+        // transitions keep going while the game is paused
+        SceneTransitions::update(app.context(), unscaled_dt);
         app.context().pop_ready_queue();
-        app.context().emit(Update { dt });
+        let changed = app.context().get_resource_mut::<Quality>().take_changed();
+        if let Some((preset, quality)) = changed {
+            app.renderer_mut().set_quality(quality);
+            app.context()
+                .get_resource_mut::<GpuMemoryStats>()
+                .set_texture_budget(Some(quality.texture_budget));
+            if let Err(e) = app
+                .context()
+                .get_resource_mut::<Settings>()
+                .set(QualityPreset::SETTINGS_KEY, preset)
+            {
+                log::warn!("failed to save the quality preset: {e}");
+            }
+        }
+        app.context().assets.collect_unused(unscaled_dt);
+        app.context()
+            .get_resource_mut::<GpuMemoryStats>()
+            .refresh(&app.context().assets, unscaled_dt);
+        for error in app.context().assets.take_errors() {
+            app.context().emit(error);
+        }
+        for loaded in app.context().assets.take_loaded() {
+            app.context().emit(loaded);
+        }
+        // copied out so handlers can read the input while they run
+        let (touches, gestures) = {
+            let input = app.context().get_resource::<Input>();
+            let touches = input.touches.changes().to_vec();
+            (touches, input.touches.gestures().to_vec())
+        };
+        for touch in touches {
+            app.context().emit(TouchInput(touch));
+        }
+        for gesture in gestures {
+            app.context().emit(gesture);
+        }
+        app.context().emit(Update { dt, unscaled_dt });
     }
 
     fn fixed_update(&self, app: &mut crate::App<crate::Running>) {
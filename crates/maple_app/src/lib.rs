@@ -11,6 +11,9 @@ pub mod plugin;
 pub use app::*;
 pub use plugin::Plugin;
 
+/// the version of this crate
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
 pub mod prelude {
     pub use crate::app::{Init, Running};
     pub use crate::config::*;
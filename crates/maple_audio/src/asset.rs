@@ -1,10 +1,11 @@
-use std::{path::Path, sync::Arc};
+use std::{io::Cursor, path::Path, sync::Arc};
 
 use kira::sound::static_sound::StaticSoundData;
 use maple_engine::asset::{Asset, AssetLoader, FileLoader, IntoAsset, LoadErr};
 
 pub(crate) enum AudioData {
     Static(StaticSoundData),
+    #[cfg(not(target_arch = "wasm32"))]
     Streaming(Arc<Path>),
 }
 
@@ -12,7 +13,7 @@ pub(crate) enum AudioData {
 ///
 /// Static audio sources can be loaded with `assets.load("path/to/audio")`
 /// Streaming audio sources can be added with `assets.add(StreamedAudio::new("path/to/audio"))`
-/// see: [`StreamedAudio`]
+/// see: [`StreamedAudio`], streaming isn't supported on the web
 pub struct Audio {
     pub(crate) data: AudioData,
 }
@@ -34,9 +35,27 @@ impl FileLoader for AudioLoader {
         path: &std::path::Path,
         _library: &maple_engine::prelude::AssetLibrary,
     ) -> Result<Self::Asset, maple_engine::asset::LoadErr> {
+        // kira can't open files on the web, the library fetches them into `load_bytes` there
+        #[cfg(target_arch = "wasm32")]
+        let data = Err(format!("{} can't be read on the web", path.display()));
+        #[cfg(not(target_arch = "wasm32"))]
+        let data = StaticSoundData::from_file(path).map_err(|err| err.to_string());
+
+        Ok(Audio {
+            data: AudioData::Static(data.map_err(LoadErr::Import)?),
+        })
+    }
+
+    fn load_bytes(
+        &self,
+        _path: &Path,
+        bytes: Vec<u8>,
+        _library: &maple_engine::prelude::AssetLibrary,
+    ) -> Result<Self::Asset, LoadErr> {
         Ok(Audio {
             data: AudioData::Static(
-                StaticSoundData::from_file(path).map_err(|err| LoadErr::Import(err.to_string()))?,
+                StaticSoundData::from_cursor(Cursor::new(bytes))
+                    .map_err(|err| LoadErr::Import(err.to_string()))?,
             ),
         })
     }
@@ -57,11 +76,23 @@ impl IntoAsset<Audio> for StreamedAudio {
         _loader: &<Audio as Asset>::Loader,
         _library: &maple_engine::prelude::AssetLibrary,
     ) -> Result<Audio, LoadErr> {
-        if !self.0.exists() {
-            return Err(LoadErr::Missing);
+        // kira streams from the file system which the browser doesn't have
+        #[cfg(target_arch = "wasm32")]
+        {
+            Err(LoadErr::Import(format!(
+                "{} can't be streamed on the web, load it with `assets.load` instead",
+                self.0.display()
+            )))
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if !self.0.exists() {
+                return Err(LoadErr::Missing);
+            }
+            Ok(Audio {
+                data: AudioData::Streaming(self.0),
+            })
         }
-        Ok(Audio {
-            data: AudioData::Streaming(self.0),
-        })
     }
 }
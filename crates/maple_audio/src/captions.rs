@@ -0,0 +1,352 @@
+//! subtitles and captions that follow the playback of a sound
+//!
+//! [`Captions`] are loaded from `.srt` or `.vtt` files and attached to a playing sound with
+//! [`crate::resource::AudioManager::track_captions`]. while the sound plays the
+//! [`CaptionShown`] and [`CaptionHidden`] events are emitted to the scene and the
+//! [`ActiveCaptions`] resource holds the captions that should currently be on screen.
+//!
+//! with the `egui` feature enabled [`caption_display`] draws the active captions.
+//!
+//! ```rust, ignore
+//! let voice = ctx.assets.load::<Audio>("res/audio/intro.ogg");
+//! let captions = ctx.assets.load::<Captions>("res/audio/intro.srt");
+//!
+//! let mut audio = ctx.get_resource_mut::<AudioManager>();
+//! let sound = audio.play(voice, SoundSettings::default());
+//! audio.track_captions(&sound, captions);
+//! ```
+
+use maple_engine::{
+    asset::{Asset, AssetHandle, AssetLoader, FileLoader, LoadErr},
+    color::Color,
+    prelude::{AssetLibrary, EventLabel, Resource},
+};
+
+use crate::sound::SoundHandle;
+
+/// a single line of text shown between two points in a sound
+#[derive(Clone, Debug, PartialEq)]
+pub struct Caption {
+    /// time in seconds from the start of the sound the caption appears
+    pub start: f64,
+    /// time in seconds from the start of the sound the caption disappears
+    pub end: f64,
+    /// who is speaking if the caption was written as `<v Speaker>text`, or as `Speaker: text`
+    /// when parsed with [`Captions::parse_with_speaker_prefixes`]
+    pub speaker: Option<String>,
+    /// the caption text. may contain multiple lines
+    pub text: String,
+}
+
+/// [`Asset`] containing a timed list of captions
+pub struct Captions {
+    pub captions: Vec<Caption>,
+}
+
+impl Asset for Captions {
+    type Loader = CaptionLoader;
+}
+
+impl Captions {
+    /// parse captions in the SubRip (`.srt`) or WebVTT (`.vtt`) format
+    ///
+    /// only WebVTT voice tags (`<v Speaker>text`) set the speaker, a line like `Note: run` is
+    /// kept as text
+    pub fn parse(source: &str) -> Result<Self, LoadErr> {
+        Self::parse_captions(source, false)
+    }
+
+    /// like [`Self::parse`] but a short name before `: ` at the start of a caption is also taken
+    /// as the speaker, for SubRip files that write `Guard: Who goes there?`
+    pub fn parse_with_speaker_prefixes(source: &str) -> Result<Self, LoadErr> {
+        Self::parse_captions(source, true)
+    }
+
+    fn parse_captions(source: &str, speaker_prefixes: bool) -> Result<Self, LoadErr> {
+        let mut captions = Vec::new();
+
+        for block in source.replace("\r\n", "\n").split("\n\n") {
+            let mut lines = block.lines().map(str::trim).filter(|line| !line.is_empty());
+
+            // skip the cue index or header until we find the timing line
+            let timing = loop {
+                match lines.next() {
+                    Some(line) if line.contains("-->") => break Some(line),
+                    Some(_) => continue,
+                    None => break None,
+                }
+            };
+            let Some(timing) = timing else {
+                continue;
+            };
+
+            let (start, end) = timing
+                .split_once("-->")
+                .ok_or_else(|| LoadErr::Import(format!("invalid caption timing: {timing}")))?;
+            let start = parse_timestamp(start)?;
+            // vtt allows cue settings after the end time
+            let end = parse_timestamp(end.split_whitespace().next().unwrap_or_default())?;
+
+            let mut text = lines.collect::<Vec<_>>().join("\n");
+            let mut speaker = None;
+
+            if let Some(rest) = text.strip_prefix("<v ")
+                && let Some((name, line)) = rest.split_once('>')
+            {
+                speaker = Some(name.trim().to_string());
+                text = line.trim_start().to_string();
+            } else if speaker_prefixes
+                && let Some((name, line)) = text.split_once(": ")
+                && !name.contains('\n')
+                && name.split_whitespace().count() <= 3
+            {
+                speaker = Some(name.to_string());
+                text = line.to_string();
+            }
+
+            captions.push(Caption {
+                start,
+                end,
+                speaker,
+                text,
+            });
+        }
+
+        captions.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+        Ok(Self { captions })
+    }
+
+    /// captions that should be visible at `time` seconds into the sound
+    pub fn at(&self, time: f64) -> impl Iterator<Item = (usize, &Caption)> {
+        self.captions
+            .iter()
+            .enumerate()
+            .filter(move |(_, caption)| caption.start <= time && time < caption.end)
+    }
+}
+
+/// parses `hh:mm:ss,mmm`, `mm:ss.mmm` or plain seconds
+fn parse_timestamp(timestamp: &str) -> Result<f64, LoadErr> {
+    let invalid = || LoadErr::Import(format!("invalid caption timestamp: {timestamp}"));
+
+    let mut seconds = 0.0;
+    for part in timestamp.trim().split(':') {
+        let value: f64 = part.replace(',', ".").parse().map_err(|_| invalid())?;
+        seconds = seconds * 60.0 + value;
+    }
+
+    Ok(seconds)
+}
+
+/// loader for [`Captions`] files
+///
+/// the audio plugin registers the default loader, register one with `speaker_prefixes` set to
+/// read speakers from `Speaker: text` lines as well
+///
+/// ```rust, ignore
+/// ctx.assets.register_loader(CaptionLoader { speaker_prefixes: true });
+/// ```
+#[derive(Default)]
+pub struct CaptionLoader {
+    /// see [`Captions::parse_with_speaker_prefixes`]
+    pub speaker_prefixes: bool,
+}
+
+impl CaptionLoader {
+    fn parse(&self, source: &str) -> Result<Captions, LoadErr> {
+        Captions::parse_captions(source, self.speaker_prefixes)
+    }
+}
+
+impl AssetLoader for CaptionLoader {
+    type Asset = Captions;
+}
+
+impl FileLoader for CaptionLoader {
+    fn load_path(
+        &self,
+        path: &std::path::Path,
+        _library: &AssetLibrary,
+    ) -> Result<Self::Asset, LoadErr> {
+        let source =
+            std::fs::read_to_string(path).map_err(|err| LoadErr::Import(err.to_string()))?;
+        self.parse(&source)
+    }
+
+    fn load_bytes(
+        &self,
+        _path: &std::path::Path,
+        bytes: Vec<u8>,
+        _library: &AssetLibrary,
+    ) -> Result<Self::Asset, LoadErr> {
+        let source = String::from_utf8(bytes).map_err(|err| LoadErr::Import(err.to_string()))?;
+        self.parse(&source)
+    }
+}
+
+/// event emitted to the scene when a caption should appear
+pub struct CaptionShown {
+    pub caption: Caption,
+}
+impl EventLabel for CaptionShown {}
+
+/// event emitted to the scene when a caption should disappear
+pub struct CaptionHidden {
+    pub caption: Caption,
+}
+impl EventLabel for CaptionHidden {}
+
+/// resource with the captions that are currently on screen in the order they appeared
+#[derive(Default)]
+pub struct ActiveCaptions {
+    pub captions: Vec<Caption>,
+}
+
+impl Resource for ActiveCaptions {}
+
+/// a sound that is being followed by a caption track
+pub(crate) struct CaptionTracker {
+    pub(crate) sound: SoundHandle,
+    pub(crate) captions: AssetHandle<Captions>,
+    /// the captions currently shown from this track, kept by value so they can still be hidden
+    /// after the captions are reloaded
+    pub(crate) shown: Vec<Caption>,
+}
+
+/// how captions are drawn by [`caption_display`]
+#[derive(Clone, Debug)]
+pub struct CaptionStyle {
+    /// font size in points
+    pub font_size: f32,
+    pub text_color: Color,
+    /// color used for the speaker name
+    pub speaker_color: Color,
+    pub background: Color,
+    /// distance from the bottom of the screen in points
+    pub bottom_margin: f32,
+    /// maximum width of a caption box as a fraction of the screen width
+    pub max_width: f32,
+    pub show_speaker: bool,
+}
+
+impl Default for CaptionStyle {
+    fn default() -> Self {
+        Self {
+            font_size: 22.0,
+            text_color: Color::WHITE,
+            speaker_color: Color::from_normalized(1.0, 0.85, 0.3, 1.0),
+            background: Color::from_normalized(0.0, 0.0, 0.0, 0.65),
+            bottom_margin: 48.0,
+            max_width: 0.7,
+            show_speaker: true,
+        }
+    }
+}
+
+#[cfg(feature = "egui")]
+pub use ui::caption_display;
+
+#[cfg(feature = "egui")]
+mod ui {
+    use maple_egui::{
+        egui,
+        plugin::{EguiUpdate, FromColor},
+    };
+    use maple_engine::{Node, prelude::EventCtx};
+
+    use super::{ActiveCaptions, CaptionStyle};
+
+    /// event handler that draws the [`ActiveCaptions`] at the bottom of the screen
+    ///
+    /// attach it to any node:
+    /// ```rust, ignore
+    /// scene
+    ///     .spawn(Empty::default())
+    ///     .on::<EguiUpdate>(caption_display(CaptionStyle::default()));
+    /// ```
+    pub fn caption_display<N: Node>(
+        style: CaptionStyle,
+    ) -> impl FnMut(EventCtx<EguiUpdate, N>) + Send + Sync + 'static {
+        move |ctx| {
+            let active = ctx.get_resource::<ActiveCaptions>();
+            if active.captions.is_empty() {
+                return;
+            }
+
+            let screen = ctx.event.content_rect();
+            let background = egui::Color32::from_color(style.background);
+            let text_color = egui::Color32::from_color(style.text_color);
+            let speaker_color = egui::Color32::from_color(style.speaker_color);
+
+            egui::Area::new(egui::Id::new("maple_audio_captions"))
+                .anchor(
+                    egui::Align2::CENTER_BOTTOM,
+                    egui::vec2(0.0, -style.bottom_margin),
+                )
+                .interactable(false)
+                .show(ctx.event, |ui| {
+                    ui.set_max_width(screen.width() * style.max_width);
+                    for caption in &active.captions {
+                        egui::Frame::new()
+                            .fill(background)
+                            .corner_radius(4.0)
+                            .inner_margin(egui::Margin::symmetric(12, 6))
+                            .show(ui, |ui| {
+                                let mut job = egui::text::LayoutJob::default();
+                                let font = egui::FontId::proportional(style.font_size);
+                                if style.show_speaker
+                                    && let Some(speaker) = &caption.speaker
+                                {
+                                    job.append(
+                                        &format!("{speaker}: "),
+                                        0.0,
+                                        egui::TextFormat::simple(font.clone(), speaker_color),
+                                    );
+                                }
+                                job.append(
+                                    &caption.text,
+                                    0.0,
+                                    egui::TextFormat::simple(font, text_color),
+                                );
+                                ui.label(job);
+                            });
+                    }
+                });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_srt() {
+        let captions = Captions::parse_with_speaker_prefixes(
+            "1\n00:00:01,000 --> 00:00:02,500\nGuard: Who goes there?\n\n2\n00:00:03,000 --> 00:00:04,000\n(footsteps)\n",
+        )
+        .unwrap();
+
+        assert_eq!(captions.captions.len(), 2);
+        assert_eq!(captions.captions[0].start, 1.0);
+        assert_eq!(captions.captions[0].end, 2.5);
+        assert_eq!(captions.captions[0].speaker.as_deref(), Some("Guard"));
+        assert_eq!(captions.captions[0].text, "Who goes there?");
+        assert_eq!(captions.captions[1].speaker, None);
+        assert_eq!(captions.at(3.5).count(), 1);
+    }
+
+    #[test]
+    fn test_only_voice_tags_are_speakers_by_default() {
+        let captions = Captions::parse(
+            "WEBVTT\n\n00:01.000 --> 00:02.000\nNote: run\n\n00:02.000 --> 00:03.000\n<v Guard>Halt!\n",
+        )
+        .unwrap();
+
+        assert_eq!(captions.captions[0].speaker, None);
+        assert_eq!(captions.captions[0].text, "Note: run");
+        assert_eq!(captions.captions[1].speaker.as_deref(), Some("Guard"));
+        assert_eq!(captions.captions[1].text, "Halt!");
+    }
+}
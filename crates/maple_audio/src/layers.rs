@@ -0,0 +1,68 @@
+use std::ops::{BitAnd, BitOr, Not};
+
+/// groups of [`crate::nodes::AudioSource`]s as bits, a source can be on several
+///
+/// the [`crate::nodes::AudioListener`] picks the layers it hears and the layers that play in the
+/// world. sources on the others, such as [`AudioLayers::UI`], are heard the same wherever they
+/// are without being panned, faded with distance, occluded or changed by
+/// [`crate::nodes::ReverbZone`]s
+///
+/// ```rust, ignore
+/// const FOOTSTEPS: AudioLayers = AudioLayers::layer(2);
+///
+/// // a listener that only hears the ui, for example while a menu is open
+/// listener.layers = AudioLayers::UI;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AudioLayers(pub u32);
+
+impl AudioLayers {
+    pub const NONE: Self = Self(0);
+    pub const ALL: Self = Self(u32::MAX);
+    /// sounds placed in the world, the layer of new sources
+    pub const WORLD: Self = Self(1);
+    /// interface sounds that don't come from anywhere
+    pub const UI: Self = Self(1 << 1);
+
+    /// the layer of bit `index`, bits 0 and 1 are [`AudioLayers::WORLD`] and [`AudioLayers::UI`]
+    pub const fn layer(index: u32) -> Self {
+        Self(1 << index)
+    }
+
+    /// true if every layer of `other` is in these
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// true if any layer is in both
+    pub const fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl Default for AudioLayers {
+    fn default() -> Self {
+        Self::WORLD
+    }
+}
+
+impl BitOr for AudioLayers {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitAnd for AudioLayers {
+    type Output = Self;
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl Not for AudioLayers {
+    type Output = Self;
+    fn not(self) -> Self {
+        Self(!self.0)
+    }
+}
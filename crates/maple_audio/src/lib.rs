@@ -4,19 +4,39 @@
 //! [`asset::Audio`] assets.
 
 pub mod asset;
+pub mod captions;
+pub mod layers;
 pub mod nodes;
 pub mod plugin;
 pub mod resource;
 pub mod settings;
 pub mod sound;
+pub mod trigger;
+
+/// the version of this crate
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// the cargo features this crate was built with
+pub const FEATURES: &[&str] = &[
+    #[cfg(feature = "physics")]
+    "physics",
+    #[cfg(feature = "egui")]
+    "egui",
+];
 
 pub mod prelude {
     pub use crate::asset::Audio;
+    pub use crate::captions::{
+        ActiveCaptions, CaptionHidden, CaptionShown, CaptionStyle, Captions,
+    };
 
+    pub use crate::layers::AudioLayers;
     pub use crate::plugin::AudioPlugin;
 
     pub use crate::nodes::AudioListener;
     pub use crate::nodes::AudioSource;
+    pub use crate::nodes::{AudioEnvironment, ReverbZone};
     pub use crate::settings::*;
     pub use crate::sound::SoundHandle;
+    pub use crate::trigger::{SoundTrigger, SoundTriggerExt};
 }
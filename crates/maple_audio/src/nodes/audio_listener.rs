@@ -1,9 +1,32 @@
 use maple_engine::{Node, prelude::NodeTransform};
 
-#[derive(Default, Clone)]
+use crate::layers::AudioLayers;
+
+#[derive(Clone)]
 pub struct AudioListener {
     pub transform: NodeTransform,
     pub priority: i32,
+    /// the layers of the sources the listener hears, the others are silent
+    ///
+    /// Default: [`AudioLayers::ALL`]
+    pub layers: AudioLayers,
+    /// the layers of the sources that play in the world, panned and faded with distance, occluded
+    /// and changed by [`crate::nodes::ReverbZone`]s. the others play as if they were at the
+    /// listener
+    ///
+    /// Default: [`AudioLayers::WORLD`]
+    pub spatial_layers: AudioLayers,
+}
+
+impl Default for AudioListener {
+    fn default() -> Self {
+        Self {
+            transform: NodeTransform::default(),
+            priority: 0,
+            layers: AudioLayers::ALL,
+            spatial_layers: AudioLayers::WORLD,
+        }
+    }
 }
 
 impl Node for AudioListener {
@@ -1,9 +1,14 @@
 use std::collections::VecDeque;
 
-use kira::{Decibels, StartTime, Tween, Value, track::SpatialTrackHandle};
+use glam::Vec3;
+use kira::{
+    Decibels, StartTime, Tween, Value,
+    effect::{filter::FilterHandle, volume_control::VolumeControlHandle},
+    track::SpatialTrackHandle,
+};
 use maple_engine::{Node, asset::AssetHandle, prelude::NodeTransform};
 
-use crate::{asset::Audio, settings::SoundSettings, sound::SoundHandle};
+use crate::{asset::Audio, layers::AudioLayers, settings::SoundSettings, sound::SoundHandle};
 
 pub enum DeferredSourceCommand {
     SetVolume {
@@ -51,11 +56,68 @@ impl SourceHandle {
     }
 }
 
-#[derive(Default)]
+/// effects on the spatial track used to muffle a source when it is occluded, silence it when
+/// the listener doesn't hear its layers and apply reverb zones
+pub(crate) struct OcclusionEffects {
+    pub(crate) filter: FilterHandle,
+    pub(crate) volume: VolumeControlHandle,
+    pub(crate) occluded: bool,
+    pub(crate) muted: bool,
+    pub(crate) zone_filter: FilterHandle,
+    /// the reverb send and low-pass cutoff last set from the reverb zones
+    pub(crate) zone: (f32, f64),
+    /// false while the source plays at the listener
+    pub(crate) spatial: bool,
+}
+
 pub struct AudioSource {
     pub transform: NodeTransform,
+
+    /// the layers the source is on, see [`AudioLayers`]
+    ///
+    /// Default: [`AudioLayers::WORLD`]
+    pub layers: AudioLayers,
+
+    /// how strongly the velocity of the source and listener shift the pitch of playing sounds.
+    ///
+    /// 0.0 disables the doppler effect and 1.0 is physically accurate
+    pub doppler_factor: f32,
+
+    /// muffle the source when a collider is between it and the listener.
+    ///
+    /// requires the `physics` feature and the physics plugin
+    pub occlusion: bool,
+    /// low-pass cutoff frequency in hertz used while occluded
+    pub occlusion_cutoff: f64,
+    /// volume change applied while occluded
+    pub occlusion_attenuation: Decibels,
+
     pub(crate) handle: SourceHandle,
+    pub(crate) effects: Option<OcclusionEffects>,
     pub(crate) queue: VecDeque<(AssetHandle<Audio>, SoundSettings, SoundHandle)>,
+    /// sounds currently playing on this source with their base playback rate
+    pub(crate) playing: Vec<(SoundHandle, f64)>,
+    pub(crate) last_position: Option<Vec3>,
+    pub(crate) velocity: Vec3,
+}
+
+impl Default for AudioSource {
+    fn default() -> Self {
+        Self {
+            transform: NodeTransform::default(),
+            layers: AudioLayers::WORLD,
+            doppler_factor: 1.0,
+            occlusion: false,
+            occlusion_cutoff: 800.0,
+            occlusion_attenuation: Decibels(-9.0),
+            handle: SourceHandle::default(),
+            effects: None,
+            queue: VecDeque::default(),
+            playing: Vec::new(),
+            last_position: None,
+            velocity: Vec3::ZERO,
+        }
+    }
 }
 
 impl Node for AudioSource {
@@ -65,6 +127,18 @@ impl Node for AudioSource {
 }
 
 impl AudioSource {
+    /// velocity of the source in world space tracked from the change in its position each frame
+    pub fn velocity(&self) -> Vec3 {
+        self.velocity
+    }
+
+    /// whether the source was occluded from the listener last frame
+    pub fn is_occluded(&self) -> bool {
+        self.effects
+            .as_ref()
+            .is_some_and(|effects| effects.occluded)
+    }
+
     pub fn play(&mut self, source: AssetHandle<Audio>, settings: SoundSettings) -> SoundHandle {
         let handle = SoundHandle::default();
         self.queue.push_back((source, settings, handle.clone()));
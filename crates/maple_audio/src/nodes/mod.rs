@@ -1,5 +1,7 @@
 mod audio_listener;
 mod audio_source;
+mod reverb_zone;
 
 pub use audio_listener::*;
 pub use audio_source::*;
+pub use reverb_zone::*;
@@ -0,0 +1,203 @@
+use glam::{Mat4, Vec3};
+use maple_engine::{Node, prelude::NodeTransform};
+
+/// cutoff of the low-pass filters while they are open (above human hearing)
+pub(crate) const OPEN_CUTOFF: f64 = 20_000.0;
+
+/// a box that changes how the world sounds while the listener is in it, such as the echo of a
+/// cave or the muffled sound of being underwater
+///
+/// the effects fade in over [`ReverbZone::blend_distance`] around the box as the listener gets
+/// closer. zones inside other zones are given a higher [`ReverbZone::priority`] and are blended
+/// over the ones around them. only sources on the listener's
+/// [`spatial_layers`](crate::nodes::AudioListener::spatial_layers) are changed
+///
+/// ```rust, ignore
+/// ctx.scene.spawn(ReverbZone {
+///     transform: NodeTransform::new(Vec3::new(0.0, 5.0, -40.0), Quat::IDENTITY, Vec3::ONE),
+///     extents: Vec3::new(20.0, 5.0, 20.0),
+///     reverb: 0.6,
+///     room_size: 0.95,
+///     ..Default::default()
+/// });
+/// ```
+#[derive(Clone)]
+pub struct ReverbZone {
+    pub transform: NodeTransform,
+    /// half the size of the box in local space
+    ///
+    /// Default: `Vec3::splat(5.0)`
+    pub extents: Vec3,
+    /// how far around the box in world units the effects fade out
+    ///
+    /// Default: `2.0`
+    pub blend_distance: f32,
+    /// zones with a higher priority are blended over the others
+    ///
+    /// Default: `0`
+    pub priority: i32,
+    /// how much of the sound goes to the reverb from `0.0` to `1.0`
+    ///
+    /// Default: `0.5`
+    pub reverb: f32,
+    /// how long the reverb rings from `0.0` to `1.0`, bigger rooms ring longer
+    ///
+    /// Default: `0.8`
+    pub room_size: f64,
+    /// how quickly the high frequencies of the reverb fade from `0.0` to `1.0`
+    ///
+    /// Default: `0.5`
+    pub damping: f64,
+    /// low-pass cutoff in hertz applied to the sources, the default lets everything through
+    ///
+    /// Default: `20_000.0`
+    pub low_pass_cutoff: f64,
+}
+
+impl Default for ReverbZone {
+    fn default() -> Self {
+        Self {
+            transform: NodeTransform::default(),
+            extents: Vec3::splat(5.0),
+            blend_distance: 2.0,
+            priority: 0,
+            reverb: 0.5,
+            room_size: 0.8,
+            damping: 0.5,
+            low_pass_cutoff: OPEN_CUTOFF,
+        }
+    }
+}
+
+impl Node for ReverbZone {
+    fn get_transform(&mut self) -> &mut NodeTransform {
+        &mut self.transform
+    }
+}
+
+impl ReverbZone {
+    /// how much the zone applies at `point` in world space, `1.0` inside the box and fading to
+    /// `0.0` at [`ReverbZone::blend_distance`] away from it
+    pub fn weight_at(&self, point: Vec3) -> f32 {
+        zone_weight(
+            self.transform.world_space().matrix(),
+            self.extents,
+            self.blend_distance,
+            point,
+        )
+    }
+
+    fn environment(&self) -> AudioEnvironment {
+        AudioEnvironment {
+            reverb: self.reverb.clamp(0.0, 1.0),
+            room_size: self.room_size,
+            damping: self.damping,
+            low_pass_cutoff: self.low_pass_cutoff,
+        }
+    }
+}
+
+fn zone_weight(model: &Mat4, extents: Vec3, blend_distance: f32, point: Vec3) -> f32 {
+    let local = model.inverse().transform_point3(point);
+    let closest = local.clamp(-extents, extents);
+    // measured in the world so scaled zones fade over the same distance
+    let distance = model
+        .transform_point3(closest)
+        .distance(model.transform_point3(local));
+    if distance <= 0.0 {
+        return 1.0;
+    }
+    (1.0 - distance / blend_distance.max(f32::EPSILON)).max(0.0)
+}
+
+/// the zone effects applied where the listener is, see [`ReverbZone`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioEnvironment {
+    /// how much of the sound goes to the reverb from `0.0` to `1.0`
+    pub reverb: f32,
+    pub room_size: f64,
+    pub damping: f64,
+    /// low-pass cutoff in hertz of the sources
+    pub low_pass_cutoff: f64,
+}
+
+impl Default for AudioEnvironment {
+    /// outside of every zone
+    fn default() -> Self {
+        Self {
+            reverb: 0.0,
+            room_size: 0.8,
+            damping: 0.5,
+            low_pass_cutoff: OPEN_CUTOFF,
+        }
+    }
+}
+
+impl AudioEnvironment {
+    /// blend from `self` at `0.0` to `other` at `1.0`
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        let t64 = t as f64;
+        Self {
+            reverb: self.reverb + (other.reverb - self.reverb) * t,
+            room_size: self.room_size + (other.room_size - self.room_size) * t64,
+            damping: self.damping + (other.damping - self.damping) * t64,
+            // blended in octaves so the filter closes evenly
+            low_pass_cutoff: self.low_pass_cutoff.powf(1.0 - t64) * other.low_pass_cutoff.powf(t64),
+        }
+    }
+
+    /// the zones blended at `listener` from the lowest priority up
+    pub fn at(listener: Vec3, zones: &[ReverbZone]) -> Self {
+        let mut zones: Vec<&ReverbZone> = zones.iter().collect();
+        zones.sort_by_key(|zone| zone.priority);
+
+        zones
+            .into_iter()
+            .fold(Self::default(), |environment, zone| {
+                let weight = zone.weight_at(listener);
+                match weight > 0.0 {
+                    true => environment.lerp(&zone.environment(), weight),
+                    false => environment,
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zones_fade_in_and_nest() {
+        let scene = maple_engine::Scene::default();
+        scene.spawn(ReverbZone {
+            extents: Vec3::splat(10.0),
+            blend_distance: 4.0,
+            reverb: 1.0,
+            ..Default::default()
+        });
+        scene.spawn(ReverbZone {
+            transform: NodeTransform::new(Vec3::X * 5.0, Default::default(), Vec3::ONE),
+            extents: Vec3::ONE,
+            priority: 1,
+            reverb: 0.0,
+            low_pass_cutoff: 500.0,
+            ..Default::default()
+        });
+        scene.sync_world_transform();
+        let mut zones = Vec::new();
+        scene.for_each_ref(&mut |zone: &ReverbZone| zones.push(zone.clone()));
+
+        let outside = AudioEnvironment::at(Vec3::X * 20.0, &zones);
+        assert_eq!(outside, AudioEnvironment::default());
+
+        // halfway through the blend around the cave
+        let edge = AudioEnvironment::at(Vec3::Z * 12.0, &zones);
+        assert!((edge.reverb - 0.5).abs() < 1e-5);
+
+        // the inner zone replaces the cave
+        let inner = AudioEnvironment::at(Vec3::X * 5.0, &zones);
+        assert_eq!(inner.reverb, 0.0);
+        assert!((inner.low_pass_cutoff - 500.0).abs() < 1e-6);
+    }
+}
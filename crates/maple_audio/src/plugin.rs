@@ -1,8 +1,11 @@
-use std::ops::DerefMut;
+use std::{ops::DerefMut, time::Duration};
 
 use glam::{Quat, Vec3};
+#[cfg(not(target_arch = "wasm32"))]
+use kira::sound::streaming::StreamingSoundData;
 use kira::{
-    AudioManagerSettings, DefaultBackend, Tween, sound::streaming::StreamingSoundData,
+    AudioManagerSettings, Decibels, DefaultBackend, PlaybackRate, Tween, Value,
+    effect::{filter::FilterBuilder, volume_control::VolumeControlBuilder},
     track::SpatialTrackBuilder,
 };
 use maple_app::Plugin;
@@ -10,7 +13,11 @@ use maple_engine::prelude::Frame;
 
 use crate::{
     asset::{AudioData, AudioLoader},
-    nodes::{AudioListener, AudioSource, SourceHandle},
+    captions::{ActiveCaptions, Caption, CaptionHidden, CaptionLoader, CaptionShown},
+    nodes::{
+        AudioEnvironment, AudioListener, AudioSource, OPEN_CUTOFF, OcclusionEffects, ReverbZone,
+        SourceHandle,
+    },
     resource::AudioManager,
     sound::{DeferredSoundCommand, SoundState},
 };
@@ -26,10 +33,17 @@ impl Plugin for AudioPlugin {
             kira::AudioManager::<DefaultBackend>::new(AudioManagerSettings::default()).unwrap(),
         ));
 
+        app.context_mut().insert_resource(ActiveCaptions::default());
+
         app.context_mut().assets.register_loader(AudioLoader);
+        app.context_mut()
+            .assets
+            .register_loader(CaptionLoader::default());
     }
 
     fn update(&self, app: &mut maple_app::App<maple_app::Running>) {
+        update_captions(app);
+
         let mut manager = app.context().get_resource_mut::<AudioManager>();
 
         for (audio, settings, handle) in std::mem::take(&mut manager.queue) {
@@ -49,6 +63,7 @@ impl Plugin for AudioPlugin {
                     }
                     *state = SoundState::Handle(real_handle)
                 }
+                #[cfg(not(target_arch = "wasm32"))]
                 AudioData::Streaming(path) => {
                     let data = match StreamingSoundData::from_file(path) {
                         Ok(data) => data,
@@ -89,37 +104,101 @@ impl Plugin for AudioPlugin {
         }
         let listener = manager.listener.as_mut().unwrap();
 
-        let tween = Tween {
-            duration: app.context().get_resource::<Frame>().time_delta,
-            ..Default::default()
+        // audio plays in real time even when the game is slowed down
+        let (dt, tween) = {
+            let frame = app.context().get_resource::<Frame>();
+            let tween = Tween {
+                duration: frame.unscaled_time_delta,
+                ..Default::default()
+            };
+            (frame.unscaled_time_delta_f32, tween)
         };
 
-        listener.set_position(
-            active_listener.read().transform.world_space().position,
-            tween,
-        );
+        let listener_position = active_listener.read().transform.world_space().position();
+        let (heard_layers, spatial_layers) = {
+            let listener = active_listener.read();
+            (listener.layers, listener.spatial_layers)
+        };
+        listener.set_position(listener_position, tween);
         listener.set_orientation(
-            active_listener.read().transform.world_space().rotation,
+            active_listener.read().transform.world_space().rotation(),
             tween,
         );
 
         let id = listener.id();
 
+        let listener_velocity =
+            track_velocity(&mut manager.listener_last_position, listener_position, dt);
+        manager.listener_velocity = listener_velocity;
+        let speed_of_sound = manager.speed_of_sound;
+
+        let mut zones = Vec::new();
+        app.context()
+            .scene
+            .for_each_ref(&mut |zone: &ReverbZone| zones.push(zone.clone()));
+        let environment = AudioEnvironment::at(listener_position, &zones);
+        if manager.environment != environment {
+            if let Some(zone_reverb) = &mut manager.zone_reverb {
+                zone_reverb
+                    .reverb
+                    .set_feedback(environment.room_size, tween);
+                zone_reverb.reverb.set_damping(environment.damping, tween);
+            }
+            manager.environment = environment;
+        }
+        let reverb_send = manager.zone_reverb.as_ref().map(|zone| zone.track.id());
+
+        #[cfg(feature = "physics")]
+        let physics = app
+            .context()
+            .has_resource::<maple_physics::resource::Physics>()
+            .then(|| {
+                app.context()
+                    .get_resource::<maple_physics::resource::Physics>()
+            });
+
         app.context().scene.for_each::<AudioSource>(&mut |source| {
             if let SourceHandle::DeferredCommands(commands) = &mut source.handle {
+                let mut builder =
+                    SpatialTrackBuilder::default().spatialization_strength(SPATIALIZATION);
+                if let Some(send) = reverb_send {
+                    builder = builder.with_send(send, Decibels::SILENCE);
+                }
+                let filter = builder.add_effect(FilterBuilder::new().cutoff(OPEN_CUTOFF));
+                let zone_filter = builder.add_effect(FilterBuilder::new().cutoff(OPEN_CUTOFF));
+                let volume = builder.add_effect(VolumeControlBuilder::new(Decibels::IDENTITY));
+
                 let mut handle = manager
                     .manager
-                    .add_spatial_sub_track(id, Vec3::ZERO, SpatialTrackBuilder::default())
+                    .add_spatial_sub_track(id, Vec3::ZERO, builder)
                     .expect("max spatial tracks reached");
                 SourceHandle::apply_commands_spatial(&mut handle, commands);
                 source.handle = SourceHandle::SpatialHandle(handle);
+                source.effects = Some(OcclusionEffects {
+                    filter,
+                    volume,
+                    occluded: false,
+                    muted: false,
+                    zone_filter,
+                    zone: (0.0, OPEN_CUTOFF),
+                    spatial: true,
+                });
             }
 
             let SourceHandle::SpatialHandle(spatial_handle) = &mut source.handle else {
                 unreachable!("just resolved above")
             };
 
-            spatial_handle.set_position(source.transform.world_space().position, Tween::default());
+            let spatial = source.layers.intersects(spatial_layers);
+            let heard = source.layers.intersects(heard_layers);
+
+            // sources outside the world play at the listener so they aren't faded with distance
+            let source_position = match spatial {
+                true => source.transform.world_space().position(),
+                false => listener_position,
+            };
+            spatial_handle.set_position(source_position, Tween::default());
+            source.velocity = track_velocity(&mut source.last_position, source_position, dt);
 
             for (audio, settings, sound_handle) in std::mem::take(&mut source.queue) {
                 let Some(data) = app.context().assets.get(&audio) else {
@@ -128,9 +207,11 @@ impl Plugin for AudioPlugin {
                 };
                 match &data.data {
                     AudioData::Static(sound_data) => {
+                        let base_rate = base_playback_rate(&settings);
                         let mut real_handle = spatial_handle
                             .play(sound_data.clone().with_settings(settings.into()))
                             .expect("failed to play sound");
+                        source.playing.push((sound_handle.clone(), base_rate));
 
                         let mut state = sound_handle.0.lock();
                         if let SoundState::Deferred(commands) = state.deref_mut() {
@@ -138,6 +219,7 @@ impl Plugin for AudioPlugin {
                         }
                         *state = SoundState::Handle(real_handle)
                     }
+                    #[cfg(not(target_arch = "wasm32"))]
                     AudioData::Streaming(path) => {
                         let data = match StreamingSoundData::from_file(path) {
                             Ok(data) => data,
@@ -146,9 +228,11 @@ impl Plugin for AudioPlugin {
                                 continue;
                             }
                         };
+                        let base_rate = base_playback_rate(&settings);
                         let mut real_handle = spatial_handle
                             .play(data.with_settings(settings.into()))
                             .expect("failed to stream sound");
+                        source.playing.push((sound_handle.clone(), base_rate));
                         let mut state = sound_handle.0.lock();
                         if let SoundState::Deferred(commands) = state.deref_mut() {
                             DeferredSoundCommand::apply_commands_streaming(
@@ -160,6 +244,239 @@ impl Plugin for AudioPlugin {
                     }
                 }
             }
+
+            source.playing.retain(|(handle, _)| !handle.is_stopped());
+
+            if spatial && source.doppler_factor > 0.0 {
+                let ratio = doppler_ratio(
+                    source_position,
+                    source.velocity * source.doppler_factor,
+                    listener_position,
+                    listener_velocity * source.doppler_factor,
+                    speed_of_sound,
+                );
+                for (handle, base_rate) in &mut source.playing {
+                    handle.set_playback_rate(PlaybackRate(*base_rate * ratio), tween);
+                }
+            }
+
+            #[cfg(feature = "physics")]
+            let occluded = spatial
+                && source.occlusion
+                && physics.as_ref().is_some_and(|physics| {
+                    let to_listener = listener_position - source_position;
+                    let distance = to_listener.length();
+                    physics
+                        .cast_ray_from_inside(source_position, to_listener, distance)
+                        .is_some_and(|hit| hit < distance - OCCLUSION_EPSILON)
+                });
+            #[cfg(not(feature = "physics"))]
+            let occluded = false;
+
+            let Some(effects) = &mut source.effects else {
+                return;
+            };
+
+            if effects.occluded != occluded || effects.muted == heard {
+                let tween = Tween {
+                    duration: OCCLUSION_FADE,
+                    ..Default::default()
+                };
+                let (cutoff, volume) = match (heard, occluded) {
+                    (false, _) => (OPEN_CUTOFF, Decibels::SILENCE),
+                    (true, true) => (source.occlusion_cutoff, source.occlusion_attenuation),
+                    (true, false) => (OPEN_CUTOFF, Decibels::IDENTITY),
+                };
+                effects.filter.set_cutoff(cutoff, tween);
+                effects.volume.set_volume(volume, tween);
+                effects.occluded = occluded;
+                effects.muted = !heard;
+            }
+
+            if effects.spatial != spatial {
+                let strength = if spatial { SPATIALIZATION } else { 0.0 };
+                spatial_handle.set_spatialization_strength(strength, Tween::default());
+                effects.spatial = spatial;
+            }
+
+            let zone = match spatial {
+                true => (environment.reverb, environment.low_pass_cutoff),
+                false => (0.0, OPEN_CUTOFF),
+            };
+            if effects.zone != zone {
+                effects.zone_filter.set_cutoff(zone.1, tween);
+                if let Some(send) = reverb_send
+                    && spatial_handle
+                        .set_send(send, send_volume(zone.0), tween)
+                        .is_err()
+                {
+                    log::warn!("audio source is missing its reverb send");
+                }
+                effects.zone = zone;
+            }
         })
     }
 }
+
+/// shows and hides captions based on the position of the sounds they are tracking
+fn update_captions(app: &maple_app::App<maple_app::Running>) {
+    let mut shown = Vec::new();
+    let mut hidden = Vec::new();
+
+    {
+        let mut manager = app.context().get_resource_mut::<AudioManager>();
+        if manager.caption_tracks.is_empty() {
+            return;
+        }
+
+        manager.caption_tracks.retain_mut(|track| {
+            let Some(captions) = app.context().assets.get(&track.captions) else {
+                return true; // still loading
+            };
+
+            let finished = track.sound.is_stopped();
+            let visible: Vec<Caption> = match track.sound.position() {
+                Some(position) if !finished => captions
+                    .at(position)
+                    .map(|(_, caption)| caption.clone())
+                    .collect(),
+                _ => Vec::new(),
+            };
+
+            for caption in track.shown.iter().filter(|c| !visible.contains(c)) {
+                hidden.push(caption.clone());
+            }
+            for caption in visible.iter().filter(|c| !track.shown.contains(c)) {
+                shown.push(caption.clone());
+            }
+            track.shown = visible;
+
+            !finished
+        });
+    }
+
+    if shown.is_empty() && hidden.is_empty() {
+        return;
+    }
+
+    {
+        let mut active = app.context().get_resource_mut::<ActiveCaptions>();
+        active.captions.retain(|caption| !hidden.contains(caption));
+        active.captions.extend(shown.iter().cloned());
+    }
+
+    // the manager lock is released so handlers can play sounds in response
+    for caption in hidden {
+        app.context().emit(CaptionHidden { caption });
+    }
+    for caption in shown {
+        app.context().emit(CaptionShown { caption });
+    }
+}
+
+/// how strongly sources in the world are panned towards the side they are on
+const SPATIALIZATION: f32 = 0.75;
+/// how long it takes to fade in and out of the occluded state
+const OCCLUSION_FADE: Duration = Duration::from_millis(100);
+/// hits this close to the listener are ignored so a listener inside a collider isn't occluded by it
+#[cfg(feature = "physics")]
+const OCCLUSION_EPSILON: f32 = 0.05;
+
+/// updates the last known position and returns the velocity traveled since
+fn track_velocity(last_position: &mut Option<Vec3>, position: Vec3, dt: f32) -> Vec3 {
+    let velocity = match *last_position {
+        Some(last) if dt > 0.0 => (position - last) / dt,
+        _ => Vec3::ZERO,
+    };
+    *last_position = Some(position);
+    velocity
+}
+
+/// the volume of the route to the zone reverb for a send `amount` from `0.0` to `1.0`
+fn send_volume(amount: f32) -> Decibels {
+    if amount <= 0.0 {
+        return Decibels::SILENCE;
+    }
+    Decibels((20.0 * amount.log10()).max(Decibels::SILENCE.0))
+}
+
+fn base_playback_rate(settings: &crate::settings::SoundSettings) -> f64 {
+    match settings.playback_rate {
+        Value::Fixed(PlaybackRate(rate)) => rate,
+        _ => 1.0,
+    }
+}
+
+/// ratio of the perceived frequency to the emitted frequency
+///
+/// velocities are clamped to half the speed of sound so the ratio stays finite
+fn doppler_ratio(
+    source_position: Vec3,
+    source_velocity: Vec3,
+    listener_position: Vec3,
+    listener_velocity: Vec3,
+    speed_of_sound: f32,
+) -> f64 {
+    let Some(direction) = (listener_position - source_position).try_normalize() else {
+        return 1.0;
+    };
+
+    let max_speed = speed_of_sound * 0.5;
+    // positive when moving toward the other
+    let source_speed = source_velocity.dot(direction).clamp(-max_speed, max_speed);
+    let listener_speed = (-listener_velocity.dot(direction)).clamp(-max_speed, max_speed);
+
+    ((speed_of_sound + listener_speed) / (speed_of_sound - source_speed)) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_velocity_is_tracked_between_frames() {
+        let mut last = None;
+        // nothing to compare the first position with
+        assert_eq!(track_velocity(&mut last, Vec3::ZERO, 0.5), Vec3::ZERO);
+        assert_eq!(
+            track_velocity(&mut last, Vec3::new(1.0, 0.0, 2.0), 0.5),
+            Vec3::new(2.0, 0.0, 4.0)
+        );
+        // a paused frame doesn't divide by zero
+        assert_eq!(track_velocity(&mut last, Vec3::X * 5.0, 0.0), Vec3::ZERO);
+        assert_eq!(last, Some(Vec3::X * 5.0));
+    }
+
+    #[test]
+    fn test_doppler_rises_when_approaching() {
+        let speed = 343.0;
+        let listener = Vec3::X * 100.0;
+
+        let still = doppler_ratio(Vec3::ZERO, Vec3::ZERO, listener, Vec3::ZERO, speed);
+        assert_eq!(still, 1.0);
+
+        let approaching = doppler_ratio(Vec3::ZERO, Vec3::X * 34.3, listener, Vec3::ZERO, speed);
+        assert!((approaching - 343.0 / 308.7).abs() < 1e-5);
+        let leaving = doppler_ratio(Vec3::ZERO, -Vec3::X * 34.3, listener, Vec3::ZERO, speed);
+        assert!(leaving < 1.0);
+
+        // the listener moving towards the source raises the pitch as well
+        let listener_moving =
+            doppler_ratio(Vec3::ZERO, Vec3::ZERO, listener, -Vec3::X * 34.3, speed);
+        assert!((listener_moving - 1.1).abs() < 1e-5);
+
+        // sideways motion doesn't change the distance
+        let sideways = doppler_ratio(Vec3::ZERO, Vec3::Y * 50.0, listener, Vec3::ZERO, speed);
+        assert!((sideways - 1.0).abs() < 1e-6);
+
+        // faster than sound is clamped to half of it
+        let clamped = doppler_ratio(Vec3::ZERO, Vec3::X * 1000.0, listener, Vec3::ZERO, speed);
+        assert!((clamped - 2.0).abs() < 1e-5);
+
+        // on top of each other
+        assert_eq!(
+            doppler_ratio(listener, Vec3::X, listener, Vec3::ZERO, speed),
+            1.0
+        );
+    }
+}
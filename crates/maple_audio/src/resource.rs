@@ -1,25 +1,91 @@
 use std::collections::VecDeque;
 
-use kira::{AudioManager as Manager, listener::ListenerHandle};
+use glam::Vec3;
+use kira::{
+    AudioManager as Manager, Mix,
+    effect::reverb::{ReverbBuilder, ReverbHandle},
+    listener::ListenerHandle,
+    track::{SendTrackBuilder, SendTrackHandle},
+};
 use maple_engine::{asset::AssetHandle, prelude::Resource};
 
-use crate::{asset::Audio, settings::SoundSettings, sound::SoundHandle};
+use crate::{
+    asset::Audio,
+    captions::{CaptionTracker, Captions},
+    nodes::AudioEnvironment,
+    settings::SoundSettings,
+    sound::SoundHandle,
+};
+
+/// the send track every world source feeds the reverb of the zones through
+pub(crate) struct ZoneReverb {
+    pub(crate) track: SendTrackHandle,
+    pub(crate) reverb: ReverbHandle,
+}
 
 pub struct AudioManager {
     pub(crate) manager: Manager,
     pub(crate) listener: Option<ListenerHandle>,
     pub(crate) queue: VecDeque<(AssetHandle<Audio>, SoundSettings, SoundHandle)>,
+    pub(crate) listener_last_position: Option<Vec3>,
+    pub(crate) listener_velocity: Vec3,
+    pub(crate) speed_of_sound: f32,
+    pub(crate) caption_tracks: Vec<CaptionTracker>,
+    pub(crate) zone_reverb: Option<ZoneReverb>,
+    pub(crate) environment: AudioEnvironment,
 }
 
 impl AudioManager {
-    pub(crate) fn new(manager: Manager) -> Self {
+    pub(crate) fn new(mut manager: Manager) -> Self {
+        let mut builder = SendTrackBuilder::new();
+        let reverb = builder.add_effect(ReverbBuilder::new().mix(Mix::WET));
+        let zone_reverb = match manager.add_send_track(builder) {
+            Ok(track) => Some(ZoneReverb { track, reverb }),
+            Err(err) => {
+                log::warn!("reverb zones won't be heard: {err}");
+                None
+            }
+        };
+
         Self {
             manager,
             listener: None,
             queue: VecDeque::default(),
+            listener_last_position: None,
+            listener_velocity: Vec3::ZERO,
+            speed_of_sound: 343.0,
+            caption_tracks: Vec::new(),
+            zone_reverb,
+            environment: AudioEnvironment::default(),
         }
     }
 
+    /// show `captions` in sync with a playing sound
+    ///
+    /// works with sounds from both the manager and [`crate::nodes::AudioSource`] nodes. the
+    /// captions stop when the sound is stopped.
+    pub fn track_captions(&mut self, sound: &SoundHandle, captions: AssetHandle<Captions>) {
+        self.caption_tracks.push(CaptionTracker {
+            sound: sound.clone(),
+            captions,
+            shown: Vec::new(),
+        });
+    }
+
+    /// set the speed of sound in world units per second used for the doppler effect (default 343)
+    pub fn set_speed_of_sound(&mut self, speed: f32) {
+        self.speed_of_sound = speed.max(f32::EPSILON);
+    }
+
+    pub fn speed_of_sound(&self) -> f32 {
+        self.speed_of_sound
+    }
+
+    /// the [`crate::nodes::ReverbZone`]s blended where the listener was last frame
+    pub fn environment(&self) -> &AudioEnvironment {
+        &self.environment
+    }
+
     pub fn play(&mut self, sound: AssetHandle<Audio>, settings: SoundSettings) -> SoundHandle {
         let handle = SoundHandle::default();
         self.queue.push_back((sound, settings, handle.clone()));
@@ -11,8 +11,10 @@ pub use kira::sound::EndPosition;
 pub use kira::sound::PlaybackPosition;
 pub use kira::sound::Region;
 use kira::sound::static_sound::StaticSoundSettings;
+#[cfg(not(target_arch = "wasm32"))]
 use kira::sound::streaming::StreamingSoundSettings;
 
+#[derive(Clone)]
 pub struct SoundSettings {
     pub start_time: StartTime,
     pub start_position: PlaybackPosition,
@@ -54,6 +56,7 @@ impl From<SoundSettings> for StaticSoundSettings {
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl From<SoundSettings> for StreamingSoundSettings {
     fn from(value: SoundSettings) -> Self {
         Self {
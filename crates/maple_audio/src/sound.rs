@@ -1,10 +1,10 @@
 use std::{collections::VecDeque, ops::DerefMut, sync::Arc};
 
+#[cfg(not(target_arch = "wasm32"))]
+use kira::sound::{FromFileError, streaming::StreamingSoundHandle};
 use kira::{
     Decibels, Panning, PlaybackRate, StartTime, Tween, Value,
-    sound::{
-        FromFileError, Region, static_sound::StaticSoundHandle, streaming::StreamingSoundHandle,
-    },
+    sound::{PlaybackState, Region, static_sound::StaticSoundHandle},
 };
 use parking_lot::Mutex;
 
@@ -63,6 +63,7 @@ impl DeferredSoundCommand {
         }
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
     fn apply_command_streaming(
         handle: &mut StreamingSoundHandle<FromFileError>,
         cmd: DeferredSoundCommand,
@@ -86,6 +87,7 @@ impl DeferredSoundCommand {
         }
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn apply_commands_streaming(
         handle: &mut StreamingSoundHandle<FromFileError>,
         cmds: &mut VecDeque<DeferredSoundCommand>,
@@ -98,6 +100,7 @@ impl DeferredSoundCommand {
 
 pub enum SoundState {
     Handle(StaticSoundHandle),
+    #[cfg(not(target_arch = "wasm32"))]
     StreamingHandle(StreamingSoundHandle<FromFileError>),
     Deferred(VecDeque<DeferredSoundCommand>),
 }
@@ -112,10 +115,31 @@ impl Default for SoundState {
 pub struct SoundHandle(pub(crate) Arc<Mutex<SoundState>>);
 
 impl SoundHandle {
+    /// the playback position in seconds or None if the sound hasn't started playing yet
+    pub fn position(&self) -> Option<f64> {
+        match &*self.0.lock() {
+            SoundState::Handle(handle) => Some(handle.position()),
+            #[cfg(not(target_arch = "wasm32"))]
+            SoundState::StreamingHandle(handle) => Some(handle.position()),
+            SoundState::Deferred(_) => None,
+        }
+    }
+
+    /// returns true once the sound has finished playing or was stopped
+    pub fn is_stopped(&self) -> bool {
+        match &*self.0.lock() {
+            SoundState::Handle(handle) => handle.state() == PlaybackState::Stopped,
+            #[cfg(not(target_arch = "wasm32"))]
+            SoundState::StreamingHandle(handle) => handle.state() == PlaybackState::Stopped,
+            SoundState::Deferred(_) => false,
+        }
+    }
+
     pub fn set_volume(&mut self, volume: impl Into<Value<Decibels>>, tween: Tween) {
         let mut state = self.0.lock();
         match state.deref_mut() {
             SoundState::Handle(handle) => handle.set_volume(volume, tween),
+            #[cfg(not(target_arch = "wasm32"))]
             SoundState::StreamingHandle(handle) => handle.set_volume(volume, tween),
             SoundState::Deferred(commands) => commands.push_back(DeferredSoundCommand::SetVolume {
                 volume: volume.into(),
@@ -132,6 +156,7 @@ impl SoundHandle {
         let mut state = self.0.lock();
         match state.deref_mut() {
             SoundState::Handle(handle) => handle.set_playback_rate(playback_rate, tween),
+            #[cfg(not(target_arch = "wasm32"))]
             SoundState::StreamingHandle(handle) => handle.set_playback_rate(playback_rate, tween),
             SoundState::Deferred(commands) => {
                 commands.push_back(DeferredSoundCommand::SetPlaybackRate {
@@ -146,6 +171,7 @@ impl SoundHandle {
         let mut state = self.0.lock();
         match state.deref_mut() {
             SoundState::Handle(handle) => handle.set_panning(panning, tween),
+            #[cfg(not(target_arch = "wasm32"))]
             SoundState::StreamingHandle(handle) => handle.set_panning(panning, tween),
             SoundState::Deferred(commands) => {
                 commands.push_back(DeferredSoundCommand::SetPanning {
@@ -160,6 +186,7 @@ impl SoundHandle {
         let mut state = self.0.lock();
         match state.deref_mut() {
             SoundState::Handle(handle) => handle.set_loop_region(region),
+            #[cfg(not(target_arch = "wasm32"))]
             SoundState::StreamingHandle(handle) => handle.set_loop_region(region),
             SoundState::Deferred(commands) => commands.push_back(
                 DeferredSoundCommand::SetLoopReigon(region.into_optional_region()),
@@ -171,6 +198,7 @@ impl SoundHandle {
         let mut state = self.0.lock();
         match state.deref_mut() {
             SoundState::Handle(handle) => handle.pause(tween),
+            #[cfg(not(target_arch = "wasm32"))]
             SoundState::StreamingHandle(handle) => handle.pause(tween),
             SoundState::Deferred(commands) => {
                 commands.push_back(DeferredSoundCommand::Pause(tween))
@@ -182,6 +210,7 @@ impl SoundHandle {
         let mut state = self.0.lock();
         match state.deref_mut() {
             SoundState::Handle(handle) => handle.resume(tween),
+            #[cfg(not(target_arch = "wasm32"))]
             SoundState::StreamingHandle(handle) => handle.resume(tween),
             SoundState::Deferred(commands) => {
                 commands.push_back(DeferredSoundCommand::Resume(tween))
@@ -193,6 +222,7 @@ impl SoundHandle {
         let mut state = self.0.lock();
         match state.deref_mut() {
             SoundState::Handle(handle) => handle.resume_at(start_time, tween),
+            #[cfg(not(target_arch = "wasm32"))]
             SoundState::StreamingHandle(handle) => handle.resume_at(start_time, tween),
             SoundState::Deferred(commands) => {
                 commands.push_back(DeferredSoundCommand::ResumeAt { start_time, tween })
@@ -204,6 +234,7 @@ impl SoundHandle {
         let mut state = self.0.lock();
         match state.deref_mut() {
             SoundState::Handle(handle) => handle.stop(tween),
+            #[cfg(not(target_arch = "wasm32"))]
             SoundState::StreamingHandle(handle) => handle.stop(tween),
             SoundState::Deferred(commands) => commands.push_back(DeferredSoundCommand::Stop(tween)),
         }
@@ -0,0 +1,267 @@
+//! declarative sound triggers
+//!
+//! instead of writing an event handler that looks up an [`AudioSource`] and calls
+//! [`AudioSource::play`] a [`SoundTrigger`] can be attached to any node for any event:
+//!
+//! ```rust, ignore
+//! scene
+//!     .spawn(RigidBody3D::builder().build())
+//!     .play_sound_on::<ColliderEnter>(
+//!         SoundTrigger::new(thud)
+//!             .scale_volume(|e: &ColliderEnter| e.impact_velocity)
+//!             .velocity_range(0.5, 10.0),
+//!     );
+//! ```
+//!
+//! the sound is played through the node itself if it is an [`AudioSource`], otherwise through
+//! its first [`AudioSource`] child. if neither exist the sound is played without spatialization
+//! through the [`AudioManager`] resource.
+//!
+//! any [`EventLabel`] can be used as a trigger so animation markers, area/sensor events and
+//! collisions all go through the same path.
+
+use std::sync::Arc;
+
+use kira::{Decibels, Mapping, Value};
+use maple_engine::{Node, asset::AssetHandle, prelude::EventLabel, scene::NodeHandle};
+
+use crate::{asset::Audio, nodes::AudioSource, resource::AudioManager, settings::SoundSettings};
+
+type EventScale<E> = Arc<dyn Fn(&E) -> f32 + Send + Sync>;
+type EventFilter<E> = Arc<dyn Fn(&E) -> bool + Send + Sync>;
+
+/// describes a sound that is played whenever an event `E` reaches a node
+pub struct SoundTrigger<E: EventLabel> {
+    audio: AssetHandle<Audio>,
+    settings: SoundSettings,
+    scale: Option<EventScale<E>>,
+    filter: Option<EventFilter<E>>,
+    velocity_range: (f32, f32),
+    min_volume: f32,
+}
+
+impl<E: EventLabel> SoundTrigger<E> {
+    /// play `audio` with the default [`SoundSettings`] every time the event is received
+    pub fn new(audio: AssetHandle<Audio>) -> Self {
+        Self {
+            audio,
+            settings: SoundSettings::default(),
+            scale: None,
+            filter: None,
+            velocity_range: (0.0, 1.0),
+            min_volume: 0.0,
+        }
+    }
+
+    /// set the base settings the sound is played with
+    pub fn settings(mut self, settings: SoundSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// only play the sound if `filter` returns true for the event
+    pub fn filter(mut self, filter: impl Fn(&E) -> bool + Send + Sync + 'static) -> Self {
+        self.filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// scale the volume of the sound by a value read from the event such as an impact velocity
+    ///
+    /// the value is mapped through [`Self::velocity_range`] to an amplitude between
+    /// [`Self::min_volume`] and 1.0. volumes that come from a modulator or the listener distance
+    /// keep their curve and are lowered as a whole
+    pub fn scale_volume(mut self, scale: impl Fn(&E) -> f32 + Send + Sync + 'static) -> Self {
+        self.scale = Some(Arc::new(scale));
+        self
+    }
+
+    /// the range of values returned by [`Self::scale_volume`] that map to quietest and loudest.
+    ///
+    /// values below `min` are silent and values above `max` play at full volume
+    pub fn velocity_range(mut self, min: f32, max: f32) -> Self {
+        self.velocity_range = (min, max.max(min + f32::EPSILON));
+        self
+    }
+
+    /// the amplitude (0.0 - 1.0) used once the scaled value passes the minimum of the velocity range
+    pub fn min_volume(mut self, min_volume: f32) -> Self {
+        self.min_volume = min_volume.clamp(0.0, 1.0);
+        self
+    }
+
+    /// returns the settings to play for this event or None if the trigger should not fire
+    fn resolve(&self, event: &E) -> Option<SoundSettings> {
+        if let Some(filter) = &self.filter
+            && !filter(event)
+        {
+            return None;
+        }
+
+        let mut settings = self.settings.clone();
+
+        if let Some(scale) = &self.scale {
+            let (min, max) = self.velocity_range;
+            let value = scale(event);
+            if value < min {
+                return None;
+            }
+
+            let t = ((value - min) / (max - min)).clamp(0.0, 1.0);
+            let amplitude = self.min_volume + (1.0 - self.min_volume) * t;
+            if amplitude <= 0.0 {
+                return None;
+            }
+
+            settings.volume = add_gain(settings.volume, 20.0 * amplitude.log10());
+        }
+
+        Some(settings)
+    }
+}
+
+/// add `gain` decibels to a volume, mapped volumes have their whole output range shifted
+fn add_gain(volume: Value<Decibels>, gain: f32) -> Value<Decibels> {
+    let shift = |mapping: Mapping<Decibels>| Mapping {
+        output_range: (
+            Decibels(mapping.output_range.0.0 + gain),
+            Decibels(mapping.output_range.1.0 + gain),
+        ),
+        ..mapping
+    };
+
+    match volume {
+        Value::Fixed(Decibels(db)) => Value::Fixed(Decibels(db + gain)),
+        Value::FromModulator { id, mapping } => Value::FromModulator {
+            id,
+            mapping: shift(mapping),
+        },
+        Value::FromListenerDistance(mapping) => Value::FromListenerDistance(shift(mapping)),
+    }
+}
+
+impl<E: EventLabel> Clone for SoundTrigger<E> {
+    fn clone(&self) -> Self {
+        Self {
+            audio: self.audio.clone(),
+            settings: self.settings.clone(),
+            scale: self.scale.clone(),
+            filter: self.filter.clone(),
+            velocity_range: self.velocity_range,
+            min_volume: self.min_volume,
+        }
+    }
+}
+
+/// adds [`SoundTrigger`] helpers to node handles
+pub trait SoundTriggerExt {
+    /// play a sound every time the event `E` is emitted to this node
+    fn play_sound_on<E: EventLabel>(&self, trigger: SoundTrigger<E>) -> &Self;
+}
+
+impl<T: Node> SoundTriggerExt for NodeHandle<'_, T> {
+    fn play_sound_on<E: EventLabel>(&self, trigger: SoundTrigger<E>) -> &Self {
+        self.on::<E>(move |ctx| {
+            let Some(settings) = trigger.resolve(ctx.event) else {
+                return;
+            };
+            let audio = trigger.audio.clone();
+
+            if let Some(source) = ctx.scene().get::<AudioSource>(ctx.node_id()) {
+                source.write().play(audio, settings);
+            } else if let Some(source) = ctx.first_child::<AudioSource>() {
+                source.write().play(audio, settings);
+            } else {
+                ctx.get_resource_mut::<AudioManager>().play(audio, settings);
+            }
+        });
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use kira::Easing;
+    use maple_engine::prelude::AssetLibrary;
+
+    use super::*;
+    use crate::asset::AudioData;
+
+    struct Impact(f32);
+
+    impl EventLabel for Impact {}
+
+    fn trigger() -> SoundTrigger<Impact> {
+        let assets = AssetLibrary::new();
+        SoundTrigger::new(assets.register(Audio {
+            data: AudioData::Streaming(Arc::from(Path::new("thud.ogg"))),
+        }))
+    }
+
+    fn volume(settings: Option<SoundSettings>) -> Option<Value<Decibels>> {
+        settings.map(|settings| settings.volume)
+    }
+
+    #[test]
+    fn test_filter_skips_events() {
+        let trigger = trigger().filter(|e: &Impact| e.0 > 1.0);
+        assert!(trigger.resolve(&Impact(0.5)).is_none());
+        assert_eq!(
+            volume(trigger.resolve(&Impact(2.0))),
+            Some(Value::Fixed(Decibels::IDENTITY))
+        );
+    }
+
+    #[test]
+    fn test_scale_maps_the_value_to_a_volume() {
+        let trigger = trigger()
+            .scale_volume(|e: &Impact| e.0)
+            .velocity_range(1.0, 3.0)
+            .min_volume(0.1);
+
+        // below the range the trigger doesn't fire
+        assert!(trigger.resolve(&Impact(0.5)).is_none());
+        assert_eq!(
+            volume(trigger.resolve(&Impact(1.0))),
+            Some(Value::Fixed(Decibels(-20.0)))
+        );
+        assert_eq!(
+            volume(trigger.resolve(&Impact(5.0))),
+            Some(Value::Fixed(Decibels(0.0)))
+        );
+
+        let half = volume(trigger.resolve(&Impact(2.0)));
+        let Some(Value::Fixed(Decibels(db))) = half else {
+            panic!("expected a fixed volume, got {half:?}");
+        };
+        assert!((db - 20.0 * 0.55f32.log10()).abs() < 1e-4);
+
+        let silent = trigger.clone().min_volume(0.0);
+        assert!(silent.resolve(&Impact(1.0)).is_none());
+    }
+
+    #[test]
+    fn test_scale_shifts_mapped_volumes() {
+        let distance = Mapping {
+            input_range: (1.0, 20.0),
+            output_range: (Decibels(0.0), Decibels(-30.0)),
+            easing: Easing::Linear,
+        };
+        let trigger = trigger()
+            .settings(SoundSettings {
+                volume: Value::FromListenerDistance(distance),
+                ..Default::default()
+            })
+            .scale_volume(|e: &Impact| e.0)
+            .min_volume(0.1);
+
+        assert_eq!(
+            volume(trigger.resolve(&Impact(0.0))),
+            Some(Value::FromListenerDistance(Mapping {
+                output_range: (Decibels(-20.0), Decibels(-50.0)),
+                ..distance
+            }))
+        );
+    }
+}
@@ -1,6 +1,6 @@
 //! # maple_derive
 //!
-//! This crate provides a custom derive macro for implementing the `Node` trait in the `maple` game engine.
+//! This crate provides custom derive macros for implementing the `Node` and `ShaderParams` traits in the `maple` game engine.
 //!
 //! ## Usage
 //!
@@ -101,3 +101,97 @@ pub fn derive_node(input: TokenStream) -> TokenStream {
 
     TokenStream::from(expanded)
 }
+
+/// Derives `ShaderParams` for a `#[repr(C)]` struct so its layout can be checked against a shader
+/// with `ShaderLayout`.
+///
+/// ## Example
+///
+/// ```rust,ignore
+/// use maple::derive::ShaderParams;
+///
+/// #[repr(C)]
+/// #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, ShaderParams)]
+/// struct Params {
+///     color: [f32; 3],
+///     _padding: f32,
+///     time: f32,
+/// }
+/// ```
+#[proc_macro_derive(ShaderParams)]
+pub fn derive_shader_params(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let struct_name = &input.ident;
+
+    let fields = match &input.data {
+        Data::Struct(data_struct) => match &data_struct.fields {
+            Fields::Named(fields_named) => &fields_named.named,
+            _ => {
+                return syn::Error::new_spanned(
+                    struct_name,
+                    "ShaderParams can only be derived for structs with named fields",
+                )
+                .to_compile_error()
+                .into();
+            }
+        },
+        _ => {
+            return syn::Error::new_spanned(
+                struct_name,
+                "ShaderParams can only be derived for structs",
+            )
+            .to_compile_error()
+            .into();
+        }
+    };
+
+    // without repr(C) rust is free to reorder the fields so the offsets mean nothing
+    let mut repr_c = false;
+    for attr in &input.attrs {
+        if attr.path().is_ident("repr") {
+            let _ = attr.parse_nested_meta(|meta| {
+                repr_c |= meta.path.is_ident("C");
+                Ok(())
+            });
+        }
+    }
+
+    if !repr_c {
+        return syn::Error::new_spanned(
+            struct_name,
+            "ShaderParams needs a fixed field order\n\
+        Example:\n\
+        #[repr(C)]\n\
+        #[derive(ShaderParams)]",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let params = fields.iter().map(|field| {
+        let ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        let name = ident.to_string();
+        let ty_name = quote!(#ty).to_string().replace(' ', "");
+
+        quote! {
+            ::maple::renderer::core::ParamField {
+                name: #name,
+                ty: #ty_name,
+                offset: ::core::mem::offset_of!(Self, #ident),
+                size: ::core::mem::size_of::<#ty>(),
+            }
+        }
+    });
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let expanded = quote! {
+        impl #impl_generics ::maple::renderer::core::ShaderParams for #struct_name #ty_generics #where_clause {
+            const FIELDS: &'static [::maple::renderer::core::ParamField] = &[#(#params),*];
+        }
+    };
+
+    TokenStream::from(expanded)
+}
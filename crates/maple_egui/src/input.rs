@@ -1,9 +1,31 @@
 use egui::{Event as EguiEvent, Key as EguiKey, Modifiers, MouseWheelUnit, Pos2, Rect};
 use maple_engine::{
+    GameContext,
     prelude::{Input, MouseButton, TouchPhase},
-    resources::KeyCode,
+    resources::{KeyCode, VirtualCursor},
 };
 
+/// let the [`VirtualCursor`] snap onto a widget, call it for every widget a gamepad should be
+/// able to reach each frame it's drawn
+///
+/// ```rust, ignore
+/// let play = ui.button("Play");
+/// cursor_target(ctx.game, &play);
+/// ```
+///
+/// does nothing if the game has no [`VirtualCursor`]
+pub fn cursor_target(game: &GameContext, response: &egui::Response) {
+    if !game.has_resource::<VirtualCursor>() {
+        return;
+    }
+    let rect = response.interact_rect;
+    let pixels_per_point = response.ctx.pixels_per_point();
+    game.get_resource_mut::<VirtualCursor>().add_target(
+        glam::vec2(rect.min.x, rect.min.y) * pixels_per_point,
+        glam::vec2(rect.max.x, rect.max.y) * pixels_per_point,
+    );
+}
+
 /// Builds an [`egui::RawInput`] for this frame purely from Input's public API.
 pub fn input_to_egui_raw_input(
     input: &Input,
@@ -0,0 +1,148 @@
+//! a debug window for looking through the scene while the game runs
+//!
+//! the window lists the scene as a tree, clicking a node selects it. the selected node's
+//! transform is shown along with what it cost to draw last frame from [`RenderStats`], its draw
+//! calls, triangles and share of the gpu time in each pass, so expensive assets are easy to find.
+
+use std::time::Duration;
+
+use maple_engine::{Node, Scene, prelude::EventCtx, scene::NodeId};
+use maple_renderer::render_graph::stats::RenderStats;
+
+use crate::{egui, plugin::EguiUpdate};
+
+/// event handler that draws the inspector window
+///
+/// attach it to any node:
+/// ```rust, ignore
+/// scene
+///     .spawn(Empty::default())
+///     .on::<EguiUpdate>(inspector());
+/// ```
+pub fn inspector<N: Node>() -> impl FnMut(EventCtx<EguiUpdate, N>) + Send + Sync + 'static {
+    let mut selected: Option<NodeId> = None;
+    move |ctx| {
+        let scene = &ctx.game.scene;
+        egui::Window::new("Inspector")
+            .default_open(false)
+            .show(ctx.event, |ui| {
+                egui::ScrollArea::vertical()
+                    .id_salt("scene tree")
+                    .max_height(240.0)
+                    .show(ui, |ui| {
+                        for root in scene.root_ids() {
+                            tree_ui(ui, scene, root, &mut selected);
+                        }
+                    });
+
+                ui.separator();
+
+                // the node may have been despawned since it was selected
+                match selected.filter(|id| scene.with_transform(*id, |_| ()).is_some()) {
+                    Some(id) => {
+                        node_ui(ui, scene, id);
+                        ui.separator();
+                        match ctx.game.has_resource::<RenderStats>() {
+                            true => stats_ui(ui, &ctx.game.get_resource::<RenderStats>(), id),
+                            false => {
+                                ui.weak("the app has no render stats");
+                            }
+                        }
+                    }
+                    None => {
+                        selected = None;
+                        ui.weak("select a node to inspect it");
+                    }
+                }
+            });
+    }
+}
+
+fn node_label(scene: &Scene, id: NodeId) -> String {
+    scene.node_name(id).unwrap_or_else(|| format!("{id:?}"))
+}
+
+fn tree_ui(ui: &mut egui::Ui, scene: &Scene, id: NodeId, selected: &mut Option<NodeId>) {
+    let children = scene.children_ids(id);
+    let label = node_label(scene, id);
+    let is_selected = *selected == Some(id);
+
+    if children.is_empty() {
+        if ui.selectable_label(is_selected, label).clicked() {
+            *selected = Some(id);
+        }
+        return;
+    }
+
+    let response = egui::CollapsingHeader::new(label)
+        .id_salt(id)
+        .show(ui, |ui| {
+            for child in children {
+                tree_ui(ui, scene, child, selected);
+            }
+        });
+    if response.header_response.clicked() {
+        *selected = Some(id);
+    }
+    if is_selected {
+        response.header_response.highlight();
+    }
+}
+
+fn node_ui(ui: &mut egui::Ui, scene: &Scene, id: NodeId) {
+    ui.heading(node_label(scene, id));
+    scene.with_transform(id, |transform| {
+        let (axis, angle) = transform.rotation.to_axis_angle();
+        egui::Grid::new("transform").num_columns(2).show(ui, |ui| {
+            ui.label("position");
+            ui.label(format!("{:.2}", transform.position));
+            ui.end_row();
+            ui.label("rotation");
+            ui.label(format!("{:.1}° around {:.2}", angle.to_degrees(), axis));
+            ui.end_row();
+            ui.label("scale");
+            ui.label(format!("{:.2}", transform.scale));
+            ui.end_row();
+        });
+    });
+}
+
+fn stats_ui(ui: &mut egui::Ui, stats: &RenderStats, id: NodeId) {
+    let node = stats.node(id);
+    if node.passes.is_empty() {
+        ui.weak("not drawn last frame");
+        return;
+    }
+
+    egui::Grid::new("render stats")
+        .num_columns(4)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.strong("pass");
+            ui.strong("draws");
+            ui.strong("triangles");
+            ui.strong("gpu");
+            ui.end_row();
+
+            for (pass, draws) in &node.passes {
+                ui.label(pass);
+                ui.label(draws.draw_calls.to_string());
+                ui.label(draws.triangles.to_string());
+                ui.label(gpu_time(draws.gpu_time));
+                ui.end_row();
+            }
+
+            ui.strong("total");
+            ui.strong(node.draw_calls().to_string());
+            ui.strong(node.triangles().to_string());
+            ui.strong(gpu_time(node.gpu_time()));
+            ui.end_row();
+        });
+}
+
+fn gpu_time(time: Option<Duration>) -> String {
+    match time {
+        Some(time) => format!("{:.3} ms", time.as_secs_f64() * 1000.0),
+        None => "-".to_string(),
+    }
+}
@@ -1,13 +1,20 @@
 //! egui implementation for maple
 
 pub mod input;
+pub mod inspector;
+pub mod memory_panel;
 pub mod plugin;
 pub mod render;
+pub mod tweak_panel;
 
 pub use egui;
 
 pub mod prelude {
+    pub use crate::input::cursor_target;
+    pub use crate::inspector::inspector;
+    pub use crate::memory_panel::gpu_memory_panel;
     pub use crate::plugin::EguiPlugin;
     pub use crate::plugin::EguiUpdate;
+    pub use crate::tweak_panel::uniform_tweak_panel;
     pub use egui;
 }
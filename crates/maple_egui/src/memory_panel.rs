@@ -0,0 +1,137 @@
+//! a debug window showing the gpu memory the loaded assets take
+//!
+//! the assets in [`GpuMemoryStats`] are added up by the directory they were loaded from so the
+//! folders over budget stand out, the largest assets are listed below with their format, size and
+//! mips. copy json puts the whole report on the clipboard.
+
+use maple_engine::{Node, prelude::EventCtx};
+use maple_renderer::render_graph::memory::{AssetMemory, GpuMemoryStats, MemoryKind};
+
+use crate::{egui, plugin::EguiUpdate};
+
+/// how many of the largest assets are listed
+const LARGEST: usize = 32;
+
+/// event handler that draws a window with the gpu memory of the loaded assets
+///
+/// attach it to any node:
+/// ```rust, ignore
+/// scene
+///     .spawn(Empty::default())
+///     .on::<EguiUpdate>(gpu_memory_panel());
+/// ```
+pub fn gpu_memory_panel<N: Node>() -> impl FnMut(EventCtx<EguiUpdate, N>) + Send + Sync + 'static {
+    move |ctx| {
+        egui::Window::new("GPU Memory")
+            .default_open(false)
+            .show(ctx.event, |ui| {
+                if !ctx.game.has_resource::<GpuMemoryStats>() {
+                    ui.weak("the app has no memory stats");
+                    return;
+                }
+                let memory = ctx.game.get_resource::<GpuMemoryStats>();
+
+                ui.horizontal(|ui| {
+                    ui.strong(format!("total {}", format_bytes(memory.total_bytes())));
+                    if let Some(budget) = memory.texture_budget() {
+                        let textures = format!(
+                            "textures {} of {}",
+                            format_bytes(memory.texture_bytes()),
+                            format_bytes(budget)
+                        );
+                        if memory.is_over_budget() {
+                            ui.colored_label(ui.visuals().warn_fg_color, textures);
+                        } else {
+                            ui.label(textures);
+                        }
+                    }
+                    if ui.button("copy json").clicked() {
+                        ui.ctx().copy_text(memory.to_json());
+                    }
+                });
+                ui.separator();
+                categories_ui(ui, &memory);
+                ui.separator();
+
+                egui::CollapsingHeader::new("largest assets").show(ui, |ui| {
+                    egui::ScrollArea::vertical()
+                        .max_height(240.0)
+                        .show(ui, |ui| {
+                            assets_ui(ui, &memory.assets()[..LARGEST.min(memory.assets().len())])
+                        });
+                });
+            });
+    }
+}
+
+fn categories_ui(ui: &mut egui::Ui, memory: &GpuMemoryStats) {
+    egui::Grid::new("memory categories")
+        .num_columns(4)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.strong("directory");
+            ui.strong("textures");
+            ui.strong("meshes");
+            ui.strong("total");
+            ui.end_row();
+
+            for category in memory.categories() {
+                ui.label(&category.name);
+                ui.label(format!(
+                    "{} ({})",
+                    format_bytes(category.texture_bytes),
+                    category.textures
+                ));
+                ui.label(format!(
+                    "{} ({})",
+                    format_bytes(category.mesh_bytes),
+                    category.meshes
+                ));
+                ui.label(format_bytes(category.bytes()));
+                ui.end_row();
+            }
+        });
+}
+
+fn assets_ui(ui: &mut egui::Ui, assets: &[AssetMemory]) {
+    egui::Grid::new("memory assets")
+        .num_columns(3)
+        .striped(true)
+        .show(ui, |ui| {
+            for asset in assets {
+                ui.label(asset.name());
+                ui.label(match asset.kind {
+                    MemoryKind::Texture {
+                        format,
+                        width,
+                        height,
+                        mip_levels,
+                    } => format!("{format:?} {width}x{height} {mip_levels} mips"),
+                    MemoryKind::Mesh {
+                        vertex_bytes,
+                        index_bytes,
+                    } => format!(
+                        "{} vertices {} indices",
+                        format_bytes(vertex_bytes),
+                        format_bytes(index_bytes)
+                    ),
+                });
+                ui.label(format_bytes(asset.bytes()));
+                ui.end_row();
+            }
+        });
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{bytes} B"),
+        _ => format!("{size:.1} {}", UNITS[unit]),
+    }
+}
@@ -1,11 +1,12 @@
 use std::ops::Deref;
 
-use egui::{Color32, Context, FullOutput};
+use egui::{Color32, Context, FullOutput, TextureId};
 use maple_app::Plugin;
 use maple_engine::{
     color::Color,
     prelude::{EventLabel, Frame, Input, Resource},
 };
+use maple_renderer::core::texture::Texture;
 
 use crate::render::EguiRender;
 
@@ -19,6 +20,7 @@ impl Plugin for EguiPlugin {
         app.context_mut().insert_resource(EguiResource {
             context: Context::default(),
             full_output: None,
+            textures: Vec::new(),
         });
 
         app.renderer_mut()
@@ -65,6 +67,13 @@ impl Plugin for EguiPlugin {
 
         let output = ctx.end_pass();
 
+        // hovers and other animations ask for the next frame
+        if let Some(viewport) = output.viewport_output.get(&egui::ViewportId::ROOT)
+            && viewport.repaint_delay != std::time::Duration::MAX
+        {
+            app.context().request_redraw_after(viewport.repaint_delay);
+        }
+
         app.context().get_resource_mut::<EguiResource>().full_output = Some(output);
     }
 }
@@ -73,6 +82,33 @@ impl Plugin for EguiPlugin {
 pub struct EguiResource {
     pub context: Context,
     pub full_output: Option<FullOutput>,
+    /// textures drawn through [`TextureId::User`], indexed by the id
+    textures: Vec<Texture>,
+}
+
+impl EguiResource {
+    /// the id egui draws `texture` with, e.g. in [`egui::Image`] or [`egui::Painter::image`]
+    ///
+    /// the same texture always gets the same id. registered textures are kept for as long as the
+    /// resource is
+    pub fn register_texture(&mut self, texture: &Texture) -> TextureId {
+        let index = match self.textures.iter().position(|t| t == texture) {
+            Some(index) => index,
+            None => {
+                self.textures.push(texture.clone());
+                self.textures.len() - 1
+            }
+        };
+        TextureId::User(index as u64)
+    }
+
+    /// the texture registered as `id` with [`Self::register_texture`]
+    pub fn user_texture(&self, id: TextureId) -> Option<&Texture> {
+        match id {
+            TextureId::User(index) => self.textures.get(index as usize),
+            TextureId::Managed(_) => None,
+        }
+    }
 }
 
 impl Resource for EguiResource {}
@@ -82,6 +82,11 @@ impl RenderNode for EguiRender {
     where
         Self: Sized,
     {
+        // egui colors are gamma encoded, srgb surfaces expect them decoded
+        let entry_point = match rcx.surface_format().is_srgb() {
+            true => "fs_main_linear_framebuffer",
+            false => "fs_main_gamma_framebuffer",
+        };
         let fragment = rcx
             .device()
             .compile_shader(ShaderSource {
@@ -89,7 +94,7 @@ impl RenderNode for EguiRender {
                     "egui.wgsl"
                 )),
                 label: Some("egui shader"),
-                entry_point: Some("fs_main_linear_framebuffer"),
+                entry_point: Some(entry_point),
             })
             .expect("failed to compile egui fragment shader");
         let vertex = rcx
@@ -199,6 +204,13 @@ impl RenderNode for EguiRender {
             .tessellate(full_output.shapes, full_output.pixels_per_point);
 
         let (vertices, indices, mesh_ranges) = Self::flatten_primitives(&clipped_primitives);
+        for (_, _, id) in &mesh_ranges {
+            if !self.textures.contains_key(id)
+                && let Some(texture) = egui_res.user_texture(*id)
+            {
+                self.add_user_texture(rcx, *id, texture);
+            }
+        }
         if vertices.is_empty() {
             for id in &full_output.textures_delta.free {
                 self.textures.remove(id);
@@ -231,6 +243,7 @@ impl RenderNode for EguiRender {
                     depth_target: None,
                     clear_color: None,
                     clear_depth: None,
+                    discard_depth: false,
                 },
                 move |mut fb| {
                     fb.use_pipeline(&self.pipeline)
@@ -309,6 +322,22 @@ impl EguiRender {
         }
     }
 
+    /// bind a texture registered with [`EguiResource::register_texture`]
+    fn add_user_texture(&mut self, rcx: &RenderContext, id: TextureId, texture: &Texture) {
+        let descriptor = rcx.device().build_descriptor_set(
+            DescriptorSet::builder(&self.texture_layout)
+                .texture_view(0, &texture.create_view())
+                .sampler(1, &self.sampler),
+        );
+        self.textures.insert(
+            id,
+            EguiTexture {
+                texture: texture.clone(),
+                descriptor,
+            },
+        );
+    }
+
     fn flatten_primitives(
         clipped_primitives: &[egui::ClippedPrimitive],
     ) -> (
@@ -0,0 +1,129 @@
+//! a debug window for tuning shader uniforms while the game runs
+//!
+//! every [`UniformTweak`] a pass has made is listed with an editor for each of its members,
+//! sliders for numbers and color pickers for vectors named like colors. edits are written into the
+//! pass's uniforms the next frame, reset puts the pass back in control.
+
+use maple_engine::{Node, prelude::EventCtx};
+use maple_renderer::core::{UniformMember, UniformTweak, UniformValue, uniform_tweaks};
+
+use crate::{egui, plugin::EguiUpdate};
+
+/// event handler that draws a window with every [`UniformTweak`]
+///
+/// attach it to any node:
+/// ```rust, ignore
+/// scene
+///     .spawn(Empty::default())
+///     .on::<EguiUpdate>(uniform_tweak_panel());
+/// ```
+pub fn uniform_tweak_panel<N: Node>() -> impl FnMut(EventCtx<EguiUpdate, N>) + Send + Sync + 'static
+{
+    move |ctx| {
+        egui::Window::new("Shader Uniforms")
+            .default_open(false)
+            .show(ctx.event, |ui| {
+                let tweaks = uniform_tweaks();
+                if tweaks.is_empty() {
+                    ui.label("no pass has tweakable uniforms");
+                }
+                for (index, tweak) in tweaks.iter().enumerate() {
+                    ui.push_id(index, |ui| tweak_ui(ui, tweak));
+                }
+            });
+    }
+}
+
+/// the editors for one tweak, for putting them in a window of your own
+pub fn tweak_ui(ui: &mut egui::Ui, tweak: &UniformTweak) {
+    let block = tweak.block();
+    egui::CollapsingHeader::new(format!("{}: {}", tweak.label(), block.name))
+        .id_salt((block.group, block.binding))
+        .show(ui, |ui| {
+            egui::Grid::new("members").num_columns(2).show(ui, |ui| {
+                for member in block.members.iter().filter(|member| !member.is_padding()) {
+                    ui.label(&member.name);
+                    member_ui(ui, tweak, member);
+                    ui.end_row();
+                }
+            });
+            if ui.button("reset").clicked() {
+                tweak.reset();
+            }
+        });
+}
+
+fn member_ui(ui: &mut egui::Ui, tweak: &UniformTweak, member: &UniformMember) {
+    let (Some(mut value), Some(original)) = (tweak.get(&member.name), tweak.original(&member.name))
+    else {
+        ui.weak(format!("{} bytes", member.size));
+        return;
+    };
+
+    let color = is_color(&member.name);
+    let changed = match &mut value {
+        UniformValue::F32(value) => ui.add(slider(value, original_f32(original, 0))).changed(),
+        UniformValue::Vec3(rgb) if color => ui.color_edit_button_rgb(rgb).changed(),
+        UniformValue::Vec4(rgba) if color => ui.color_edit_button_rgba_unmultiplied(rgba).changed(),
+        UniformValue::Vec2(values) => components_ui(ui, values, original),
+        UniformValue::Vec3(values) => components_ui(ui, values, original),
+        UniformValue::Vec4(values) => components_ui(ui, values, original),
+        UniformValue::I32(value) => ui.add(egui::DragValue::new(value)).changed(),
+        UniformValue::U32(value) => ui.add(egui::DragValue::new(value)).changed(),
+    };
+
+    if changed {
+        tweak.set(&member.name, value);
+    }
+}
+
+fn components_ui(ui: &mut egui::Ui, values: &mut [f32], original: UniformValue) -> bool {
+    ui.horizontal(|ui| {
+        values
+            .iter_mut()
+            .enumerate()
+            .map(|(index, value)| {
+                let range = slider_range(original_f32(original, index));
+                ui.add(
+                    egui::DragValue::new(value)
+                        .speed((range.end() - range.start()) / 200.0)
+                        .max_decimals(3),
+                )
+                .changed()
+            })
+            // every component is drawn even after one changed
+            .fold(false, |changed, component| changed | component)
+    })
+    .inner
+}
+
+fn slider(value: &mut f32, original: f32) -> egui::Slider<'_> {
+    egui::Slider::new(value, slider_range(original)).clamping(egui::SliderClamping::Never)
+}
+
+/// uniforms have no range, so the slider covers a few times the value the pass started with
+fn slider_range(original: f32) -> std::ops::RangeInclusive<f32> {
+    let extent = (original.abs() * 4.0).max(1.0);
+    match original < 0.0 {
+        true => -extent..=extent,
+        false => 0.0..=extent,
+    }
+}
+
+fn original_f32(original: UniformValue, component: usize) -> f32 {
+    let components: &[f32] = match &original {
+        UniformValue::F32(value) => std::slice::from_ref(value),
+        UniformValue::Vec2(values) => values,
+        UniformValue::Vec3(values) => values,
+        UniformValue::Vec4(values) => values,
+        UniformValue::I32(_) | UniformValue::U32(_) => &[],
+    };
+    components.get(component).copied().unwrap_or(0.0)
+}
+
+fn is_color(name: &str) -> bool {
+    let name = name.to_lowercase();
+    ["color", "colour", "tint", "albedo"]
+        .iter()
+        .any(|word| name.contains(word))
+}
@@ -1,18 +1,24 @@
 use std::{
     any::{Any, TypeId},
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     error::Error,
     fmt::Display,
     marker::PhantomData,
     ops::{Deref, DerefMut},
+    panic::AssertUnwindSafe,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
     thread,
-    time::Duration,
+    time::{Duration, SystemTime},
 };
 
 use parking_lot::{ArcRwLockReadGuard, ArcRwLockWriteGuard, Mutex, RawRwLock, RwLock};
 
+use crate::components::EventLabel;
+
 /// Error that happened during loading
 #[derive(Debug, Clone)]
 pub enum LoadErr {
@@ -47,6 +53,128 @@ impl Display for LoadErr {
 
 impl Error for LoadErr {}
 
+/// emitted to the scene for every asset that failed to load
+///
+/// the asset shows up as its placeholder from [`AssetLibrary::set_fallback`] if the type has one
+#[derive(Debug, Clone)]
+pub struct AssetError {
+    pub id: AssetId,
+    /// the rust type name of the asset
+    pub asset_type: &'static str,
+    pub error: LoadErr,
+}
+impl EventLabel for AssetError {}
+
+/// emitted to the scene for every asset that finished loading from a file or with
+/// [`AssetLibrary::add`]
+#[derive(Debug, Clone)]
+pub struct AssetLoaded {
+    pub id: AssetId,
+    /// the rust type name of the asset
+    pub asset_type: &'static str,
+    /// true if the file changed and the asset was loaded again, see
+    /// [`AssetLibrary::set_hot_reload`]
+    pub reloaded: bool,
+}
+impl EventLabel for AssetLoaded {}
+
+#[cfg(not(target_arch = "wasm32"))]
+type LoadJob = Box<dyn FnOnce() + Send>;
+
+/// loads the file of a path asset again into the slot it was first loaded into
+type Reload = Arc<dyn Fn(&AssetLibrary, &Path) + Send + Sync>;
+
+/// how often files are checked for changes while hot reloading
+const HOT_RELOAD_INTERVAL: Duration = Duration::from_millis(250);
+
+/// how long an asset goes without handles before it is freed unless set otherwise
+const DEFAULT_FREE_UNUSED_AFTER: f32 = 30.0;
+
+/// how many assets are checked each [`AssetLibrary::collect_unused`] unless set otherwise
+const DEFAULT_COLLECT_BUDGET: usize = 64;
+
+/// a file loaded with [`AssetLibrary::load`]
+struct Watched {
+    /// when the file was last changed as of the last check
+    modified: Option<SystemTime>,
+    reload: Reload,
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+/// worker threads the loads of an [`AssetLibrary`] run on
+///
+/// the web has no threads so loads run as soon as their files are fetched instead
+struct LoadPool {
+    #[cfg(not(target_arch = "wasm32"))]
+    jobs: Mutex<std::sync::mpsc::Sender<LoadJob>>,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl LoadPool {
+    fn new(_workers: usize) -> Self {
+        Self {}
+    }
+
+    fn default_workers() -> usize {
+        0
+    }
+
+    fn run(&self, job: impl FnOnce() + Send + 'static) {
+        job();
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl LoadPool {
+    /// the workers stop once every library sharing the pool is dropped
+    fn new(workers: usize) -> Self {
+        let (sender, receiver) = std::sync::mpsc::channel::<LoadJob>();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        for i in 0..workers.max(1) {
+            let receiver = Arc::clone(&receiver);
+            let spawned = thread::Builder::new()
+                .name(format!("asset loader {i}"))
+                .spawn(move || {
+                    loop {
+                        // the lock is let go before the job runs so the other workers can take jobs
+                        let job = receiver.lock().recv();
+                        match job {
+                            Ok(job) => job(),
+                            Err(_) => break,
+                        }
+                    }
+                });
+            if let Err(err) = spawned {
+                log::warn!("failed to start asset loader thread: {err}");
+            }
+        }
+
+        Self {
+            jobs: Mutex::new(sender),
+        }
+    }
+
+    /// one worker for every core but the one the game loop runs on
+    fn default_workers() -> usize {
+        thread::available_parallelism()
+            .map(|cores| cores.get().saturating_sub(1))
+            .unwrap_or(1)
+            .max(1)
+    }
+
+    fn run(&self, job: impl FnOnce() + Send + 'static) {
+        if self.jobs.lock().send(Box::new(job)).is_err() {
+            log::warn!("asset loader threads are gone, the asset will never load");
+        }
+    }
+}
+
 /// A asset loader is a factory that is used to create Assets
 ///
 /// it can contains resources such as a render device that is needed during loading but not usage
@@ -57,6 +185,23 @@ pub trait AssetLoader: Any + Send + Sync + 'static {
 /// This loader can load an Asset from a file
 pub trait FileLoader: AssetLoader {
     fn load_path(&self, path: &Path, library: &AssetLibrary) -> Result<Self::Asset, LoadErr>;
+
+    /// load the asset from the contents of the file at `path`, used on the web where files are
+    /// fetched from the server instead of read from disk
+    ///
+    /// `path` tells formats apart. loaders that can only read files return an error by default
+    fn load_bytes(
+        &self,
+        path: &Path,
+        bytes: Vec<u8>,
+        library: &AssetLibrary,
+    ) -> Result<Self::Asset, LoadErr> {
+        let _ = (bytes, library);
+        Err(LoadErr::Import(format!(
+            "{} can only be loaded from a file",
+            path.display()
+        )))
+    }
 }
 
 /// An Asset is type of resource which is loaded at runtime and can be placed around a scene or
@@ -129,10 +274,13 @@ impl AssetId {
 
 /// stores a refrence to a asset within the [`AssetLibrary`]
 ///
-/// internally this is just a [`AssetId`] and a type
+/// internally this is just a [`AssetId`] and a type. the library counts the handles to each asset
+/// and frees assets that have gone without one for a while, see [`AssetLibrary::collect_unused`]
 #[derive(Debug)]
 pub struct AssetHandle<T: Asset> {
     pub id: AssetId,
+    /// every handle to an asset shares this with the library
+    refs: Arc<()>,
     _ty: PhantomData<T>,
 }
 
@@ -140,11 +288,51 @@ impl<T: Asset> Clone for AssetHandle<T> {
     fn clone(&self) -> Self {
         AssetHandle {
             id: self.id.clone(),
+            refs: Arc::clone(&self.refs),
             _ty: PhantomData,
         }
     }
 }
 
+/// how an asset is used as of the last time the collector checked it
+struct AssetUsage {
+    /// handles hold a clone, the library holds one more
+    refs: Arc<()>,
+    /// the collector clock when the asset was first seen without handles
+    unused_since: Option<f64>,
+    pinned: bool,
+    /// assets are only freed once they have finished loading
+    is_loading: fn(&(dyn Any + Send + Sync)) -> bool,
+}
+
+/// frees assets nothing holds a handle to a few at a time
+struct Collector {
+    usage: HashMap<AssetId, AssetUsage>,
+    /// the assets in the order they are checked, each check moves it to the back
+    order: VecDeque<AssetId>,
+    /// seconds passed to [`AssetLibrary::collect_unused`] so far
+    clock: f64,
+    free_after: Option<f32>,
+    budget: usize,
+}
+
+impl Default for Collector {
+    fn default() -> Self {
+        Self {
+            usage: HashMap::new(),
+            order: VecDeque::new(),
+            clock: 0.0,
+            free_after: Some(DEFAULT_FREE_UNUSED_AFTER),
+            budget: DEFAULT_COLLECT_BUDGET,
+        }
+    }
+}
+
+fn slot_is_loading<T: Asset>(slot: &(dyn Any + Send + Sync)) -> bool {
+    slot.downcast_ref::<Mutex<AssetSlot<T>>>()
+        .is_some_and(|slot| slot.lock().state.is_loading())
+}
+
 struct AssetSlot<T: Asset> {
     state: AssetState<T>,
     pending: Vec<Box<dyn FnOnce(&mut T) + Send>>,
@@ -171,21 +359,100 @@ impl<T: Asset> AssetSlot<T> {
 /// Assets by nature are shared data and should never be stored directly outside of the this library. to
 /// refrence an asset use [`AssetHandle`].
 ///
-/// Assets are loaded through their own [`AssetLoader`] on their own thread so multiple assets can
-/// be loaded in parallel without blocking the game loop as asset loading can be expensive.
+/// Assets are loaded through their own [`AssetLoader`] on a pool of worker threads so multiple
+/// assets can be loaded in parallel without blocking the game loop as asset loading can be
+/// expensive. handles are returned straight away and nodes using them draw once the asset is there,
+/// each finished asset is reported with an [`AssetLoaded`] event.
 ///
 /// assets can be added directly with [`Self::add`] loaded from a file with [`Self::load`] if the
 /// assetloader implements [`FileLoader`] or registered directly
+///
+/// assets that fail to load are reported with an [`AssetError`] and replaced with the placeholder
+/// set with [`Self::set_fallback`] so a broken path doesn't take the game down with it
+///
+/// files are only loaded once, loading the same path again returns a handle to the same asset.
+/// with [`Self::set_hot_reload`] on, files that change on disk are loaded again in place so every
+/// handle to them sees the new asset
+///
+/// assets without a handle for [`Self::set_free_unused_after`] seconds are freed by
+/// [`Self::collect_unused`] which the app calls once a frame. [`Self::pin`] keeps an asset around
+/// even without handles, for example a level that is loaded again often
 pub struct AssetLibrary {
     slots: Arc<Mutex<HashMap<AssetId, Arc<dyn Any + Send + Sync>>>>,
     loaders: Arc<RwLock<HashMap<TypeId, Arc<dyn Any + Send + Sync>>>>,
+    fallbacks: Arc<RwLock<HashMap<TypeId, Arc<dyn Any + Send + Sync>>>>,
+    errors: Arc<Mutex<Vec<AssetError>>>,
+    loaded: Arc<Mutex<Vec<AssetLoaded>>>,
+    pool: Arc<LoadPool>,
+    watched: Arc<Mutex<HashMap<PathBuf, Watched>>>,
+    hot_reload: Arc<AtomicBool>,
+    reloads: Arc<AtomicU64>,
+    waker: Arc<RwLock<Option<Waker>>>,
+    collector: Arc<Mutex<Collector>>,
+    collected: Arc<AtomicU64>,
+    loads: Arc<LoadCounts>,
 }
 
+/// loads run on the pool, counted for [`LoadBatch`]
+#[derive(Default)]
+struct LoadCounts {
+    started: AtomicU64,
+    finished: AtomicU64,
+}
+
+/// the loads started since [`AssetLibrary::begin_batch`], a loading screen shows its
+/// [`Self::progress`] until [`Self::is_done`]
+///
+/// assets loaded by other assets while they load, such as the textures of a model, are part of
+/// the batch too so the progress can slow down while it grows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadBatch {
+    started: u64,
+    finished: u64,
+}
+
+impl LoadBatch {
+    /// how many loads of the batch finished or failed and how many were started
+    pub fn counts(&self, assets: &AssetLibrary) -> (u64, u64) {
+        let total = assets.loads.started.load(Ordering::Acquire) - self.started;
+        // loads from before the batch can finish during it
+        let finished = assets.loads.finished.load(Ordering::Acquire) - self.finished;
+        (finished.min(total), total)
+    }
+
+    /// from `0.0` to `1.0`, `1.0` if nothing was loaded
+    pub fn progress(&self, assets: &AssetLibrary) -> f32 {
+        match self.counts(assets) {
+            (_, 0) => 1.0,
+            (finished, total) => finished as f32 / total as f32,
+        }
+    }
+
+    /// true once every load of the batch finished or failed
+    pub fn is_done(&self, assets: &AssetLibrary) -> bool {
+        let (finished, total) = self.counts(assets);
+        finished == total
+    }
+}
+
+type Waker = Arc<dyn Fn() + Send + Sync>;
+
 impl Clone for AssetLibrary {
     fn clone(&self) -> Self {
         Self {
             slots: Arc::clone(&self.slots),
             loaders: Arc::clone(&self.loaders),
+            fallbacks: Arc::clone(&self.fallbacks),
+            errors: Arc::clone(&self.errors),
+            loaded: Arc::clone(&self.loaded),
+            pool: Arc::clone(&self.pool),
+            watched: Arc::clone(&self.watched),
+            hot_reload: Arc::clone(&self.hot_reload),
+            reloads: Arc::clone(&self.reloads),
+            waker: Arc::clone(&self.waker),
+            collector: Arc::clone(&self.collector),
+            collected: Arc::clone(&self.collected),
+            loads: Arc::clone(&self.loads),
         }
     }
 }
@@ -253,9 +520,53 @@ impl Default for AssetLibrary {
 
 impl AssetLibrary {
     pub fn new() -> Self {
+        Self::with_workers(LoadPool::default_workers())
+    }
+
+    /// a library that loads on `workers` threads instead of one for every core but one
+    pub fn with_workers(workers: usize) -> Self {
         Self {
             slots: Arc::new(Mutex::new(HashMap::new())),
             loaders: Arc::new(RwLock::new(HashMap::new())),
+            fallbacks: Arc::new(RwLock::new(HashMap::new())),
+            errors: Arc::new(Mutex::new(Vec::new())),
+            loaded: Arc::new(Mutex::new(Vec::new())),
+            pool: Arc::new(LoadPool::new(workers)),
+            watched: Arc::new(Mutex::new(HashMap::new())),
+            hot_reload: Arc::new(AtomicBool::new(false)),
+            reloads: Arc::new(AtomicU64::new(0)),
+            waker: Arc::new(RwLock::new(None)),
+            collector: Arc::new(Mutex::new(Collector::default())),
+            collected: Arc::new(AtomicU64::new(0)),
+            loads: Arc::new(LoadCounts::default()),
+        }
+    }
+
+    /// a new handle to the asset `id`, counted by the collector
+    fn handle<T: Asset>(&self, id: AssetId) -> AssetHandle<T> {
+        let mut collector = self.collector.lock();
+        let refs = match collector.usage.get(&id) {
+            Some(usage) => Arc::clone(&usage.refs),
+            None => {
+                let refs = Arc::new(());
+                collector.usage.insert(
+                    id.clone(),
+                    AssetUsage {
+                        refs: Arc::clone(&refs),
+                        unused_since: None,
+                        pinned: false,
+                        is_loading: slot_is_loading::<T>,
+                    },
+                );
+                collector.order.push_back(id.clone());
+                refs
+            }
+        };
+
+        AssetHandle {
+            id,
+            refs,
+            _ty: PhantomData,
         }
     }
 
@@ -275,38 +586,35 @@ impl AssetLibrary {
         }
         let library = self.clone();
         let id_clone = id.clone();
-        thread::spawn(move || {
-            let inner_handle = loop {
-                match library.get_status(&source) {
-                    AssetStatus::Loaded(source) => match f(source) {
-                        Some(handle) => break handle,
-                        None => {
-                            state.lock().state = AssetState::Error(LoadErr::Missing);
-                            return;
-                        }
-                    },
-                    AssetStatus::Error(err) => {
-                        state.lock().state = AssetState::Error(err);
-                        return;
-                    }
-                    AssetStatus::Loading | AssetStatus::Borrowed => {
-                        thread::sleep(Duration::from_millis(4))
-                    }
-                    AssetStatus::Removed => {
-                        state.lock().state = AssetState::Removed;
-                        return;
+        // waits on the source so it isn't run on a loader
+        crate::platform::poll_until(move || {
+            let inner_handle = match library.get_status(&source) {
+                AssetStatus::Loaded(source) => match f(source) {
+                    Some(handle) => handle,
+                    None => {
+                        state.lock().state = AssetState::Error(LoadErr::Missing);
+                        return true;
                     }
+                },
+                AssetStatus::Error(err) => {
+                    state.lock().state = AssetState::Error(err);
+                    return true;
+                }
+                AssetStatus::Loading | AssetStatus::Borrowed => return false,
+                AssetStatus::Removed => {
+                    state.lock().state = AssetState::Removed;
+                    return true;
                 }
             };
 
             let inner_slots = library.slots.lock();
             let Some(inner_state_any) = inner_slots.get(&inner_handle.id).cloned() else {
-                return;
+                return true;
             };
             drop(inner_slots);
 
             let Ok(inner_slot) = inner_state_any.clone().downcast::<Mutex<AssetSlot<T>>>() else {
-                return;
+                return true;
             };
 
             // Merge any pending mutations queued on the outer handle into the
@@ -332,12 +640,10 @@ impl AssetLibrary {
             }
 
             let mut states = library.slots.lock();
-            states.insert(id_clone, inner_state_any);
+            states.insert(id_clone.clone(), inner_state_any);
+            true
         });
-        AssetHandle {
-            id,
-            _ty: PhantomData,
-        }
+        self.handle(id)
     }
 
     /// modify a asset througn a callback
@@ -371,7 +677,21 @@ impl AssetLibrary {
         }
     }
 
-    fn finish_slot<T: Asset>(slot: &Mutex<AssetSlot<T>>, result: Result<T, LoadErr>) {
+    fn finish_slot<T: Asset>(
+        &self,
+        id: &AssetId,
+        slot: &Mutex<AssetSlot<T>>,
+        result: Result<T, LoadErr>,
+    ) {
+        match &result {
+            Ok(_) => self.loaded.lock().push(AssetLoaded {
+                id: id.clone(),
+                asset_type: std::any::type_name::<T>(),
+                reloaded: false,
+            }),
+            Err(err) => self.report_error::<T>(id, err.clone()),
+        }
+
         let mut slot_lock = slot.lock();
         slot_lock.state = match result {
             Ok(asset) => AssetState::Loaded(Arc::new(RwLock::new(asset))),
@@ -387,6 +707,330 @@ impl AssetLibrary {
             }
         }
         // if it errored, pending mutations are just dropped — nothing to apply them to
+        drop(slot_lock);
+        // counted once the asset is in its slot so a finished batch can be used straight away
+        self.loads.finished.fetch_add(1, Ordering::Release);
+        self.wake();
+    }
+
+    /// swap the asset in `slot` for its reloaded version, the old asset is kept if it failed
+    fn finish_reload<T: Asset>(
+        &self,
+        id: &AssetId,
+        slot: &Mutex<AssetSlot<T>>,
+        result: Result<T, LoadErr>,
+    ) {
+        let asset = match result {
+            Ok(asset) => asset,
+            Err(err) => {
+                self.report_error::<T>(id, err);
+                self.wake();
+                return;
+            }
+        };
+
+        let mut slot_lock = slot.lock();
+        match &slot_lock.state {
+            // written into the same lock so handles mapped onto this asset see it too
+            AssetState::Loaded(lock) => *lock.write() = asset,
+            AssetState::Error(_) => {
+                slot_lock.state = AssetState::Loaded(Arc::new(RwLock::new(asset)));
+            }
+            // the first load hasn't finished and will pick up the change itself
+            AssetState::Loading | AssetState::Removed => return,
+        }
+        drop(slot_lock);
+
+        log::info!("reloaded {} {id:?}", std::any::type_name::<T>());
+        self.reloads.fetch_add(1, Ordering::Relaxed);
+        self.loaded.lock().push(AssetLoaded {
+            id: id.clone(),
+            asset_type: std::any::type_name::<T>(),
+            reloaded: true,
+        });
+        self.wake();
+    }
+
+    /// load files that changed on disk again while the game runs. off by default, the app turns
+    /// it on in debug builds
+    ///
+    /// files are checked a few times a second. a file that fails to load after changing keeps
+    /// the asset it had. scenes already merged from an asset aren't changed by a reload
+    pub fn set_hot_reload(&self, enabled: bool) {
+        if cfg!(target_arch = "wasm32") {
+            if enabled {
+                log::warn!("hot reloading isn't supported on the web");
+            }
+            return;
+        }
+
+        let was_enabled = self.hot_reload.swap(enabled, Ordering::Relaxed);
+        if !enabled || was_enabled {
+            return;
+        }
+
+        let library = self.clone();
+        let spawned = thread::Builder::new()
+            .name("asset hot reload".to_string())
+            .spawn(move || {
+                while library.hot_reload.load(Ordering::Relaxed) {
+                    thread::sleep(HOT_RELOAD_INTERVAL);
+                    library.reload_changed();
+                }
+            });
+        if let Err(err) = spawned {
+            log::warn!("failed to start asset hot reloading: {err}");
+        }
+    }
+
+    /// whether changed files are loaded again, see [`Self::set_hot_reload`]
+    pub fn hot_reload(&self) -> bool {
+        self.hot_reload.load(Ordering::Relaxed)
+    }
+
+    /// start loading every file that changed since it was loaded, returns how many did
+    ///
+    /// called for you while [`Self::set_hot_reload`] is on
+    pub fn reload_changed(&self) -> usize {
+        let changed: Vec<(PathBuf, Reload)> = self
+            .watched
+            .lock()
+            .iter_mut()
+            .filter_map(|(path, watched)| {
+                let modified = modified_time(path);
+                // a file that is gone is left alone until it is back
+                if modified.is_none() || modified == watched.modified {
+                    return None;
+                }
+                watched.modified = modified;
+                Some((path.clone(), Arc::clone(&watched.reload)))
+            })
+            .collect();
+
+        let count = changed.len();
+        for (path, reload) in changed {
+            let library = self.clone();
+            self.pool.run(move || reload(&library, &path));
+        }
+        count
+    }
+
+    /// how many times an asset has been reloaded, caches built from assets can be rebuilt when
+    /// it changes
+    pub fn reloads(&self) -> u64 {
+        self.reloads.load(Ordering::Relaxed)
+    }
+
+    /// free assets that have had no handles for `seconds`, or never with None
+    ///
+    /// Default: `Some(30.0)`
+    pub fn set_free_unused_after(&self, seconds: Option<f32>) {
+        self.collector.lock().free_after = seconds.map(|seconds| seconds.max(0.0));
+    }
+
+    /// how long assets go without handles before they are freed, see
+    /// [`Self::set_free_unused_after`]
+    pub fn free_unused_after(&self) -> Option<f32> {
+        self.collector.lock().free_after
+    }
+
+    /// how many assets [`Self::collect_unused`] checks each call. a bigger budget frees assets
+    /// closer to when they time out but costs more each frame
+    ///
+    /// Default: `64`
+    pub fn set_collect_budget(&self, assets: usize) {
+        self.collector.lock().budget = assets.max(1);
+    }
+
+    /// keep an asset even when it has no handles
+    pub fn pin<T: Asset>(&self, handle: &AssetHandle<T>) {
+        self.set_pinned(&handle.id, true);
+    }
+
+    /// let a pinned asset be freed again once it has no handles
+    pub fn unpin<T: Asset>(&self, handle: &AssetHandle<T>) {
+        self.set_pinned(&handle.id, false);
+    }
+
+    fn set_pinned(&self, id: &AssetId, pinned: bool) {
+        if let Some(usage) = self.collector.lock().usage.get_mut(id) {
+            usage.pinned = pinned;
+            usage.unused_since = None;
+        }
+    }
+
+    /// whether an asset was pinned with [`Self::pin`]
+    pub fn is_pinned<T: Asset>(&self, handle: &AssetHandle<T>) -> bool {
+        self.collector
+            .lock()
+            .usage
+            .get(&handle.id)
+            .is_some_and(|usage| usage.pinned)
+    }
+
+    /// how many handles to an asset exist
+    pub fn handle_count<T: Asset>(&self, handle: &AssetHandle<T>) -> usize {
+        Arc::strong_count(&handle.refs) - 1
+    }
+
+    /// check the next few assets and free the ones that have had no handles for long enough,
+    /// returns how many were freed
+    ///
+    /// `dt` is the seconds since the last call. the app calls this once a frame, see
+    /// [`Self::set_free_unused_after`] and [`Self::set_collect_budget`]. assets that are still
+    /// loading or [pinned](Self::pin) are never freed
+    pub fn collect_unused(&self, dt: f32) -> usize {
+        let mut freed = Vec::new();
+        {
+            // the slots are locked first so no handle can be made to an asset while it's freed
+            let mut slots = self.slots.lock();
+            let mut collector = self.collector.lock();
+            collector.clock += dt as f64;
+
+            let Some(free_after) = collector.free_after else {
+                return 0;
+            };
+            let clock = collector.clock;
+            let checks = collector.budget.min(collector.order.len());
+
+            for _ in 0..checks {
+                let Some(id) = collector.order.pop_front() else {
+                    break;
+                };
+                let Some(usage) = collector.usage.get_mut(&id) else {
+                    continue;
+                };
+                // removed from the library some other way
+                let Some(slot) = slots.get(&id) else {
+                    collector.usage.remove(&id);
+                    continue;
+                };
+
+                let unused = Arc::strong_count(&usage.refs) == 1
+                    && !usage.pinned
+                    && !(usage.is_loading)(slot.as_ref());
+                if !unused {
+                    usage.unused_since = None;
+                    collector.order.push_back(id);
+                    continue;
+                }
+
+                let since = *usage.unused_since.get_or_insert(clock);
+                if clock - since < free_after as f64 {
+                    collector.order.push_back(id);
+                    continue;
+                }
+
+                collector.usage.remove(&id);
+                if let Some(slot) = slots.remove(&id) {
+                    freed.push((id, slot));
+                }
+            }
+        }
+
+        let count = freed.len();
+        if count == 0 {
+            return 0;
+        }
+
+        let mut watched = self.watched.lock();
+        for (id, _) in &freed {
+            log::debug!("freed unused asset {id:?}");
+            if let AssetId::Path(path) = id {
+                watched.remove(path);
+            }
+        }
+        drop(watched);
+
+        self.collected.fetch_add(count as u64, Ordering::Relaxed);
+        // the assets themselves are dropped here with nothing locked
+        drop(freed);
+        count
+    }
+
+    /// how many assets [`Self::collect_unused`] has freed, caches built from assets can drop
+    /// what they hold when it changes
+    pub fn collected(&self) -> u64 {
+        self.collected.load(Ordering::Relaxed)
+    }
+
+    fn report_error<T: Asset>(&self, id: &AssetId, error: LoadErr) {
+        let asset_type = std::any::type_name::<T>();
+        match self.fallbacks.read().contains_key(&TypeId::of::<T>()) {
+            true => log::warn!("{asset_type} {id:?} failed to load, using a placeholder: {error}"),
+            false => log::warn!("{asset_type} {id:?} failed to load: {error}"),
+        }
+
+        self.errors.lock().push(AssetError {
+            id: id.clone(),
+            asset_type,
+            error,
+        });
+    }
+
+    /// start counting the loads from here on, see [`LoadBatch`]
+    pub fn begin_batch(&self) -> LoadBatch {
+        LoadBatch {
+            started: self.loads.started.load(Ordering::Acquire),
+            finished: self.loads.finished.load(Ordering::Acquire),
+        }
+    }
+
+    /// the load errors since the last call, the app emits each one as an [`AssetError`] event
+    pub fn take_errors(&self) -> Vec<AssetError> {
+        std::mem::take(&mut *self.errors.lock())
+    }
+
+    /// the assets that finished loading since the last call, the app emits each one as an
+    /// [`AssetLoaded`] event
+    pub fn take_loaded(&self) -> Vec<AssetLoaded> {
+        std::mem::take(&mut *self.loaded.lock())
+    }
+
+    /// true if assets finished loading or failed since the last [`Self::take_loaded`] or
+    /// [`Self::take_errors`]
+    pub fn has_results(&self) -> bool {
+        !self.loaded.lock().is_empty() || !self.errors.lock().is_empty()
+    }
+
+    /// call `waker` from the loading thread every time an asset finishes loading or fails, an
+    /// app that renders on demand wakes up with it to emit the asset's events
+    pub fn set_waker(&self, waker: impl Fn() + Send + Sync + 'static) {
+        *self.waker.write() = Some(Arc::new(waker));
+    }
+
+    fn wake(&self) {
+        // cloned out so the waker can use the library
+        let waker = self.waker.read().clone();
+        if let Some(waker) = waker {
+            waker();
+        }
+    }
+
+    /// set the placeholder [`Self::get`] returns for assets of this type that failed to load
+    ///
+    /// the placeholder is shared by every failed asset and can't be borrowed mutably
+    pub fn set_fallback<T: Asset>(&self, source: impl IntoAsset<T>) -> Result<(), LoadErr> {
+        let loader = self
+            .get_loader::<T>()
+            .expect("Loader not registered for this asset");
+        let asset = source.into_asset(&loader, self)?;
+
+        self.fallbacks
+            .write()
+            .insert(TypeId::of::<T>(), Arc::new(Arc::new(RwLock::new(asset))));
+        Ok(())
+    }
+
+    /// the placeholder for assets of this type that failed to load, see [`Self::set_fallback`]
+    pub fn fallback<T: Asset>(&self) -> Option<AssetRef<T>> {
+        let fallbacks = self.fallbacks.read();
+        let fallback = fallbacks
+            .get(&TypeId::of::<T>())?
+            .downcast_ref::<Arc<RwLock<T>>>()?;
+        Some(AssetRef {
+            guard: fallback.try_read_arc()?,
+        })
     }
 
     /// returns whether an asset is loaded or not
@@ -429,6 +1073,29 @@ impl AssetLibrary {
             .and_then(|l| l.clone().downcast::<T::Loader>().ok())
     }
 
+    /// call `f` with every loaded asset of type `T`, assets borrowed mutably are skipped
+    pub fn for_each<T: Asset>(&self, mut f: impl FnMut(&AssetId, &T)) {
+        // copied out so `f` can use the library
+        let slots: Vec<_> = self
+            .slots
+            .lock()
+            .iter()
+            .map(|(id, slot)| (id.clone(), Arc::clone(slot)))
+            .collect();
+
+        for (id, slot) in slots {
+            let Some(slot) = slot.downcast_ref::<Mutex<AssetSlot<T>>>() else {
+                continue;
+            };
+            let AssetState::Loaded(asset) = slot.lock().state.clone() else {
+                continue;
+            };
+            if let Some(asset) = asset.try_read() {
+                f(&id, &asset);
+            }
+        }
+    }
+
     /// register a already loaded asset
     pub fn register<T: Asset>(&self, asset: T) -> AssetHandle<T> {
         let id = AssetId::new_id();
@@ -436,10 +1103,7 @@ impl AssetLibrary {
         let mut slot_lock = self.slots.lock();
         slot_lock.insert(id.clone(), slot);
 
-        AssetHandle {
-            id,
-            _ty: PhantomData,
-        }
+        self.handle(id)
     }
 
     fn spawn_loader<T: Asset>(
@@ -451,9 +1115,21 @@ impl AssetLibrary {
     ) where
         T::Loader: FileLoader,
     {
-        thread::spawn(move || {
-            let result = loader.load_path(&path, &library);
-            Self::finish_slot(&slot, result);
+        self.loads.started.fetch_add(1, Ordering::Release);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        self.pool.run(move || {
+            let result = catch_load_panic(|| loader.load_path(&path, &library));
+            library.finish_slot(&AssetId::Path(path), &slot, result);
+        });
+
+        #[cfg(target_arch = "wasm32")]
+        wasm_bindgen_futures::spawn_local(async move {
+            let result = match crate::platform::web::fetch(&path).await {
+                Ok(bytes) => catch_load_panic(|| loader.load_bytes(&path, bytes, &library)),
+                Err(err) => Err(err),
+            };
+            library.finish_slot(&AssetId::Path(path), &slot, result);
         });
     }
 
@@ -466,11 +1142,9 @@ impl AssetLibrary {
         let id = AssetId::Path(path.clone());
 
         let mut slots = self.slots.lock();
+        // the slots stay locked so the collector can't free the asset before there is a handle
         if slots.contains_key(&id) {
-            return AssetHandle {
-                id,
-                _ty: PhantomData,
-            };
+            return self.handle(id);
         }
 
         let loader = self
@@ -479,14 +1153,29 @@ impl AssetLibrary {
 
         let slot = Arc::new(Mutex::new(AssetSlot::<T>::loading()));
         slots.insert(id.clone(), slot.clone());
+        let handle = self.handle(id);
         drop(slots);
 
+        // the library is passed in instead of captured so the watch list doesn't keep it alive
+        let reload: Reload = {
+            let loader = Arc::clone(&loader);
+            let slot = Arc::clone(&slot);
+            Arc::new(move |library: &AssetLibrary, path: &Path| {
+                let result = catch_load_panic(|| loader.load_path(path, library));
+                library.finish_reload(&AssetId::Path(path.to_path_buf()), &slot, result);
+            })
+        };
+        self.watched.lock().insert(
+            path.clone(),
+            Watched {
+                modified: modified_time(&path),
+                reload,
+            },
+        );
+
         self.spawn_loader::<T>(path.clone(), loader, slot, self.clone());
 
-        AssetHandle {
-            id,
-            _ty: PhantomData,
-        }
+        handle
     }
 
     /// get the status of the asset and ablity to check the current state the asset is in
@@ -515,7 +1204,8 @@ impl AssetLibrary {
 
     /// get a refrence to an assets
     ///
-    /// returns None if the [`AssetStatus`] is not [`AssetStatus::Loaded`]
+    /// returns the [`Self::fallback`] if the asset failed to load and None if the [`AssetStatus`]
+    /// is otherwise not [`AssetStatus::Loaded`]
     pub fn get<T: Asset>(&self, handle: &AssetHandle<T>) -> Option<AssetRef<T>> {
         // bunch of vars because I guess the val gets dropped mid chain
         let slots = self.slots.lock();
@@ -527,6 +1217,7 @@ impl AssetLibrary {
             AssetState::Loaded(lock) => Some(AssetRef {
                 guard: lock.try_read_arc()?,
             }),
+            AssetState::Error(_) => self.fallback(),
             _ => None,
         }
     }
@@ -550,14 +1241,16 @@ impl AssetLibrary {
 
     fn spawn_converter<T: Asset>(
         &self,
+        id: AssetId,
         source: impl IntoAsset<T>,
         loader: Arc<T::Loader>,
         slot: Arc<Mutex<AssetSlot<T>>>,
         library: AssetLibrary,
     ) {
-        thread::spawn(move || {
-            let result = source.into_asset(&loader, &library);
-            Self::finish_slot(&slot, result);
+        self.loads.started.fetch_add(1, Ordering::Release);
+        self.pool.run(move || {
+            let result = catch_load_panic(|| source.into_asset(&loader, &library));
+            library.finish_slot(&id, &slot, result);
         });
     }
 
@@ -575,18 +1268,24 @@ impl AssetLibrary {
             slots_lock.insert(id.clone(), slot.clone());
         }
 
-        self.spawn_converter(source, loader, slot, self.clone());
+        self.spawn_converter(id.clone(), source, loader, slot, self.clone());
 
-        AssetHandle {
-            id,
-            _ty: PhantomData,
-        }
+        self.handle(id)
     }
 
     /// remove an asset from the library
     pub fn remove<T: Asset>(&self, handle: AssetHandle<T>) -> Option<T> {
+        if let AssetId::Path(path) = &handle.id {
+            self.watched.lock().remove(path);
+        }
+
         let mut slots = self.slots.lock();
         let slot_any = slots.remove(&handle.id)?;
+        {
+            let mut collector = self.collector.lock();
+            collector.usage.remove(&handle.id);
+            collector.order.retain(|id| *id != handle.id);
+        }
 
         let slot_mutex = slot_any.downcast::<Mutex<AssetSlot<T>>>().ok()?;
 
@@ -600,3 +1299,249 @@ impl AssetLibrary {
         }
     }
 }
+
+/// a loader that panics fails its asset instead of taking a worker thread down with it
+fn catch_load_panic<T>(load: impl FnOnce() -> Result<T, LoadErr>) -> Result<T, LoadErr> {
+    std::panic::catch_unwind(AssertUnwindSafe(load)).unwrap_or_else(|panic| {
+        let message = panic
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        Err(LoadErr::Import(format!("loader panicked: {message}")))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Text(String);
+    impl Asset for Text {
+        type Loader = TextLoader;
+    }
+
+    struct TextLoader;
+    impl AssetLoader for TextLoader {
+        type Asset = Text;
+    }
+    impl FileLoader for TextLoader {
+        fn load_path(&self, path: &Path, _library: &AssetLibrary) -> Result<Text, LoadErr> {
+            std::fs::read_to_string(path)
+                .map(Text)
+                .map_err(|_| LoadErr::Missing)
+        }
+    }
+
+    fn wait_for<T: Asset>(assets: &AssetLibrary, handle: &AssetHandle<T>) {
+        while assets.is_loading(handle) {
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_missing_asset_uses_fallback() {
+        let assets = AssetLibrary::new();
+        assets.register_loader(TextLoader);
+
+        let missing = assets.load::<Text>("does/not/exist.txt");
+        wait_for(&assets, &missing);
+        assert!(assets.get(&missing).is_none());
+        assert!(matches!(
+            assets.get_status(&missing),
+            AssetStatus::Error(LoadErr::Missing)
+        ));
+
+        assets.set_fallback(Text("placeholder".into())).unwrap();
+        assert_eq!(assets.get(&missing).unwrap().0, "placeholder");
+        // the placeholder is shared so it can't be changed through a failed handle
+        assert!(assets.get_mut(&missing).is_none());
+
+        let errors = assets.take_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].id, missing.id);
+        assert!(errors[0].asset_type.ends_with("Text"));
+        assert!(assets.take_errors().is_empty());
+    }
+
+    struct Panics;
+    impl IntoAsset<Text> for Panics {
+        fn into_asset(
+            self,
+            _loader: &TextLoader,
+            _library: &AssetLibrary,
+        ) -> Result<Text, LoadErr> {
+            panic!("bad asset")
+        }
+    }
+
+    #[test]
+    fn test_loads_queue_on_workers_and_report_when_loaded() {
+        let assets = AssetLibrary::with_workers(1);
+        assets.register_loader(TextLoader);
+
+        let dir = std::env::temp_dir().join(format!("maple_asset_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let handles: Vec<AssetHandle<Text>> = (0..4)
+            .map(|i| {
+                let path = dir.join(format!("{i}.txt"));
+                std::fs::write(&path, format!("text {i}")).unwrap();
+                assets.load::<Text>(path)
+            })
+            .collect();
+        // a panicking loader fails its asset and leaves the worker for the rest
+        let broken = assets.add(Panics);
+        let added = assets.add(Text("added".into()));
+
+        for handle in handles.iter().chain([&broken, &added]) {
+            wait_for(&assets, handle);
+        }
+        for (i, handle) in handles.iter().enumerate() {
+            assert_eq!(assets.get(handle).unwrap().0, format!("text {i}"));
+        }
+        assert_eq!(assets.get(&added).unwrap().0, "added");
+        assert!(matches!(
+            assets.get_status(&broken),
+            AssetStatus::Error(LoadErr::Import(_))
+        ));
+
+        let loaded = assets.take_loaded();
+        assert_eq!(loaded.len(), 5);
+        assert!(loaded.iter().all(|loaded| loaded.id != broken.id));
+        assert!(loaded.iter().any(|loaded| loaded.id == added.id));
+        assert_eq!(assets.take_errors().len(), 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_batch_counts_loads_started_after_it() {
+        let assets = AssetLibrary::with_workers(1);
+        assets.register_loader(TextLoader);
+
+        let before = assets.load::<Text>("does/not/exist_before.txt");
+        wait_for(&assets, &before);
+
+        let batch = assets.begin_batch();
+        assert!(batch.is_done(&assets));
+        assert_eq!(batch.progress(&assets), 1.0);
+
+        let missing = assets.load::<Text>("does/not/exist.txt");
+        let added = assets.add(Text("added".into()));
+        wait_for(&assets, &missing);
+        wait_for(&assets, &added);
+
+        // failed loads count as finished so a loading screen never hangs on them
+        assert_eq!(batch.counts(&assets), (2, 2));
+        assert!(batch.is_done(&assets));
+        assert_eq!(batch.progress(&assets), 1.0);
+    }
+
+    #[test]
+    fn test_changed_files_reload_in_place() {
+        let assets = AssetLibrary::with_workers(1);
+        assets.register_loader(TextLoader);
+
+        let dir = std::env::temp_dir().join(format!("maple_reload_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("text.txt");
+        std::fs::write(&path, "old").unwrap();
+
+        let handle = assets.load::<Text>(&path);
+        // the same path is only loaded once
+        assert_eq!(assets.load::<Text>(&path).id, handle.id);
+        wait_for(&assets, &handle);
+        assert_eq!(assets.reload_changed(), 0);
+
+        std::fs::write(&path, "new").unwrap();
+        // file times can be too coarse to see a write straight after another
+        let later = SystemTime::now() + Duration::from_secs(5);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert_eq!(assets.reload_changed(), 1);
+        while assets.reloads() == 0 {
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(assets.get(&handle).unwrap().0, "new");
+
+        let loaded = assets.take_loaded();
+        assert_eq!(loaded.len(), 2);
+        assert!(!loaded[0].reloaded && loaded[1].reloaded);
+
+        // a deleted file is left alone with the asset it had
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(assets.reload_changed(), 0);
+        assert_eq!(assets.get(&handle).unwrap().0, "new");
+    }
+
+    #[test]
+    fn test_unused_assets_are_freed_after_a_while() {
+        let assets = AssetLibrary::with_workers(1);
+        assets.set_free_unused_after(Some(1.0));
+        assets.set_collect_budget(2);
+
+        let kept = assets.register(Text("kept".into()));
+        let pinned = assets.register(Text("pinned".into()));
+        let dropped = assets.register(Text("dropped".into()));
+        let dropped_id = dropped.id.clone();
+        assets.pin(&pinned);
+
+        let copy = kept.clone();
+        assert_eq!(assets.handle_count(&kept), 2);
+        drop(copy);
+        assert_eq!(assets.handle_count(&kept), 1);
+
+        let pinned_id = pinned.id.clone();
+        drop(pinned);
+        drop(dropped);
+
+        // the budget only reaches two assets a call so it takes a few to see all of them
+        for _ in 0..4 {
+            assert_eq!(assets.collect_unused(0.4), 0);
+        }
+        assert_eq!(assets.collect_unused(0.4), 1);
+        assert_eq!(assets.collected(), 1);
+        assert!(assets.get(&kept).is_some());
+        assert!(!assets.slots.lock().contains_key(&dropped_id));
+        assert!(assets.slots.lock().contains_key(&pinned_id));
+
+        // nothing is freed when collection is off
+        drop(kept);
+        assets.set_free_unused_after(None);
+        for _ in 0..8 {
+            assert_eq!(assets.collect_unused(10.0), 0);
+        }
+        assert_eq!(assets.slots.lock().len(), 2);
+    }
+
+    #[test]
+    fn test_for_each_visits_loaded_assets_of_a_type() {
+        let assets = AssetLibrary::with_workers(1);
+        assets.register_loader(TextLoader);
+        let first = assets.register(Text("first".into()));
+        let second = assets.register(Text("second".into()));
+        let _missing = assets.load::<Text>("does/not/exist.txt");
+
+        let mut seen = Vec::new();
+        assets.for_each::<Text>(|id, text| seen.push((id.clone(), text.0.clone())));
+        seen.sort();
+        assert_eq!(
+            seen,
+            vec![
+                (first.id.clone(), "first".into()),
+                (second.id, "second".into())
+            ]
+        );
+
+        // borrowed assets are skipped instead of waited on
+        let guard = assets.get_mut(&first).unwrap();
+        let mut count = 0;
+        assets.for_each::<Text>(|_, _| count += 1);
+        assert_eq!(count, 1);
+        drop(guard);
+    }
+}
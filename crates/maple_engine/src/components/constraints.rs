@@ -0,0 +1,167 @@
+//! constraints move or turn a node in world space after every update so common rigs like a camera
+//! following the player don't need an update handler per game
+//!
+//! constraints are kept by the scene, see [`crate::Scene::set_constraints`], and applied by
+//! [`crate::GameContext::apply_constraints`], which the app calls after update. parents are
+//! constrained before their children and each node runs its rules in this order: follow, axis
+//! lock, clamp and then look at.
+//!
+//! # Example
+//! ```rust
+//! # use maple_engine::prelude::*;
+//! # use glam::Vec3;
+//! # let ctx = GameContext::new();
+//! let player = ctx.scene.spawn(Empty::default()).id();
+//!
+//! ctx.scene.spawn(
+//!     Empty::builder()
+//!         .constraints(
+//!             TransformConstraints::new()
+//!                 .follow_smoothed(player, Vec3::new(0.0, 5.0, 10.0), 8.0)
+//!                 .look_at(player),
+//!         )
+//!         .build(),
+//! );
+//! ```
+
+use glam::{BVec3, Mat3, Quat, Vec3};
+
+use crate::{scene::NodeId, utils::smoothing::damp};
+
+/// a point a constraint moves towards or turns to face
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConstraintTarget {
+    /// the world position of a node, the constraint does nothing if the node is despawned
+    Node(NodeId),
+    /// a fixed point in world space
+    Point(Vec3),
+}
+
+impl From<NodeId> for ConstraintTarget {
+    fn from(value: NodeId) -> Self {
+        Self::Node(value)
+    }
+}
+
+impl From<Vec3> for ConstraintTarget {
+    fn from(value: Vec3) -> Self {
+        Self::Point(value)
+    }
+}
+
+/// moves a node to a target plus an offset
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Follow {
+    pub target: ConstraintTarget,
+    /// added to the target's position in world space
+    pub offset: Vec3,
+    /// how quickly the node catches up, 0 snaps to the target every frame. eased with
+    /// [`damp`] so it is the same at any frame rate
+    pub smoothing: f32,
+}
+
+/// rules applied to a node's world transform after every update
+///
+/// constraints reference nodes by id so they are not saved with scenes
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TransformConstraints {
+    pub follow: Option<Follow>,
+    /// world position axes held at the same axis of `locked_position`
+    pub lock_axes: BVec3,
+    pub locked_position: Vec3,
+    /// the corners of a box in world space the node is kept inside of
+    pub clamp: Option<(Vec3, Vec3)>,
+    /// a target the node's forward vector points at
+    pub look_at: Option<ConstraintTarget>,
+}
+
+impl TransformConstraints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// move to the target plus `offset` every frame
+    pub fn follow(mut self, target: impl Into<ConstraintTarget>, offset: Vec3) -> Self {
+        self.follow = Some(Follow {
+            target: target.into(),
+            offset,
+            smoothing: 0.0,
+        });
+        self
+    }
+
+    /// like [`Self::follow`] but eases towards the target, higher smoothing catches up faster
+    pub fn follow_smoothed(
+        mut self,
+        target: impl Into<ConstraintTarget>,
+        offset: Vec3,
+        smoothing: f32,
+    ) -> Self {
+        self.follow = Some(Follow {
+            target: target.into(),
+            offset,
+            smoothing: smoothing.max(0.0),
+        });
+        self
+    }
+
+    /// keep the world position on the `axes` set to the same axis of `position`
+    pub fn lock_axes(mut self, axes: BVec3, position: Vec3) -> Self {
+        self.lock_axes = axes;
+        self.locked_position = position;
+        self
+    }
+
+    /// keep the world position inside the box between `min` and `max`
+    pub fn clamp(mut self, min: Vec3, max: Vec3) -> Self {
+        self.clamp = Some((min.min(max), min.max(max)));
+        self
+    }
+
+    /// turn the node to face the target every frame
+    pub fn look_at(mut self, target: impl Into<ConstraintTarget>) -> Self {
+        self.look_at = Some(target.into());
+        self
+    }
+
+    /// the world position after following, locking and clamping
+    ///
+    /// `follow_target` is where the follow target is this frame or `None` if it is gone
+    pub(crate) fn constrain_position(
+        &self,
+        position: Vec3,
+        follow_target: Option<Vec3>,
+        dt: f32,
+    ) -> Vec3 {
+        let mut position = position;
+
+        if let (Some(follow), Some(target)) = (self.follow, follow_target) {
+            let goal = target + follow.offset;
+            position = match follow.smoothing > 0.0 {
+                true => damp(position, goal, follow.smoothing, dt),
+                false => goal,
+            };
+        }
+
+        position = Vec3::select(self.lock_axes, self.locked_position, position);
+
+        if let Some((min, max)) = self.clamp {
+            position = position.clamp(min, max);
+        }
+
+        position
+    }
+}
+
+/// the world rotation of something at `position` with its forward vector pointing at `target`
+pub(crate) fn look_rotation(position: Vec3, target: Vec3) -> Option<Quat> {
+    let forward = (target - position).try_normalize()?;
+    let up = match forward.dot(Vec3::Y).abs() > 0.999 {
+        true => Vec3::Z,
+        false => Vec3::Y,
+    };
+
+    let right = forward.cross(up).normalize();
+    let up = right.cross(forward);
+    Some(Quat::from_mat3(&Mat3::from_cols(right, up, -forward)).normalize())
+}
@@ -9,10 +9,12 @@ use crate::asset::AssetLibrary;
 use crate::context::{GameContext, Res, ResMut, Resource};
 use crate::nodes::Node;
 use crate::platform::SendSync;
-use crate::scene::{NodeHandle, NodeId, NodeReadGuard, NodeWriteGuard};
+use crate::scene::{NodeHandle, NodeId, NodeReadGuard, NodeWriteGuard, SceneCommands};
+use crate::tasks::{TaskCtx, TaskHandle};
 use std::any::{Any, TypeId};
 use std::collections::HashMap;
 use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
 pub trait EventLabel: Any {}
@@ -23,7 +25,10 @@ impl EventLabel for Ready {}
 
 #[derive(Clone, Copy, Debug)]
 pub struct Update {
+    /// seconds of game time since the last update, scaled by [`crate::GameContext::time_scale`]
     pub dt: f32,
+    /// real seconds since the last update, use this for things that should ignore pausing
+    pub unscaled_dt: f32,
 }
 impl EventLabel for Update {}
 
@@ -31,10 +36,76 @@ impl EventLabel for Update {}
 pub struct FixedUpdate;
 impl EventLabel for FixedUpdate {}
 
+/// emitted when the app goes into the background, mobile platforms take the window away so
+/// nothing is updated or drawn until [`Resumed`]. save anything the player would lose here since
+/// the os may close the app without another event
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub struct Suspended;
+impl EventLabel for Suspended {}
+
+/// emitted when the app comes back from the background after [`Suspended`]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub struct Resumed;
+impl EventLabel for Resumed {}
+
+/// emitted to a node after it is added to the scene, before its [`Ready`] event
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub struct OnAdded;
+impl EventLabel for OnAdded {}
+
+/// emitted to a node after it was despawned.
+///
+/// the node has already been removed from the scene so [`EventCtx::scene`] no longer contains
+/// it but the node and its removed children can still be accessed through the ctx
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub struct OnRemoved;
+impl EventLabel for OnRemoved {}
+
+/// emitted to a node after a child is spawned, merged or reparented under it, after the child's
+/// [`OnAdded`]
+///
+/// ```rust,ignore
+/// vehicle.on::<OnChildAdded>(|ctx| {
+///     if ctx.scene().get::<Wheel>(ctx.child).is_some() {
+///         ctx.node_mut().wheels.push(ctx.child);
+///     }
+/// });
+/// ```
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+pub struct OnChildAdded {
+    pub child: NodeId,
+    /// the child's path for [`Scene::get_id_by_path`], `None` if it or an ancestor has no name
+    pub path: Option<String>,
+}
+impl EventLabel for OnChildAdded {}
+
+/// emitted to a node after a child was despawned or reparented away from it
+///
+/// a despawned child is no longer in the scene, `path` is where it was
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+pub struct OnChildRemoved {
+    pub child: NodeId,
+    pub path: Option<String>,
+}
+impl EventLabel for OnChildRemoved {}
+
+/// emitted to a node when it or one of its parents is enabled with [`Scene::set_enabled`] or
+/// [`Node::set_enabled`]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub struct OnEnabled;
+impl EventLabel for OnEnabled {}
+
+/// emitted to a node when it or one of its parents is disabled with [`Scene::set_enabled`] or
+/// [`Node::set_enabled`]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub struct OnDisabled;
+impl EventLabel for OnDisabled {}
+
 pub struct EventCtx<'a, E, N: Node> {
     node: NodeHandle<'a, N>,
     pub game: &'a GameContext,
     pub event: &'a E,
+    consumed: &'a AtomicBool,
 }
 
 impl<'a, E, N: Node> Deref for EventCtx<'a, E, N> {
@@ -61,6 +132,12 @@ impl<'a, E, N: Node> EventCtx<'a, E, N> {
         &self.game.scene
     }
 
+    /// structural changes to the scene such as spawning or despawning nodes that will be applied
+    /// once the event has finished
+    pub fn commands(&self) -> &SceneCommands {
+        self.game.scene.commands()
+    }
+
     pub fn node_ref(&self) -> NodeReadGuard<N> {
         self.node.read()
     }
@@ -105,28 +182,47 @@ impl<'a, E, N: Node> EventCtx<'a, E, N> {
     pub fn node_handle(&self) -> &'a NodeHandle<'_, N> {
         &self.node
     }
-}
 
-#[cfg(not(target_arch = "wasm32"))]
-type ErasedEventCallback = Box<dyn FnMut(&Scene, NodeId, &GameContext, &dyn Any) + Send + Sync>;
-#[cfg(target_arch = "wasm32")]
-type ErasedEventCallback = Box<dyn FnMut(&Scene, NodeId, &GameContext, &dyn Any)>;
+    /// stop a bubbling event from reaching the ancestors of this node. see [`Scene::bubble`]
+    ///
+    /// this has no effect on events that are broadcast or emitted to a single node
+    pub fn consume(&self) {
+        self.consumed.store(true, Ordering::Release);
+    }
 
-#[derive(Default)]
-pub struct EventReceiver {
-    callbacks: HashMap<TypeId, Vec<Arc<Mutex<ErasedEventCallback>>>>,
+    /// true if [`EventCtx::consume`] was called by this or another handler on the same node
+    pub fn is_consumed(&self) -> bool {
+        self.consumed.load(Ordering::Acquire)
+    }
+
+    /// start an async task on this node that is dropped with it, see [`crate::tasks`]
+    ///
+    /// the task first runs after update this frame
+    pub fn spawn<F, Fut>(&self, f: F) -> TaskHandle
+    where
+        F: FnOnce(TaskCtx) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.game.tasks.spawn(Some(self.node.id()), f)
+    }
 }
 
-impl Clone for EventReceiver {
-    fn clone(&self) -> Self {
-        let callbacks = self
-            .callbacks
-            .iter()
-            .map(|(id, cbs)| (*id, cbs.iter().map(Arc::clone).collect()))
-            .collect();
+type ErasedEventCallback =
+    Box<dyn FnMut(&Scene, NodeId, &GameContext, &dyn Any, &AtomicBool) + Send + Sync>;
 
-        Self { callbacks }
-    }
+#[derive(Clone)]
+struct Handler {
+    priority: i32,
+    callback: Arc<Mutex<ErasedEventCallback>>,
+}
+
+/// the callbacks of a node
+///
+/// callbacks for the same event run from the highest to the lowest priority and in the order
+/// they were registered when the priority is the same
+#[derive(Default, Clone)]
+pub struct EventReceiver {
+    callbacks: HashMap<TypeId, Vec<Handler>>,
 }
 
 impl EventReceiver {
@@ -138,7 +234,18 @@ impl EventReceiver {
     }
 
     /// Register a callback for event `E` on node type `N`
-    pub fn on<E, N, F>(&mut self, mut f: F)
+    pub fn on<E, N, F>(&mut self, f: F)
+    where
+        E: EventLabel + 'static,
+        N: Node + 'static,
+        F: for<'a> FnMut(EventCtx<'a, E, N>) + SendSync + 'static,
+    {
+        self.on_with_priority(0, f);
+    }
+
+    /// Register a callback for event `E` on node type `N` that runs before callbacks with a lower
+    /// priority. [`EventReceiver::on`] uses a priority of 0
+    pub fn on_with_priority<E, N, F>(&mut self, priority: i32, mut f: F)
     where
         E: EventLabel + 'static,
         N: Node + 'static,
@@ -147,7 +254,7 @@ impl EventReceiver {
         let event_id = TypeId::of::<E>();
 
         let callback: ErasedEventCallback = Box::new(
-            move |scene, node_id, game: &GameContext, event_data: &dyn Any| {
+            move |scene, node_id, game: &GameContext, event_data: &dyn Any, consumed| {
                 // Downcast event
                 let event = match event_data.downcast_ref::<E>() {
                     Some(e) => e,
@@ -162,16 +269,66 @@ impl EventReceiver {
                     node: handle,
                     game,
                     event,
+                    consumed,
                 };
 
                 f(ctx);
             },
         );
 
+        self.insert(event_id, priority, callback);
+    }
+
+    /// Register an async callback for event `E` on node type `N` that starts a task on the node
+    /// each time the event is triggered, see [`crate::tasks`]
+    ///
+    /// the task first runs after update in the frame the event was triggered
+    pub fn on_async<E, N, F, Fut>(&mut self, mut f: F)
+    where
+        E: EventLabel + 'static,
+        N: Node + 'static,
+        F: FnMut(TaskCtx) -> Fut + SendSync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.on::<E, N, _>(move |ctx| {
+            ctx.spawn(&mut f);
+        });
+    }
+
+    /// Register a callback for event `E` that runs for any node type
+    pub(crate) fn on_any<E, F>(&mut self, priority: i32, mut f: F)
+    where
+        E: EventLabel + 'static,
+        F: FnMut(&E) + SendSync + 'static,
+    {
+        let callback: ErasedEventCallback = Box::new(move |_, _, _, event_data: &dyn Any, _| {
+            if let Some(event) = event_data.downcast_ref::<E>() {
+                f(event);
+            }
+        });
+
+        self.insert(TypeId::of::<E>(), priority, callback);
+    }
+
+    fn insert(&mut self, event_id: TypeId, priority: i32, callback: ErasedEventCallback) {
+        let handlers = self.callbacks.entry(event_id).or_default();
+        let index = handlers.partition_point(|handler| handler.priority >= priority);
+        handlers.insert(
+            index,
+            Handler {
+                priority,
+                callback: Arc::new(Mutex::new(callback)),
+            },
+        );
+    }
+
+    /// the priorities of the callbacks registered for event `E` from highest to lowest
+    pub fn priorities<E: EventLabel>(&self) -> impl Iterator<Item = i32> + '_ {
         self.callbacks
-            .entry(event_id)
-            .or_default()
-            .push(Arc::new(Mutex::new(callback)));
+            .get(&TypeId::of::<E>())
+            .into_iter()
+            .flatten()
+            .map(|handler| handler.priority)
     }
 
     /// Trigger an event for a specific node
@@ -182,15 +339,55 @@ impl EventReceiver {
         node_id: NodeId,
         game: &GameContext,
     ) {
-        let event_id = TypeId::of::<E>();
+        self.trigger_consumable(event, scene, node_id, game);
+    }
 
-        if let Some(callbacks) = self.callbacks.get(&event_id) {
-            for callback in callbacks {
-                if let Ok(mut callback) = callback.lock() {
-                    callback(scene, node_id, game, event as &dyn Any);
+    /// Trigger an event for a specific node and return true if a callback consumed it
+    pub fn trigger_consumable<E: EventLabel>(
+        &self,
+        event: &E,
+        scene: &Scene,
+        node_id: NodeId,
+        game: &GameContext,
+    ) -> bool {
+        self.trigger_filtered(event, scene, node_id, game, None)
+    }
+
+    /// Trigger only the callbacks with the given priority
+    pub fn trigger_priority<E: EventLabel>(
+        &self,
+        event: &E,
+        scene: &Scene,
+        node_id: NodeId,
+        game: &GameContext,
+        priority: i32,
+    ) {
+        self.trigger_filtered(event, scene, node_id, game, Some(priority));
+    }
+
+    fn trigger_filtered<E: EventLabel>(
+        &self,
+        event: &E,
+        scene: &Scene,
+        node_id: NodeId,
+        game: &GameContext,
+        priority: Option<i32>,
+    ) -> bool {
+        let event_id = TypeId::of::<E>();
+        let consumed = AtomicBool::new(false);
+
+        if let Some(handlers) = self.callbacks.get(&event_id) {
+            let handlers = handlers
+                .iter()
+                .filter(|handler| priority.is_none_or(|priority| handler.priority == priority));
+            for handler in handlers {
+                if let Ok(mut callback) = handler.callback.lock() {
+                    callback(scene, node_id, game, event as &dyn Any, &consumed);
                 }
             }
         }
+
+        consumed.into_inner()
     }
 }
 
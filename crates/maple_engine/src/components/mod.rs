@@ -1,12 +1,16 @@
 //! Contains components that nodes use such as their transform or Mesh.
 
+pub mod constraints;
 mod event_reciever;
 // pub mod mesh;
 pub mod node_transform;
+pub mod tween;
 
 // re-export components
+pub use constraints::{ConstraintTarget, Follow, TransformConstraints};
 pub use event_reciever::{EventReceiver, FixedUpdate, Ready, Update};
 // pub use mesh::Mesh;
 pub use node_transform::NodeTransform;
+pub use tween::{Ease, Repeat, TransformTween, TweenFinished};
 
 pub use event_reciever::*;
@@ -1,9 +1,13 @@
 //! represents the current transform of a given node. each node has a transform that can be manipulated to move, rotate, and scale the node in 3D space.
 
 use glam::{Mat4, Quat, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::{components::TransformConstraints, scene::NodeId, utils::aabb::AABB};
 
 /// Represents a nodes transform data in 3d space with position, rotation, and scale as well as a precalculated model matrix.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(from = "TransformData", into = "TransformData")]
 pub struct NodeTransform {
     /// position in 3D space with y as up.
     pub position: Vec3,
@@ -15,19 +19,96 @@ pub struct NodeTransform {
     pub matrix: Mat4,
     /// readonly field that stores the nodes position in world space
     world_transform: WorldTransform,
+    /// the world transform of the parent the world transform was last computed from
+    parent_world: WorldTransform,
+    /// the local position, rotation and scale the world transform was last computed from
+    synced: Option<(Vec3, Quat, Vec3)>,
+    /// the node the scene last synced this transform as, a transform copied from another node
+    /// keeps that node's world transform until it is synced again
+    synced_as: Option<NodeId>,
+    /// if true the node is drawn between where it was before and after the last fixed update
+    interpolated: bool,
+    /// the world transform before the last fixed update
+    previous_world: Option<WorldTransform>,
+    /// the world transform the node is drawn at if it or a parent is interpolated
+    render_world: Option<WorldTransform>,
+    /// a change to the enabled flag the scene picks up when the node is spawned or synced, the
+    /// flag itself is kept by the scene. see [`crate::nodes::Node::set_enabled`]
+    pub(crate) enabled_request: Option<bool>,
+    /// like `enabled_request` for the visible flag, see [`crate::nodes::Node::set_visible`]
+    pub(crate) visible_request: Option<bool>,
+    /// readonly field that stores if the node and all of its parents are visible
+    visible_in_tree: bool,
+    /// constraints set by a builder, the scene takes them when the node is spawned so copying a
+    /// transform doesn't copy them. see [`crate::Scene::set_constraints`]
+    pub(crate) constraints: Option<TransformConstraints>,
+    /// the bounding box of the node itself in local space
+    local_aabb: Option<AABB>,
+    /// readonly field that stores the bounding box of the node and its descendants in world space
+    world_aabb: Option<AABB>,
+    /// true if the local bounding box or the children changed since the world box was merged
+    aabb_dirty: bool,
+}
+
+/// the serialized form of a [`NodeTransform`]. the matrices are derived so they aren't stored
+#[derive(Serialize, Deserialize)]
+struct TransformData {
+    position: Vec3,
+    rotation: Quat,
+    scale: Vec3,
+    #[serde(default = "default_flag", skip_serializing_if = "is_set")]
+    enabled: bool,
+    #[serde(default = "default_flag", skip_serializing_if = "is_set")]
+    visible: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    interpolated: bool,
+}
+
+fn default_flag() -> bool {
+    true
+}
+
+fn is_set(flag: &bool) -> bool {
+    *flag
+}
+
+impl From<TransformData> for NodeTransform {
+    fn from(value: TransformData) -> Self {
+        let mut transform = NodeTransform::new(value.position, value.rotation, value.scale);
+        transform.enabled_request = (!value.enabled).then_some(false);
+        transform.visible_request = (!value.visible).then_some(false);
+        transform.interpolated = value.interpolated;
+        transform
+    }
+}
+
+impl From<NodeTransform> for TransformData {
+    fn from(value: NodeTransform) -> Self {
+        Self {
+            position: value.position,
+            rotation: value.rotation,
+            scale: value.scale,
+            enabled: value.enabled_request.unwrap_or(true),
+            visible: value.visible_request.unwrap_or(true),
+            interpolated: value.interpolated,
+        }
+    }
 }
 
 /// represents a position in worldspace
+///
+/// world transforms are computed by the scene and are read only, move a node by changing its
+/// [`NodeTransform`]
 #[derive(Clone, Copy)]
 pub struct WorldTransform {
     /// position in worldspace
-    pub position: Vec3,
+    position: Vec3,
     /// rotation in worldspace
-    pub rotation: Quat,
+    rotation: Quat,
     /// scale in worldspace
-    pub scale: Vec3,
+    scale: Vec3,
     /// matrix of the world position
-    pub matrix: Mat4,
+    matrix: Mat4,
 }
 
 impl Default for WorldTransform {
@@ -80,6 +161,53 @@ impl std::ops::Add for WorldTransform {
 }
 
 impl WorldTransform {
+    /// position in worldspace
+    pub fn position(&self) -> Vec3 {
+        self.position
+    }
+
+    /// rotation in worldspace
+    pub fn rotation(&self) -> Quat {
+        self.rotation
+    }
+
+    /// scale in worldspace
+    pub fn scale(&self) -> Vec3 {
+        self.scale
+    }
+
+    /// the model matrix of the world transform
+    pub fn matrix(&self) -> &Mat4 {
+        &self.matrix
+    }
+
+    /// the direction the transform faces in worldspace
+    pub fn forward(&self) -> Vec3 {
+        self.rotation * Vec3::NEG_Z
+    }
+
+    /// converts a point relative to this transform into worldspace
+    pub fn transform_point(&self, point: Vec3) -> Vec3 {
+        self.position + (self.rotation * point) * self.scale
+    }
+
+    /// converts a point in worldspace into one relative to this transform
+    pub fn inverse_transform_point(&self, point: Vec3) -> Vec3 {
+        self.rotation.inverse() * ((point - self.position) * safe_recip(self.scale))
+    }
+
+    /// blend between two world transforms
+    pub fn lerp(a: &Self, b: &Self, t: f32) -> Self {
+        let mut out = Self {
+            position: a.position.lerp(b.position, t),
+            rotation: a.rotation.slerp(b.rotation, t),
+            scale: a.scale.lerp(b.scale, t),
+            matrix: Mat4::IDENTITY,
+        };
+        out.update_matrix();
+        out
+    }
+
     /// updates the model matrix based on the position, rotation, and scale.
     fn update_matrix(&mut self) {
         self.matrix = Mat4::from_scale_rotation_translation(
@@ -90,6 +218,11 @@ impl WorldTransform {
     }
 }
 
+/// `1 / scale` with zero scaled axes left at zero instead of becoming infinite
+fn safe_recip(scale: Vec3) -> Vec3 {
+    Vec3::select(scale.cmpeq(Vec3::ZERO), Vec3::ZERO, scale.recip())
+}
+
 impl Default for NodeTransform {
     /// the default constructor for NodeTransform sets the position to (0, 0, 0), rotation to identity, scale to (1, 1, 1), and matrix to identity.
     fn default() -> Self {
@@ -99,6 +232,19 @@ impl Default for NodeTransform {
             scale: Vec3::ONE,
             matrix: Mat4::IDENTITY,
             world_transform: WorldTransform::default(),
+            parent_world: WorldTransform::default(),
+            synced: None,
+            synced_as: None,
+            interpolated: false,
+            previous_world: None,
+            render_world: None,
+            enabled_request: None,
+            visible_request: None,
+            visible_in_tree: true,
+            constraints: None,
+            local_aabb: None,
+            world_aabb: None,
+            aabb_dirty: false,
         };
         transform.update_matrix();
         transform
@@ -149,6 +295,19 @@ impl NodeTransform {
             scale: scale.into(),
             matrix: Mat4::IDENTITY,
             world_transform: WorldTransform::default(),
+            parent_world: WorldTransform::default(),
+            synced: None,
+            synced_as: None,
+            interpolated: false,
+            previous_world: None,
+            render_world: None,
+            enabled_request: None,
+            visible_request: None,
+            visible_in_tree: true,
+            constraints: None,
+            local_aabb: None,
+            world_aabb: None,
+            aabb_dirty: false,
         };
         transform.update_matrix();
         transform
@@ -165,7 +324,8 @@ impl NodeTransform {
 
     /// returns the world space of the object
     ///
-    /// this is not meant to be modified and will not update when you modify localspace
+    /// this is not meant to be modified and will not update when you modify localspace. it is
+    /// cached and only recomputed when the scene syncs for nodes whose transform or parent changed
     pub fn world_space(&self) -> &WorldTransform {
         &self.world_transform
     }
@@ -185,7 +345,170 @@ impl NodeTransform {
         };
 
         self.world_transform = parent_space + local_world_space;
+        self.parent_world = parent_space;
         self.world_transform.update_matrix();
+        self.synced = Some((self.position, self.rotation, self.scale));
+    }
+
+    /// true if the local transform changed since the scene last computed the world transform
+    pub fn is_dirty(&self) -> bool {
+        self.synced != Some((self.position, self.rotation, self.scale))
+    }
+
+    /// replace the parent transform the world space getters and setters go through, the scene
+    /// sets this when it knows the parent moved since the last sync
+    pub(crate) fn set_parent_world(&mut self, parent: WorldTransform) {
+        self.parent_world = parent;
+    }
+
+    /// true if the scene synced this transform as the node `id` and it hasn't changed since
+    pub(crate) fn is_synced_as(&self, id: NodeId) -> bool {
+        self.synced_as == Some(id) && !self.is_dirty()
+    }
+
+    /// forces the world transform to be recomputed the next time the scene syncs
+    pub(crate) fn mark_dirty(&mut self) {
+        self.synced = None;
+    }
+
+    /// the world transform the node should be drawn at
+    ///
+    /// this is the same as [`Self::world_space`] unless the node or one of its parents is
+    /// [interpolated](Self::set_interpolated)
+    pub fn render_space(&self) -> &WorldTransform {
+        self.render_world.as_ref().unwrap_or(&self.world_transform)
+    }
+
+    /// draw the node between where it was before and after the last fixed update
+    ///
+    /// nodes that are moved in fixed update such as rigid bodies stutter when the frame rate
+    /// doesn't match the fixed update rate. interpolated nodes are drawn one fixed update behind
+    /// but move smoothly. children of interpolated nodes are drawn relative to them
+    pub fn set_interpolated(&mut self, interpolated: bool) -> &mut Self {
+        self.interpolated = interpolated;
+        self.previous_world = None;
+        self
+    }
+
+    pub fn is_interpolated(&self) -> bool {
+        self.interpolated
+    }
+
+    /// draw the node where it is until the next fixed update, call this after teleporting an
+    /// interpolated node so it doesn't slide to its new position
+    pub fn reset_interpolation(&mut self) -> &mut Self {
+        self.previous_world = None;
+        self
+    }
+
+    /// remembers the current world transform before a fixed update moves the node
+    pub(crate) fn store_previous_world(&mut self) {
+        if self.interpolated {
+            self.previous_world = Some(self.world_transform);
+        }
+    }
+
+    /// updates the transform the node is drawn at and returns it if children need to be drawn
+    /// relative to it
+    pub(crate) fn sync_render_space(
+        &mut self,
+        parent_render: Option<WorldTransform>,
+        alpha: f32,
+    ) -> Option<WorldTransform> {
+        self.render_world = match (self.interpolated, parent_render) {
+            (true, _) => Some(WorldTransform::lerp(
+                self.previous_world
+                    .as_ref()
+                    .unwrap_or(&self.world_transform),
+                &self.world_transform,
+                alpha,
+            )),
+            (false, Some(parent)) => Some(parent + WorldTransform::from(*self)),
+            (false, None) => None,
+        };
+        self.render_world
+    }
+
+    /// recompute the world transform of the node `id` only if this transform or its parent
+    /// changed, or if it was last synced as a different node
+    ///
+    /// returns true if the world transform was recomputed so children know to recompute theirs
+    pub(crate) fn sync_world_space(
+        &mut self,
+        id: NodeId,
+        parent_space: &WorldTransform,
+        parent_changed: bool,
+    ) -> bool {
+        if !parent_changed && self.is_synced_as(id) {
+            return false;
+        }
+
+        self.get_world_space(*parent_space);
+        self.synced_as = Some(id);
+        true
+    }
+
+    /// returns true if the node and all of its parents are visible
+    ///
+    /// like [`Self::world_space`] this is updated when the scene syncs world transforms, see
+    /// [`crate::Scene::is_visible`]
+    pub fn is_visible_in_tree(&self) -> bool {
+        self.visible_in_tree
+    }
+
+    /// the bounding box of the node itself in local space, see [`Self::set_local_aabb`]
+    pub fn local_aabb(&self) -> Option<&AABB> {
+        self.local_aabb.as_ref()
+    }
+
+    /// set the bounding box of the node's own contents in local space
+    ///
+    /// models set this from their meshes. nodes without one are only as big as their children
+    pub fn set_local_aabb(&mut self, aabb: Option<AABB>) -> &mut Self {
+        if self.local_aabb != aabb {
+            self.local_aabb = aabb;
+            self.aabb_dirty = true;
+        }
+        self
+    }
+
+    /// the bounding box of the node and all of its descendants in world space
+    ///
+    /// like [`Self::world_space`] this is updated when the scene syncs world transforms. `None`
+    /// if neither the node nor its descendants have a bounding box
+    pub fn world_aabb(&self) -> Option<&AABB> {
+        self.world_aabb.as_ref()
+    }
+
+    /// forces the world bounding box to be merged again the next time the scene syncs
+    pub(crate) fn mark_aabb_dirty(&mut self) {
+        self.aabb_dirty = true;
+    }
+
+    /// true if the world bounding box needs to be merged again, clears the flag
+    pub(crate) fn take_aabb_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.aabb_dirty)
+    }
+
+    /// merge the node's own bounding box with its children's, returns true if the box changed
+    pub(crate) fn sync_world_aabb(&mut self, children: Option<AABB>) -> bool {
+        let own = self
+            .local_aabb
+            .map(|aabb| aabb.transform(self.world_transform.matrix()));
+
+        let merged = match (own, children) {
+            (Some(own), Some(children)) => Some(own.merge(&children)),
+            (own, children) => own.or(children),
+        };
+
+        let changed = merged != self.world_aabb;
+        self.world_aabb = merged;
+        changed
+    }
+
+    /// update the cached visibility from the scene
+    pub(crate) fn sync_visibility(&mut self, visible_in_tree: bool) {
+        self.visible_in_tree = visible_in_tree;
     }
 
     /// gets the position of the transform.
@@ -365,6 +688,73 @@ impl NodeTransform {
         self
     }
 
+    /// gets the position of the transform in world space.
+    ///
+    /// this goes through the parent transform from the last time the scene synced so it follows
+    /// changes to this transform right away but not changes to its parents
+    pub fn get_world_position(&self) -> Vec3 {
+        self.parent_world.transform_point(self.position)
+    }
+
+    /// moves the transform to a position in world space by working out the local position
+    /// relative to the parent.
+    ///
+    /// like [`Self::get_world_position`] this uses the parent from the last sync, use
+    /// [`crate::Scene::set_world_position`] if the parent may have moved since
+    ///
+    /// # Arguments
+    /// - `position` - the position in world space.
+    ///
+    /// # Returns
+    /// a mutable reference to the NodeTransform.
+    pub fn set_world_position(&mut self, position: impl Into<Vec3>) -> &mut Self {
+        self.position = self.parent_world.inverse_transform_point(position.into());
+        self.update_matrix();
+        self
+    }
+
+    /// gets the rotation of the transform in world space, through the parent from the last sync
+    pub fn get_world_rotation(&self) -> Quat {
+        (self.parent_world.rotation * self.rotation).normalize()
+    }
+
+    /// rotates the transform to face a rotation in world space regardless of how its parents are
+    /// rotated.
+    ///
+    /// uses the parent from the last sync, see [`crate::Scene::set_world_rotation`]
+    ///
+    /// # Arguments
+    /// - `rotation` - the rotation in world space.
+    ///
+    /// # Returns
+    /// a mutable reference to the NodeTransform.
+    pub fn set_world_rotation(&mut self, rotation: Quat) -> &mut Self {
+        self.rotation = (self.parent_world.rotation.inverse() * rotation).normalize();
+        self.update_matrix();
+        self
+    }
+
+    /// gets the scale of the transform in world space, through the parent from the last sync
+    pub fn get_world_scale(&self) -> Vec3 {
+        self.parent_world.scale * self.scale
+    }
+
+    /// scales the transform so it has a scale in world space regardless of how its parents are
+    /// scaled.
+    ///
+    /// uses the parent from the last sync, see [`crate::Scene::set_world_scale`]
+    ///
+    /// # Arguments
+    /// - `scale` - the scale in world space.
+    ///
+    /// # Returns
+    /// a mutable reference to the NodeTransform.
+    pub fn set_world_scale(&mut self, scale: impl Into<Vec3>) -> &mut Self {
+        self.scale = scale.into() * safe_recip(self.parent_world.scale);
+        self.update_matrix();
+        self
+    }
+
     /// rotates the transform by the given axis and degrees.
     ///
     /// # Arguments
@@ -579,4 +969,30 @@ mod tests {
             result
         );
     }
+
+    #[test]
+    fn test_world_setters_convert_through_parent() {
+        let parent = WorldTransform::from(NodeTransform::new(
+            Vec3::new(5.0, 0.0, 0.0),
+            Quat::from_axis_angle(Vec3::Y, 90.0_f32.to_radians()),
+            Vec3::splat(2.0),
+        ));
+
+        let mut child = NodeTransform::default();
+        child.get_world_space(parent);
+
+        let rotation = Quat::from_axis_angle(Vec3::X, 30.0_f32.to_radians());
+        child
+            .set_world_position(Vec3::new(1.0, 2.0, 3.0))
+            .set_world_rotation(rotation)
+            .set_world_scale(Vec3::ONE);
+        child.get_world_space(parent);
+
+        let world = child.world_space();
+        assert!((world.position() - Vec3::new(1.0, 2.0, 3.0)).length() < 1e-5);
+        assert!(world.rotation().angle_between(rotation) < 1e-4);
+        assert!((world.scale() - Vec3::ONE).length() < 1e-5);
+        assert!((child.get_world_position() - world.position()).length() < 1e-5);
+        assert_eq!(child.scale, Vec3::splat(0.5));
+    }
 }
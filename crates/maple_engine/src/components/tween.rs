@@ -0,0 +1,379 @@
+//! tweens move, turn or scale a node over time so simple motion like doors, platforms or ui
+//! sliding in doesn't need an update handler
+//!
+//! a [`TransformTween`] is a sequence of steps that each animate one part of the node's local transform
+//! with an [`Ease`] curve. tweens are stored in the scene with [`crate::Scene::animate`] and
+//! advanced by [`crate::GameContext::update_tweens`], which the app calls after update. a node can
+//! have several tweens at once, they run side by side.
+//!
+//! # Example
+//! ```rust
+//! # use maple_engine::prelude::*;
+//! # use glam::Vec3;
+//! # let ctx = GameContext::new();
+//! ctx.scene.spawn(Empty::default()).animate(
+//!     TransformTween::new()
+//!         .position_by(Vec3::Y * 3.0, 1.5)
+//!         .ease(Ease::InOutSine)
+//!         .wait(0.5)
+//!         .repeat(Repeat::PingPong),
+//! );
+//! ```
+
+use std::f32::consts::PI;
+
+use glam::{Quat, Vec3};
+
+use crate::prelude::{EventLabel, NodeTransform};
+
+/// emitted to a node when one of its tweens has finished
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TweenFinished;
+impl EventLabel for TweenFinished {}
+
+/// the curve a tween step follows from its start to its end
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Ease {
+    #[default]
+    Linear,
+    InQuad,
+    OutQuad,
+    InOutQuad,
+    InCubic,
+    OutCubic,
+    InOutCubic,
+    InSine,
+    OutSine,
+    InOutSine,
+    /// pulls back a little before moving
+    InBack,
+    /// overshoots the end a little and settles back
+    OutBack,
+    /// bounces against the end like a dropped ball
+    OutBounce,
+}
+
+impl Ease {
+    /// how far along the curve is `t` of the way through, `t` is clamped to 0..1
+    pub fn apply(self, t: f32) -> f32 {
+        const BACK: f32 = 1.70158;
+
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Ease::Linear => t,
+            Ease::InQuad => t * t,
+            Ease::OutQuad => 1.0 - (1.0 - t).powi(2),
+            Ease::InOutQuad => match t < 0.5 {
+                true => 2.0 * t * t,
+                false => 1.0 - (-2.0 * t + 2.0).powi(2) / 2.0,
+            },
+            Ease::InCubic => t * t * t,
+            Ease::OutCubic => 1.0 - (1.0 - t).powi(3),
+            Ease::InOutCubic => match t < 0.5 {
+                true => 4.0 * t * t * t,
+                false => 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0,
+            },
+            Ease::InSine => 1.0 - (t * PI / 2.0).cos(),
+            Ease::OutSine => (t * PI / 2.0).sin(),
+            Ease::InOutSine => -((PI * t).cos() - 1.0) / 2.0,
+            Ease::InBack => (BACK + 1.0) * t * t * t - BACK * t * t,
+            Ease::OutBack => {
+                let t = t - 1.0;
+                1.0 + (BACK + 1.0) * t * t * t + BACK * t * t
+            }
+            Ease::OutBounce => {
+                const N: f32 = 7.5625;
+                const D: f32 = 2.75;
+                if t < 1.0 / D {
+                    N * t * t
+                } else if t < 2.0 / D {
+                    let t = t - 1.5 / D;
+                    N * t * t + 0.75
+                } else if t < 2.5 / D {
+                    let t = t - 2.25 / D;
+                    N * t * t + 0.9375
+                } else {
+                    let t = t - 2.625 / D;
+                    N * t * t + 0.984375
+                }
+            }
+        }
+    }
+}
+
+/// what a tween does once it reaches the end of its last step
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Repeat {
+    /// finish after playing once
+    #[default]
+    Once,
+    /// play this many times in total, jumping back to the start each time
+    Times(u32),
+    /// play forever, jumping back to the start each time
+    Loop,
+    /// play forever, going back through the steps in reverse each other time
+    PingPong,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Target {
+    Position(Vec3),
+    PositionBy(Vec3),
+    Rotation(Quat),
+    Scale(Vec3),
+    Wait,
+}
+
+/// the values a step moves between, found from the transform when the step first starts
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Span {
+    Position(Vec3, Vec3),
+    Rotation(Quat, Quat),
+    Scale(Vec3, Vec3),
+    Wait,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Step {
+    target: Target,
+    duration: f32,
+    ease: Ease,
+    span: Option<Span>,
+}
+
+impl Step {
+    fn span(&mut self, transform: &NodeTransform) -> Span {
+        *self.span.get_or_insert(match self.target {
+            Target::Position(to) => Span::Position(transform.position, to),
+            Target::PositionBy(by) => Span::Position(transform.position, transform.position + by),
+            Target::Rotation(to) => Span::Rotation(transform.rotation, to),
+            Target::Scale(to) => Span::Scale(transform.scale, to),
+            Target::Wait => Span::Wait,
+        })
+    }
+
+    /// write the value `progress` of the way through the step, before easing
+    fn apply(&mut self, transform: &mut NodeTransform, progress: f32) {
+        let t = self.ease.apply(progress);
+        match self.span(transform) {
+            Span::Position(from, to) => transform.position = from.lerp(to, t),
+            Span::Rotation(from, to) => transform.rotation = from.slerp(to, t),
+            Span::Scale(from, to) => transform.scale = from.lerp(to, t),
+            Span::Wait => {}
+        }
+    }
+}
+
+/// animates a node's local transform through a sequence of steps, see the
+/// [module docs](self)
+///
+/// each step starts from wherever the node is when the step begins
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TransformTween {
+    steps: Vec<Step>,
+    repeat: Repeat,
+    /// the step being played
+    index: usize,
+    /// seconds into the current step
+    elapsed: f32,
+    /// playing the steps backwards in a ping pong
+    reversed: bool,
+    /// how many times every step has been played
+    plays: u32,
+}
+
+impl TransformTween {
+    /// a tween with no steps, it finishes right away
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn step(mut self, target: Target, duration: f32) -> Self {
+        self.steps.push(Step {
+            target,
+            duration: duration.max(0.0),
+            ease: Ease::Linear,
+            span: None,
+        });
+        self
+    }
+
+    /// move to `position` over `duration` seconds
+    pub fn position_to(self, position: Vec3, duration: f32) -> Self {
+        self.step(Target::Position(position), duration)
+    }
+
+    /// move by `offset` from where the node is when the step starts over `duration` seconds
+    pub fn position_by(self, offset: Vec3, duration: f32) -> Self {
+        self.step(Target::PositionBy(offset), duration)
+    }
+
+    /// turn to `rotation` over `duration` seconds
+    pub fn rotation_to(self, rotation: Quat, duration: f32) -> Self {
+        self.step(Target::Rotation(rotation), duration)
+    }
+
+    /// scale to `scale` over `duration` seconds
+    pub fn scale_to(self, scale: Vec3, duration: f32) -> Self {
+        self.step(Target::Scale(scale), duration)
+    }
+
+    /// do nothing for `duration` seconds
+    pub fn wait(self, duration: f32) -> Self {
+        self.step(Target::Wait, duration)
+    }
+
+    /// the curve of the last step added. Default: [`Ease::Linear`]
+    pub fn ease(mut self, ease: Ease) -> Self {
+        if let Some(step) = self.steps.last_mut() {
+            step.ease = ease;
+        }
+        self
+    }
+
+    /// what happens once the last step ends. Default: [`Repeat::Once`]
+    pub fn repeat(mut self, repeat: Repeat) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    /// how long it takes to play every step once
+    pub fn duration(&self) -> f32 {
+        self.steps.iter().map(|step| step.duration).sum()
+    }
+
+    /// play `dt` more seconds of the tween on `transform`, returns true once it has finished
+    pub(crate) fn advance(&mut self, transform: &mut NodeTransform, dt: f32) -> bool {
+        let mut remaining = dt.max(0.0);
+        loop {
+            let Some(step) = self.steps.get_mut(self.index) else {
+                return true;
+            };
+
+            let left = step.duration - self.elapsed;
+            if remaining < left {
+                self.elapsed += remaining;
+                let progress = self.elapsed / step.duration;
+                step.apply(transform, directed(progress, self.reversed));
+                return false;
+            }
+
+            remaining -= left.max(0.0);
+            step.apply(transform, directed(1.0, self.reversed));
+            self.elapsed = 0.0;
+
+            let last = match self.reversed {
+                true => 0,
+                false => self.steps.len() - 1,
+            };
+            if self.index != last {
+                match self.reversed {
+                    true => self.index -= 1,
+                    false => self.index += 1,
+                }
+                continue;
+            }
+
+            self.plays = self.plays.saturating_add(1);
+            match self.repeat {
+                Repeat::Once => return true,
+                Repeat::Times(times) if self.plays >= times => return true,
+                Repeat::Times(_) | Repeat::Loop => self.index = 0,
+                Repeat::PingPong => self.reversed = !self.reversed,
+            }
+
+            // a tween that takes no time would repeat forever
+            if self.duration() <= 0.0 {
+                return false;
+            }
+        }
+    }
+}
+
+fn directed(progress: f32, reversed: bool) -> f32 {
+    match reversed {
+        true => 1.0 - progress,
+        false => progress,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ease_endpoints() {
+        let eases = [
+            Ease::Linear,
+            Ease::InQuad,
+            Ease::OutQuad,
+            Ease::InOutQuad,
+            Ease::InCubic,
+            Ease::OutCubic,
+            Ease::InOutCubic,
+            Ease::InSine,
+            Ease::OutSine,
+            Ease::InOutSine,
+            Ease::InBack,
+            Ease::OutBack,
+            Ease::OutBounce,
+        ];
+        for ease in eases {
+            assert!(ease.apply(0.0).abs() < 1e-5, "{ease:?} doesn't start at 0");
+            assert!(
+                (ease.apply(1.0) - 1.0).abs() < 1e-5,
+                "{ease:?} doesn't end at 1"
+            );
+        }
+        assert!(Ease::OutCubic.apply(0.5) > 0.5);
+        assert!(Ease::InBack.apply(0.2) < 0.0);
+        assert_eq!(Ease::Linear.apply(2.0), 1.0);
+    }
+
+    #[test]
+    fn test_steps_play_in_order() {
+        let mut transform = NodeTransform::default();
+        let mut tween = TransformTween::new()
+            .position_to(Vec3::X * 2.0, 1.0)
+            .wait(1.0)
+            .scale_to(Vec3::splat(3.0), 1.0);
+        assert_eq!(tween.duration(), 3.0);
+
+        assert!(!tween.advance(&mut transform, 0.5));
+        assert_eq!(transform.position, Vec3::X);
+
+        // a step that ends part way through a frame carries the rest into the next step
+        assert!(!tween.advance(&mut transform, 2.0));
+        assert_eq!(transform.position, Vec3::X * 2.0);
+        assert_eq!(transform.scale, Vec3::splat(2.0));
+
+        assert!(tween.advance(&mut transform, 1.0));
+        assert_eq!(transform.scale, Vec3::splat(3.0));
+    }
+
+    #[test]
+    fn test_ping_pong_and_times() {
+        let mut transform = NodeTransform::default();
+        let mut tween = TransformTween::new()
+            .position_by(Vec3::Y * 4.0, 1.0)
+            .repeat(Repeat::PingPong);
+
+        assert!(!tween.advance(&mut transform, 1.5));
+        assert_eq!(transform.position, Vec3::Y * 2.0);
+        assert!(!tween.advance(&mut transform, 0.5));
+        assert_eq!(transform.position, Vec3::ZERO);
+
+        let mut transform = NodeTransform::default();
+        let mut tween = TransformTween::new()
+            .position_by(Vec3::X, 1.0)
+            .repeat(Repeat::Times(2));
+        assert!(!tween.advance(&mut transform, 1.5));
+        assert_eq!(transform.position, Vec3::X * 0.5);
+        assert!(tween.advance(&mut transform, 1.0));
+        assert_eq!(transform.position, Vec3::X);
+
+        // looping a tween with no length doesn't hang
+        let mut empty = TransformTween::new().wait(0.0).repeat(Repeat::Loop);
+        assert!(!empty.advance(&mut transform, 1.0));
+    }
+}
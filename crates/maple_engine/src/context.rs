@@ -6,18 +6,29 @@ use std::{
     sync::Arc,
 };
 
-use parking_lot::{ArcRwLockReadGuard, ArcRwLockWriteGuard, RawRwLock, RwLock};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{Duration, Instant};
+#[cfg(target_arch = "wasm32")]
+use web_time::{Duration, Instant};
+
+use parking_lot::{ArcRwLockReadGuard, ArcRwLockWriteGuard, Mutex, RawRwLock, RwLock};
 use winit::event::{DeviceEvent, WindowEvent};
 
 use crate::{
     asset::AssetLibrary,
-    components::EventLabel,
-    resources::{Frame, Input},
-    scene::Scene,
+    components::{EventLabel, TweenFinished},
+    event_bus::EventBus,
+    platform::SendSync,
+    resources::{Frame, Input, SpatialHash, VirtualCursor},
+    scene::{Scene, TransformSync},
+    serialization::NodeRegistry,
+    tasks::{TaskCtx, TaskHandle, Tasks},
 };
 
 pub trait Resource: Any {}
 
+type DeferredEvent = Box<dyn FnOnce(&GameContext) + Send + Sync>;
+
 pub struct Res<T: Resource + 'static> {
     lock: ArcRwLockReadGuard<RawRwLock, Box<dyn Any + Send + Sync>>,
     _ty: PhantomData<T>,
@@ -63,6 +74,13 @@ pub struct GameContext {
     pub assets: AssetLibrary,
 
     resources: HashMap<TypeId, Arc<RwLock<Box<dyn Any + Send + Sync>>>>,
+
+    deferred: Mutex<Vec<DeferredEvent>>,
+
+    /// the soonest frame asked for with [`GameContext::request_redraw_after`]
+    redraw_at: Mutex<Option<Instant>>,
+
+    pub(crate) tasks: Tasks,
 }
 
 impl Default for GameContext {
@@ -82,11 +100,19 @@ impl GameContext {
     /// # Returns
     /// The new game context.
     pub fn new() -> GameContext {
-        GameContext {
+        let assets = AssetLibrary::new();
+        let mut context = GameContext {
             scene: Scene::new(),
             resources: HashMap::new(),
-            assets: AssetLibrary::new(),
-        }
+            tasks: Tasks::new(assets.clone()),
+            assets,
+            deferred: Mutex::new(Vec::new()),
+            redraw_at: Mutex::new(None),
+        };
+        context.insert_resource(NodeRegistry::default());
+        context.insert_resource(SpatialHash::default());
+        context.insert_resource(EventBus::default());
+        context
     }
 
     pub fn device_event(&mut self, event: &DeviceEvent) {
@@ -98,14 +124,59 @@ impl GameContext {
     }
 
     pub fn begin_frame(&mut self) {
+        // this frame draws the changes and requests so far
+        self.scene.clear_changed();
+        let now = Instant::now();
+        self.redraw_at.lock().take_if(|at| *at <= now);
+
         self.scene.poll_async(&self.assets);
         self.get_resource_mut::<Frame>().update();
+        EventBus::dispatch(self);
+
+        let dt = self.get_resource::<Frame>().unscaled_time_delta_f32;
+        self.get_resource_mut::<Input>().touches.step(dt);
+        if self.has_resource::<VirtualCursor>() {
+            self.get_resource_mut::<VirtualCursor>()
+                .drive(&mut self.get_resource_mut::<Input>(), dt);
+        }
     }
 
     pub fn end_frame(&mut self) {
         self.get_resource_mut::<Input>().end_frame();
     }
 
+    /// draw another frame after this one even if nothing else changed
+    ///
+    /// only needed by apps that render on demand, for changes the engine can't see such as a
+    /// material's color or a ui that is still animating. moving, spawning and hiding nodes, input
+    /// and assets that finish loading already draw a frame
+    pub fn request_redraw(&self) {
+        self.request_redraw_after(Duration::ZERO);
+    }
+
+    /// draw a frame once `delay` has passed, see [`GameContext::request_redraw`]
+    pub fn request_redraw_after(&self, delay: Duration) {
+        // a delay too long to add to the time never comes
+        let Some(at) = Instant::now().checked_add(delay) else {
+            return;
+        };
+        let mut redraw_at = self.redraw_at.lock();
+        *redraw_at = Some(redraw_at.map_or(at, |current| current.min(at)));
+    }
+
+    /// when an app that renders on demand should draw its next frame, `None` if nothing needs
+    /// one until the next input
+    ///
+    /// a frame is needed straight away if the last one changed the scene, assets finished
+    /// loading or a key, button or finger is held down
+    pub fn next_redraw(&self) -> Option<Instant> {
+        let held = self.has_resource::<Input>() && self.get_resource::<Input>().is_held();
+        if held || self.scene.is_changed() || self.assets.has_results() {
+            return Some(Instant::now());
+        }
+        *self.redraw_at.lock()
+    }
+
     pub fn get_resource<R: Resource>(&self) -> Res<R> {
         let id = TypeId::of::<R>();
         let name = std::any::type_name::<R>();
@@ -137,20 +208,187 @@ impl GameContext {
             _ty: PhantomData,
         }
     }
+
+    /// returns true if a resource of type `R` has been inserted
+    pub fn has_resource<R: Resource>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<R>())
+    }
+
     pub fn insert_resource<R: Resource + Send + Sync>(&mut self, resource: R) {
         let id = TypeId::of::<R>();
         self.resources
             .insert(id, Arc::new(RwLock::new(Box::new(resource))));
     }
 
+    /// updates the world transform of every node and the positions in the [`SpatialHash`]
+    ///
+    /// this is done by the app after every update, call this to see the changes of the current
+    /// frame early
+    pub fn sync_world_transform(&self) {
+        self.sync_transforms(false);
+    }
+
+    /// like [`GameContext::sync_world_transform`] but done after each fixed update so
+    /// interpolated nodes know where they were before the step
+    pub fn sync_fixed_world_transform(&self) {
+        self.sync_transforms(true);
+    }
+
+    fn sync_transforms(&self, fixed_step: bool) {
+        let alpha = match fixed_step || !self.has_resource::<Frame>() {
+            true => 1.0,
+            false => self.get_resource::<Frame>().fixed_alpha(),
+        };
+
+        let mut spatial_hash = self
+            .has_resource::<SpatialHash>()
+            .then(|| self.get_resource_mut::<SpatialHash>());
+
+        self.scene.sync_world_transform_with(&mut TransformSync {
+            spatial_hash: spatial_hash.as_deref_mut(),
+            fixed_step,
+            alpha,
+        });
+    }
+
+    /// slow down, speed up or pause the game, see [`Frame::set_time_scale`]
+    pub fn set_time_scale(&self, scale: f32) {
+        if self.has_resource::<Frame>() {
+            self.get_resource_mut::<Frame>().set_time_scale(scale);
+        }
+    }
+
+    /// how fast game time passes compared to real time, 1 if there is no [`Frame`]
+    pub fn time_scale(&self) -> f32 {
+        match self.has_resource::<Frame>() {
+            true => self.get_resource::<Frame>().time_scale(),
+            false => 1.0,
+        }
+    }
+
+    /// applies every node's [`crate::components::TransformConstraints`] and syncs the nodes that
+    /// moved, the app does this after update each frame
+    pub fn apply_constraints(&self) {
+        let dt = match self.has_resource::<Frame>() {
+            true => self.get_resource::<Frame>().time_delta_f32,
+            false => 0.0,
+        };
+
+        if self.scene.apply_constraints(dt) {
+            self.sync_world_transform();
+        }
+    }
+
+    /// advances every node's [`crate::components::TransformTween`]s and emits
+    /// [`crate::components::TweenFinished`] to the nodes whose tweens finished, the app does this
+    /// after update each frame
+    pub fn update_tweens(&self) {
+        let dt = match self.has_resource::<Frame>() {
+            true => self.get_resource::<Frame>().time_delta_f32,
+            false => 0.0,
+        };
+
+        for id in self.scene.update_tweens(dt) {
+            self.scene.emit_to(id, &TweenFinished, self);
+        }
+    }
+
+    /// start an async task that isn't tied to a node, see [`crate::tasks`]
+    ///
+    /// ```rust,ignore
+    /// ctx.spawn(|task| async move {
+    ///     task.wait(3.0).await;
+    ///     task.with(|ctx| ctx.emit(WaveStarted)).await;
+    /// });
+    /// ```
+    pub fn spawn<F, Fut>(&self, f: F) -> TaskHandle
+    where
+        F: FnOnce(TaskCtx) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.tasks.spawn(None, f)
+    }
+
+    /// how many async tasks are still running
+    pub fn task_count(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// polls the async tasks started with [`GameContext::spawn`] and on nodes, the app does this
+    /// after update each frame
+    pub fn update_tasks(&self) {
+        let (dt, unscaled_dt) = match self.has_resource::<Frame>() {
+            true => {
+                let frame = self.get_resource::<Frame>();
+                (frame.time_delta_f32, frame.unscaled_time_delta_f32)
+            }
+            false => (0.0, 0.0),
+        };
+
+        self.tasks.step(self, dt, unscaled_dt);
+    }
+
     pub fn pop_ready_queue(&self) {
         self.scene.pop_ready_queue(self);
     }
 
+    /// the [`EventBus`] of events that don't go through the scene
+    pub fn events(&self) -> Res<EventBus> {
+        self.get_resource::<EventBus>()
+    }
+
     /// emits an event to the currently loaded nodes in the context
     pub fn emit<E: EventLabel>(&self, event: E) {
         let nodes = &self.scene;
 
         nodes.emit(&event, self);
     }
+
+    /// queue an event that is emitted to the scene once this frame has been updated, before it is
+    /// rendered
+    ///
+    /// the scene isn't being traversed when queued events are dispatched so handlers of deferred
+    /// events can freely change the scene
+    pub fn emit_deferred<E: EventLabel + SendSync>(&self, event: E) {
+        self.deferred
+            .lock()
+            .push(Box::new(move |ctx: &GameContext| ctx.emit(event)));
+    }
+
+    /// emits every event queued with [`GameContext::emit_deferred`]
+    ///
+    /// events deferred by the handlers of these events wait for the next flush, so a handler
+    /// deferring its own event runs once a frame instead of forever
+    pub fn flush_deferred(&self) {
+        let queued = std::mem::take(&mut *self.deferred.lock());
+        for emit in queued {
+            emit(self);
+        }
+    }
+
+    /// emits an event to the node at a `/` separated path of names such as `"player/arm"`
+    ///
+    /// returns false if there is no node at the path
+    pub fn emit_to<E: EventLabel>(&self, path: &str, event: E) -> bool {
+        let Some(id) = self.scene.get_id_by_path(path) else {
+            log::warn!("no node at {path} to emit to");
+            return false;
+        };
+
+        self.scene.emit_to(id, &event, self);
+        true
+    }
+
+    /// emits an event to the node at `path` and then its ancestors until it is consumed. see
+    /// [`Scene::bubble`]
+    ///
+    /// returns true if the event was consumed
+    pub fn bubble<E: EventLabel>(&self, path: &str, event: E) -> bool {
+        let Some(id) = self.scene.get_id_by_path(path) else {
+            log::warn!("no node at {path} to emit to");
+            return false;
+        };
+
+        self.scene.bubble(id, &event, self)
+    }
 }
@@ -0,0 +1,230 @@
+//! events published to whoever subscribed to them instead of to nodes of the scene
+//!
+//! [`EventLabel`](crate::components::EventLabel) events travel through the scene and reach the
+//! nodes listening for them. the bus is for the code that isn't a node, such as plugins and game
+//! systems, so they don't need a node in the scene just to hear about something:
+//!
+//! ```rust,ignore
+//! #[derive(Clone)]
+//! struct ScoreChanged(u32);
+//!
+//! // a plugin's setup
+//! app.context().events().on(|ScoreChanged(score), ctx| log::info!("score is {score}"));
+//!
+//! // anything holding the game context
+//! let scores = ctx.events().subscribe::<ScoreChanged>();
+//! ctx.events().publish(ScoreChanged(10));
+//!
+//! // and once a frame later
+//! for ScoreChanged(score) in scores.drain() {}
+//! ```
+//!
+//! published events are delivered together at the start of the next frame by
+//! [`EventBus::dispatch`], events published while they are delivered wait for the frame after
+//! that. the bus is a resource every [`GameContext`] has
+
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::{Arc, Weak},
+};
+
+use parking_lot::Mutex;
+
+use crate::context::{GameContext, Resource};
+
+type Handler<E> = Arc<dyn Fn(&E, &GameContext) + Send + Sync>;
+
+type Delivery = Box<dyn FnOnce(&GameContext)>;
+
+/// the events of one type and who receives them
+struct Channel<E> {
+    pending: Vec<E>,
+    subscriptions: Vec<Weak<Mutex<Vec<E>>>>,
+    handlers: Vec<Handler<E>>,
+}
+
+impl<E> Default for Channel<E> {
+    fn default() -> Self {
+        Self {
+            pending: Vec::new(),
+            subscriptions: Vec::new(),
+            handlers: Vec::new(),
+        }
+    }
+}
+
+trait AnyChannel: Send + Sync {
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    /// queue the pending events for the subscriptions and return the calls to the handlers
+    fn deliver(&mut self) -> Option<Delivery>;
+}
+
+impl<E: Clone + Send + Sync + 'static> AnyChannel for Channel<E> {
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn deliver(&mut self) -> Option<Delivery> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        let events = std::mem::take(&mut self.pending);
+
+        self.subscriptions.retain(|subscription| {
+            let Some(queue) = subscription.upgrade() else {
+                return false;
+            };
+            queue.lock().extend(events.iter().cloned());
+            true
+        });
+
+        if self.handlers.is_empty() {
+            return None;
+        }
+        let handlers = self.handlers.clone();
+        Some(Box::new(move |ctx: &GameContext| {
+            for event in &events {
+                for handler in &handlers {
+                    handler(event, ctx);
+                }
+            }
+        }))
+    }
+}
+
+/// the events of type `E` published since the subscription was made, see the [module docs](self)
+///
+/// dropping it unsubscribes. events are kept until they are drained so a subscription that is
+/// never drained grows
+pub struct Subscription<E> {
+    queue: Arc<Mutex<Vec<E>>>,
+}
+
+impl<E> Subscription<E> {
+    /// take the events received so far, oldest first
+    pub fn drain(&self) -> Vec<E> {
+        std::mem::take(&mut *self.queue.lock())
+    }
+
+    /// the last event received, dropping the others
+    pub fn latest(&self) -> Option<E> {
+        self.drain().pop()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.lock().is_empty()
+    }
+}
+
+/// a publish and subscribe channel for typed events that doesn't go through the scene, see the
+/// [module docs](self)
+#[derive(Default)]
+pub struct EventBus {
+    channels: Mutex<HashMap<TypeId, Box<dyn AnyChannel>>>,
+}
+
+impl Resource for EventBus {}
+
+impl EventBus {
+    fn with_channel<E: Clone + Send + Sync + 'static, R>(
+        &self,
+        f: impl FnOnce(&mut Channel<E>) -> R,
+    ) -> R {
+        let mut channels = self.channels.lock();
+        let channel = channels
+            .entry(TypeId::of::<E>())
+            .or_insert_with(|| Box::new(Channel::<E>::default()));
+        f(channel
+            .as_any_mut()
+            .downcast_mut()
+            .expect("channel should be stored under the type id of its events"))
+    }
+
+    /// send `event` to every subscriber of `E` at the start of the next frame
+    pub fn publish<E: Clone + Send + Sync + 'static>(&self, event: E) {
+        self.with_channel(|channel: &mut Channel<E>| channel.pending.push(event));
+    }
+
+    /// receive the events of type `E` published from now on
+    pub fn subscribe<E: Clone + Send + Sync + 'static>(&self) -> Subscription<E> {
+        let queue = Arc::new(Mutex::new(Vec::new()));
+        self.with_channel(|channel: &mut Channel<E>| {
+            channel.subscriptions.push(Arc::downgrade(&queue))
+        });
+        Subscription { queue }
+    }
+
+    /// call `handler` with every event of type `E` when they are delivered, for as long as the
+    /// bus lives
+    pub fn on<E: Clone + Send + Sync + 'static>(
+        &self,
+        handler: impl Fn(&E, &GameContext) + Send + Sync + 'static,
+    ) {
+        self.with_channel(|channel: &mut Channel<E>| channel.handlers.push(Arc::new(handler)));
+    }
+
+    /// deliver the events published since the last dispatch to the subscriptions and handlers
+    pub fn dispatch(ctx: &GameContext) {
+        // handlers run without the bus locked so they can publish and subscribe
+        let deliveries: Vec<Delivery> = ctx
+            .events()
+            .channels
+            .lock()
+            .values_mut()
+            .filter_map(|channel| channel.deliver())
+            .collect();
+        for deliver in deliveries {
+            deliver(ctx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct ScoreChanged(u32);
+
+    #[test]
+    fn test_events_are_delivered_on_dispatch() {
+        let ctx = GameContext::new();
+        let scores = ctx.events().subscribe::<ScoreChanged>();
+        let others = ctx.events().subscribe::<u32>();
+
+        ctx.events().publish(ScoreChanged(10));
+        ctx.events().publish(ScoreChanged(20));
+        assert!(scores.is_empty());
+
+        EventBus::dispatch(&ctx);
+        assert_eq!(scores.drain(), [ScoreChanged(10), ScoreChanged(20)]);
+        assert!(scores.is_empty());
+        assert!(others.is_empty());
+
+        // dropped subscriptions stop receiving
+        drop(others);
+        ctx.events().publish(5u32);
+        EventBus::dispatch(&ctx);
+        let subscribed = ctx
+            .events()
+            .with_channel(|channel: &mut Channel<u32>| channel.subscriptions.len());
+        assert_eq!(subscribed, 0);
+    }
+
+    #[test]
+    fn test_events_published_by_handlers_wait_a_dispatch() {
+        let ctx = GameContext::new();
+        let totals = ctx.events().subscribe::<u64>();
+        ctx.events()
+            .on(|ScoreChanged(score): &ScoreChanged, ctx| ctx.events().publish(*score as u64));
+
+        ctx.events().publish(ScoreChanged(3));
+        EventBus::dispatch(&ctx);
+        assert!(totals.is_empty());
+
+        EventBus::dispatch(&ctx);
+        assert_eq!(totals.latest(), Some(3));
+    }
+}
@@ -0,0 +1,211 @@
+//! where a game keeps its files on each platform and writing them safely
+//!
+//! games shouldn't write next to their executable, it's often read only once installed and is
+//! shared by every user. [`save_dir`] is the place for saves, settings and replays that should be
+//! kept and [`cache_dir`] for anything that can be made again, both are folders named after the
+//! game under the platform's usual location:
+//!
+//! | platform | [`save_dir`] | [`cache_dir`] |
+//! |----------|--------------|---------------|
+//! | linux | `$XDG_DATA_HOME` or `~/.local/share` | `$XDG_CACHE_HOME` or `~/.cache` |
+//! | macos | `~/Library/Application Support` | `~/Library/Caches` |
+//! | windows | `%APPDATA%` | `%LOCALAPPDATA%` |
+//!
+//! the folder is called whatever [`set_app_name`] was given, the app sets it from its config, and
+//! the executable's name otherwise.
+//!
+//! [`write_atomic`] writes next to the file and renames over it so a crash mid save leaves the
+//! old file instead of half of the new one.
+//!
+//! # Example
+//! ```rust,no_run
+//! # use maple_engine::fs;
+//! fs::set_app_name("my_game");
+//! let slot = fs::save_dir()?.join("slot_1.json");
+//! fs::write_atomic(slot, r#"{"level": 3}"#)?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::{
+    ffi::OsString,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use parking_lot::RwLock;
+
+static APP_NAME: RwLock<Option<String>> = RwLock::new(None);
+
+/// name the folders [`save_dir`] and [`cache_dir`] return, it should stay the same between
+/// versions of the game or saves will be left behind
+pub fn set_app_name(name: impl Into<String>) {
+    *APP_NAME.write() = Some(name.into());
+}
+
+/// the name given to [`set_app_name`] or the executable's name without its extension
+pub fn app_name() -> String {
+    if let Some(name) = APP_NAME.read().clone() {
+        return name;
+    }
+
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| {
+            exe.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| "maple".to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DirKind {
+    Save,
+    Cache,
+}
+
+/// the folder of the game's saves, settings and replays, created if it doesn't exist yet
+pub fn save_dir() -> io::Result<PathBuf> {
+    app_dir(DirKind::Save)
+}
+
+/// the folder of files the game can make again such as shader caches, created if it doesn't
+/// exist yet. the os or the user may clear it at any time
+pub fn cache_dir() -> io::Result<PathBuf> {
+    app_dir(DirKind::Cache)
+}
+
+fn app_dir(kind: DirKind) -> io::Result<PathBuf> {
+    let base = platform_dir(kind, |key| std::env::var_os(key)).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            "couldn't find the user's data folder on this platform",
+        )
+    })?;
+
+    let dir = base.join(app_name());
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// the platform's folder for `kind` with the environment read through `env`
+fn platform_dir(kind: DirKind, env: impl Fn(&str) -> Option<OsString>) -> Option<PathBuf> {
+    // env vars have to be absolute paths to be used, relative ones are ignored like the xdg
+    // spec says
+    let var = |key: &str| {
+        env(key)
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+    };
+
+    if cfg!(target_arch = "wasm32") {
+        None
+    } else if cfg!(target_os = "windows") {
+        match kind {
+            DirKind::Save => var("APPDATA"),
+            DirKind::Cache => var("LOCALAPPDATA"),
+        }
+    } else if cfg!(target_os = "macos") {
+        let home = var("HOME")?;
+        match kind {
+            DirKind::Save => Some(home.join("Library/Application Support")),
+            DirKind::Cache => Some(home.join("Library/Caches")),
+        }
+    } else {
+        match kind {
+            DirKind::Save => {
+                var("XDG_DATA_HOME").or_else(|| Some(var("HOME")?.join(".local/share")))
+            }
+            DirKind::Cache => var("XDG_CACHE_HOME").or_else(|| Some(var("HOME")?.join(".cache"))),
+        }
+    }
+}
+
+/// write `contents` to `path` so it either has the old contents or all of the new ones, even if
+/// the game crashes or the power goes out while saving. missing parent folders are created
+pub fn write_atomic(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let path = path.as_ref();
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} isn't a file path", path.display()),
+        )
+    })?;
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    // next to the file so the rename doesn't cross file systems
+    let mut temp_name = file_name.to_os_string();
+    temp_name.push(".tmp");
+    let temp = path.with_file_name(temp_name);
+
+    let written = (|| {
+        let mut file = std::fs::File::create(&temp)?;
+        file.write_all(contents.as_ref())?;
+        file.sync_all()?;
+        std::fs::rename(&temp, path)
+    })();
+    if written.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    written
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_atomic_replaces_file() {
+        let dir = std::env::temp_dir().join(format!("maple_fs_test_{}", std::process::id()));
+        let path = dir.join("saves/slot.json");
+
+        write_atomic(&path, "first").unwrap();
+        write_atomic(&path, "second").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "second");
+        // nothing is left over from the writes
+        let files = std::fs::read_dir(dir.join("saves")).unwrap().count();
+        assert_eq!(files, 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn test_xdg_dirs() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |key: &str| {
+                vars.iter()
+                    .find(|(k, _)| *k == key)
+                    .map(|(_, v)| OsString::from(v))
+            }
+        };
+
+        let home = env(&[("HOME", "/home/maple")]);
+        assert_eq!(
+            platform_dir(DirKind::Save, home),
+            Some(PathBuf::from("/home/maple/.local/share"))
+        );
+        assert_eq!(
+            platform_dir(DirKind::Cache, home),
+            Some(PathBuf::from("/home/maple/.cache"))
+        );
+
+        let xdg = env(&[("HOME", "/home/maple"), ("XDG_DATA_HOME", "/data")]);
+        assert_eq!(
+            platform_dir(DirKind::Save, xdg),
+            Some(PathBuf::from("/data"))
+        );
+
+        // relative paths in the env are ignored
+        let relative = env(&[("HOME", "/home/maple"), ("XDG_CACHE_HOME", "cache")]);
+        assert_eq!(
+            platform_dir(DirKind::Cache, relative),
+            Some(PathBuf::from("/home/maple/.cache"))
+        );
+        assert_eq!(platform_dir(DirKind::Save, env(&[])), None);
+    }
+}
@@ -6,16 +6,27 @@ pub mod asset;
 pub mod color;
 pub mod components;
 pub mod context;
+pub mod event_bus;
+pub mod fs;
+pub mod loading;
 pub mod nodes;
 pub mod platform;
+pub mod prefab;
 pub mod resources;
 pub mod scene;
+pub mod serialization;
+pub mod tasks;
+pub mod transition;
+pub mod utils;
 
 pub use context::GameContext;
 pub use scene::{Scene, SceneBuilder};
 
 pub use nodes::{Buildable, Builder, Node};
 
+/// the version of this crate
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
 pub mod prelude {
     pub use crate::components::*;
 
@@ -27,7 +38,21 @@ pub mod prelude {
 
     pub use crate::scene::*;
 
+    pub use crate::prefab::{Prefab, PrefabOverrides};
+
+    pub use crate::serialization::{NodeRegistry, SceneFileError};
+
     pub use crate::asset::{AssetHandle, AssetLibrary};
 
+    pub use crate::event_bus::{EventBus, Subscription};
+
+    pub use crate::loading::{LoadingFinished, LoadingProgress, SceneLoader};
+
+    pub use crate::transition::{
+        SceneTransitions, Transition, TransitionEffect, TransitionFinished,
+    };
+
+    pub use crate::tasks::{TaskCtx, TaskHandle};
+
     pub use crate::color::Color;
 }
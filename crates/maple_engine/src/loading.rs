@@ -0,0 +1,201 @@
+//! loading screens shown while the assets of a scene load
+//!
+//! [`SceneLoader::load`] merges a loading scene straight away and builds the target scene, which
+//! starts loading its assets on the worker pool. the app keeps drawing frames while they load and
+//! emits [`LoadingProgress`] every time more of them finish. once they are all done the loading
+//! scene is despawned, the target scene is merged and [`LoadingFinished`] is emitted.
+//!
+//! ```rust,ignore
+//! App::new(Config::default())
+//!     .add_plugin(Core3D)
+//!     .load_scene_with_loading_screen(LoadingScreen, Level1)
+//!     .run();
+//!
+//! // a later level from a node
+//! ctx.game
+//!     .get_resource_mut::<SceneLoader>()
+//!     .load(LoadingScreen, Level2);
+//! ```
+//!
+//! the loading scene is a normal scene so a progress bar is a node that listens for
+//! [`LoadingProgress`]. render nodes can read [`SceneLoader::progress`] from the game context
+//! they draw with instead.
+//!
+//! every load started while the target scene is built counts, including the assets those assets
+//! load themselves, see [`LoadBatch`]
+
+use crate::{
+    asset::{AssetLibrary, LoadBatch},
+    components::EventLabel,
+    context::{GameContext, Resource},
+    scene::{IntoScene, NodeId, Scene},
+};
+
+/// emitted to the scene while a [`SceneLoader`] loads, each time the progress changes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoadingProgress {
+    /// from `0.0` to `1.0`, never goes down during a load
+    pub progress: f32,
+    /// assets that finished or failed
+    pub loaded: u64,
+    /// assets started so far
+    pub total: u64,
+}
+impl EventLabel for LoadingProgress {}
+
+/// emitted to the scene once the target scene of a [`SceneLoader`] was merged
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadingFinished {
+    /// the root nodes of the merged scene
+    pub roots: usize,
+}
+impl EventLabel for LoadingFinished {}
+
+type BuildScene = Box<dyn FnOnce(&AssetLibrary) -> Scene + Send + Sync>;
+
+struct LoadRequest {
+    loading: BuildScene,
+    target: BuildScene,
+}
+
+struct ActiveLoad {
+    batch: LoadBatch,
+    target: Scene,
+    /// the roots of the loading scene, despawned when done
+    loading_roots: Vec<NodeId>,
+    progress: f32,
+}
+
+/// shows a loading scene until the assets of another scene are loaded, see the
+/// [module docs](self)
+///
+/// the app inserts it as a resource and calls [`SceneLoader::update`] every frame
+#[derive(Default)]
+pub struct SceneLoader {
+    requested: Option<LoadRequest>,
+    active: Option<ActiveLoad>,
+}
+
+impl Resource for SceneLoader {}
+
+impl SceneLoader {
+    /// show `loading` until the assets `target` loads while it's built are done, then swap it for
+    /// `target`. starts at the next update and replaces a load that is still running
+    pub fn load<L, LM, T, TM>(&mut self, loading: L, target: T)
+    where
+        L: IntoScene<LM> + Send + Sync + 'static,
+        T: IntoScene<TM> + Send + Sync + 'static,
+    {
+        self.requested = Some(LoadRequest {
+            loading: Box::new(move |assets| loading.into_scene(assets)),
+            target: Box::new(move |assets| target.into_scene(assets)),
+        });
+    }
+
+    /// true while a loading scene is shown or about to be
+    pub fn is_loading(&self) -> bool {
+        self.requested.is_some() || self.active.is_some()
+    }
+
+    /// the progress of the running load from `0.0` to `1.0`, `None` if nothing is loading
+    pub fn progress(&self) -> Option<f32> {
+        self.active.as_ref().map(|active| active.progress)
+    }
+
+    /// start requested loads, emit [`LoadingProgress`] and swap in the target scene once its
+    /// assets are done
+    pub fn update(ctx: &GameContext) {
+        if !ctx.has_resource::<SceneLoader>() {
+            return;
+        }
+
+        // the resource isn't held while scenes are built or events are emitted so handlers can
+        // start another load
+        let requested = ctx.get_resource_mut::<SceneLoader>().requested.take();
+        if let Some(request) = requested {
+            let previous = ctx.get_resource_mut::<SceneLoader>().active.take();
+            if let Some(previous) = previous {
+                for id in previous.loading_roots {
+                    ctx.scene.despawn(id);
+                }
+            }
+
+            let loading_roots = ctx.scene.merge((request.loading)(&ctx.assets));
+            let batch = ctx.assets.begin_batch();
+            let target = (request.target)(&ctx.assets);
+            ctx.get_resource_mut::<SceneLoader>().active = Some(ActiveLoad {
+                batch,
+                target,
+                loading_roots,
+                progress: -1.0,
+            });
+        }
+
+        let (progress, done) = {
+            let mut loader = ctx.get_resource_mut::<SceneLoader>();
+            let Some(active) = &mut loader.active else {
+                return;
+            };
+
+            let (loaded, total) = active.batch.counts(&ctx.assets);
+            let progress = active.batch.progress(&ctx.assets).max(active.progress);
+            let changed = (progress > active.progress).then_some(LoadingProgress {
+                progress,
+                loaded,
+                total,
+            });
+            active.progress = progress;
+
+            let done = active
+                .batch
+                .is_done(&ctx.assets)
+                .then(|| loader.active.take())
+                .flatten();
+            (changed, done)
+        };
+
+        if let Some(progress) = progress {
+            ctx.emit(progress);
+        }
+
+        if let Some(done) = done {
+            for id in done.loading_roots {
+                ctx.scene.despawn(id);
+            }
+            let roots = ctx.scene.merge(done.target);
+            ctx.emit(LoadingFinished { roots: roots.len() });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::Empty;
+
+    #[test]
+    fn test_loading_scene_is_swapped_for_the_target() {
+        let mut ctx = GameContext::new();
+        ctx.insert_resource(SceneLoader::default());
+        ctx.get_resource_mut::<SceneLoader>().load(
+            || {
+                let scene = Scene::default();
+                scene.spawn_with_name("loading", Empty::default());
+                scene
+            },
+            || {
+                let scene = Scene::default();
+                scene.spawn_with_name("level", Empty::default());
+                scene
+            },
+        );
+        assert!(ctx.get_resource::<SceneLoader>().is_loading());
+
+        // nothing to load so the target is merged on the first update
+        SceneLoader::update(&ctx);
+        assert!(ctx.scene.get_id_by_path("loading").is_none());
+        assert!(ctx.scene.get_id_by_path("level").is_some());
+        assert!(!ctx.get_resource::<SceneLoader>().is_loading());
+        assert_eq!(ctx.get_resource::<SceneLoader>().progress(), None);
+    }
+}
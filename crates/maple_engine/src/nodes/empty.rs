@@ -6,6 +6,8 @@
 //! # Notes
 //! While the Empty node has no special functionality it still contains a transform, children, and
 //! events.
+use serde::{Deserialize, Serialize};
+
 use crate::components::NodeTransform;
 
 use super::{
@@ -14,7 +16,7 @@ use super::{
 };
 
 /// Empty nodes are nodes with no special functionality.
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Empty {
     /// The transform of the node.
     pub transform: NodeTransform,
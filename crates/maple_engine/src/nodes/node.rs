@@ -25,7 +25,7 @@
 //! }
 //! ```
 
-use crate::{components::NodeTransform, platform::SendSync};
+use crate::{components::NodeTransform, platform::SendSync, utils::aabb::AABB};
 use std::any::Any;
 
 /// The Node trait is used to define that a type is a node in the scene graph.
@@ -37,6 +37,29 @@ pub trait Node: Any + Casting + SendSync {
     /// # Returns
     /// a mutable reference to the transform of the node.
     fn get_transform(&mut self) -> &mut NodeTransform;
+
+    /// enable or disable the node. disabled nodes and their children don't receive events
+    /// emitted to the whole scene such as `Update` until they are enabled again.
+    ///
+    /// the flag is kept by the scene which notices the change when it syncs world transforms,
+    /// use [`crate::Scene::set_enabled`] for it to take effect immediately.
+    fn set_enabled(&mut self, enabled: bool) {
+        self.get_transform().enabled_request = Some(enabled);
+    }
+
+    /// show or hide the node. hidden nodes and their children are skipped by the renderer.
+    ///
+    /// like [`Self::set_enabled`] the scene notices the change when it syncs, see
+    /// [`crate::Scene::set_visible`]
+    fn set_visible(&mut self, visible: bool) {
+        self.get_transform().visible_request = Some(visible);
+    }
+
+    /// the bounding box of the node and all of its descendants in world space as of the last
+    /// sync, see [`NodeTransform::world_aabb`]
+    fn world_aabb(&mut self) -> Option<AABB> {
+        self.get_transform().world_aabb().copied()
+    }
 }
 
 // impl fmt::Debug for dyn Node {
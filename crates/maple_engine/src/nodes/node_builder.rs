@@ -18,7 +18,7 @@ use glam as math;
 use glam::Vec3;
 
 use super::Node;
-use crate::components::NodeTransform;
+use crate::components::{NodeTransform, TransformConstraints};
 use crate::nodes::node::IntoNode;
 
 /// a prototype node contains all the components that all nodes have but nothing else
@@ -97,6 +97,30 @@ pub trait Builder: Sized {
         self.prototype().transform.scale *= scale_factor;
         self
     }
+
+    /// set if the node starts enabled. see [`Node::set_enabled`]
+    fn enabled(mut self, enabled: bool) -> Self {
+        self.prototype().transform.enabled_request = Some(enabled);
+        self
+    }
+
+    /// set if the node starts visible. see [`Node::set_visible`]
+    fn visible(mut self, visible: bool) -> Self {
+        self.prototype().transform.visible_request = Some(visible);
+        self
+    }
+
+    /// draw the node between fixed updates. see [`NodeTransform::set_interpolated`]
+    fn interpolated(mut self, interpolated: bool) -> Self {
+        self.prototype().transform.set_interpolated(interpolated);
+        self
+    }
+
+    /// move or turn the node after every update. see [`TransformConstraints`]
+    fn constraints(mut self, constraints: TransformConstraints) -> Self {
+        self.prototype().transform.constraints = Some(constraints);
+        self
+    }
 }
 
 /// Buildable nodes have a builder to configure nodes before they are added into a scene
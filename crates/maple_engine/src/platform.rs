@@ -1,13 +1,87 @@
 //! provides platform specific traits like send sync for both wasm and standard
+//!
+//! the web build turns on wgpu's `fragile-send-sync-non-atomic-wasm` so gpu types are send and
+//! sync there too and [`SendSync`] means the same thing on every platform
 
-#[cfg(not(target_arch = "wasm32"))]
 pub trait SendSync: Send + Sync {}
 
-#[cfg(target_arch = "wasm32")]
-pub trait SendSync {}
-
-#[cfg(not(target_arch = "wasm32"))]
 impl<T: Send + Sync + ?Sized> SendSync for T {}
 
+/// run `poll` every few milliseconds until it returns true without blocking the caller
+///
+/// native platforms poll on a thread of its own and the web polls between frames of the browser's
+/// event loop
+pub(crate) fn poll_until(mut poll: impl FnMut() -> bool + Send + 'static) {
+    const INTERVAL: std::time::Duration = std::time::Duration::from_millis(4);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    std::thread::spawn(move || {
+        while !poll() {
+            std::thread::sleep(INTERVAL);
+        }
+    });
+
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_futures::spawn_local(async move {
+        while !poll() {
+            web::sleep(INTERVAL).await;
+        }
+    });
+}
+
+/// the browser side of things that need a file system or threads on native platforms
 #[cfg(target_arch = "wasm32")]
-impl<T: ?Sized> SendSync for T {}
+pub(crate) mod web {
+    use std::{path::Path, time::Duration};
+
+    use wasm_bindgen::{JsCast, JsValue};
+    use wasm_bindgen_futures::JsFuture;
+
+    use crate::asset::LoadErr;
+
+    fn js_error(value: JsValue) -> LoadErr {
+        LoadErr::Import(format!("{value:?}"))
+    }
+
+    /// get a file from the server the page came from, `path` is relative to the page
+    pub(crate) async fn fetch(path: &Path) -> Result<Vec<u8>, LoadErr> {
+        let window = web_sys::window().ok_or(LoadErr::Import("there is no window".into()))?;
+        // urls always use forward slashes
+        let url = path.to_string_lossy().replace('\\', "/");
+
+        let response: web_sys::Response = JsFuture::from(window.fetch_with_str(&url))
+            .await
+            .map_err(js_error)?
+            .dyn_into()
+            .map_err(js_error)?;
+        match response.status() {
+            404 => return Err(LoadErr::Missing),
+            _ if !response.ok() => {
+                return Err(LoadErr::Import(format!(
+                    "fetching {url} failed with {} {}",
+                    response.status(),
+                    response.status_text()
+                )));
+            }
+            _ => {}
+        }
+
+        let buffer = JsFuture::from(response.array_buffer().map_err(js_error)?)
+            .await
+            .map_err(js_error)?;
+        Ok(js_sys::Uint8Array::new(&buffer).to_vec())
+    }
+
+    /// resolves after `duration` through the browser's timer
+    pub(crate) async fn sleep(duration: Duration) {
+        let promise = js_sys::Promise::new(&mut |resolve, _| {
+            if let Some(window) = web_sys::window() {
+                let _ = window.set_timeout_with_callback_and_timeout_and_arguments_0(
+                    &resolve,
+                    duration.as_millis() as i32,
+                );
+            }
+        });
+        let _ = JsFuture::from(promise).await;
+    }
+}
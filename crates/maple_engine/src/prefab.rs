@@ -0,0 +1,385 @@
+//! prefabs are reusable templates of a node subtree
+//!
+//! a [`Prefab`] wraps anything that can build a scene such as a [`crate::SceneBuilder`], a
+//! function returning a [`Scene`] or an [`InstancableScene`] and spawns a fresh copy of it each
+//! time it is instantiated. properties of the nodes inside the prefab can be exposed by name so
+//! each instance can override them.
+//!
+//! ```rust, ignore
+//! let enemy = Prefab::new(|assets: &AssetLibrary| {
+//!     let scene = Scene::new();
+//!     scene
+//!         .spawn(Empty::default())
+//!         .spawn_child_with_name("body", Enemy::default());
+//!     scene
+//! })
+//! .expose("health", "body", |enemy: &mut Enemy, health: f32| enemy.health = health);
+//!
+//! // spawns the prefab as "enemy_3" under the "enemies" node
+//! ctx.instantiate(&enemy, "enemies/enemy_3");
+//!
+//! ctx.instantiate_with(
+//!     &enemy,
+//!     "enemies/boss",
+//!     PrefabOverrides::new()
+//!         .position(Vec3::new(0.0, 0.0, 10.0))
+//!         .set("health", 500.0f32),
+//! );
+//! ```
+
+use std::{any::Any, collections::HashMap, sync::Arc};
+
+use glam::Vec3;
+
+use crate::{
+    GameContext,
+    asset::AssetLibrary,
+    components::{EventCtx, NodeTransform},
+    nodes::{Empty, Node},
+    platform::SendSync,
+    scene::{InstancableScene, IntoScene, NodeId, Scene},
+};
+
+type PrefabBuilder = Arc<dyn Fn(&AssetLibrary) -> Scene + Send + Sync>;
+
+type PropertySetter = Arc<dyn Fn(&mut dyn Node, &dyn Any) -> bool + Send + Sync>;
+
+/// a property of a node inside a prefab that can be overridden per instance
+#[derive(Clone)]
+struct ExposedProperty {
+    /// path from the prefab root to the node
+    path: String,
+    setter: PropertySetter,
+}
+
+/// a reusable template of a node subtree. cloning a prefab is cheap
+#[derive(Clone)]
+pub struct Prefab {
+    builder: PrefabBuilder,
+    properties: HashMap<String, ExposedProperty>,
+}
+
+impl Prefab {
+    /// create a prefab from anything that can build a scene. it is built again for every instance
+    pub fn new<S, M>(scene: S) -> Self
+    where
+        S: IntoScene<M> + Clone + SendSync + 'static,
+    {
+        Self {
+            builder: Arc::new(move |assets| scene.clone().into_scene(assets)),
+            properties: HashMap::new(),
+        }
+    }
+
+    /// expose a property of the node at `path` (relative to the prefab root, `""` for the root
+    /// itself) so it can be set with [`PrefabOverrides::set`]
+    pub fn expose<T, V>(
+        mut self,
+        name: impl Into<String>,
+        path: impl Into<String>,
+        setter: impl Fn(&mut T, V) + SendSync + 'static,
+    ) -> Self
+    where
+        T: Node,
+        V: Clone + 'static,
+    {
+        let setter: PropertySetter = Arc::new(move |node, value| {
+            let (Some(node), Some(value)) = (node.downcast_mut::<T>(), value.downcast_ref::<V>())
+            else {
+                return false;
+            };
+            setter(node, value.clone());
+            true
+        });
+
+        self.properties.insert(
+            name.into(),
+            ExposedProperty {
+                path: path.into(),
+                setter,
+            },
+        );
+        self
+    }
+
+    /// names of the exposed properties
+    pub fn properties(&self) -> impl Iterator<Item = &str> {
+        self.properties.keys().map(String::as_str)
+    }
+
+    /// build a standalone copy of the prefab with a single root named `name` and apply the
+    /// overrides to it
+    pub fn build(
+        &self,
+        name: impl Into<String>,
+        overrides: PrefabOverrides,
+        assets: &AssetLibrary,
+    ) -> (Scene, NodeId) {
+        let scene = (self.builder)(assets);
+
+        // group prefabs with multiple roots so the instance can be refered to by one node
+        let roots = scene.root_ids();
+        let root = match roots.as_slice() {
+            [root] => *root,
+            _ => {
+                let group = scene.spawn(Empty::default()).id();
+                for root in roots {
+                    scene.reparent(root, Some(group));
+                }
+                group
+            }
+        };
+        scene.rename(root, Some(name.into()));
+
+        if let Some((_, node)) = scene.node_storage(root) {
+            let mut node = node.write();
+            if let Some(transform) = overrides.transform {
+                *node.get_transform() = transform;
+            }
+            if let Some(position) = overrides.position {
+                node.get_transform().set_position(position);
+            }
+        }
+
+        for (name, value) in overrides.properties {
+            let Some(property) = self.properties.get(&name) else {
+                log::warn!("prefab has no exposed property {name}");
+                continue;
+            };
+            let Some((_, node)) = scene
+                .get_child_id_by_path(root, &property.path)
+                .and_then(|id| scene.node_storage(id))
+            else {
+                log::warn!(
+                    "prefab property {name} refers to a missing node {}",
+                    property.path
+                );
+                continue;
+            };
+            if !(property.setter)(&mut **node.write(), &*value) {
+                log::warn!("prefab property {name} was set with the wrong type");
+            }
+        }
+
+        (scene, root)
+    }
+}
+
+impl From<InstancableScene> for Prefab {
+    fn from(value: InstancableScene) -> Self {
+        let scene = Arc::new(value);
+        Self {
+            builder: Arc::new(move |_| scene.instance()),
+            properties: HashMap::new(),
+        }
+    }
+}
+
+/// values that replace the defaults of a single prefab instance
+#[derive(Default)]
+pub struct PrefabOverrides {
+    transform: Option<NodeTransform>,
+    position: Option<Vec3>,
+    properties: Vec<(String, Box<dyn Any>)>,
+}
+
+impl PrefabOverrides {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// replace the transform of the instance root
+    pub fn transform(mut self, transform: NodeTransform) -> Self {
+        self.transform = Some(transform);
+        self
+    }
+
+    /// move the instance root keeping the rest of its transform
+    pub fn position(mut self, position: impl Into<Vec3>) -> Self {
+        self.position = Some(position.into());
+        self
+    }
+
+    /// set a property exposed with [`Prefab::expose`]. the value must be the same type the
+    /// property was exposed with
+    pub fn set<V: Any>(mut self, name: impl Into<String>, value: V) -> Self {
+        self.properties.push((name.into(), Box::new(value)));
+        self
+    }
+}
+
+/// split `"a/b/name"` into the parent path and name
+fn split_path(path: &str) -> (Option<&str>, &str) {
+    match path.rsplit_once('/') {
+        Some((parent, name)) => (Some(parent), name),
+        None => (None, path),
+    }
+}
+
+impl Scene {
+    /// spawn a copy of a prefab. see [`Scene::instantiate_with`]
+    pub fn instantiate(&self, prefab: &Prefab, path: &str, assets: &AssetLibrary) -> NodeId {
+        self.instantiate_with(prefab, path, PrefabOverrides::default(), assets)
+    }
+
+    /// spawn a copy of a prefab with its root at `path`.
+    ///
+    /// the last part of the path is the name of the instance and the rest is the path to its
+    /// parent. parents that don't exist are created as [`Empty`] nodes.
+    pub fn instantiate_with(
+        &self,
+        prefab: &Prefab,
+        path: &str,
+        overrides: PrefabOverrides,
+        assets: &AssetLibrary,
+    ) -> NodeId {
+        let (parent, name) = split_path(path);
+        let (instance, root) = prefab.build(name, overrides, assets);
+        self.merge_at_path(instance, parent);
+        root
+    }
+
+    fn merge_at_path(&self, instance: Scene, parent: Option<&str>) {
+        match parent.map(|parent| self.get_or_create_path(parent)) {
+            Some(parent) => self.merge_as_child(instance, parent),
+            None => self.merge(instance),
+        };
+    }
+
+    /// find the node at `path` creating any missing nodes along the way
+    fn get_or_create_path(&self, path: &str) -> NodeId {
+        let mut names = path.split('/').filter(|name| !name.is_empty());
+        let Some(first) = names.next() else {
+            return self.spawn(Empty::default()).id();
+        };
+
+        let mut current = match self.get_id_by_path(first) {
+            Some(id) => id,
+            None => self.spawn_with_name(first, Empty::default()).id(),
+        };
+
+        for name in names {
+            current = match self.get_child_id_by_path(current, name) {
+                Some(id) => id,
+                None => self
+                    .spawn_as_child_with_name(name, Empty::default(), current)
+                    .id(),
+            };
+        }
+
+        current
+    }
+}
+
+impl GameContext {
+    /// spawn a copy of a prefab at `path`. see [`Scene::instantiate_with`]
+    pub fn instantiate(&self, prefab: &Prefab, path: &str) -> NodeId {
+        self.scene.instantiate(prefab, path, &self.assets)
+    }
+
+    /// spawn a copy of a prefab with overrides at `path`. see [`Scene::instantiate_with`]
+    pub fn instantiate_with(
+        &self,
+        prefab: &Prefab,
+        path: &str,
+        overrides: PrefabOverrides,
+    ) -> NodeId {
+        self.scene
+            .instantiate_with(prefab, path, overrides, &self.assets)
+    }
+}
+
+impl<E, N: Node> EventCtx<'_, E, N> {
+    /// spawn a copy of a prefab at `path` once the event has finished. see
+    /// [`Scene::instantiate_with`]
+    pub fn instantiate(&self, prefab: &Prefab, path: &str) -> NodeId {
+        self.instantiate_with(prefab, path, PrefabOverrides::default())
+    }
+
+    /// spawn a copy of a prefab with overrides at `path` once the event has finished. the
+    /// returned id is valid once the commands are applied
+    pub fn instantiate_with(
+        &self,
+        prefab: &Prefab,
+        path: &str,
+        overrides: PrefabOverrides,
+    ) -> NodeId {
+        let (parent, name) = split_path(path);
+        let (instance, root) = prefab.build(name, overrides, self.assets());
+        let parent = parent.map(str::to_string);
+
+        self.commands().add(move |scene| {
+            scene.merge_at_path(instance, parent.as_deref());
+        });
+
+        root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Enemy {
+        transform: NodeTransform,
+        health: f32,
+    }
+
+    impl Node for Enemy {
+        fn get_transform(&mut self) -> &mut NodeTransform {
+            &mut self.transform
+        }
+    }
+
+    #[test]
+    fn test_instantiate_with_overrides() {
+        let prefab = Prefab::new(|| {
+            let scene = Scene::new();
+            scene.spawn(Empty::default()).spawn_child_with_name(
+                "body",
+                Enemy {
+                    health: 10.0,
+                    ..Default::default()
+                },
+            );
+            scene
+        })
+        .expose("health", "body", |enemy: &mut Enemy, health: f32| {
+            enemy.health = health
+        });
+
+        let scene = Scene::new();
+        let assets = AssetLibrary::new();
+
+        let first = scene.instantiate(&prefab, "enemies/enemy_1", &assets);
+        let second = scene.instantiate_with(
+            &prefab,
+            "enemies/enemy_2",
+            PrefabOverrides::new()
+                .position(Vec3::X)
+                .set("health", 50.0f32),
+            &assets,
+        );
+
+        assert_eq!(scene.get_id_by_path("enemies/enemy_1"), Some(first));
+        assert_eq!(scene.parent_id(first), scene.parent_id(second));
+        assert_eq!(scene.root_ids().len(), 1);
+
+        let health = |root| {
+            let body = scene.get_child_id_by_path(root, "body").unwrap();
+            scene.get::<Enemy>(body).unwrap().read().health
+        };
+        assert_eq!(health(first), 10.0);
+        assert_eq!(health(second), 50.0);
+        assert_eq!(
+            scene
+                .get::<Empty>(second)
+                .unwrap()
+                .read()
+                .transform
+                .position,
+            Vec3::X
+        );
+    }
+}
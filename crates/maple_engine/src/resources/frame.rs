@@ -116,10 +116,16 @@ pub struct Frame {
     pub stats: FrameStats,
 
     last_frame_time: Instant,
-    /// the time between the last frame and the current frame
+    /// the time between the last frame and the current frame multiplied by the time scale
     pub time_delta: Duration,
     /// delta time in seconds as a float
     pub time_delta_f32: f32,
+    /// the real time between the last frame and the current frame, for things like UI that
+    /// should keep moving while the game is paused
+    pub unscaled_time_delta: Duration,
+    /// unscaled delta time in seconds as a float
+    pub unscaled_time_delta_f32: f32,
+    time_scale: f32,
     /// fixed timestep for fixed update events
     pub fixed_timestep: FixedTimeStep,
 }
@@ -145,6 +151,9 @@ impl Frame {
             last_frame_time: Instant::now(),
             time_delta: Duration::default(),
             time_delta_f32: 0.0,
+            unscaled_time_delta: Duration::default(),
+            unscaled_time_delta_f32: 0.0,
+            time_scale: 1.0,
             fixed_timestep: FixedTimeStep::new(60),
         }
     }
@@ -157,10 +166,12 @@ impl Frame {
         self.elapsed = self.start_time.elapsed();
 
         // update time delta
-        self.time_delta = now.duration_since(self.last_frame_time);
+        self.unscaled_time_delta = now.duration_since(self.last_frame_time);
+        self.unscaled_time_delta_f32 = self.unscaled_time_delta.as_secs_f32();
+        self.time_delta = self.unscaled_time_delta.mul_f32(self.time_scale);
         self.time_delta_f32 = self.time_delta.as_secs_f32();
 
-        self.stats.record(self.time_delta_f32);
+        self.stats.record(self.unscaled_time_delta_f32);
 
         // accumulate time for fixed timestep
         self.fixed_timestep.accumulator += self.time_delta_f32;
@@ -168,10 +179,28 @@ impl Frame {
         let max_accumulator = self.fixed_timestep.fixed_dt * 5.0;
         self.fixed_timestep.accumulator = self.fixed_timestep.accumulator.min(max_accumulator);
 
-        self.fps = 1.0 / self.time_delta_f32;
+        self.fps = 1.0 / self.unscaled_time_delta_f32;
         self.last_frame_time = now;
     }
 
+    /// time the next frame from now so time the app spent suspended isn't one long frame
+    pub fn skip_time(&mut self) {
+        self.last_frame_time = Instant::now();
+    }
+
+    /// how fast game time passes compared to real time
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    /// scale [`Frame::time_delta`] and how often fixed updates run, 0.5 is half speed and 0
+    /// pauses the game
+    ///
+    /// [`Frame::unscaled_time_delta`] and the fps keep using real time
+    pub fn set_time_scale(&mut self, scale: f32) {
+        self.time_scale = scale.max(0.0);
+    }
+
     /// Checks if a fixed update should run and consumes the accumulator
     ///
     /// Returns true if the accumulator has enough time for a fixed update step.
@@ -185,6 +214,12 @@ impl Frame {
         }
     }
 
+    /// how far the time left over after the fixed updates of this frame is into the next fixed
+    /// update from 0 to 1. used to draw interpolated nodes between fixed updates
+    pub fn fixed_alpha(&self) -> f32 {
+        (self.fixed_timestep.accumulator / self.fixed_timestep.fixed_dt).clamp(0.0, 1.0)
+    }
+
     /// Returns the fixed delta time (1/60 of a second by default)
     pub fn fixed_delta_time(&self) -> f32 {
         self.fixed_timestep.fixed_dt
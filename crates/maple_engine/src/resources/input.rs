@@ -4,6 +4,7 @@
 //! - `event-driven`: Uses the `glfw` crate to poll events from the window.
 //! - `key-presses`: Tracks which keys are currently pressed and which were just pressed.
 //! - `mouse-buttons`: Tracks which mouse buttons are currently pressed and which were just pressed.
+//! - `touch`: Tracks fingers on touch screens and the gestures they make, see [`Touches`].
 //!
 //! ## Usage
 //! Use this within nodes behavior to have dynamic behavior based on user input.
@@ -13,20 +14,32 @@ use std::{collections::HashSet, sync::Arc};
 use winit::{
     event::{DeviceEvent, ElementState, MouseScrollDelta, WindowEvent},
     keyboard::PhysicalKey,
-    window::Window,
+    window::{CursorGrabMode, Window},
 }; // Importing the nalgebra_glm crate for mathematical operations
 
 pub use winit::event::MouseButton;
 pub use winit::event::TouchPhase;
 pub use winit::keyboard::KeyCode;
 
-use crate::context::Resource;
+use crate::{context::Resource, resources::Touches};
 
 impl Resource for Input {}
 
+/// how the cursor behaves inside the window
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CursorMode {
+    /// the cursor is visible and can leave the window
+    #[default]
+    Normal,
+    /// the cursor is visible but can't leave the window, useful for edge scrolling
+    Confined,
+    /// the cursor is hidden and held in place, use [`Input::mouse_delta`] for camera controls
+    Locked,
+}
+
 /// Manages the input from the user
 pub struct Input {
-    window: Arc<Window>, // local window so it can call cursor commands
+    window: Option<Arc<Window>>, // local window so it can call cursor commands
     events: Vec<WindowEvent>,
 
     pub keys: HashSet<KeyCode>,
@@ -48,15 +61,30 @@ pub struct Input {
     pub scroll_delta_pixels: math::Vec2,
     pub scroll_phase: Option<TouchPhase>,
 
-    cursor_locked: bool,
-    cursor_lock_applied: bool,
+    /// fingers on the touch screen and their gestures
+    pub touches: Touches,
+    /// the finger emulating the mouse
+    mouse_touch: Option<u64>,
+
+    cursor_mode: CursorMode,
+    applied_cursor_mode: CursorMode,
 }
 
 impl Input {
     /// Creates a new input manager with a window reference
     pub fn new(window: Arc<Window>) -> Self {
+        Self::with_window(Some(window))
+    }
+
+    /// an input manager without a window. the cursor mode is tracked but not applied and the
+    /// screen size is zero, useful for tests
+    pub fn headless() -> Self {
+        Self::with_window(None)
+    }
+
+    fn with_window(window: Option<Arc<Window>>) -> Self {
         let mut input_manager = Self {
-            window: window.clone(),
+            window,
             events: Vec::new(),
             keys: HashSet::new(),
             key_just_pressed: HashSet::new(),
@@ -72,47 +100,43 @@ impl Input {
             scroll_delta_lines: math::vec2(0.0, 0.0),
             scroll_delta_pixels: math::vec2(0.0, 0.0),
             scroll_phase: None,
-            cursor_locked: false,
-            cursor_lock_applied: false,
+            touches: Touches::default(),
+            mouse_touch: None,
+            cursor_mode: CursorMode::Normal,
+            applied_cursor_mode: CursorMode::Normal,
         };
 
-        // Apply initial cursor lock state
-        input_manager.apply_cursor_lock();
+        // Apply initial cursor mode
+        input_manager.apply_cursor_mode();
         input_manager
     }
 
-    // Internal method to apply cursor lock state
-    fn apply_cursor_lock(&mut self) {
-        if self.cursor_locked && !self.cursor_lock_applied {
-            // Lock the cursor
-            match self
-                .window
-                .set_cursor_grab(winit::window::CursorGrabMode::Locked)
-            {
-                Ok(_) => {
-                    self.cursor_lock_applied = true;
-                    self.window.set_cursor_visible(false);
-
-                    // Don't try to center cursor immediately - let it settle first
-                    // The centering will happen in the first few mouse move events
-                }
-                Err(e) => {
-                    log::error!("Failed to lock cursor: {:?}", e);
-                }
+    // Internal method to apply the cursor mode
+    fn apply_cursor_mode(&mut self) {
+        if self.cursor_mode == self.applied_cursor_mode {
+            return;
+        }
+        let Some(window) = &self.window else {
+            self.applied_cursor_mode = self.cursor_mode;
+            return;
+        };
+
+        let result = match self.cursor_mode {
+            CursorMode::Normal => window.set_cursor_grab(CursorGrabMode::None),
+            CursorMode::Confined => window.set_cursor_grab(CursorGrabMode::Confined),
+            // not every platform can lock the cursor so fall back to confining it
+            CursorMode::Locked => window
+                .set_cursor_grab(CursorGrabMode::Locked)
+                .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined)),
+        };
+
+        match result {
+            Ok(_) => {
+                self.applied_cursor_mode = self.cursor_mode;
+                window.set_cursor_visible(self.cursor_mode != CursorMode::Locked);
             }
-        } else if !self.cursor_locked && self.cursor_lock_applied {
-            // Unlock the cursor
-            match self
-                .window
-                .set_cursor_grab(winit::window::CursorGrabMode::None)
-            {
-                Ok(_) => {
-                    self.cursor_lock_applied = false;
-                    self.window.set_cursor_visible(true);
-                }
-                Err(e) => {
-                    log::error!("Failed to unlock cursor: {:?}", e);
-                }
+            Err(e) => {
+                log::error!("Failed to set cursor mode {:?}: {:?}", self.cursor_mode, e);
             }
         }
     }
@@ -190,10 +214,45 @@ impl Input {
                     }
                 }
             }
+            WindowEvent::Touch(touch) => {
+                let position = math::vec2(touch.location.x as f32, touch.location.y as f32);
+                let force = touch.force.map(|force| force.normalized() as f32);
+                let first = self.touches.count() == 0;
+                self.touches.handle(touch.id, touch.phase, position, force);
+
+                if self.touches.emulate_mouse {
+                    self.emulate_mouse(touch.id, touch.phase, position, first);
+                }
+            }
             _ => {}
         }
     }
 
+    /// the first finger down moves the cursor and holds the left button
+    fn emulate_mouse(&mut self, id: u64, phase: TouchPhase, position: math::Vec2, first: bool) {
+        if phase == TouchPhase::Started && first {
+            self.mouse_touch = Some(id);
+            self.mouse_buttons.insert(MouseButton::Left);
+            self.mouse_button_just_pressed.insert(MouseButton::Left);
+        }
+        if self.mouse_touch != Some(id) {
+            return;
+        }
+
+        self.mouse_delta += position - self.cursor_position;
+        self.cursor_position = position;
+        if matches!(phase, TouchPhase::Ended | TouchPhase::Cancelled) {
+            self.mouse_touch = None;
+            self.mouse_buttons.remove(&MouseButton::Left);
+            self.mouse_button_just_released.insert(MouseButton::Left);
+        }
+    }
+
+    /// true while a key, mouse button or finger is held down
+    pub fn is_held(&self) -> bool {
+        !self.keys.is_empty() || !self.mouse_buttons.is_empty() || self.touches.count() > 0
+    }
+
     pub fn end_frame(&mut self) {
         self.key_just_pressed.clear();
         self.key_just_released.clear();
@@ -206,30 +265,48 @@ impl Input {
         self.text_input.clear();
         self.scroll_delta_lines = Vec2::ZERO;
         self.scroll_delta_pixels = Vec2::ZERO;
+        self.touches.end_frame();
 
         self.events.clear();
     }
 
+    /// Set how the cursor behaves inside the window
+    pub fn set_cursor_mode(&mut self, mode: CursorMode) {
+        if self.cursor_mode != mode {
+            self.cursor_mode = mode;
+            self.apply_cursor_mode(); // Apply the change immediately
+        }
+    }
+
+    pub fn cursor_mode(&self) -> CursorMode {
+        self.cursor_mode
+    }
+
     /// Toggle cursor lock state
     pub fn set_cursor_locked(&mut self, locked: bool) {
-        if self.cursor_locked != locked {
-            self.cursor_locked = locked;
-            self.apply_cursor_lock(); // Apply the change immediately
-        }
+        self.set_cursor_mode(match locked {
+            true => CursorMode::Locked,
+            false => CursorMode::Normal,
+        });
     }
 
     pub fn is_cursor_locked(&self) -> bool {
-        self.cursor_locked
+        self.cursor_mode == CursorMode::Locked
     }
 
     pub fn screen_size_pixels(&self) -> math::Vec2 {
-        let size = self.window.inner_size();
+        let Some(window) = &self.window else {
+            return Vec2::ZERO;
+        };
+        let size = window.inner_size();
         math::vec2(size.width as f32, size.height as f32)
     }
 
     /// Window's scale factor / pixels-per-point (DPI), e.g. 1.0, 1.5, 2.0
     pub fn scale_factor(&self) -> f32 {
-        self.window.scale_factor() as f32
+        self.window
+            .as_ref()
+            .map_or(1.0, |window| window.scale_factor() as f32)
     }
 
     /// Logical (points) screen size = physical pixels / scale factor.
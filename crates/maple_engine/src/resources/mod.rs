@@ -1,5 +1,15 @@
 mod frame;
 mod input;
+mod settings;
+mod spatial_hash;
+mod terrain;
+mod touch;
+mod virtual_cursor;
 
 pub use frame::*;
 pub use input::*;
+pub use settings::*;
+pub use spatial_hash::*;
+pub use terrain::*;
+pub use touch::*;
+pub use virtual_cursor::*;
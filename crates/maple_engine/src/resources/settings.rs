@@ -0,0 +1,151 @@
+//! user settings kept between runs of the game, such as the graphics quality or volume
+//!
+//! [`Settings`] is a json object in `settings.json` under [`crate::fs::save_dir`]. every
+//! [`Settings::set`] writes the file again with [`crate::fs::write_atomic`] so a crash never loses
+//! a choice the user made. the app inserts the store as a resource when it starts:
+//!
+//! ```rust,ignore
+//! let mut settings = ctx.game.get_resource_mut::<Settings>();
+//! let volume: f32 = settings.get("music_volume").unwrap_or(0.8);
+//! settings.set("music_volume", volume * 0.5)?;
+//! ```
+//!
+//! on the web there is no save folder so settings only last until the page is closed
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::{Map, Value};
+
+use crate::context::Resource;
+
+/// the file name of the store in the save folder
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// a store of user settings by key, see the [module docs](self)
+#[derive(Debug, Clone, Default)]
+pub struct Settings {
+    /// `None` keeps the settings in memory only
+    path: Option<PathBuf>,
+    values: Map<String, Value>,
+}
+
+impl Resource for Settings {}
+
+impl Settings {
+    /// load the store from the game's save folder, empty if it doesn't have one yet. a file that
+    /// can't be read is logged and replaced on the next [`Settings::set`]
+    pub fn load() -> Self {
+        match crate::fs::save_dir() {
+            Ok(dir) => Self::load_from(dir.join(SETTINGS_FILE)),
+            Err(e) => {
+                log::info!("settings won't be saved: {e}");
+                Self::default()
+            }
+        }
+    }
+
+    /// load the store from a json file at `path`, empty if it doesn't exist yet
+    pub fn load_from(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let values = match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                log::warn!("ignoring broken settings in {}: {e}", path.display());
+                Map::new()
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Map::new(),
+            Err(e) => {
+                log::warn!("failed to read settings from {}: {e}", path.display());
+                Map::new()
+            }
+        };
+
+        Self {
+            path: Some(path),
+            values,
+        }
+    }
+
+    /// the file the store is saved to, `None` if it's only kept in memory
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// the value under `key`, `None` if there isn't one or it isn't a `T`
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let value = self.values.get(key)?;
+        serde_json::from_value(value.clone()).ok()
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.values.contains_key(key)
+    }
+
+    /// store `value` under `key` and save the file
+    pub fn set<T: Serialize>(&mut self, key: impl Into<String>, value: T) -> io::Result<()> {
+        let value = serde_json::to_value(value).map_err(io::Error::other)?;
+        self.values.insert(key.into(), value);
+        self.save()
+    }
+
+    /// remove the value under `key` and save the file
+    pub fn remove(&mut self, key: &str) -> io::Result<()> {
+        if self.values.remove(key).is_some() {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let json = serde_json::to_string_pretty(&self.values).map_err(io::Error::other)?;
+        crate::fs::write_atomic(path, json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_are_kept_between_loads() {
+        let dir = std::env::temp_dir().join(format!("maple_settings_test_{}", std::process::id()));
+        let path = dir.join(SETTINGS_FILE);
+
+        let mut settings = Settings::load_from(&path);
+        assert_eq!(settings.get::<f32>("volume"), None);
+        settings.set("volume", 0.5f32).unwrap();
+        settings.set("name", "maple").unwrap();
+
+        let mut loaded = Settings::load_from(&path);
+        assert_eq!(loaded.get::<f32>("volume"), Some(0.5));
+        assert_eq!(loaded.get::<String>("name").as_deref(), Some("maple"));
+        // the wrong type reads as missing
+        assert_eq!(loaded.get::<bool>("volume"), None);
+
+        loaded.remove("volume").unwrap();
+        assert!(!Settings::load_from(&path).contains("volume"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_broken_file_loads_empty() {
+        let dir =
+            std::env::temp_dir().join(format!("maple_settings_broken_{}", std::process::id()));
+        let path = dir.join(SETTINGS_FILE);
+        crate::fs::write_atomic(&path, "{ not json").unwrap();
+
+        let mut settings = Settings::load_from(&path);
+        assert!(!settings.contains("volume"));
+        settings.set("volume", 1.0f32).unwrap();
+        assert_eq!(Settings::load_from(&path).get::<f32>("volume"), Some(1.0));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,241 @@
+//! a grid of buckets used to find nodes that are close to a point without asking physics
+//!
+//! nodes are added with [`SpatialHash::insert`] and their position is kept up to date when the
+//! scene syncs world transforms through [`crate::GameContext::sync_world_transform`]. nodes that
+//! are despawned are removed on the next sync.
+//!
+//! # Example
+//! ```rust
+//! # use maple_engine::prelude::*;
+//! # use glam::Vec3;
+//! # let ctx = GameContext::new();
+//! # let enemy = ctx.scene.spawn(Empty::default()).id();
+//! ctx.get_resource_mut::<SpatialHash>().insert(enemy, Vec3::ZERO);
+//!
+//! // later, in an update handler
+//! let close = ctx.get_resource::<SpatialHash>().nearby(Vec3::new(1.0, 0.0, 0.0), 5.0);
+//! assert_eq!(close, vec![enemy]);
+//! ```
+
+use std::collections::HashMap;
+
+use glam::{IVec3, Vec3};
+
+use crate::{context::Resource, scene::NodeId};
+
+/// buckets nodes by their world position for quick proximity queries
+///
+/// queries only look at the buckets a sphere overlaps so they stay cheap as long as the cell size
+/// is close to the radius usually queried
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<IVec3, Vec<NodeId>>,
+    entries: HashMap<NodeId, (Vec3, IVec3)>,
+}
+
+impl Resource for SpatialHash {}
+
+impl Default for SpatialHash {
+    fn default() -> Self {
+        Self::new(10.0)
+    }
+}
+
+impl SpatialHash {
+    /// create an empty hash with cells `cell_size` units wide
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size: cell_size.max(f32::EPSILON),
+            cells: HashMap::new(),
+            entries: HashMap::new(),
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    /// number of nodes in the hash
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.entries.contains_key(&id)
+    }
+
+    /// the last known position of a node
+    pub fn position(&self, id: NodeId) -> Option<Vec3> {
+        self.entries.get(&id).map(|(position, _)| *position)
+    }
+
+    fn cell(&self, position: Vec3) -> IVec3 {
+        (position / self.cell_size).floor().as_ivec3()
+    }
+
+    /// add a node at a position or move it if it is already in the hash
+    pub fn insert(&mut self, id: NodeId, position: Vec3) {
+        let cell = self.cell(position);
+
+        match self.entries.insert(id, (position, cell)) {
+            Some((_, old_cell)) if old_cell == cell => return,
+            Some((_, old_cell)) => self.remove_from_cell(id, old_cell),
+            None => {}
+        }
+
+        self.cells.entry(cell).or_default().push(id);
+    }
+
+    /// remove a node. returns false if it wasn't in the hash
+    pub fn remove(&mut self, id: NodeId) -> bool {
+        let Some((_, cell)) = self.entries.remove(&id) else {
+            return false;
+        };
+        self.remove_from_cell(id, cell);
+        true
+    }
+
+    fn remove_from_cell(&mut self, id: NodeId, cell: IVec3) {
+        if let Some(bucket) = self.cells.get_mut(&cell) {
+            bucket.retain(|other| *other != id);
+            if bucket.is_empty() {
+                self.cells.remove(&cell);
+            }
+        }
+    }
+
+    /// remove every node
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.entries.clear();
+    }
+
+    /// every node within `radius` of `position` paired with its position
+    pub fn nearby_with_positions(&self, position: Vec3, radius: f32) -> Vec<(NodeId, Vec3)> {
+        let radius = radius.max(0.0);
+        let min = self.cell(position - Vec3::splat(radius));
+        let max = self.cell(position + Vec3::splat(radius));
+        let radius_squared = radius * radius;
+
+        let mut found = Vec::new();
+        let mut search = |bucket: &Vec<NodeId>| {
+            for id in bucket {
+                let (other, _) = self.entries[id];
+                if other.distance_squared(position) <= radius_squared {
+                    found.push((*id, other));
+                }
+            }
+        };
+
+        // a radius much bigger than the cells covers more cells than there are nodes
+        let span = (max.as_i64vec3() - min.as_i64vec3() + 1).as_u64vec3();
+        let covered = span.x.saturating_mul(span.y).saturating_mul(span.z);
+        if covered > self.cells.len() as u64 {
+            self.cells
+                .iter()
+                .filter(|(cell, _)| cell.cmpge(min).all() && cell.cmple(max).all())
+                .for_each(|(_, bucket)| search(bucket));
+            return found;
+        }
+
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                for z in min.z..=max.z {
+                    if let Some(bucket) = self.cells.get(&IVec3::new(x, y, z)) {
+                        search(bucket);
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    /// every node within `radius` of `position`
+    pub fn nearby(&self, position: Vec3, radius: f32) -> Vec<NodeId> {
+        self.nearby_with_positions(position, radius)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// the closest node within `radius` of `position`
+    pub fn nearest(&self, position: Vec3, radius: f32) -> Option<NodeId> {
+        self.nearby_with_positions(position, radius)
+            .into_iter()
+            .min_by(|(_, a), (_, b)| {
+                a.distance_squared(position)
+                    .total_cmp(&b.distance_squared(position))
+            })
+            .map(|(id, _)| id)
+    }
+
+    /// drop nodes that aren't in the scene anymore
+    pub(crate) fn retain(&mut self, mut keep: impl FnMut(NodeId) -> bool) {
+        let removed: Vec<NodeId> = self
+            .entries
+            .keys()
+            .copied()
+            .filter(|id| !keep(*id))
+            .collect();
+
+        for id in removed {
+            self.remove(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nearby_checks_distance_across_cells() {
+        let mut hash = SpatialHash::new(2.0);
+        let (a, b, c) = (NodeId::new(), NodeId::new(), NodeId::new());
+        hash.insert(a, Vec3::new(0.5, 0.0, 0.0));
+        hash.insert(b, Vec3::new(-1.5, 0.0, 0.0));
+        hash.insert(c, Vec3::new(2.5, 2.5, 0.0));
+
+        let mut found = hash.nearby(Vec3::ZERO, 2.0);
+        found.sort();
+        let mut expected = vec![a, b];
+        expected.sort();
+        assert_eq!(found, expected);
+        assert_eq!(hash.nearest(Vec3::new(3.0, 3.0, 0.0), 5.0), Some(c));
+    }
+
+    #[test]
+    fn test_insert_moves_between_cells() {
+        let mut hash = SpatialHash::new(1.0);
+        let id = NodeId::new();
+        hash.insert(id, Vec3::ZERO);
+        hash.insert(id, Vec3::new(10.0, 0.0, 0.0));
+
+        assert_eq!(hash.len(), 1);
+        assert!(hash.nearby(Vec3::ZERO, 1.0).is_empty());
+        assert_eq!(hash.nearby(Vec3::new(10.0, 0.0, 0.0), 0.5), vec![id]);
+
+        assert!(hash.remove(id));
+        assert!(hash.is_empty());
+        assert!(hash.nearby(Vec3::new(10.0, 0.0, 0.0), 0.5).is_empty());
+    }
+
+    #[test]
+    fn test_huge_radius_only_visits_occupied_cells() {
+        let mut hash = SpatialHash::new(0.5);
+        let (near, far) = (NodeId::new(), NodeId::new());
+        hash.insert(near, Vec3::new(1.0, 0.0, 0.0));
+        hash.insert(far, Vec3::new(0.0, -1.0e6, 0.0));
+
+        // this covers more cells than fit in memory so it has to go through the occupied ones
+        let mut found = hash.nearby(Vec3::ZERO, 1.0e7);
+        found.sort();
+        assert_eq!(found, vec![near, far]);
+        assert_eq!(hash.nearby(Vec3::ZERO, f32::INFINITY).len(), 2);
+        assert_eq!(hash.nearest(Vec3::ZERO, f32::MAX), Some(near));
+    }
+}
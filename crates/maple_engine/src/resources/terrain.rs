@@ -0,0 +1,264 @@
+//! a noise heightfield that can be sampled the same way on the CPU and in a shader
+//!
+//! a raymarched terrain only exists on the GPU so nothing on the CPU knows where the ground is.
+//! insert a [`TerrainParams`] resource, upload [`TerrainParams::to_uniform`] to the shader and
+//! build it with [`TerrainParams::WGSL`], then use [`TerrainParams::height_at`] or
+//! [`TerrainParams::raycast`] to put gameplay objects on the same ground that gets drawn.
+//!
+//! # Example
+//! ```rust
+//! # use maple_engine::prelude::*;
+//! # use glam::Vec3;
+//! # let mut ctx = GameContext::new();
+//! ctx.insert_resource(TerrainParams {
+//!     height: 40.0,
+//!     seed: 7,
+//!     ..Default::default()
+//! });
+//!
+//! let terrain = ctx.get_resource::<TerrainParams>();
+//! let spawn = terrain.place(Vec3::new(12.0, 0.0, -30.0));
+//! assert_eq!(spawn.y, terrain.height_at(12.0, -30.0));
+//! ```
+
+use glam::{Vec2, Vec3};
+
+use crate::{
+    context::Resource,
+    utils::noise::{Fbm, NoiseKind},
+};
+
+/// height fields closer than this are treated as touching when raymarching
+const HIT_DISTANCE: f32 = 0.001;
+
+/// the shape of a noise terrain
+///
+/// the height at a point is `base_height + fbm(point + offset) * height`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TerrainParams {
+    /// the noise layered to make the ground
+    pub noise: Fbm,
+    pub seed: u32,
+    /// how far above and below `base_height` the ground can reach
+    pub height: f32,
+    /// the height of flat ground
+    pub base_height: f32,
+    /// moves the noise across the ground
+    pub offset: Vec2,
+}
+
+impl Resource for TerrainParams {}
+
+impl Default for TerrainParams {
+    fn default() -> Self {
+        Self {
+            noise: Fbm {
+                octaves: 5,
+                frequency: 0.01,
+                ..Default::default()
+            },
+            seed: 0,
+            height: 20.0,
+            base_height: 0.0,
+            offset: Vec2::ZERO,
+        }
+    }
+}
+
+impl TerrainParams {
+    /// the WGSL version of the terrain, including [`crate::utils::noise::NOISE_WGSL`]
+    ///
+    /// it declares a `TerrainParams` struct laid out like [`TerrainUniform`] and the functions
+    /// `terrain_height`, `terrain_normal` and `terrain_raymarch` which match the methods here
+    pub const WGSL: &str = concat!(
+        include_str!("../utils/noise.wgsl"),
+        include_str!("terrain.wgsl")
+    );
+
+    /// the height of the ground at a point on the XZ plane
+    pub fn height_at(&self, x: f32, z: f32) -> f32 {
+        let point = Vec2::new(x, z) + self.offset;
+        self.base_height + self.noise.sample2(point, self.seed) * self.height
+    }
+
+    /// the up facing normal of the ground at a point on the XZ plane
+    pub fn normal_at(&self, x: f32, z: f32) -> Vec3 {
+        let step = 0.05;
+        let left = self.height_at(x - step, z);
+        let right = self.height_at(x + step, z);
+        let down = self.height_at(x, z - step);
+        let up = self.height_at(x, z + step);
+
+        Vec3::new(left - right, 2.0 * step, down - up).normalize()
+    }
+
+    /// `position` moved up or down onto the ground
+    pub fn place(&self, position: Vec3) -> Vec3 {
+        Vec3::new(
+            position.x,
+            self.height_at(position.x, position.z),
+            position.z,
+        )
+    }
+
+    /// how far above the ground a position is, negative when it is underground
+    pub fn height_above(&self, position: Vec3) -> f32 {
+        position.y - self.height_at(position.x, position.z)
+    }
+
+    /// march a ray until it goes under the ground and return the distance to the hit
+    ///
+    /// this is the same march `terrain_raymarch` does in WGSL so a ray cast from the camera hits
+    /// the pixel the shader drew there. very steep terrain can be stepped through
+    pub fn raycast(&self, origin: Vec3, direction: Vec3, max_distance: f32) -> Option<f32> {
+        let direction = direction.normalize_or_zero();
+        if direction == Vec3::ZERO {
+            return None;
+        }
+
+        let mut previous = 0.0;
+        let mut distance = 0.0;
+
+        for _ in 0..256 {
+            let gap = self.height_above(origin + direction * distance);
+
+            if gap < HIT_DISTANCE {
+                // narrow down between the last point above the ground and the first one below it
+                let (mut above, mut below) = (previous, distance);
+                for _ in 0..8 {
+                    let middle = (above + below) * 0.5;
+                    match self.height_above(origin + direction * middle) < HIT_DISTANCE {
+                        true => below = middle,
+                        false => above = middle,
+                    }
+                }
+                return Some(below);
+            }
+
+            previous = distance;
+            distance += (gap * 0.5).max(0.01 + distance * 0.002);
+            if distance > max_distance {
+                return None;
+            }
+        }
+
+        None
+    }
+
+    /// sample heights on a grid centered on `center` and spanning `size`
+    ///
+    /// returns `resolution.0` samples along X for each of the `resolution.1` rows along Z. both
+    /// are at least 2 so the corners are always included
+    pub fn sample_grid(&self, center: Vec2, size: Vec2, resolution: (usize, usize)) -> Vec<f32> {
+        let (columns, rows) = (resolution.0.max(2), resolution.1.max(2));
+        let start = center - size * 0.5;
+        let spacing = size / Vec2::new((columns - 1) as f32, (rows - 1) as f32);
+
+        let mut heights = Vec::with_capacity(columns * rows);
+        for row in 0..rows {
+            for column in 0..columns {
+                let point = start + spacing * Vec2::new(column as f32, row as f32);
+                heights.push(self.height_at(point.x, point.y));
+            }
+        }
+        heights
+    }
+
+    /// the parameters laid out for the `TerrainParams` struct in [`TerrainParams::WGSL`]
+    pub fn to_uniform(&self) -> TerrainUniform {
+        TerrainUniform {
+            offset: self.offset.to_array(),
+            frequency: self.noise.frequency,
+            lacunarity: self.noise.lacunarity,
+            gain: self.noise.gain,
+            height: self.height,
+            base_height: self.base_height,
+            seed: self.seed,
+            octaves: self.noise.octaves,
+            kind: match self.noise.kind {
+                NoiseKind::Perlin => 0,
+                NoiseKind::Simplex => 1,
+            },
+            _padding: [0; 2],
+        }
+    }
+}
+
+/// [`TerrainParams`] as it's laid out in a uniform buffer
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TerrainUniform {
+    pub offset: [f32; 2],
+    pub frequency: f32,
+    pub lacunarity: f32,
+    pub gain: f32,
+    pub height: f32,
+    pub base_height: f32,
+    pub seed: u32,
+    pub octaves: u32,
+    /// 0 for perlin and 1 for simplex
+    pub kind: u32,
+    pub _padding: [u32; 2],
+}
+
+impl TerrainUniform {
+    /// the bytes to write to the uniform buffer
+    pub fn to_bytes(&self) -> [u8; 48] {
+        let words = [
+            self.offset[0].to_bits(),
+            self.offset[1].to_bits(),
+            self.frequency.to_bits(),
+            self.lacunarity.to_bits(),
+            self.gain.to_bits(),
+            self.height.to_bits(),
+            self.base_height.to_bits(),
+            self.seed,
+            self.octaves,
+            self.kind,
+            self._padding[0],
+            self._padding[1],
+        ];
+
+        let mut bytes = [0; 48];
+        for (chunk, word) in bytes.chunks_exact_mut(4).zip(words) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raycast_lands_on_height() {
+        let terrain = TerrainParams {
+            seed: 3,
+            ..Default::default()
+        };
+
+        for (x, z) in [(0.0, 0.0), (35.5, -12.0), (-80.0, 140.25)] {
+            let ground = terrain.height_at(x, z);
+            let from = Vec3::new(x, ground + 50.0, z);
+
+            let hit = terrain.raycast(from, Vec3::NEG_Y, 100.0).unwrap();
+            assert!((from.y - hit - ground).abs() < 0.01);
+            assert!((terrain.place(from).y - ground).abs() < f32::EPSILON);
+        }
+
+        let sideways = terrain.raycast(Vec3::new(0.0, 1000.0, 0.0), Vec3::X, 50.0);
+        assert_eq!(sideways, None);
+    }
+
+    #[test]
+    fn test_grid_matches_height() {
+        let terrain = TerrainParams::default();
+        let heights = terrain.sample_grid(Vec2::new(10.0, 10.0), Vec2::new(20.0, 40.0), (3, 5));
+
+        assert_eq!(heights.len(), 15);
+        assert_eq!(heights[0], terrain.height_at(0.0, -10.0));
+        assert_eq!(heights[14], terrain.height_at(20.0, 30.0));
+        assert_eq!(heights[3 + 1], terrain.height_at(10.0, 0.0));
+    }
+}
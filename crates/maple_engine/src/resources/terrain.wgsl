@@ -0,0 +1,81 @@
+// the GPU side of maple_engine::resources::TerrainParams, needs noise.wgsl in front of it
+//
+// keep this in step with terrain.rs so the ground gameplay stands on is the ground that's drawn
+
+struct TerrainParams {
+    offset: vec2<f32>,
+    frequency: f32,
+    lacunarity: f32,
+    gain: f32,
+    height: f32,
+    base_height: f32,
+    seed: u32,
+    octaves: u32,
+    kind: u32,
+    _padding: vec2<u32>,
+}
+
+const TERRAIN_HIT_DISTANCE: f32 = 0.001;
+
+fn terrain_height(point: vec2<f32>, terrain: TerrainParams) -> f32 {
+    let noise = noise_fbm2(
+        point + terrain.offset,
+        terrain.seed,
+        terrain.kind,
+        terrain.octaves,
+        terrain.frequency,
+        terrain.lacunarity,
+        terrain.gain,
+    );
+    return terrain.base_height + noise * terrain.height;
+}
+
+fn terrain_normal(point: vec2<f32>, terrain: TerrainParams) -> vec3<f32> {
+    let step = 0.05;
+    let left = terrain_height(point - vec2<f32>(step, 0.0), terrain);
+    let right = terrain_height(point + vec2<f32>(step, 0.0), terrain);
+    let down = terrain_height(point - vec2<f32>(0.0, step), terrain);
+    let up = terrain_height(point + vec2<f32>(0.0, step), terrain);
+    return normalize(vec3<f32>(left - right, 2.0 * step, down - up));
+}
+
+fn terrain_height_above(position: vec3<f32>, terrain: TerrainParams) -> f32 {
+    return position.y - terrain_height(position.xz, terrain);
+}
+
+// the distance along the ray to the ground, or -1.0 when it misses
+fn terrain_raymarch(origin: vec3<f32>, ray: vec3<f32>, max_distance: f32, terrain: TerrainParams) -> f32 {
+    if dot(ray, ray) == 0.0 {
+        return -1.0;
+    }
+    let direction = normalize(ray);
+
+    var previous = 0.0;
+    var distance = 0.0;
+
+    for (var i = 0; i < 256; i++) {
+        let gap = terrain_height_above(origin + direction * distance, terrain);
+
+        if gap < TERRAIN_HIT_DISTANCE {
+            var above = previous;
+            var below = distance;
+            for (var j = 0; j < 8; j++) {
+                let middle = (above + below) * 0.5;
+                if terrain_height_above(origin + direction * middle, terrain) < TERRAIN_HIT_DISTANCE {
+                    below = middle;
+                } else {
+                    above = middle;
+                }
+            }
+            return below;
+        }
+
+        previous = distance;
+        distance += max(gap * 0.5, 0.01 + distance * 0.002);
+        if distance > max_distance {
+            return -1.0;
+        }
+    }
+
+    return -1.0;
+}
@@ -0,0 +1,439 @@
+//! fingers on a touch screen and the gestures they make
+//!
+//! [`Input::touches`](crate::resources::Input::touches) tracks every finger on the screen by the
+//! id the platform gives it and recognizes taps, double taps, long presses, swipes and two
+//! finger pinches and rotations from them. each gesture is in [`Touches::gestures`] for the frame
+//! it was made in and is emitted to the scene as well, along with a [`TouchInput`] every time a
+//! finger goes down, moves or lifts.
+//!
+//! the first finger on the screen also moves the cursor and holds the left mouse button so
+//! anything reading the mouse works with touch, see [`Touches::emulate_mouse`].
+//!
+//! # Example
+//! ```rust
+//! # use maple_engine::prelude::*;
+//! # fn handler(ctx: EventCtx<Update, Empty>) {
+//! let input = ctx.game.get_resource::<Input>();
+//! for gesture in input.touches.gestures() {
+//!     match gesture {
+//!         Gesture::Tap { position } => println!("tapped at {position}"),
+//!         Gesture::Pinch { scale, .. } => println!("zoomed by {scale}"),
+//!         _ => {}
+//!     }
+//! }
+//! # }
+//! ```
+
+use std::collections::HashMap;
+
+use glam::Vec2;
+use winit::event::TouchPhase;
+
+use crate::components::EventLabel;
+
+/// one finger on the screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Touch {
+    /// the platform's id for the finger, the same from when it goes down until it lifts
+    pub id: u64,
+    /// in pixels from the top left of the window
+    pub position: Vec2,
+    /// where the finger went down
+    pub start_position: Vec2,
+    /// how far the finger moved this frame
+    pub delta: Vec2,
+    pub phase: TouchPhase,
+    /// how hard the finger presses from 0 to 1 on screens that can tell
+    pub force: Option<f32>,
+    /// seconds since the finger went down
+    pub held: f32,
+}
+
+/// emitted to the scene when a finger goes down, moves or lifts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TouchInput(pub Touch);
+impl EventLabel for TouchInput {}
+
+/// a gesture recognized from the fingers on the screen, emitted to the scene when it happens
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Gesture {
+    /// a finger went down and lifted quickly without moving
+    Tap { position: Vec2 },
+    /// a second tap close to the first one, the first is still reported as a tap
+    DoubleTap { position: Vec2 },
+    /// a finger held still for [`Touches::long_press_time`], reported once while it's still down
+    LongPress { position: Vec2 },
+    /// a finger moved quickly and lifted, `velocity` is in pixels per second
+    Swipe {
+        start: Vec2,
+        end: Vec2,
+        velocity: Vec2,
+    },
+    /// two fingers moved apart or together this frame, `scale` is the new distance between them
+    /// over the old one
+    Pinch { center: Vec2, scale: f32 },
+    /// two fingers turned around each other this frame by `angle` radians, clockwise on screen
+    Rotate { center: Vec2, angle: f32 },
+}
+impl EventLabel for Gesture {}
+
+/// the fingers on the screen and the gestures made with them, see the [module docs](self)
+#[derive(Debug, Clone)]
+pub struct Touches {
+    /// the longest a finger can be down for a tap in seconds. Default: 0.3
+    pub tap_time: f32,
+    /// the furthest a finger can move in pixels and still tap or long press. Default: 12
+    pub tap_distance: f32,
+    /// the most seconds between the taps of a double tap. Default: 0.3
+    pub double_tap_time: f32,
+    /// seconds a finger has to be held still for a long press. Default: 0.5
+    pub long_press_time: f32,
+    /// the least a finger has to move in pixels for a swipe. Default: 60
+    pub swipe_distance: f32,
+    /// the slowest a finger can lift in pixels per second and still swipe. Default: 400
+    pub swipe_speed: f32,
+    /// the first finger down moves the cursor and holds the left mouse button. Default: true
+    pub emulate_mouse: bool,
+
+    active: HashMap<u64, Touch>,
+    /// fingers in the order they went down, the first one emulates the mouse
+    order: Vec<u64>,
+    /// every change to a finger this frame
+    changes: Vec<Touch>,
+    gestures: Vec<Gesture>,
+    /// seconds since the touches were made, touches are timed with it
+    time: f32,
+    /// fingers that long pressed or were down with others so they don't tap or swipe
+    claimed: Vec<u64>,
+    /// when and where the last tap was for double taps
+    last_tap: Option<(f32, Vec2)>,
+    /// the distance and angle between the first two fingers at the last step
+    pair: Option<(u64, u64, f32, f32)>,
+}
+
+impl Default for Touches {
+    fn default() -> Self {
+        Self {
+            tap_time: 0.3,
+            tap_distance: 12.0,
+            double_tap_time: 0.3,
+            long_press_time: 0.5,
+            swipe_distance: 60.0,
+            swipe_speed: 400.0,
+            emulate_mouse: true,
+            active: HashMap::new(),
+            order: Vec::new(),
+            changes: Vec::new(),
+            gestures: Vec::new(),
+            time: 0.0,
+            claimed: Vec::new(),
+            last_tap: None,
+            pair: None,
+        }
+    }
+}
+
+impl Touches {
+    /// a finger on the screen by its id
+    pub fn get(&self, id: u64) -> Option<&Touch> {
+        self.active.get(&id)
+    }
+
+    /// every finger on the screen in the order they went down
+    pub fn iter(&self) -> impl Iterator<Item = &Touch> {
+        self.order.iter().filter_map(|id| self.active.get(id))
+    }
+
+    /// the number of fingers on the screen
+    pub fn count(&self) -> usize {
+        self.active.len()
+    }
+
+    /// the finger that went down first
+    pub fn primary(&self) -> Option<&Touch> {
+        self.order.first().and_then(|id| self.active.get(id))
+    }
+
+    /// fingers that went down this frame
+    pub fn just_started(&self) -> impl Iterator<Item = &Touch> {
+        self.changes
+            .iter()
+            .filter(|touch| touch.phase == TouchPhase::Started)
+    }
+
+    /// fingers that lifted or were cancelled this frame
+    pub fn just_ended(&self) -> impl Iterator<Item = &Touch> {
+        self.changes
+            .iter()
+            .filter(|touch| matches!(touch.phase, TouchPhase::Ended | TouchPhase::Cancelled))
+    }
+
+    /// every change to a finger this frame in the order they happened
+    pub fn changes(&self) -> &[Touch] {
+        &self.changes
+    }
+
+    /// the gestures made this frame
+    pub fn gestures(&self) -> &[Gesture] {
+        &self.gestures
+    }
+
+    /// a finger went down, moved or lifted
+    pub fn handle(&mut self, id: u64, phase: TouchPhase, position: Vec2, force: Option<f32>) {
+        let touch = match phase {
+            TouchPhase::Started => {
+                let touch = Touch {
+                    id,
+                    position,
+                    start_position: position,
+                    delta: Vec2::ZERO,
+                    phase,
+                    force,
+                    held: 0.0,
+                };
+                if self.active.insert(id, touch).is_none() {
+                    self.order.push(id);
+                }
+                if self.order.len() > 1 {
+                    self.claimed.extend(self.order.iter().copied());
+                }
+                touch
+            }
+            _ => {
+                let Some(touch) = self.active.get_mut(&id) else {
+                    return;
+                };
+                touch.delta += position - touch.position;
+                touch.position = position;
+                touch.phase = phase;
+                touch.force = force;
+                *touch
+            }
+        };
+        self.changes.push(touch);
+
+        match phase {
+            TouchPhase::Ended => {
+                self.lifted(&touch);
+                self.remove(id);
+            }
+            TouchPhase::Cancelled => self.remove(id),
+            _ => {}
+        }
+    }
+
+    fn remove(&mut self, id: u64) {
+        self.active.remove(&id);
+        self.order.retain(|other| *other != id);
+        self.claimed.retain(|other| *other != id);
+    }
+
+    /// recognize taps and swipes from a finger that lifted
+    fn lifted(&mut self, touch: &Touch) {
+        if self.claimed.contains(&touch.id) {
+            return;
+        }
+
+        let moved = touch.position - touch.start_position;
+        if touch.held <= self.tap_time && moved.length() <= self.tap_distance {
+            self.gestures.push(Gesture::Tap {
+                position: touch.position,
+            });
+            match self.last_tap {
+                Some((time, position))
+                    if self.time - time <= self.double_tap_time
+                        && position.distance(touch.position) <= self.tap_distance * 2.0 =>
+                {
+                    self.gestures.push(Gesture::DoubleTap {
+                        position: touch.position,
+                    });
+                    self.last_tap = None;
+                }
+                _ => self.last_tap = Some((self.time, touch.position)),
+            }
+            return;
+        }
+
+        let velocity = moved / touch.held.max(f32::EPSILON);
+        if moved.length() >= self.swipe_distance && velocity.length() >= self.swipe_speed {
+            self.gestures.push(Gesture::Swipe {
+                start: touch.start_position,
+                end: touch.position,
+                velocity,
+            });
+        }
+    }
+
+    /// move the touches `dt` seconds on and recognize the gestures of fingers still down, called
+    /// at the start of every frame
+    pub fn step(&mut self, dt: f32) {
+        self.time += dt;
+        for touch in self.active.values_mut() {
+            touch.held += dt;
+        }
+
+        if let [id] = self.order[..] {
+            let touch = self.active[&id];
+            if touch.held >= self.long_press_time
+                && touch.position.distance(touch.start_position) <= self.tap_distance
+                && !self.claimed.contains(&id)
+            {
+                self.claimed.push(id);
+                self.gestures.push(Gesture::LongPress {
+                    position: touch.position,
+                });
+            }
+        }
+
+        self.step_pair();
+    }
+
+    /// pinches and rotations of the first two fingers since the last step
+    fn step_pair(&mut self) {
+        let [a, b, ..] = self.order[..] else {
+            self.pair = None;
+            return;
+        };
+        let (first, second) = (self.active[&a].position, self.active[&b].position);
+        let offset = second - first;
+        let (distance, angle) = (offset.length(), offset.y.atan2(offset.x));
+        let center = (first + second) * 0.5;
+
+        if let Some((last_a, last_b, last_distance, last_angle)) = self.pair
+            && (last_a, last_b) == (a, b)
+        {
+            if distance != last_distance && last_distance > 0.0 {
+                self.gestures.push(Gesture::Pinch {
+                    center,
+                    scale: distance / last_distance,
+                });
+            }
+
+            // the shortest way round so going past half a turn doesn't flip the angle
+            let turned = (angle - last_angle + std::f32::consts::PI)
+                .rem_euclid(std::f32::consts::TAU)
+                - std::f32::consts::PI;
+            if turned != 0.0 {
+                self.gestures.push(Gesture::Rotate {
+                    center,
+                    angle: turned,
+                });
+            }
+        }
+
+        self.pair = Some((a, b, distance, angle));
+    }
+
+    /// forget this frame's changes and gestures
+    pub fn end_frame(&mut self) {
+        self.changes.clear();
+        self.gestures.clear();
+        for touch in self.active.values_mut() {
+            touch.delta = Vec2::ZERO;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(touches: &mut Touches, dt: f32) -> Vec<Gesture> {
+        touches.step(dt);
+        let gestures = touches.gestures().to_vec();
+        touches.end_frame();
+        gestures
+    }
+
+    #[test]
+    fn test_taps_and_long_press() {
+        let mut touches = Touches::default();
+        let position = Vec2::new(100.0, 200.0);
+
+        touches.handle(0, TouchPhase::Started, position, None);
+        frame(&mut touches, 0.1);
+        touches.handle(0, TouchPhase::Ended, position, None);
+        assert_eq!(frame(&mut touches, 0.1), vec![Gesture::Tap { position }]);
+
+        touches.handle(1, TouchPhase::Started, position, None);
+        touches.handle(1, TouchPhase::Ended, position, None);
+        assert_eq!(
+            frame(&mut touches, 0.1),
+            vec![Gesture::Tap { position }, Gesture::DoubleTap { position }]
+        );
+
+        // held still it long presses once and doesn't tap when lifted
+        touches.handle(2, TouchPhase::Started, position, None);
+        frame(&mut touches, 0.3);
+        assert_eq!(
+            frame(&mut touches, 0.3),
+            vec![Gesture::LongPress { position }]
+        );
+        assert_eq!(frame(&mut touches, 0.3), vec![]);
+        touches.handle(2, TouchPhase::Ended, position, None);
+        assert_eq!(frame(&mut touches, 0.1), vec![]);
+        assert_eq!(touches.count(), 0);
+    }
+
+    #[test]
+    fn test_swipe() {
+        let mut touches = Touches::default();
+
+        touches.handle(0, TouchPhase::Started, Vec2::new(0.0, 100.0), None);
+        frame(&mut touches, 0.1);
+        touches.handle(0, TouchPhase::Moved, Vec2::new(150.0, 100.0), None);
+        assert_eq!(touches.get(0).unwrap().delta, Vec2::new(150.0, 0.0));
+        touches.handle(0, TouchPhase::Ended, Vec2::new(200.0, 100.0), None);
+
+        let gestures = frame(&mut touches, 0.1);
+        let [
+            Gesture::Swipe {
+                start,
+                end,
+                velocity,
+            },
+        ] = gestures[..]
+        else {
+            panic!("expected a swipe, got {gestures:?}");
+        };
+        assert_eq!(
+            (start, end),
+            (Vec2::new(0.0, 100.0), Vec2::new(200.0, 100.0))
+        );
+        assert!((velocity.x - 2000.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_pinch_and_rotate() {
+        let mut touches = Touches::default();
+
+        touches.handle(0, TouchPhase::Started, Vec2::new(100.0, 100.0), None);
+        touches.handle(1, TouchPhase::Started, Vec2::new(200.0, 100.0), None);
+        assert_eq!(frame(&mut touches, 0.016), vec![]);
+
+        // fingers spread to twice the distance
+        touches.handle(0, TouchPhase::Moved, Vec2::new(50.0, 100.0), None);
+        touches.handle(1, TouchPhase::Moved, Vec2::new(250.0, 100.0), None);
+        assert_eq!(
+            frame(&mut touches, 0.016),
+            vec![Gesture::Pinch {
+                center: Vec2::new(150.0, 100.0),
+                scale: 2.0
+            }]
+        );
+
+        // a quarter turn clockwise on screen
+        touches.handle(0, TouchPhase::Moved, Vec2::new(150.0, 0.0), None);
+        touches.handle(1, TouchPhase::Moved, Vec2::new(150.0, 200.0), None);
+        let gestures = frame(&mut touches, 0.016);
+        let [Gesture::Rotate { angle, .. }] = gestures[..] else {
+            panic!("expected a rotation, got {gestures:?}");
+        };
+        assert!((angle - std::f32::consts::FRAC_PI_2).abs() < 1e-5);
+
+        // lifting out of a pinch doesn't tap
+        touches.handle(0, TouchPhase::Ended, Vec2::new(150.0, 0.0), None);
+        touches.handle(1, TouchPhase::Ended, Vec2::new(150.0, 200.0), None);
+        assert_eq!(frame(&mut touches, 0.016), vec![]);
+    }
+}
@@ -0,0 +1,330 @@
+//! a cursor moved with a gamepad stick for console style menus
+//!
+//! the game feeds the stick and buttons of its gamepad into the [`VirtualCursor`] resource and
+//! the cursor writes its position and clicks into [`Input`] at the start of every frame, so egui,
+//! picking and anything else reading the mouse works with it unchanged. the stick goes through a
+//! dead zone and a response curve and the cursor speeds up the longer it's held. ui registers
+//! the rects it can be clicked on with [`VirtualCursor::add_target`], the cursor slows down over
+//! them and glides onto the closest one once the stick is let go.
+//!
+//! moving the real mouse takes the cursor back, the virtual cursor carries on from wherever the
+//! mouse left it.
+//!
+//! # Example
+//! ```rust
+//! # use maple_engine::prelude::*;
+//! # use glam::Vec2;
+//! # let mut ctx = GameContext::new();
+//! ctx.insert_resource(VirtualCursor::default());
+//!
+//! // in an update handler, with the gamepad's left stick and south button
+//! let mut cursor = ctx.get_resource_mut::<VirtualCursor>();
+//! cursor.set_stick(Vec2::new(0.5, -0.2));
+//! cursor.set_button(MouseButton::Left, true);
+//!
+//! // while drawing a menu
+//! cursor.add_target(Vec2::new(100.0, 40.0), Vec2::new(260.0, 80.0));
+//! ```
+
+use std::collections::HashSet;
+
+use glam::Vec2;
+
+use crate::{
+    context::Resource,
+    resources::{Input, MouseButton},
+    utils::smoothing::damp,
+};
+
+/// a rect the cursor is pulled onto, in pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Target {
+    min: Vec2,
+    max: Vec2,
+}
+
+impl Target {
+    fn center(&self) -> Vec2 {
+        (self.min + self.max) * 0.5
+    }
+
+    fn contains(&self, point: Vec2) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+
+    fn distance(&self, point: Vec2) -> f32 {
+        point.clamp(self.min, self.max).distance(point)
+    }
+}
+
+/// a mouse cursor driven by a gamepad stick, see the [module docs](self)
+#[derive(Debug, Clone)]
+pub struct VirtualCursor {
+    /// while false the cursor leaves [`Input`] alone. Default: true
+    pub enabled: bool,
+    /// pixels per second with the stick all the way over. Default: 1400
+    pub speed: f32,
+    /// stick lengths below this are ignored. Default: 0.15
+    pub dead_zone: f32,
+    /// the power the stick is raised to after the dead zone, above 1 gives finer control of
+    /// small movements. Default: 2
+    pub curve: f32,
+    /// seconds the stick has to be held before the cursor reaches full speed. Default: 0.3
+    pub acceleration_time: f32,
+    /// how much slower the cursor moves over a target. Default: 0.5
+    pub target_friction: f32,
+    /// how far from a target the cursor can be to glide onto it. Default: 64
+    pub snap_radius: f32,
+    /// how quickly the cursor glides onto a target, higher is faster. Default: 14
+    pub snap_speed: f32,
+
+    position: Vec2,
+    stick: Vec2,
+    /// seconds the stick has been out of the dead zone
+    held: f32,
+    buttons: HashSet<MouseButton>,
+    /// the buttons the cursor is holding down in [`Input`]
+    pressed: HashSet<MouseButton>,
+    /// the position written to [`Input`] last frame, anything else means the mouse moved
+    written: Option<Vec2>,
+    /// set once the stick or a button is used and cleared when the mouse moves
+    active: bool,
+    targets: Vec<Target>,
+    /// the targets added during the last frame, the ones the cursor uses
+    last_targets: Vec<Target>,
+}
+
+impl Resource for VirtualCursor {}
+
+impl Default for VirtualCursor {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            speed: 1400.0,
+            dead_zone: 0.15,
+            curve: 2.0,
+            acceleration_time: 0.3,
+            target_friction: 0.5,
+            snap_radius: 64.0,
+            snap_speed: 14.0,
+            position: Vec2::ZERO,
+            stick: Vec2::ZERO,
+            held: 0.0,
+            buttons: HashSet::new(),
+            pressed: HashSet::new(),
+            written: None,
+            active: false,
+            targets: Vec::new(),
+            last_targets: Vec::new(),
+        }
+    }
+}
+
+impl VirtualCursor {
+    /// where the cursor is in pixels from the top left of the window
+    pub fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    /// move the cursor straight to `position` in pixels
+    pub fn set_position(&mut self, position: Vec2) {
+        self.position = position;
+    }
+
+    /// the stick moving the cursor, each axis from -1 to 1 with y pointing up like gamepads
+    /// report it
+    pub fn set_stick(&mut self, stick: Vec2) {
+        self.stick = stick.clamp_length_max(1.0);
+    }
+
+    /// hold or let go of a mouse button, usually from a gamepad face button
+    pub fn set_button(&mut self, button: MouseButton, pressed: bool) {
+        match pressed {
+            true => self.buttons.insert(button),
+            false => self.buttons.remove(&button),
+        };
+    }
+
+    /// a rect in pixels the cursor can be snapped onto, add every focusable element each frame
+    /// it is drawn
+    pub fn add_target(&mut self, min: Vec2, max: Vec2) {
+        self.targets.push(Target {
+            min: min.min(max),
+            max: min.max(max),
+        });
+    }
+
+    /// the target under the cursor as its min and max corners
+    pub fn hovered_target(&self) -> Option<(Vec2, Vec2)> {
+        self.last_targets
+            .iter()
+            .find(|target| target.contains(self.position))
+            .map(|target| (target.min, target.max))
+    }
+
+    /// how fast the stick moves the cursor in pixels per second before targets slow it
+    fn velocity(&self) -> Vec2 {
+        let length = self.stick.length();
+        if length <= self.dead_zone {
+            return Vec2::ZERO;
+        }
+
+        let tilt = ((length - self.dead_zone) / (1.0 - self.dead_zone).max(f32::EPSILON))
+            .clamp(0.0, 1.0)
+            .powf(self.curve.max(0.0));
+        let ramp = match self.acceleration_time > 0.0 {
+            true => (self.held / self.acceleration_time).clamp(0.2, 1.0),
+            false => 1.0,
+        };
+
+        // screens count y downwards
+        let direction = self.stick / length * Vec2::new(1.0, -1.0);
+        direction * tilt * ramp * self.speed
+    }
+
+    /// move the cursor `dt` seconds on in a window `screen` pixels wide
+    pub(crate) fn step(&mut self, screen: Vec2, dt: f32) {
+        self.last_targets = std::mem::take(&mut self.targets);
+
+        let velocity = self.velocity();
+        match velocity == Vec2::ZERO {
+            true => {
+                self.held = 0.0;
+                self.snap(dt);
+            }
+            false => {
+                self.held += dt;
+                let friction = match self.hovered_target() {
+                    Some(_) => self.target_friction,
+                    None => 1.0,
+                };
+                self.position += velocity * friction * dt;
+            }
+        }
+
+        self.position = self.position.clamp(Vec2::ZERO, screen.max(Vec2::ZERO));
+    }
+
+    /// glide onto the center of the closest target in reach
+    fn snap(&mut self, dt: f32) {
+        let Some(target) = self
+            .last_targets
+            .iter()
+            .filter(|target| target.distance(self.position) <= self.snap_radius)
+            .min_by(|a, b| {
+                let a = a.distance(self.position);
+                let b = b.distance(self.position);
+                a.total_cmp(&b)
+            })
+        else {
+            return;
+        };
+
+        self.position = damp(self.position, target.center(), self.snap_speed, dt);
+    }
+
+    /// move the cursor and write it into `input` as if the mouse did it, called by
+    /// [`crate::GameContext::begin_frame`]
+    pub(crate) fn drive(&mut self, input: &mut Input, dt: f32) {
+        // the real mouse moved since last frame so carry on from where it is
+        if self.written != Some(input.cursor_position) {
+            self.position = input.cursor_position;
+            self.active = false;
+        }
+        if self.velocity() != Vec2::ZERO || !self.buttons.is_empty() {
+            self.active = true;
+        }
+
+        match self.enabled && self.active {
+            true => {
+                self.step(input.screen_size_pixels(), dt);
+
+                let moved = self.position - input.cursor_position;
+                input.cursor_position = self.position;
+                input.mouse_delta += moved;
+                self.written = Some(self.position);
+            }
+            // the cursor doesn't glide onto targets under a mouse that stopped moving
+            false => {
+                self.last_targets = std::mem::take(&mut self.targets);
+                self.written = None;
+            }
+        }
+
+        let held = match self.enabled {
+            true => self.buttons.clone(),
+            false => HashSet::new(),
+        };
+        for button in &held {
+            if self.pressed.insert(*button) && input.mouse_buttons.insert(*button) {
+                input.mouse_button_just_pressed.insert(*button);
+            }
+        }
+        // only buttons the cursor pressed are let go so the real mouse keeps its own
+        for button in self.pressed.clone().difference(&held) {
+            self.pressed.remove(button);
+            if input.mouse_buttons.remove(button) {
+                input.mouse_button_just_released.insert(*button);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCREEN: Vec2 = Vec2::new(1920.0, 1080.0);
+
+    #[test]
+    fn test_stick_moves_with_dead_zone_and_acceleration() {
+        let mut cursor = VirtualCursor::default();
+        cursor.set_position(Vec2::new(500.0, 500.0));
+
+        cursor.set_stick(Vec2::new(0.1, 0.0));
+        cursor.step(SCREEN, 0.1);
+        assert_eq!(cursor.position(), Vec2::new(500.0, 500.0));
+
+        // up on the stick is up on the screen
+        cursor.set_stick(Vec2::Y);
+        cursor.step(SCREEN, 0.1);
+        let first = 500.0 - cursor.position().y;
+        assert!(first > 0.0);
+        cursor.step(SCREEN, 0.1);
+        let second = 500.0 - first - cursor.position().y;
+        assert!(
+            second > first,
+            "the cursor speeds up while the stick is held"
+        );
+
+        cursor.set_stick(Vec2::new(-1.0, 0.0));
+        for _ in 0..100 {
+            cursor.step(SCREEN, 0.1);
+        }
+        assert_eq!(cursor.position().x, 0.0);
+    }
+
+    #[test]
+    fn test_snaps_to_nearby_target() {
+        let mut cursor = VirtualCursor::default();
+        cursor.set_position(Vec2::new(90.0, 60.0));
+
+        for _ in 0..60 {
+            cursor.add_target(Vec2::new(100.0, 40.0), Vec2::new(200.0, 80.0));
+            cursor.add_target(Vec2::new(600.0, 40.0), Vec2::new(700.0, 80.0));
+            cursor.step(SCREEN, 1.0 / 60.0);
+        }
+        assert!(cursor.position().distance(Vec2::new(150.0, 60.0)) < 1.0);
+        assert_eq!(
+            cursor.hovered_target(),
+            Some((Vec2::new(100.0, 40.0), Vec2::new(200.0, 80.0)))
+        );
+
+        // targets out of reach don't pull
+        let mut cursor = VirtualCursor::default();
+        cursor.set_position(Vec2::new(400.0, 60.0));
+        cursor.add_target(Vec2::new(100.0, 40.0), Vec2::new(200.0, 80.0));
+        cursor.step(SCREEN, 1.0);
+        assert_eq!(cursor.position(), Vec2::new(400.0, 60.0));
+    }
+}
@@ -1,11 +1,16 @@
 use std::{
     any::TypeId,
-    collections::{HashMap, VecDeque},
+    collections::{BTreeSet, HashMap, VecDeque},
     marker::PhantomData,
     ops::{Deref, DerefMut},
-    sync::Arc,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
 };
 
+use glam::{Quat, Vec3};
+use parking_lot::Mutex;
 use parking_lot::{ArcRwLockReadGuard, ArcRwLockWriteGuard, RawRwLock, RwLock};
 
 use crate::{
@@ -13,10 +18,18 @@ use crate::{
     asset::{Asset, AssetHandle, AssetLibrary, AssetStatus},
     nodes::{Instanceable, node::IntoNode},
     platform::SendSync,
-    prelude::{EventCtx, EventLabel, EventReceiver, Ready, node_transform::WorldTransform},
+    prelude::{
+        EventCtx, EventLabel, EventReceiver, NodeTransform, OnAdded, OnChildAdded, OnChildRemoved,
+        OnDisabled, OnEnabled, OnRemoved, Ready, TransformConstraints, TransformTween,
+        constraints::{self, ConstraintTarget},
+        node_transform::WorldTransform,
+    },
+    resources::SpatialHash,
+    tasks::TaskCtx,
+    utils::{aabb::AABB, ray::Ray},
 };
 
-#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub struct NodeId(u64);
 
 impl Default for NodeId {
@@ -39,6 +52,32 @@ pub struct SceneNode {
     children: Vec<NodeId>,
     parent: Option<NodeId>,
     type_id: TypeId,
+    /// false if the node itself was disabled, see [`Scene::set_enabled`]
+    own_enabled: bool,
+    /// false if the node itself was hidden, see [`Scene::set_visible`]
+    own_visible: bool,
+    /// true if the node and all of its parents are enabled
+    enabled: bool,
+    /// true if the node and all of its parents are visible, updated when the scene syncs
+    visible: bool,
+}
+
+/// lifecycle events waiting for a [`GameContext`] to be emitted
+enum Lifecycle {
+    Added(NodeId),
+    Enabled(NodeId),
+    Disabled(NodeId),
+    ChildAdded {
+        parent: NodeId,
+        child: NodeId,
+    },
+    /// the path is taken when the child is removed since it may be gone by the time this is
+    /// emitted
+    ChildRemoved {
+        parent: NodeId,
+        child: NodeId,
+        path: Option<String>,
+    },
 }
 
 type NodeStorage = Arc<RwLock<Box<dyn Node>>>;
@@ -140,6 +179,12 @@ impl<'a, T: Node> NodeHandle<'a, T> {
         self
     }
 
+    /// start a tween on this node, see [`Scene::animate`]
+    pub fn animate(&self, tween: TransformTween) -> &Self {
+        self.scene.animate(self.id, tween);
+        self
+    }
+
     /// returns the children of this node
     pub fn children_ids(&self) -> Vec<NodeId> {
         self.scene.children_ids(self.id)
@@ -234,6 +279,34 @@ impl<'a, T: Node> NodeHandle<'a, T> {
         self
     }
 
+    /// same as [`NodeHandle::on`] but the handler runs before handlers with a lower priority.
+    /// see [`Scene::on_with_priority`]
+    pub fn on_with_priority<E: EventLabel>(
+        &self,
+        priority: i32,
+        handler: impl FnMut(EventCtx<E, T>) + Send + Sync + 'static,
+    ) -> &Self {
+        self.scene.on_with_priority(self.id(), priority, handler);
+        self
+    }
+
+    /// start a task on this node each time the event reaches it, see [`crate::tasks`]
+    ///
+    /// ```rust,ignore
+    ///  scene.spawn(Empty::default()).on_async::<Ready, _, _>(|task| async move {
+    ///      task.wait(1.0).await;
+    ///      task.node(|door: &mut Empty| door.transform.position.y += 2.0).await;
+    ///  });
+    /// ```
+    pub fn on_async<E: EventLabel, F, Fut>(&self, handler: F) -> &Self
+    where
+        F: FnMut(TaskCtx) -> Fut + SendSync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.scene.on_async::<E, T, _, _>(self.id(), handler);
+        self
+    }
+
     /// provides immutible access to this node.
     ///
     /// Multiple reader can access the same node at the same time but blocks if a writer holds the
@@ -271,6 +344,298 @@ impl<'a, T: Node> NodeHandle<'a, T> {
     }
 }
 
+/// extra work done while syncing world transforms
+pub(crate) struct TransformSync<'a> {
+    /// nodes in the hash are moved to their new world position
+    pub spatial_hash: Option<&'a mut SpatialHash>,
+    /// true when syncing after a fixed update. interpolated nodes remember where they were
+    pub fixed_step: bool,
+    /// how far between their previous and current world transform interpolated nodes are drawn
+    pub alpha: f32,
+}
+
+impl Default for TransformSync<'_> {
+    fn default() -> Self {
+        Self {
+            spatial_hash: None,
+            fixed_step: false,
+            alpha: 1.0,
+        }
+    }
+}
+
+/// what a node gets from its parent while syncing world transforms
+#[derive(Clone, Copy)]
+struct ParentSync {
+    world: WorldTransform,
+    changed: bool,
+    enabled: bool,
+    visible: bool,
+    /// set if the parent is drawn somewhere other than its world transform
+    render: Option<WorldTransform>,
+}
+
+/// what a parent gets back from its children while syncing world transforms
+#[derive(Clone, Copy, Default)]
+struct ChildSync {
+    /// the merged bounding box of the child and its descendants
+    aabb: Option<AABB>,
+    /// true if the box is different from the last sync
+    changed: bool,
+}
+
+/// a node visited by [`Scene::walk`]
+///
+/// holds a read lock on the node until dropped. derefs to the node.
+pub struct WalkEntry {
+    /// ids from the root down to and including this node
+    pub path: Vec<NodeId>,
+    /// the world transform of this node
+    pub world: WorldTransform,
+    guard: ArcRwLockReadGuard<RawRwLock, Box<dyn Node>>,
+}
+
+impl WalkEntry {
+    /// id of the visited node
+    pub fn id(&self) -> NodeId {
+        *self.path.last().expect("path always contains the node")
+    }
+
+    /// id of the parent of the visited node
+    pub fn parent_id(&self) -> Option<NodeId> {
+        self.path.len().checked_sub(2).map(|i| self.path[i])
+    }
+
+    /// how many ancestors the node has (roots are 0)
+    pub fn depth(&self) -> usize {
+        self.path.len() - 1
+    }
+}
+
+impl Deref for WalkEntry {
+    type Target = dyn Node;
+
+    fn deref(&self) -> &Self::Target {
+        &**self.guard
+    }
+}
+
+/// a node hit by [`Scene::raycast`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RayHit {
+    pub node: NodeId,
+    /// ids from the root down to and including the hit node
+    pub path: Vec<NodeId>,
+    /// how far along the ray the hit is
+    pub distance: f32,
+    /// where the ray hit in world space
+    pub point: Vec3,
+}
+
+/// depth-first iterator over the scene tree. see [`Scene::walk`]
+pub struct SceneWalk<'a> {
+    scene: &'a Scene,
+    stack: Vec<(NodeId, WorldTransform, Vec<NodeId>)>,
+}
+
+impl Iterator for SceneWalk<'_> {
+    type Item = WalkEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (id, parent_world, mut path) = self.stack.pop()?;
+
+            let Some(node_lock) = self.scene.nodes.read().get(&id).map(Arc::clone) else {
+                continue;
+            };
+
+            // transforms are only accessible mutably so take a write lock and downgrade it
+            let mut guard = RwLock::write_arc(&node_lock);
+            let world = parent_world + WorldTransform::from(*guard.get_transform());
+            let guard = ArcRwLockWriteGuard::downgrade(guard);
+
+            path.push(id);
+
+            let mut children = self.scene.children_ids(id);
+            children.reverse();
+            for child in children {
+                self.stack.push((child, world, path.clone()));
+            }
+
+            return Some(WalkEntry { path, world, guard });
+        }
+    }
+}
+
+type SceneCommand = Box<dyn FnOnce(&Scene) + Send + Sync>;
+
+/// a buffer of structural changes to the scene that are applied after events have finished
+///
+/// the scene tree is being traversed while events run so spawning nodes with handlers,
+/// despawning or reparenting directly from an event handler can deadlock. queue them here
+/// instead.
+///
+/// cloning the commands gives another handle to the same queue, so a clone can be moved to a
+/// background thread (an asset load or a network connection) to request changes that are applied
+/// at the start of the next frame. this keeps the scene itself on the game thread. clones taken
+/// from a scene that is later merged into another still send to the merged scene's old queue.
+///
+/// # Example
+/// ```rust, ignore
+/// player.on::<Update>(|ctx| {
+///     if ctx.get_resource::<Input>().key_just_pressed(KeyCode::Space) {
+///         ctx.commands().spawn_with(Bullet::default(), |bullet| {
+///             bullet.on::<Update>(|ctx| {
+///                 if ctx.node_ref().lifetime <= 0.0 {
+///                     ctx.commands().despawn(ctx.node_id());
+///                 }
+///             });
+///         });
+///     }
+/// });
+///
+/// let commands = ctx.scene.commands().clone();
+/// std::thread::spawn(move || {
+///     for message in connection.messages() {
+///         commands.spawn(RemotePlayer::from(message));
+///     }
+/// });
+/// ```
+#[derive(Default, Clone)]
+pub struct SceneCommands {
+    queue: Arc<Mutex<Vec<SceneCommand>>>,
+}
+
+impl SceneCommands {
+    /// queue a closure to run on the scene once events have finished
+    pub fn add(&self, command: impl FnOnce(&Scene) + SendSync + 'static) {
+        self.queue.lock().push(Box::new(command));
+    }
+
+    /// queue a new root node. the returned id is valid once the commands are applied
+    pub fn spawn<T, M>(&self, node: T) -> NodeId
+    where
+        T: IntoNode<M>,
+        T::Node: 'static,
+    {
+        self.spawn_with(node, |_| {})
+    }
+
+    /// queue a new root node and run `f` on it once it is spawned (for example to add events)
+    pub fn spawn_with<T, M, F>(&self, node: T, f: F) -> NodeId
+    where
+        T: IntoNode<M>,
+        T::Node: 'static,
+        F: for<'s> FnOnce(NodeHandle<'s, T::Node>) + SendSync + 'static,
+    {
+        self.queue_spawn(None, node.into_node(), None, f)
+    }
+
+    /// queue a new named root node
+    pub fn spawn_with_name<T, M>(&self, name: impl Into<String>, node: T) -> NodeId
+    where
+        T: IntoNode<M>,
+        T::Node: 'static,
+    {
+        self.queue_spawn(Some(name.into()), node.into_node(), None, |_| {})
+    }
+
+    /// queue a new node as a child of `parent`
+    pub fn spawn_as_child<T, M>(&self, node: T, parent: NodeId) -> NodeId
+    where
+        T: IntoNode<M>,
+        T::Node: 'static,
+    {
+        self.queue_spawn(None, node.into_node(), Some(parent), |_| {})
+    }
+
+    /// queue a new node as a child of `parent` and run `f` on it once it is spawned
+    pub fn spawn_as_child_with<T, M, F>(&self, node: T, parent: NodeId, f: F) -> NodeId
+    where
+        T: IntoNode<M>,
+        T::Node: 'static,
+        F: for<'s> FnOnce(NodeHandle<'s, T::Node>) + SendSync + 'static,
+    {
+        self.queue_spawn(None, node.into_node(), Some(parent), f)
+    }
+
+    /// queue removal of a node and all of its children. see [`Scene::despawn`]
+    pub fn despawn(&self, id: NodeId) {
+        self.add(move |scene| {
+            scene.despawn(id);
+        });
+    }
+
+    /// queue moving a node to a new parent. see [`Scene::reparent`]
+    pub fn reparent(&self, id: NodeId, parent: Option<NodeId>) {
+        self.add(move |scene| {
+            if !scene.reparent(id, parent) {
+                log::warn!("failed to reparent {id:?} to {parent:?}");
+            }
+        });
+    }
+
+    /// queue moving a node to the root without moving it in the world. see [`Scene::detach`]
+    pub fn detach(&self, id: NodeId) {
+        self.add(move |scene| {
+            if !scene.detach(id) {
+                log::warn!("failed to detach {id:?}");
+            }
+        });
+    }
+
+    /// queue renaming a node. see [`Scene::rename`]
+    pub fn rename(&self, id: NodeId, name: impl Into<String>) {
+        let name = name.into();
+        self.add(move |scene| {
+            scene.rename(id, Some(name));
+        });
+    }
+
+    /// queue enabling or disabling a node. see [`Scene::set_enabled`]
+    pub fn set_enabled(&self, id: NodeId, enabled: bool) {
+        self.add(move |scene| {
+            scene.set_enabled(id, enabled);
+        });
+    }
+
+    /// queue showing or hiding a node. see [`Scene::set_visible`]
+    pub fn set_visible(&self, id: NodeId, visible: bool) {
+        self.add(move |scene| {
+            scene.set_visible(id, visible);
+        });
+    }
+
+    /// returns true if there are commands waiting to be applied
+    pub fn is_empty(&self) -> bool {
+        self.queue.lock().is_empty()
+    }
+
+    fn queue_spawn<T, F>(
+        &self,
+        name: Option<String>,
+        node: T,
+        parent: Option<NodeId>,
+        f: F,
+    ) -> NodeId
+    where
+        T: Node,
+        F: for<'s> FnOnce(NodeHandle<'s, T>) + SendSync + 'static,
+    {
+        let id = NodeId::new();
+        self.add(move |scene| {
+            if let Some(parent) = parent
+                && !scene.heirarchy.read().contains_key(&parent)
+            {
+                log::warn!("parent {parent:?} was removed before {id:?} could be spawned");
+                return;
+            }
+            f(scene.spawn_with_id(id, name, node, parent));
+        });
+        id
+    }
+}
+
 type PendingAssetEntry = (Box<dyn PendingSceneAsset>, Option<NodeId>);
 
 /// A hierarchical scene graph for storing and organizing nodes.
@@ -300,6 +665,30 @@ pub struct Scene {
     ready_queue: RwLock<VecDeque<NodeId>>,
 
     pending_assets: RwLock<Vec<PendingAssetEntry>>,
+
+    /// structural changes queued while events are running
+    commands: SceneCommands,
+
+    /// how many emits are currently running so commands are only applied by the outermost one
+    emit_depth: AtomicUsize,
+
+    /// lifecycle events are queued since nodes can be added and removed without a context
+    lifecycle_queue: Mutex<VecDeque<Lifecycle>>,
+
+    /// despawned subtrees waiting for their [`OnRemoved`] event
+    removed: Mutex<Vec<Scene>>,
+
+    /// true while lifecycle events are being emitted so they aren't emitted recursively
+    flushing_lifecycle: AtomicBool,
+
+    /// tweens playing on nodes, see [`Scene::animate`]
+    tweens: Mutex<Vec<(NodeId, TransformTween)>>,
+
+    /// constrained nodes in the order they were constrained, see [`Scene::set_constraints`]
+    constraints: Mutex<Vec<(NodeId, TransformConstraints)>>,
+
+    /// set when something a frame shows changed, see [`Scene::is_changed`]
+    changed: AtomicBool,
 }
 
 impl Default for Scene {
@@ -316,6 +705,14 @@ impl<'a> Scene {
             events: RwLock::new(HashMap::new()),
             ready_queue: RwLock::new(VecDeque::new()),
             pending_assets: RwLock::new(Vec::new()),
+            commands: SceneCommands::default(),
+            emit_depth: AtomicUsize::new(0),
+            lifecycle_queue: Mutex::new(VecDeque::new()),
+            removed: Mutex::new(Vec::new()),
+            flushing_lifecycle: AtomicBool::new(false),
+            tweens: Mutex::new(Vec::new()),
+            constraints: Mutex::new(Vec::new()),
+            changed: AtomicBool::new(false),
         }
     }
 
@@ -365,21 +762,124 @@ impl<'a> Scene {
             .on::<E, N, _>(handler);
     }
 
+    /// add a handler to a node that runs before handlers with a lower priority
+    ///
+    /// when an event is emitted to the whole scene every handler with the highest priority runs
+    /// first, in tree order, before any handler with the next priority. this orders systems
+    /// across nodes, e.g. input handlers at 100 before camera controllers at 0 before animation
+    /// at -100. handlers added with [`Scene::on`] have a priority of 0
+    pub fn on_with_priority<E: EventLabel, N: Node>(
+        &self,
+        node: NodeId,
+        priority: i32,
+        handler: impl FnMut(EventCtx<E, N>) + SendSync + 'static,
+    ) {
+        self.events
+            .write()
+            .entry(node)
+            .or_default()
+            .on_with_priority::<E, N, _>(priority, handler);
+    }
+
+    /// add an async handler to a node that starts a task each time the event reaches it, see
+    /// [`crate::tasks`]
+    ///
+    /// ```rust,ignore
+    /// scene.on_async::<Ready, Empty, _, _>(door, |task| async move {
+    ///     task.wait(1.0).await;
+    ///     task.node(|door: &mut Empty| door.transform.position.y += 2.0).await;
+    /// });
+    /// ```
+    pub fn on_async<E, N, F, Fut>(&self, node: NodeId, handler: F)
+    where
+        E: EventLabel,
+        N: Node,
+        F: FnMut(TaskCtx) -> Fut + SendSync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.events
+            .write()
+            .entry(node)
+            .or_default()
+            .on_async::<E, N, _, _>(handler);
+    }
+
+    /// calls `f` with every `E` that reaches `node` after every other handler
+    pub(crate) fn listen<E: EventLabel>(
+        &self,
+        node: NodeId,
+        f: impl FnMut(&E) + SendSync + 'static,
+    ) {
+        self.events
+            .write()
+            .entry(node)
+            .or_default()
+            .on_any::<E, _>(i32::MIN, f);
+    }
+
     fn spawn_with_parent<T: Node, N: Into<String>>(
         &'a self,
         name: Option<N>,
         node: T,
         parent: Option<NodeId>,
     ) -> NodeHandle<'a, T> {
+        self.spawn_with_id(NodeId::new(), name, node, parent)
+    }
+
+    fn spawn_with_id<T: Node, N: Into<String>>(
+        &'a self,
+        id: NodeId,
+        name: Option<N>,
+        node: T,
+        parent: Option<NodeId>,
+    ) -> NodeHandle<'a, T> {
+        self.insert_node(id, name.map(|s| s.into()), Box::new(node), parent);
+
+        NodeHandle {
+            id,
+            scene: self,
+            _ty: PhantomData,
+        }
+    }
+
+    /// add a node whose type is only known at runtime
+    pub(crate) fn spawn_boxed(
+        &self,
+        name: Option<String>,
+        node: Box<dyn Node>,
+        parent: Option<NodeId>,
+    ) -> NodeId {
         let id = NodeId::new();
+        self.insert_node(id, name, node, parent);
+        id
+    }
 
-        let scene_node = SceneNode {
+    fn insert_node(
+        &self,
+        id: NodeId,
+        name: Option<String>,
+        mut node: Box<dyn Node>,
+        parent: Option<NodeId>,
+    ) {
+        let transform = node.get_transform();
+        let own_enabled = transform.enabled_request.take().unwrap_or(true);
+        let own_visible = transform.visible_request.take().unwrap_or(true);
+        let mut scene_node = SceneNode {
             _id: id,
-            name: name.map(|s| s.into()),
+            name,
             children: Vec::new(),
             parent,
-            type_id: TypeId::of::<T>(),
+            type_id: node.as_any().type_id(),
+            own_enabled,
+            own_visible,
+            enabled: own_enabled,
+            visible: own_visible,
         };
+        // a node cloned from another scene keeps the world transform it had there
+        node.get_transform().mark_dirty();
+        if let Some(constraints) = node.get_transform().constraints.take() {
+            self.constraints.lock().push((id, constraints));
+        }
 
         {
             let mut hierarchy = self.heirarchy.write();
@@ -387,13 +887,15 @@ impl<'a> Scene {
                 && let Some(parent_node) = hierarchy.get_mut(&parent_id)
             {
                 parent_node.children.push(id);
+                scene_node.enabled &= parent_node.enabled;
+                scene_node.visible &= parent_node.visible;
             }
             hierarchy.insert(id, scene_node);
         }
 
         {
             let mut nodes = self.nodes.write();
-            nodes.insert(id, Arc::new(RwLock::new(Box::new(node))));
+            nodes.insert(id, Arc::new(RwLock::new(node)));
         }
 
         {
@@ -401,10 +903,10 @@ impl<'a> Scene {
             ready_queue.push_back(id);
         }
 
-        NodeHandle {
-            id,
-            scene: self,
-            _ty: PhantomData,
+        let mut lifecycle = self.lifecycle_queue.lock();
+        lifecycle.push_back(Lifecycle::Added(id));
+        if let Some(parent) = parent {
+            lifecycle.push_back(Lifecycle::ChildAdded { parent, child: id });
         }
     }
 
@@ -452,10 +954,16 @@ impl<'a> Scene {
             let mut self_nodes = self.nodes.write();
             let mut self_events = self.events.write();
 
+            // nodes merged under a disabled parent start disabled
+            let parent_enabled = parent
+                .and_then(|parent_id| self_heirarchy.get(&parent_id))
+                .is_none_or(|parent_node| parent_node.enabled);
+
             for (id, mut scene_node) in other_hierarchy.drain() {
                 if scene_node.parent.is_none() {
                     scene_node.parent = parent;
                 }
+                scene_node.enabled &= parent_enabled;
                 self_heirarchy.insert(id, scene_node);
             }
 
@@ -475,6 +983,29 @@ impl<'a> Scene {
                 .write()
                 .append(&mut other.pending_assets.write());
 
+            self.commands
+                .queue
+                .lock()
+                .append(&mut other.commands.queue.lock());
+
+            let mut lifecycle = self.lifecycle_queue.lock();
+            lifecycle.append(&mut other.lifecycle_queue.lock());
+            if let Some(parent) = parent {
+                lifecycle.extend(
+                    root_ids
+                        .iter()
+                        .map(|&child| Lifecycle::ChildAdded { parent, child }),
+                );
+            }
+            drop(lifecycle);
+
+            self.removed.lock().append(&mut other.removed.lock());
+
+            self.tweens.lock().append(&mut other.tweens.lock());
+            self.constraints
+                .lock()
+                .append(&mut other.constraints.lock());
+
             if let Some(parent_id) = parent
                 && let Some(parent_node) = self_heirarchy.get_mut(&parent_id)
             {
@@ -482,6 +1013,11 @@ impl<'a> Scene {
             }
         }
 
+        // the merged roots may have been synced in the other scene without a parent
+        for id in &root_ids {
+            self.mark_dirty(*id);
+        }
+
         root_ids
     }
 
@@ -521,6 +1057,35 @@ impl<'a> Scene {
         None
     }
 
+    /// find a node by the names of it and its ancestors separated by `/` such as
+    /// `"level/enemies/enemy_3"` starting from a root node
+    pub fn get_id_by_path(&self, path: &str) -> Option<NodeId> {
+        let (first, rest) = path.split_once('/').unwrap_or((path, ""));
+        let root = self
+            .root_ids()
+            .into_iter()
+            .find(|id| self.node_name(*id).as_deref() == Some(first))?;
+        self.get_child_id_by_path(root, rest)
+    }
+
+    /// find a descendant of `id` by a `/` separated path of names. an empty path returns `id`
+    pub fn get_child_id_by_path(&self, id: NodeId, path: &str) -> Option<NodeId> {
+        path.split('/')
+            .filter(|name| !name.is_empty())
+            .try_fold(id, |current, name| {
+                self.children_ids(current)
+                    .into_iter()
+                    .find(|child| self.node_name(*child).as_deref() == Some(name))
+            })
+    }
+
+    /// the runtime type and storage of a node
+    pub(crate) fn node_storage(&self, id: NodeId) -> Option<(TypeId, NodeStorage)> {
+        let type_id = self.heirarchy.read().get(&id)?.type_id;
+        let storage = self.nodes.read().get(&id)?.clone();
+        Some((type_id, storage))
+    }
+
     /// get the parent of the node
     pub fn parent_id(&self, id: NodeId) -> Option<NodeId> {
         self.heirarchy.read().get(&id).and_then(|n| n.parent)
@@ -563,6 +1128,94 @@ impl<'a> Scene {
             .flatten()
     }
 
+    /// the names of a node and its ancestors separated by `/`, the inverse of
+    /// [`Scene::get_id_by_path`]. `None` if the node or one of its ancestors has no name
+    pub fn node_path(&self, id: NodeId) -> Option<String> {
+        let hierarchy = self.heirarchy.read();
+        let mut names = Vec::new();
+        let mut current = Some(id);
+        while let Some(id) = current {
+            let node = hierarchy.get(&id)?;
+            names.push(node.name.as_deref()?);
+            current = node.parent;
+        }
+        names.reverse();
+        Some(names.join("/"))
+    }
+
+    /// read or change the transform of a node without knowing its type, `None` if the node
+    /// doesn't exist
+    ///
+    /// the node is locked while `f` runs so `f` must not access the same node through the scene
+    pub fn with_transform<R>(
+        &self,
+        id: NodeId,
+        f: impl FnOnce(&mut NodeTransform) -> R,
+    ) -> Option<R> {
+        let node = self.nodes.read().get(&id).map(Arc::clone)?;
+        let mut node = node.write();
+        Some(f(node.get_transform()))
+    }
+
+    /// the current world transform of a node, `None` if the node doesn't exist
+    ///
+    /// unlike [`NodeTransform::world_space`] this is computed from the node and its parents as
+    /// they are now instead of when the scene last synced
+    pub fn world_transform(&self, id: NodeId) -> Option<WorldTransform> {
+        let local = self.with_transform(id, |transform| WorldTransform::from(*transform))?;
+        Some(self.parent_world(id) + local)
+    }
+
+    /// move a node to a position in world space, see [`NodeTransform::set_world_position`]
+    ///
+    /// the parent is read as it is now so this works for nodes whose parents moved since the
+    /// last sync
+    pub fn set_world_position(&self, id: NodeId, position: impl Into<Vec3>) {
+        let parent = self.parent_world(id);
+        self.with_transform(id, |transform| {
+            transform.set_parent_world(parent);
+            transform.set_world_position(position);
+        });
+    }
+
+    /// rotate a node to a rotation in world space, see [`Self::set_world_position`]
+    pub fn set_world_rotation(&self, id: NodeId, rotation: Quat) {
+        let parent = self.parent_world(id);
+        self.with_transform(id, |transform| {
+            transform.set_parent_world(parent);
+            transform.set_world_rotation(rotation);
+        });
+    }
+
+    /// scale a node to a scale in world space, see [`Self::set_world_position`]
+    pub fn set_world_scale(&self, id: NodeId, scale: impl Into<Vec3>) {
+        let parent = self.parent_world(id);
+        self.with_transform(id, |transform| {
+            transform.set_parent_world(parent);
+            transform.set_world_scale(scale);
+        });
+    }
+
+    /// the world transform of the parent of a node from the current local transforms
+    ///
+    /// each parent is locked on its own so this must not be called while one of them is locked
+    fn parent_world(&self, id: NodeId) -> WorldTransform {
+        let mut parents = Vec::new();
+        let mut current = self.parent_id(id);
+        while let Some(parent) = current {
+            parents.push(parent);
+            current = self.parent_id(parent);
+        }
+
+        parents
+            .into_iter()
+            .rev()
+            .filter_map(|parent| {
+                self.with_transform(parent, |transform| WorldTransform::from(*transform))
+            })
+            .fold(WorldTransform::default(), |world, local| world + local)
+    }
+
     /// collects all nodes of a specific type
     pub fn collect<T: Node>(&'a self) -> Vec<NodeHandle<'a, T>> {
         let heirarchy = self.heirarchy.read();
@@ -579,68 +1232,380 @@ impl<'a> Scene {
             .collect()
     }
 
+    /// collects all nodes of a specific type that are visible in the tree. hidden nodes are
+    /// skipped, see [`Scene::set_visible`]
+    pub fn collect_visible<T: Node>(&'a self) -> Vec<NodeHandle<'a, T>> {
+        let nodes = self.collect::<T>();
+        let hierarchy = self.heirarchy.read();
+        nodes
+            .into_iter()
+            .filter(|node| hierarchy.get(&node.id).is_some_and(|node| node.visible))
+            .collect()
+    }
+
     /// get all the root node ids
+    /// the nodes without a parent ordered by [`NodeId`], which is the order their ids were
+    /// created in, so the order is deterministic. a queued spawn gets its id when it is queued
+    /// and a node moved to the root keeps its id
     pub fn root_ids(&self) -> Vec<NodeId> {
         let hierarchy = self.heirarchy.read();
-        hierarchy
+        let mut roots: Vec<NodeId> = hierarchy
             .iter()
             .filter(|(_, node)| node.parent.is_none())
             .map(|(id, _)| *id)
-            .collect()
+            .collect();
+        roots.sort_unstable();
+        roots
     }
 
     /// emit an event to the scene (this will also update world space transforms)
+    ///
+    /// nodes are visited depth first with roots in [`Scene::root_ids`] order. handlers with a
+    /// higher priority all run before handlers with a lower one. see [`Scene::on_with_priority`]
     pub fn emit<E: EventLabel>(&self, event: &E, ctx: &GameContext) {
-        for root_id in self.root_ids() {
-            self.emit_recursive(root_id, event, ctx);
+        self.emit_depth.fetch_add(1, Ordering::AcqRel);
+
+        let priorities: BTreeSet<i32> = self
+            .events
+            .read()
+            .values()
+            .flat_map(|events| events.priorities::<E>())
+            .collect();
+
+        // most events only have handlers of one priority so they need a single pass
+        if priorities.len() <= 1 {
+            for root_id in self.root_ids() {
+                self.emit_recursive(root_id, event, ctx, None);
+            }
+        } else {
+            for priority in priorities.into_iter().rev() {
+                for root_id in self.root_ids() {
+                    self.emit_recursive(root_id, event, ctx, Some(priority));
+                }
+            }
         }
+
+        self.finish_emit(ctx);
     }
 
-    fn emit_recursive<E: EventLabel>(&self, id: NodeId, event: &E, ctx: &GameContext) {
+    fn emit_recursive<E: EventLabel>(
+        &self,
+        id: NodeId,
+        event: &E,
+        ctx: &GameContext,
+        priority: Option<i32>,
+    ) {
+        // disabled nodes and their children are paused
+        if !self.is_enabled(id) {
+            return;
+        }
+
         // if an event receiver exist trigger the event to it
         if let Some(events) = self.events.read().get(&id) {
-            events.trigger(event, self, id, ctx);
+            match priority {
+                Some(priority) => events.trigger_priority(event, self, id, ctx, priority),
+                None => events.trigger(event, self, id, ctx),
+            }
         }
 
         let children = self.children_ids(id);
         for child_id in children {
-            self.emit_recursive(child_id, event, ctx);
+            self.emit_recursive(child_id, event, ctx, priority);
         }
     }
 
     /// goes through every node and updates the world position recursively
     ///
-    /// this is done once per frame after update
+    /// this is done once per frame after update. world transforms are cached so only nodes whose
+    /// local transform changed and their descendants are recomputed. this also picks up nodes
+    /// that were enabled, disabled, shown or hidden through [`Node::set_enabled`] and
+    /// [`Node::set_visible`], and merges bounding boxes up the tree into
+    /// [`crate::components::NodeTransform::world_aabb`]
     pub fn sync_world_transform(&self) {
-        for id in self.root_ids() {
-            self.sync_world_transform_recursive(id, WorldTransform::default());
+        self.sync_world_transform_with(&mut TransformSync::default());
+    }
+
+    /// syncs world transforms with extra work done along the way, see [`TransformSync`]
+    pub(crate) fn sync_world_transform_with(&self, pass: &mut TransformSync) {
+        let root = ParentSync {
+            world: WorldTransform::default(),
+            changed: false,
+            enabled: true,
+            visible: true,
+            render: None,
+        };
+
+        for id in self.root_ids() {
+            self.sync_world_transform_recursive(id, root, pass);
+        }
+
+        if let Some(spatial_hash) = pass.spatial_hash.as_deref_mut() {
+            let hierarchy = self.heirarchy.read();
+            spatial_hash.retain(|id| hierarchy.contains_key(&id));
         }
     }
 
-    fn sync_world_transform_recursive(&self, id: NodeId, parent_world: WorldTransform) {
+    fn sync_world_transform_recursive(
+        &self,
+        id: NodeId,
+        parent: ParentSync,
+        pass: &mut TransformSync,
+    ) -> ChildSync {
         let node_lock = {
             let nodes = self.nodes.read();
             nodes.get(&id).map(Arc::clone)
         };
 
         let Some(node_lock) = node_lock else {
-            return;
+            return ChildSync::default();
         };
 
         let mut node = node_lock.write();
+        let transform = node.get_transform();
 
-        node.get_transform().get_world_space(parent_world);
-        let current_world = *node.get_transform().world_space();
+        if pass.fixed_step {
+            transform.store_previous_world();
+        }
+        let changed = transform.sync_world_space(id, &parent.world, parent.changed);
+        if changed {
+            self.mark_changed();
+        }
+        let requests = (
+            transform.enabled_request.take(),
+            transform.visible_request.take(),
+        );
+        let (own_enabled, visible) = self.sync_flags(id, requests, parent.visible);
+        transform.sync_visibility(visible);
+        let current = ParentSync {
+            world: *transform.world_space(),
+            changed,
+            enabled: parent.enabled && own_enabled,
+            visible,
+            render: transform.sync_render_space(parent.render, pass.alpha),
+        };
+        let aabb_dirty = transform.take_aabb_dirty() || changed;
+        let cached_aabb = transform.world_aabb().copied();
 
         drop(node);
 
+        if changed
+            && let Some(spatial_hash) = pass.spatial_hash.as_deref_mut()
+            && spatial_hash.contains(id)
+        {
+            spatial_hash.insert(id, current.world.position());
+        }
+
+        self.update_enabled(id, current.enabled);
+
+        let mut children_aabb: Option<AABB> = None;
+        let mut children_changed = false;
         let children = self.children_ids(id);
         for child in children {
-            self.sync_world_transform_recursive(child, current_world);
+            let child = self.sync_world_transform_recursive(child, current, pass);
+            children_changed |= child.changed;
+            children_aabb = match (children_aabb, child.aabb) {
+                (Some(merged), Some(aabb)) => Some(merged.merge(&aabb)),
+                (merged, aabb) => merged.or(aabb),
+            };
+        }
+
+        // boxes only have to be merged again if something below this node changed
+        if !aabb_dirty && !children_changed {
+            return ChildSync {
+                aabb: cached_aabb,
+                changed: false,
+            };
+        }
+
+        let mut node = node_lock.write();
+        let transform = node.get_transform();
+        let changed = transform.sync_world_aabb(children_aabb);
+        ChildSync {
+            aabb: transform.world_aabb().copied(),
+            changed,
+        }
+    }
+
+    /// applies flags set through [`Node::set_enabled`] or [`Node::set_visible`] and updates
+    /// visibility in the tree, returns if the node itself is enabled and if it is visible
+    fn sync_flags(
+        &self,
+        id: NodeId,
+        (enabled, visible): (Option<bool>, Option<bool>),
+        parent_visible: bool,
+    ) -> (bool, bool) {
+        let mut hierarchy = self.heirarchy.write();
+        let Some(scene_node) = hierarchy.get_mut(&id) else {
+            return (true, parent_visible);
+        };
+
+        scene_node.own_enabled = enabled.unwrap_or(scene_node.own_enabled);
+        scene_node.own_visible = visible.unwrap_or(scene_node.own_visible);
+        scene_node.visible = parent_visible && scene_node.own_visible;
+        (scene_node.own_enabled, scene_node.visible)
+    }
+
+    /// start playing a tween on a node alongside any it already has
+    ///
+    /// the tween is dropped once it finishes or the node is despawned. tweens on disabled nodes
+    /// are paused
+    pub fn animate(&self, id: NodeId, tween: TransformTween) {
+        self.tweens.lock().push((id, tween));
+    }
+
+    /// stop every tween on a node where it is
+    pub fn stop_tweens(&self, id: NodeId) {
+        self.tweens.lock().retain(|(node, _)| *node != id);
+    }
+
+    /// true if a node has a tween that hasn't finished
+    pub fn is_tweening(&self, id: NodeId) -> bool {
+        self.tweens.lock().iter().any(|(node, _)| *node == id)
+    }
+
+    /// advances every tween by `dt` seconds, returns the nodes that had a tween finish
+    ///
+    /// the moved nodes need a sync before their world transforms are up to date
+    pub fn update_tweens(&self, dt: f32) -> Vec<NodeId> {
+        let tweens = std::mem::take(&mut *self.tweens.lock());
+
+        let mut playing = Vec::with_capacity(tweens.len());
+        let mut finished = Vec::new();
+        for (id, mut tween) in tweens {
+            let Some((_, node)) = self.node_storage(id) else {
+                continue;
+            };
+            if !self.is_enabled(id) {
+                playing.push((id, tween));
+                continue;
+            }
+
+            match tween.advance(node.write().get_transform(), dt) {
+                true => finished.push(id),
+                false => playing.push((id, tween)),
+            }
+        }
+
+        // keep tweens that were started while these were playing
+        let mut tweens = self.tweens.lock();
+        playing.append(&mut tweens);
+        *tweens = playing;
+
+        finished
+    }
+
+    /// move or turn a node in world space after every update, this replaces any constraints it
+    /// already has. see [`TransformConstraints`]
+    pub fn set_constraints(&self, id: NodeId, constraints: TransformConstraints) {
+        let mut constrained = self.constraints.lock();
+        match constrained.iter_mut().find(|(node, _)| *node == id) {
+            Some((_, rules)) => *rules = constraints,
+            None => constrained.push((id, constraints)),
+        }
+    }
+
+    /// the constraints applied to a node after every update
+    pub fn constraints(&self, id: NodeId) -> Option<TransformConstraints> {
+        self.constraints
+            .lock()
+            .iter()
+            .find(|(node, _)| *node == id)
+            .map(|(_, rules)| *rules)
+    }
+
+    /// stop constraining a node, it stays where it is
+    pub fn clear_constraints(&self, id: NodeId) {
+        self.constraints.lock().retain(|(node, _)| *node != id);
+    }
+
+    /// moves and turns every enabled node with [`TransformConstraints`]
+    ///
+    /// this runs after update once world transforms are synced so targets are read where they
+    /// are drawn this frame. parents are constrained before their children and nodes at the same
+    /// depth in the order they were constrained. returns true if any node moved, the moved nodes
+    /// need another sync before their world transforms are up to date
+    pub fn apply_constraints(&self, dt: f32) -> bool {
+        let mut constrained = self.constraints.lock().clone();
+        {
+            let hierarchy = self.heirarchy.read();
+            constrained.sort_by_cached_key(|(id, _)| {
+                let mut depth = 0;
+                let mut current = *id;
+                while let Some(parent) = hierarchy.get(&current).and_then(|node| node.parent) {
+                    depth += 1;
+                    current = parent;
+                }
+                depth
+            });
+        }
+
+        let mut moved = false;
+        for (id, rules) in constrained {
+            if !self.is_enabled(id) {
+                continue;
+            }
+            let Some(node_lock) = self.nodes.read().get(&id).map(Arc::clone) else {
+                continue;
+            };
+
+            // targets are read before locking the node so a node targeting itself can't deadlock
+            let follow_target = rules
+                .follow
+                .and_then(|follow| self.constraint_target(follow.target, id));
+            let look_target = rules
+                .look_at
+                .and_then(|target| self.constraint_target(target, id));
+
+            // parents are constrained first so this sees where they were moved to
+            let parent = self.parent_world(id);
+
+            let mut node = node_lock.write();
+            let transform = node.get_transform();
+            transform.set_parent_world(parent);
+
+            let current = transform.get_world_position();
+            let position = rules.constrain_position(current, follow_target, dt);
+            if position != current {
+                transform.set_world_position(position);
+                moved = true;
+            }
+
+            if let Some(rotation) =
+                look_target.and_then(|target| constraints::look_rotation(position, target))
+                && !transform.get_world_rotation().abs_diff_eq(rotation, 1e-6)
+            {
+                transform.set_world_rotation(rotation);
+                moved = true;
+            }
+        }
+
+        moved
+    }
+
+    /// where a constraint target is in world space, `None` if it's a node that doesn't exist
+    fn constraint_target(&self, target: ConstraintTarget, constrained: NodeId) -> Option<Vec3> {
+        let id = match target {
+            ConstraintTarget::Point(point) => return Some(point),
+            ConstraintTarget::Node(id) if id == constrained => return None,
+            ConstraintTarget::Node(id) => id,
+        };
+
+        // a target moved by its own constraint this frame hasn't been synced yet
+        let synced = self.with_transform(id, |transform| {
+            transform
+                .is_synced_as(id)
+                .then(|| transform.render_space().position())
+        })?;
+        match synced {
+            Some(position) => Some(position),
+            None => self.world_transform(id).map(|world| world.position()),
         }
     }
 
     pub(crate) fn pop_ready_queue(&self, ctx: &GameContext) {
+        // commands sent from other threads since the last event pass
+        self.apply_commands();
+        self.flush_lifecycle(ctx);
         loop {
             let id = self.ready_queue.write().pop_front();
             let Some(id) = id else { break };
@@ -650,10 +1615,450 @@ impl<'a> Scene {
 
     /// emit an event to a single node
     pub fn emit_to<E: EventLabel>(&self, id: NodeId, event: &E, ctx: &GameContext) {
+        self.emit_depth.fetch_add(1, Ordering::AcqRel);
         // if an event receiver exist trigger the event to it
         if let Some(events) = self.events.read().get(&id) {
             events.trigger(event, self, id, ctx);
         }
+        self.finish_emit(ctx);
+    }
+
+    /// emit an event to a node and then to each of its ancestors until a handler calls
+    /// [`EventCtx::consume`]
+    ///
+    /// returns true if the event was consumed. this is useful for hit handling and for children
+    /// telling their parents about something without broadcasting to the whole scene
+    pub fn bubble<E: EventLabel>(&self, id: NodeId, event: &E, ctx: &GameContext) -> bool {
+        self.emit_depth.fetch_add(1, Ordering::AcqRel);
+        let mut current = Some(id);
+        let mut consumed = false;
+        while let Some(id) = current {
+            if let Some(events) = self.events.read().get(&id) {
+                consumed = events.trigger_consumable(event, self, id, ctx);
+            }
+            if consumed {
+                break;
+            }
+            current = self.parent_id(id);
+        }
+        self.finish_emit(ctx);
+        consumed
+    }
+
+    /// applies queued commands and lifecycle events once the outermost emit has finished
+    fn finish_emit(&self, ctx: &GameContext) {
+        if self.emit_depth.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.apply_commands();
+            self.flush_lifecycle(ctx);
+        }
+    }
+
+    /// emits queued [`OnAdded`], [`OnRemoved`], [`OnEnabled`] and [`OnDisabled`] events
+    ///
+    /// this happens after every event pass and at the start of each frame
+    fn flush_lifecycle(&self, ctx: &GameContext) {
+        if self.flushing_lifecycle.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        loop {
+            let queued = std::mem::take(&mut *self.lifecycle_queue.lock());
+            let removed = std::mem::take(&mut *self.removed.lock());
+            if queued.is_empty() && removed.is_empty() {
+                break;
+            }
+
+            for lifecycle in queued {
+                match lifecycle {
+                    Lifecycle::Added(id) => self.emit_to(id, &OnAdded, ctx),
+                    Lifecycle::Enabled(id) => self.emit_to(id, &OnEnabled, ctx),
+                    Lifecycle::Disabled(id) => self.emit_to(id, &OnDisabled, ctx),
+                    Lifecycle::ChildAdded { parent, child } => {
+                        // the child may have moved on before the event is emitted
+                        if self.parent_id(child) == Some(parent) {
+                            let path = self.node_path(child);
+                            self.emit_to(parent, &OnChildAdded { child, path }, ctx);
+                        }
+                    }
+                    Lifecycle::ChildRemoved {
+                        parent,
+                        child,
+                        path,
+                    } => self.emit_to(parent, &OnChildRemoved { child, path }, ctx),
+                }
+            }
+
+            for subtree in removed {
+                subtree.emit(&OnRemoved, ctx);
+            }
+        }
+
+        self.flushing_lifecycle.store(false, Ordering::Release);
+    }
+
+    /// the command buffer for this scene.
+    ///
+    /// commands are applied after the current event pass finishes or when
+    /// [`Scene::apply_commands`] is called. use this instead of [`Scene::despawn`],
+    /// [`Scene::reparent`] or spawning nodes with event handlers from inside an event handler.
+    /// clone it to send commands from another thread.
+    pub fn commands(&self) -> &SceneCommands {
+        &self.commands
+    }
+
+    /// applies all queued [`SceneCommands`] in the order they were queued
+    ///
+    /// this is called automatically after events so it is rarely needed. must not be called
+    /// from inside an event handler.
+    pub fn apply_commands(&self) {
+        loop {
+            let queued = std::mem::take(&mut *self.commands.queue.lock());
+            if queued.is_empty() {
+                break;
+            }
+            for command in queued {
+                command(self);
+            }
+        }
+    }
+
+    /// removes a node and all of its descendants from the scene
+    ///
+    /// returns false if the node does not exist. this must not be called while events are
+    /// being emitted, use [`SceneCommands::despawn`] instead.
+    pub fn despawn(&self, id: NodeId) -> bool {
+        let path = self.node_path(id);
+        let mut hierarchy = self.heirarchy.write();
+        let Some(scene_node) = hierarchy.get(&id) else {
+            return false;
+        };
+
+        let parent = scene_node.parent;
+        if let Some(parent) = parent
+            && let Some(parent_node) = hierarchy.get_mut(&parent)
+        {
+            parent_node.children.retain(|child| *child != id);
+        }
+
+        // the removed nodes are kept in their own scene until OnRemoved is emitted
+        let subtree = Scene::new();
+
+        let mut removed = Vec::new();
+        let mut stack = vec![id];
+        while let Some(current) = stack.pop() {
+            if let Some(mut scene_node) = hierarchy.remove(&current) {
+                stack.extend(scene_node.children.iter().copied());
+                if current == id {
+                    scene_node.parent = None;
+                }
+                // removed nodes always get OnRemoved even if they were disabled
+                scene_node.enabled = true;
+                subtree.heirarchy.write().insert(current, scene_node);
+                removed.push(current);
+            }
+        }
+        drop(hierarchy);
+
+        if let Some(parent) = parent {
+            self.mark_aabb_dirty(parent);
+        }
+
+        // nodes that never got OnAdded don't get OnRemoved either, the same goes for parents
+        let mut never_added = Vec::new();
+        let mut announced = true;
+        self.lifecycle_queue
+            .lock()
+            .retain(|lifecycle| match lifecycle {
+                Lifecycle::Added(queued) if removed.contains(queued) => {
+                    never_added.push(*queued);
+                    false
+                }
+                Lifecycle::Added(_) => true,
+                Lifecycle::Enabled(queued) | Lifecycle::Disabled(queued) => {
+                    !removed.contains(queued)
+                }
+                Lifecycle::ChildAdded {
+                    parent: queued_parent,
+                    child,
+                } => {
+                    if *child == id && Some(*queued_parent) == parent {
+                        announced = false;
+                    }
+                    !removed.contains(queued_parent) && !removed.contains(child)
+                }
+                Lifecycle::ChildRemoved { parent, .. } => !removed.contains(parent),
+            });
+
+        let mut nodes = self.nodes.write();
+        let mut events = self.events.write();
+        for removed_id in &removed {
+            if let Some(node) = nodes.remove(removed_id) {
+                subtree.nodes.write().insert(*removed_id, node);
+            }
+            if let Some(receiver) = events.remove(removed_id)
+                && !never_added.contains(removed_id)
+            {
+                subtree.events.write().insert(*removed_id, receiver);
+            }
+        }
+        drop((nodes, events));
+
+        self.ready_queue
+            .write()
+            .retain(|queued| !removed.contains(queued));
+        self.constraints
+            .lock()
+            .retain(|(node, _)| !removed.contains(node));
+
+        if let Some(parent) = parent
+            && announced
+        {
+            self.lifecycle_queue
+                .lock()
+                .push_back(Lifecycle::ChildRemoved {
+                    parent,
+                    child: id,
+                    path,
+                });
+        }
+
+        self.removed.lock().push(subtree);
+        self.mark_changed();
+
+        true
+    }
+
+    /// enables or disables a node and its children.
+    ///
+    /// disabled nodes don't receive events emitted to the whole scene. [`OnEnabled`] or
+    /// [`OnDisabled`] is emitted to every node in the subtree whose state changed. returns false
+    /// if the node doesn't exist. this must not be called while the node is borrowed, use
+    /// [`SceneCommands::set_enabled`] from inside an event handler.
+    pub fn set_enabled(&self, id: NodeId, enabled: bool) -> bool {
+        let Some((_, node)) = self.node_storage(id) else {
+            return false;
+        };
+        // a flag set on the node that hasn't been synced yet is replaced by this one
+        node.write().get_transform().enabled_request = None;
+        if let Some(scene_node) = self.heirarchy.write().get_mut(&id) {
+            scene_node.own_enabled = enabled;
+        }
+        self.mark_changed();
+
+        let parent_enabled = self
+            .parent_id(id)
+            .is_none_or(|parent| self.is_enabled(parent));
+        self.refresh_enabled(id, parent_enabled);
+
+        true
+    }
+
+    fn refresh_enabled(&self, id: NodeId, parent_enabled: bool) {
+        let Some(own_enabled) = self.heirarchy.read().get(&id).map(|node| node.own_enabled) else {
+            return;
+        };
+        let enabled = parent_enabled && own_enabled;
+
+        self.update_enabled(id, enabled);
+
+        for child in self.children_ids(id) {
+            self.refresh_enabled(child, enabled);
+        }
+    }
+
+    /// sets if a node is enabled in the tree queueing [`OnEnabled`] or [`OnDisabled`] if it
+    /// changed
+    fn update_enabled(&self, id: NodeId, enabled: bool) {
+        if self.is_enabled(id) == enabled {
+            return;
+        }
+
+        let mut hierarchy = self.heirarchy.write();
+        let Some(scene_node) = hierarchy.get_mut(&id) else {
+            return;
+        };
+        scene_node.enabled = enabled;
+
+        self.lifecycle_queue.lock().push_back(if enabled {
+            Lifecycle::Enabled(id)
+        } else {
+            Lifecycle::Disabled(id)
+        });
+    }
+
+    /// returns true if the node exists and it and all of its parents are enabled
+    pub fn is_enabled(&self, id: NodeId) -> bool {
+        self.heirarchy
+            .read()
+            .get(&id)
+            .is_some_and(|scene_node| scene_node.enabled)
+    }
+
+    /// shows or hides a node and its children. hidden nodes are skipped by the renderer.
+    ///
+    /// the visibility of the children is updated the next time world transforms are synced.
+    /// returns false if the node doesn't exist. this must not be called while the node is
+    /// borrowed, use [`SceneCommands::set_visible`] from inside an event handler.
+    pub fn set_visible(&self, id: NodeId, visible: bool) -> bool {
+        let Some((_, node)) = self.node_storage(id) else {
+            return false;
+        };
+        node.write().get_transform().visible_request = None;
+        if let Some(scene_node) = self.heirarchy.write().get_mut(&id) {
+            scene_node.own_visible = visible;
+        }
+        self.mark_changed();
+        true
+    }
+
+    /// true if a node moved, was despawned, enabled, disabled, shown or hidden since
+    /// [`Scene::clear_changed`]
+    ///
+    /// moves and spawns are seen when world transforms are synced. an app that renders on demand
+    /// draws another frame while this is true after a frame
+    pub fn is_changed(&self) -> bool {
+        self.changed.load(Ordering::Relaxed)
+    }
+
+    /// count the scene as changed for something it can't see itself, like a material's color
+    pub fn mark_changed(&self) {
+        self.changed.store(true, Ordering::Relaxed);
+    }
+
+    /// forget the changes so far, the context does this at the start of every frame
+    pub fn clear_changed(&self) {
+        self.changed.store(false, Ordering::Relaxed);
+    }
+
+    /// returns true if the node exists and it and all of its parents are visible.
+    ///
+    /// like [`NodeTransform::is_visible_in_tree`] this is updated when the scene syncs
+    pub fn is_visible(&self, id: NodeId) -> bool {
+        self.heirarchy
+            .read()
+            .get(&id)
+            .is_some_and(|scene_node| scene_node.visible)
+    }
+
+    /// if the node itself is enabled and visible regardless of its parents
+    pub(crate) fn own_flags(&self, id: NodeId) -> (bool, bool) {
+        self.heirarchy
+            .read()
+            .get(&id)
+            .map_or((true, true), |node| (node.own_enabled, node.own_visible))
+    }
+
+    /// moves a node under a new parent or to the root if `parent` is None
+    ///
+    /// the local transform is kept so the node will move with its new parent. returns false if
+    /// either node doesn't exist or the new parent is the node itself or one of its descendants.
+    pub fn reparent(&self, id: NodeId, parent: Option<NodeId>) -> bool {
+        let path = self.node_path(id);
+        let mut hierarchy = self.heirarchy.write();
+        if !hierarchy.contains_key(&id) {
+            return false;
+        }
+
+        if let Some(new_parent) = parent {
+            // walk up from the new parent to make sure we dont create a cycle
+            let mut current = Some(new_parent);
+            while let Some(ancestor) = current {
+                if ancestor == id {
+                    return false;
+                }
+                let Some(node) = hierarchy.get(&ancestor) else {
+                    return false;
+                };
+                current = node.parent;
+            }
+        }
+
+        let old_parent = hierarchy.get(&id).and_then(|node| node.parent);
+        if let Some(old_parent) = old_parent
+            && let Some(old_parent_node) = hierarchy.get_mut(&old_parent)
+        {
+            old_parent_node.children.retain(|child| *child != id);
+        }
+
+        if let Some(new_parent) = parent
+            && let Some(new_parent_node) = hierarchy.get_mut(&new_parent)
+        {
+            new_parent_node.children.push(id);
+        }
+
+        if let Some(node) = hierarchy.get_mut(&id) {
+            node.parent = parent;
+        }
+        drop(hierarchy);
+
+        // the local transform is the same but it is now relative to a different parent
+        self.mark_dirty(id);
+        if let Some(old_parent) = old_parent {
+            self.mark_aabb_dirty(old_parent);
+        }
+
+        if old_parent != parent {
+            let mut lifecycle = self.lifecycle_queue.lock();
+            if let Some(old_parent) = old_parent {
+                lifecycle.push_back(Lifecycle::ChildRemoved {
+                    parent: old_parent,
+                    child: id,
+                    path,
+                });
+            }
+            if let Some(parent) = parent {
+                lifecycle.push_back(Lifecycle::ChildAdded { parent, child: id });
+            }
+        }
+
+        true
+    }
+
+    /// moves a node to the root of the scene and keeps it where it is in the world, for pulling
+    /// a part off a model
+    ///
+    /// uses the world transform from the last time the scene synced. returns false if the node
+    /// doesn't exist
+    pub fn detach(&self, id: NodeId) -> bool {
+        let Some(world) = self.with_transform(id, |transform| *transform.world_space()) else {
+            return false;
+        };
+        if !self.reparent(id, None) {
+            return false;
+        }
+        self.with_transform(id, |transform| {
+            transform
+                .set_position(world.position())
+                .set_rotation(world.rotation())
+                .set_scale(world.scale());
+        });
+        true
+    }
+
+    fn mark_dirty(&self, id: NodeId) {
+        let node = self.nodes.read().get(&id).map(Arc::clone);
+        if let Some(node) = node {
+            node.write().get_transform().mark_dirty();
+        }
+    }
+
+    /// the node lost a child so its bounding box has to be merged again
+    fn mark_aabb_dirty(&self, id: NodeId) {
+        let node = self.nodes.read().get(&id).map(Arc::clone);
+        if let Some(node) = node {
+            node.write().get_transform().mark_aabb_dirty();
+        }
+    }
+
+    /// sets or clears the name of a node. returns false if the node doesn't exist
+    pub fn rename(&self, id: NodeId, name: Option<String>) -> bool {
+        match self.heirarchy.write().get_mut(&id) {
+            Some(node) => {
+                node.name = name;
+                true
+            }
+            None => false,
+        }
     }
 
     /// run a callback on each node of a specific type
@@ -725,6 +2130,133 @@ impl<'a> Scene {
         }
     }
 
+    /// depth-first traversal of the whole scene tree.
+    ///
+    /// each [`WalkEntry`] contains the path of ids from the root to the node, read access to the
+    /// node and the world transform computed from its parents during the walk, so it is correct
+    /// even if transforms were modified since the last [`Scene::sync_world_transform`].
+    ///
+    /// # Example
+    /// ```rust, ignore
+    /// for entry in scene.walk() {
+    ///     println!("{:?} at depth {} is at {}", entry.id(), entry.depth(), entry.world.position);
+    /// }
+    /// ```
+    pub fn walk(&self) -> SceneWalk<'_> {
+        let mut roots = self.root_ids();
+        roots.reverse();
+
+        SceneWalk {
+            scene: self,
+            stack: roots
+                .into_iter()
+                .map(|id| (id, WorldTransform::default(), Vec::new()))
+                .collect(),
+        }
+    }
+
+    /// depth-first traversal of the subtree starting at `id` (including `id`)
+    ///
+    /// world transforms are computed from the cached world transform of the parent of `id`
+    pub fn walk_from(&self, id: NodeId) -> SceneWalk<'_> {
+        let mut path = Vec::new();
+        let mut current = self.parent_id(id);
+        while let Some(parent) = current {
+            path.push(parent);
+            current = self.parent_id(parent);
+        }
+        path.reverse();
+
+        let parent_world = path
+            .last()
+            .and_then(|parent| self.nodes.read().get(parent).map(Arc::clone))
+            .map(|node| *node.write().get_transform().world_space())
+            .unwrap_or_default();
+
+        SceneWalk {
+            scene: self,
+            stack: vec![(id, parent_world, path)],
+        }
+    }
+
+    /// the closest node the ray hits, see [`Scene::raycast_all`]
+    ///
+    /// # Example
+    /// ```rust, ignore
+    /// let ray = camera.screen_to_ray(input.cursor_position, input.screen_size_pixels());
+    /// if let Some(hit) = ctx.scene.raycast(&ray) {
+    ///     println!("clicked {:?} at {}", ctx.scene.node_name(hit.node), hit.point);
+    /// }
+    /// ```
+    pub fn raycast(&self, ray: &Ray) -> Option<RayHit> {
+        self.raycast_all(ray).into_iter().next()
+    }
+
+    /// every node whose bounding box the ray passes through from closest to furthest
+    ///
+    /// nodes are hit with their [`crate::components::NodeTransform::local_aabb`] in world space
+    /// so nodes without one are never hit. subtrees whose merged
+    /// [`crate::components::NodeTransform::world_aabb`] the ray misses and hidden nodes are
+    /// skipped. uses the transforms from the last [`Scene::sync_world_transform`]
+    pub fn raycast_all(&self, ray: &Ray) -> Vec<RayHit> {
+        let mut hits = Vec::new();
+        let mut path = Vec::new();
+        for root in self.root_ids() {
+            self.raycast_recursive(root, ray, &mut path, &mut hits);
+        }
+
+        hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        hits
+    }
+
+    fn raycast_recursive(
+        &self,
+        id: NodeId,
+        ray: &Ray,
+        path: &mut Vec<NodeId>,
+        hits: &mut Vec<RayHit>,
+    ) {
+        let Some((_, node)) = self.node_storage(id) else {
+            return;
+        };
+
+        let distance = {
+            let mut node = node.write();
+            let transform = node.get_transform();
+            if !transform.is_visible_in_tree() {
+                return;
+            }
+
+            // the merged box covers the node and all of its children
+            if transform
+                .world_aabb()
+                .and_then(|aabb| ray.intersect_aabb(aabb))
+                .is_none()
+            {
+                return;
+            }
+
+            transform.local_aabb().and_then(|aabb| {
+                ray.intersect_aabb(&aabb.transform(transform.world_space().matrix()))
+            })
+        };
+
+        path.push(id);
+        if let Some(distance) = distance {
+            hits.push(RayHit {
+                node: id,
+                path: path.clone(),
+                distance,
+                point: ray.at(distance),
+            });
+        }
+
+        for child in self.children_ids(id) {
+            self.raycast_recursive(child, ray, path, hits);
+        }
+        path.pop();
+    }
+
     /// polls pending assets and adds them if ready
     pub fn poll_async(&mut self, assets: &AssetLibrary) {
         // Take the whole pending list out from behind the lock so we don't
@@ -842,7 +2374,7 @@ impl<T: SceneBuilder> IntoScene<BuilderMarker> for T {
 
 type InstanceableNodeStorage = Arc<RwLock<Box<dyn Instanceable>>>;
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
 pub struct InstanceId(NodeId);
 
 pub struct InstanceSceneNode {
@@ -881,11 +2413,34 @@ impl<'a> InstancableScene {
             hierarchy.keys().map(|&iid| (iid, NodeId::new())).collect();
 
         let mut new_nodes = HashMap::with_capacity(nodes.len());
+        let mut own_flags = HashMap::with_capacity(nodes.len());
+        let mut constrained = Vec::new();
         for (iid, node_storage) in nodes.iter() {
             let new_id = id_map[iid];
-            let cloned: Box<dyn Node> = node_storage.read().instance();
+            let mut cloned: Box<dyn Node> = node_storage.read().instance();
+            let transform = cloned.get_transform();
+            let enabled = transform.enabled_request.take().unwrap_or(true);
+            let visible = transform.visible_request.take().unwrap_or(true);
+            own_flags.insert(*iid, (enabled, visible));
+            if let Some(constraints) = cloned.get_transform().constraints.take() {
+                constrained.push((*iid, new_id, constraints));
+            }
             new_nodes.insert(new_id, Arc::new(RwLock::new(cloned)));
         }
+        // in the order the nodes were added so every instance is constrained the same way
+        constrained.sort_by_key(|(iid, _, _)| *iid);
+
+        let own = |iid: &InstanceId| own_flags.get(iid).copied().unwrap_or((true, true));
+        // a node is enabled or visible if it and all of its parents are
+        let in_tree = |mut iid: InstanceId, flag: fn((bool, bool)) -> bool| loop {
+            if !flag(own(&iid)) {
+                break false;
+            }
+            match hierarchy.get(&iid).and_then(|node| node.parent) {
+                Some(parent) => iid = parent,
+                None => break true,
+            }
+        };
 
         let mut new_hierarchy = HashMap::with_capacity(hierarchy.len());
         for (iid, scene_node) in hierarchy.iter() {
@@ -898,18 +2453,34 @@ impl<'a> InstancableScene {
                     children: scene_node.children.iter().map(|c| id_map[c]).collect(),
                     parent: scene_node.parent.map(|p| id_map[&p]),
                     type_id: scene_node.type_id,
+                    own_enabled: own(iid).0,
+                    own_visible: own(iid).1,
+                    enabled: in_tree(*iid, |flags| flags.0),
+                    visible: in_tree(*iid, |flags| flags.1),
                 },
             );
         }
 
         let new_ready_queue: VecDeque<NodeId> = id_map.values().copied().collect();
+        let mut lifecycle: VecDeque<Lifecycle> =
+            id_map.values().map(|id| Lifecycle::Added(*id)).collect();
+        lifecycle.extend(new_hierarchy.iter().filter_map(|(&child, node)| {
+            let parent = node.parent?;
+            Some(Lifecycle::ChildAdded { parent, child })
+        }));
 
         Scene {
             nodes: RwLock::new(new_nodes),
             heirarchy: RwLock::new(new_hierarchy),
-            events: RwLock::new(HashMap::new()),
             ready_queue: RwLock::new(new_ready_queue),
-            pending_assets: RwLock::new(Vec::new()),
+            lifecycle_queue: Mutex::new(lifecycle),
+            constraints: Mutex::new(
+                constrained
+                    .into_iter()
+                    .map(|(_, id, constraints)| (id, constraints))
+                    .collect(),
+            ),
+            ..Scene::new()
         }
     }
 
@@ -984,10 +2555,796 @@ impl<'a> InstancableScene {
     /// get all the root node ids
     pub fn root_ids(&self) -> Vec<InstanceId> {
         let hierarchy = self.heirarchy.read();
-        hierarchy
+        let mut roots: Vec<InstanceId> = hierarchy
             .iter()
             .filter(|(_, node)| node.parent.is_none())
             .map(|(id, _)| *id)
-            .collect()
+            .collect();
+        roots.sort_unstable();
+        roots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use super::*;
+    use crate::nodes::{Container, Empty};
+
+    fn empty_at(position: Vec3) -> Empty {
+        Empty {
+            transform: NodeTransform::new(position, glam::Quat::IDENTITY, Vec3::ONE),
+        }
+    }
+
+    #[test]
+    fn test_with_transform_any_node_type() {
+        let scene = Scene::new();
+        let parent = scene.spawn(empty_at(Vec3::X));
+        let child = parent.spawn_child(Container::<u32>::default());
+
+        assert_eq!(
+            scene.with_transform(child.id(), |transform| {
+                transform.position = Vec3::Y;
+                transform.position
+            }),
+            Some(Vec3::Y)
+        );
+        scene.sync_world_transform();
+        assert_eq!(
+            scene.with_transform(child.id(), |transform| transform.world_space().position()),
+            Some(Vec3::new(1.0, 1.0, 0.0))
+        );
+
+        scene.despawn(child.id());
+        assert!(scene.with_transform(child.id(), |_| ()).is_none());
+    }
+
+    #[test]
+    fn test_tweens_move_enabled_nodes() {
+        use crate::components::{TransformTween, TweenFinished};
+        use std::sync::atomic::AtomicUsize;
+
+        let ctx = GameContext::new();
+        let finished = Arc::new(AtomicUsize::new(0));
+        let counter = finished.clone();
+        let door = ctx.scene.spawn(Empty::default());
+        door.animate(TransformTween::new().position_to(Vec3::Y * 2.0, 1.0))
+            .animate(TransformTween::new().scale_to(Vec3::splat(2.0), 2.0))
+            .on::<TweenFinished>(move |_| {
+                counter.fetch_add(1, Ordering::Relaxed);
+            });
+        let gone = ctx.scene.spawn(Empty::default()).id();
+        ctx.scene.animate(gone, TransformTween::new().wait(1.0));
+        ctx.scene.despawn(gone);
+
+        let position = || ctx.scene.with_transform(door.id(), |t| t.position).unwrap();
+
+        // tweens on the same node run side by side
+        assert!(ctx.scene.update_tweens(0.5).is_empty());
+        assert_eq!(position(), Vec3::Y);
+        assert!(!ctx.scene.is_tweening(gone));
+
+        ctx.scene.set_enabled(door.id(), false);
+        assert!(ctx.scene.update_tweens(0.5).is_empty());
+        assert_eq!(position(), Vec3::Y);
+        ctx.scene.set_enabled(door.id(), true);
+
+        for id in ctx.scene.update_tweens(0.5) {
+            ctx.scene.emit_to(id, &TweenFinished, &ctx);
+        }
+        assert_eq!(position(), Vec3::Y * 2.0);
+        assert_eq!(finished.load(Ordering::Relaxed), 1);
+        assert!(ctx.scene.is_tweening(door.id()));
+
+        ctx.scene.stop_tweens(door.id());
+        assert!(!ctx.scene.is_tweening(door.id()));
+    }
+
+    #[test]
+    fn test_walk_depth_first_with_world_transform() {
+        let scene = Scene::new();
+        let root = scene.spawn(empty_at(Vec3::new(1.0, 0.0, 0.0)));
+        let child = root.spawn_child(empty_at(Vec3::new(0.0, 2.0, 0.0)));
+        let grandchild = child.spawn_child(empty_at(Vec3::new(0.0, 0.0, 3.0)));
+        let sibling = root.spawn_child(empty_at(Vec3::ZERO));
+
+        let visited: Vec<(NodeId, usize, Vec3)> = scene
+            .walk()
+            .map(|entry| (entry.id(), entry.depth(), entry.world.position()))
+            .collect();
+
+        assert_eq!(
+            visited,
+            vec![
+                (root.id(), 0, Vec3::new(1.0, 0.0, 0.0)),
+                (child.id(), 1, Vec3::new(1.0, 2.0, 0.0)),
+                (grandchild.id(), 2, Vec3::new(1.0, 2.0, 3.0)),
+                (sibling.id(), 1, Vec3::new(1.0, 0.0, 0.0)),
+            ]
+        );
+
+        let sub: Vec<NodeId> = scene.walk_from(child.id()).map(|e| e.id()).collect();
+        assert_eq!(sub, vec![child.id(), grandchild.id()]);
+    }
+
+    #[test]
+    fn test_despawn_removes_subtree() {
+        let scene = Scene::new();
+        let root = scene.spawn(Empty::default());
+        let child = root.spawn_child(Empty::default());
+        let grandchild = child.spawn_child(Empty::default());
+        let other = root.spawn_child(Empty::default());
+
+        assert!(scene.despawn(child.id()));
+        assert!(scene.get::<Empty>(child.id()).is_none());
+        assert!(scene.get::<Empty>(grandchild.id()).is_none());
+        assert_eq!(scene.children_ids(root.id()), vec![other.id()]);
+        assert!(!scene.despawn(child.id()));
+    }
+
+    #[test]
+    fn test_reparent_rejects_cycles() {
+        let scene = Scene::new();
+        let root = scene.spawn(Empty::default());
+        let child = root.spawn_child(Empty::default());
+
+        assert!(!scene.reparent(root.id(), Some(child.id())));
+        assert!(scene.reparent(child.id(), None));
+        assert!(scene.children_ids(root.id()).is_empty());
+        assert!(scene.reparent(root.id(), Some(child.id())));
+        assert_eq!(scene.parent_id(root.id()), Some(child.id()));
+    }
+
+    #[test]
+    fn test_detach_keeps_world_transform() {
+        let scene = Scene::new();
+        let root = scene.spawn(empty_at(Vec3::new(1.0, 0.0, 0.0)));
+        let child = root.spawn_child(empty_at(Vec3::new(0.0, 2.0, 0.0)));
+        scene.sync_world_transform();
+
+        assert!(scene.detach(child.id()));
+        assert_eq!(scene.parent_id(child.id()), None);
+        assert_eq!(child.read().transform.position, Vec3::new(1.0, 2.0, 0.0));
+
+        scene.sync_world_transform();
+        assert_eq!(
+            child.read().transform.world_space().position(),
+            Vec3::new(1.0, 2.0, 0.0)
+        );
+        assert!(!scene.detach(NodeId::default()));
+    }
+
+    #[test]
+    fn test_world_transform_propagates_dirty_nodes() {
+        let scene = Scene::new();
+        let root = scene.spawn(empty_at(Vec3::new(1.0, 0.0, 0.0)));
+        let child = root.spawn_child(empty_at(Vec3::new(0.0, 2.0, 0.0)));
+        let other = scene.spawn(empty_at(Vec3::new(0.0, 0.0, 5.0)));
+
+        scene.sync_world_transform();
+        assert!(!child.write().get_transform().is_dirty());
+        assert_eq!(
+            child.read().transform.world_space().position(),
+            Vec3::new(1.0, 2.0, 0.0)
+        );
+
+        // a clean child is still moved with its parent
+        root.write().transform.position.x = 3.0;
+        assert!(root.write().get_transform().is_dirty());
+        scene.sync_world_transform();
+        assert_eq!(
+            child.read().transform.world_space().position(),
+            Vec3::new(3.0, 2.0, 0.0)
+        );
+
+        scene.reparent(child.id(), Some(other.id()));
+        scene.sync_world_transform();
+        assert_eq!(
+            child.read().transform.world_space().position(),
+            Vec3::new(0.0, 2.0, 5.0)
+        );
+
+        // a transform copied from another node isn't clean for this one
+        let copied = child.read().transform;
+        root.write().transform = copied;
+        scene.sync_world_transform();
+        assert_eq!(
+            root.read().transform.world_space().position(),
+            Vec3::new(0.0, 2.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_scene_tracks_changes() {
+        let scene = Scene::new();
+        let node = scene.spawn(empty_at(Vec3::ZERO));
+        scene.sync_world_transform();
+        assert!(scene.is_changed());
+
+        // a sync with nothing moved isn't a change
+        scene.clear_changed();
+        scene.sync_world_transform();
+        assert!(!scene.is_changed());
+
+        node.write().transform.position.x = 1.0;
+        scene.sync_world_transform();
+        assert!(scene.is_changed());
+
+        scene.clear_changed();
+        scene.set_visible(node.id(), false);
+        assert!(scene.is_changed());
+    }
+
+    #[test]
+    fn test_interpolated_nodes_render_between_fixed_steps() {
+        let scene = Scene::new();
+        let root = scene.spawn(empty_at(Vec3::ZERO));
+        let child = root.spawn_child(empty_at(Vec3::new(0.0, 1.0, 0.0)));
+        root.write().transform.set_interpolated(true);
+        scene.sync_world_transform();
+
+        // a fixed update moves the root
+        root.write().transform.position.x = 2.0;
+        scene.sync_world_transform_with(&mut TransformSync {
+            fixed_step: true,
+            ..Default::default()
+        });
+        scene.sync_world_transform_with(&mut TransformSync {
+            alpha: 0.25,
+            ..Default::default()
+        });
+
+        let root = root.read();
+        let child = child.read();
+        assert_eq!(
+            root.transform.world_space().position(),
+            Vec3::new(2.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            root.transform.render_space().position(),
+            Vec3::new(0.5, 0.0, 0.0)
+        );
+        assert_eq!(
+            child.transform.world_space().position(),
+            Vec3::new(2.0, 1.0, 0.0)
+        );
+        assert_eq!(
+            child.transform.render_space().position(),
+            Vec3::new(0.5, 1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_spatial_hash_follows_synced_nodes() {
+        use crate::resources::SpatialHash;
+
+        let ctx = GameContext::new();
+        let root = ctx.scene.spawn(empty_at(Vec3::ZERO));
+        let child = root.spawn_child(empty_at(Vec3::new(1.0, 0.0, 0.0)));
+        ctx.get_resource_mut::<SpatialHash>()
+            .insert(child.id(), Vec3::new(1.0, 0.0, 0.0));
+
+        // moving the parent moves the registered child
+        root.write().transform.position = Vec3::new(50.0, 0.0, 0.0);
+        ctx.sync_world_transform();
+        {
+            let spatial_hash = ctx.get_resource::<SpatialHash>();
+            assert!(spatial_hash.nearby(Vec3::ZERO, 5.0).is_empty());
+            assert_eq!(
+                spatial_hash.nearby(Vec3::new(50.0, 0.0, 0.0), 5.0),
+                vec![child.id()]
+            );
+        }
+
+        ctx.scene.despawn(root.id());
+        ctx.sync_world_transform();
+        assert!(ctx.get_resource::<SpatialHash>().is_empty());
+    }
+
+    struct Shoot;
+    impl EventLabel for Shoot {}
+
+    #[test]
+    fn test_commands_applied_after_emit() {
+        let ctx = GameContext::new();
+        let gun = ctx.scene.spawn_with_name("gun", Empty::default());
+        gun.on::<Shoot>(|ctx| {
+            let parent = ctx.node_id();
+            ctx.commands()
+                .spawn_as_child_with(Empty::default(), parent, |bullet| {
+                    bullet.on::<Shoot>(|_| {});
+                });
+            ctx.commands().rename(parent, "fired");
+            // nothing is applied until the event pass is over
+            assert!(ctx.node_children_ids().is_empty());
+        });
+
+        ctx.emit(Shoot);
+
+        assert_eq!(ctx.scene.children_ids(gun.id()).len(), 1);
+        assert_eq!(gun.name().as_deref(), Some("fired"));
+        assert!(ctx.scene.commands().is_empty());
+    }
+
+    #[test]
+    fn test_commands_sent_from_another_thread() {
+        let ctx = GameContext::new();
+        let commands = ctx.scene.commands().clone();
+        let id = std::thread::spawn(move || commands.spawn_with_name("remote", Empty::default()))
+            .join()
+            .unwrap();
+
+        // nothing happens until the next frame starts
+        assert!(ctx.scene.get::<Empty>(id).is_none());
+        ctx.pop_ready_queue();
+        assert_eq!(ctx.scene.node_name(id).as_deref(), Some("remote"));
+        assert!(ctx.scene.commands().is_empty());
+    }
+
+    #[test]
+    fn test_lifecycle_events() {
+        use std::sync::Mutex;
+
+        let ctx = GameContext::new();
+        let log = Arc::new(Mutex::new(Vec::new()));
+
+        let root = ctx.scene.spawn(Empty::default());
+        let child = root.spawn_child(Empty::default());
+        for id in [root.id(), child.id()] {
+            let (added, removed, disabled) = (log.clone(), log.clone(), log.clone());
+            let node = ctx.scene.get::<Empty>(id).unwrap();
+            node.on::<OnAdded>(move |_| added.lock().unwrap().push(("added", id)))
+                .on::<OnRemoved>(move |ctx| {
+                    // the removed node can still be accessed
+                    let _ = ctx.node_ref();
+                    removed.lock().unwrap().push(("removed", id));
+                })
+                .on::<OnDisabled>(move |_| disabled.lock().unwrap().push(("disabled", id)));
+        }
+
+        ctx.pop_ready_queue();
+        assert_eq!(log.lock().unwrap().len(), 2);
+
+        assert!(ctx.scene.set_enabled(child.id(), false));
+        assert!(!ctx.scene.is_enabled(child.id()));
+        ctx.pop_ready_queue();
+        ctx.scene.despawn(root.id());
+        ctx.pop_ready_queue();
+
+        let log = log.lock().unwrap();
+        assert_eq!(log[2], ("disabled", child.id()));
+        assert!(log.contains(&("removed", root.id())));
+        assert!(log.contains(&("removed", child.id())));
+        assert_eq!(log.len(), 5);
+    }
+
+    #[test]
+    fn test_parents_hear_about_children() {
+        use std::sync::Mutex;
+
+        let ctx = GameContext::new();
+        let log = Arc::new(Mutex::new(Vec::new()));
+
+        let (added, removed) = (log.clone(), log.clone());
+        let vehicle = ctx.scene.spawn_with_name("car", Empty::default());
+        vehicle
+            .on::<OnChildAdded>(move |ctx| added.lock().unwrap().push(("added", ctx.path.clone())))
+            .on::<OnChildRemoved>(move |ctx| {
+                removed.lock().unwrap().push(("removed", ctx.path.clone()))
+            });
+        let trailer = ctx.scene.spawn_with_name("trailer", Empty::default());
+
+        let wheel = vehicle
+            .spawn_child_with_name("wheel", Empty::default())
+            .id();
+        ctx.pop_ready_queue();
+        ctx.scene.reparent(wheel, Some(trailer.id()));
+        ctx.scene.reparent(trailer.id(), Some(vehicle.id()));
+        ctx.pop_ready_queue();
+        ctx.scene.despawn(trailer.id());
+        // children that are gone before the events go out aren't announced
+        let spare = vehicle.spawn_child(Empty::default()).id();
+        ctx.scene.despawn(spare);
+        ctx.pop_ready_queue();
+
+        let path = |path: &str| Some(path.to_string());
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                ("added", path("car/wheel")),
+                ("removed", path("car/wheel")),
+                ("added", path("car/trailer")),
+                ("removed", path("car/trailer")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_disabled_subtree_is_paused() {
+        use crate::prelude::Update;
+        use std::sync::atomic::AtomicUsize;
+
+        let ctx = GameContext::new();
+        let updates = Arc::new(AtomicUsize::new(0));
+        let disabled = Arc::new(AtomicUsize::new(0));
+
+        let root = ctx.scene.spawn(Empty::default());
+        let child = root.spawn_child(Empty::default());
+        let (child_updates, child_disabled) = (updates.clone(), disabled.clone());
+        child
+            .on::<Update>(move |_| {
+                child_updates.fetch_add(1, Ordering::Relaxed);
+            })
+            .on::<OnDisabled>(move |_| {
+                child_disabled.fetch_add(1, Ordering::Relaxed);
+            });
+        ctx.pop_ready_queue();
+
+        ctx.scene.set_enabled(root.id(), false);
+        assert!(!ctx.scene.is_enabled(child.id()));
+        ctx.emit(Update {
+            dt: 0.0,
+            unscaled_dt: 0.0,
+        });
+        assert_eq!(updates.load(Ordering::Relaxed), 0);
+        assert_eq!(disabled.load(Ordering::Relaxed), 1);
+
+        // flags set on the node itself are picked up when the scene syncs
+        root.write().set_enabled(true);
+        ctx.scene.sync_world_transform();
+        ctx.emit(Update {
+            dt: 0.0,
+            unscaled_dt: 0.0,
+        });
+        assert_eq!(updates.load(Ordering::Relaxed), 1);
+
+        root.write().set_visible(false);
+        ctx.scene.sync_world_transform();
+        assert!(!ctx.scene.is_visible(child.id()));
+        assert!(ctx.scene.collect_visible::<Empty>().is_empty());
+    }
+
+    #[test]
+    fn test_copied_transforms_keep_the_nodes_flags() {
+        let scene = Scene::new();
+        let hidden = scene.spawn(Empty::default());
+        let shown = scene.spawn(Empty::default());
+        scene.set_visible(hidden.id(), false);
+        scene.set_enabled(hidden.id(), false);
+        scene.sync_world_transform();
+
+        // flags belong to the node so assigning a whole transform doesn't move them
+        let copied = *hidden.write().get_transform();
+        *shown.write().get_transform() = copied;
+        scene.sync_world_transform();
+        assert!(scene.is_visible(shown.id()) && scene.is_enabled(shown.id()));
+        assert!(!scene.is_visible(hidden.id()) && !scene.is_enabled(hidden.id()));
+        assert_eq!(scene.collect_visible::<Empty>().len(), 1);
+    }
+
+    #[test]
+    fn test_bubbling_stops_when_consumed() {
+        use std::sync::Mutex;
+
+        struct Clicked;
+        impl EventLabel for Clicked {}
+
+        let ctx = GameContext::new();
+        let received = Arc::new(Mutex::new(Vec::new()));
+
+        let log = |name: &'static str, consume: bool| {
+            let received = received.clone();
+            move |ctx: EventCtx<Clicked, Empty>| {
+                received.lock().unwrap().push(name);
+                if consume {
+                    ctx.consume();
+                }
+            }
+        };
+
+        let root = ctx.scene.spawn_with_name("ui", Empty::default());
+        root.on::<Clicked>(log("ui", false));
+        let panel = root.spawn_child_with_name("panel", Empty::default());
+        panel.on::<Clicked>(log("panel", true));
+        let button = panel.spawn_child_with_name("button", Empty::default());
+        button.on::<Clicked>(log("button", false));
+
+        assert!(ctx.bubble("ui/panel/button", Clicked));
+        assert_eq!(*received.lock().unwrap(), vec!["button", "panel"]);
+
+        received.lock().unwrap().clear();
+        assert!(ctx.emit_to("ui/panel/button", Clicked));
+        assert!(!ctx.emit_to("ui/missing", Clicked));
+        assert!(!ctx.scene.bubble(root.id(), &Clicked, &ctx));
+        assert_eq!(*received.lock().unwrap(), vec!["button", "ui"]);
+    }
+
+    #[test]
+    fn test_handler_priority_orders_across_nodes() {
+        use crate::prelude::Update;
+        use std::sync::Mutex;
+
+        let ctx = GameContext::new();
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let push = |name: &'static str| {
+            let order = order.clone();
+            move |_: EventCtx<Update, Empty>| order.lock().unwrap().push(name)
+        };
+
+        let camera = ctx.scene.spawn(Empty::default());
+        camera.on::<Update>(push("camera"));
+        camera
+            .spawn_child(Empty::default())
+            .on_with_priority::<Update>(-100, push("animation"));
+        let player = ctx.scene.spawn(Empty::default());
+        player
+            .on::<Update>(push("player"))
+            .on_with_priority::<Update>(100, push("input"));
+
+        ctx.emit(Update {
+            dt: 0.0,
+            unscaled_dt: 0.0,
+        });
+        assert_eq!(
+            *order.lock().unwrap(),
+            vec!["input", "camera", "player", "animation"]
+        );
+    }
+
+    #[test]
+    fn test_deferred_events_wait_for_flush() {
+        use crate::prelude::Update;
+        use std::sync::atomic::AtomicUsize;
+
+        struct Hit;
+        impl EventLabel for Hit {}
+
+        let ctx = GameContext::new();
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        ctx.scene
+            .spawn(Empty::default())
+            .on::<Update>(|ctx| ctx.game.emit_deferred(Hit))
+            .on::<Hit>(move |_| {
+                counter.fetch_add(1, Ordering::Relaxed);
+            });
+
+        ctx.emit(Update {
+            dt: 0.0,
+            unscaled_dt: 0.0,
+        });
+        assert_eq!(hits.load(Ordering::Relaxed), 0);
+        ctx.flush_deferred();
+        assert_eq!(hits.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_events_deferred_during_a_flush_wait_for_the_next() {
+        use std::sync::atomic::AtomicUsize;
+
+        #[derive(Clone, Copy)]
+        struct Hit;
+        impl EventLabel for Hit {}
+
+        let ctx = GameContext::new();
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        ctx.scene.spawn(Empty::default()).on::<Hit>(move |ctx| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            ctx.game.emit_deferred(Hit);
+        });
+
+        ctx.emit_deferred(Hit);
+        ctx.flush_deferred();
+        assert_eq!(hits.load(Ordering::Relaxed), 1);
+        ctx.flush_deferred();
+        assert_eq!(hits.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_world_aabb_merges_up_the_tree() {
+        let scene = Scene::new();
+        let unit = Some(AABB::new(Vec3::splat(-1.0), Vec3::ONE));
+
+        let root = scene.spawn(empty_at(Vec3::new(10.0, 0.0, 0.0)));
+        let child = root.spawn_child(empty_at(Vec3::new(0.0, 5.0, 0.0)));
+        let grandchild = child.spawn_child(empty_at(Vec3::new(0.0, 0.0, -4.0)));
+        child.write().transform.set_local_aabb(unit);
+        grandchild.write().transform.set_local_aabb(unit);
+        scene.sync_world_transform();
+
+        let merged = AABB::new(Vec3::new(9.0, 4.0, -5.0), Vec3::new(11.0, 6.0, 1.0));
+        assert_eq!(root.write().world_aabb(), Some(merged));
+        assert_eq!(
+            grandchild.write().world_aabb().unwrap().center(),
+            Vec3::new(10.0, 5.0, -4.0)
+        );
+
+        root.write().transform.set_position((0.0, 0.0, 0.0));
+        scene.sync_world_transform();
+        assert_eq!(
+            root.write().world_aabb().unwrap().center(),
+            Vec3::new(0.0, 5.0, -2.0)
+        );
+
+        scene.despawn(grandchild.id());
+        scene.sync_world_transform();
+        assert_eq!(
+            root.write().world_aabb(),
+            Some(AABB::new(
+                Vec3::new(-1.0, 4.0, -1.0),
+                Vec3::new(1.0, 6.0, 1.0)
+            ))
+        );
+
+        child.write().transform.set_local_aabb(None);
+        scene.sync_world_transform();
+        assert_eq!(root.write().world_aabb(), None);
+    }
+
+    #[test]
+    fn test_raycast_hits_closest_box_first() {
+        let scene = Scene::new();
+        let unit = Some(AABB::new(Vec3::splat(-1.0), Vec3::ONE));
+
+        let root = scene.spawn(empty_at(Vec3::ZERO));
+        let near = root.spawn_child(empty_at(Vec3::new(0.0, 0.0, -5.0)));
+        let far = root.spawn_child(empty_at(Vec3::new(0.0, 0.0, -10.0)));
+        let aside = scene.spawn(empty_at(Vec3::new(5.0, 0.0, -5.0)));
+        for node in [near.id(), far.id(), aside.id()] {
+            scene
+                .get::<Empty>(node)
+                .unwrap()
+                .write()
+                .transform
+                .set_local_aabb(unit);
+        }
+        scene.sync_world_transform();
+
+        let ray = Ray::new(Vec3::ZERO, Vec3::NEG_Z);
+        let hits = scene.raycast_all(&ray);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].node, near.id());
+        assert_eq!(hits[0].path, vec![root.id(), near.id()]);
+        assert!((hits[0].distance - 4.0).abs() < 1e-4);
+        assert!(hits[0].point.distance(Vec3::new(0.0, 0.0, -4.0)) < 1e-4);
+        assert_eq!(hits[1].node, far.id());
+
+        near.write().set_visible(false);
+        scene.sync_world_transform();
+        assert_eq!(scene.raycast(&ray).map(|hit| hit.node), Some(far.id()));
+
+        let miss = Ray::new(Vec3::ZERO, Vec3::Z);
+        assert!(scene.raycast(&miss).is_none());
+    }
+
+    #[test]
+    fn test_constraints_follow_and_look_at_target() {
+        use crate::components::TransformConstraints;
+
+        let ctx = GameContext::new();
+        let target = ctx.scene.spawn(empty_at(Vec3::new(1.0, 0.0, 0.0)));
+        let rig = ctx.scene.spawn(empty_at(Vec3::new(10.0, 0.0, 0.0)));
+        let camera = rig.spawn_child(Empty::default());
+        ctx.scene.set_constraints(
+            camera.id(),
+            TransformConstraints::new()
+                .follow(target.id(), Vec3::new(0.0, 2.0, 5.0))
+                .clamp(Vec3::splat(-100.0), Vec3::new(100.0, 1.5, 100.0))
+                .look_at(target.id()),
+        );
+        ctx.sync_world_transform();
+
+        ctx.apply_constraints();
+        let world = *camera.write().get_transform().world_space();
+        assert!(world.position().abs_diff_eq(Vec3::new(1.0, 1.5, 5.0), 1e-5));
+        let facing = (Vec3::new(1.0, 0.0, 0.0) - world.position()).normalize();
+        assert!(world.forward().abs_diff_eq(facing, 1e-5));
+
+        // nothing moves once the node has settled and despawned targets are ignored
+        assert!(!ctx.scene.apply_constraints(0.0));
+        ctx.scene.despawn(target.id());
+        assert!(!ctx.scene.apply_constraints(0.0));
+    }
+
+    #[test]
+    fn test_constraints_stay_with_their_node() {
+        use crate::{Buildable, Builder, components::TransformConstraints};
+
+        let ctx = GameContext::new();
+        let target = ctx.scene.spawn(empty_at(Vec3::new(4.0, 0.0, 0.0))).id();
+        let leader = ctx.scene.spawn(
+            Empty::builder()
+                .constraints(TransformConstraints::new().follow(target, Vec3::Y))
+                .build(),
+        );
+        let other = ctx.scene.spawn(Empty::default());
+        // chained constraints read the leader after it moved since it was constrained first
+        let follower = ctx.scene.spawn(Empty::default());
+        ctx.scene.set_constraints(
+            follower.id(),
+            TransformConstraints::new().follow(leader.id(), Vec3::Y),
+        );
+        assert!(ctx.scene.constraints(leader.id()).is_some());
+
+        // copying a transform copies where a node is, not how it's constrained
+        let copied = *leader.write().get_transform();
+        *other.write().get_transform() = copied;
+        ctx.sync_world_transform();
+        ctx.apply_constraints();
+
+        let position = |node: NodeId| {
+            let node = ctx.scene.node_storage(node).unwrap().1;
+            node.write().get_transform().world_space().position()
+        };
+        assert_eq!(position(leader.id()), Vec3::new(4.0, 1.0, 0.0));
+        assert_eq!(position(follower.id()), Vec3::new(4.0, 2.0, 0.0));
+        assert_eq!(position(other.id()), Vec3::ZERO);
+        assert!(ctx.scene.constraints(other.id()).is_none());
+
+        ctx.scene.clear_constraints(follower.id());
+        ctx.scene.despawn(leader.id());
+        assert!(ctx.scene.constraints(leader.id()).is_none());
+        assert!(!ctx.scene.apply_constraints(0.0));
+    }
+
+    #[test]
+    fn test_constrained_children_follow_their_parents_this_frame() {
+        use crate::components::TransformConstraints;
+
+        let ctx = GameContext::new();
+        let rig = ctx.scene.spawn(Empty::default());
+        let camera = rig.spawn_child(Empty::default());
+        // the child is registered first but its parent still has to move first
+        ctx.scene.set_constraints(
+            camera.id(),
+            TransformConstraints::new().follow(Vec3::new(0.0, 0.0, 5.0), Vec3::ZERO),
+        );
+        ctx.scene.set_constraints(
+            rig.id(),
+            TransformConstraints::new().follow(Vec3::new(10.0, 0.0, 0.0), Vec3::ZERO),
+        );
+        ctx.sync_world_transform();
+
+        ctx.apply_constraints();
+        let world = *camera.write().get_transform().world_space();
+        assert_eq!(world.position(), Vec3::new(0.0, 0.0, 5.0));
+        assert!(!ctx.scene.apply_constraints(0.0));
+    }
+
+    #[test]
+    fn test_world_setters_use_the_current_parent() {
+        let scene = Scene::new();
+        let parent = scene.spawn(empty_at(Vec3::new(10.0, 0.0, 0.0)));
+        let child = parent.spawn_child(Empty::default());
+        scene.sync_world_transform();
+
+        // the parent moves and grows after the last sync
+        parent
+            .write()
+            .get_transform()
+            .set_position(Vec3::new(0.0, 5.0, 0.0))
+            .set_scale(Vec3::splat(2.0));
+
+        scene.set_world_position(child.id(), Vec3::new(1.0, 1.0, 1.0));
+        scene.set_world_scale(child.id(), Vec3::ONE);
+        let rotation = Quat::from_rotation_y(1.0);
+        scene.set_world_rotation(child.id(), rotation);
+
+        let world = scene.world_transform(child.id()).unwrap();
+        assert!(world.position().abs_diff_eq(Vec3::new(1.0, 1.0, 1.0), 1e-5));
+        assert!(world.scale().abs_diff_eq(Vec3::ONE, 1e-5));
+        assert!(world.rotation().abs_diff_eq(rotation, 1e-5));
+
+        scene.sync_world_transform();
+        let synced = *child.write().get_transform().world_space();
+        assert!(
+            synced
+                .position()
+                .abs_diff_eq(Vec3::new(1.0, 1.0, 1.0), 1e-5)
+        );
+        assert!(scene.world_transform(NodeId(u64::MAX)).is_none());
     }
 }
@@ -0,0 +1,340 @@
+//! saving and loading scenes to json files
+//!
+//! a saved scene stores the node tree with each node's name, type, transform and any fields the
+//! node serializes. node types need to be registered in the [`NodeRegistry`] resource under a
+//! stable name to be saved with their data, unregistered nodes are saved with only their transform
+//! and are loaded back as [`Empty`] nodes.
+//!
+//! ```rust, ignore
+//! #[derive(Node, Serialize, Deserialize)]
+//! struct Spawner {
+//!     #[transform]
+//!     transform: NodeTransform,
+//!     interval: f32,
+//! }
+//!
+//! ctx.get_resource_mut::<NodeRegistry>().register::<Spawner>("spawner");
+//!
+//! let registry = ctx.get_resource::<NodeRegistry>();
+//! ctx.scene.save("res/scenes/level.json", &registry)?;
+//!
+//! let level = Scene::load("res/scenes/level.json", &registry)?;
+//! ctx.scene.merge(level);
+//! ```
+
+use std::{any::TypeId, collections::HashMap, error::Error, fmt::Display, path::Path};
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+
+use crate::{
+    components::NodeTransform,
+    context::Resource,
+    nodes::{Empty, Node},
+    scene::{NodeId, Scene},
+};
+
+/// Error that happened while saving or loading a scene
+#[derive(Debug)]
+pub enum SceneFileError {
+    /// the file could not be read or written
+    Io(std::io::Error),
+    /// the file is not a valid scene
+    Format(String),
+    /// a registered node failed to serialize or deserialize
+    Node {
+        /// the registered name of the node type
+        type_name: String,
+        error: String,
+    },
+}
+
+impl Display for SceneFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SceneFileError::Io(e) => {
+                write!(f, "failed to access scene file: {}", e)
+            }
+            SceneFileError::Format(e) => {
+                write!(f, "invalid scene file: {}", e)
+            }
+            SceneFileError::Node { type_name, error } => {
+                write!(
+                    f,
+                    "failed to serialize node of type {}: {}",
+                    type_name, error
+                )
+            }
+        }
+    }
+}
+
+impl Error for SceneFileError {}
+
+impl From<std::io::Error> for SceneFileError {
+    fn from(value: std::io::Error) -> Self {
+        SceneFileError::Io(value)
+    }
+}
+
+type SaveFn = fn(&dyn Node) -> Result<Value, serde_json::Error>;
+type LoadFn = fn(Value) -> Result<Box<dyn Node>, serde_json::Error>;
+
+struct RegisteredNode {
+    save: SaveFn,
+    load: LoadFn,
+}
+
+/// maps node types to the names they are saved under
+///
+/// the engine inserts this resource with [`Empty`] already registered.
+pub struct NodeRegistry {
+    nodes: HashMap<String, RegisteredNode>,
+    names: HashMap<TypeId, String>,
+}
+
+impl Resource for NodeRegistry {}
+
+impl Default for NodeRegistry {
+    fn default() -> Self {
+        let mut registry = Self {
+            nodes: HashMap::new(),
+            names: HashMap::new(),
+        };
+        registry.register::<Empty>("empty");
+        registry
+    }
+}
+
+impl NodeRegistry {
+    /// create a registry with the default engine nodes registered
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// register a node type so it can be saved and loaded with its data.
+    ///
+    /// `name` is written to the scene file so it should not change between versions of the game.
+    /// registering the same type or name again replaces the old entry.
+    pub fn register<T>(&mut self, name: impl Into<String>) -> &mut Self
+    where
+        T: Node + Serialize + DeserializeOwned,
+    {
+        let name = name.into();
+
+        if let Some(old) = self.names.insert(TypeId::of::<T>(), name.clone()) {
+            self.nodes.remove(&old);
+        }
+
+        self.nodes.insert(
+            name,
+            RegisteredNode {
+                save: |node| {
+                    let node = node
+                        .downcast::<T>()
+                        .expect("registered node type should match the scene type");
+                    serde_json::to_value(node)
+                },
+                load: |value| Ok(Box::new(serde_json::from_value::<T>(value)?)),
+            },
+        );
+
+        self
+    }
+
+    /// the name a node type was registered under
+    pub fn name_of<T: Node>(&self) -> Option<&str> {
+        self.names.get(&TypeId::of::<T>()).map(String::as_str)
+    }
+
+    /// true if a type was registered under `name`
+    pub fn contains(&self, name: &str) -> bool {
+        self.nodes.contains_key(name)
+    }
+}
+
+/// a saved scene file
+#[derive(Serialize, Deserialize)]
+struct SceneFile {
+    nodes: Vec<SavedNode>,
+}
+
+/// a node and its children in a saved scene
+#[derive(Serialize, Deserialize)]
+struct SavedNode {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    /// the registered type name or None if the type wasn't registered
+    #[serde(rename = "type", default)]
+    type_name: Option<String>,
+    #[serde(default)]
+    transform: NodeTransform,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    data: Option<Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    children: Vec<SavedNode>,
+}
+
+impl Scene {
+    /// save the scene to a json file at `path` with [`crate::fs::write_atomic`]. see
+    /// [`crate::serialization`]
+    pub fn save(
+        &self,
+        path: impl AsRef<Path>,
+        registry: &NodeRegistry,
+    ) -> Result<(), SceneFileError> {
+        let json = self.to_json(registry)?;
+        crate::fs::write_atomic(path, json)?;
+        Ok(())
+    }
+
+    /// load a scene from a json file at `path`. see [`crate::serialization`]
+    pub fn load(path: impl AsRef<Path>, registry: &NodeRegistry) -> Result<Scene, SceneFileError> {
+        let json = std::fs::read_to_string(path)?;
+        Self::from_json(&json, registry)
+    }
+
+    /// serialize the scene to a json string
+    pub fn to_json(&self, registry: &NodeRegistry) -> Result<String, SceneFileError> {
+        let nodes = self
+            .root_ids()
+            .into_iter()
+            .map(|id| self.save_node(id, registry))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        serde_json::to_string_pretty(&SceneFile { nodes })
+            .map_err(|e| SceneFileError::Format(e.to_string()))
+    }
+
+    /// create a scene from a json string created by [`Scene::to_json`]
+    pub fn from_json(json: &str, registry: &NodeRegistry) -> Result<Scene, SceneFileError> {
+        let file: SceneFile =
+            serde_json::from_str(json).map_err(|e| SceneFileError::Format(e.to_string()))?;
+
+        let scene = Scene::new();
+        for node in file.nodes {
+            scene.load_node(node, None, registry)?;
+        }
+
+        Ok(scene)
+    }
+
+    fn save_node(&self, id: NodeId, registry: &NodeRegistry) -> Result<SavedNode, SceneFileError> {
+        let (type_id, storage) = self
+            .node_storage(id)
+            .ok_or_else(|| SceneFileError::Format(format!("node {:?} is missing", id)))?;
+
+        let type_name = registry.names.get(&type_id).cloned();
+
+        let (transform, data) = {
+            let mut node = storage.write();
+            // the flags are kept by the scene but saved with the transform
+            let mut transform = *node.get_transform();
+            let (enabled, visible) = self.own_flags(id);
+            transform.enabled_request = Some(transform.enabled_request.unwrap_or(enabled));
+            transform.visible_request = Some(transform.visible_request.unwrap_or(visible));
+
+            let data = match &type_name {
+                Some(name) => {
+                    let entry = &registry.nodes[name];
+                    let value = (entry.save)(&**node).map_err(|e| SceneFileError::Node {
+                        type_name: name.clone(),
+                        error: e.to_string(),
+                    })?;
+                    Some(value)
+                }
+                None => None,
+            };
+
+            (transform, data)
+        };
+
+        let children = self
+            .children_ids(id)
+            .into_iter()
+            .map(|child| self.save_node(child, registry))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(SavedNode {
+            name: self.node_name(id),
+            type_name,
+            transform,
+            data,
+            children,
+        })
+    }
+
+    fn load_node(
+        &self,
+        saved: SavedNode,
+        parent: Option<NodeId>,
+        registry: &NodeRegistry,
+    ) -> Result<(), SceneFileError> {
+        let entry = saved
+            .type_name
+            .as_ref()
+            .and_then(|name| registry.nodes.get(name).map(|entry| (name, entry)));
+
+        let mut node: Box<dyn Node> = match (entry, saved.data) {
+            (Some((name, entry)), Some(data)) => {
+                (entry.load)(data).map_err(|e| SceneFileError::Node {
+                    type_name: name.clone(),
+                    error: e.to_string(),
+                })?
+            }
+            _ => {
+                if let Some(name) = &saved.type_name {
+                    log::warn!("node type {name} is not registered, loading as Empty");
+                }
+                Box::new(Empty::default())
+            }
+        };
+
+        *node.get_transform() = saved.transform;
+
+        let id = self.spawn_boxed(saved.name, node, parent);
+
+        for child in saved.children {
+            self.load_node(child, Some(id), registry)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use super::*;
+
+    #[test]
+    fn test_scene_round_trip() {
+        let scene = Scene::new();
+        let root = scene.spawn_with_name(
+            "root",
+            Empty {
+                transform: NodeTransform::new(
+                    Vec3::new(1.0, 2.0, 3.0),
+                    Default::default(),
+                    Vec3::ONE,
+                ),
+            },
+        );
+        root.spawn_child_with_name("child", Empty::default());
+
+        let registry = NodeRegistry::default();
+        let json = scene.to_json(&registry).unwrap();
+        let loaded = Scene::from_json(&json, &registry).unwrap();
+
+        let root = loaded.get_by_name::<Empty>("root").unwrap();
+        assert_eq!(root.read().transform.position, Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(
+            root.children_ids()
+                .into_iter()
+                .filter_map(|id| loaded.node_name(id))
+                .collect::<Vec<_>>(),
+            vec!["child".to_string()]
+        );
+    }
+}
@@ -0,0 +1,684 @@
+//! async tasks for gameplay that happens over several frames
+//!
+//! a cutscene, a door that opens after a switch is held or a boss that waits between attacks
+//! reads top to bottom as an async block instead of a state machine spread over update handlers:
+//!
+//! ```rust,ignore
+//! scene.spawn(Empty::default()).on_async::<Ready, _, _>(|task| async move {
+//!     task.wait(1.0).await;
+//!     task.node(|door: &mut Empty| door.transform.position.y += 2.0).await;
+//!
+//!     let TriggerEntered(other) = task.event::<TriggerEntered>().await;
+//!     let music = task.load::<Audio>("res/boss.ogg").await;
+//! });
+//! ```
+//!
+//! tasks are run by the [`GameContext`] once a frame after update and are polled until they wait
+//! for something: a timer, the next frame, an event reaching their node or an asset. a task
+//! reaches the game with [`TaskCtx::with`] which runs straight away in the same frame, it doesn't
+//! hold the context across awaits.
+//!
+//! tasks started on a node are dropped with it and paused while it is disabled, their timers
+//! don't run while they are paused. tasks started while an event is being handled first run
+//! after update that frame
+
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    future::Future,
+    marker::PhantomData,
+    path::Path,
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use parking_lot::Mutex;
+
+use crate::{
+    asset::{Asset, AssetHandle, AssetLibrary, AssetStatus, FileLoader, LoadErr},
+    components::EventLabel,
+    context::GameContext,
+    nodes::Node,
+    platform::SendSync,
+    scene::NodeId,
+};
+
+/// how many times a task can reach the game in one frame before it is continued in the next
+const MAX_REQUESTS_PER_FRAME: usize = 1000;
+
+type Request = Box<dyn FnOnce(&GameContext) + Send>;
+type Reply<R> = Box<dyn FnOnce(&GameContext) -> R + Send>;
+type BoxedTask = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// runs the tasks of a [`GameContext`], see the [module docs](self)
+pub(crate) struct Tasks {
+    tasks: Mutex<Vec<Task>>,
+    /// tasks started since the last step, first polled in the next one
+    spawned: Mutex<Vec<Task>>,
+    shared: Arc<SharedState>,
+    /// true while the tasks are polled so a task can't step them again
+    stepping: AtomicBool,
+}
+
+struct Task {
+    node: Option<NodeId>,
+    future: BoxedTask,
+    state: Arc<TaskState>,
+}
+
+/// what the tasks share with the executor
+struct SharedState {
+    assets: AssetLibrary,
+    clock: Mutex<Clock>,
+    mailboxes: Mutex<HashMap<(NodeId, TypeId), Mailbox>>,
+}
+
+#[derive(Default)]
+struct Clock {
+    /// game seconds the tasks have been stepped for
+    time: f64,
+    unscaled_time: f64,
+    time_scale: f64,
+    steps: u64,
+    /// the soonest a waiting task wants to be polled again in real seconds
+    next_wake: Option<f64>,
+}
+
+/// the events of one type that reached a node while tasks wait for them
+#[derive(Default)]
+struct Mailbox {
+    waiting: usize,
+    next: u64,
+    events: Vec<(u64, Arc<dyn Any + Send + Sync>)>,
+}
+
+#[derive(Default)]
+struct TaskState {
+    requests: Mutex<Vec<Request>>,
+    /// the step and the event after the last one the task got of each mailbox
+    cursors: Mutex<HashMap<(NodeId, TypeId), (u64, u64)>>,
+    cancelled: AtomicBool,
+    finished: AtomicBool,
+    /// game and real seconds the task's node was disabled for, taken off its timers
+    paused: Mutex<(f64, f64)>,
+}
+
+impl Tasks {
+    pub(crate) fn new(assets: AssetLibrary) -> Self {
+        Self {
+            tasks: Mutex::new(Vec::new()),
+            spawned: Mutex::new(Vec::new()),
+            shared: Arc::new(SharedState {
+                assets,
+                clock: Mutex::new(Clock::default()),
+                mailboxes: Mutex::new(HashMap::new()),
+            }),
+            stepping: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn spawn<F, Fut>(&self, node: Option<NodeId>, f: F) -> TaskHandle
+    where
+        F: FnOnce(TaskCtx) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let state = Arc::new(TaskState::default());
+        let ctx = TaskCtx {
+            node,
+            state: Arc::clone(&state),
+            shared: Arc::clone(&self.shared),
+        };
+
+        self.spawned.lock().push(Task {
+            node,
+            future: Box::pin(f(ctx)),
+            state: Arc::clone(&state),
+        });
+        TaskHandle { state }
+    }
+
+    /// how many tasks haven't finished
+    pub(crate) fn len(&self) -> usize {
+        self.tasks.lock().len() + self.spawned.lock().len()
+    }
+
+    /// advance the clock and poll every task until it waits, `dt` is game time
+    pub(crate) fn step(&self, game: &GameContext, dt: f32, unscaled_dt: f32) {
+        if self.stepping.swap(true, Ordering::AcqRel) {
+            log::warn!("tasks can't be stepped from inside a task");
+            return;
+        }
+
+        {
+            let mut clock = self.shared.clock.lock();
+            clock.time += dt as f64;
+            clock.unscaled_time += unscaled_dt as f64;
+            clock.time_scale = game.time_scale() as f64;
+            clock.steps += 1;
+            clock.next_wake = None;
+        }
+        // events from before this step have been seen by every waiting task after it
+        let seen: HashMap<(NodeId, TypeId), u64> = self
+            .shared
+            .mailboxes
+            .lock()
+            .iter()
+            .map(|(key, mailbox)| (*key, mailbox.next))
+            .collect();
+
+        let mut tasks = std::mem::take(&mut *self.tasks.lock());
+        tasks.append(&mut self.spawned.lock());
+
+        let waker = Waker::noop();
+        let mut cx = Context::from_waker(waker);
+        tasks.retain_mut(|task| {
+            if task.state.cancelled.load(Ordering::Acquire) {
+                return false;
+            }
+            if let Some(node) = task.node {
+                if game.scene.node_storage(node).is_none() {
+                    return false;
+                }
+                if !game.scene.is_enabled(node) {
+                    let mut paused = task.state.paused.lock();
+                    paused.0 += dt as f64;
+                    paused.1 += unscaled_dt as f64;
+                    return true;
+                }
+            }
+
+            for _ in 0..MAX_REQUESTS_PER_FRAME {
+                if task.future.as_mut().poll(&mut cx).is_ready() {
+                    task.state.finished.store(true, Ordering::Release);
+                    return false;
+                }
+
+                let requests = std::mem::take(&mut *task.state.requests.lock());
+                if requests.is_empty() {
+                    return true;
+                }
+                for request in requests {
+                    request(game);
+                }
+            }
+            log::warn!("a task reached the game {MAX_REQUESTS_PER_FRAME} times without waiting");
+            true
+        });
+
+        // tasks started by other tasks are kept for the next step
+        let mut running = self.tasks.lock();
+        tasks.append(&mut running);
+        *running = tasks;
+        drop(running);
+
+        let mut mailboxes = self.shared.mailboxes.lock();
+        mailboxes.retain(|(node, _), _| game.scene.node_storage(*node).is_some());
+        for (key, mailbox) in mailboxes.iter_mut() {
+            if let Some(seen) = seen.get(key) {
+                mailbox.events.retain(|(seq, _)| seq >= seen);
+            }
+        }
+        drop(mailboxes);
+
+        // an app rendering on demand is woken for the next timer
+        if let Some(wake) = self.shared.clock.lock().next_wake {
+            game.request_redraw_after(Duration::from_secs_f64(wake.max(0.0)));
+        }
+
+        self.stepping.store(false, Ordering::Release);
+    }
+}
+
+/// a task started with [`GameContext::spawn`] or [`crate::components::EventCtx::spawn`]
+#[derive(Clone)]
+pub struct TaskHandle {
+    state: Arc<TaskState>,
+}
+
+impl TaskHandle {
+    /// stop the task where it is waiting, it is dropped in the next step
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::Release);
+    }
+
+    /// true once the task ran to the end
+    pub fn is_finished(&self) -> bool {
+        self.state.finished.load(Ordering::Acquire)
+    }
+}
+
+/// what an async task uses to wait and to reach the game, cheap to clone and held across awaits
+#[derive(Clone)]
+pub struct TaskCtx {
+    node: Option<NodeId>,
+    state: Arc<TaskState>,
+    shared: Arc<SharedState>,
+}
+
+impl TaskCtx {
+    /// the node the task was started on, `None` for tasks started with [`GameContext::spawn`]
+    pub fn node_id(&self) -> Option<NodeId> {
+        self.node
+    }
+
+    /// the asset library, usable without awaiting
+    pub fn assets(&self) -> &AssetLibrary {
+        &self.shared.assets
+    }
+
+    /// run `f` with the game context, it runs in the same frame as the task gets to it
+    ///
+    /// ```rust,ignore
+    /// let score = task.with(|ctx| ctx.get_resource::<Score>().points).await;
+    /// ```
+    pub fn with<R, F>(&self, f: F) -> With<R>
+    where
+        F: FnOnce(&GameContext) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        With {
+            request: Some(Box::new(f)),
+            result: Arc::new(Mutex::new(None)),
+            state: Arc::clone(&self.state),
+        }
+    }
+
+    /// change the task's node, `None` if it isn't a node of type `N` anymore
+    pub fn node<N, R, F>(&self, f: F) -> With<Option<R>>
+    where
+        N: Node,
+        F: FnOnce(&mut N) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let node = self.node;
+        self.with(move |ctx| {
+            let handle = ctx.scene.get::<N>(node?)?;
+            let mut node = handle.write();
+            Some(f(&mut node))
+        })
+    }
+
+    /// wait `seconds` of game time, this is slowed and paused with
+    /// [`GameContext::set_time_scale`]
+    pub fn wait(&self, seconds: f32) -> Wait {
+        Wait {
+            seconds: seconds as f64,
+            until: None,
+            unscaled: false,
+            state: Arc::clone(&self.state),
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    /// wait `seconds` of real time even while the game is paused
+    pub fn wait_unscaled(&self, seconds: f32) -> Wait {
+        Wait {
+            unscaled: true,
+            ..self.wait(seconds)
+        }
+    }
+
+    /// wait until the next frame
+    pub fn next_frame(&self) -> NextFrame {
+        NextFrame {
+            polled: false,
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    /// wait for the next `E` to reach the task's node, broadcast or sent to it
+    ///
+    /// # Panics
+    /// panics if the task wasn't started on a node, use [`TaskCtx::event_on`] for those
+    pub fn event<E: EventLabel + Clone + SendSync>(&self) -> NextEvent<E> {
+        let node = self
+            .node
+            .expect("TaskCtx::event needs a task started on a node, use event_on instead");
+        self.event_on(node)
+    }
+
+    /// wait for the next `E` to reach `node`
+    pub fn event_on<E: EventLabel + Clone + SendSync>(&self, node: NodeId) -> NextEvent<E> {
+        NextEvent {
+            node,
+            since: Arc::new(Mutex::new(None)),
+            state: Arc::clone(&self.state),
+            shared: Arc::clone(&self.shared),
+            requested: false,
+            done: false,
+            _event: PhantomData,
+        }
+    }
+
+    /// load an asset and wait for it to finish, see [`AssetLibrary::load`]
+    pub fn load<T: Asset>(&self, path: impl AsRef<Path>) -> Loading<T>
+    where
+        T::Loader: FileLoader,
+    {
+        self.loaded(self.shared.assets.load::<T>(path))
+    }
+
+    /// wait for an asset to finish loading
+    pub fn loaded<T: Asset>(&self, handle: AssetHandle<T>) -> Loading<T> {
+        Loading {
+            handle: Some(handle),
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+/// the future of [`TaskCtx::with`]
+pub struct With<R> {
+    request: Option<Reply<R>>,
+    result: Arc<Mutex<Option<R>>>,
+    state: Arc<TaskState>,
+}
+
+impl<R: Send + 'static> Future for With<R> {
+    type Output = R;
+
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<R> {
+        if let Some(request) = self.request.take() {
+            let result = Arc::clone(&self.result);
+            self.state.requests.lock().push(Box::new(move |ctx| {
+                *result.lock() = Some(request(ctx));
+            }));
+            return Poll::Pending;
+        }
+
+        match self.result.lock().take() {
+            Some(result) => Poll::Ready(result),
+            None => Poll::Pending,
+        }
+    }
+}
+
+/// the future of [`TaskCtx::wait`]
+pub struct Wait {
+    seconds: f64,
+    /// the time on the task's clock it wakes at
+    until: Option<f64>,
+    unscaled: bool,
+    state: Arc<TaskState>,
+    shared: Arc<SharedState>,
+}
+
+impl Future for Wait {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+        let this = &mut *self;
+        let mut clock = this.shared.clock.lock();
+        let (paused, unscaled_paused) = *this.state.paused.lock();
+        let now = match this.unscaled {
+            true => clock.unscaled_time - unscaled_paused,
+            false => clock.time - paused,
+        };
+        let until = *this.until.get_or_insert(now + this.seconds);
+        if now >= until {
+            return Poll::Ready(());
+        }
+
+        // a paused game leaves waking the app to whatever unpauses it
+        let remaining = match this.unscaled {
+            true => until - now,
+            false if clock.time_scale > 0.0 => (until - now) / clock.time_scale,
+            false => return Poll::Pending,
+        };
+        clock.next_wake = Some(
+            clock
+                .next_wake
+                .map_or(remaining, |wake| wake.min(remaining)),
+        );
+        Poll::Pending
+    }
+}
+
+/// the future of [`TaskCtx::next_frame`]
+pub struct NextFrame {
+    polled: bool,
+    shared: Arc<SharedState>,
+}
+
+impl Future for NextFrame {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+        if self.polled {
+            return Poll::Ready(());
+        }
+        self.polled = true;
+        self.shared.clock.lock().next_wake = Some(0.0);
+        Poll::Pending
+    }
+}
+
+/// the future of [`TaskCtx::event`]
+pub struct NextEvent<E: 'static> {
+    node: NodeId,
+    /// the first event number this waits for once it is listening
+    since: Arc<Mutex<Option<u64>>>,
+    state: Arc<TaskState>,
+    shared: Arc<SharedState>,
+    requested: bool,
+    done: bool,
+    _event: PhantomData<fn() -> E>,
+}
+
+impl<E: EventLabel + Clone + SendSync> Future for NextEvent<E> {
+    type Output = E;
+
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<E> {
+        let key = (self.node, TypeId::of::<E>());
+        let Some(since) = *self.since.lock() else {
+            // listening to the node's events needs the scene
+            let since = Arc::clone(&self.since);
+            let state = Arc::clone(&self.state);
+            let shared = Arc::clone(&self.shared);
+            let node = self.node;
+            if !std::mem::replace(&mut self.requested, true) {
+                self.state.requests.lock().push(Box::new(move |ctx| {
+                    let steps = shared.clock.lock().steps;
+                    let mut mailboxes = shared.mailboxes.lock();
+                    let listening = mailboxes.contains_key(&key);
+                    let mailbox = mailboxes.entry(key).or_default();
+                    mailbox.waiting += 1;
+                    // waiting again in the same step picks up the events after the last one
+                    *since.lock() = Some(match state.cursors.lock().get(&key) {
+                        Some(&(step, next)) if step == steps => next,
+                        _ => mailbox.next,
+                    });
+                    drop(mailboxes);
+
+                    if !listening {
+                        let shared = Arc::clone(&shared);
+                        ctx.scene.listen::<E>(node, move |event| {
+                            let mut mailboxes = shared.mailboxes.lock();
+                            if let Some(mailbox) = mailboxes.get_mut(&key)
+                                && mailbox.waiting > 0
+                            {
+                                let seq = mailbox.next;
+                                mailbox.next += 1;
+                                mailbox.events.push((seq, Arc::new(event.clone())));
+                            }
+                        });
+                    }
+                }));
+            }
+            return Poll::Pending;
+        };
+
+        let event = {
+            let mailboxes = self.shared.mailboxes.lock();
+            mailboxes.get(&key).and_then(|mailbox| {
+                mailbox
+                    .events
+                    .iter()
+                    .find(|(seq, _)| *seq >= since)
+                    .and_then(|(seq, event)| Some((*seq, event.downcast_ref::<E>()?.clone())))
+            })
+        };
+        match event {
+            Some((seq, event)) => {
+                let steps = self.shared.clock.lock().steps;
+                self.state.cursors.lock().insert(key, (steps, seq + 1));
+                self.stop_waiting();
+                Poll::Ready(event)
+            }
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl<E: 'static> NextEvent<E> {
+    fn stop_waiting(&mut self) {
+        if self.done || self.since.lock().is_none() {
+            return;
+        }
+        self.done = true;
+        let key = (self.node, TypeId::of::<E>());
+        if let Some(mailbox) = self.shared.mailboxes.lock().get_mut(&key) {
+            mailbox.waiting = mailbox.waiting.saturating_sub(1);
+        }
+    }
+}
+
+impl<E: 'static> Drop for NextEvent<E> {
+    fn drop(&mut self) {
+        self.stop_waiting();
+    }
+}
+
+/// the future of [`TaskCtx::load`], gives back the handle once the asset loaded
+pub struct Loading<T: Asset> {
+    handle: Option<AssetHandle<T>>,
+    shared: Arc<SharedState>,
+}
+
+impl<T: Asset> Future for Loading<T> {
+    type Output = Result<AssetHandle<T>, LoadErr>;
+
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let Some(handle) = self.handle.as_ref() else {
+            return Poll::Ready(Err(LoadErr::Missing));
+        };
+
+        match self.shared.assets.get_status(handle) {
+            AssetStatus::Loading => Poll::Pending,
+            AssetStatus::Loaded(_) | AssetStatus::Borrowed => {
+                Poll::Ready(Ok(self.handle.take().unwrap()))
+            }
+            AssetStatus::Error(err) => Poll::Ready(Err(err)),
+            AssetStatus::Removed => Poll::Ready(Err(LoadErr::Missing)),
+        }
+    }
+}
+
+impl<T: Asset> Unpin for Loading<T> {}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+    use crate::{components::Ready, nodes::Empty};
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Hit(u32);
+    impl EventLabel for Hit {}
+
+    #[test]
+    fn test_tasks_wait_and_reach_the_game() {
+        let ctx = GameContext::new();
+        let steps = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&steps);
+        let handle = ctx.spawn(|task| async move {
+            task.wait(1.0).await;
+            let count = Arc::clone(&counter);
+            task.with(move |_| count.fetch_add(1, Ordering::Relaxed))
+                .await;
+            task.next_frame().await;
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+        assert_eq!(ctx.task_count(), 1);
+
+        // the wait starts when the task first runs
+        ctx.tasks.step(&ctx, 0.5, 0.5);
+        ctx.tasks.step(&ctx, 0.5, 0.5);
+        assert_eq!(steps.load(Ordering::Relaxed), 0);
+        ctx.tasks.step(&ctx, 0.5, 0.5);
+        assert_eq!(steps.load(Ordering::Relaxed), 1);
+        assert!(!handle.is_finished());
+        ctx.tasks.step(&ctx, 0.0, 0.0);
+        assert_eq!(steps.load(Ordering::Relaxed), 2);
+        assert!(handle.is_finished());
+        assert_eq!(ctx.task_count(), 0);
+
+        let cancelled = ctx.spawn(|task| async move { task.wait(1.0).await });
+        cancelled.cancel();
+        ctx.tasks.step(&ctx, 2.0, 2.0);
+        assert!(!cancelled.is_finished());
+        assert_eq!(ctx.task_count(), 0);
+    }
+
+    #[test]
+    fn test_node_tasks_wait_for_events() {
+        let ctx = GameContext::new();
+        let hits = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&hits);
+        let node = ctx.scene.spawn(Empty::default());
+        node.on_async::<Ready, _, _>(move |task| {
+            let seen = Arc::clone(&seen);
+            async move {
+                loop {
+                    let Hit(damage) = task.event::<Hit>().await;
+                    seen.lock().push(damage);
+                }
+            }
+        });
+        let id = node.id();
+
+        // hits before the task waits for them are missed
+        ctx.scene.emit_to(id, &Hit(1), &ctx);
+        ctx.scene.emit_to(id, &Ready, &ctx);
+        ctx.tasks.step(&ctx, 0.1, 0.1);
+        assert_eq!(ctx.task_count(), 1);
+
+        ctx.scene.emit_to(id, &Hit(2), &ctx);
+        ctx.scene.emit_to(id, &Hit(3), &ctx);
+        ctx.tasks.step(&ctx, 0.1, 0.1);
+        ctx.tasks.step(&ctx, 0.1, 0.1);
+        assert_eq!(*hits.lock(), vec![2, 3]);
+
+        // disabled nodes keep their tasks without running them, their timers included
+        let waited = Arc::new(AtomicBool::new(false));
+        let done = Arc::clone(&waited);
+        ctx.tasks.spawn(Some(id), move |task| async move {
+            task.wait(0.5).await;
+            done.store(true, Ordering::Relaxed);
+        });
+        ctx.tasks.step(&ctx, 0.1, 0.1);
+        ctx.scene.set_enabled(id, false);
+        for _ in 0..10 {
+            ctx.tasks.step(&ctx, 0.1, 0.1);
+        }
+        assert_eq!(ctx.task_count(), 2);
+        ctx.scene.set_enabled(id, true);
+        ctx.tasks.step(&ctx, 0.1, 0.1);
+        assert!(!waited.load(Ordering::Relaxed));
+        for _ in 0..5 {
+            ctx.tasks.step(&ctx, 0.1, 0.1);
+        }
+        assert!(waited.load(Ordering::Relaxed));
+
+        ctx.scene.despawn(id);
+        ctx.tasks.step(&ctx, 0.1, 0.1);
+        assert_eq!(ctx.task_count(), 0);
+        assert!(ctx.tasks.shared.mailboxes.lock().is_empty());
+    }
+}
@@ -0,0 +1,289 @@
+//! transitions between scenes such as fading through black or wiping the new scene in
+//!
+//! [`SceneTransitions::change_scene`] replaces every root node of the scene with another scene
+//! behind a [`Transition`]. the renderer draws the effect over the frame, the old scene is kept as
+//! the last frame drawn before the swap for the effects that show both at once:
+//! - [`TransitionEffect::Fade`] covers the old scene with a color and uncovers the new one, the
+//!   scenes are swapped when the screen is fully covered
+//! - [`TransitionEffect::Crossfade`] blends from the last frame of the old scene to the new scene
+//! - [`TransitionEffect::Wipe`] moves an edge across the screen with the new scene behind it
+//!
+//! ```rust,ignore
+//! ctx.game.get_resource_mut::<SceneTransitions>().change_scene(
+//!     Transition::fade(Color::BLACK, 1.0).ease(Ease::InOutSine),
+//!     Level2,
+//! );
+//! ```
+//!
+//! [`TransitionFinished`] is emitted to the new scene once the effect is done. the app inserts the
+//! resource and calls [`SceneTransitions::update`] every frame
+
+use glam::Vec2;
+
+use crate::{
+    color::Color,
+    components::{EventLabel, tween::Ease},
+    context::{GameContext, Resource},
+    scene::{IntoScene, Scene},
+};
+
+/// emitted to the scene once a transition of [`SceneTransitions`] is done
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransitionFinished;
+impl EventLabel for TransitionFinished {}
+
+/// how the old scene is replaced on screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransitionEffect {
+    /// fade the old scene out to the color and the new scene in from it
+    Fade(Color),
+    /// blend from the old scene to the new one
+    Crossfade,
+    /// reveal the new scene behind an edge moving across the screen
+    Wipe {
+        /// the direction the edge moves in, in uv space so [`Vec2::X`] wipes from left to right
+        direction: Vec2,
+        /// how much of the screen the edge is blurred over, `0.0` for a hard edge
+        softness: f32,
+    },
+}
+
+impl TransitionEffect {
+    /// true if the effect needs the last frame of the old scene after the swap
+    pub fn shows_both_scenes(&self) -> bool {
+        !matches!(self, TransitionEffect::Fade(_))
+    }
+}
+
+/// an effect played over a scene change, see the [module docs](self)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transition {
+    pub effect: TransitionEffect,
+    /// seconds from the start of the effect to its end
+    pub duration: f32,
+    /// the curve the effect follows
+    ///
+    /// Default: [`Ease::Linear`]
+    pub ease: Ease,
+}
+
+impl Transition {
+    pub fn new(effect: TransitionEffect, duration: f32) -> Self {
+        Self {
+            effect,
+            duration,
+            ease: Ease::Linear,
+        }
+    }
+
+    /// fade out to `color` and in to the new scene
+    pub fn fade(color: Color, duration: f32) -> Self {
+        Self::new(TransitionEffect::Fade(color), duration)
+    }
+
+    /// blend from the old scene to the new one
+    pub fn crossfade(duration: f32) -> Self {
+        Self::new(TransitionEffect::Crossfade, duration)
+    }
+
+    /// wipe the new scene in from the side `direction` points away from
+    pub fn wipe(direction: Vec2, duration: f32) -> Self {
+        Self::new(
+            TransitionEffect::Wipe {
+                direction,
+                softness: 0.05,
+            },
+            duration,
+        )
+    }
+
+    pub fn ease(mut self, ease: Ease) -> Self {
+        self.ease = ease;
+        self
+    }
+}
+
+/// what the renderer draws over a frame while a transition runs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransitionFrame {
+    pub effect: TransitionEffect,
+    /// how far along the eased effect is from `0.0` to `1.0`
+    pub progress: f32,
+    /// the frame shows the old scene for the last time and should be kept, only for effects
+    /// that [show both scenes](TransitionEffect::shows_both_scenes)
+    pub capture: bool,
+}
+
+type BuildScene = Box<dyn FnOnce(&crate::asset::AssetLibrary) -> Scene + Send + Sync>;
+
+struct ActiveTransition {
+    transition: Transition,
+    /// taken when the scenes are swapped
+    target: Option<BuildScene>,
+    elapsed: f32,
+    /// updates since the transition was started, the frame drawn after the first one still shows
+    /// the old scene
+    frames: u32,
+}
+
+impl ActiveTransition {
+    fn progress(&self) -> f32 {
+        let duration = self.transition.duration.max(f32::EPSILON);
+        self.transition.ease.apply(self.elapsed / duration)
+    }
+}
+
+/// plays [`Transition`]s between scenes, see the [module docs](self)
+#[derive(Default)]
+pub struct SceneTransitions {
+    active: Option<ActiveTransition>,
+}
+
+impl Resource for SceneTransitions {}
+
+impl SceneTransitions {
+    /// replace the scene with `scene` behind `transition`. a transition that is still running is
+    /// cancelled and its scene is never swapped in if it wasn't yet
+    pub fn change_scene<S, M>(&mut self, transition: Transition, scene: S)
+    where
+        S: IntoScene<M> + Send + Sync + 'static,
+    {
+        self.active = Some(ActiveTransition {
+            transition,
+            target: Some(Box::new(move |assets| scene.into_scene(assets))),
+            elapsed: 0.0,
+            frames: 0,
+        });
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.active.is_some()
+    }
+
+    /// what to draw over this frame, `None` if no transition is running
+    pub fn frame(&self) -> Option<TransitionFrame> {
+        let active = self.active.as_ref()?;
+        Some(TransitionFrame {
+            effect: active.transition.effect,
+            progress: active.progress(),
+            capture: active.frames == 1 && active.transition.effect.shows_both_scenes(),
+        })
+    }
+
+    /// advance the running transition by `dt` seconds, swapping the scenes and emitting
+    /// [`TransitionFinished`] when it's time
+    pub fn update(ctx: &GameContext, dt: f32) {
+        if !ctx.has_resource::<SceneTransitions>() {
+            return;
+        }
+
+        // the resource isn't held while the scene is built or events are emitted so handlers can
+        // start another transition
+        let (target, finished) = {
+            let mut transitions = ctx.get_resource_mut::<SceneTransitions>();
+            let Some(active) = &mut transitions.active else {
+                return;
+            };
+
+            // the first frame is drawn with the old scene at the start of the effect
+            if active.frames > 0 {
+                active.elapsed += dt;
+            }
+            active.frames += 1;
+
+            let swap = match active.transition.effect {
+                // swapped while the screen is covered
+                TransitionEffect::Fade(_) => active.progress() >= 0.5,
+                // once the first frame was drawn and kept
+                _ => active.frames > 1,
+            };
+            let target = swap.then(|| active.target.take()).flatten();
+            let finished = active.target.is_none() && active.elapsed >= active.transition.duration;
+            if finished {
+                transitions.active = None;
+            }
+            (target, finished)
+        };
+
+        if let Some(target) = target {
+            for root in ctx.scene.root_ids() {
+                ctx.scene.despawn(root);
+            }
+            ctx.scene.merge(target(&ctx.assets));
+        }
+
+        if finished {
+            ctx.emit(TransitionFinished);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::Empty;
+
+    fn level(name: &'static str) -> impl Fn() -> Scene {
+        move || {
+            let scene = Scene::default();
+            scene.spawn_with_name(name, Empty::default());
+            scene
+        }
+    }
+
+    #[test]
+    fn test_fade_swaps_scenes_when_covered() {
+        let mut ctx = GameContext::new();
+        ctx.insert_resource(SceneTransitions::default());
+        ctx.scene.merge(level("old")());
+        ctx.get_resource_mut::<SceneTransitions>()
+            .change_scene(Transition::fade(Color::BLACK, 1.0), level("new"));
+
+        // nothing moves on the first frame
+        SceneTransitions::update(&ctx, 0.4);
+        assert_eq!(
+            ctx.get_resource::<SceneTransitions>()
+                .frame()
+                .unwrap()
+                .progress,
+            0.0
+        );
+        SceneTransitions::update(&ctx, 0.4);
+        assert!(ctx.scene.get_id_by_path("old").is_some());
+
+        SceneTransitions::update(&ctx, 0.2);
+        assert!(ctx.scene.get_id_by_path("old").is_none());
+        assert!(ctx.scene.get_id_by_path("new").is_some());
+
+        SceneTransitions::update(&ctx, 0.5);
+        assert!(!ctx.get_resource::<SceneTransitions>().is_running());
+    }
+
+    #[test]
+    fn test_crossfade_keeps_the_first_frame() {
+        let mut ctx = GameContext::new();
+        ctx.insert_resource(SceneTransitions::default());
+        ctx.scene.merge(level("old")());
+        ctx.get_resource_mut::<SceneTransitions>()
+            .change_scene(Transition::crossfade(0.5), level("new"));
+
+        // the frame after the first update is drawn with the old scene and kept
+        SceneTransitions::update(&ctx, 0.1);
+        assert!(
+            ctx.get_resource::<SceneTransitions>()
+                .frame()
+                .unwrap()
+                .capture
+        );
+        assert!(ctx.scene.get_id_by_path("old").is_some());
+
+        SceneTransitions::update(&ctx, 0.1);
+        assert!(ctx.scene.get_id_by_path("old").is_none());
+        assert!(ctx.scene.get_id_by_path("new").is_some());
+        let frame = ctx.get_resource::<SceneTransitions>().frame().unwrap();
+        assert!(!frame.capture);
+
+        SceneTransitions::update(&ctx, 0.4);
+        assert!(ctx.get_resource::<SceneTransitions>().frame().is_none());
+    }
+}
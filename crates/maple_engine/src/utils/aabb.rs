@@ -1,43 +1,39 @@
-use glam::{Mat4, Vec3};
+//! axis aligned bounding boxes
+//!
+//! nodes can be given a local bounding box with [`crate::components::NodeTransform::set_local_aabb`]
+//! and the scene merges them up the tree into [`crate::components::NodeTransform::world_aabb`]
 
-use crate::math::Vertex;
+use glam::{Mat4, Vec3};
 
-#[derive(Clone, Copy, Debug)]
+/// an axis aligned box between two corners
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct AABB {
     pub min: Vec3,
     pub max: Vec3,
 }
 
 impl AABB {
-    /// create an AABB bounding box from a list of vertices
-    pub fn from_vertices(positions: &[Vertex]) -> Self {
-        let Some(first) = positions.first() else {
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self {
+            min: min.min(max),
+            max: min.max(max),
+        }
+    }
+
+    /// the smallest box around the points, a box at the origin if there are none
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Self {
+        let mut points = points.into_iter();
+        let Some(first) = points.next() else {
             return Self {
                 min: Vec3::ZERO,
                 max: Vec3::ZERO,
             };
         };
 
-        let mut min_x = first.position[0];
-        let mut min_y = first.position[1];
-        let mut min_z = first.position[2];
-        let mut max_x = first.position[0];
-        let mut max_y = first.position[1];
-        let mut max_z = first.position[2];
-
-        for v in positions.iter().skip(1) {
-            min_x = min_x.min(v.position[0]);
-            min_y = min_y.min(v.position[1]);
-            min_z = min_z.min(v.position[2]);
-            max_x = max_x.max(v.position[0]);
-            max_y = max_y.max(v.position[1]);
-            max_z = max_z.max(v.position[2]);
-        }
-
-        Self {
-            min: Vec3::new(min_x, min_y, min_z),
-            max: Vec3::new(max_x, max_y, max_z),
-        }
+        points.fold(Self::new(first, first), |aabb, point| Self {
+            min: aabb.min.min(point),
+            max: aabb.max.max(point),
+        })
     }
 
     pub fn from_positions(positions: &[[f32; 3]]) -> Self {
@@ -109,4 +105,29 @@ impl AABB {
 
         result
     }
+
+    /// the smallest box containing both boxes
+    pub fn merge(&self, other: &Self) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+
+    /// true if the boxes overlap or touch
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.cmple(other.max).all() && self.max.cmpge(other.min).all()
+    }
 }
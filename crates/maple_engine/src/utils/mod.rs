@@ -0,0 +1,15 @@
+//! general helpers that don't belong to the scene or a resource
+//!
+//! - [`aabb`] axis aligned bounding boxes
+//! - [`noise`] seeded perlin, simplex and fractal noise with a matching WGSL version
+//! - [`random`] weighted picks, seeded rngs and random directions
+//! - [`ray`] rays and their intersections with boxes and triangles
+//! - [`smoothing`] easing towards a target the same way at any frame rate
+//! - [`stress`] large random scenes for stress tests and fuzzing
+
+pub mod aabb;
+pub mod noise;
+pub mod random;
+pub mod ray;
+pub mod smoothing;
+pub mod stress;
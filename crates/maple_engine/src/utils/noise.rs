@@ -0,0 +1,351 @@
+//! seeded gradient noise for terrain, particles and gameplay
+//!
+//! every function here has a twin in [`NOISE_WGSL`] that returns the same values so terrain
+//! heights worked out on the CPU for physics or placement match what a shader draws. the hash is
+//! integer only and the gradients come from a fixed table so the results don't depend on the
+//! platform's trig functions.
+//!
+//! all noise functions return values roughly between -1 and 1.
+//!
+//! # Example
+//! ```rust
+//! use glam::Vec2;
+//! use maple_engine::utils::noise::{Fbm, NoiseKind, perlin2};
+//!
+//! let height = perlin2(Vec2::new(1.5, 2.25), 42);
+//! assert!((-1.0..=1.0).contains(&height));
+//!
+//! let terrain = Fbm {
+//!     kind: NoiseKind::Simplex,
+//!     octaves: 5,
+//!     frequency: 0.01,
+//!     ..Default::default()
+//! };
+//! let height = terrain.sample2(Vec2::new(120.0, 40.0), 42) * 30.0;
+//! ```
+
+use glam::{IVec2, IVec3, Vec2, Vec3};
+
+/// the WGSL version of this module
+///
+/// add it in front of a shader's source to use `noise_perlin2`, `noise_perlin3`,
+/// `noise_simplex2`, `noise_simplex3`, `noise_fbm2` and `noise_fbm3` in it. a seed of `u32`
+/// passed to a WGSL function gives the same noise as passing it to the function of the same name
+/// here.
+pub const NOISE_WGSL: &str = include_str!("noise.wgsl");
+
+/// a 32 bit integer hash (PCG output permutation)
+pub fn hash(value: u32) -> u32 {
+    let state = value.wrapping_mul(747796405).wrapping_add(2891336453);
+    let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+    (word >> 22) ^ word
+}
+
+/// hash of a 2D grid cell
+pub fn hash2(cell: IVec2, seed: u32) -> u32 {
+    hash((cell.x as u32) ^ hash((cell.y as u32) ^ hash(seed)))
+}
+
+/// hash of a 3D grid cell
+pub fn hash3(cell: IVec3, seed: u32) -> u32 {
+    hash((cell.x as u32) ^ hash((cell.y as u32) ^ hash((cell.z as u32) ^ hash(seed))))
+}
+
+const DIAGONAL: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+/// eight evenly spaced unit directions
+const GRADIENTS_2D: [Vec2; 8] = [
+    Vec2::new(1.0, 0.0),
+    Vec2::new(-1.0, 0.0),
+    Vec2::new(0.0, 1.0),
+    Vec2::new(0.0, -1.0),
+    Vec2::new(DIAGONAL, DIAGONAL),
+    Vec2::new(-DIAGONAL, DIAGONAL),
+    Vec2::new(DIAGONAL, -DIAGONAL),
+    Vec2::new(-DIAGONAL, -DIAGONAL),
+];
+
+/// the twelve edges of a cube
+const GRADIENTS_3D: [Vec3; 12] = [
+    Vec3::new(1.0, 1.0, 0.0),
+    Vec3::new(-1.0, 1.0, 0.0),
+    Vec3::new(1.0, -1.0, 0.0),
+    Vec3::new(-1.0, -1.0, 0.0),
+    Vec3::new(1.0, 0.0, 1.0),
+    Vec3::new(-1.0, 0.0, 1.0),
+    Vec3::new(1.0, 0.0, -1.0),
+    Vec3::new(-1.0, 0.0, -1.0),
+    Vec3::new(0.0, 1.0, 1.0),
+    Vec3::new(0.0, -1.0, 1.0),
+    Vec3::new(0.0, 1.0, -1.0),
+    Vec3::new(0.0, -1.0, -1.0),
+];
+
+fn gradient2(cell: IVec2, seed: u32) -> Vec2 {
+    GRADIENTS_2D[(hash2(cell, seed) & 7) as usize]
+}
+
+fn gradient3(cell: IVec3, seed: u32) -> Vec3 {
+    GRADIENTS_3D[(hash3(cell, seed) % 12) as usize]
+}
+
+/// quintic smoothstep used to blend between grid cells
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// 2D perlin noise
+pub fn perlin2(point: Vec2, seed: u32) -> f32 {
+    let cell = point.floor();
+    let local = point - cell;
+    let cell = cell.as_ivec2();
+
+    let corner = |offset: IVec2| gradient2(cell + offset, seed).dot(local - offset.as_vec2());
+
+    let u = Vec2::new(fade(local.x), fade(local.y));
+    let bottom = lerp(corner(IVec2::new(0, 0)), corner(IVec2::new(1, 0)), u.x);
+    let top = lerp(corner(IVec2::new(0, 1)), corner(IVec2::new(1, 1)), u.x);
+
+    // unit gradients reach at most sqrt(0.5) in the middle of a cell
+    lerp(bottom, top, u.y) * std::f32::consts::SQRT_2
+}
+
+/// 3D perlin noise
+pub fn perlin3(point: Vec3, seed: u32) -> f32 {
+    let cell = point.floor();
+    let local = point - cell;
+    let cell = cell.as_ivec3();
+
+    let corner = |offset: IVec3| gradient3(cell + offset, seed).dot(local - offset.as_vec3());
+
+    let u = Vec3::new(fade(local.x), fade(local.y), fade(local.z));
+    let x00 = lerp(
+        corner(IVec3::new(0, 0, 0)),
+        corner(IVec3::new(1, 0, 0)),
+        u.x,
+    );
+    let x10 = lerp(
+        corner(IVec3::new(0, 1, 0)),
+        corner(IVec3::new(1, 1, 0)),
+        u.x,
+    );
+    let x01 = lerp(
+        corner(IVec3::new(0, 0, 1)),
+        corner(IVec3::new(1, 0, 1)),
+        u.x,
+    );
+    let x11 = lerp(
+        corner(IVec3::new(0, 1, 1)),
+        corner(IVec3::new(1, 1, 1)),
+        u.x,
+    );
+
+    let near = lerp(x00, x10, u.y);
+    let far = lerp(x01, x11, u.y);
+    lerp(near, far, u.z)
+}
+
+/// 2D simplex noise
+pub fn simplex2(point: Vec2, seed: u32) -> f32 {
+    const SKEW: f32 = 0.366_025_42; // (sqrt(3) - 1) / 2
+    const UNSKEW: f32 = 0.211_324_87; // (3 - sqrt(3)) / 6
+
+    let cell = (point + Vec2::splat((point.x + point.y) * SKEW)).floor();
+    let origin = point - (cell - Vec2::splat((cell.x + cell.y) * UNSKEW));
+    let cell = cell.as_ivec2();
+
+    let middle = match origin.x > origin.y {
+        true => IVec2::new(1, 0),
+        false => IVec2::new(0, 1),
+    };
+
+    let corners = [
+        (IVec2::ZERO, origin),
+        (middle, origin - middle.as_vec2() + Vec2::splat(UNSKEW)),
+        (IVec2::ONE, origin - Vec2::ONE + Vec2::splat(2.0 * UNSKEW)),
+    ];
+
+    let mut total = 0.0;
+    for (offset, distance) in corners {
+        let t = 0.5 - distance.length_squared();
+        if t > 0.0 {
+            let t = t * t;
+            total += t * t * gradient2(cell + offset, seed).dot(distance);
+        }
+    }
+
+    total * 99.0
+}
+
+/// 3D simplex noise
+pub fn simplex3(point: Vec3, seed: u32) -> f32 {
+    const SKEW: f32 = 1.0 / 3.0;
+    const UNSKEW: f32 = 1.0 / 6.0;
+
+    let cell = (point + Vec3::splat((point.x + point.y + point.z) * SKEW)).floor();
+    let origin = point - (cell - Vec3::splat((cell.x + cell.y + cell.z) * UNSKEW));
+    let cell = cell.as_ivec3();
+
+    // which of the six tetrahedra in the skewed cube the point is in
+    let (first, second) = match (
+        origin.x >= origin.y,
+        origin.y >= origin.z,
+        origin.x >= origin.z,
+    ) {
+        (true, true, _) => (IVec3::X, IVec3::new(1, 1, 0)),
+        (true, false, true) => (IVec3::X, IVec3::new(1, 0, 1)),
+        (true, false, false) => (IVec3::Z, IVec3::new(1, 0, 1)),
+        (false, false, _) => (IVec3::Z, IVec3::new(0, 1, 1)),
+        (false, true, false) => (IVec3::Y, IVec3::new(0, 1, 1)),
+        (false, true, true) => (IVec3::Y, IVec3::new(1, 1, 0)),
+    };
+
+    let corners = [
+        (IVec3::ZERO, origin),
+        (first, origin - first.as_vec3() + Vec3::splat(UNSKEW)),
+        (
+            second,
+            origin - second.as_vec3() + Vec3::splat(2.0 * UNSKEW),
+        ),
+        (IVec3::ONE, origin - Vec3::ONE + Vec3::splat(3.0 * UNSKEW)),
+    ];
+
+    let mut total = 0.0;
+    for (offset, distance) in corners {
+        let t = 0.6 - distance.length_squared();
+        if t > 0.0 {
+            let t = t * t;
+            total += t * t * gradient3(cell + offset, seed).dot(distance);
+        }
+    }
+
+    total * 32.0
+}
+
+/// the noise function layered by [`Fbm`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NoiseKind {
+    #[default]
+    Perlin,
+    Simplex,
+}
+
+/// fractal brownian motion, layers of noise at rising frequencies and falling amplitudes
+///
+/// the result is divided by the total amplitude so it stays between -1 and 1 like the noise it
+/// is built from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fbm {
+    pub kind: NoiseKind,
+    /// how many layers of noise are added
+    pub octaves: u32,
+    /// the frequency of the first layer
+    pub frequency: f32,
+    /// how much the frequency is multiplied by each layer
+    pub lacunarity: f32,
+    /// how much the amplitude is multiplied by each layer
+    pub gain: f32,
+}
+
+impl Default for Fbm {
+    fn default() -> Self {
+        Self {
+            kind: NoiseKind::Perlin,
+            octaves: 4,
+            frequency: 1.0,
+            lacunarity: 2.0,
+            gain: 0.5,
+        }
+    }
+}
+
+impl Fbm {
+    fn layer<P: Copy + std::ops::Mul<f32, Output = P>>(
+        &self,
+        point: P,
+        seed: u32,
+        noise: impl Fn(P, u32) -> f32,
+    ) -> f32 {
+        let mut frequency = self.frequency;
+        let mut amplitude = 1.0;
+        let mut total = 0.0;
+        let mut max = 0.0;
+
+        for octave in 0..self.octaves.max(1) {
+            // each layer gets its own seed so the layers don't line up at the origin
+            total += noise(point * frequency, seed.wrapping_add(octave)) * amplitude;
+            max += amplitude;
+            frequency *= self.lacunarity;
+            amplitude *= self.gain;
+        }
+
+        total / max
+    }
+
+    /// sample the fractal at a 2D point
+    pub fn sample2(&self, point: Vec2, seed: u32) -> f32 {
+        match self.kind {
+            NoiseKind::Perlin => self.layer(point, seed, perlin2),
+            NoiseKind::Simplex => self.layer(point, seed, simplex2),
+        }
+    }
+
+    /// sample the fractal at a 3D point
+    pub fn sample3(&self, point: Vec3, seed: u32) -> f32 {
+        match self.kind {
+            NoiseKind::Perlin => self.layer(point, seed, perlin3),
+            NoiseKind::Simplex => self.layer(point, seed, simplex3),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples() -> impl Iterator<Item = Vec3> {
+        (0..4000).map(|i| {
+            let i = i as f32;
+            Vec3::new(i * 0.137 - 200.0, i * 0.071 + 3.3, i * -0.053)
+        })
+    }
+
+    #[test]
+    fn test_noise_stays_in_range() {
+        for point in samples() {
+            for value in [
+                perlin2(point.truncate(), 7),
+                perlin3(point, 7),
+                simplex2(point.truncate(), 7),
+                simplex3(point, 7),
+                Fbm::default().sample3(point, 7),
+            ] {
+                assert!((-1.0..=1.0).contains(&value), "{value} at {point}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_noise_is_seeded_and_repeatable() {
+        let point = Vec3::new(3.7, -1.2, 8.9);
+        assert_eq!(perlin3(point, 1), perlin3(point, 1));
+        assert_ne!(perlin3(point, 1), perlin3(point, 2));
+        assert_ne!(simplex2(point.truncate(), 1), simplex2(point.truncate(), 2));
+
+        // perlin noise is zero on the grid
+        assert_eq!(perlin2(Vec2::new(4.0, -3.0), 1), 0.0);
+    }
+
+    #[test]
+    fn test_noise_is_continuous() {
+        let step = Vec3::splat(0.001);
+        for point in samples().take(500) {
+            assert!((perlin3(point, 3) - perlin3(point + step, 3)).abs() < 0.02);
+            assert!((simplex3(point, 3) - simplex3(point + step, 3)).abs() < 0.05);
+        }
+    }
+}
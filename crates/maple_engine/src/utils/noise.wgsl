@@ -0,0 +1,212 @@
+// seeded gradient noise, the GPU side of maple_engine::utils::noise
+//
+// keep this in step with noise.rs so heights sampled on the CPU match the ones drawn here
+
+fn noise_hash(value: u32) -> u32 {
+    let state = value * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+fn noise_hash2(cell: vec2<i32>, seed: u32) -> u32 {
+    return noise_hash(bitcast<u32>(cell.x) ^ noise_hash(bitcast<u32>(cell.y) ^ noise_hash(seed)));
+}
+
+fn noise_hash3(cell: vec3<i32>, seed: u32) -> u32 {
+    return noise_hash(bitcast<u32>(cell.x) ^ noise_hash(bitcast<u32>(cell.y) ^ noise_hash(bitcast<u32>(cell.z) ^ noise_hash(seed))));
+}
+
+const NOISE_DIAGONAL: f32 = 0.70710678;
+
+fn noise_gradient2(cell: vec2<i32>, seed: u32) -> vec2<f32> {
+    var gradients = array<vec2<f32>, 8>(
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(-1.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(0.0, -1.0),
+        vec2<f32>(NOISE_DIAGONAL, NOISE_DIAGONAL),
+        vec2<f32>(-NOISE_DIAGONAL, NOISE_DIAGONAL),
+        vec2<f32>(NOISE_DIAGONAL, -NOISE_DIAGONAL),
+        vec2<f32>(-NOISE_DIAGONAL, -NOISE_DIAGONAL),
+    );
+    return gradients[noise_hash2(cell, seed) & 7u];
+}
+
+fn noise_gradient3(cell: vec3<i32>, seed: u32) -> vec3<f32> {
+    var gradients = array<vec3<f32>, 12>(
+        vec3<f32>(1.0, 1.0, 0.0),
+        vec3<f32>(-1.0, 1.0, 0.0),
+        vec3<f32>(1.0, -1.0, 0.0),
+        vec3<f32>(-1.0, -1.0, 0.0),
+        vec3<f32>(1.0, 0.0, 1.0),
+        vec3<f32>(-1.0, 0.0, 1.0),
+        vec3<f32>(1.0, 0.0, -1.0),
+        vec3<f32>(-1.0, 0.0, -1.0),
+        vec3<f32>(0.0, 1.0, 1.0),
+        vec3<f32>(0.0, -1.0, 1.0),
+        vec3<f32>(0.0, 1.0, -1.0),
+        vec3<f32>(0.0, -1.0, -1.0),
+    );
+    return gradients[noise_hash3(cell, seed) % 12u];
+}
+
+fn noise_fade(t: f32) -> f32 {
+    return t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+}
+
+fn noise_perlin2(point: vec2<f32>, seed: u32) -> f32 {
+    let floored = floor(point);
+    let local = point - floored;
+    let cell = vec2<i32>(floored);
+
+    let c00 = dot(noise_gradient2(cell, seed), local);
+    let c10 = dot(noise_gradient2(cell + vec2<i32>(1, 0), seed), local - vec2<f32>(1.0, 0.0));
+    let c01 = dot(noise_gradient2(cell + vec2<i32>(0, 1), seed), local - vec2<f32>(0.0, 1.0));
+    let c11 = dot(noise_gradient2(cell + vec2<i32>(1, 1), seed), local - vec2<f32>(1.0, 1.0));
+
+    let u = vec2<f32>(noise_fade(local.x), noise_fade(local.y));
+    return mix(mix(c00, c10, u.x), mix(c01, c11, u.x), u.y) * 1.41421356;
+}
+
+fn noise_perlin3(point: vec3<f32>, seed: u32) -> f32 {
+    let floored = floor(point);
+    let local = point - floored;
+    let cell = vec3<i32>(floored);
+
+    let c000 = dot(noise_gradient3(cell, seed), local);
+    let c100 = dot(noise_gradient3(cell + vec3<i32>(1, 0, 0), seed), local - vec3<f32>(1.0, 0.0, 0.0));
+    let c010 = dot(noise_gradient3(cell + vec3<i32>(0, 1, 0), seed), local - vec3<f32>(0.0, 1.0, 0.0));
+    let c110 = dot(noise_gradient3(cell + vec3<i32>(1, 1, 0), seed), local - vec3<f32>(1.0, 1.0, 0.0));
+    let c001 = dot(noise_gradient3(cell + vec3<i32>(0, 0, 1), seed), local - vec3<f32>(0.0, 0.0, 1.0));
+    let c101 = dot(noise_gradient3(cell + vec3<i32>(1, 0, 1), seed), local - vec3<f32>(1.0, 0.0, 1.0));
+    let c011 = dot(noise_gradient3(cell + vec3<i32>(0, 1, 1), seed), local - vec3<f32>(0.0, 1.0, 1.0));
+    let c111 = dot(noise_gradient3(cell + vec3<i32>(1, 1, 1), seed), local - vec3<f32>(1.0, 1.0, 1.0));
+
+    let u = vec3<f32>(noise_fade(local.x), noise_fade(local.y), noise_fade(local.z));
+    let near = mix(mix(c000, c100, u.x), mix(c010, c110, u.x), u.y);
+    let far = mix(mix(c001, c101, u.x), mix(c011, c111, u.x), u.y);
+    return mix(near, far, u.z);
+}
+
+fn noise_simplex2_corner(cell: vec2<i32>, distance: vec2<f32>, seed: u32) -> f32 {
+    let t = 0.5 - dot(distance, distance);
+    if t <= 0.0 {
+        return 0.0;
+    }
+    let t2 = t * t;
+    return t2 * t2 * dot(noise_gradient2(cell, seed), distance);
+}
+
+fn noise_simplex2(point: vec2<f32>, seed: u32) -> f32 {
+    let skew = 0.36602542;
+    let unskew = 0.21132487;
+
+    let floored = floor(point + vec2<f32>((point.x + point.y) * skew));
+    let origin = point - (floored - vec2<f32>((floored.x + floored.y) * unskew));
+    let cell = vec2<i32>(floored);
+
+    var middle = vec2<i32>(0, 1);
+    if origin.x > origin.y {
+        middle = vec2<i32>(1, 0);
+    }
+
+    var total = noise_simplex2_corner(cell, origin, seed);
+    total += noise_simplex2_corner(cell + middle, origin - vec2<f32>(middle) + vec2<f32>(unskew), seed);
+    total += noise_simplex2_corner(cell + vec2<i32>(1, 1), origin - vec2<f32>(1.0) + vec2<f32>(2.0 * unskew), seed);
+    return total * 99.0;
+}
+
+fn noise_simplex3_corner(cell: vec3<i32>, distance: vec3<f32>, seed: u32) -> f32 {
+    let t = 0.6 - dot(distance, distance);
+    if t <= 0.0 {
+        return 0.0;
+    }
+    let t2 = t * t;
+    return t2 * t2 * dot(noise_gradient3(cell, seed), distance);
+}
+
+fn noise_simplex3(point: vec3<f32>, seed: u32) -> f32 {
+    let skew = 1.0 / 3.0;
+    let unskew = 1.0 / 6.0;
+
+    let floored = floor(point + vec3<f32>((point.x + point.y + point.z) * skew));
+    let origin = point - (floored - vec3<f32>((floored.x + floored.y + floored.z) * unskew));
+    let cell = vec3<i32>(floored);
+
+    var first: vec3<i32>;
+    var second: vec3<i32>;
+    if origin.x >= origin.y {
+        if origin.y >= origin.z {
+            first = vec3<i32>(1, 0, 0);
+            second = vec3<i32>(1, 1, 0);
+        } else if origin.x >= origin.z {
+            first = vec3<i32>(1, 0, 0);
+            second = vec3<i32>(1, 0, 1);
+        } else {
+            first = vec3<i32>(0, 0, 1);
+            second = vec3<i32>(1, 0, 1);
+        }
+    } else {
+        if origin.y < origin.z {
+            first = vec3<i32>(0, 0, 1);
+            second = vec3<i32>(0, 1, 1);
+        } else if origin.x < origin.z {
+            first = vec3<i32>(0, 1, 0);
+            second = vec3<i32>(0, 1, 1);
+        } else {
+            first = vec3<i32>(0, 1, 0);
+            second = vec3<i32>(1, 1, 0);
+        }
+    }
+
+    var total = noise_simplex3_corner(cell, origin, seed);
+    total += noise_simplex3_corner(cell + first, origin - vec3<f32>(first) + vec3<f32>(unskew), seed);
+    total += noise_simplex3_corner(cell + second, origin - vec3<f32>(second) + vec3<f32>(2.0 * unskew), seed);
+    total += noise_simplex3_corner(cell + vec3<i32>(1, 1, 1), origin - vec3<f32>(1.0) + vec3<f32>(3.0 * unskew), seed);
+    return total * 32.0;
+}
+
+// kind is 0 for perlin and 1 for simplex, like NoiseKind
+fn noise_fbm2(point: vec2<f32>, seed: u32, kind: u32, octaves: u32, frequency: f32, lacunarity: f32, gain: f32) -> f32 {
+    var freq = frequency;
+    var amplitude = 1.0;
+    var total = 0.0;
+    var max_total = 0.0;
+
+    for (var octave = 0u; octave < max(octaves, 1u); octave++) {
+        var value: f32;
+        if kind == 0u {
+            value = noise_perlin2(point * freq, seed + octave);
+        } else {
+            value = noise_simplex2(point * freq, seed + octave);
+        }
+        total += value * amplitude;
+        max_total += amplitude;
+        freq *= lacunarity;
+        amplitude *= gain;
+    }
+
+    return total / max_total;
+}
+
+fn noise_fbm3(point: vec3<f32>, seed: u32, kind: u32, octaves: u32, frequency: f32, lacunarity: f32, gain: f32) -> f32 {
+    var freq = frequency;
+    var amplitude = 1.0;
+    var total = 0.0;
+    var max_total = 0.0;
+
+    for (var octave = 0u; octave < max(octaves, 1u); octave++) {
+        var value: f32;
+        if kind == 0u {
+            value = noise_perlin3(point * freq, seed + octave);
+        } else {
+            value = noise_simplex3(point * freq, seed + octave);
+        }
+        total += value * amplitude;
+        max_total += amplitude;
+        freq *= lacunarity;
+        amplitude *= gain;
+    }
+
+    return total / max_total;
+}
@@ -0,0 +1,215 @@
+//! small random helpers for weighted picks and random directions
+//!
+//! everything takes the rng as an argument so the same code works with [`rand::rng`] for one-off
+//! variation and with [`seeded`] when the result has to be the same every run, like world
+//! generation.
+//!
+//! # Example
+//! ```rust
+//! use maple_engine::utils::random::{WeightedTable, seeded};
+//!
+//! let loot = WeightedTable::new([("copper", 10.0), ("silver", 4.0), ("gold", 1.0)]);
+//!
+//! let mut rng = seeded(1234);
+//! let drop = loot.pick(&mut rng);
+//! assert!(drop.is_some());
+//! ```
+
+use glam::{Vec2, Vec3};
+use rand::{Rng, RngExt, SeedableRng, rngs::SmallRng};
+
+/// an rng that gives the same numbers every time for the same seed
+///
+/// the sequence can change between platforms and `rand` versions, so save generated results
+/// instead of the seed when they must never change
+pub fn seeded(seed: u64) -> SmallRng {
+    SmallRng::seed_from_u64(seed)
+}
+
+/// pick an index with a chance proportional to its weight
+///
+/// negative and non finite weights count as zero. returns `None` if every weight is zero
+pub fn weighted_index<R: Rng + ?Sized>(rng: &mut R, weights: &[f32]) -> Option<usize> {
+    let clean = |weight: f32| match weight.is_finite() {
+        true => weight.max(0.0),
+        false => 0.0,
+    };
+
+    let total: f32 = weights.iter().copied().map(clean).sum();
+    if total <= 0.0 {
+        return None;
+    }
+
+    let mut roll = rng.random_range(0.0..total);
+    for (index, weight) in weights.iter().copied().map(clean).enumerate() {
+        if roll < weight {
+            return Some(index);
+        }
+        roll -= weight;
+    }
+
+    // float error can leave a sliver at the end, give it to the last item that can be picked
+    weights.iter().rposition(|weight| clean(*weight) > 0.0)
+}
+
+/// pick one of the items with a chance proportional to its weight
+pub fn weighted_choice<'a, T, R: Rng + ?Sized>(
+    rng: &mut R,
+    items: &'a [(T, f32)],
+) -> Option<&'a T> {
+    let weights: Vec<f32> = items.iter().map(|(_, weight)| *weight).collect();
+    weighted_index(rng, &weights).map(|index| &items[index].0)
+}
+
+/// a list of weighted items built once and picked from many times
+///
+/// picking is a binary search over the running total of the weights so large tables stay cheap
+#[derive(Clone, Debug)]
+pub struct WeightedTable<T> {
+    items: Vec<T>,
+    cumulative: Vec<f32>,
+}
+
+impl<T> Default for WeightedTable<T> {
+    fn default() -> Self {
+        Self {
+            items: Vec::new(),
+            cumulative: Vec::new(),
+        }
+    }
+}
+
+impl<T> WeightedTable<T> {
+    /// build a table from items and their weights
+    pub fn new(items: impl IntoIterator<Item = (T, f32)>) -> Self {
+        let mut table = Self::default();
+        for (item, weight) in items {
+            table.push(item, weight);
+        }
+        table
+    }
+
+    /// add an item. items with a weight of zero or less are never picked
+    pub fn push(&mut self, item: T, weight: f32) {
+        let weight = match weight.is_finite() {
+            true => weight.max(0.0),
+            false => 0.0,
+        };
+        self.cumulative.push(self.total_weight() + weight);
+        self.items.push(item);
+    }
+
+    /// the sum of every weight in the table
+    pub fn total_weight(&self) -> f32 {
+        self.cumulative.last().copied().unwrap_or(0.0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    /// pick the index of an item. returns `None` if the table has no weight
+    pub fn pick_index<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<usize> {
+        let total = self.total_weight();
+        if total <= 0.0 {
+            return None;
+        }
+
+        let roll = rng.random_range(0.0..total);
+        let index = self.cumulative.partition_point(|sum| *sum <= roll);
+        Some(index.min(self.items.len() - 1))
+    }
+
+    /// pick an item. returns `None` if the table has no weight
+    pub fn pick<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<&T> {
+        self.pick_index(rng).map(|index| &self.items[index])
+    }
+}
+
+/// `base` moved by up to `amount` either way, handy for particle and sound variation
+pub fn vary<R: Rng + ?Sized>(rng: &mut R, base: f32, amount: f32) -> f32 {
+    let amount = amount.abs();
+    if amount == 0.0 {
+        return base;
+    }
+    base + rng.random_range(-amount..=amount)
+}
+
+/// a random direction on the unit circle
+pub fn unit_circle<R: Rng + ?Sized>(rng: &mut R) -> Vec2 {
+    let angle = rng.random_range(0.0..std::f32::consts::TAU);
+    Vec2::from_angle(angle)
+}
+
+/// a random direction on the unit sphere
+pub fn unit_sphere<R: Rng + ?Sized>(rng: &mut R) -> Vec3 {
+    let z: f32 = rng.random_range(-1.0..=1.0);
+    let angle = rng.random_range(0.0..std::f32::consts::TAU);
+    let radius = (1.0 - z * z).max(0.0).sqrt();
+    Vec3::new(radius * angle.cos(), radius * angle.sin(), z)
+}
+
+/// a random point inside the unit sphere, spread evenly through its volume
+pub fn in_unit_sphere<R: Rng + ?Sized>(rng: &mut R) -> Vec3 {
+    let distance = rng.random_range(0.0f32..=1.0).cbrt();
+    unit_sphere(rng) * distance
+}
+
+/// a random direction within `angle` radians of `direction`, for spray and spread cones
+pub fn in_cone<R: Rng + ?Sized>(rng: &mut R, direction: Vec3, angle: f32) -> Vec3 {
+    let direction = direction.normalize_or(Vec3::Y);
+    let min_z = angle.clamp(0.0, std::f32::consts::PI).cos();
+    let z: f32 = rng.random_range(min_z..=1.0);
+    let around = rng.random_range(0.0..std::f32::consts::TAU);
+    let radius = (1.0 - z * z).max(0.0).sqrt();
+
+    let (x_axis, y_axis) = direction.any_orthonormal_pair();
+    (x_axis * around.cos() * radius + y_axis * around.sin() * radius + direction * z).normalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weighted_picks_follow_weights() {
+        let mut rng = seeded(5);
+        let table = WeightedTable::new([("never", 0.0), ("rare", 1.0), ("common", 3.0)]);
+
+        let mut counts = [0; 3];
+        for _ in 0..8000 {
+            counts[table.pick_index(&mut rng).unwrap()] += 1;
+            assert_ne!(weighted_index(&mut rng, &[0.0, -2.0, 1.0]), Some(1));
+        }
+
+        assert_eq!(counts[0], 0);
+        let ratio = counts[2] as f32 / counts[1] as f32;
+        assert!((2.6..3.4).contains(&ratio), "{ratio}");
+
+        assert_eq!(weighted_index(&mut rng, &[0.0, 0.0]), None);
+        assert!(WeightedTable::<u8>::default().pick(&mut rng).is_none());
+    }
+
+    #[test]
+    fn test_seeded_rng_repeats() {
+        let first: Vec<Vec3> = (0..4).map(|_| in_unit_sphere(&mut seeded(9))).collect();
+        let second: Vec<Vec3> = (0..4).map(|_| in_unit_sphere(&mut seeded(9))).collect();
+        assert_eq!(first, second);
+
+        let mut rng = seeded(9);
+        for _ in 0..200 {
+            assert!(in_unit_sphere(&mut rng).length() <= 1.0 + 1e-5);
+            assert!((unit_sphere(&mut rng).length() - 1.0).abs() < 1e-4);
+            let spread = in_cone(&mut rng, Vec3::X, 0.3);
+            assert!(spread.angle_between(Vec3::X) <= 0.3 + 1e-3);
+        }
+    }
+}
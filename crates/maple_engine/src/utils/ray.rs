@@ -0,0 +1,88 @@
+//! rays for picking and line of sight checks
+//!
+//! cast one into the scene with [`crate::Scene::raycast`]
+
+use glam::{Mat4, Vec3};
+
+use crate::utils::aabb::AABB;
+
+/// a half line starting at `origin` going in `direction`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: Vec3,
+    /// always normalized when made with [`Ray::new`]
+    pub direction: Vec3,
+}
+
+impl Ray {
+    /// a ray from `origin` towards `direction`, the direction doesn't need to be normalized
+    pub fn new(origin: Vec3, direction: Vec3) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize_or_zero(),
+        }
+    }
+
+    /// the ray from `from` through `to`
+    pub fn between(from: Vec3, to: Vec3) -> Self {
+        Self::new(from, to - from)
+    }
+
+    /// the point `distance` along the ray
+    pub fn at(&self, distance: f32) -> Vec3 {
+        self.origin + self.direction * distance
+    }
+
+    /// the ray moved by a matrix, use the inverse of a model matrix to get a ray in local space
+    ///
+    /// the direction is not normalized again so distances along the new ray are the same as
+    /// distances along this one
+    pub fn transform(&self, matrix: &Mat4) -> Self {
+        Self {
+            origin: matrix.transform_point3(self.origin),
+            direction: matrix.transform_vector3(self.direction),
+        }
+    }
+
+    /// the distance to where the ray enters the box, 0 if it starts inside
+    pub fn intersect_aabb(&self, aabb: &AABB) -> Option<f32> {
+        let inverse = self.direction.recip();
+        let a = (aabb.min - self.origin) * inverse;
+        let b = (aabb.max - self.origin) * inverse;
+
+        // axes the ray is parallel to give infinities, or nan if the origin is on the face, and
+        // min and max ignore the nan
+        let near = a.min(b).max_element();
+        let far = a.max(b).min_element();
+
+        (far >= near.max(0.0)).then_some(near.max(0.0))
+    }
+
+    /// the distance to where the ray hits a triangle from either side
+    pub fn intersect_triangle(&self, a: Vec3, b: Vec3, c: Vec3) -> Option<f32> {
+        let edge_ab = b - a;
+        let edge_ac = c - a;
+
+        let p = self.direction.cross(edge_ac);
+        let determinant = edge_ab.dot(p);
+        if determinant.abs() < f32::EPSILON {
+            return None;
+        }
+        let inverse = 1.0 / determinant;
+
+        let to_origin = self.origin - a;
+        let u = to_origin.dot(p) * inverse;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = to_origin.cross(edge_ab);
+        let v = self.direction.dot(q) * inverse;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let distance = edge_ac.dot(q) * inverse;
+        (distance >= 0.0).then_some(distance)
+    }
+}
@@ -0,0 +1,165 @@
+//! easing towards a target the same way at any frame rate
+//!
+//! moving a fraction of the way every frame, like `position.lerp(target, 0.1)`, catches up faster
+//! the higher the frame rate. the helpers here take the frame's delta time and give the same
+//! motion at 30 and 240 fps:
+//!
+//! - [`damp`] closes the same share of the gap every second, quick to start and slow to arrive
+//! - [`smooth_damp`] is a critically damped spring that speeds up and slows down smoothly and
+//!   keeps its velocity when the target moves, for cameras
+//!
+//! # Example
+//! ```rust
+//! use glam::Vec3;
+//! use maple_engine::utils::smoothing::smooth_damp;
+//!
+//! let target = Vec3::new(0.0, 5.0, 10.0);
+//! let mut position = Vec3::ZERO;
+//! let mut velocity = Vec3::ZERO;
+//!
+//! // in update, reaching the target in about 0.3 seconds
+//! # let dt = 1.0 / 60.0;
+//! position = smooth_damp(position, target, &mut velocity, 0.3, dt);
+//! ```
+
+use glam::{Quat, Vec2, Vec3, Vec4};
+
+/// the share of the gap [`damp`] closes over `dt` seconds at `rate`
+pub fn damp_factor(rate: f32, dt: f32) -> f32 {
+    1.0 - (-rate.max(0.0) * dt).exp()
+}
+
+/// values that can be eased towards a target, see the [module docs](self)
+pub trait Smooth: Copy {
+    /// move towards `target`, closing the share of the gap given by [`damp_factor`]
+    fn damp(self, target: Self, rate: f32, dt: f32) -> Self;
+
+    /// move towards `target` like a critically damped spring that arrives in about
+    /// `smooth_time` seconds, `velocity` is kept between calls and starts at zero
+    fn smooth_damp(self, target: Self, velocity: &mut Self, smooth_time: f32, dt: f32) -> Self;
+}
+
+/// `current` moved towards `target`, a higher `rate` catches up faster and 0 never moves
+pub fn damp<T: Smooth>(current: T, target: T, rate: f32, dt: f32) -> T {
+    current.damp(target, rate, dt)
+}
+
+/// `current` moved towards `target` by a spring that arrives in about `smooth_time` seconds
+/// without overshooting, `velocity` has to be kept between calls
+pub fn smooth_damp<T: Smooth>(
+    current: T,
+    target: T,
+    velocity: &mut T,
+    smooth_time: f32,
+    dt: f32,
+) -> T {
+    current.smooth_damp(target, velocity, smooth_time, dt)
+}
+
+macro_rules! impl_smooth {
+    ($($ty:ty),*) => {$(
+        impl Smooth for $ty {
+            fn damp(self, target: Self, rate: f32, dt: f32) -> Self {
+                self + (target - self) * damp_factor(rate, dt)
+            }
+
+            fn smooth_damp(
+                self,
+                target: Self,
+                velocity: &mut Self,
+                smooth_time: f32,
+                dt: f32,
+            ) -> Self {
+                // the exact step of the spring so any number of steps gives the same motion
+                let omega = 2.0 / smooth_time.max(1e-4);
+                let decay = (-omega * dt).exp();
+                let offset = self - target;
+                let push = (*velocity + offset * omega) * dt;
+                *velocity = (*velocity - push * omega) * decay;
+                target + (offset + push) * decay
+            }
+        }
+    )*};
+}
+
+impl_smooth!(f32, Vec2, Vec3, Vec4);
+
+impl Smooth for Quat {
+    fn damp(self, target: Self, rate: f32, dt: f32) -> Self {
+        self.slerp(target, damp_factor(rate, dt))
+    }
+
+    /// the components are smoothed and normalized, which stays close to turning at a steady
+    /// speed for the small steps of a frame. `velocity` starts at
+    /// `Quat::from_xyzw(0.0, 0.0, 0.0, 0.0)`
+    fn smooth_damp(self, target: Self, velocity: &mut Self, smooth_time: f32, dt: f32) -> Self {
+        // turn the short way around
+        let target = match self.dot(target) < 0.0 {
+            true => -target,
+            false => target,
+        };
+
+        let mut components = Vec4::from(*velocity);
+        let rotation =
+            Vec4::from(self).smooth_damp(Vec4::from(target), &mut components, smooth_time, dt);
+        *velocity = Quat::from_vec4(components);
+        Quat::from_vec4(rotation).normalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run<T: Smooth>(
+        mut value: T,
+        target: T,
+        fps: u32,
+        mut step: impl FnMut(T, T, f32) -> T,
+    ) -> T {
+        let dt = 1.0 / fps as f32;
+        for _ in 0..fps {
+            value = step(value, target, dt);
+        }
+        value
+    }
+
+    #[test]
+    fn test_smoothing_is_frame_rate_independent() {
+        let target = Vec3::new(10.0, -4.0, 2.0);
+
+        let damped = |fps| run(Vec3::ZERO, target, fps, |v, t, dt| damp(v, t, 3.0, dt));
+        assert!(damped(30).abs_diff_eq(damped(240), 1e-3));
+
+        let sprung = |fps| {
+            let mut velocity = Vec3::ZERO;
+            run(Vec3::ZERO, target, fps, |v, t, dt| {
+                smooth_damp(v, t, &mut velocity, 0.3, dt)
+            })
+        };
+        assert!(sprung(30).abs_diff_eq(sprung(240), 1e-3));
+        // a second is well past the smooth time
+        assert!(sprung(60).distance(target) < 0.2);
+
+        let turned = |fps| {
+            let mut velocity = Quat::from_xyzw(0.0, 0.0, 0.0, 0.0);
+            let target = Quat::from_rotation_y(2.0);
+            run(Quat::IDENTITY, target, fps, |v, t, dt| {
+                smooth_damp(v, t, &mut velocity, 0.2, dt)
+            })
+        };
+        assert!(turned(30).angle_between(turned(240)) < 1e-2);
+        assert!(turned(60).angle_between(Quat::from_rotation_y(2.0)) < 1e-2);
+    }
+
+    #[test]
+    fn test_smooth_damp_does_not_overshoot() {
+        let mut velocity = 0.0;
+        let mut value = 0.0;
+        for _ in 0..120 {
+            value = smooth_damp(value, 1.0, &mut velocity, 0.25, 1.0 / 60.0);
+            assert!(value <= 1.0);
+        }
+        assert!((value - 1.0).abs() < 1e-3);
+    }
+}
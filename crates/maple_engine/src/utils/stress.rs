@@ -0,0 +1,243 @@
+//! large randomized scenes for stress tests, benches and fuzzing
+//!
+//! a [`StressScene`] spawns thousands of nodes in random hierarchies with random transforms and
+//! flags. with [`StressScene::random_events`] every node also gets an [`Update`] handler that
+//! queues random spawns, despawns, reparents and toggles, so running a few frames with
+//! [`stress_frame`] goes through the paths that usually only break under load.
+//!
+//! the same seed always makes the same scene, so a seed that panics can be replayed.
+//!
+//! # Example
+//! ```rust
+//! use maple_engine::{GameContext, utils::stress::{StressScene, stress_frame}};
+//!
+//! let ctx = GameContext::new();
+//! StressScene::new(2_000).seed(7).random_events(true).populate(&ctx.scene);
+//!
+//! for _ in 0..10 {
+//!     stress_frame(&ctx, 1.0 / 60.0);
+//! }
+//! ```
+
+use std::sync::Arc;
+
+use glam::{Quat, Vec3};
+use rand::{RngExt, rngs::SmallRng};
+
+use crate::{
+    GameContext, Node, Scene,
+    nodes::Empty,
+    prelude::{EventCtx, Update},
+    scene::NodeId,
+    utils::{aabb::AABB, random, ray::Ray},
+};
+
+/// generates a random scene, see the [module docs](self)
+#[derive(Debug, Clone)]
+pub struct StressScene {
+    nodes: usize,
+    max_depth: usize,
+    seed: u64,
+    extent: f32,
+    random_events: bool,
+}
+
+impl StressScene {
+    /// a scene of `nodes` nodes at most 8 levels deep
+    pub fn new(nodes: usize) -> Self {
+        Self {
+            nodes,
+            max_depth: 8,
+            seed: 0,
+            extent: 100.0,
+            random_events: false,
+        }
+    }
+
+    /// the seed everything random is made from
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// the deepest a node can be, roots are at depth 0
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// root nodes are placed within `extent` of the origin on each axis
+    pub fn extent(mut self, extent: f32) -> Self {
+        self.extent = extent;
+        self
+    }
+
+    /// give each node an [`Update`] handler that randomly changes the scene
+    pub fn random_events(mut self, enabled: bool) -> Self {
+        self.random_events = enabled;
+        self
+    }
+
+    /// a new scene filled with [`Empty`] nodes
+    pub fn build(&self) -> Scene {
+        let scene = Scene::new();
+        self.populate(&scene);
+        scene
+    }
+
+    /// spawn [`Empty`] nodes into an existing scene, returns the spawned ids in spawn order
+    pub fn populate(&self, scene: &Scene) -> Vec<NodeId> {
+        self.populate_with(scene, |_| Empty::default())
+    }
+
+    /// spawn nodes made by `make` into an existing scene, returns the spawned ids in spawn order
+    ///
+    /// the generated transform replaces the node's own and nodes without a local bounding box are
+    /// given a random one so they can be raycast
+    pub fn populate_with<T: Node>(
+        &self,
+        scene: &Scene,
+        mut make: impl FnMut(&mut SmallRng) -> T,
+    ) -> Vec<NodeId> {
+        let mut rng = random::seeded(self.seed);
+        let mut spawned: Vec<(NodeId, usize)> = Vec::with_capacity(self.nodes);
+
+        for _ in 0..self.nodes {
+            // picking from every earlier node makes bushy trees with a few long branches
+            let parent = match spawned.is_empty() || rng.random_bool(0.1) {
+                true => None,
+                false => Some(spawned[rng.random_range(0..spawned.len())])
+                    .filter(|(_, depth)| *depth < self.max_depth),
+            };
+
+            let mut node = make(&mut rng);
+            self.randomize(&mut rng, &mut node, parent.is_none());
+
+            let id = match parent {
+                Some((parent, _)) => scene.spawn_as_child(node, parent).id(),
+                None => scene.spawn(node).id(),
+            };
+            spawned.push((id, parent.map_or(0, |(_, depth)| depth + 1)));
+        }
+
+        let ids: Arc<[NodeId]> = spawned.iter().map(|(id, _)| *id).collect();
+        if self.random_events {
+            for (index, id) in ids.iter().enumerate() {
+                let mut rng = random::seeded(self.seed ^ (index as u64 + 1).wrapping_mul(0x9e37));
+                let ids = ids.clone();
+                scene.on::<Update, T>(*id, move |ctx| random_command(&ctx, &mut rng, &ids));
+            }
+        }
+
+        ids.to_vec()
+    }
+
+    fn randomize<T: Node>(&self, rng: &mut SmallRng, node: &mut T, root: bool) {
+        let offset = match root {
+            true => self.extent,
+            false => 5.0,
+        };
+
+        let transform = node.get_transform();
+        transform.position = Vec3::new(
+            rng.random_range(-offset..=offset),
+            rng.random_range(-offset..=offset),
+            rng.random_range(-offset..=offset),
+        );
+        transform.rotation = Quat::from_axis_angle(
+            random::unit_sphere(rng),
+            rng.random_range(0.0..std::f32::consts::TAU),
+        );
+        transform.scale = Vec3::splat(rng.random_range(0.5..2.0));
+        transform.visible_request = Some(rng.random_bool(0.9));
+        transform.enabled_request = Some(rng.random_bool(0.95));
+
+        if transform.local_aabb().is_none() {
+            let half = Vec3::splat(rng.random_range(0.1..2.0));
+            transform.set_local_aabb(Some(AABB::new(-half, half)));
+        }
+    }
+}
+
+/// queue one random change, sometimes aimed at nodes that have already been despawned
+fn random_command<T: Node>(ctx: &EventCtx<Update, T>, rng: &mut SmallRng, ids: &[NodeId]) {
+    let commands = ctx.commands();
+    let id = ctx.node_id();
+    let other = ids[rng.random_range(0..ids.len())];
+
+    match rng.random_range(0..100) {
+        0..=1 => commands.despawn(id),
+        2..=3 => {
+            commands.spawn_as_child(Empty::default(), id);
+        }
+        // reparenting onto a descendant is refused by the scene
+        4..=5 => commands.reparent(id, Some(other)),
+        6 => commands.reparent(id, None),
+        7..=8 => commands.set_visible(other, rng.random_bool(0.5)),
+        9..=10 => commands.set_enabled(other, rng.random_bool(0.5)),
+        11 => commands.rename(id, format!("node {}", rng.random_range(0..1000))),
+        _ => ctx.node_mut().get_transform().position += random::unit_sphere(rng) * ctx.dt,
+    }
+}
+
+/// run one frame of the scene systems and traversals
+///
+/// emits [`Update`], syncs transforms, walks the tree, collects visible nodes and casts rays
+/// through the scene. returns the number of nodes walked
+pub fn stress_frame(ctx: &GameContext, dt: f32) -> usize {
+    ctx.pop_ready_queue();
+    ctx.emit(Update {
+        dt,
+        unscaled_dt: dt,
+    });
+    ctx.sync_world_transform();
+
+    let walked = ctx.scene.walk().count();
+    let _ = ctx.scene.collect_visible::<Empty>();
+
+    for direction in [Vec3::X, Vec3::NEG_Y, Vec3::ONE, Vec3::new(-1.0, 0.5, 0.25)] {
+        let _ = ctx.scene.raycast_all(&Ray::new(Vec3::ZERO, direction));
+    }
+
+    walked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_scene() {
+        let shape = |scene: &Scene| {
+            scene.sync_world_transform();
+            scene
+                .walk()
+                .map(|entry| (entry.depth(), entry.world.position()))
+                .collect::<Vec<_>>()
+        };
+        let a = StressScene::new(500).seed(3).build();
+        let b = StressScene::new(500).seed(3).build();
+
+        assert_eq!(shape(&a).len(), 500);
+        assert_eq!(shape(&a), shape(&b));
+        assert!(shape(&a).iter().all(|(depth, _)| *depth <= 8));
+    }
+
+    #[test]
+    fn test_random_scenes_survive_random_events() {
+        for seed in 0..8 {
+            let ctx = GameContext::new();
+            StressScene::new(2_000)
+                .seed(seed)
+                .max_depth(seed as usize % 12)
+                .random_events(true)
+                .populate(&ctx.scene);
+
+            for _ in 0..20 {
+                let walked = stress_frame(&ctx, 1.0 / 60.0);
+                assert!(walked > 0);
+                assert!(ctx.scene.commands().is_empty());
+            }
+        }
+    }
+}
@@ -0,0 +1,609 @@
+//! data driven dialogue
+//!
+//! a [`Dialogue`] is a graph of lines loaded from a json file. the [`DialogueRunner`] resource
+//! walks the graph, tracks variables used by conditions and emits [`DialogueStarted`],
+//! [`DialogueLine`] and [`DialogueEnded`] events to the scene so ui nodes can display it.
+//!
+//! # Format
+//! ```json
+//! {
+//!     "start": "greet",
+//!     "nodes": {
+//!         "greet": {
+//!             "speaker": "Guard",
+//!             "text": "Halt! Who goes there?",
+//!             "choices": [
+//!                 { "text": "A friend.", "next": "friend" },
+//!                 {
+//!                     "text": "Here's some gold.",
+//!                     "next": "bribe",
+//!                     "conditions": [{ "var": "has_gold", "op": "==", "value": true }],
+//!                     "set": { "has_gold": false }
+//!                 }
+//!             ]
+//!         },
+//!         "friend": { "speaker": "Guard", "text": "Move along then." },
+//!         "bribe": { "speaker": "Guard", "text": "I didn't see anything.", "next": "friend" }
+//!     }
+//! }
+//! ```
+//!
+//! lines without choices continue to `next` (or end the dialogue) when advanced. a node whose
+//! `conditions` fail jumps to `otherwise` instead of being shown.
+//!
+//! # Example
+//! ```rust, ignore
+//! let dialogue = ctx.assets.load::<Dialogue>("res/dialogue/guard.json");
+//! ctx.get_resource_mut::<DialogueRunner>().start(dialogue);
+//!
+//! ui.on::<DialogueLine>(|ctx| {
+//!     println!("{}: {}", ctx.speaker.as_deref().unwrap_or(""), ctx.text);
+//! });
+//! ```
+
+use std::collections::HashMap;
+
+use maple_app::{App, Plugin, Running};
+use maple_engine::{
+    asset::{Asset, AssetHandle, AssetLoader, FileLoader, LoadErr},
+    prelude::{AssetLibrary, EventLabel, Input, KeyCode, Resource},
+};
+use serde::{Deserialize, Serialize};
+
+/// a value stored in the [`DialogueRunner`] variables
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DialogueValue {
+    Bool(bool),
+    Number(f64),
+    Text(String),
+}
+
+impl From<bool> for DialogueValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<f64> for DialogueValue {
+    fn from(value: f64) -> Self {
+        Self::Number(value)
+    }
+}
+
+impl From<&str> for DialogueValue {
+    fn from(value: &str) -> Self {
+        Self::Text(value.to_string())
+    }
+}
+
+impl From<String> for DialogueValue {
+    fn from(value: String) -> Self {
+        Self::Text(value)
+    }
+}
+
+/// comparison used by a [`Condition`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompareOp {
+    #[serde(rename = "==")]
+    Equal,
+    #[serde(rename = "!=")]
+    NotEqual,
+    #[serde(rename = ">")]
+    Greater,
+    #[serde(rename = ">=")]
+    GreaterEqual,
+    #[serde(rename = "<")]
+    Less,
+    #[serde(rename = "<=")]
+    LessEqual,
+}
+
+/// a check against a dialogue variable. missing variables never pass except for `!=`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Condition {
+    pub var: String,
+    #[serde(default = "default_op")]
+    pub op: CompareOp,
+    pub value: DialogueValue,
+}
+
+fn default_op() -> CompareOp {
+    CompareOp::Equal
+}
+
+impl Condition {
+    /// returns true if the condition holds for the given variables
+    pub fn check(&self, variables: &HashMap<String, DialogueValue>) -> bool {
+        let Some(current) = variables.get(&self.var) else {
+            return self.op == CompareOp::NotEqual;
+        };
+
+        match self.op {
+            CompareOp::Equal => *current == self.value,
+            CompareOp::NotEqual => *current != self.value,
+            op => {
+                let (DialogueValue::Number(a), DialogueValue::Number(b)) = (current, &self.value)
+                else {
+                    return false;
+                };
+                match op {
+                    CompareOp::Greater => a > b,
+                    CompareOp::GreaterEqual => a >= b,
+                    CompareOp::Less => a < b,
+                    CompareOp::LessEqual => a <= b,
+                    CompareOp::Equal | CompareOp::NotEqual => unreachable!("handled above"),
+                }
+            }
+        }
+    }
+}
+
+/// an option the player can pick at a line
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Choice {
+    pub text: String,
+    /// node to go to when picked. the dialogue ends if None
+    #[serde(default)]
+    pub next: Option<String>,
+    /// the choice is hidden unless all conditions pass
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    /// variables to set when picked
+    #[serde(default)]
+    pub set: HashMap<String, DialogueValue>,
+}
+
+/// a single line in a dialogue graph
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DialogueNode {
+    #[serde(default)]
+    pub speaker: Option<String>,
+    pub text: String,
+    #[serde(default)]
+    pub choices: Vec<Choice>,
+    /// node to continue to once advanced if there are no choices
+    #[serde(default)]
+    pub next: Option<String>,
+    /// the node is skipped unless all conditions pass
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    /// node to go to instead if the conditions fail. the dialogue ends if None
+    #[serde(default)]
+    pub otherwise: Option<String>,
+    /// variables to set when the line is shown
+    #[serde(default)]
+    pub set: HashMap<String, DialogueValue>,
+}
+
+/// [`Asset`] containing a dialogue graph
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Dialogue {
+    /// the node the dialogue starts at
+    pub start: String,
+    pub nodes: HashMap<String, DialogueNode>,
+}
+
+impl Asset for Dialogue {
+    type Loader = DialogueLoader;
+}
+
+impl Dialogue {
+    /// parse a dialogue from json
+    pub fn from_json(source: &str) -> Result<Self, LoadErr> {
+        let dialogue: Dialogue =
+            serde_json::from_str(source).map_err(|err| LoadErr::Import(err.to_string()))?;
+
+        if !dialogue.nodes.contains_key(&dialogue.start) {
+            return Err(LoadErr::Import(format!(
+                "dialogue start node `{}` does not exist",
+                dialogue.start
+            )));
+        }
+
+        Ok(dialogue)
+    }
+}
+
+/// loader for [`Dialogue`] json files
+pub struct DialogueLoader;
+
+impl AssetLoader for DialogueLoader {
+    type Asset = Dialogue;
+}
+
+impl FileLoader for DialogueLoader {
+    fn load_path(
+        &self,
+        path: &std::path::Path,
+        _library: &AssetLibrary,
+    ) -> Result<Self::Asset, LoadErr> {
+        let source =
+            std::fs::read_to_string(path).map_err(|err| LoadErr::Import(err.to_string()))?;
+        Dialogue::from_json(&source)
+    }
+
+    fn load_bytes(
+        &self,
+        _path: &std::path::Path,
+        bytes: Vec<u8>,
+        _library: &AssetLibrary,
+    ) -> Result<Self::Asset, LoadErr> {
+        let source = String::from_utf8(bytes).map_err(|err| LoadErr::Import(err.to_string()))?;
+        Dialogue::from_json(&source)
+    }
+}
+
+/// event emitted when a dialogue begins
+pub struct DialogueStarted;
+impl EventLabel for DialogueStarted {}
+
+/// event emitted when a new line should be shown
+#[derive(Clone, Debug, PartialEq)]
+pub struct DialogueLine {
+    /// id of the node in the dialogue graph
+    pub id: String,
+    pub speaker: Option<String>,
+    pub text: String,
+    /// text of the choices that can be picked with [`DialogueRunner::choose`]
+    pub choices: Vec<String>,
+}
+impl EventLabel for DialogueLine {}
+
+/// event emitted when a dialogue finishes or is stopped
+pub struct DialogueEnded;
+impl EventLabel for DialogueEnded {}
+
+enum DialogueAction {
+    Start(AssetHandle<Dialogue>),
+    Advance,
+    Choose(usize),
+    Stop,
+}
+
+enum PendingEvent {
+    Started,
+    Line(DialogueLine),
+    Ended,
+}
+
+struct ActiveDialogue {
+    dialogue: AssetHandle<Dialogue>,
+    /// None until the dialogue asset has loaded
+    current: Option<String>,
+    /// indices into the choices of the current node that passed their conditions
+    available: Vec<usize>,
+}
+
+/// resource that runs a [`Dialogue`]
+///
+/// actions are applied during the [`DialoguePlugin`] update so they can be called from any
+/// event handler.
+pub struct DialogueRunner {
+    /// variables read by conditions and written by `set`
+    pub variables: HashMap<String, DialogueValue>,
+    /// keys that advance a line without choices
+    pub advance_keys: Vec<KeyCode>,
+    /// pick choices with the number keys 1 - 9
+    pub number_key_choices: bool,
+
+    active: Option<ActiveDialogue>,
+    line: Option<DialogueLine>,
+    actions: Vec<DialogueAction>,
+    pending: Vec<PendingEvent>,
+}
+
+impl Resource for DialogueRunner {}
+
+impl Default for DialogueRunner {
+    fn default() -> Self {
+        Self {
+            variables: HashMap::new(),
+            advance_keys: vec![KeyCode::Space, KeyCode::Enter],
+            number_key_choices: true,
+            active: None,
+            line: None,
+            actions: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl DialogueRunner {
+    /// start a dialogue replacing any running one. it begins once the asset is loaded
+    pub fn start(&mut self, dialogue: AssetHandle<Dialogue>) {
+        self.actions.push(DialogueAction::Start(dialogue));
+    }
+
+    /// continue past the current line. does nothing if the line has choices
+    pub fn advance(&mut self) {
+        self.actions.push(DialogueAction::Advance);
+    }
+
+    /// pick one of the choices of the current line
+    pub fn choose(&mut self, index: usize) {
+        self.actions.push(DialogueAction::Choose(index));
+    }
+
+    /// end the running dialogue
+    pub fn stop(&mut self) {
+        self.actions.push(DialogueAction::Stop);
+    }
+
+    /// returns true while a dialogue is running
+    pub fn is_running(&self) -> bool {
+        self.active.is_some()
+    }
+
+    /// the line currently being shown
+    pub fn line(&self) -> Option<&DialogueLine> {
+        self.line.as_ref()
+    }
+
+    /// set a variable used by conditions
+    pub fn set_variable(&mut self, name: impl Into<String>, value: impl Into<DialogueValue>) {
+        self.variables.insert(name.into(), value.into());
+    }
+
+    /// applies queued actions
+    fn process(&mut self, assets: &AssetLibrary) {
+        for action in std::mem::take(&mut self.actions) {
+            match action {
+                DialogueAction::Start(dialogue) => {
+                    if self.active.is_some() {
+                        self.end();
+                    }
+                    self.active = Some(ActiveDialogue {
+                        dialogue,
+                        current: None,
+                        available: Vec::new(),
+                    });
+                }
+                DialogueAction::Stop => {
+                    if self.active.is_some() {
+                        self.end();
+                    }
+                }
+                DialogueAction::Advance | DialogueAction::Choose(_) => {
+                    let Some(active) = &self.active else {
+                        continue;
+                    };
+                    let Some(dialogue) = assets.get(&active.dialogue) else {
+                        continue;
+                    };
+                    self.step(&dialogue, &action);
+                }
+            }
+        }
+
+        // begin dialogues once they have loaded
+        if let Some(active) = &self.active
+            && active.current.is_none()
+            && let Some(dialogue) = assets.get(&active.dialogue)
+        {
+            self.pending.push(PendingEvent::Started);
+            let start = dialogue.start.clone();
+            self.goto(&dialogue, Some(start));
+        }
+    }
+
+    /// handles advance and choose on a loaded dialogue
+    fn step(&mut self, dialogue: &Dialogue, action: &DialogueAction) {
+        let Some(active) = &self.active else {
+            return;
+        };
+        let Some(node) = active
+            .current
+            .as_ref()
+            .and_then(|current| dialogue.nodes.get(current))
+        else {
+            return;
+        };
+
+        match action {
+            DialogueAction::Advance if node.choices.is_empty() => {
+                let next = node.next.clone();
+                self.goto(dialogue, next);
+            }
+            DialogueAction::Choose(index) => {
+                let Some(choice) = active
+                    .available
+                    .get(*index)
+                    .and_then(|i| node.choices.get(*i))
+                else {
+                    log::warn!("dialogue choice {index} is not available");
+                    return;
+                };
+                self.variables.extend(choice.set.clone());
+                let next = choice.next.clone();
+                self.goto(dialogue, next);
+            }
+            _ => {}
+        }
+    }
+
+    /// moves to a node following `otherwise` while conditions fail
+    fn goto(&mut self, dialogue: &Dialogue, mut next: Option<String>) {
+        // bounded so a cycle of failing conditions can't hang the game
+        for _ in 0..=dialogue.nodes.len() {
+            let Some(id) = next else {
+                self.end();
+                return;
+            };
+            let Some(node) = dialogue.nodes.get(&id) else {
+                log::error!("dialogue node `{id}` does not exist");
+                self.end();
+                return;
+            };
+
+            if !node.conditions.iter().all(|c| c.check(&self.variables)) {
+                next = node.otherwise.clone();
+                continue;
+            }
+
+            self.variables.extend(node.set.clone());
+
+            let available: Vec<usize> = node
+                .choices
+                .iter()
+                .enumerate()
+                .filter(|(_, choice)| choice.conditions.iter().all(|c| c.check(&self.variables)))
+                .map(|(i, _)| i)
+                .collect();
+
+            let line = DialogueLine {
+                id: id.clone(),
+                speaker: node.speaker.clone(),
+                text: node.text.clone(),
+                choices: available
+                    .iter()
+                    .map(|i| node.choices[*i].text.clone())
+                    .collect(),
+            };
+
+            if let Some(active) = &mut self.active {
+                active.current = Some(id);
+                active.available = available;
+            }
+            self.line = Some(line.clone());
+            self.pending.push(PendingEvent::Line(line));
+            return;
+        }
+
+        log::error!("dialogue conditions loop without showing a line");
+        self.end();
+    }
+
+    fn end(&mut self) {
+        self.active = None;
+        self.line = None;
+        self.pending.push(PendingEvent::Ended);
+    }
+
+    /// turns key presses into actions
+    fn handle_input(&mut self, input: &Input) {
+        let Some(line) = &self.line else {
+            return;
+        };
+
+        if line.choices.is_empty() {
+            if self
+                .advance_keys
+                .iter()
+                .any(|key| input.key_just_pressed.contains(key))
+            {
+                self.advance();
+            }
+            return;
+        }
+
+        if !self.number_key_choices {
+            return;
+        }
+
+        const NUMBER_KEYS: [KeyCode; 9] = [
+            KeyCode::Digit1,
+            KeyCode::Digit2,
+            KeyCode::Digit3,
+            KeyCode::Digit4,
+            KeyCode::Digit5,
+            KeyCode::Digit6,
+            KeyCode::Digit7,
+            KeyCode::Digit8,
+            KeyCode::Digit9,
+        ];
+
+        let pressed = NUMBER_KEYS
+            .iter()
+            .take(line.choices.len())
+            .position(|key| input.key_just_pressed.contains(key));
+
+        if let Some(index) = pressed {
+            self.choose(index);
+        }
+    }
+}
+
+/// plugin that drives the [`DialogueRunner`] resource
+pub struct DialoguePlugin;
+
+impl Plugin for DialoguePlugin {
+    fn setup(&self, app: &mut App<maple_app::Init>) {
+        app.context_mut().insert_resource(DialogueRunner::default());
+        app.context_mut().assets.register_loader(DialogueLoader);
+    }
+
+    fn update(&self, app: &mut App<Running>) {
+        let pending = {
+            let mut runner = app.context().get_resource_mut::<DialogueRunner>();
+            if app.context().has_resource::<Input>() {
+                runner.handle_input(&app.context().get_resource::<Input>());
+            }
+            runner.process(&app.context().assets);
+            std::mem::take(&mut runner.pending)
+        };
+
+        // the runner is unlocked so handlers can queue the next action
+        for event in pending {
+            match event {
+                PendingEvent::Started => app.context().emit(DialogueStarted),
+                PendingEvent::Line(line) => app.context().emit(line),
+                PendingEvent::Ended => app.context().emit(DialogueEnded),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GUARD: &str = r#"{
+        "start": "greet",
+        "nodes": {
+            "greet": {
+                "speaker": "Guard",
+                "text": "Halt!",
+                "choices": [
+                    { "text": "A friend.", "next": "friend" },
+                    {
+                        "text": "Gold?",
+                        "next": "bribe",
+                        "conditions": [{ "var": "gold", "op": ">=", "value": 10 }],
+                        "set": { "bribed": true }
+                    }
+                ]
+            },
+            "friend": { "text": "Move along.", "next": "bribed" },
+            "bribe": { "text": "Fine." },
+            "bribed": {
+                "text": "Thanks for the gold.",
+                "conditions": [{ "var": "bribed", "value": true }]
+            }
+        }
+    }"#;
+
+    #[test]
+    fn test_dialogue_conditions_and_choices() {
+        let assets = AssetLibrary::new();
+        let dialogue = assets.register(Dialogue::from_json(GUARD).unwrap());
+
+        let mut runner = DialogueRunner::default();
+        runner.start(dialogue);
+        runner.process(&assets);
+
+        // not enough gold so only one choice is offered
+        assert_eq!(runner.line().unwrap().choices, vec!["A friend."]);
+
+        runner.choose(0);
+        runner.process(&assets);
+        assert_eq!(runner.line().unwrap().text, "Move along.");
+
+        // "bribed" fails its condition and has no otherwise so the dialogue ends
+        runner.advance();
+        runner.process(&assets);
+        assert!(!runner.is_running());
+        assert!(matches!(runner.pending.last(), Some(PendingEvent::Ended)));
+    }
+}
@@ -0,0 +1,655 @@
+//! item definitions and inventories
+//!
+//! items are defined in json files and loaded into the [`ItemDatabase`] resource. an
+//! [`Inventory`] is a list of slots holding stacks of those items and can be stored on any node
+//! that needs one, e.g. in a field of a player node or in a [`maple_engine::nodes::Container`].
+//!
+//! with the `egui` feature enabled [`inventory_grid`] draws an inventory as a grid of slots.
+//!
+//! # Format
+//! ```json
+//! {
+//!     "items": [
+//!         {
+//!             "id": "potion",
+//!             "name": "Health Potion",
+//!             "icon": "res/icons/potion.png",
+//!             "stack_size": 10,
+//!             "properties": { "heal": 25, "consumable": true }
+//!         },
+//!         { "id": "sword", "name": "Iron Sword", "properties": { "damage": 8 } }
+//!     ]
+//! }
+//! ```
+//!
+//! # Example
+//! ```rust, ignore
+//! let items = ctx.assets.load::<ItemList>("res/items.json");
+//! ctx.get_resource_mut::<ItemDatabase>().load(items);
+//!
+//! let mut inventory = Inventory::new(20);
+//! let leftover = inventory.add(&ctx.get_resource::<ItemDatabase>(), "potion", 3);
+//! ```
+
+use std::collections::HashMap;
+
+use maple_app::{App, Init, Plugin, Running};
+use maple_engine::{
+    asset::{Asset, AssetHandle, AssetLoader, FileLoader, LoadErr},
+    prelude::{AssetLibrary, Resource},
+};
+use maple_renderer::core::texture::Texture;
+use serde::{Deserialize, Serialize};
+
+/// a value of an item property
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ItemProperty {
+    Bool(bool),
+    Number(f64),
+    Text(String),
+}
+
+impl ItemProperty {
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            ItemProperty::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            ItemProperty::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            ItemProperty::Text(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+fn default_stack_size() -> u32 {
+    1
+}
+
+/// the definition of an item type
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ItemDef {
+    /// unique id used to refer to the item from code and other data files
+    pub id: String,
+    /// name shown to the player
+    pub name: String,
+    /// path to the icon texture
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// how many of this item fit in a single slot
+    #[serde(default = "default_stack_size")]
+    pub stack_size: u32,
+    /// game specific data such as damage or value
+    #[serde(default)]
+    pub properties: HashMap<String, ItemProperty>,
+    /// the loaded icon. set by [`ItemDatabase::load_icons`]
+    #[serde(skip)]
+    pub icon_texture: Option<AssetHandle<Texture>>,
+}
+
+impl ItemDef {
+    /// get a property of this item
+    pub fn property(&self, name: &str) -> Option<&ItemProperty> {
+        self.properties.get(name)
+    }
+}
+
+/// [`Asset`] containing a list of item definitions
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ItemList {
+    pub items: Vec<ItemDef>,
+}
+
+impl Asset for ItemList {
+    type Loader = ItemListLoader;
+}
+
+impl ItemList {
+    /// parse an item list from json
+    pub fn from_json(source: &str) -> Result<Self, LoadErr> {
+        serde_json::from_str(source).map_err(|err| LoadErr::Import(err.to_string()))
+    }
+}
+
+/// loader for [`ItemList`] json files
+pub struct ItemListLoader;
+
+impl AssetLoader for ItemListLoader {
+    type Asset = ItemList;
+}
+
+impl FileLoader for ItemListLoader {
+    fn load_path(
+        &self,
+        path: &std::path::Path,
+        _library: &AssetLibrary,
+    ) -> Result<Self::Asset, LoadErr> {
+        let source =
+            std::fs::read_to_string(path).map_err(|err| LoadErr::Import(err.to_string()))?;
+        ItemList::from_json(&source)
+    }
+
+    fn load_bytes(
+        &self,
+        _path: &std::path::Path,
+        bytes: Vec<u8>,
+        _library: &AssetLibrary,
+    ) -> Result<Self::Asset, LoadErr> {
+        let source = String::from_utf8(bytes).map_err(|err| LoadErr::Import(err.to_string()))?;
+        ItemList::from_json(&source)
+    }
+}
+
+/// resource containing every item definition in the game
+#[derive(Default)]
+pub struct ItemDatabase {
+    items: HashMap<String, ItemDef>,
+    /// lists waiting to finish loading
+    pending: Vec<AssetHandle<ItemList>>,
+}
+
+impl Resource for ItemDatabase {}
+
+impl ItemDatabase {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// add the items of a list once it has loaded. items with the same id replace earlier ones
+    pub fn load(&mut self, list: AssetHandle<ItemList>) {
+        self.pending.push(list);
+    }
+
+    /// add all items of a list
+    pub fn extend(&mut self, list: &ItemList) {
+        for item in &list.items {
+            self.insert(item.clone());
+        }
+    }
+
+    /// add or replace a single item definition
+    pub fn insert(&mut self, item: ItemDef) {
+        self.items.insert(item.id.clone(), item);
+    }
+
+    /// get an item definition
+    pub fn get(&self, id: &str) -> Option<&ItemDef> {
+        self.items.get(id)
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.items.contains_key(id)
+    }
+
+    /// iterate over every item definition
+    pub fn iter(&self) -> impl Iterator<Item = &ItemDef> {
+        self.items.values()
+    }
+
+    /// true if there are item lists that haven't finished loading
+    pub fn is_loading(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// start loading the icon of every item that has one
+    pub fn load_icons(&mut self, assets: &AssetLibrary) {
+        for item in self.items.values_mut() {
+            if item.icon_texture.is_none()
+                && let Some(icon) = &item.icon
+            {
+                item.icon_texture = Some(assets.load::<Texture>(icon));
+            }
+        }
+    }
+
+    /// move loaded lists into the database. returns true if anything was added
+    fn poll(&mut self, assets: &AssetLibrary) -> bool {
+        let mut added = false;
+
+        for list in std::mem::take(&mut self.pending) {
+            if let Some(items) = assets.get(&list) {
+                self.extend(&items);
+                added = true;
+            } else if assets.is_loading(&list) {
+                self.pending.push(list);
+            } else {
+                log::error!("failed to load item list");
+            }
+        }
+
+        added
+    }
+}
+
+/// a number of one item in a slot
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemStack {
+    pub item: String,
+    pub count: u32,
+}
+
+impl ItemStack {
+    pub fn new(item: impl Into<String>, count: u32) -> Self {
+        Self {
+            item: item.into(),
+            count,
+        }
+    }
+}
+
+/// a fixed number of slots that hold item stacks
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Inventory {
+    slots: Vec<Option<ItemStack>>,
+}
+
+impl Inventory {
+    /// create an empty inventory with `size` slots
+    pub fn new(size: usize) -> Self {
+        Self {
+            slots: vec![None; size],
+        }
+    }
+
+    /// the number of slots
+    pub fn size(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// change the number of slots. stacks in removed slots are returned
+    pub fn resize(&mut self, size: usize) -> Vec<ItemStack> {
+        let removed = if size < self.slots.len() {
+            self.slots.drain(size..).flatten().collect()
+        } else {
+            Vec::new()
+        };
+        self.slots.resize(size, None);
+        removed
+    }
+
+    pub fn slots(&self) -> &[Option<ItemStack>] {
+        &self.slots
+    }
+
+    /// the stack in a slot
+    pub fn slot(&self, index: usize) -> Option<&ItemStack> {
+        self.slots.get(index)?.as_ref()
+    }
+
+    /// add items filling existing stacks first then empty slots.
+    ///
+    /// returns how many items did not fit. unknown items don't fit anywhere
+    pub fn add(&mut self, items: &ItemDatabase, item: &str, mut count: u32) -> u32 {
+        let Some(def) = items.get(item) else {
+            log::warn!("tried to add unknown item {item} to an inventory");
+            return count;
+        };
+        let stack_size = def.stack_size.max(1);
+
+        for stack in self.slots.iter_mut().flatten() {
+            if count == 0 {
+                break;
+            }
+            if stack.item == item && stack.count < stack_size {
+                let moved = count.min(stack_size - stack.count);
+                stack.count += moved;
+                count -= moved;
+            }
+        }
+
+        for slot in self.slots.iter_mut().filter(|slot| slot.is_none()) {
+            if count == 0 {
+                break;
+            }
+            let moved = count.min(stack_size);
+            *slot = Some(ItemStack::new(item, moved));
+            count -= moved;
+        }
+
+        count
+    }
+
+    /// remove up to `count` of an item taking from the last slots first. returns how many were
+    /// removed
+    pub fn remove(&mut self, item: &str, count: u32) -> u32 {
+        let mut removed = 0;
+
+        for slot in self.slots.iter_mut().rev() {
+            if removed == count {
+                break;
+            }
+            if let Some(stack) = slot
+                && stack.item == item
+            {
+                let taken = stack.count.min(count - removed);
+                stack.count -= taken;
+                removed += taken;
+                if stack.count == 0 {
+                    *slot = None;
+                }
+            }
+        }
+
+        removed
+    }
+
+    /// total number of an item across all slots
+    pub fn count(&self, item: &str) -> u32 {
+        self.slots
+            .iter()
+            .flatten()
+            .filter(|stack| stack.item == item)
+            .map(|stack| stack.count)
+            .sum()
+    }
+
+    pub fn contains(&self, item: &str, count: u32) -> bool {
+        self.count(item) >= count
+    }
+
+    /// take the whole stack out of a slot
+    pub fn take(&mut self, index: usize) -> Option<ItemStack> {
+        self.slots.get_mut(index)?.take()
+    }
+
+    /// put a stack in a slot returning what was there before
+    pub fn set(&mut self, index: usize, stack: Option<ItemStack>) -> Option<ItemStack> {
+        std::mem::replace(self.slots.get_mut(index)?, stack)
+    }
+
+    /// swap two slots
+    pub fn swap(&mut self, a: usize, b: usize) {
+        if a < self.slots.len() && b < self.slots.len() {
+            self.slots.swap(a, b);
+        }
+    }
+
+    /// move the stack in `from` onto `to`. matching items are merged up to the stack size and
+    /// anything else is swapped
+    pub fn move_stack(&mut self, items: &ItemDatabase, from: usize, to: usize) {
+        if from == to || from >= self.slots.len() || to >= self.slots.len() {
+            return;
+        }
+
+        if let (Some(source), Some(target)) = (&self.slots[from], &self.slots[to])
+            && source.item == target.item
+        {
+            let stack_size = items
+                .get(&source.item)
+                .map_or(1, |def| def.stack_size.max(1));
+            let moved = source.count.min(stack_size.saturating_sub(target.count));
+            if moved > 0 {
+                let remaining = source.count - moved;
+                if let Some(target) = &mut self.slots[to] {
+                    target.count += moved;
+                }
+                if remaining == 0 {
+                    self.slots[from] = None;
+                } else if let Some(source) = &mut self.slots[from] {
+                    source.count = remaining;
+                }
+                return;
+            }
+        }
+
+        self.slots.swap(from, to);
+    }
+
+    /// true if no slot is empty
+    pub fn is_full(&self) -> bool {
+        self.slots.iter().all(Option::is_some)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.iter().all(Option::is_none)
+    }
+
+    /// remove every item
+    pub fn clear(&mut self) {
+        self.slots.iter_mut().for_each(|slot| *slot = None);
+    }
+}
+
+/// adds the [`ItemDatabase`] resource and loads item lists queued with [`ItemDatabase::load`]
+pub struct InventoryPlugin;
+
+impl Plugin for InventoryPlugin {
+    fn setup(&self, app: &mut App<Init>) {
+        app.context_mut().insert_resource(ItemDatabase::default());
+        app.context_mut().assets.register_loader(ItemListLoader);
+    }
+
+    fn update(&self, app: &mut App<Running>) {
+        let assets = &app.context().assets;
+        let mut items = app.context().get_resource_mut::<ItemDatabase>();
+        if items.is_loading() && items.poll(assets) {
+            items.load_icons(assets);
+        }
+    }
+}
+
+#[cfg(feature = "egui")]
+pub use ui::{InventoryGrid, InventoryGridResponse, inventory_grid};
+
+#[cfg(feature = "egui")]
+mod ui {
+    use maple_egui::{
+        egui,
+        plugin::{EguiResource, FromColor},
+    };
+    use maple_engine::{color::Color, prelude::AssetLibrary};
+
+    use super::{Inventory, ItemDatabase};
+
+    /// how [`inventory_grid`] lays out and draws slots
+    #[derive(Clone, Debug)]
+    pub struct InventoryGrid {
+        /// number of slots in each row
+        pub columns: usize,
+        /// width and height of a slot in points
+        pub slot_size: f32,
+        pub spacing: f32,
+        pub slot_color: Color,
+        pub hovered_color: Color,
+        pub selected_color: Color,
+        pub text_color: Color,
+        /// slot drawn highlighted
+        pub selected: Option<usize>,
+    }
+
+    impl Default for InventoryGrid {
+        fn default() -> Self {
+            Self {
+                columns: 5,
+                slot_size: 48.0,
+                spacing: 4.0,
+                slot_color: Color::from_normalized(0.15, 0.15, 0.15, 0.9),
+                hovered_color: Color::from_normalized(0.25, 0.25, 0.25, 0.9),
+                selected_color: Color::from_normalized(0.45, 0.35, 0.1, 0.9),
+                text_color: Color::WHITE,
+                selected: None,
+            }
+        }
+    }
+
+    /// what happened to the grid this frame
+    #[derive(Clone, Debug, Default, PartialEq, Eq)]
+    pub struct InventoryGridResponse {
+        /// slot that was clicked
+        pub clicked: Option<usize>,
+        /// slot under the pointer
+        pub hovered: Option<usize>,
+        /// a stack was dragged from the first slot and dropped on the second
+        pub moved: Option<(usize, usize)>,
+    }
+
+    /// draw an inventory as a grid of slots showing each item's icon and count.
+    ///
+    /// items without a loaded icon (see [`ItemDatabase::load_icons`]) show the start of their
+    /// name instead. dragging a stack onto another slot is reported in
+    /// [`InventoryGridResponse::moved`] so it can be applied with [`Inventory::move_stack`].
+    /// hovering a slot shows the item name.
+    ///
+    /// ```rust, ignore
+    /// let response = inventory_grid(
+    ///     ui,
+    ///     &player.inventory,
+    ///     &ctx.get_resource::<ItemDatabase>(),
+    ///     &ctx.assets,
+    ///     &mut ctx.get_resource_mut::<EguiResource>(),
+    ///     &InventoryGrid::default(),
+    /// );
+    /// ```
+    pub fn inventory_grid(
+        ui: &mut egui::Ui,
+        inventory: &Inventory,
+        items: &ItemDatabase,
+        assets: &AssetLibrary,
+        egui_res: &mut EguiResource,
+        grid: &InventoryGrid,
+    ) -> InventoryGridResponse {
+        let mut response = InventoryGridResponse::default();
+        let drag_id = ui.id().with("maple_inventory_drag");
+        let dragging: Option<usize> = ui.ctx().data(|data| data.get_temp(drag_id));
+
+        let slot_color = egui::Color32::from_color(grid.slot_color);
+        let hovered_color = egui::Color32::from_color(grid.hovered_color);
+        let selected_color = egui::Color32::from_color(grid.selected_color);
+        let text_color = egui::Color32::from_color(grid.text_color);
+
+        egui::Grid::new(ui.id().with("maple_inventory_grid"))
+            .spacing(egui::vec2(grid.spacing, grid.spacing))
+            .show(ui, |ui| {
+                for (index, slot) in inventory.slots().iter().enumerate() {
+                    let (rect, slot_response) = ui.allocate_exact_size(
+                        egui::vec2(grid.slot_size, grid.slot_size),
+                        egui::Sense::click_and_drag(),
+                    );
+
+                    let fill = if grid.selected == Some(index) {
+                        selected_color
+                    } else if slot_response.hovered() {
+                        hovered_color
+                    } else {
+                        slot_color
+                    };
+                    ui.painter().rect_filled(rect, 4.0, fill);
+
+                    if let Some(stack) = slot {
+                        let def = items.get(&stack.item);
+                        let name = def.map_or(stack.item.as_str(), |def| def.name.as_str());
+                        let icon = def
+                            .and_then(|def| def.icon_texture.as_ref())
+                            .and_then(|handle| assets.get(handle));
+
+                        if let Some(icon) = icon {
+                            ui.painter().image(
+                                egui_res.register_texture(&icon),
+                                rect.shrink(grid.slot_size * 0.1),
+                                egui::Rect::from_min_max(
+                                    egui::pos2(0.0, 0.0),
+                                    egui::pos2(1.0, 1.0),
+                                ),
+                                egui::Color32::WHITE,
+                            );
+                        } else {
+                            ui.painter().text(
+                                rect.center(),
+                                egui::Align2::CENTER_CENTER,
+                                name.chars().take(3).collect::<String>(),
+                                egui::FontId::proportional(grid.slot_size * 0.3),
+                                text_color,
+                            );
+                        }
+                        if stack.count > 1 {
+                            ui.painter().text(
+                                rect.right_bottom() - egui::vec2(4.0, 2.0),
+                                egui::Align2::RIGHT_BOTTOM,
+                                stack.count.to_string(),
+                                egui::FontId::proportional(grid.slot_size * 0.25),
+                                text_color,
+                            );
+                        }
+
+                        if slot_response.drag_started() {
+                            ui.ctx().data_mut(|data| data.insert_temp(drag_id, index));
+                        }
+
+                        slot_response.clone().on_hover_text(name);
+                    }
+
+                    if slot_response.clicked() {
+                        response.clicked = Some(index);
+                    }
+                    if slot_response.hovered() {
+                        response.hovered = Some(index);
+                    }
+                    if let Some(from) = dragging
+                        && ui.input(|input| input.pointer.any_released())
+                        && ui.rect_contains_pointer(rect)
+                    {
+                        response.moved = Some((from, index));
+                    }
+
+                    if (index + 1) % grid.columns.max(1) == 0 {
+                        ui.end_row();
+                    }
+                }
+            });
+
+        if dragging.is_some() && ui.input(|input| input.pointer.any_released()) {
+            ui.ctx().data_mut(|data| data.remove::<usize>(drag_id));
+        }
+
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ITEMS: &str = r#"{
+        "items": [
+            { "id": "potion", "name": "Health Potion", "stack_size": 5, "properties": { "heal": 25 } },
+            { "id": "sword", "name": "Iron Sword" }
+        ]
+    }"#;
+
+    #[test]
+    fn test_inventory_stacking() {
+        let mut items = ItemDatabase::new();
+        items.extend(&ItemList::from_json(ITEMS).unwrap());
+
+        assert_eq!(
+            items.get("potion").unwrap().property("heal"),
+            Some(&ItemProperty::Number(25.0))
+        );
+
+        let mut inventory = Inventory::new(3);
+        assert_eq!(inventory.add(&items, "potion", 7), 0);
+        assert_eq!(inventory.slot(0), Some(&ItemStack::new("potion", 5)));
+        assert_eq!(inventory.slot(1), Some(&ItemStack::new("potion", 2)));
+
+        assert_eq!(inventory.add(&items, "sword", 2), 1);
+        assert!(inventory.is_full());
+
+        assert_eq!(inventory.remove("potion", 3), 3);
+        assert_eq!(inventory.count("potion"), 4);
+        assert_eq!(inventory.slot(1), None);
+
+        inventory.move_stack(&items, 2, 1);
+        assert_eq!(inventory.slot(1), Some(&ItemStack::new("sword", 1)));
+    }
+}
@@ -0,0 +1,48 @@
+//! common gameplay systems for the maple engine
+//!
+//! provides data driven building blocks most games end up writing themselves such as the
+//! [`dialogue`] and [`inventory`] systems, grid [`pathfinding`] and [`steering`] behaviors.
+
+pub mod dialogue;
+pub mod inventory;
+pub mod pathfinding;
+pub mod steering;
+
+/// the version of this crate
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// the cargo features this crate was built with
+pub const FEATURES: &[&str] = &[
+    #[cfg(feature = "egui")]
+    "egui",
+    #[cfg(feature = "tilemap")]
+    "tilemap",
+    #[cfg(feature = "physics")]
+    "physics",
+];
+
+pub mod prelude {
+    pub use crate::dialogue::{
+        Dialogue, DialogueEnded, DialogueLine, DialoguePlugin, DialogueRunner, DialogueStarted,
+        DialogueValue,
+    };
+
+    pub use crate::inventory::{
+        Inventory, InventoryPlugin, ItemDatabase, ItemDef, ItemList, ItemProperty, ItemStack,
+    };
+
+    pub use crate::pathfinding::{
+        DiagonalMovement, PathFound, PathGrid, PathRequest, Pathfinder, PathfindingPlugin,
+    };
+
+    pub use crate::steering::{
+        Neighbor, SteeringAgent, SteeringAgentBuilder, SteeringBehavior, SteeringPlugin,
+        SteeringTarget,
+    };
+
+    #[cfg(feature = "egui")]
+    pub use crate::inventory::{InventoryGrid, InventoryGridResponse, inventory_grid};
+
+    #[cfg(feature = "egui")]
+    pub use crate::pathfinding::{PathGridDebug, path_grid_debug};
+}
@@ -0,0 +1,562 @@
+//! A* pathfinding on tile grids
+//!
+//! a [`PathGrid`] stores the cost of walking through each cell. it can be built by hand or,
+//! with the `tilemap` feature, from the collision layer of a tilemap where every tile blocks
+//! movement. cells use the same coordinates as the tilemap with (0, 0) in the top left corner.
+//!
+//! paths can be found right away with [`PathGrid::find_path`] or in the background with the
+//! [`Pathfinder`] resource which emits [`PathFound`] to the requesting node once it is done.
+//!
+//! with the `egui` feature enabled [`path_grid_debug`] draws a grid and a path for debugging.
+//!
+//! # Example
+//! ```rust, ignore
+//! let grid = Arc::new(PathGrid::from_tile_layer(&walls.read()));
+//!
+//! scene
+//!     .spawn(Enemy::default())
+//!     .on::<Ready>(move |ctx| {
+//!         ctx.get_resource_mut::<Pathfinder>().request(
+//!             ctx.node_id(),
+//!             grid.clone(),
+//!             UVec2::new(1, 1),
+//!             UVec2::new(12, 7),
+//!             DiagonalMovement::NoCornerCutting,
+//!         );
+//!     })
+//!     .on::<PathFound>(|ctx| {
+//!         if let Some(path) = &ctx.event.path {
+//!             ctx.node_mut().follow(path.clone());
+//!         }
+//!     });
+//! ```
+
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    f32::consts::SQRT_2,
+    sync::{Arc, Mutex},
+};
+
+use glam::{IVec2, UVec2};
+use maple_app::{App, Init, Plugin, Running};
+use maple_engine::{
+    prelude::{EventLabel, Resource},
+    scene::NodeId,
+};
+
+/// how a path may move between cells
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DiagonalMovement {
+    /// only move up, down, left and right
+    Never,
+    /// move diagonally when both cells next to the corner are walkable
+    #[default]
+    NoCornerCutting,
+    /// move diagonally unless the target cell is blocked
+    Always,
+}
+
+/// the cost of walking through each cell of a grid
+///
+/// a cost of 1.0 is normal ground and higher costs are avoided when a cheaper way around
+/// exists. blocked cells can never be walked through.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathGrid {
+    width: u32,
+    height: u32,
+    costs: Vec<f32>,
+}
+
+impl PathGrid {
+    /// a grid where every cell is walkable with a cost of 1.0
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            costs: vec![1.0; (width * height) as usize],
+        }
+    }
+
+    /// build a grid from the cost of each cell. `None` blocks the cell
+    pub fn from_fn(width: u32, height: u32, mut cost: impl FnMut(UVec2) -> Option<f32>) -> Self {
+        let costs = (0..height)
+            .flat_map(|y| (0..width).map(move |x| UVec2::new(x, y)))
+            .map(|cell| {
+                cost(cell)
+                    .filter(|cost| *cost > 0.0)
+                    .unwrap_or(f32::INFINITY)
+            })
+            .collect();
+
+        Self {
+            width,
+            height,
+            costs,
+        }
+    }
+
+    /// a grid where every cell with a tile in `layer` is blocked
+    #[cfg(feature = "tilemap")]
+    pub fn from_tile_layer(layer: &maple_3d::tilemap::TileLayer) -> Self {
+        Self::from_fn(layer.width, layer.height, |cell| {
+            (!layer.has_tile(cell.x, cell.y)).then_some(1.0)
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// true if the cell is inside the grid
+    pub fn contains(&self, cell: UVec2) -> bool {
+        cell.x < self.width && cell.y < self.height
+    }
+
+    fn index(&self, cell: UVec2) -> usize {
+        (cell.y * self.width + cell.x) as usize
+    }
+
+    fn cell(&self, index: usize) -> UVec2 {
+        UVec2::new(index as u32 % self.width, index as u32 / self.width)
+    }
+
+    /// the cost of walking through a cell or `None` if it is blocked or outside the grid
+    pub fn cost(&self, cell: UVec2) -> Option<f32> {
+        if !self.contains(cell) {
+            return None;
+        }
+        let cost = self.costs[self.index(cell)];
+        cost.is_finite().then_some(cost)
+    }
+
+    /// true if the cell is inside the grid and not blocked
+    pub fn is_walkable(&self, cell: UVec2) -> bool {
+        self.cost(cell).is_some()
+    }
+
+    /// set the cost of a cell. `None` blocks the cell
+    pub fn set_cost(&mut self, cell: UVec2, cost: Option<f32>) {
+        if !self.contains(cell) {
+            return;
+        }
+        let index = self.index(cell);
+        self.costs[index] = cost.filter(|cost| *cost > 0.0).unwrap_or(f32::INFINITY);
+    }
+
+    /// block or unblock a cell. unblocked cells have a cost of 1.0
+    pub fn set_walkable(&mut self, cell: UVec2, walkable: bool) {
+        self.set_cost(cell, walkable.then_some(1.0));
+    }
+
+    fn walkable_offset(&self, cell: UVec2, offset: IVec2) -> Option<UVec2> {
+        let target = cell.as_ivec2() + offset;
+        if target.x < 0 || target.y < 0 {
+            return None;
+        }
+        let target = target.as_uvec2();
+        self.is_walkable(target).then_some(target)
+    }
+
+    /// the walkable cells that can be reached in one step from a cell and the distance to them
+    pub fn neighbors(
+        &self,
+        cell: UVec2,
+        diagonal: DiagonalMovement,
+    ) -> impl Iterator<Item = (UVec2, f32)> + '_ {
+        const OFFSETS: [IVec2; 8] = [
+            IVec2::new(1, 0),
+            IVec2::new(-1, 0),
+            IVec2::new(0, 1),
+            IVec2::new(0, -1),
+            IVec2::new(1, 1),
+            IVec2::new(-1, 1),
+            IVec2::new(1, -1),
+            IVec2::new(-1, -1),
+        ];
+
+        OFFSETS.iter().filter_map(move |offset| {
+            let target = self.walkable_offset(cell, *offset)?;
+            if offset.x == 0 || offset.y == 0 {
+                return Some((target, 1.0));
+            }
+
+            match diagonal {
+                DiagonalMovement::Never => None,
+                DiagonalMovement::Always => Some((target, SQRT_2)),
+                DiagonalMovement::NoCornerCutting => {
+                    let sides = self
+                        .walkable_offset(cell, IVec2::new(offset.x, 0))
+                        .is_some()
+                        && self
+                            .walkable_offset(cell, IVec2::new(0, offset.y))
+                            .is_some();
+                    sides.then_some((target, SQRT_2))
+                }
+            }
+        })
+    }
+
+    /// find the cheapest path between two cells with A*
+    ///
+    /// the path includes both the start and the goal. returns `None` if either cell is blocked
+    /// or the goal can't be reached
+    pub fn find_path(
+        &self,
+        start: UVec2,
+        goal: UVec2,
+        diagonal: DiagonalMovement,
+    ) -> Option<Vec<UVec2>> {
+        if !self.is_walkable(start) || !self.is_walkable(goal) {
+            return None;
+        }
+
+        // the heuristic has to use the cheapest cost to never overestimate
+        let min_cost = self
+            .costs
+            .iter()
+            .copied()
+            .filter(|cost| cost.is_finite())
+            .fold(f32::INFINITY, f32::min);
+        let heuristic = |cell: UVec2| -> f32 {
+            let delta = (cell.as_ivec2() - goal.as_ivec2()).abs().as_vec2();
+            let distance = match diagonal {
+                DiagonalMovement::Never => delta.x + delta.y,
+                _ => delta.max_element() + (SQRT_2 - 1.0) * delta.min_element(),
+            };
+            distance * min_cost
+        };
+
+        let mut cost_so_far = vec![f32::INFINITY; self.costs.len()];
+        let mut came_from = vec![usize::MAX; self.costs.len()];
+        let mut open = BinaryHeap::new();
+
+        let start_index = self.index(start);
+        let goal_index = self.index(goal);
+        cost_so_far[start_index] = 0.0;
+        open.push(OpenCell {
+            estimate: heuristic(start),
+            index: start_index,
+        });
+
+        while let Some(OpenCell { estimate, index }) = open.pop() {
+            if index == goal_index {
+                let mut path = vec![goal];
+                let mut current = index;
+                while current != start_index {
+                    current = came_from[current];
+                    path.push(self.cell(current));
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            let cell = self.cell(index);
+            // skip entries that were superseded by a cheaper way to the same cell
+            if estimate > cost_so_far[index] + heuristic(cell) {
+                continue;
+            }
+
+            for (neighbor, distance) in self.neighbors(cell, diagonal) {
+                let neighbor_index = self.index(neighbor);
+                let cost = cost_so_far[index] + distance * self.costs[neighbor_index];
+                if cost < cost_so_far[neighbor_index] {
+                    cost_so_far[neighbor_index] = cost;
+                    came_from[neighbor_index] = index;
+                    open.push(OpenCell {
+                        estimate: cost + heuristic(neighbor),
+                        index: neighbor_index,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// an entry in the open set ordered so the heap pops the lowest estimate first
+struct OpenCell {
+    estimate: f32,
+    index: usize,
+}
+
+impl PartialEq for OpenCell {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OpenCell {}
+
+impl PartialOrd for OpenCell {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OpenCell {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .estimate
+            .total_cmp(&self.estimate)
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+/// identifies a path requested from the [`Pathfinder`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PathRequest(u64);
+
+/// emitted to the node that requested a path once it has been found
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathFound {
+    pub request: PathRequest,
+    /// the cells from start to goal or `None` if there is no path
+    pub path: Option<Vec<UVec2>>,
+}
+impl EventLabel for PathFound {}
+
+type FinishedPath = (NodeId, PathFound);
+
+/// finds paths on a background thread
+///
+/// added by the [`PathfindingPlugin`]
+#[derive(Default)]
+pub struct Pathfinder {
+    next_request: u64,
+    finished: Arc<Mutex<Vec<FinishedPath>>>,
+}
+
+impl Resource for Pathfinder {}
+
+impl Pathfinder {
+    /// find a path in the background. [`PathFound`] is emitted to `node` when it is done
+    pub fn request(
+        &mut self,
+        node: NodeId,
+        grid: Arc<PathGrid>,
+        start: UVec2,
+        goal: UVec2,
+        diagonal: DiagonalMovement,
+    ) -> PathRequest {
+        let request = PathRequest(self.next_request);
+        self.next_request += 1;
+
+        let finished = self.finished.clone();
+        rayon::spawn(move || {
+            let path = grid.find_path(start, goal, diagonal);
+            finished
+                .lock()
+                .unwrap()
+                .push((node, PathFound { request, path }));
+        });
+
+        request
+    }
+
+    fn take_finished(&self) -> Vec<FinishedPath> {
+        std::mem::take(&mut *self.finished.lock().unwrap())
+    }
+}
+
+/// adds the [`Pathfinder`] resource and delivers finished paths
+pub struct PathfindingPlugin;
+
+impl Plugin for PathfindingPlugin {
+    fn setup(&self, app: &mut App<Init>) {
+        app.context_mut().insert_resource(Pathfinder::default());
+    }
+
+    fn update(&self, app: &mut App<Running>) {
+        let finished = app.context().get_resource::<Pathfinder>().take_finished();
+
+        // the resource is unlocked so handlers can request the next path
+        let ctx = app.context();
+        for (node, found) in finished {
+            ctx.scene.emit_to(node, &found, ctx);
+        }
+    }
+}
+
+#[cfg(feature = "egui")]
+pub use ui::{PathGridDebug, path_grid_debug};
+
+#[cfg(feature = "egui")]
+mod ui {
+    use glam::UVec2;
+    use maple_egui::{egui, plugin::FromColor};
+    use maple_engine::color::Color;
+
+    use super::PathGrid;
+
+    /// how [`path_grid_debug`] draws a grid
+    #[derive(Clone, Debug)]
+    pub struct PathGridDebug {
+        /// width and height of a cell in points
+        pub cell_size: f32,
+        pub walkable_color: Color,
+        /// color of the most expensive cells. cheaper cells blend towards `walkable_color`
+        pub expensive_color: Color,
+        pub blocked_color: Color,
+        pub path_color: Color,
+    }
+
+    impl Default for PathGridDebug {
+        fn default() -> Self {
+            Self {
+                cell_size: 8.0,
+                walkable_color: Color::from_normalized(0.25, 0.25, 0.25, 0.9),
+                expensive_color: Color::from_normalized(0.45, 0.3, 0.1, 0.9),
+                blocked_color: Color::from_normalized(0.05, 0.05, 0.05, 0.9),
+                path_color: Color::from_normalized(0.2, 0.8, 0.3, 1.0),
+            }
+        }
+    }
+
+    /// draw a grid with its blocked and expensive cells and a path through it
+    ///
+    /// returns the cell under the cursor
+    pub fn path_grid_debug(
+        ui: &mut egui::Ui,
+        grid: &PathGrid,
+        path: &[UVec2],
+        style: &PathGridDebug,
+    ) -> Option<UVec2> {
+        let size = egui::vec2(grid.width() as f32, grid.height() as f32) * style.cell_size;
+        let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+
+        let max_cost = (0..grid.height())
+            .flat_map(|y| (0..grid.width()).map(move |x| UVec2::new(x, y)))
+            .filter_map(|cell| grid.cost(cell))
+            .fold(1.0, f32::max);
+
+        let cell_rect = |cell: UVec2| {
+            egui::Rect::from_min_size(
+                rect.min + egui::vec2(cell.x as f32, cell.y as f32) * style.cell_size,
+                egui::vec2(style.cell_size, style.cell_size),
+            )
+        };
+        let cell_center = |cell: UVec2| cell_rect(cell).center();
+
+        for y in 0..grid.height() {
+            for x in 0..grid.width() {
+                let cell = UVec2::new(x, y);
+                let color = match grid.cost(cell) {
+                    None => style.blocked_color,
+                    Some(cost) if max_cost > 1.0 => {
+                        let t = (cost - 1.0).max(0.0) / (max_cost - 1.0);
+                        style.walkable_color.lerp(&style.expensive_color, t)
+                    }
+                    Some(_) => style.walkable_color,
+                };
+                painter.rect_filled(
+                    cell_rect(cell).shrink(0.5),
+                    0.0,
+                    egui::Color32::from_color(color),
+                );
+            }
+        }
+
+        let stroke = egui::Stroke::new(
+            (style.cell_size * 0.25).max(1.0),
+            egui::Color32::from_color(style.path_color),
+        );
+        for step in path.windows(2) {
+            painter.line_segment([cell_center(step[0]), cell_center(step[1])], stroke);
+        }
+        if let (Some(start), Some(goal)) = (path.first(), path.last()) {
+            let radius = style.cell_size * 0.3;
+            painter.circle_filled(cell_center(*start), radius, stroke.color);
+            painter.circle_stroke(cell_center(*goal), radius, stroke);
+        }
+
+        let hovered = response.hover_pos()?;
+        let cell = ((hovered - rect.min) / style.cell_size).floor();
+        let cell = UVec2::new(cell.x as u32, cell.y as u32);
+        grid.contains(cell).then_some(cell)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(rows: &[&str]) -> PathGrid {
+        let height = rows.len() as u32;
+        let width = rows[0].len() as u32;
+        PathGrid::from_fn(width, height, |cell| {
+            match rows[cell.y as usize].as_bytes()[cell.x as usize] {
+                b'#' => None,
+                b'~' => Some(5.0),
+                _ => Some(1.0),
+            }
+        })
+    }
+
+    #[test]
+    fn test_path_goes_around_walls() {
+        let grid = grid(&[
+            "....", //
+            ".##.", //
+            "....", //
+        ]);
+
+        let path = grid
+            .find_path(UVec2::new(0, 1), UVec2::new(3, 1), DiagonalMovement::Never)
+            .unwrap();
+        assert_eq!(path.first(), Some(&UVec2::new(0, 1)));
+        assert_eq!(path.last(), Some(&UVec2::new(3, 1)));
+        assert_eq!(path.len(), 6);
+        assert!(path.iter().all(|cell| grid.is_walkable(*cell)));
+    }
+
+    #[test]
+    fn test_corner_cutting() {
+        let grid = grid(&[
+            ".#", //
+            "..", //
+        ]);
+        let start = UVec2::new(0, 0);
+        let goal = UVec2::new(1, 1);
+
+        let path = grid.find_path(start, goal, DiagonalMovement::Always);
+        assert_eq!(path, Some(vec![start, goal]));
+
+        let path = grid.find_path(start, goal, DiagonalMovement::NoCornerCutting);
+        assert_eq!(path.map(|path| path.len()), Some(3));
+    }
+
+    #[test]
+    fn test_expensive_cells_are_avoided() {
+        let grid = grid(&[
+            ".~.", //
+            "...", //
+        ]);
+
+        let path = grid
+            .find_path(UVec2::new(0, 0), UVec2::new(2, 0), DiagonalMovement::Never)
+            .unwrap();
+        assert!(!path.contains(&UVec2::new(1, 0)));
+    }
+
+    #[test]
+    fn test_unreachable_goal() {
+        let grid = grid(&[
+            ".#.", //
+            ".#.", //
+        ]);
+
+        let path = grid.find_path(UVec2::new(0, 0), UVec2::new(2, 0), DiagonalMovement::Always);
+        assert_eq!(path, None);
+        assert_eq!(
+            grid.find_path(UVec2::new(1, 0), UVec2::new(0, 0), DiagonalMovement::Never),
+            None
+        );
+    }
+}
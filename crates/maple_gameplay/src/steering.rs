@@ -0,0 +1,643 @@
+//! steering behaviors for moving agents and flocks
+//!
+//! a [`SteeringAgent`] node moves itself by adding up a list of weighted
+//! [`SteeringBehavior`]s every fixed update. behaviors can be combined freely, a flock of birds is
+//! [`SteeringBehavior::Separation`], [`SteeringBehavior::Alignment`] and
+//! [`SteeringBehavior::Cohesion`] while a guard that walks to a point without bumping into walls
+//! is [`SteeringBehavior::Arrive`] and [`SteeringBehavior::ObstacleAvoidance`].
+//!
+//! agents find each other through the [`SpatialHash`] resource so flocking stays cheap with many
+//! agents. obstacle avoidance casts rays into the physics world and needs the `physics` feature
+//! and one of the physics plugins.
+//!
+//! # Example
+//! ```no_run
+//! # use maple_engine::prelude::*;
+//! # use maple_gameplay::prelude::*;
+//! # use glam::Vec3;
+//! # let scene = Scene::default();
+//! scene.spawn(
+//!     SteeringAgent::builder()
+//!         .max_speed(4.0)
+//!         .behavior(
+//!             SteeringBehavior::Arrive {
+//!                 target: Vec3::new(10.0, 0.0, 0.0).into(),
+//!                 slowing_radius: 3.0,
+//!             },
+//!             1.0,
+//!         )
+//!         .behavior(SteeringBehavior::Separation { radius: 1.5 }, 2.0),
+//! );
+//! ```
+//!
+//! agents move their local position by how far they moved in the world so they should be root
+//! nodes or children of nodes that aren't rotated or scaled.
+
+use std::collections::HashMap;
+
+use glam::{Quat, Vec3};
+use maple_app::{App, Init, Plugin, Running};
+use maple_engine::{
+    Buildable, Builder, GameContext, Node,
+    nodes::node_builder::NodePrototype,
+    prelude::{Frame, NodeId, NodeTransform, SpatialHash},
+};
+
+/// something a behavior steers towards or away from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SteeringTarget {
+    /// a fixed point in the world
+    Point(Vec3),
+    /// the world position of a node. behaviors with a despawned target do nothing
+    Node(NodeId),
+}
+
+impl From<Vec3> for SteeringTarget {
+    fn from(value: Vec3) -> Self {
+        SteeringTarget::Point(value)
+    }
+}
+
+impl From<NodeId> for SteeringTarget {
+    fn from(value: NodeId) -> Self {
+        SteeringTarget::Node(value)
+    }
+}
+
+/// a rule that pushes a [`SteeringAgent`] in some direction
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SteeringBehavior {
+    /// move towards the target at full speed
+    Seek(SteeringTarget),
+    /// move away from the target while it is closer than `radius`
+    Flee { target: SteeringTarget, radius: f32 },
+    /// move towards the target and slow down inside `slowing_radius` to stop on it
+    Arrive {
+        target: SteeringTarget,
+        slowing_radius: f32,
+    },
+    /// keep away from other agents closer than `radius`
+    Separation { radius: f32 },
+    /// match the heading of other agents within `radius`
+    Alignment { radius: f32 },
+    /// move towards the center of other agents within `radius`
+    Cohesion { radius: f32 },
+    /// turn away from colliders up to `distance` ahead
+    ObstacleAvoidance { distance: f32 },
+}
+
+impl SteeringBehavior {
+    /// how far away other agents affect this behavior
+    fn neighbor_radius(&self) -> f32 {
+        match self {
+            SteeringBehavior::Separation { radius }
+            | SteeringBehavior::Alignment { radius }
+            | SteeringBehavior::Cohesion { radius } => *radius,
+            _ => 0.0,
+        }
+    }
+}
+
+/// another agent seen by a [`SteeringAgent`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Neighbor {
+    pub position: Vec3,
+    pub velocity: Vec3,
+}
+
+/// the force that turns `velocity` into moving towards `target` at `max_speed`
+pub fn seek(position: Vec3, velocity: Vec3, target: Vec3, max_speed: f32) -> Vec3 {
+    (target - position).normalize_or_zero() * max_speed - velocity
+}
+
+/// the force that turns `velocity` into moving away from `target` at `max_speed`
+pub fn flee(position: Vec3, velocity: Vec3, target: Vec3, max_speed: f32) -> Vec3 {
+    (position - target).normalize_or_zero() * max_speed - velocity
+}
+
+/// like [`seek`] but slows down linearly inside `slowing_radius` so the agent stops on `target`
+pub fn arrive(
+    position: Vec3,
+    velocity: Vec3,
+    target: Vec3,
+    max_speed: f32,
+    slowing_radius: f32,
+) -> Vec3 {
+    let offset = target - position;
+    let distance = offset.length();
+    let speed = match distance < slowing_radius {
+        true => max_speed * distance / slowing_radius,
+        false => max_speed,
+    };
+    offset.normalize_or_zero() * speed - velocity
+}
+
+/// pushes away from neighbors closer than `radius`, harder the closer they are
+pub fn separation(position: Vec3, neighbors: &[Neighbor], radius: f32) -> Vec3 {
+    neighbors
+        .iter()
+        .filter_map(|neighbor| {
+            let away = position - neighbor.position;
+            let distance = away.length();
+            (distance > 0.0 && distance < radius)
+                .then(|| away / distance * (1.0 - distance / radius))
+        })
+        .sum()
+}
+
+/// the force that turns `velocity` into the average velocity of the neighbors
+pub fn alignment(velocity: Vec3, neighbors: &[Neighbor]) -> Vec3 {
+    if neighbors.is_empty() {
+        return Vec3::ZERO;
+    }
+    let average: Vec3 = neighbors.iter().map(|neighbor| neighbor.velocity).sum();
+    average / neighbors.len() as f32 - velocity
+}
+
+/// seeks the average position of the neighbors
+pub fn cohesion(position: Vec3, velocity: Vec3, neighbors: &[Neighbor], max_speed: f32) -> Vec3 {
+    if neighbors.is_empty() {
+        return Vec3::ZERO;
+    }
+    let center: Vec3 = neighbors.iter().map(|neighbor| neighbor.position).sum();
+    seek(
+        position,
+        velocity,
+        center / neighbors.len() as f32,
+        max_speed,
+    )
+}
+
+/// steers away from obstacles found with `raycast` along three feelers facing `heading`
+///
+/// `raycast` takes an origin, a direction and a max distance and returns the distance to a hit.
+/// the feeler that hits closest pushes the agent back and to the side that is more open
+pub fn obstacle_avoidance(
+    position: Vec3,
+    heading: Vec3,
+    distance: f32,
+    max_speed: f32,
+    raycast: impl Fn(Vec3, Vec3, f32) -> Option<f32>,
+) -> Vec3 {
+    let Some(forward) = heading.try_normalize() else {
+        return Vec3::ZERO;
+    };
+    let side = forward.cross(Vec3::Y).try_normalize().unwrap_or(Vec3::X);
+
+    let feelers = [
+        (forward, distance),
+        (
+            Quat::from_axis_angle(Vec3::Y, 0.5) * forward,
+            distance * 0.7,
+        ),
+        (
+            Quat::from_axis_angle(Vec3::Y, -0.5) * forward,
+            distance * 0.7,
+        ),
+    ];
+
+    let mut force = Vec3::ZERO;
+    let mut openness = [1.0; 3];
+    for (index, (direction, length)) in feelers.iter().enumerate() {
+        if let Some(hit) = raycast(position, *direction, *length) {
+            let closeness = 1.0 - (hit / length).clamp(0.0, 1.0);
+            openness[index] -= closeness;
+            force -= *direction * closeness * max_speed;
+        }
+    }
+
+    if openness[0] < 1.0 && openness[1] == openness[2] {
+        // straight at a wall with both sides equally open, pick a side instead of stopping
+        force += side * (1.0 - openness[0]) * max_speed;
+    }
+    force
+}
+
+/// an agent that moves itself with [`SteeringBehavior`]s
+pub struct SteeringAgent {
+    pub transform: NodeTransform,
+
+    pub velocity: Vec3,
+    pub max_speed: f32,
+    /// how fast the agent can change its velocity in units per second squared
+    pub max_force: f32,
+    /// the behaviors and their weights
+    pub behaviors: Vec<(SteeringBehavior, f32)>,
+    /// agents only flock with agents in the same group
+    pub group: u32,
+    /// how far the agent reaches out from its position. rays start outside of this so the agent's
+    /// own collider isn't hit
+    pub radius: f32,
+    /// move on the XZ plane only
+    pub planar: bool,
+    /// rotate the agent to face where it is moving
+    pub face_velocity: bool,
+}
+
+impl Node for SteeringAgent {
+    fn get_transform(&mut self) -> &mut NodeTransform {
+        &mut self.transform
+    }
+}
+
+/// what an agent knows about the world while steering
+pub struct SteeringSurroundings<'a> {
+    /// the world position of the agent
+    pub position: Vec3,
+    /// other agents in the same group close enough to matter
+    pub neighbors: &'a [Neighbor],
+    /// finds the world position of a target
+    pub resolve: &'a dyn Fn(&SteeringTarget) -> Option<Vec3>,
+    /// returns the distance to the first collider along a ray if there is one
+    pub raycast: Option<&'a dyn Fn(Vec3, Vec3, f32) -> Option<f32>>,
+}
+
+impl SteeringAgent {
+    /// add a behavior with a weight
+    pub fn add_behavior(&mut self, behavior: SteeringBehavior, weight: f32) {
+        self.behaviors.push((behavior, weight));
+    }
+
+    /// remove every behavior
+    pub fn clear_behaviors(&mut self) {
+        self.behaviors.clear();
+    }
+
+    /// the farthest any behavior looks for other agents
+    pub fn neighbor_radius(&self) -> f32 {
+        self.behaviors
+            .iter()
+            .map(|(behavior, _)| behavior.neighbor_radius())
+            .fold(0.0, f32::max)
+    }
+
+    /// the weighted sum of every behavior limited to [`Self::max_force`]
+    pub fn steering_force(&self, surroundings: &SteeringSurroundings) -> Vec3 {
+        let position = surroundings.position;
+        let velocity = self.velocity;
+        let max_speed = self.max_speed;
+
+        let within = |radius: f32| -> Vec<Neighbor> {
+            surroundings
+                .neighbors
+                .iter()
+                .filter(|neighbor| neighbor.position.distance_squared(position) < radius * radius)
+                .copied()
+                .collect()
+        };
+
+        let mut force = Vec3::ZERO;
+        for (behavior, weight) in &self.behaviors {
+            let behavior_force = match behavior {
+                SteeringBehavior::Seek(target) => (surroundings.resolve)(target)
+                    .map(|target| seek(position, velocity, target, max_speed)),
+                SteeringBehavior::Flee { target, radius } => (surroundings.resolve)(target)
+                    .filter(|target| target.distance(position) < *radius)
+                    .map(|target| flee(position, velocity, target, max_speed)),
+                SteeringBehavior::Arrive {
+                    target,
+                    slowing_radius,
+                } => (surroundings.resolve)(target)
+                    .map(|target| arrive(position, velocity, target, max_speed, *slowing_radius)),
+                SteeringBehavior::Separation { radius } => {
+                    Some(separation(position, &within(*radius), *radius) * max_speed)
+                }
+                SteeringBehavior::Alignment { radius } => {
+                    Some(alignment(velocity, &within(*radius)))
+                }
+                SteeringBehavior::Cohesion { radius } => {
+                    Some(cohesion(position, velocity, &within(*radius), max_speed))
+                }
+                SteeringBehavior::ObstacleAvoidance { distance } => {
+                    surroundings.raycast.map(|raycast| {
+                        let heading = velocity.try_normalize().unwrap_or(self.forward());
+                        let origin = position + heading * self.radius;
+                        obstacle_avoidance(origin, heading, *distance, max_speed, raycast)
+                    })
+                }
+            };
+
+            force += behavior_force.unwrap_or(Vec3::ZERO) * *weight;
+        }
+
+        if self.planar {
+            force.y = 0.0;
+        }
+        force.clamp_length_max(self.max_force)
+    }
+
+    /// apply a steering force for `dt` seconds and return how far the agent moves
+    pub fn integrate(&mut self, force: Vec3, dt: f32) -> Vec3 {
+        self.velocity = (self.velocity + force * dt).clamp_length_max(self.max_speed);
+        if self.planar {
+            self.velocity.y = 0.0;
+        }
+
+        if self.face_velocity
+            && let Some(direction) = self.velocity.try_normalize()
+        {
+            let rotation = match self.planar {
+                true => Quat::from_rotation_y((-direction.x).atan2(-direction.z)),
+                false => Quat::from_rotation_arc(Vec3::NEG_Z, direction),
+            };
+            self.transform.set_rotation(rotation);
+        }
+
+        let offset = self.velocity * dt;
+        self.transform.translate(offset);
+        offset
+    }
+
+    /// the direction the agent faces
+    pub fn forward(&self) -> Vec3 {
+        self.transform.get_forward_vector()
+    }
+}
+
+impl Buildable for SteeringAgent {
+    type Builder = SteeringAgentBuilder;
+
+    fn builder() -> Self::Builder {
+        SteeringAgentBuilder {
+            prototype: NodePrototype::default(),
+            velocity: Vec3::ZERO,
+            max_speed: 5.0,
+            max_force: 10.0,
+            behaviors: Vec::new(),
+            group: 0,
+            radius: 0.5,
+            planar: true,
+            face_velocity: true,
+        }
+    }
+}
+
+pub struct SteeringAgentBuilder {
+    prototype: NodePrototype,
+    velocity: Vec3,
+    max_speed: f32,
+    max_force: f32,
+    behaviors: Vec<(SteeringBehavior, f32)>,
+    group: u32,
+    radius: f32,
+    planar: bool,
+    face_velocity: bool,
+}
+
+impl Builder for SteeringAgentBuilder {
+    type Node = SteeringAgent;
+
+    fn prototype(&mut self) -> &mut NodePrototype {
+        &mut self.prototype
+    }
+
+    fn build(self) -> Self::Node {
+        SteeringAgent {
+            transform: self.prototype.transform,
+            velocity: self.velocity,
+            max_speed: self.max_speed,
+            max_force: self.max_force,
+            behaviors: self.behaviors,
+            group: self.group,
+            radius: self.radius,
+            planar: self.planar,
+            face_velocity: self.face_velocity,
+        }
+    }
+}
+
+impl SteeringAgentBuilder {
+    /// add a behavior with a weight
+    pub fn behavior(mut self, behavior: SteeringBehavior, weight: f32) -> Self {
+        self.behaviors.push((behavior, weight));
+        self
+    }
+
+    /// the starting velocity
+    pub fn velocity(mut self, velocity: impl Into<Vec3>) -> Self {
+        self.velocity = velocity.into();
+        self
+    }
+
+    /// the fastest the agent moves. Default: `5.0`
+    pub fn max_speed(mut self, speed: f32) -> Self {
+        self.max_speed = speed;
+        self
+    }
+
+    /// how fast the agent changes its velocity. Default: `10.0`
+    pub fn max_force(mut self, force: f32) -> Self {
+        self.max_force = force;
+        self
+    }
+
+    /// agents only flock with agents in the same group. Default: `0`
+    pub fn group(mut self, group: u32) -> Self {
+        self.group = group;
+        self
+    }
+
+    /// how far the agent reaches out from its position. Default: `0.5`
+    pub fn radius(mut self, radius: f32) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    /// move on the XZ plane only. Default: `true`
+    pub fn planar(mut self, planar: bool) -> Self {
+        self.planar = planar;
+        self
+    }
+
+    /// rotate to face where the agent moves. Default: `true`
+    pub fn face_velocity(mut self, face_velocity: bool) -> Self {
+        self.face_velocity = face_velocity;
+        self
+    }
+}
+
+/// moves every enabled [`SteeringAgent`] in fixed update
+pub struct SteeringPlugin;
+
+impl Plugin for SteeringPlugin {
+    fn setup(&self, app: &mut App<Init>) {
+        if !app.context().has_resource::<SpatialHash>() {
+            app.context_mut().insert_resource(SpatialHash::default());
+        }
+    }
+
+    fn fixed_update(&self, app: &mut App<Running>) {
+        let dt = app.context().get_resource::<Frame>().fixed_delta_time();
+        update_agents(app.context(), dt);
+    }
+}
+
+struct AgentState {
+    position: Vec3,
+    velocity: Vec3,
+    group: u32,
+}
+
+/// steers and moves every enabled agent by `dt` seconds
+pub fn update_agents(ctx: &GameContext, dt: f32) {
+    let agents: Vec<_> = ctx
+        .scene
+        .collect::<SteeringAgent>()
+        .into_iter()
+        .filter(|agent| ctx.scene.is_enabled(agent.id()))
+        .collect();
+
+    // every agent steers from where the others were at the start of the step
+    let states: HashMap<NodeId, AgentState> = agents
+        .iter()
+        .map(|agent| {
+            let node = agent.read();
+            let state = AgentState {
+                position: node.transform.world_space().position(),
+                velocity: node.velocity,
+                group: node.group,
+            };
+            (agent.id(), state)
+        })
+        .collect();
+
+    {
+        let mut spatial_hash = ctx.get_resource_mut::<SpatialHash>();
+        for (id, state) in &states {
+            spatial_hash.insert(*id, state.position);
+        }
+    }
+
+    let resolve = |target: &SteeringTarget| -> Option<Vec3> {
+        match target {
+            SteeringTarget::Point(point) => Some(*point),
+            SteeringTarget::Node(id) => match states.get(id) {
+                Some(state) => Some(state.position),
+                None => ctx
+                    .scene
+                    .walk_from(*id)
+                    .next()
+                    .map(|entry| entry.world.position()),
+            },
+        }
+    };
+
+    #[cfg(feature = "physics")]
+    let physics = ctx
+        .has_resource::<maple_physics::resource::Physics>()
+        .then(|| ctx.get_resource::<maple_physics::resource::Physics>());
+    #[cfg(feature = "physics")]
+    let cast = |origin: Vec3, direction: Vec3, distance: f32| {
+        physics
+            .as_ref()
+            .and_then(|physics| physics.cast_ray_from_inside(origin, direction, distance))
+    };
+    #[cfg(feature = "physics")]
+    let raycast: Option<&dyn Fn(Vec3, Vec3, f32) -> Option<f32>> = match physics.is_some() {
+        true => Some(&cast),
+        false => None,
+    };
+    #[cfg(not(feature = "physics"))]
+    let raycast: Option<&dyn Fn(Vec3, Vec3, f32) -> Option<f32>> = None;
+
+    // forces are worked out for every agent before any of them moves
+    let forces: Vec<Vec3> = {
+        let spatial_hash = ctx.get_resource::<SpatialHash>();
+        agents
+            .iter()
+            .map(|agent| {
+                let id = agent.id();
+                let state = &states[&id];
+                let node = agent.read();
+
+                let neighbors: Vec<Neighbor> = spatial_hash
+                    .nearby(state.position, node.neighbor_radius())
+                    .into_iter()
+                    .filter(|other| *other != id)
+                    .filter_map(|other| states.get(&other))
+                    .filter(|other| other.group == state.group)
+                    .map(|other| Neighbor {
+                        position: other.position,
+                        velocity: other.velocity,
+                    })
+                    .collect();
+
+                node.steering_force(&SteeringSurroundings {
+                    position: state.position,
+                    neighbors: &neighbors,
+                    resolve: &resolve,
+                    raycast,
+                })
+            })
+            .collect()
+    };
+
+    for (agent, force) in agents.iter().zip(forces) {
+        agent.write().integrate(force, dt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arrive_slows_down_near_target() {
+        let far = arrive(Vec3::ZERO, Vec3::ZERO, Vec3::new(10.0, 0.0, 0.0), 4.0, 2.0);
+        let near = arrive(Vec3::ZERO, Vec3::ZERO, Vec3::new(1.0, 0.0, 0.0), 4.0, 2.0);
+        assert_eq!(far, Vec3::new(4.0, 0.0, 0.0));
+        assert_eq!(near, Vec3::new(2.0, 0.0, 0.0));
+        assert_eq!(
+            arrive(Vec3::ZERO, Vec3::ZERO, Vec3::ZERO, 4.0, 2.0),
+            Vec3::ZERO
+        );
+    }
+
+    #[test]
+    fn test_agents_separate() {
+        let ctx = GameContext::new();
+        let agent = || {
+            SteeringAgent::builder()
+                .behavior(SteeringBehavior::Separation { radius: 2.0 }, 1.0)
+                .face_velocity(false)
+        };
+        let left = ctx
+            .scene
+            .spawn(agent().position(Vec3::new(-0.5, 0.0, 0.0)))
+            .id();
+        let right = ctx
+            .scene
+            .spawn(agent().position(Vec3::new(0.5, 0.0, 0.0)))
+            .id();
+        ctx.sync_world_transform();
+
+        for _ in 0..10 {
+            update_agents(&ctx, 0.1);
+            ctx.sync_world_transform();
+        }
+
+        let x = |id| {
+            ctx.scene
+                .get::<SteeringAgent>(id)
+                .unwrap()
+                .read()
+                .transform
+                .position
+                .x
+        };
+        assert!(x(left) < -0.5);
+        assert!(x(right) > 0.5);
+    }
+
+    #[test]
+    fn test_avoidance_turns_away_from_walls() {
+        // a wall 2 units ahead along -z
+        let raycast = |origin: Vec3, direction: Vec3, distance: f32| {
+            let t = (origin.z + 2.0) / -direction.z;
+            (direction.z < 0.0 && t <= distance).then_some(t)
+        };
+        let force = obstacle_avoidance(Vec3::ZERO, Vec3::NEG_Z, 4.0, 1.0, raycast);
+        assert!(force.z > 0.0);
+        assert!(force.x.abs() > 0.0);
+    }
+}
@@ -1,17 +1,27 @@
 //! [`rapier3d`] implemented for maple engine
 //!
 //! provides nodes such as [`nodes::RigidBody3D`] and [`nodes::Collider3D`] for adding physics
-//! behaviors to scene nodes
+//! behaviors to scene nodes and [`nodes::CharacterController3D`] for players that are moved by
+//! gameplay but still collide with the level
+//!
+//! sprites and tilemaps can use [`nodes::RigidBody2D`] and [`nodes::Collider2D`] which move on
+//! the XY plane and only sync the x and y of the node. with the `tilemap` feature
+//! [`tilemap::spawn_tile_object_colliders`] builds them from the object layers of a tilemap
 
 pub mod nodes;
 pub mod plugin;
 pub mod resource;
+#[cfg(feature = "tilemap")]
+pub mod tilemap;
 
 pub use rapier3d::prelude::{ActiveEvents, Group, InteractionGroups, InteractionTestMode};
 
+/// the version of this crate
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
 pub mod prelude {
     pub use crate::ActiveEvents;
     pub use crate::nodes::*;
-    pub use crate::plugin::Physics3D;
+    pub use crate::plugin::{Physics2D, Physics3D};
     pub use crate::resource::{ColliderEnter, ColliderExit, Physics};
 }
@@ -1,6 +1,235 @@
-use rapier3d::{control::KinematicCharacterController, prelude::RigidBodyHandle};
+use glam::Vec3;
+use maple_engine::{
+    Buildable, Builder, Node, nodes::node_builder::NodePrototype, prelude::NodeTransform,
+};
+use rapier3d::{
+    control::{CharacterAutostep, CharacterLength, KinematicCharacterController},
+    prelude::{ColliderHandle, RigidBodyHandle},
+};
 
-pub struct CharacterController {
-    body: Option<RigidBodyHandle>,
-    controller: rapier3d::control::KinematicCharacterController,
+use crate::nodes::{CapsuleAxis, ColliderShape};
+
+pub struct CharacterConfiguration {
+    pub shape: ColliderShape,
+    pub gravity_scale: f32,
+    /// steepest slope in radians the character can walk up
+    pub max_slope_climb: f32,
+    /// slopes steeper than this in radians slide the character down
+    pub min_slope_slide: f32,
+    /// how far down the character snaps to the ground when walking off a step
+    pub snap_to_ground: Option<f32>,
+    /// the highest step the character walks onto without jumping
+    pub step_height: Option<f32>,
+}
+
+/// a kinematic character that is moved by gameplay instead of forces
+///
+/// the character slides along and stands on the colliders in the scene instead of passing through
+/// them, but isn't pushed around by rigid bodies. set [`CharacterController3D::velocity`] from an
+/// update handler and the physics step moves it as far as it can go.
+///
+/// # Example
+/// ```rust, ignore
+/// scene
+///     .spawn(CharacterController3D::builder().capsule(0.5, 0.3).build())
+///     .on::<Update>(|ctx| {
+///         let input = ctx.get_resource::<Input>();
+///         let mut character = ctx.node_mut();
+///         character.velocity = match input.keys.contains(&KeyCode::KeyD) {
+///             true => Vec3::X * 5.0,
+///             false => Vec3::ZERO,
+///         };
+///         if input.key_just_pressed.contains(&KeyCode::Space) && character.is_grounded() {
+///             character.jump(6.0);
+///         }
+///     });
+/// ```
+pub struct CharacterController3D {
+    pub transform: NodeTransform,
+
+    /// the velocity the character tries to move with, gravity is added on top of it
+    pub velocity: Vec3,
+
+    fall_speed: f32,
+    grounded: bool,
+
+    pub(crate) handle: Option<RigidBodyHandle>,
+    pub(crate) collider: Option<ColliderHandle>,
+
+    pub(crate) config: CharacterConfiguration,
+}
+
+impl Node for CharacterController3D {
+    fn get_transform(&mut self) -> &mut NodeTransform {
+        &mut self.transform
+    }
+}
+
+impl CharacterController3D {
+    pub fn get_handle(&self) -> Option<RigidBodyHandle> {
+        self.handle
+    }
+
+    /// true if the character was standing on something after the last physics step
+    pub fn is_grounded(&self) -> bool {
+        self.grounded
+    }
+
+    /// the speed the character is falling at from gravity, negative while moving up after a jump
+    pub fn fall_speed(&self) -> f32 {
+        self.fall_speed
+    }
+
+    /// launch the character upwards at `speed`, gravity brings it back down
+    pub fn jump(&mut self, speed: f32) {
+        self.fall_speed = -speed;
+        self.grounded = false;
+    }
+
+    pub(crate) fn rapier_controller(&self) -> KinematicCharacterController {
+        KinematicCharacterController {
+            max_slope_climb_angle: self.config.max_slope_climb,
+            min_slope_slide_angle: self.config.min_slope_slide,
+            snap_to_ground: self.config.snap_to_ground.map(CharacterLength::Absolute),
+            autostep: self.config.step_height.map(|height| CharacterAutostep {
+                max_height: CharacterLength::Absolute(height),
+                min_width: CharacterLength::Relative(0.5),
+                include_dynamic_bodies: false,
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// the movement wanted this step, made of the velocity and the fall speed after gravity
+    pub(crate) fn desired_translation(&mut self, gravity: Vec3, dt: f32) -> Vec3 {
+        let down = gravity.normalize_or(Vec3::NEG_Y);
+
+        // don't build up speed while standing, a small push keeps the character on the ground
+        if self.grounded && self.fall_speed > 0.0 {
+            self.fall_speed = 0.0;
+        }
+        self.fall_speed += gravity.length() * self.config.gravity_scale * dt;
+
+        (self.velocity + down * self.fall_speed) * dt
+    }
+
+    pub(crate) fn finish_move(&mut self, translation: Vec3, grounded: bool) {
+        self.transform.position += translation;
+        self.grounded = grounded;
+        if grounded && self.fall_speed > 0.0 {
+            self.fall_speed = 0.0;
+        }
+    }
+}
+
+impl Buildable for CharacterController3D {
+    type Builder = CharacterController3DBuilder;
+
+    fn builder() -> Self::Builder {
+        CharacterController3DBuilder {
+            proto: NodePrototype::default(),
+            shape: ColliderShape::Capsule {
+                half_height: 0.5,
+                radius: 0.3,
+                axis: CapsuleAxis::Y,
+            },
+            velocity: Vec3::ZERO,
+            gravity_scale: 1.0,
+            max_slope_climb: 45f32.to_radians(),
+            min_slope_slide: 45f32.to_radians(),
+            snap_to_ground: Some(0.2),
+            step_height: None,
+        }
+    }
+}
+
+pub struct CharacterController3DBuilder {
+    proto: NodePrototype,
+    shape: ColliderShape,
+    velocity: Vec3,
+    gravity_scale: f32,
+    max_slope_climb: f32,
+    min_slope_slide: f32,
+    snap_to_ground: Option<f32>,
+    step_height: Option<f32>,
+}
+
+impl Builder for CharacterController3DBuilder {
+    type Node = CharacterController3D;
+
+    fn prototype(&mut self) -> &mut NodePrototype {
+        &mut self.proto
+    }
+
+    fn build(self) -> Self::Node {
+        CharacterController3D {
+            transform: self.proto.transform,
+            velocity: self.velocity,
+            fall_speed: 0.0,
+            grounded: false,
+            handle: None,
+            collider: None,
+            config: CharacterConfiguration {
+                shape: self.shape,
+                gravity_scale: self.gravity_scale,
+                max_slope_climb: self.max_slope_climb,
+                min_slope_slide: self.min_slope_slide,
+                snap_to_ground: self.snap_to_ground,
+                step_height: self.step_height,
+            },
+        }
+    }
+}
+
+impl CharacterController3DBuilder {
+    /// Set the shape the character collides with (a Y capsule by default)
+    pub fn shape(mut self, shape: ColliderShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    /// Collide as an upright capsule
+    pub fn capsule(self, half_height: f32, radius: f32) -> Self {
+        self.shape(ColliderShape::Capsule {
+            half_height,
+            radius,
+            axis: CapsuleAxis::Y,
+        })
+    }
+
+    /// Set the initial velocity
+    pub fn velocity(mut self, velocity: impl Into<Vec3>) -> Self {
+        self.velocity = velocity.into();
+        self
+    }
+
+    /// Set the gravity scale (1.0 = normal gravity, 0.0 = no gravity)
+    pub fn gravity_scale(mut self, scale: f32) -> Self {
+        self.gravity_scale = scale;
+        self
+    }
+
+    /// Set the steepest slope in degrees the character can walk up
+    pub fn max_slope(mut self, degrees: f32) -> Self {
+        self.max_slope_climb = degrees.to_radians();
+        self
+    }
+
+    /// Set the slope in degrees past which the character slides down
+    pub fn slide_slope(mut self, degrees: f32) -> Self {
+        self.min_slope_slide = degrees.to_radians();
+        self
+    }
+
+    /// Snap down to the ground within `distance` when walking off ledges, `None` to fall instead
+    pub fn snap_to_ground(mut self, distance: Option<f32>) -> Self {
+        self.snap_to_ground = distance;
+        self
+    }
+
+    /// Walk onto steps up to `height` without jumping
+    pub fn step_height(mut self, height: f32) -> Self {
+        self.step_height = Some(height);
+        self
+    }
 }
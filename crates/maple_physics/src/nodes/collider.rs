@@ -1,8 +1,13 @@
-use glam::Vec3;
+use glam::{Vec2, Vec3};
 use maple_engine::{
-    Buildable, Builder, Node, nodes::node_builder::NodePrototype, prelude::NodeTransform,
+    Buildable, Builder, Node,
+    nodes::node_builder::NodePrototype,
+    prelude::{NodeTransform, TerrainParams},
+};
+use rapier3d::{
+    parry::utils::Array2,
+    prelude::{ActiveEvents, ColliderBuilder, ColliderHandle, Group, InteractionGroups},
 };
-use rapier3d::prelude::{ActiveEvents, ColliderBuilder, ColliderHandle, Group, InteractionGroups};
 
 /// Collider shape types
 #[derive(Clone)]
@@ -23,6 +28,15 @@ pub enum ColliderShape {
     Cone { half_height: f32, radius: f32 },
     /// A triangle
     Triangle { a: Vec3, b: Vec3, c: Vec3 },
+    /// A grid of heights centered on the collider, `columns` samples along X for each of the
+    /// `rows` along Z, spread over `size`. it needs at least 2x2 samples and `columns * rows`
+    /// heights, a grid that doesn't is logged and replaced with the default ball
+    HeightField {
+        heights: Vec<f32>,
+        columns: usize,
+        rows: usize,
+        size: Vec2,
+    },
     /// Custom shape (placeholder for more complex shapes)
     Custom,
 }
@@ -33,6 +47,70 @@ impl Default for ColliderShape {
     }
 }
 
+impl ColliderShape {
+    /// a rapier collider with this shape and default properties
+    pub(crate) fn to_rapier(&self) -> ColliderBuilder {
+        match self {
+            ColliderShape::Ball { radius } => ColliderBuilder::ball(*radius),
+            ColliderShape::Cuboid { hx, hy, hz } => ColliderBuilder::cuboid(*hx, *hy, *hz),
+            ColliderShape::Capsule {
+                half_height,
+                radius,
+                axis,
+            } => match axis {
+                CapsuleAxis::X => ColliderBuilder::capsule_x(*half_height, *radius),
+                CapsuleAxis::Y => ColliderBuilder::capsule_y(*half_height, *radius),
+                CapsuleAxis::Z => ColliderBuilder::capsule_z(*half_height, *radius),
+            },
+            ColliderShape::Cylinder {
+                half_height,
+                radius,
+            } => ColliderBuilder::cylinder(*half_height, *radius),
+            ColliderShape::Cone {
+                half_height,
+                radius,
+            } => ColliderBuilder::cone(*half_height, *radius),
+            ColliderShape::Triangle { a, b, c } => {
+                ColliderBuilder::triangle((*a).into(), (*b).into(), (*c).into())
+            }
+            ColliderShape::HeightField {
+                heights,
+                columns,
+                rows,
+                size,
+            } if *columns < 2 || *rows < 2 || heights.len() < columns * rows => {
+                log::error!(
+                    "height field collider needs at least 2x2 samples and {} heights for \
+                     {columns}x{rows}, it has {}",
+                    columns * rows,
+                    heights.len()
+                );
+                ColliderBuilder::ball(0.5)
+            }
+            ColliderShape::HeightField {
+                heights,
+                columns,
+                rows,
+                size,
+            } => {
+                // rapier wants the heights column major with rows along Z
+                let mut data = Vec::with_capacity(columns * rows);
+                for column in 0..*columns {
+                    for row in 0..*rows {
+                        data.push(heights[row * columns + column]);
+                    }
+                }
+                let heights = Array2::new(*rows, *columns, data);
+                ColliderBuilder::heightfield(heights, Vec3::new(size.x, 1.0, size.y))
+            }
+            ColliderShape::Custom => {
+                // Default to a small ball for custom shapes
+                ColliderBuilder::ball(0.5)
+            }
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub enum CapsuleAxis {
     X,
@@ -40,8 +118,8 @@ pub enum CapsuleAxis {
     Z,
 }
 
-pub struct ColliderConfiguration {
-    pub shape: ColliderShape,
+pub struct ColliderConfiguration<S = ColliderShape> {
+    pub shape: S,
     pub sensor: bool,
     pub friction: f32,
     pub restitution: f32,
@@ -54,6 +132,32 @@ pub struct ColliderConfiguration {
     pub active_events: ActiveEvents,
 }
 
+impl<S> ColliderConfiguration<S> {
+    /// apply every property except the shape to a collider
+    pub(crate) fn apply(&self, mut builder: ColliderBuilder) -> ColliderBuilder {
+        builder = builder
+            .sensor(self.sensor)
+            .friction(self.friction)
+            .restitution(self.restitution)
+            .collision_groups(self.collision_groups)
+            .solver_groups(self.solver_groups)
+            .enabled(self.enabled)
+            .active_events(self.active_events);
+
+        if let Some(mass) = self.mass {
+            builder = builder.mass(mass);
+        } else {
+            builder = builder.density(self.density);
+        }
+
+        if self.contact_skin > 0.0 {
+            builder = builder.contact_skin(self.contact_skin);
+        }
+
+        builder
+    }
+}
+
 /// Colliders are shapes used for collision testing
 ///
 /// Colliders with a parent [`crate::nodes::RigidBody3D`] node will be attached and used as the
@@ -98,59 +202,13 @@ impl Collider3D {
     }
 
     pub(crate) fn get_rapier_collidor(&self) -> ColliderBuilder {
-        let mut builder = match &self.config.shape {
-            ColliderShape::Ball { radius } => ColliderBuilder::ball(*radius),
-            ColliderShape::Cuboid { hx, hy, hz } => ColliderBuilder::cuboid(*hx, *hy, *hz),
-            ColliderShape::Capsule {
-                half_height,
-                radius,
-                axis,
-            } => match axis {
-                CapsuleAxis::X => ColliderBuilder::capsule_x(*half_height, *radius),
-                CapsuleAxis::Y => ColliderBuilder::capsule_y(*half_height, *radius),
-                CapsuleAxis::Z => ColliderBuilder::capsule_z(*half_height, *radius),
-            },
-            ColliderShape::Cylinder {
-                half_height,
-                radius,
-            } => ColliderBuilder::cylinder(*half_height, *radius),
-            ColliderShape::Cone {
-                half_height,
-                radius,
-            } => ColliderBuilder::cone(*half_height, *radius),
-            ColliderShape::Triangle { a, b, c } => {
-                ColliderBuilder::triangle((*a).into(), (*b).into(), (*c).into())
-            }
-            ColliderShape::Custom => {
-                // Default to a small ball for custom shapes
-                ColliderBuilder::ball(0.5)
-            }
-        };
+        let builder = self.config.shape.to_rapier();
 
         // Note: position and rotation are relative to parent rigid body
         // They are applied as offsets, not absolute transforms
 
         // Apply all properties
-        builder = builder
-            .sensor(self.config.sensor)
-            .friction(self.config.friction)
-            .restitution(self.config.restitution)
-            .collision_groups(self.config.collision_groups)
-            .solver_groups(self.config.solver_groups)
-            .enabled(self.config.enabled)
-            .active_events(self.config.active_events);
-
-        if let Some(mass) = self.config.mass {
-            builder = builder.mass(mass);
-        } else {
-            builder = builder.density(self.config.density);
-        }
-
-        if self.config.contact_skin > 0.0 {
-            builder = builder.contact_skin(self.config.contact_skin);
-        }
-
-        builder
+        self.config.apply(builder)
     }
 
     pub fn get_handle(&self) -> Option<ColliderHandle> {
@@ -301,6 +359,25 @@ impl Collider3DBuilder {
         })
     }
 
+    /// Create a height field collider matching a noise terrain
+    ///
+    /// the terrain is sampled `resolution` times along each side of a `size` area around
+    /// `center` and the collider is placed there, so it should not have a parent. finer
+    /// resolutions follow the terrain closer but cost more to build and collide with
+    pub fn terrain(terrain: &TerrainParams, center: Vec2, size: Vec2, resolution: usize) -> Self {
+        let resolution = resolution.max(2);
+        let heights = terrain.sample_grid(center, size, (resolution, resolution));
+
+        Collider3D::builder()
+            .shape(ColliderShape::HeightField {
+                heights,
+                columns: resolution,
+                rows: resolution,
+                size,
+            })
+            .position(Vec3::new(center.x, 0.0, center.y))
+    }
+
     /// Set the collider shape
     pub fn shape(mut self, shape: ColliderShape) -> Self {
         self.shape = shape;
@@ -378,3 +455,29 @@ impl Collider3DBuilder {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_height_fields_fall_back_to_a_ball() {
+        let shape = ColliderShape::HeightField {
+            heights: vec![0.0; 5],
+            columns: 3,
+            rows: 2,
+            size: Vec2::ONE,
+        };
+        let collider = shape.to_rapier().build();
+        assert!(collider.shape().as_ball().is_some());
+
+        let shape = ColliderShape::HeightField {
+            heights: vec![0.0; 6],
+            columns: 3,
+            rows: 2,
+            size: Vec2::ONE,
+        };
+        let collider = shape.to_rapier().build();
+        assert!(collider.shape().as_heightfield().is_some());
+    }
+}
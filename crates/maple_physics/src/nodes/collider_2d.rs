@@ -0,0 +1,273 @@
+use glam::{Vec2, Vec3};
+use maple_engine::{
+    Buildable, Builder, Node, nodes::node_builder::NodePrototype, prelude::NodeTransform,
+};
+use rapier3d::prelude::{ActiveEvents, ColliderBuilder, ColliderHandle, Group, InteractionGroups};
+
+use crate::nodes::{collider::ColliderConfiguration, rigid_body_2d::plane_angle};
+
+/// how far 2D shapes reach in front of and behind the XY plane
+///
+/// every 2D collider is at z = 0 in the physics world so this only matters when 2D and 3D
+/// colliders touch
+const HALF_DEPTH: f32 = 0.5;
+
+/// 2D collider shape types
+#[derive(Clone)]
+pub enum ColliderShape2D {
+    /// A circle with the given radius
+    Circle { radius: f32 },
+    /// A rectangle with half-extents
+    Rectangle { hx: f32, hy: f32 },
+    /// A capsule standing along the Y axis
+    Capsule { half_height: f32, radius: f32 },
+    /// The convex hull of a set of points
+    ConvexPolygon { points: Vec<Vec2> },
+}
+
+impl Default for ColliderShape2D {
+    fn default() -> Self {
+        ColliderShape2D::Circle { radius: 0.5 }
+    }
+}
+
+/// Colliders are shapes used for collision testing on the XY plane
+///
+/// Colliders with a parent [`crate::nodes::RigidBody2D`] node will be attached at their local
+/// x and y offset and used as the collider shape for that rigid body.
+///
+/// 2D colliders emit the same [`crate::resource::ColliderEnter`] and
+/// [`crate::resource::ColliderExit`] events as [`crate::nodes::Collider3D`]
+pub struct Collider2D {
+    pub transform: NodeTransform,
+
+    pub(crate) handle: Option<ColliderHandle>,
+
+    pub(crate) config: ColliderConfiguration<ColliderShape2D>,
+}
+
+impl Node for Collider2D {
+    fn get_transform(&mut self) -> &mut NodeTransform {
+        &mut self.transform
+    }
+}
+
+impl Collider2D {
+    pub fn new(shape: ColliderShape2D) -> Self {
+        Self::builder().shape(shape).build()
+    }
+
+    /// the position of the collider on the plane
+    pub(crate) fn plane_translation(&self) -> Vec3 {
+        self.transform.position.truncate().extend(0.0)
+    }
+
+    /// the rotation of the collider around the Z axis as a scaled axis
+    pub(crate) fn plane_rotation(&self) -> Vec3 {
+        Vec3::Z * plane_angle(self.transform.rotation)
+    }
+
+    pub(crate) fn get_rapier_collidor(&self) -> ColliderBuilder {
+        let builder = match &self.config.shape {
+            ColliderShape2D::Circle { radius } => ColliderBuilder::ball(*radius),
+            ColliderShape2D::Rectangle { hx, hy } => ColliderBuilder::cuboid(*hx, *hy, HALF_DEPTH),
+            ColliderShape2D::Capsule {
+                half_height,
+                radius,
+            } => ColliderBuilder::capsule_y(*half_height, *radius),
+            ColliderShape2D::ConvexPolygon { points } => {
+                let points: Vec<Vec3> = points
+                    .iter()
+                    .flat_map(|point| [point.extend(-HALF_DEPTH), point.extend(HALF_DEPTH)])
+                    .collect();
+
+                ColliderBuilder::convex_hull(&points).unwrap_or_else(|| {
+                    log::warn!("convex polygon collider needs at least 3 points not on a line");
+                    ColliderBuilder::ball(0.5)
+                })
+            }
+        };
+
+        self.config.apply(builder)
+    }
+
+    pub fn get_handle(&self) -> Option<ColliderHandle> {
+        self.handle
+    }
+
+    pub fn set_handle(&mut self, handle: ColliderHandle) {
+        self.handle = Some(handle);
+    }
+}
+
+impl Default for Collider2D {
+    fn default() -> Self {
+        Self::new(ColliderShape2D::default())
+    }
+}
+
+impl Buildable for Collider2D {
+    type Builder = Collider2DBuilder;
+
+    fn builder() -> Self::Builder {
+        Collider2DBuilder {
+            proto: NodePrototype::default(),
+            shape: ColliderShape2D::default(),
+            sensor: false,
+            friction: 0.5,
+            restitution: 0.0,
+            density: 1.0,
+            mass: None,
+            collision_groups: InteractionGroups::all(),
+            solver_groups: InteractionGroups::all(),
+            contact_skin: 0.0,
+            enabled: true,
+        }
+    }
+}
+
+pub struct Collider2DBuilder {
+    proto: NodePrototype,
+    shape: ColliderShape2D,
+    sensor: bool,
+    friction: f32,
+    restitution: f32,
+    density: f32,
+    mass: Option<f32>,
+    collision_groups: InteractionGroups,
+    solver_groups: InteractionGroups,
+    contact_skin: f32,
+    enabled: bool,
+}
+
+impl Builder for Collider2DBuilder {
+    type Node = Collider2D;
+
+    fn prototype(&mut self) -> &mut NodePrototype {
+        &mut self.proto
+    }
+
+    fn build(self) -> Self::Node {
+        Collider2D {
+            transform: self.proto.transform,
+            handle: None,
+
+            config: ColliderConfiguration {
+                shape: self.shape,
+                sensor: self.sensor,
+                friction: self.friction,
+                restitution: self.restitution,
+                density: self.density,
+                mass: self.mass,
+                collision_groups: self.collision_groups,
+                solver_groups: self.solver_groups,
+                contact_skin: self.contact_skin,
+                enabled: self.enabled,
+                active_events: ActiveEvents::COLLISION_EVENTS,
+            },
+        }
+    }
+}
+
+impl Collider2DBuilder {
+    /// Create a circle collider
+    pub fn circle(radius: f32) -> Self {
+        Collider2D::builder().shape(ColliderShape2D::Circle { radius })
+    }
+
+    /// Create a rectangle collider
+    pub fn rectangle(hx: f32, hy: f32) -> Self {
+        Collider2D::builder().shape(ColliderShape2D::Rectangle { hx, hy })
+    }
+
+    /// Create a square collider (all sides equal)
+    pub fn square(half_extent: f32) -> Self {
+        Self::rectangle(half_extent, half_extent)
+    }
+
+    /// Create a capsule along the Y axis
+    pub fn capsule(half_height: f32, radius: f32) -> Self {
+        Collider2D::builder().shape(ColliderShape2D::Capsule {
+            half_height,
+            radius,
+        })
+    }
+
+    /// Create a collider from the convex hull of some points
+    pub fn convex_polygon(points: impl IntoIterator<Item = impl Into<Vec2>>) -> Self {
+        Collider2D::builder().shape(ColliderShape2D::ConvexPolygon {
+            points: points.into_iter().map(Into::into).collect(),
+        })
+    }
+
+    /// Set the collider shape
+    pub fn shape(mut self, shape: ColliderShape2D) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    /// Make this collider a sensor/trigger (no physics response, only overlap detection)
+    pub fn sensor(mut self, sensor: bool) -> Self {
+        self.sensor = sensor;
+        self
+    }
+
+    /// Set the friction coefficient (0.0 = no friction, higher = more friction)
+    pub fn friction(mut self, friction: f32) -> Self {
+        self.friction = friction;
+        self
+    }
+
+    /// Set the restitution/bounciness (0.0 = no bounce, 1.0 = perfectly bouncy)
+    pub fn restitution(mut self, restitution: f32) -> Self {
+        self.restitution = restitution;
+        self
+    }
+
+    /// Set the density (used to calculate mass if mass is not set directly)
+    pub fn density(mut self, density: f32) -> Self {
+        self.density = density;
+        self.mass = None; // Clear explicit mass
+        self
+    }
+
+    /// Set the mass directly (overrides density-based calculation)
+    pub fn mass(mut self, mass: f32) -> Self {
+        self.mass = Some(mass);
+        self
+    }
+
+    /// Set collision groups (what this collider collides with)
+    pub fn collision_groups(mut self, groups: InteractionGroups) -> Self {
+        self.collision_groups = groups;
+        self
+    }
+
+    /// Set solver groups (what this collider interacts with in physics solver)
+    pub fn solver_groups(mut self, groups: InteractionGroups) -> Self {
+        self.solver_groups = groups;
+        self
+    }
+
+    /// Set collision memberships and filters manually
+    pub fn collision_membership_filter(mut self, memberships: Group, filter: Group) -> Self {
+        self.collision_groups = InteractionGroups::new(
+            memberships,
+            filter,
+            rapier3d::prelude::InteractionTestMode::And,
+        );
+        self
+    }
+
+    /// Set contact skin thickness (for performance optimization)
+    pub fn contact_skin(mut self, skin: f32) -> Self {
+        self.contact_skin = skin;
+        self
+    }
+
+    /// Set whether the collider is enabled
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+}
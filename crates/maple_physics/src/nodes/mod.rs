@@ -1,6 +1,11 @@
-mod character_controller;
-mod collider;
-mod rigid_body;
+pub(crate) mod character_controller;
+pub(crate) mod collider;
+mod collider_2d;
+pub(crate) mod rigid_body;
+pub(crate) mod rigid_body_2d;
 
+pub use character_controller::{CharacterController3D, CharacterController3DBuilder};
 pub use collider::{CapsuleAxis, Collider3D, Collider3DBuilder, ColliderShape};
+pub use collider_2d::{Collider2D, Collider2DBuilder, ColliderShape2D};
 pub use rigid_body::{RigidBody3D, RigidBody3DBuilder};
+pub use rigid_body_2d::{RigidBody2D, RigidBody2DBuilder};
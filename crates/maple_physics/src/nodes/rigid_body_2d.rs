@@ -0,0 +1,276 @@
+use glam::{EulerRot, Quat, Vec2, Vec3};
+use maple_engine::{
+    Buildable, Builder, Node, nodes::node_builder::NodePrototype, prelude::NodeTransform,
+};
+use rapier3d::prelude::{LockedAxes, RigidBodyBuilder, RigidBodyHandle, RigidBodyType};
+
+use crate::nodes::rigid_body::RigidBodyConfiguration;
+
+/// the axes a 2D body can never move along so it stays on the XY plane
+pub(crate) const PLANE_LOCKED_AXES: LockedAxes = LockedAxes::TRANSLATION_LOCKED_Z
+    .union(LockedAxes::ROTATION_LOCKED_X)
+    .union(LockedAxes::ROTATION_LOCKED_Y);
+
+/// the rotation around the Z axis of a rotation
+pub(crate) fn plane_angle(rotation: Quat) -> f32 {
+    rotation.to_euler(EulerRot::ZYX).0
+}
+
+/// a rigid body that moves on the XY plane
+///
+/// 2D bodies only read and write the x and y of their position and the rotation around Z so
+/// the z of the node can be used for draw order. they derive their shape from child
+/// [`crate::nodes::Collider2D`] nodes.
+///
+/// 2D nodes are simulated in the same world as the 3D nodes with their depth locked. they
+/// collide with each other as if they were all at z = 0.
+pub struct RigidBody2D {
+    pub transform: NodeTransform,
+
+    pub velocity: Vec2,
+    /// counter clockwise rotation speed in radians per second
+    pub angular_velocity: f32,
+
+    pub(crate) handle: Option<RigidBodyHandle>,
+
+    // Configuration
+    pub(crate) config: RigidBodyConfiguration,
+}
+
+impl Node for RigidBody2D {
+    fn get_transform(&mut self) -> &mut NodeTransform {
+        &mut self.transform
+    }
+}
+
+impl RigidBody2D {
+    pub fn get_handle(&self) -> Option<RigidBodyHandle> {
+        self.handle
+    }
+
+    /// the rotation of the body around the Z axis in radians
+    pub fn angle(&self) -> f32 {
+        plane_angle(self.transform.rotation)
+    }
+
+    pub fn to_rapier_body(&self) -> RigidBodyBuilder {
+        let mut builder = match self.config.body_type {
+            RigidBodyType::Dynamic => RigidBodyBuilder::dynamic(),
+            RigidBodyType::Fixed => RigidBodyBuilder::fixed(),
+            RigidBodyType::KinematicPositionBased => RigidBodyBuilder::kinematic_position_based(),
+            RigidBodyType::KinematicVelocityBased => RigidBodyBuilder::kinematic_velocity_based(),
+        };
+
+        builder = builder
+            .translation(self.transform.position.truncate().extend(0.0))
+            .rotation(Vec3::Z * self.angle());
+
+        builder = builder
+            .gravity_scale(self.config.gravity_scale)
+            .linear_damping(self.config.linear_damping)
+            .angular_damping(self.config.angular_damping)
+            .linvel(self.velocity.extend(0.0))
+            .angvel(Vec3::Z * self.angular_velocity)
+            .locked_axes(self.config.locked_axes)
+            .ccd_enabled(self.config.ccd_enabled)
+            .can_sleep(self.config.can_sleep)
+            .sleeping(self.config.sleeping)
+            .dominance_group(self.config.dominance_group)
+            .enabled(self.config.enabled);
+
+        if self.config.additional_mass > 0.0 {
+            builder = builder.additional_mass(self.config.additional_mass);
+        }
+
+        builder
+    }
+}
+
+impl Buildable for RigidBody2D {
+    type Builder = RigidBody2DBuilder;
+
+    fn builder() -> Self::Builder {
+        RigidBody2DBuilder {
+            proto: NodePrototype::default(),
+            body_type: RigidBodyType::Dynamic,
+            gravity_scale: 1.0,
+            linear_damping: 0.0,
+            angular_damping: 0.0,
+            linear_velocity: Vec2::ZERO,
+            angular_velocity: 0.0,
+            locked_axes: LockedAxes::empty(),
+            ccd_enabled: false,
+            can_sleep: true,
+            sleeping: false,
+            dominance_group: 0,
+            additional_mass: 0.0,
+            enabled: true,
+        }
+    }
+}
+
+pub struct RigidBody2DBuilder {
+    proto: NodePrototype,
+    body_type: RigidBodyType,
+    gravity_scale: f32,
+    linear_damping: f32,
+    angular_damping: f32,
+    linear_velocity: Vec2,
+    angular_velocity: f32,
+    locked_axes: LockedAxes,
+    ccd_enabled: bool,
+    can_sleep: bool,
+    sleeping: bool,
+    dominance_group: i8,
+    additional_mass: f32,
+    enabled: bool,
+}
+
+impl Builder for RigidBody2DBuilder {
+    type Node = RigidBody2D;
+
+    fn prototype(&mut self) -> &mut NodePrototype {
+        &mut self.proto
+    }
+
+    fn build(self) -> Self::Node {
+        RigidBody2D {
+            transform: self.proto.transform,
+            handle: None,
+
+            velocity: self.linear_velocity,
+            angular_velocity: self.angular_velocity,
+            config: RigidBodyConfiguration {
+                body_type: self.body_type,
+                gravity_scale: self.gravity_scale,
+                linear_damping: self.linear_damping,
+                angular_damping: self.angular_damping,
+                locked_axes: self.locked_axes | PLANE_LOCKED_AXES,
+                ccd_enabled: self.ccd_enabled,
+                can_sleep: self.can_sleep,
+                sleeping: self.sleeping,
+                dominance_group: self.dominance_group,
+                additional_mass: self.additional_mass,
+                enabled: self.enabled,
+            },
+        }
+    }
+}
+
+impl RigidBody2DBuilder {
+    /// Create a dynamic rigid body (affected by forces and gravity)
+    pub fn dynamic() -> Self {
+        RigidBody2D::builder().body_type(RigidBodyType::Dynamic)
+    }
+
+    /// Create a static rigid body (immovable, infinite mass)
+    pub fn fixed() -> Self {
+        RigidBody2D::builder().body_type(RigidBodyType::Fixed)
+    }
+
+    /// Create a kinematic body controlled by position
+    pub fn kinematic_position_based() -> Self {
+        RigidBody2D::builder().body_type(RigidBodyType::KinematicPositionBased)
+    }
+
+    /// Create a kinematic body controlled by velocity
+    pub fn kinematic_velocity_based() -> Self {
+        RigidBody2D::builder().body_type(RigidBodyType::KinematicVelocityBased)
+    }
+
+    /// Set the rigid body type
+    pub fn body_type(mut self, body_type: RigidBodyType) -> Self {
+        self.body_type = body_type;
+        self
+    }
+
+    /// Set the gravity scale (1.0 = normal gravity, 0.0 = no gravity)
+    pub fn gravity_scale(mut self, scale: f32) -> Self {
+        self.gravity_scale = scale;
+        self
+    }
+
+    /// Set linear damping (resistance to linear motion)
+    pub fn linear_damping(mut self, damping: f32) -> Self {
+        self.linear_damping = damping;
+        self
+    }
+
+    /// Set angular damping (resistance to rotation)
+    pub fn angular_damping(mut self, damping: f32) -> Self {
+        self.angular_damping = damping;
+        self
+    }
+
+    /// Set initial linear velocity
+    pub fn linear_velocity(mut self, velocity: impl Into<Vec2>) -> Self {
+        self.linear_velocity = velocity.into();
+        self
+    }
+
+    /// Set initial angular velocity in radians per second
+    pub fn angular_velocity(mut self, velocity: f32) -> Self {
+        self.angular_velocity = velocity;
+        self
+    }
+
+    /// Lock translation on both axes
+    pub fn lock_translations(mut self) -> Self {
+        self.locked_axes |= LockedAxes::TRANSLATION_LOCKED_X | LockedAxes::TRANSLATION_LOCKED_Y;
+        self
+    }
+
+    /// Lock translation on X axis
+    pub fn lock_translation_x(mut self) -> Self {
+        self.locked_axes |= LockedAxes::TRANSLATION_LOCKED_X;
+        self
+    }
+
+    /// Lock translation on Y axis
+    pub fn lock_translation_y(mut self) -> Self {
+        self.locked_axes |= LockedAxes::TRANSLATION_LOCKED_Y;
+        self
+    }
+
+    /// Lock rotation so the body never spins, useful for characters
+    pub fn lock_rotation(mut self) -> Self {
+        self.locked_axes |= LockedAxes::ROTATION_LOCKED_Z;
+        self
+    }
+
+    /// Enable Continuous Collision Detection
+    pub fn ccd_enabled(mut self, enabled: bool) -> Self {
+        self.ccd_enabled = enabled;
+        self
+    }
+
+    /// Allow the rigid body to sleep when inactive
+    pub fn can_sleep(mut self, can_sleep: bool) -> Self {
+        self.can_sleep = can_sleep;
+        self
+    }
+
+    /// Start the rigid body in a sleeping state
+    pub fn sleeping(mut self, sleeping: bool) -> Self {
+        self.sleeping = sleeping;
+        self
+    }
+
+    /// Set dominance group (higher values dominate lower values in constraints)
+    pub fn dominance_group(mut self, group: i8) -> Self {
+        self.dominance_group = group;
+        self
+    }
+
+    /// Add additional mass to the rigid body
+    pub fn additional_mass(mut self, mass: f32) -> Self {
+        self.additional_mass = mass;
+        self
+    }
+
+    /// Set whether the rigid body is enabled
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+}
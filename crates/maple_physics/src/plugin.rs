@@ -1,29 +1,122 @@
+use std::any::TypeId;
+
 use glam::Vec3;
 use maple_app::{App, Plugin, Running};
+use maple_engine::{GameContext, context::Resource};
 
 use crate::resource::Physics;
 
+/// simulates the 2D and 3D physics nodes in the scene
 pub struct Physics3D;
 
 impl Plugin for Physics3D {
     fn ready(&self, app: &mut App<Running>) {
-        let physics = Physics::new(Vec3 {
-            x: 0.0,
-            y: -9.81,
-            z: 0.0,
-        });
+        insert_physics::<Self>(app.context_mut());
+    }
+
+    fn fixed_update(&self, app: &mut App<Running>) {
+        step_physics::<Self>(app.context_mut());
+    }
+}
 
-        app.context_mut().insert_resource(physics);
+/// simulates the 2D physics nodes in the scene
+///
+/// 2D and 3D nodes share one [`Physics`] world so this and [`Physics3D`] do the same work. adding
+/// both still steps the world once per fixed update.
+pub struct Physics2D;
+
+impl Plugin for Physics2D {
+    fn ready(&self, app: &mut App<Running>) {
+        insert_physics::<Self>(app.context_mut());
     }
 
     fn fixed_update(&self, app: &mut App<Running>) {
-        let ctx = app.context_mut();
-
-        let mut physics = ctx.get_resource_mut::<Physics>();
-        physics.initialize_bodies(&ctx.scene);
-        physics.sync_to_rapier(&ctx.scene);
-        physics.step();
-        physics.sync_to_maple(&ctx.scene);
-        physics.dispatch_events(ctx);
+        step_physics::<Self>(app.context_mut());
+    }
+}
+
+/// the plugin that steps the shared world, the first physics plugin that was ready
+struct PhysicsStepper(TypeId);
+
+impl Resource for PhysicsStepper {}
+
+fn insert_physics<P: 'static>(ctx: &mut GameContext) {
+    if !ctx.has_resource::<PhysicsStepper>() {
+        ctx.insert_resource(PhysicsStepper(TypeId::of::<P>()));
+    }
+
+    if ctx.has_resource::<Physics>() {
+        return;
+    }
+
+    let physics = Physics::new(Vec3 {
+        x: 0.0,
+        y: -9.81,
+        z: 0.0,
+    });
+
+    ctx.insert_resource(physics);
+}
+
+fn step_physics<P: 'static>(ctx: &mut GameContext) {
+    if ctx.get_resource::<PhysicsStepper>().0 != TypeId::of::<P>() {
+        return;
+    }
+
+    let mut physics = ctx.get_resource_mut::<Physics>();
+    physics.initialize_bodies(&ctx.scene);
+    physics.sync_to_rapier(&ctx.scene);
+    physics.move_characters(&ctx.scene);
+    physics.step();
+    physics.sync_to_maple(&ctx.scene);
+    physics.dispatch_events(ctx);
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+    use maple_engine::{Buildable, Builder};
+
+    use super::*;
+    use crate::nodes::{Collider2DBuilder, RigidBody2D};
+
+    /// a 2D ball above the origin stepped for a few fixed updates by `plugins`
+    fn fall(plugins: &[fn(&mut GameContext)]) -> (Vec3, Vec2) {
+        let mut ctx = GameContext::new();
+        insert_physics::<Physics2D>(&mut ctx);
+        insert_physics::<Physics3D>(&mut ctx);
+
+        let body = ctx
+            .scene
+            .spawn(
+                RigidBody2D::builder()
+                    .position(Vec3::new(0.0, 10.0, 3.0))
+                    .build(),
+            )
+            .id();
+        ctx.scene
+            .spawn_as_child(Collider2DBuilder::circle(0.5).build(), body);
+
+        for _ in 0..3 {
+            for step in plugins {
+                step(&mut ctx);
+            }
+        }
+
+        let body = ctx.scene.get::<RigidBody2D>(body).unwrap();
+        let body = body.read();
+        (body.transform.position, body.velocity)
+    }
+
+    #[test]
+    fn test_2d_bodies_are_stepped_once_with_both_plugins() {
+        let (position, velocity) = fall(&[step_physics::<Physics2D>]);
+        assert!(position.y < 10.0 && velocity.y < 0.0);
+        // 2D bodies only sync x and y so the z of the node is kept for draw order
+        assert_eq!(position.z, 3.0);
+        assert_eq!(position.x, 0.0);
+
+        let both = fall(&[step_physics::<Physics2D>, step_physics::<Physics3D>]);
+        assert_eq!(both, (position, velocity));
     }
 }
@@ -3,7 +3,7 @@ use std::{
     sync::{Arc, Mutex},
 };
 
-use glam::{Quat, Vec3};
+use glam::{Quat, Vec2, Vec3};
 use log::error;
 use maple_engine::{
     GameContext, Node, Scene,
@@ -11,17 +11,25 @@ use maple_engine::{
     scene::NodeId,
 };
 use rapier3d::prelude::{
-    ActiveCollisionTypes, CCDSolver, Collider, ColliderBuilder, ColliderHandle, ColliderSet,
-    CollisionEvent, DefaultBroadPhase, EventHandler, ImpulseJointSet, IntegrationParameters,
-    IslandManager, MultibodyJointSet, NarrowPhase, PhysicsPipeline, RigidBodyBuilder,
-    RigidBodyHandle, RigidBodySet,
+    ActiveCollisionTypes, ActiveEvents, CCDSolver, Collider, ColliderBuilder, ColliderHandle,
+    ColliderSet, CollisionEvent, DefaultBroadPhase, EventHandler, ImpulseJointSet,
+    IntegrationParameters, IslandManager, MultibodyJointSet, NarrowPhase, PhysicsPipeline, Pose,
+    QueryFilter, Ray, RigidBody, RigidBodyBuilder, RigidBodyHandle, RigidBodySet,
 };
 
-use crate::nodes::{Collider3D, RigidBody3D};
+use crate::nodes::{
+    CharacterController3D, Collider2D, Collider3D, RigidBody2D, RigidBody3D,
+    rigid_body::RigidBodyConfiguration, rigid_body_2d::plane_angle,
+};
 
 /// event is triggered when 2 colliders begin to intersect eachother
 pub struct ColliderEnter {
     pub other: NodeId,
+    /// speed the two colliders were moving towards eachother at when they touched.
+    ///
+    /// this is the length of the relative linear velocity of their rigid bodies and is 0 for
+    /// colliders without a parent rigid body on both sides
+    pub impact_velocity: f32,
 }
 impl EventLabel for ColliderEnter {}
 
@@ -141,6 +149,67 @@ impl Physics {
 
             node.handle = Some(self.add_free_collidor(handle))
         });
+
+        scene.for_each_with_id(&mut |node_id, node: &mut RigidBody2D| {
+            if node.handle.is_some() {
+                return;
+            }
+
+            let handle = self.add_rigid_body(node.to_rapier_body());
+            node.handle = Some(handle);
+
+            // 2D colliders keep their offset on the plane from the body
+            for child_id in scene.children_ids(node_id) {
+                if let Some(child) = scene.get::<Collider2D>(child_id) {
+                    let mut child_node = child.write();
+                    let collider = child_node
+                        .get_rapier_collidor()
+                        .translation(child_node.plane_translation())
+                        .rotation(child_node.plane_rotation());
+                    child_node.handle = Some(self.add_collidor_with_parent(&handle, collider));
+                }
+            }
+        });
+
+        scene.for_each(&mut |node: &mut Collider2D| {
+            if node.handle.is_some() {
+                return;
+            }
+
+            let collider = node
+                .get_rapier_collidor()
+                .translation(node.plane_translation())
+                .rotation(node.plane_rotation())
+                .active_collision_types(
+                    ActiveCollisionTypes::default() | ActiveCollisionTypes::FIXED_FIXED,
+                )
+                .build();
+
+            node.handle = Some(self.add_free_collidor(collider))
+        });
+
+        scene.for_each(&mut |node: &mut CharacterController3D| {
+            if node.handle.is_some() {
+                return;
+            }
+
+            let body = self.add_rigid_body(
+                RigidBodyBuilder::kinematic_position_based()
+                    .translation(node.transform.position)
+                    .rotation(node.transform.rotation.to_scaled_axis()),
+            );
+
+            // sensors only report kinematic bodies that ask for it
+            let collider = node
+                .config
+                .shape
+                .to_rapier()
+                .active_collision_types(ActiveCollisionTypes::all())
+                .active_events(ActiveEvents::COLLISION_EVENTS);
+
+            node.handle = Some(body);
+            node.collider = Some(self.add_collidor_with_parent(&body, collider));
+        });
     }
 
     pub fn sync_to_rapier(&mut self, scene: &Scene) {
@@ -151,21 +220,7 @@ impl Physics {
             };
 
             let body = &mut self.rigid_body_set[handle];
-
-            body.set_gravity_scale(node.config.gravity_scale, !node.config.sleeping);
-            body.set_linear_damping(node.config.linear_damping);
-            body.set_angular_damping(node.config.angular_damping);
-            body.set_locked_axes(node.config.locked_axes, !node.config.sleeping);
-            body.enable_ccd(node.config.ccd_enabled);
-            if node.config.sleeping {
-                body.sleep()
-            } else {
-                body.wake_up(false)
-            }
-            body.set_dominance_group(node.config.dominance_group);
-            body.set_additional_mass(node.config.additional_mass, !node.config.sleeping);
-            body.set_enabled(node.config.enabled);
-            body.set_body_type(node.config.body_type, !node.config.sleeping);
+            apply_body_config(body, &node.config);
 
             // Check if position changed (only update if different to avoid resetting velocity)
             let rapier_pos: Vec3 = body.translation();
@@ -212,6 +267,93 @@ impl Physics {
                 }
             }
         });
+
+        scene.for_each_ref(&mut |node: &RigidBody2D| {
+            let Some(handle) = node.handle else {
+                error!("node not added");
+                return;
+            };
+
+            let body = &mut self.rigid_body_set[handle];
+            apply_body_config(body, &node.config);
+
+            // only x and y are simulated so the z of the node is left alone
+            let position = node.transform.position.truncate();
+            if (position - body.translation().truncate()).length_squared() > 1e-6 {
+                body.set_translation(position.extend(0.0), true);
+            }
+
+            let angle = node.angle();
+            if (angle - plane_angle(*body.rotation())).abs() > 1e-4 {
+                body.set_rotation(Quat::from_rotation_z(angle), true);
+            }
+
+            body.set_linvel(node.velocity.extend(0.0), true);
+            body.set_angvel(Vec3::Z * node.angular_velocity, true);
+        });
+
+        scene.for_each_ref(&mut |node: &Collider2D| {
+            let Some(handle) = node.handle else {
+                return;
+            };
+
+            if self.collider_set[handle].parent().is_some() {
+                return;
+            }
+
+            if let Some(collider) = self.collider_set.get_mut(handle) {
+                let position = node.plane_translation();
+                if (position - collider.translation()).length_squared() > 1e-6 {
+                    collider.set_translation(position);
+                }
+
+                let angle = plane_angle(node.transform.rotation);
+                if (angle - plane_angle(collider.rotation())).abs() > 1e-4 {
+                    collider.set_rotation(Quat::from_rotation_z(angle));
+                }
+            }
+        });
+    }
+
+    /// move every [`CharacterController3D`] as far as it can go with its velocity and gravity
+    ///
+    /// this runs before [`Physics::step`] so the kinematic bodies are moved by the step
+    pub fn move_characters(&mut self, scene: &Scene) {
+        let dt = self.integration_parameters.dt;
+
+        scene.for_each(&mut |node: &mut CharacterController3D| {
+            let (Some(handle), Some(collider)) = (node.handle, node.collider) else {
+                return;
+            };
+
+            // the node was moved by gameplay since the last step
+            let body = &mut self.rigid_body_set[handle];
+            if (node.transform.position - body.translation()).length_squared() > 1e-6 {
+                body.set_translation(node.transform.position, true);
+            }
+            body.set_rotation(node.transform.rotation, true);
+
+            let desired = node.desired_translation(self.gravity, dt);
+            let query_pipeline = self.broad_phase.as_query_pipeline(
+                self.narrow_phase.query_dispatcher(),
+                &self.rigid_body_set,
+                &self.collider_set,
+                QueryFilter::default()
+                    .exclude_sensors()
+                    .exclude_rigid_body(handle),
+            );
+            let movement = node.rapier_controller().move_shape(
+                dt,
+                &query_pipeline,
+                self.collider_set[collider].shape(),
+                &Pose::from_parts(node.transform.position, node.transform.rotation),
+                desired,
+                |_| {},
+            );
+
+            node.finish_move(movement.translation, movement.grounded);
+            self.rigid_body_set[handle].set_next_kinematic_translation(node.transform.position);
+        });
     }
 
     /// step in the physics sim should be every 1/60 of a second
@@ -247,6 +389,87 @@ impl Physics {
             node.velocity = body.linvel();
             node.angular_velocity = body.angvel();
         });
+
+        scene.for_each(&mut |node: &mut RigidBody2D| {
+            let Some(handle) = node.handle else {
+                log::error!("not all nodes added");
+                return;
+            };
+
+            let body = &self.rigid_body_set[handle];
+
+            let translation = body.translation();
+            node.transform.position.x = translation.x;
+            node.transform.position.y = translation.y;
+            node.transform.rotation = Quat::from_rotation_z(plane_angle(*body.rotation()));
+            node.velocity = body.linvel().truncate();
+            node.angular_velocity = body.angvel().z;
+        });
+    }
+
+    /// cast a ray into the physics world and return the distance to the first collider it hits.
+    ///
+    /// sensors are ignored. `direction` does not need to be normalized, the returned distance is
+    /// always in world units.
+    pub fn cast_ray(&self, origin: Vec3, direction: Vec3, max_distance: f32) -> Option<f32> {
+        let direction = direction.try_normalize()?;
+
+        let query_pipeline = self.broad_phase.as_query_pipeline(
+            self.narrow_phase.query_dispatcher(),
+            &self.rigid_body_set,
+            &self.collider_set,
+            QueryFilter::default().exclude_sensors(),
+        );
+
+        query_pipeline
+            .cast_ray(&Ray::new(origin, direction), max_distance, true)
+            .map(|(_, distance)| distance)
+    }
+
+    /// like [`Physics::cast_ray`] but colliders `origin` is inside of are skipped, for rays cast
+    /// from a node with its own collider such as a sound source or an agent so it doesn't hit
+    /// itself at a distance of 0
+    pub fn cast_ray_from_inside(
+        &self,
+        origin: Vec3,
+        direction: Vec3,
+        max_distance: f32,
+    ) -> Option<f32> {
+        let direction = direction.try_normalize()?;
+
+        let outside =
+            |_, collider: &Collider| !collider.shape().contains_point(collider.position(), origin);
+        let query_pipeline = self.broad_phase.as_query_pipeline(
+            self.narrow_phase.query_dispatcher(),
+            &self.rigid_body_set,
+            &self.collider_set,
+            QueryFilter::default().exclude_sensors().predicate(&outside),
+        );
+
+        query_pipeline
+            .cast_ray(&Ray::new(origin, direction), max_distance, true)
+            .map(|(_, distance)| distance)
+    }
+
+    /// cast a ray along the XY plane and return the distance to the first collider it hits.
+    ///
+    /// same as [`Physics::cast_ray`] for 2D games
+    pub fn cast_ray_2d(&self, origin: Vec2, direction: Vec2, max_distance: f32) -> Option<f32> {
+        self.cast_ray(origin.extend(0.0), direction.extend(0.0), max_distance)
+    }
+
+    /// relative speed between the rigid bodies of two colliders
+    fn impact_velocity(&self, h1: ColliderHandle, h2: ColliderHandle) -> f32 {
+        let velocity = |handle: ColliderHandle| -> Vec3 {
+            self.collider_set
+                .get(handle)
+                .and_then(|collider| collider.parent())
+                .and_then(|body| self.rigid_body_set.get(body))
+                .map(|body| body.linvel())
+                .unwrap_or(Vec3::ZERO)
+        };
+
+        (velocity(h1) - velocity(h2)).length()
     }
 
     pub fn dispatch_events(&mut self, ctx: &GameContext) {
@@ -270,6 +493,16 @@ impl Physics {
                     map.insert(handle, id);
                 }
             });
+            scene.for_each_with_id(&mut |id, node: &mut Collider2D| {
+                if let Some(handle) = node.handle {
+                    map.insert(handle, id);
+                }
+            });
+            scene.for_each_with_id(&mut |id, node: &mut CharacterController3D| {
+                if let Some(handle) = node.collider {
+                    map.insert(handle, id);
+                }
+            });
             map
         };
 
@@ -284,8 +517,23 @@ impl Physics {
 
             if let (Some(id1), Some(id2)) = (node1, node2) {
                 if is_enter {
-                    scene.emit_to(id1, &ColliderEnter { other: id2 }, ctx);
-                    scene.emit_to(id2, &ColliderEnter { other: id1 }, ctx);
+                    let impact_velocity = self.impact_velocity(h1, h2);
+                    scene.emit_to(
+                        id1,
+                        &ColliderEnter {
+                            other: id2,
+                            impact_velocity,
+                        },
+                        ctx,
+                    );
+                    scene.emit_to(
+                        id2,
+                        &ColliderEnter {
+                            other: id1,
+                            impact_velocity,
+                        },
+                        ctx,
+                    );
                 } else {
                     scene.emit_to(id1, &ColliderExit { other: id2 }, ctx);
                     scene.emit_to(id2, &ColliderExit { other: id1 }, ctx);
@@ -295,6 +543,24 @@ impl Physics {
     }
 }
 
+/// apply the settings of a rigid body node to its rapier body
+fn apply_body_config(body: &mut RigidBody, config: &RigidBodyConfiguration) {
+    body.set_gravity_scale(config.gravity_scale, !config.sleeping);
+    body.set_linear_damping(config.linear_damping);
+    body.set_angular_damping(config.angular_damping);
+    body.set_locked_axes(config.locked_axes, !config.sleeping);
+    body.enable_ccd(config.ccd_enabled);
+    if config.sleeping {
+        body.sleep()
+    } else {
+        body.wake_up(false)
+    }
+    body.set_dominance_group(config.dominance_group);
+    body.set_additional_mass(config.additional_mass, !config.sleeping);
+    body.set_enabled(config.enabled);
+    body.set_body_type(config.body_type, !config.sleeping);
+}
+
 pub struct PhysicsEventHandler {
     events: Arc<Mutex<Vec<CollisionEvent>>>,
 }
@@ -320,3 +586,31 @@ impl EventHandler for PhysicsEventHandler {
     ) {
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rays_from_inside_a_collider_skip_it() {
+        let mut physics = Physics::new(Vec3::ZERO);
+        physics.add_free_collidor(ColliderBuilder::ball(0.5).build());
+        physics.add_free_collidor(
+            ColliderBuilder::cuboid(0.5, 0.5, 0.5)
+                .translation(Vec3::new(10.0, 0.0, 0.0))
+                .build(),
+        );
+        // the broad phase learns about the colliders in a step
+        physics.step();
+
+        assert_eq!(physics.cast_ray(Vec3::ZERO, Vec3::X, 20.0), Some(0.0));
+        let hit = physics
+            .cast_ray_from_inside(Vec3::ZERO, Vec3::X, 20.0)
+            .unwrap();
+        assert!((hit - 9.5).abs() < 1e-4);
+        assert_eq!(
+            physics.cast_ray_from_inside(Vec3::ZERO, Vec3::NEG_X, 20.0),
+            None
+        );
+    }
+}
@@ -0,0 +1,206 @@
+//! static colliders for the objects of a [`maple_3d::tilemap::TiledMap`]
+//!
+//! tilemaps are loaded by `maple_3d` which doesn't know about physics, so their object layers
+//! only spawn [`TileObject`] nodes. [`spawn_tile_object_colliders`] turns those into
+//! [`Collider2D`]s once the map is in the scene.
+//!
+//! ```rust, ignore
+//! let map = ctx.scene.get_by_name::<Tilemap>("level_1").unwrap().id();
+//! spawn_tile_object_colliders(&ctx.scene, map, |object| object.class == "wall");
+//! ```
+
+use glam::{Affine3A, Vec2};
+use maple_3d::tilemap::{TileObject, tiled::TiledShape};
+use maple_engine::{Buildable, Builder, Scene, scene::NodeId};
+
+use crate::nodes::{Collider2D, ColliderShape2D};
+
+/// points used for ellipses that aren't circles
+const ELLIPSE_POINTS: usize = 16;
+
+/// the collider shape of an object, centered on [`TileObject::center`]
+///
+/// points, polylines and objects without a size have no area so they return `None`.
+/// polygons become their convex hull.
+pub fn tile_object_shape(object: &TileObject) -> Option<ColliderShape2D> {
+    scaled_shape(object, Vec2::ONE)
+}
+
+/// spawn a fixed [`Collider2D`] at the root of the scene for every [`TileObject`] under `map`
+/// that `filter` accepts and [`tile_object_shape`] has a shape for. returns the ids of the
+/// spawned colliders.
+///
+/// 2D colliders are placed with their own position on the plane, so the colliders are spawned
+/// at the world position of their object rather than as its children. call this once the map
+/// has been merged into the scene.
+pub fn spawn_tile_object_colliders(
+    scene: &Scene,
+    map: NodeId,
+    filter: impl Fn(&TileObject) -> bool,
+) -> Vec<NodeId> {
+    let mut colliders = Vec::new();
+    let mut stack = scene.children_ids(map);
+
+    while let Some(id) = stack.pop() {
+        stack.extend(scene.children_ids(id));
+
+        let Some(object) = scene.get::<TileObject>(id) else {
+            continue;
+        };
+        // the node can't stay locked while its transform is read through the scene
+        let object = object.read().clone();
+        if !filter(&object) {
+            continue;
+        }
+
+        let world = world_matrix(scene, id);
+        let (scale, rotation, _) = world.to_scale_rotation_translation();
+        let Some(shape) = scaled_shape(&object, scale.truncate().abs()) else {
+            continue;
+        };
+
+        let name = match object.name.is_empty() {
+            true => format!("object_{}_collider", object.id),
+            false => format!("{}_collider", object.name),
+        };
+        let collider = Collider2D::builder()
+            .shape(shape)
+            .position(world.transform_point3(object.center().extend(0.0)))
+            .rotation(rotation)
+            .build();
+        colliders.push(scene.spawn_with_name(name, collider).id());
+    }
+
+    colliders
+}
+
+fn scaled_shape(object: &TileObject, scale: Vec2) -> Option<ColliderShape2D> {
+    let half = object.size.abs() * scale * 0.5;
+    match &object.shape {
+        TiledShape::Point | TiledShape::Polyline(_) => None,
+        TiledShape::Rectangle | TiledShape::Tile(_) | TiledShape::Ellipse
+            if half.x <= 0.0 || half.y <= 0.0 =>
+        {
+            None
+        }
+        TiledShape::Rectangle | TiledShape::Tile(_) => Some(ColliderShape2D::Rectangle {
+            hx: half.x,
+            hy: half.y,
+        }),
+        TiledShape::Ellipse if (half.x - half.y).abs() < 1e-4 => {
+            Some(ColliderShape2D::Circle { radius: half.x })
+        }
+        TiledShape::Ellipse => Some(ColliderShape2D::ConvexPolygon {
+            points: (0..ELLIPSE_POINTS)
+                .map(|i| {
+                    let angle = i as f32 / ELLIPSE_POINTS as f32 * std::f32::consts::TAU;
+                    Vec2::new(angle.cos(), angle.sin()) * half
+                })
+                .collect(),
+        }),
+        TiledShape::Polygon(points) if points.len() >= 3 => Some(ColliderShape2D::ConvexPolygon {
+            points: points.iter().map(|point| *point * scale).collect(),
+        }),
+        TiledShape::Polygon(_) => None,
+    }
+}
+
+/// the world transform of a node from the local transforms of it and its parents
+///
+/// computed from the hierarchy since a map that was just merged hasn't been synced yet
+fn world_matrix(scene: &Scene, id: NodeId) -> Affine3A {
+    let mut matrix = Affine3A::IDENTITY;
+    let mut current = Some(id);
+    while let Some(id) = current {
+        let local = scene
+            .with_transform(id, |transform| {
+                Affine3A::from_scale_rotation_translation(
+                    transform.scale,
+                    transform.rotation,
+                    transform.position,
+                )
+            })
+            .unwrap_or(Affine3A::IDENTITY);
+        matrix = local * matrix;
+        current = scene.parent_id(id);
+    }
+    matrix
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+    use maple_3d::tilemap::tiled::TiledProperties;
+    use maple_engine::{nodes::Empty, prelude::NodeTransform};
+
+    use super::*;
+
+    fn object(shape: TiledShape, size: Vec2) -> TileObject {
+        TileObject {
+            transform: NodeTransform::default(),
+            id: 1,
+            name: String::new(),
+            class: "wall".into(),
+            size,
+            shape,
+            properties: TiledProperties::default(),
+        }
+    }
+
+    #[test]
+    fn test_object_shapes() {
+        let rectangle = tile_object_shape(&object(TiledShape::Rectangle, Vec2::new(4.0, 2.0)));
+        assert!(matches!(
+            rectangle,
+            Some(ColliderShape2D::Rectangle { hx: 2.0, hy: 1.0 })
+        ));
+        let circle = tile_object_shape(&object(TiledShape::Ellipse, Vec2::splat(2.0)));
+        assert!(matches!(
+            circle,
+            Some(ColliderShape2D::Circle { radius: 1.0 })
+        ));
+        let ellipse = tile_object_shape(&object(TiledShape::Ellipse, Vec2::new(4.0, 2.0)));
+        assert!(
+            matches!(ellipse, Some(ColliderShape2D::ConvexPolygon { points }) if points.len() == ELLIPSE_POINTS)
+        );
+
+        assert!(tile_object_shape(&object(TiledShape::Point, Vec2::ZERO)).is_none());
+        assert!(tile_object_shape(&object(TiledShape::Rectangle, Vec2::ZERO)).is_none());
+        let line = TiledShape::Polyline(vec![Vec2::ZERO, Vec2::X]);
+        assert!(tile_object_shape(&object(line, Vec2::ZERO)).is_none());
+    }
+
+    #[test]
+    fn test_colliders_are_spawned_at_the_world_position_of_their_object() {
+        let scene = Scene::new();
+        let map = scene
+            .spawn(Empty::builder().position(Vec3::new(10.0, 0.0, 0.0)).build())
+            .id();
+        let layer = scene
+            .spawn_as_child(
+                Empty::builder().position(Vec3::new(0.0, 5.0, 1.0)).build(),
+                map,
+            )
+            .id();
+
+        let mut wall = object(TiledShape::Rectangle, Vec2::new(2.0, 4.0));
+        wall.transform.set_position(Vec3::new(1.0, -1.0, 0.0));
+        scene.spawn_as_child(wall, layer);
+        let mut spawn = object(TiledShape::Point, Vec2::ZERO);
+        spawn.class = "spawn".into();
+        scene.spawn_as_child(spawn, layer);
+
+        let colliders = spawn_tile_object_colliders(&scene, map, |object| object.class == "wall");
+        assert_eq!(colliders.len(), 1);
+
+        let collider = scene.get::<Collider2D>(colliders[0]).unwrap();
+        let collider = collider.read();
+        assert_eq!(scene.parent_id(colliders[0]), None);
+        // the rectangle extends down and right from the object position
+        assert_eq!(collider.transform.position, Vec3::new(12.0, 2.0, 1.0));
+        assert!(matches!(
+            collider.config.shape,
+            ColliderShape2D::Rectangle { hx: 1.0, hy: 2.0 }
+        ));
+    }
+}
@@ -6,6 +6,18 @@ use wgpu::{
     util::{BufferInitDescriptor, DeviceExt},
 };
 
+/// the arguments of one indexed draw read from a buffer instead of given by the cpu, see
+/// [`crate::core::FrameBuilder::draw_indexed_indirect`]
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct DrawIndexedIndirectArgs {
+    pub index_count: u32,
+    pub instance_count: u32,
+    pub first_index: u32,
+    pub base_vertex: i32,
+    pub first_instance: u32,
+}
+
 #[derive(Debug)]
 pub struct Buffer<T: ?Sized + SendSync> {
     pub(crate) buffer: wgpu::Buffer,
@@ -23,7 +35,14 @@ impl<T: ?Sized + SendSync> Clone for Buffer<T> {
     }
 }
 
-impl<T: 'static + SendSync> GraphResource for Buffer<T> {}
+impl<T: ?Sized + 'static + SendSync> GraphResource for Buffer<T> {}
+
+impl<T: ?Sized + SendSync> Buffer<T> {
+    /// the size of the buffer on the gpu including any padding
+    pub fn byte_size(&self) -> u64 {
+        self.buffer.size()
+    }
+}
 
 impl<T: Pod + SendSync> Buffer<[T]> {
     pub(crate) fn from_slice(
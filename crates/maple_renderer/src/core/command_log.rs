@@ -0,0 +1,109 @@
+//! a log of the resources a renderer created and the commands nodes encoded
+//!
+//! renderers on the [`GraphicsBackend::Null`] backend keep a log since nothing they draw can be
+//! seen. a render node's setup and draw can then be tested without a gpu by looking at what it
+//! created and bound:
+//!
+//! ```rust,ignore
+//! let mut renderer = Renderer::init_headless(RenderConfig {
+//!     backend: Some(GraphicsBackend::Null),
+//!     ..Default::default()
+//! })?;
+//!
+//! renderer.graph().setup_and_add_node::<BlurPass>();
+//! renderer.context.take_commands();
+//!
+//! renderer.draw_offscreen(&GameContext::new())?;
+//! let commands = renderer.context.take_commands();
+//! assert!(commands.contains(&RecordedCommand::BeginNode("Blur".into())));
+//! ```
+//!
+//! [`GraphicsBackend::Null`]: crate::types::render_config::GraphicsBackend::Null
+
+use std::ops::Range;
+
+use maple_engine::asset::AssetId;
+use parking_lot::Mutex;
+
+use crate::core::descriptor_set::DescriptorSet;
+
+/// one thing the renderer was asked to do, see the [module docs](self)
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordedCommand {
+    /// any kind of texture was created
+    CreateTexture {
+        label: Option<&'static str>,
+    },
+    CreateDescriptorSet(DescriptorSet),
+    CreatePipeline {
+        label: Option<&'static str>,
+        id: AssetId,
+    },
+    CreateComputePipeline {
+        label: Option<&'static str>,
+    },
+    /// the render graph started drawing a node
+    BeginNode(String),
+    BeginRender {
+        label: Option<String>,
+        color_targets: usize,
+        depth_target: bool,
+    },
+    BeginCompute {
+        label: Option<String>,
+    },
+    /// a render pipeline by its [`crate::core::RenderPipeline::id`]
+    UsePipeline(AssetId),
+    UseComputePipeline,
+    BindDescriptorSet {
+        set: u32,
+        descriptor_set: DescriptorSet,
+    },
+    BindVertexBuffer {
+        len: u32,
+    },
+    BindIndexBuffer {
+        len: u32,
+    },
+    Draw {
+        vertices: Range<u32>,
+        instances: Range<u32>,
+    },
+    DrawIndexed {
+        indices: Range<u32>,
+        instances: Range<u32>,
+    },
+    DrawIndexedIndirect {
+        index: usize,
+    },
+    /// bytes set with push constants
+    PushConstants {
+        offset: u32,
+        size: u32,
+    },
+    Dispatch {
+        x: u32,
+        y: u32,
+        z: u32,
+    },
+    /// a texture was copied into another one
+    CopyTexture {
+        width: u32,
+        height: u32,
+    },
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct CommandLog {
+    commands: Mutex<Vec<RecordedCommand>>,
+}
+
+impl CommandLog {
+    pub(crate) fn push(&self, command: RecordedCommand) {
+        self.commands.lock().push(command);
+    }
+
+    pub(crate) fn take(&self) -> Vec<RecordedCommand> {
+        std::mem::take(&mut self.commands.lock())
+    }
+}
@@ -1,27 +1,35 @@
 use super::{LazyBufferable, texture};
+use crate::core::gpu_timer::GpuTimer;
 use crate::core::{Frame, RenderDevice, RenderQueue};
 use crate::platform::SendSync;
 use crate::types::Dimensions;
 use crate::{
     core::{
         buffer::Buffer,
+        command_log::{CommandLog, RecordedCommand},
         descriptor_set::{DescriptorSetLayout, DescriptorSetLayoutDescriptor},
         mipmap_generator::{self, MipmapGenerator},
-        texture::{LazyTexture, Texture, TextureCube, TextureView},
+        texture::{
+            LazyTexture, Sampler, Texture, TextureArray, TextureCube, TextureSampling, TextureView,
+        },
     },
-    render_graph::node::RenderTarget,
+    render_graph::node::{RenderTarget, SurfaceDepth},
     types::{
+        builtin_texture::{Builtin, BuiltinTextures},
         default_texture::DefaultTexture,
-        render_config::{RenderConfig, VsyncMode},
+        post_effects::PostEffects,
+        quality::QualitySettings,
+        render_config::{AdapterInfo, DeviceType, GraphicsBackend, RenderConfig, VsyncMode},
     },
 };
 use anyhow::Result;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
 use std::collections::HashMap;
 use std::{
     error::Error,
     sync::{Arc, OnceLock},
+    time::Duration,
 };
 use wgpu::{
     Adapter, Device, DeviceDescriptor, Instance, InstanceDescriptor, PresentMode, Queue,
@@ -29,12 +37,52 @@ use wgpu::{
     TextureUsages,
 };
 
+/// features used when the adapter has them, bc compressed textures can only be loaded with
+/// [`wgpu::Features::TEXTURE_COMPRESSION_BC`], indirect draws can only start at an instance
+/// with [`wgpu::Features::INDIRECT_FIRST_INSTANCE`] and push constants need
+/// [`wgpu::Features::PUSH_CONSTANTS`]
+const OPTIONAL_FEATURES: wgpu::Features = wgpu::Features::TEXTURE_COMPRESSION_BC
+    .union(wgpu::Features::INDIRECT_FIRST_INSTANCE)
+    .union(wgpu::Features::PUSH_CONSTANTS);
+
+/// the most bytes of push constants asked for, every native backend has at least this much
+const MAX_PUSH_CONSTANT_SIZE: u32 = 128;
+
+/// the default limits with as much push constant space as the adapter has up to
+/// [`MAX_PUSH_CONSTANT_SIZE`]
+fn required_limits(adapter: &Adapter) -> wgpu::Limits {
+    wgpu::Limits {
+        max_push_constant_size: adapter
+            .limits()
+            .max_push_constant_size
+            .min(MAX_PUSH_CONSTANT_SIZE),
+        ..Default::default()
+    }
+}
+
+/// an srgb surface if there is one so the gpu does the gamma encoding of the final image. web
+/// canvases only offer linear ones, the composite pass encodes the image itself on those
+fn preferred_surface_format(cap: &wgpu::SurfaceCapabilities) -> texture::TextureFormat {
+    cap.formats
+        .iter()
+        .copied()
+        .find(TextureFormat::is_srgb)
+        .unwrap_or(cap.formats[0])
+        .into()
+}
+
+/// the gpu of the last context made, see [`RenderContext::current_adapter`]
+static CURRENT_ADAPTER: RwLock<Option<AdapterInfo>> = RwLock::new(None);
+
 pub struct RenderOptions<'a> {
     pub label: Option<&'a str>,
     pub color_targets: &'a [RenderTarget],
     pub depth_target: Option<&'a TextureView>,
     pub clear_color: Option<[f32; 4]>,
     pub clear_depth: Option<f32>,
+    /// throw away the depth written once the pass is done instead of storing it, for depth only
+    /// used while drawing the pass
+    pub discard_depth: bool,
 }
 
 /// holds all raw WGPU state
@@ -50,15 +98,37 @@ struct Backend {
     dimensions: Dimensions,
 
     default_textures: OnceLock<DefaultTexture>,
+    builtin_textures: BuiltinTextures,
     mipmap_generator: MipmapGenerator,
 }
 
 impl Backend {
+    fn create_instance(config: &RenderConfig) -> Instance {
+        let mut descriptor = InstanceDescriptor::default();
+        if let Some(backend) = config.backend {
+            descriptor.backends = backend.into();
+        }
+        // wgpu only hands out the noop adapter when asked to
+        descriptor.backend_options.noop.enable = config.backend == Some(GraphicsBackend::Null);
+        Instance::new(&descriptor)
+    }
+
+    /// the device of a context, with a command log for the null backend
+    fn render_device(&self) -> RenderDevice {
+        let log = (self.config.backend == Some(GraphicsBackend::Null))
+            .then(|| Arc::new(CommandLog::default()));
+        RenderDevice {
+            device: self.device.clone(),
+            queue: self.queue.clone(),
+            log,
+        }
+    }
+
     async fn init<T>(window: Arc<T>, config: RenderConfig) -> Result<Self>
     where
         T: HasDisplayHandle + HasWindowHandle + SendSync + 'static,
     {
-        let instance = Instance::new(&InstanceDescriptor::default());
+        let instance = Self::create_instance(&config);
 
         let adapter = instance
             .request_adapter(&RequestAdapterOptions::default())
@@ -66,20 +136,22 @@ impl Backend {
 
         let (device, queue) = adapter
             .request_device(&DeviceDescriptor {
+                required_features: adapter.features() & (GpuTimer::FEATURES | OPTIONAL_FEATURES),
+                required_limits: required_limits(&adapter),
                 ..Default::default()
             })
             .await?;
 
         let surface: Surface = instance.create_surface(window)?;
         let cap = surface.get_capabilities(&adapter);
-        let surface_format: texture::TextureFormat = cap.formats[0].into();
+        let surface_format = preferred_surface_format(&cap);
 
         let device = Arc::new(device);
         let queue = Arc::new(queue);
 
         let mipmap_generator = MipmapGenerator::new(device.clone(), queue.clone());
 
-        let backend = Self {
+        let mut backend = Self {
             instance: instance,
             adapter,
             device: device,
@@ -90,6 +162,7 @@ impl Backend {
             config,
             dimensions: Dimensions::zero(),
             default_textures: OnceLock::new(),
+            builtin_textures: BuiltinTextures::default(),
             mipmap_generator,
         };
 
@@ -99,7 +172,7 @@ impl Backend {
     }
 
     async fn init_headless(config: RenderConfig) -> Result<Self> {
-        let instance = Instance::new(&InstanceDescriptor::default());
+        let instance = Self::create_instance(&config);
 
         let adapter = instance
             .request_adapter(&RequestAdapterOptions::default())
@@ -107,6 +180,8 @@ impl Backend {
 
         let (device, queue) = adapter
             .request_device(&DeviceDescriptor {
+                required_features: adapter.features() & (GpuTimer::FEATURES | OPTIONAL_FEATURES),
+                required_limits: required_limits(&adapter),
                 ..Default::default()
             })
             .await?;
@@ -127,6 +202,7 @@ impl Backend {
             config,
             dimensions: Dimensions::zero(),
             default_textures: OnceLock::new(),
+            builtin_textures: BuiltinTextures::default(),
             mipmap_generator,
         };
 
@@ -139,29 +215,39 @@ impl Backend {
     {
         let surface: Surface = self.instance.create_surface(window)?;
         let cap = surface.get_capabilities(&self.adapter);
-        self.surface_format = cap.formats[0].into();
+        self.surface_format = preferred_surface_format(&cap);
         self.surface = Some(surface);
         self.dimensions = dimensions;
         self.configure_surface();
         Ok(())
     }
 
-    fn configure_surface(&self) {
+    fn configure_surface(&mut self) {
+        // a texture acquired ahead of time can't outlive the old configuration
+        self.current_surface_texture = None;
+
         let Some(surface) = self.surface.as_ref() else {
             return;
         };
+        // minimized windows are zero sized which surfaces can't be
+        if self.dimensions.width == 0 || self.dimensions.height == 0 {
+            return;
+        }
         let format: TextureFormat = self.surface_format.into();
+        // lets screenshots copy the surface where the platform allows it
+        let usage = TextureUsages::RENDER_ATTACHMENT
+            | (surface.get_capabilities(&self.adapter).usages & TextureUsages::COPY_SRC);
 
         surface.configure(
             &self.device,
             &SurfaceConfiguration {
-                usage: TextureUsages::RENDER_ATTACHMENT,
+                usage,
                 format,
                 view_formats: vec![format.add_srgb_suffix()],
                 alpha_mode: wgpu::CompositeAlphaMode::Auto,
                 width: self.dimensions.width,
                 height: self.dimensions.height,
-                desired_maximum_frame_latency: 2,
+                desired_maximum_frame_latency: self.config.frames_in_flight.max(1),
                 present_mode: match self.config.vsync {
                     VsyncMode::Off => PresentMode::AutoNoVsync,
                     VsyncMode::On => PresentMode::AutoVsync,
@@ -173,7 +259,18 @@ impl Backend {
     pub fn acquire_surface_texture(&mut self) -> Result<&SurfaceTexture, Box<dyn Error>> {
         if self.current_surface_texture.is_none() {
             let surface = self.surface.as_ref().expect("surface not attached");
-            self.current_surface_texture = Some(surface.get_current_texture()?);
+            let texture = match surface.get_current_texture() {
+                // the swapchain went away with the display or a mobile app going to the
+                // background, configuring makes a new one
+                Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                    log::warn!("surface lost, configuring it again");
+                    self.configure_surface();
+                    let surface = self.surface.as_ref().expect("surface not attached");
+                    surface.get_current_texture()?
+                }
+                texture => texture?,
+            };
+            self.current_surface_texture = Some(texture);
         }
         Ok(self.current_surface_texture.as_ref().unwrap())
     }
@@ -182,6 +279,11 @@ impl Backend {
         self.current_surface_texture.as_ref()
     }
 
+    fn detach_surface(&mut self) {
+        self.current_surface_texture = None;
+        self.surface = None;
+    }
+
     pub fn present_surface(&mut self) -> Result<(), Box<dyn Error>> {
         if let Some(surface_tex) = self.current_surface_texture.take() {
             surface_tex.present();
@@ -200,14 +302,29 @@ impl Backend {
 
         self.configure_surface();
     }
+
+    pub fn change_frames_in_flight(&mut self, frames: u32) {
+        self.config.frames_in_flight = frames;
+
+        self.configure_surface();
+    }
 }
 
 /// Public rendering context that provides a safe API over the backend
 pub struct RenderContext {
     backend: Backend,
     layout_cache: RwLock<HashMap<DescriptorSetLayoutDescriptor, DescriptorSetLayout>>,
+    sampler_cache: RwLock<HashMap<TextureSampling, Sampler>>,
+    /// made again at the new size when the surface resizes
+    depth_cache: RwLock<HashMap<SurfaceDepth, Texture>>,
+    /// the multisampled surface textures by their sample count, made again on resize
+    msaa_cache: RwLock<HashMap<u32, Texture>>,
     device: RenderDevice,
     queue: RenderQueue,
+    /// `None` when the device can't write timestamps
+    gpu_timer: Mutex<Option<GpuTimer>>,
+    pub(crate) post_effects: PostEffects,
+    pub(crate) quality: QualitySettings,
 }
 
 impl RenderContext {
@@ -216,32 +333,31 @@ impl RenderContext {
         T: HasDisplayHandle + HasWindowHandle + SendSync + 'static,
     {
         let backend = Backend::init(window, config).await?;
-        Ok(Self {
-            layout_cache: RwLock::new(HashMap::new()),
-            device: RenderDevice {
-                device: backend.device.clone(),
-                queue: backend.queue.clone(),
-            },
-            queue: RenderQueue {
-                queue: backend.queue.clone(),
-            },
-            backend,
-        })
+        Ok(Self::from_backend(backend))
     }
 
     pub async fn init_headless(config: RenderConfig) -> Result<Self> {
         let backend = Backend::init_headless(config).await?;
-        Ok(Self {
+        Ok(Self::from_backend(backend))
+    }
+
+    fn from_backend(backend: Backend) -> Self {
+        let context = Self {
             layout_cache: RwLock::new(HashMap::new()),
-            device: RenderDevice {
-                device: backend.device.clone(),
-                queue: backend.queue.clone(),
-            },
+            sampler_cache: RwLock::new(HashMap::new()),
+            depth_cache: RwLock::new(HashMap::new()),
+            msaa_cache: RwLock::new(HashMap::new()),
+            gpu_timer: Mutex::new(GpuTimer::new(&backend.device, &backend.queue)),
+            post_effects: PostEffects::default(),
+            quality: QualitySettings::default(),
+            device: backend.render_device(),
             queue: RenderQueue {
                 queue: backend.queue.clone(),
             },
             backend,
-        })
+        };
+        *CURRENT_ADAPTER.write() = Some(context.adapter_info());
+        context
     }
 
     pub fn create_frame(&self) -> Frame<'_> {
@@ -252,16 +368,42 @@ impl RenderContext {
                 label: Some("frame command encoder"),
             });
 
-        Frame {
-            encoder: encoder,
-            renderer: self,
+        let timing = self
+            .gpu_timer
+            .lock()
+            .as_mut()
+            .and_then(|timer| timer.begin_frame(&self.backend.device));
+
+        Frame::new(self, encoder, timing)
+    }
+
+    pub fn submit_frame(&self, mut frame: Frame<'_>) {
+        let mut timer = self.gpu_timer.lock();
+        if let (Some(timer), Some(timing)) = (timer.as_mut(), frame.timing.take()) {
+            timer.resolve(&mut frame.encoder, &timing);
+        }
+
+        self.queue.queue.submit(
+            frame
+                .finished
+                .into_iter()
+                .chain(std::iter::once(frame.encoder.finish())),
+        );
+
+        if let Some(timer) = timer.as_mut() {
+            timer.map();
         }
     }
 
-    pub fn submit_frame(&self, frame: Frame<'_>) {
-        self.queue
-            .queue
-            .submit(std::iter::once(frame.encoder.finish()));
+    /// how long the passes of each render graph node took on the gpu, from a frame or two ago
+    ///
+    /// empty when the device can't time passes
+    pub fn gpu_times(&self) -> HashMap<String, Duration> {
+        self.gpu_timer
+            .lock()
+            .as_ref()
+            .map(|timer| timer.latest().clone())
+            .unwrap_or_default()
     }
 
     pub fn attach_surface<T>(&mut self, window: Arc<T>, dimensions: Dimensions) -> Result<()>
@@ -271,6 +413,12 @@ impl RenderContext {
         self.backend.attach_surface(window, dimensions)
     }
 
+    /// drop the window surface, mobile platforms destroy the window's surface when the app goes
+    /// to the background and a new one is attached when it comes back
+    pub fn detach_surface(&mut self) {
+        self.backend.detach_surface();
+    }
+
     pub fn get_surface_texture(&self) -> Option<&SurfaceTexture> {
         self.backend.get_surface_texture()
     }
@@ -291,6 +439,88 @@ impl RenderContext {
         layout
     }
 
+    /// the sampler for a texture's [`TextureSampling`], samplers are shared by every texture
+    /// sampled the same way
+    pub fn get_or_create_sampler(&self, sampling: TextureSampling) -> Sampler {
+        if let Some(sampler) = self.sampler_cache.read().get(&sampling) {
+            return sampler.clone();
+        }
+
+        let sampler = Sampler {
+            inner: self.backend.device.create_sampler(&sampling.into()),
+        };
+        self.sampler_cache.write().insert(sampling, sampler.clone());
+        sampler
+    }
+
+    /// a depth buffer the size of the surface for passes drawing to [`RenderTarget::Surface`] or
+    /// textures of the same size, passes asking for the same [`SurfaceDepth`] share one texture
+    ///
+    /// ```rust,ignore
+    /// let depth = rcx.surface_depth(SurfaceDepth::default()).create_view();
+    /// frame.render(
+    ///     RenderOptions {
+    ///         label: Some("overlay"),
+    ///         color_targets: &[RenderTarget::Surface],
+    ///         depth_target: Some(&depth),
+    ///         clear_color: None,
+    ///         clear_depth: Some(1.0),
+    ///         discard_depth: true,
+    ///     },
+    ///     |mut fb| { /* ... */ },
+    /// )?;
+    /// ```
+    pub fn surface_depth(&self, depth: SurfaceDepth) -> Texture {
+        if let Some(texture) = self.depth_cache.read().get(&depth) {
+            return texture.clone();
+        }
+
+        let dimensions = self.surface_size();
+        let texture = self.device.create_texture(texture::TextureCreateInfo {
+            label: Some("surface depth"),
+            width: dimensions.width.max(1),
+            height: dimensions.height.max(1),
+            format: depth.format,
+            usage: texture::TextureUsage::RENDER_ATTACHMENT
+                | texture::TextureUsage::TEXTURE_BINDING,
+            sample_count: depth.sample_count,
+            mip_level: 1,
+        });
+        self.depth_cache.write().insert(depth, texture.clone());
+        texture
+    }
+
+    /// the texture [`RenderTarget::MultiSampledSurface`] draws into before it is resolved, in the
+    /// surface format at the size of the surface
+    pub fn surface_msaa(&self, samples: u32) -> Texture {
+        if let Some(texture) = self.msaa_cache.read().get(&samples) {
+            return texture.clone();
+        }
+
+        let dimensions = self.surface_size();
+        let texture = self.device.create_texture(texture::TextureCreateInfo {
+            label: Some("surface msaa"),
+            width: dimensions.width.max(1),
+            height: dimensions.height.max(1),
+            format: self.surface_format(),
+            usage: texture::TextureUsage::RENDER_ATTACHMENT,
+            sample_count: samples,
+            mip_level: 1,
+        });
+        self.msaa_cache.write().insert(samples, texture.clone());
+        texture
+    }
+
+    /// true if textures of `format` can be drawn with `samples` samples per pixel, `1` and `4`
+    /// work everywhere for color and depth formats
+    pub fn supports_sample_count(&self, format: texture::TextureFormat, samples: u32) -> bool {
+        self.backend
+            .adapter
+            .get_texture_format_features(format.into())
+            .flags
+            .sample_count_supported(samples)
+    }
+
     pub fn device(&self) -> &RenderDevice {
         &self.device
     }
@@ -303,18 +533,120 @@ impl RenderContext {
         &self.backend.mipmap_generator
     }
 
+    /// the effects the composite pass applies, see [`crate::core::renderer::Renderer::post_effects`]
+    pub fn post_effects(&self) -> &PostEffects {
+        &self.post_effects
+    }
+
+    /// what the renderer trades for speed, see [`crate::core::renderer::Renderer::set_quality`]
+    pub fn quality(&self) -> &QualitySettings {
+        &self.quality
+    }
+
+    /// the size of the scene targets, the surface scaled by the render scale of the
+    /// [`QualitySettings`]
+    pub fn render_size(&self) -> Dimensions {
+        self.quality.render_size(self.surface_size())
+    }
+
     pub fn surface_format(&self) -> texture::TextureFormat {
         self.backend.surface_format
     }
 
+    /// the resources created and commands encoded since the last call, in order
+    ///
+    /// only renderers on [`GraphicsBackend::Null`] keep a log, this is empty for the others. see
+    /// [`crate::core::command_log`]
+    pub fn take_commands(&self) -> Vec<RecordedCommand> {
+        self.device
+            .log
+            .as_ref()
+            .map(|log| log.take())
+            .unwrap_or_default()
+    }
+
+    /// the graphics api the device was created with
+    pub fn graphics_backend(&self) -> GraphicsBackend {
+        GraphicsBackend::from_wgpu(self.backend.adapter.get_info().backend)
+    }
+
+    /// the gpu the device was created on
+    pub fn adapter_info(&self) -> AdapterInfo {
+        let info = self.backend.adapter.get_info();
+        AdapterInfo {
+            name: info.name,
+            device_type: DeviceType::from_wgpu(info.device_type),
+            driver: [info.driver, info.driver_info]
+                .into_iter()
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join(" "),
+            backend: GraphicsBackend::from_wgpu(info.backend),
+        }
+    }
+
+    /// the gpu of the last context made in this process, `None` before the renderer has started
+    ///
+    /// for code with no access to the renderer such as bug reports, see `maple::engine_info`
+    pub fn current_adapter() -> Option<AdapterInfo> {
+        CURRENT_ADAPTER.read().clone()
+    }
+
+    /// read one layer of a texture back into an 8 bit image, e.g. to compare what was rendered in
+    /// tests
+    ///
+    /// this blocks until the gpu is done with every submitted frame, see
+    /// [`RenderGraph::dump_targets`](crate::render_graph::graph::RenderGraph::dump_targets) for
+    /// how formats are converted
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_texture(&self, texture: &Texture, layer: u32) -> Result<image::RgbaImage> {
+        super::readback::read_layer(&self.device, &texture.inner, layer)
+    }
+
+    /// read one layer of a texture back as it is stored, tightly packed rows of texels in
+    /// [`Texture::format`], blocks like [`RenderContext::read_texture`]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_texture_bytes(&self, texture: &Texture, layer: u32) -> Result<Vec<u8>> {
+        super::readback::read_layer_bytes(&self.device, &texture.inner, layer)
+    }
+
+    /// read the surface texture drawn this frame back into an 8 bit image, call it after the
+    /// render graph ran and before the texture is presented, see `Renderer::screenshot`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_surface(&self) -> Result<image::RgbaImage> {
+        let surface_tex = self
+            .get_surface_texture()
+            .ok_or_else(|| anyhow::anyhow!("no surface texture was acquired this frame"))?;
+        super::readback::read_layer(&self.device, &surface_tex.texture, 0)
+    }
+
     pub fn resize(&mut self, new_size: Dimensions) {
         self.backend.resize(new_size);
+        self.depth_cache.get_mut().clear();
+        self.msaa_cache.get_mut().clear();
     }
 
     pub fn change_vsync(&mut self, mode: VsyncMode) {
         self.backend.change_vsync(mode);
     }
 
+    /// set how many frames can be queued for presentation. see [`RenderConfig::frames_in_flight`]
+    pub fn change_frames_in_flight(&mut self, frames: u32) {
+        self.backend.change_frames_in_flight(frames);
+    }
+
+    /// true if a window surface is attached
+    pub fn has_surface(&self) -> bool {
+        self.backend.surface.is_some()
+    }
+
+    /// true if there is a surface to draw to, minimized windows have none
+    pub fn can_present(&self) -> bool {
+        self.has_surface()
+            && self.backend.dimensions.width > 0
+            && self.backend.dimensions.height > 0
+    }
+
     pub fn acquire_surface_texture(&mut self) -> Result<&SurfaceTexture, Box<dyn Error>> {
         self.backend.acquire_surface_texture()
     }
@@ -353,6 +685,18 @@ impl RenderContext {
         })
     }
 
+    /// a utility texture shared by every pass such as blue noise or the BRDF LUT
+    ///
+    /// generated textures are made the first time they are asked for and reused after that
+    pub fn builtin_texture(&self, builtin: Builtin) -> &Texture {
+        self.backend.builtin_textures.get(
+            builtin,
+            self.get_default_texture(),
+            &self.backend.device,
+            &self.backend.queue,
+        )
+    }
+
     pub fn get_texture(&self, lazy_texture: &LazyTexture) -> Texture {
         lazy_texture.get_texture(
             &self.backend.mipmap_generator,
@@ -361,6 +705,91 @@ impl RenderContext {
         )
     }
 
+    /// a texture filled with `data`, tightly packed rows of the first mip. the other mips are
+    /// generated from it when `info.mip_level` is more than 1, compressed formats only get the
+    /// first mip
+    pub fn create_texture_with_data(
+        &self,
+        info: texture::TextureCreateInfo,
+        data: &[u8],
+    ) -> Texture {
+        let (format, mip_level) = (info.format, info.mip_level);
+        let texture = self.device.create_texture(texture::TextureCreateInfo {
+            usage: info.usage | texture::TextureUsage::COPY_DST,
+            ..info
+        });
+
+        match format.is_compressed() {
+            true => texture.write_mip(&self.backend.queue, 0, data),
+            false => texture.write(&self.backend.queue, data),
+        }
+        if mip_level > 1 && Texture::supports_mipmap_generation(format) {
+            self.generate_mipmaps(&texture, mip_level);
+        }
+        texture
+    }
+
+    /// replace the pixels of `region` in the first mip of `texture` with `data`, tightly packed
+    /// rows. the other mips keep their pixels until [`RenderContext::generate_mipmaps`]
+    pub fn write_texture(&self, texture: &Texture, region: texture::TextureRegion, data: &[u8]) {
+        texture.write_region(
+            &self.backend.queue,
+            region.x,
+            region.y,
+            region.width,
+            region.height,
+            data,
+        );
+    }
+
+    /// load a png, jpeg, hdr or exr file into a texture with all of its mips, see
+    /// [`RenderContext::load_texture_from_bytes`]
+    pub fn load_texture(
+        &self,
+        path: impl AsRef<std::path::Path>,
+        label: Option<&'static str>,
+    ) -> Result<Texture, image::ImageError> {
+        Ok(self.texture_from_image(&image::open(path)?, label))
+    }
+
+    /// decode an image from its file contents into a texture with all of its mips, high dynamic
+    /// range images are `RGBA32Float` and the others `RGBA8`
+    pub fn load_texture_from_bytes(
+        &self,
+        bytes: &[u8],
+        label: Option<&'static str>,
+    ) -> Result<Texture, image::ImageError> {
+        Ok(self.texture_from_image(&image::load_from_memory(bytes)?, label))
+    }
+
+    fn texture_from_image(
+        &self,
+        img: &image::DynamicImage,
+        label: Option<&'static str>,
+    ) -> Texture {
+        let (width, height) = (img.width(), img.height());
+        let (format, data) = match img {
+            image::DynamicImage::ImageRgb32F(_) | image::DynamicImage::ImageRgba32F(_) => (
+                texture::TextureFormat::RGBA32Float,
+                bytemuck::cast_slice(&img.to_rgba32f().into_raw()).to_vec(),
+            ),
+            _ => (texture::TextureFormat::RGBA8, img.to_rgba8().into_raw()),
+        };
+
+        self.create_texture_with_data(
+            texture::TextureCreateInfo {
+                label,
+                width,
+                height,
+                format,
+                usage: texture::TextureUsage::TEXTURE_BINDING,
+                sample_count: 1,
+                mip_level: texture::mip_level_count(width, height),
+            },
+            &data,
+        )
+    }
+
     pub fn generate_mipmaps(&self, texture: &Texture, mip_level_count: u32) {
         mipmap_generator::generate_mipmaps(
             &self.backend.mipmap_generator,
@@ -380,4 +809,16 @@ impl RenderContext {
             mip_level_count,
         );
     }
+
+    /// fill the mips of every layer of `array` from its first mip, layers are written through
+    /// [`TextureArray::create_layer_texture`] and [`RenderQueue::write_texture`]
+    pub fn generate_array_mipmaps(&self, array: &TextureArray) {
+        mipmap_generator::generate_cubemap_mipmaps(
+            &self.backend.mipmap_generator,
+            &self.backend.device,
+            &self.backend.queue,
+            &array.inner,
+            array.mip_level_count(),
+        );
+    }
 }
@@ -11,6 +11,7 @@ use wgpu::{
 use crate::{
     core::{
         buffer::Buffer,
+        ring_buffer::RingBuffer,
         texture::{Sampler, TextureFormat, TextureView},
     },
     render_graph::graph::GraphResource,
@@ -50,12 +51,19 @@ pub enum DescriptorWrite<T: SendSync> {
 #[derive(Debug, PartialEq, Eq, Hash)]
 pub enum DescriptorBindingType {
     UniformBuffer,
+    /// a uniform buffer bound with an offset given when the set is bound, see
+    /// [`crate::core::ring_buffer`]
+    DynamicUniformBuffer {
+        min_size: Option<usize>,
+    },
     TextureView {
         filterable: bool,
     },
     TextureViewCube {
         filterable: bool,
     },
+    /// a depth texture read with `texture_depth_2d`
+    TextureViewDepth,
     TextureViewDepthArray,
     TextureViewDepthCubeArray,
     Sampler {
@@ -121,6 +129,19 @@ impl DescriptorSetLayout {
                     },
                     count: None,
                 }),
+                DescriptorBindingType::DynamicUniformBuffer { min_size } => {
+                    entries.push(wgpu::BindGroupLayoutEntry {
+                        binding: i as u32,
+                        visibility: info.visibility.into(),
+                        ty: BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: true,
+                            min_binding_size: min_size
+                                .and_then(|size| NonZeroU64::new(size as u64)),
+                        },
+                        count: None,
+                    })
+                }
                 DescriptorBindingType::TextureView { filterable } => {
                     entries.push(wgpu::BindGroupLayoutEntry {
                         binding: i as u32,
@@ -149,6 +170,18 @@ impl DescriptorSetLayout {
                         count: None,
                     })
                 }
+                DescriptorBindingType::TextureViewDepth => {
+                    entries.push(wgpu::BindGroupLayoutEntry {
+                        binding: i as u32,
+                        visibility: info.visibility.into(),
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Depth,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    })
+                }
                 DescriptorBindingType::TextureViewDepthArray => {
                     entries.push(wgpu::BindGroupLayoutEntry {
                         binding: i as u32,
@@ -247,7 +280,7 @@ pub struct DescriptorSetDescriptor<'a, T: SendSync> {
     pub writes: &'a [DescriptorWrite<T>],
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct DescriptorSet {
     pub(crate) backend: BindGroup,
 }
@@ -355,6 +388,32 @@ impl<'a> DescriptorSetBuilder<'a> {
         self
     }
 
+    /// bind `size` bytes of a uniform buffer at the offset given when the set is bound, for
+    /// [`DescriptorBindingType::DynamicUniformBuffer`]
+    pub fn uniform_dynamic<T: ?Sized + SendSync>(
+        &mut self,
+        binding: u32,
+        buffer: &'a Buffer<T>,
+        size: u64,
+    ) -> &mut Self {
+        self.entries.push(BindGroupEntry {
+            binding,
+            resource: BindingResource::Buffer(BufferBinding {
+                buffer: &buffer.buffer,
+                offset: 0,
+                size: NonZeroU64::new(size),
+            }),
+        });
+        self
+    }
+
+    /// bind `size` bytes of a ring buffer at the offsets its pushes return, for dynamic uniform
+    /// and storage bindings. pushes that leave less than `size` bytes are refused from now on
+    pub fn ring(&mut self, binding: u32, ring: &'a RingBuffer, size: u64) -> &mut Self {
+        ring.set_binding_size(size);
+        self.uniform_dynamic(binding, ring.buffer(), size)
+    }
+
     pub fn write<T: SendSync>(&mut self, binding: u32, write: &'a DescriptorWrite<T>) -> &mut Self {
         match write {
             DescriptorWrite::UniformBuffer(buffer) => self.entries.push(BindGroupEntry {
@@ -4,12 +4,14 @@ use crate::shader_asset::{Shader, ShaderSource};
 use crate::{
     core::{
         ComputeShader, ComputeShaderSource, DescriptorSetBuilder,
-        buffer::Buffer,
+        buffer::{Buffer, DrawIndexedIndirectArgs},
+        command_log::{CommandLog, RecordedCommand},
         descriptor_set::{DescriptorSet, DescriptorSetLayout, DescriptorSetLayoutDescriptor},
         pipeline::{
             ComputePipeline, ComputePipelineCreateInfo, PipelineCreateInfo, PipelineLayout,
-            RenderPipeline,
+            PushConstantRange, RenderPipeline,
         },
+        ring_buffer::RingBuffer,
         texture::{
             Sampler, SamplerOptions, Texture, TextureCreateInfo, TextureCube, TextureCubeCreateInfo,
         },
@@ -27,17 +29,27 @@ use wgpu::{BufferUsages, Device, Limits, Queue};
 pub struct RenderDevice {
     pub(crate) device: Arc<Device>,
     pub(crate) queue: Arc<Queue>,
+    /// `None` unless the backend is [`crate::types::render_config::GraphicsBackend::Null`]
+    pub(crate) log: Option<Arc<CommandLog>>,
 }
 
 impl RenderDevice {
+    /// add a command to the log if there is one
+    pub(crate) fn record(&self, command: impl FnOnce() -> RecordedCommand) {
+        if let Some(log) = &self.log {
+            log.push(command());
+        }
+    }
+
     pub fn create_vertex_buffer<V>(&self, vertices: &[V]) -> Buffer<[V]>
     where
         V: VertexLayout + Pod + SendSync,
     {
+        // compute shaders can read vertices too, e.g. to skin them
         Buffer::from_slice(
             &self.device,
             vertices,
-            BufferUsages::VERTEX,
+            BufferUsages::VERTEX | BufferUsages::STORAGE,
             "Vertex Buffer",
         )
     }
@@ -96,11 +108,36 @@ impl RenderDevice {
         Buffer::from_size(
             &self.device,
             len,
-            BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            BufferUsages::VERTEX | BufferUsages::STORAGE | BufferUsages::COPY_DST,
             "vertex buffer",
         )
     }
 
+    /// `capacity` bytes handed out in aligned pieces once a frame for data bound with dynamic
+    /// offsets, see [`crate::core::ring_buffer`]
+    pub fn create_ring_buffer(&self, label: &'static str, capacity: u64) -> RingBuffer {
+        RingBuffer::new(&self.device, self.queue.clone(), label, capacity)
+    }
+
+    /// arguments for [`crate::core::FrameBuilder::draw_indexed_indirect`] that compute shaders
+    /// can write
+    pub fn create_indirect_buffer(&self, len: usize) -> Buffer<[DrawIndexedIndirectArgs]> {
+        Buffer::from_size(
+            &self.device,
+            len,
+            BufferUsages::INDIRECT | BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            "indirect buffer",
+        )
+    }
+
+    /// true if indirect draws can start at another instance than the first, without it every
+    /// indirect draw starts at instance 0
+    pub fn supports_indirect_first_instance(&self) -> bool {
+        self.device
+            .features()
+            .contains(wgpu::Features::INDIRECT_FIRST_INSTANCE)
+    }
+
     pub fn create_sized_index_buffer(&self, len: usize) -> Buffer<[u32]> {
         Buffer::from_size(
             &self.device,
@@ -119,10 +156,12 @@ impl RenderDevice {
     }
 
     pub fn create_texture(&self, info: TextureCreateInfo) -> Texture {
+        self.record(|| RecordedCommand::CreateTexture { label: info.label });
         Texture::create(&self.device, &info)
     }
 
     pub fn create_texture_cube(&self, info: TextureCubeCreateInfo) -> TextureCube {
+        self.record(|| RecordedCommand::CreateTexture { label: info.label });
         TextureCube::create(&self.device, &info)
     }
 
@@ -130,6 +169,7 @@ impl RenderDevice {
         &self,
         info: texture::TextureArrayCreateInfo,
     ) -> texture::TextureArray {
+        self.record(|| RecordedCommand::CreateTexture { label: info.label });
         texture::TextureArray::create(&self.device, &info)
     }
 
@@ -137,6 +177,7 @@ impl RenderDevice {
         &self,
         info: texture::TextureCubeArrayCreateInfo,
     ) -> texture::TextureCubeArray {
+        self.record(|| RecordedCommand::CreateTexture { label: info.label });
         texture::TextureCubeArray::create(&self.device, &info)
     }
 
@@ -149,7 +190,7 @@ impl RenderDevice {
         bytes: &[u8],
         label: Option<&'static str>,
     ) -> Result<Texture, image::ImageError> {
-        Texture::from_bytes(&self.device, &self.queue, bytes, label)
+        Texture::from_bytes(&self.device, &self.queue, bytes, label, false)
     }
 
     pub fn load_texture_from_file(
@@ -157,7 +198,7 @@ impl RenderDevice {
         path: impl AsRef<std::path::Path>,
         label: Option<&'static str>,
     ) -> Result<Texture, image::ImageError> {
-        Texture::from_file(&self.device, &self.queue, path, label)
+        Texture::from_file(&self.device, &self.queue, path, label, false)
     }
 
     pub fn create_descriptor_set_layout(
@@ -168,7 +209,9 @@ impl RenderDevice {
     }
 
     pub fn build_descriptor_set(&self, builder: &DescriptorSetBuilder) -> DescriptorSet {
-        builder.build(&self.device)
+        let descriptor_set = builder.build(&self.device);
+        self.record(|| RecordedCommand::CreateDescriptorSet(descriptor_set.clone()));
+        descriptor_set
     }
 
     pub fn compile_shader(&self, shader: ShaderSource) -> Result<Shader, LoadErr> {
@@ -186,7 +229,66 @@ impl RenderDevice {
         &self,
         pipeline_create_info: PipelineCreateInfo,
     ) -> RenderPipeline {
-        RenderPipeline::create(&self.device, pipeline_create_info)
+        let label = pipeline_create_info.label;
+        let pipeline = RenderPipeline::create(&self.device, pipeline_create_info);
+        self.record(|| RecordedCommand::CreatePipeline {
+            label,
+            id: pipeline.id.clone(),
+        });
+        pipeline
+    }
+
+    /// a pipeline layout with push constants, fails if the device has no push constants or the
+    /// ranges need more than [`RenderDevice::max_push_constant_size`]
+    pub fn create_pipeline_layout_with_push_constants(
+        &self,
+        descriptor_set_layouts: &[DescriptorSetLayout],
+        push_constants: &[PushConstantRange],
+    ) -> Result<PipelineLayout> {
+        if !self.supports_push_constants() {
+            anyhow::bail!("push constants are not supported by this device");
+        }
+        let size = push_constants
+            .iter()
+            .map(|range| range.range.end)
+            .max()
+            .unwrap_or(0);
+        if size > self.max_push_constant_size() {
+            anyhow::bail!(
+                "push constants need {size} bytes but the device has {}",
+                self.max_push_constant_size()
+            );
+        }
+        if let Some(range) = push_constants
+            .iter()
+            .find(|range| range.range.start % 4 != 0 || range.range.end % 4 != 0)
+        {
+            anyhow::bail!(
+                "push constant range {:?} is not a multiple of 4",
+                range.range
+            );
+        }
+
+        Ok(PipelineLayout::create_with_push_constants(
+            &self.device,
+            descriptor_set_layouts,
+            push_constants,
+        ))
+    }
+
+    /// true if pipeline layouts can have push constants, they are missing on the web
+    pub fn supports_push_constants(&self) -> bool {
+        self.device
+            .features()
+            .contains(wgpu::Features::PUSH_CONSTANTS)
+    }
+
+    /// the most bytes of push constants a pipeline layout can have, 0 without support
+    pub fn max_push_constant_size(&self) -> u32 {
+        match self.supports_push_constants() {
+            true => self.device.limits().max_push_constant_size,
+            false => 0,
+        }
     }
 
     // Convenience aliases for shorter method names
@@ -203,6 +305,7 @@ impl RenderDevice {
     }
 
     pub fn create_compute_pipeline(&self, info: ComputePipelineCreateInfo) -> ComputePipeline {
+        self.record(|| RecordedCommand::CreateComputePipeline { label: info.label });
         ComputePipeline::create(&self.device, info)
     }
 
@@ -1,14 +1,22 @@
-use std::ops::Range;
+use std::{ops::Range, sync::Arc};
 
 use anyhow::Result;
 use bytemuck::Pod;
 use maple_engine::platform::SendSync;
-use wgpu::{CommandEncoder, ComputePass, Operations, RenderPass, RenderPassDepthStencilAttachment};
+use wgpu::{
+    CommandBuffer, CommandEncoder, ComputePass, Operations, RenderPass,
+    RenderPassDepthStencilAttachment,
+};
 
 use crate::{
     core::{
-        ComputePipeline, RenderContext, RenderPipeline, buffer::Buffer, context::RenderOptions,
-        descriptor_set::DescriptorSet,
+        ComputePipeline, RenderContext, RenderPipeline,
+        buffer::{Buffer, DrawIndexedIndirectArgs},
+        command_log::{CommandLog, RecordedCommand},
+        context::RenderOptions,
+        descriptor_set::{DescriptorSet, StageFlags},
+        gpu_timer::FrameTiming,
+        texture::Texture,
     },
     render_graph::node::RenderTarget,
     types::vertex::VertexLayout,
@@ -17,9 +25,75 @@ use crate::{
 pub struct Frame<'a> {
     pub(crate) encoder: CommandEncoder,
     pub(crate) renderer: &'a RenderContext,
+    /// `None` when this frame isn't being timed
+    pub(crate) timing: Option<Arc<FrameTiming>>,
+    /// the render graph node the passes begun from now on are timed under
+    scope: String,
+    /// commands recorded before the ones in `encoder`, submitted first
+    pub(crate) finished: Vec<CommandBuffer>,
 }
 
-impl Frame<'_> {
+impl<'a> Frame<'a> {
+    pub(crate) fn new(
+        renderer: &'a RenderContext,
+        encoder: CommandEncoder,
+        timing: Option<FrameTiming>,
+    ) -> Self {
+        Self {
+            encoder,
+            renderer,
+            timing: timing.map(Arc::new),
+            scope: String::new(),
+            finished: Vec::new(),
+        }
+    }
+
+    /// the render graph node the passes begun from now on are timed under
+    pub(crate) fn set_scope(&mut self, scope: &str) {
+        self.scope.clear();
+        self.scope.push_str(scope);
+        self.renderer
+            .device()
+            .record(|| RecordedCommand::BeginNode(scope.into()));
+    }
+
+    /// a frame recording into its own encoder that can be recorded on another thread, its
+    /// commands are put back in order with [`Frame::join`]
+    pub(crate) fn split(&self) -> Frame<'a> {
+        let encoder =
+            self.renderer
+                .device()
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("node command encoder"),
+                });
+        Frame {
+            encoder,
+            renderer: self.renderer,
+            timing: self.timing.clone(),
+            scope: String::new(),
+            finished: Vec::new(),
+        }
+    }
+
+    /// run the commands of `frames` after the ones recorded so far, in the order given
+    pub(crate) fn join(&mut self, frames: impl IntoIterator<Item = Frame<'a>>) {
+        let next = self.split().encoder;
+        let encoder = std::mem::replace(&mut self.encoder, next);
+        self.finished.push(encoder.finish());
+        for frame in frames {
+            self.finished.extend(frame.finished);
+            self.finished.push(frame.encoder.finish());
+        }
+    }
+
+    /// the start and end queries of the next pass, `None` if it isn't timed
+    fn next_timestamps(&mut self) -> Option<(wgpu::QuerySet, u32)> {
+        let timing = self.timing.as_ref()?;
+        let start = timing.next_pass(&self.scope)?;
+        Some((timing.query_set.clone(), start))
+    }
+
     pub fn render<F>(&mut self, options: RenderOptions, execute: F) -> Result<()>
     where
         F: FnOnce(FrameBuilder),
@@ -54,6 +128,22 @@ impl Frame<'_> {
                     view: texture.inner.clone(),
                     resolve_view: Some(resolve.inner.clone()),
                 }),
+                RenderTarget::MultiSampledSurface { samples } => {
+                    let surface_tex = self.renderer.get_surface_texture().unwrap();
+                    let surface_view = surface_tex
+                        .texture
+                        .create_view(&wgpu::TextureViewDescriptor::default());
+                    match *samples > 1 {
+                        true => prepared.push(PreparedTarget {
+                            view: self.renderer.surface_msaa(*samples).create_view().inner,
+                            resolve_view: Some(surface_view),
+                        }),
+                        false => prepared.push(PreparedTarget {
+                            view: surface_view,
+                            resolve_view: None,
+                        }),
+                    }
+                }
             }
         }
 
@@ -69,7 +159,10 @@ impl Frame<'_> {
                             .clear_depth
                             .map(wgpu::LoadOp::Clear)
                             .unwrap_or(wgpu::LoadOp::Load),
-                        store: wgpu::StoreOp::Store,
+                        store: match options.discard_depth {
+                            true => wgpu::StoreOp::Discard,
+                            false => wgpu::StoreOp::Store,
+                        },
                     }),
                     stencil_ops: None,
                 });
@@ -97,15 +190,32 @@ impl Frame<'_> {
             })
             .collect();
 
+        let log = self.renderer.device().log.as_deref();
+        if let Some(log) = log {
+            log.push(RecordedCommand::BeginRender {
+                label: options.label.map(Into::into),
+                color_targets: options.color_targets.len(),
+                depth_target: options.depth_target.is_some(),
+            });
+        }
+
+        let timestamps = self.next_timestamps();
         let render_pass = self.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: options.label,
             color_attachments: &color_attachments,
             depth_stencil_attachment,
             occlusion_query_set: None,
-            timestamp_writes: None,
+            timestamp_writes: timestamps.as_ref().map(|(query_set, start)| {
+                let start = *start;
+                wgpu::RenderPassTimestampWrites {
+                    query_set,
+                    beginning_of_pass_write_index: Some(start),
+                    end_of_pass_write_index: Some(start + 1),
+                }
+            }),
         });
 
-        let frame_builder = FrameBuilder::new(render_pass);
+        let frame_builder = FrameBuilder::new(render_pass, log);
         // where we build the user command buffer pass in bound
         // automatically by frame builder
         execute(frame_builder);
@@ -119,16 +229,49 @@ impl Frame<'_> {
     where
         F: FnOnce(ComputeBuilder),
     {
+        let log = self.renderer.device().log.as_deref();
+        if let Some(log) = log {
+            log.push(RecordedCommand::BeginCompute {
+                label: label.map(Into::into),
+            });
+        }
+
+        let timestamps = self.next_timestamps();
         let compute_pass = self
             .encoder
             .begin_compute_pass(&wgpu::ComputePassDescriptor {
                 label,
-                timestamp_writes: None,
+                timestamp_writes: timestamps.as_ref().map(|(query_set, start)| {
+                    let start = *start;
+                    wgpu::ComputePassTimestampWrites {
+                        query_set,
+                        beginning_of_pass_write_index: Some(start),
+                        end_of_pass_write_index: Some(start + 1),
+                    }
+                }),
             });
 
-        let compute_builder = ComputeBuilder::new(compute_pass);
+        let compute_builder = ComputeBuilder::new(compute_pass, log);
         execute(compute_builder);
     }
+
+    /// copy the first mip of `source` into `destination`, both need the same size and format and
+    /// a sample count of 1
+    pub fn copy_texture(&mut self, source: &Texture, destination: &Texture) {
+        let (width, height) = (source.width(), source.height());
+        self.renderer
+            .device()
+            .record(|| RecordedCommand::CopyTexture { width, height });
+        self.encoder.copy_texture_to_texture(
+            source.inner.as_image_copy(),
+            destination.inner.as_image_copy(),
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
 }
 
 /// builder for a frame use this to bind buffers, descriptor sets, or anything else frame related
@@ -138,18 +281,27 @@ pub struct FrameBuilder<'encoder> {
     pub(crate) backend: RenderPass<'encoder>,
     index_count: u32,
     vertex_count: u32,
+    log: Option<&'encoder CommandLog>,
 }
 
 impl<'encoder> FrameBuilder<'encoder> {
-    pub(crate) fn new(backend: RenderPass<'encoder>) -> Self {
+    pub(crate) fn new(backend: RenderPass<'encoder>, log: Option<&'encoder CommandLog>) -> Self {
         FrameBuilder {
             backend,
             index_count: 0,
             vertex_count: 0,
+            log,
+        }
+    }
+
+    fn record(&self, command: impl FnOnce() -> RecordedCommand) {
+        if let Some(log) = self.log {
+            log.push(command());
         }
     }
 
     pub fn use_pipeline(&mut self, pipeline: &RenderPipeline) -> &mut Self {
+        self.record(|| RecordedCommand::UsePipeline(pipeline.id.clone()));
         self.backend.set_pipeline(&pipeline.backend);
 
         self
@@ -164,6 +316,9 @@ impl<'encoder> FrameBuilder<'encoder> {
             .set_vertex_buffer(0, vertex_buffer.buffer.slice(..));
 
         self.vertex_count = vertex_buffer.len() as u32;
+        self.record(|| RecordedCommand::BindVertexBuffer {
+            len: self.vertex_count,
+        });
 
         self
     }
@@ -173,18 +328,31 @@ impl<'encoder> FrameBuilder<'encoder> {
         self
     }
 
+    /// draw into a rectangle of the targets in pixels instead of all of them
+    pub fn set_viewport(&mut self, x: f32, y: f32, width: f32, height: f32) -> &mut Self {
+        self.backend.set_viewport(x, y, width, height, 0.0, 1.0);
+        self
+    }
+
     /// index buffer for the next draw_indexed call
     pub fn bind_index_buffer(&mut self, index_buffer: &Buffer<[u32]>) -> &mut Self {
         self.backend
             .set_index_buffer(index_buffer.buffer.slice(..), wgpu::IndexFormat::Uint32);
 
         self.index_count = index_buffer.len() as u32;
+        self.record(|| RecordedCommand::BindIndexBuffer {
+            len: self.index_count,
+        });
 
         self
     }
 
     // set a descriptor set must be in the pipeline layout
     pub fn bind_descriptor_set(&mut self, set: u32, descriptor_set: &DescriptorSet) -> &mut Self {
+        self.record(|| RecordedCommand::BindDescriptorSet {
+            set,
+            descriptor_set: descriptor_set.clone(),
+        });
         self.backend
             .set_bind_group(set, &descriptor_set.backend, &[]);
 
@@ -197,12 +365,44 @@ impl<'encoder> FrameBuilder<'encoder> {
         descriptor_set: &DescriptorSet,
         offsets: &[u32],
     ) -> &mut Self {
+        self.record(|| RecordedCommand::BindDescriptorSet {
+            set,
+            descriptor_set: descriptor_set.clone(),
+        });
         self.backend
             .set_bind_group(set, &descriptor_set.backend, offsets);
 
         self
     }
 
+    /// set the push constants read by `stages` to `bytes`, the pipeline layout must have a
+    /// [`crate::core::PushConstantRange`] for the stages. the length of `bytes` is a multiple of 4
+    ///
+    /// ```rust,ignore
+    /// pass.push_constants(StageFlags::VERTEX, bytemuck::bytes_of(&object_index))
+    ///     .draw_indexed(0..1);
+    /// ```
+    pub fn push_constants(&mut self, stages: StageFlags, bytes: &[u8]) -> &mut Self {
+        self.push_constants_at(stages, 0, bytes)
+    }
+
+    /// set the push constants read by `stages` starting `offset` bytes in
+    pub fn push_constants_at(
+        &mut self,
+        stages: StageFlags,
+        offset: u32,
+        bytes: &[u8],
+    ) -> &mut Self {
+        self.record(|| RecordedCommand::PushConstants {
+            offset,
+            size: bytes.len() as u32,
+        });
+        self.backend
+            .set_push_constants(stages.into(), offset, bytes);
+
+        self
+    }
+
     pub fn debug_marker(&mut self, label: &str) -> &mut Self {
         self.backend.insert_debug_marker(label);
 
@@ -211,18 +411,44 @@ impl<'encoder> FrameBuilder<'encoder> {
 
     /// draw the last bound indicies
     pub fn draw_indexed(&mut self, instances: Range<u32>) -> &mut Self {
+        self.record(|| RecordedCommand::DrawIndexed {
+            indices: 0..self.index_count,
+            instances: instances.clone(),
+        });
         self.backend.draw_indexed(0..self.index_count, 0, instances);
 
         self
     }
 
+    /// draw the last bound indicies with the arguments at `index` of `args`, usually written by
+    /// a compute shader
+    pub fn draw_indexed_indirect(
+        &mut self,
+        args: &Buffer<[DrawIndexedIndirectArgs]>,
+        index: usize,
+    ) -> &mut Self {
+        self.record(|| RecordedCommand::DrawIndexedIndirect { index });
+        let offset = (index * size_of::<DrawIndexedIndirectArgs>()) as u64;
+        self.backend.draw_indexed_indirect(&args.buffer, offset);
+
+        self
+    }
+
     pub fn draw_indexed_range(&mut self, index_range: Range<u32>) -> &mut Self {
+        self.record(|| RecordedCommand::DrawIndexed {
+            indices: index_range.clone(),
+            instances: 0..1,
+        });
         self.backend.draw_indexed(index_range, 0, 0..1);
         self
     }
 
     /// draw the last bound vertices
     pub fn draw_vertices(&mut self) -> &mut Self {
+        self.record(|| RecordedCommand::Draw {
+            vertices: 0..self.vertex_count,
+            instances: 0..1,
+        });
         self.backend.draw(0..self.vertex_count, 0..1);
 
         self
@@ -230,6 +456,10 @@ impl<'encoder> FrameBuilder<'encoder> {
 
     /// draw vertices with explicit vertex range (for vertex-less rendering like fullscreen triangles)
     pub fn draw(&mut self, vertices: std::ops::Range<u32>, instance: u32) -> &mut Self {
+        self.record(|| RecordedCommand::Draw {
+            vertices: vertices.clone(),
+            instances: instance..instance + 1,
+        });
         self.backend.draw(vertices, instance..instance + 1);
 
         self
@@ -238,30 +468,70 @@ impl<'encoder> FrameBuilder<'encoder> {
 
 pub struct ComputeBuilder<'encoder> {
     pub(crate) backend: ComputePass<'encoder>,
+    log: Option<&'encoder CommandLog>,
 }
 
 impl<'encoder> ComputeBuilder<'encoder> {
-    pub(crate) fn new(backend: ComputePass<'encoder>) -> Self {
-        Self { backend }
+    pub(crate) fn new(backend: ComputePass<'encoder>, log: Option<&'encoder CommandLog>) -> Self {
+        Self { backend, log }
+    }
+
+    fn record(&self, command: impl FnOnce() -> RecordedCommand) {
+        if let Some(log) = self.log {
+            log.push(command());
+        }
     }
 
     pub fn use_pipeline(&mut self, pipeline: &ComputePipeline) -> &mut Self {
+        self.record(|| RecordedCommand::UseComputePipeline);
         self.backend.set_pipeline(&pipeline.backend);
         self
     }
 
     pub fn bind_descriptor_set(&mut self, set: u32, descriptor_set: &DescriptorSet) -> &mut Self {
+        self.record(|| RecordedCommand::BindDescriptorSet {
+            set,
+            descriptor_set: descriptor_set.clone(),
+        });
         self.backend
             .set_bind_group(set, &descriptor_set.backend, &[]);
         self
     }
 
+    /// bind a set with dynamic bindings at `offsets`, one for each dynamic binding in order
+    pub fn bind_descriptor_set_with_offset(
+        &mut self,
+        set: u32,
+        descriptor_set: &DescriptorSet,
+        offsets: &[u32],
+    ) -> &mut Self {
+        self.record(|| RecordedCommand::BindDescriptorSet {
+            set,
+            descriptor_set: descriptor_set.clone(),
+        });
+        self.backend
+            .set_bind_group(set, &descriptor_set.backend, offsets);
+        self
+    }
+
+    /// set the push constants of the compute shader starting `offset` bytes in, see
+    /// [`FrameBuilder::push_constants`]
+    pub fn push_constants(&mut self, offset: u32, bytes: &[u8]) -> &mut Self {
+        self.record(|| RecordedCommand::PushConstants {
+            offset,
+            size: bytes.len() as u32,
+        });
+        self.backend.set_push_constants(offset, bytes);
+        self
+    }
+
     pub fn debug_marker(&mut self, label: &str) -> &mut Self {
         self.backend.insert_debug_marker(label);
         self
     }
 
     pub fn dispatch(&mut self, x: u32, y: u32, z: u32) -> &mut Self {
+        self.record(|| RecordedCommand::Dispatch { x, y, z });
         self.backend.dispatch_workgroups(x, y, z);
         self
     }
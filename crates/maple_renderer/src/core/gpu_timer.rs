@@ -0,0 +1,181 @@
+//! times render and compute passes on the gpu with timestamp queries
+//!
+//! every pass of a frame writes a timestamp when it starts and ends. the timestamps are copied to
+//! a buffer that is read a few frames later once the gpu has finished with it, so the times lag
+//! behind by a frame or two. frames drawn while the last read is still waiting aren't timed
+
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        atomic::{AtomicU8, Ordering},
+    },
+    time::Duration,
+};
+
+use parking_lot::Mutex;
+use wgpu::{
+    Buffer, BufferDescriptor, BufferUsages, CommandEncoder, Device, Features, MapMode, PollType,
+    QUERY_SIZE, QuerySet, QuerySetDescriptor, QueryType, Queue,
+};
+
+/// the most passes timed in a frame, later passes aren't timed
+const MAX_PASSES: u32 = 64;
+
+// states of the readback buffer mapping
+const PENDING: u8 = 0;
+const MAPPED: u8 = 1;
+const FAILED: u8 = 2;
+
+/// the timestamps of one frame being recorded, shared by the encoders the frame is recorded
+/// into
+pub(crate) struct FrameTiming {
+    pub(crate) query_set: QuerySet,
+    /// the scope of each pass in the order they were begun, each one has a start and end query
+    passes: Mutex<Vec<String>>,
+}
+
+impl FrameTiming {
+    /// the index of the start query of the next pass of `scope`, `None` once every query is used
+    pub(crate) fn next_pass(&self, scope: &str) -> Option<u32> {
+        let mut passes = self.passes.lock();
+        let pass = passes.len() as u32;
+        if pass >= MAX_PASSES {
+            return None;
+        }
+        passes.push(scope.to_string());
+        Some(pass * 2)
+    }
+}
+
+pub(crate) struct GpuTimer {
+    query_set: QuerySet,
+    resolve_buffer: Buffer,
+    readback_buffer: Buffer,
+    /// nanoseconds per timestamp tick
+    period: f32,
+    /// the scopes of the passes in the readback buffer while it is waiting to be read
+    reading: Option<Vec<String>>,
+    /// set by [`GpuTimer::resolve`] until the readback buffer is mapped
+    resolved: bool,
+    mapping: Arc<AtomicU8>,
+    latest: HashMap<String, Duration>,
+}
+
+impl GpuTimer {
+    /// the features a device needs for timing, requested when the adapter has them
+    pub(crate) const FEATURES: Features = Features::TIMESTAMP_QUERY;
+
+    /// `None` if the device can't write timestamps
+    pub(crate) fn new(device: &Device, queue: &Queue) -> Option<Self> {
+        if !device.features().contains(Self::FEATURES) {
+            return None;
+        }
+
+        let size = (MAX_PASSES * 2) as u64 * QUERY_SIZE as u64;
+        Some(Self {
+            query_set: device.create_query_set(&QuerySetDescriptor {
+                label: Some("pass timestamps"),
+                ty: QueryType::Timestamp,
+                count: MAX_PASSES * 2,
+            }),
+            resolve_buffer: device.create_buffer(&BufferDescriptor {
+                label: Some("pass timestamps resolve"),
+                size,
+                usage: BufferUsages::QUERY_RESOLVE | BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            readback_buffer: device.create_buffer(&BufferDescriptor {
+                label: Some("pass timestamps readback"),
+                size,
+                usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }),
+            period: queue.get_timestamp_period(),
+            reading: None,
+            resolved: false,
+            mapping: Arc::new(AtomicU8::new(PENDING)),
+            latest: HashMap::new(),
+        })
+    }
+
+    /// the time each scope took on the gpu in the last frame that was read
+    pub(crate) fn latest(&self) -> &HashMap<String, Duration> {
+        &self.latest
+    }
+
+    /// read the last timed frame if the gpu is done with it and start timing a new frame if
+    /// the readback buffer is free
+    pub(crate) fn begin_frame(&mut self, device: &Device) -> Option<FrameTiming> {
+        if self.reading.is_some() {
+            let _ = device.poll(PollType::Poll);
+            match self.mapping.swap(PENDING, Ordering::AcqRel) {
+                MAPPED => self.read(),
+                FAILED => self.reading = None,
+                _ => return None,
+            }
+        }
+
+        Some(FrameTiming {
+            query_set: self.query_set.clone(),
+            passes: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// copy the timestamps of `timing` into the readback buffer, call before the frame is
+    /// submitted
+    pub(crate) fn resolve(&mut self, encoder: &mut CommandEncoder, timing: &FrameTiming) {
+        let passes = std::mem::take(&mut *timing.passes.lock());
+        if passes.is_empty() {
+            return;
+        }
+
+        let queries = passes.len() as u32 * 2;
+        encoder.resolve_query_set(&self.query_set, 0..queries, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &self.readback_buffer,
+            0,
+            queries as u64 * QUERY_SIZE as u64,
+        );
+        self.reading = Some(passes);
+        self.resolved = true;
+    }
+
+    /// start reading the timestamps back, call after the frame is submitted
+    pub(crate) fn map(&mut self) {
+        if !std::mem::take(&mut self.resolved) {
+            return;
+        }
+        let mapping = self.mapping.clone();
+        self.readback_buffer
+            .slice(..)
+            .map_async(MapMode::Read, move |result| {
+                let state = match result {
+                    Ok(()) => MAPPED,
+                    Err(_) => FAILED,
+                };
+                mapping.store(state, Ordering::Release);
+            });
+    }
+
+    fn read(&mut self) {
+        let Some(passes) = self.reading.take() else {
+            return;
+        };
+
+        let mut latest: HashMap<String, Duration> = HashMap::new();
+        {
+            let view = self.readback_buffer.slice(..).get_mapped_range();
+            let timestamps: &[u64] = bytemuck::cast_slice(&view);
+            for (pass, scope) in passes.into_iter().enumerate() {
+                let (start, end) = (timestamps[pass * 2], timestamps[pass * 2 + 1]);
+                let nanos = end.saturating_sub(start) as f64 * self.period as f64;
+                *latest.entry(scope).or_default() += Duration::from_nanos(nanos as u64);
+            }
+        }
+        self.readback_buffer.unmap();
+        self.latest = latest;
+    }
+}
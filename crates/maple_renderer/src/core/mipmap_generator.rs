@@ -250,8 +250,8 @@ impl MipmapGenerator {
             return;
         };
 
-        // For cubemaps, we need to generate mipmaps for each face separately
-        for face in 0..6 {
+        // For cubemaps and arrays, we need to generate mipmaps for each layer separately
+        for face in 0..texture.depth_or_array_layers() {
             for src_mip in 0..(mip_level_count - 1) {
                 let dst_mip = src_mip + 1;
 
@@ -307,10 +307,11 @@ impl MipmapGenerator {
                     ],
                 });
 
-                let mip_size = texture.width() >> dst_mip;
                 let workgroup_size = 8;
-                let dispatch_x = mip_size.div_ceil(workgroup_size);
-                let dispatch_y = mip_size.div_ceil(workgroup_size);
+                let dispatch_x = (texture.width() >> dst_mip).max(1).div_ceil(workgroup_size);
+                let dispatch_y = (texture.height() >> dst_mip)
+                    .max(1)
+                    .div_ceil(workgroup_size);
 
                 let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
                     label: Some("Cubemap Mipmap Generation Pass"),
@@ -355,7 +356,12 @@ impl MipmapGenerator {
                 label: Some("Mipmap Generation"),
             });
 
-        self.generate_with_encoder(&self.device, &mut encoder, &texture.inner, mip_level_count);
+        self.generate_cubemap_with_encoder(
+            &self.device,
+            &mut encoder,
+            &texture.inner,
+            mip_level_count,
+        );
 
         self.queue.submit(std::iter::once(encoder.finish()));
     }
@@ -1,14 +1,22 @@
 pub mod buffer;
+pub mod command_log;
 pub mod context;
 pub mod descriptor_set;
 pub mod device;
 pub mod frame_builder;
+pub(crate) mod gpu_timer;
 pub mod mipmap_generator;
 pub mod pipeline;
 pub mod queue;
+// readback blocks on the gpu which the browser can't do
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod readback;
 pub mod renderer;
+pub mod ring_buffer;
 pub mod shader;
+pub mod shader_layout;
 pub mod texture;
+pub mod uniform_tweak;
 
 pub use buffer::*;
 pub use context::RenderContext;
@@ -18,4 +26,10 @@ pub use frame_builder::*;
 pub use pipeline::*;
 pub use queue::*;
 pub use renderer::*;
+pub use ring_buffer::RingBuffer;
 pub use shader::*;
+pub use shader_layout::{
+    LayoutError, ParamField, ShaderLayout, ShaderParams, UniformBlock, UniformKind, UniformMember,
+    UniformValue,
+};
+pub use uniform_tweak::{UniformTweak, uniform_tweaks};
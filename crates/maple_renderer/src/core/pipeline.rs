@@ -1,4 +1,4 @@
-use std::hash::Hash;
+use std::{hash::Hash, ops::Range};
 
 use maple_engine::asset::AssetId;
 use wgpu::{
@@ -25,7 +25,11 @@ impl From<CullMode> for Option<Face> {
 }
 
 use crate::{
-    core::{ComputeShader, descriptor_set::DescriptorSetLayout, shader::GraphicsShader},
+    core::{
+        ComputeShader,
+        descriptor_set::{DescriptorSetLayout, StageFlags},
+        shader::GraphicsShader,
+    },
     render_graph::node::DepthMode,
 };
 
@@ -34,15 +38,50 @@ pub struct PipelineLayout {
     pub(crate) backend: wgpu::PipelineLayout,
 }
 
+/// bytes of a pipeline layout set with [`crate::core::FrameBuilder::push_constants`] instead of
+/// a buffer, for small data that changes every draw such as an object index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushConstantRange {
+    /// the shader stages that read the bytes, a stage can only be in one range
+    pub stages: StageFlags,
+    /// the bytes of the range, both ends are a multiple of 4
+    pub range: Range<u32>,
+}
+
+impl PushConstantRange {
+    /// `size` bytes from the start read by `stages`
+    pub fn new(stages: StageFlags, size: u32) -> Self {
+        Self {
+            stages,
+            range: 0..size,
+        }
+    }
+}
+
 impl PipelineLayout {
     pub fn create(device: &Device, descriptor_set_layout: &[DescriptorSetLayout]) -> Self {
+        Self::create_with_push_constants(device, descriptor_set_layout, &[])
+    }
+
+    pub fn create_with_push_constants(
+        device: &Device,
+        descriptor_set_layout: &[DescriptorSetLayout],
+        push_constants: &[PushConstantRange],
+    ) -> Self {
         let binding_layouts: Vec<&BindGroupLayout> =
             descriptor_set_layout.iter().map(|d| &d.backend).collect();
+        let push_constant_ranges: Vec<wgpu::PushConstantRange> = push_constants
+            .iter()
+            .map(|range| wgpu::PushConstantRange {
+                stages: range.stages.into(),
+                range: range.range.clone(),
+            })
+            .collect();
 
         let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
             label: None,
             bind_group_layouts: &binding_layouts,
-            push_constant_ranges: &[],
+            push_constant_ranges: &push_constant_ranges,
         });
 
         PipelineLayout { backend: layout }
@@ -0,0 +1,207 @@
+//! copies textures back from the gpu so they can be saved while debugging
+
+use anyhow::{Result, anyhow};
+use image::RgbaImage;
+use wgpu::{
+    BufferDescriptor, BufferUsages, COPY_BYTES_PER_ROW_ALIGNMENT, CommandEncoderDescriptor,
+    Extent3d, MapMode, Origin3d, PollType, TexelCopyBufferInfo, TexelCopyBufferLayout,
+    TexelCopyTextureInfo, TextureAspect, TextureFormat, TextureUsages,
+};
+
+use crate::core::RenderDevice;
+
+/// read the first mip of one layer of a texture into an 8 bit image
+///
+/// float formats are clamped to 0..1 and depth is stretched so the closest and furthest depth in
+/// the texture are black and white. this blocks until the gpu is done with every submitted frame
+pub(crate) fn read_layer(
+    device: &RenderDevice,
+    texture: &wgpu::Texture,
+    layer: u32,
+) -> Result<RgbaImage> {
+    let format = texture.format();
+    let (width, height) = (texture.width(), texture.height());
+    let bytes = read_layer_bytes(device, texture, layer)?;
+    let texel_size = bytes.len() / (width * height) as usize;
+
+    let texels = bytes
+        .chunks(texel_size)
+        .map(|texel| decode(format, texel))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| anyhow!("{format:?} textures can't be saved"))?;
+
+    let texels = match format.has_depth_aspect() {
+        true => stretch_depth(texels),
+        false => texels,
+    };
+
+    let mut image = RgbaImage::new(width, height);
+    for (pixel, texel) in image.pixels_mut().zip(texels) {
+        pixel.0 = texel.map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8);
+    }
+    Ok(image)
+}
+
+/// the texels of the first mip of one layer in the texture's format with the rows tightly
+/// packed, blocks like [`read_layer`]
+pub(crate) fn read_layer_bytes(
+    device: &RenderDevice,
+    texture: &wgpu::Texture,
+    layer: u32,
+) -> Result<Vec<u8>> {
+    if texture.sample_count() > 1 {
+        return Err(anyhow!(
+            "multisampled textures can't be copied, read the resolved texture instead"
+        ));
+    }
+    if !texture.usage().contains(TextureUsages::COPY_SRC) {
+        return Err(anyhow!("texture wasn't created with COPY_SRC"));
+    }
+
+    let format = texture.format();
+    let aspect = match format.has_depth_aspect() {
+        true => TextureAspect::DepthOnly,
+        false => TextureAspect::All,
+    };
+    let texel_size = format
+        .block_copy_size(Some(aspect))
+        .ok_or_else(|| anyhow!("{format:?} textures can't be copied"))?;
+
+    let (width, height) = (texture.width(), texture.height());
+    let row_size = width * texel_size;
+    let padded_row_size =
+        row_size.div_ceil(COPY_BYTES_PER_ROW_ALIGNMENT) * COPY_BYTES_PER_ROW_ALIGNMENT;
+
+    let buffer = device.device.create_buffer(&BufferDescriptor {
+        label: Some("texture readback"),
+        size: (padded_row_size * height) as u64,
+        usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device
+        .device
+        .create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("texture readback"),
+        });
+    encoder.copy_texture_to_buffer(
+        TexelCopyTextureInfo {
+            texture,
+            mip_level: 0,
+            origin: Origin3d {
+                x: 0,
+                y: 0,
+                z: layer,
+            },
+            aspect,
+        },
+        TexelCopyBufferInfo {
+            buffer: &buffer,
+            layout: TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row_size),
+                rows_per_image: Some(height),
+            },
+        },
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+    );
+    device.queue.submit([encoder.finish()]);
+
+    let slice = buffer.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    device.device.poll(PollType::Wait)?;
+    receiver.recv()??;
+
+    let bytes = {
+        let data = slice.get_mapped_range();
+        let mut bytes = Vec::with_capacity((row_size * height) as usize);
+        for row in data.chunks(padded_row_size as usize) {
+            bytes.extend_from_slice(&row[..row_size as usize]);
+        }
+        bytes
+    };
+    buffer.unmap();
+    Ok(bytes)
+}
+
+/// one texel as rgba floats, missing channels are 0 and missing alpha is 1
+fn decode(format: TextureFormat, texel: &[u8]) -> Option<[f32; 4]> {
+    let unorm8 = |i: usize| texel[i] as f32 / 255.0;
+    let unorm16 = |i: usize| u16::from_le_bytes([texel[i * 2], texel[i * 2 + 1]]) as f32 / 65535.0;
+    let half = |i: usize| f16_to_f32(u16::from_le_bytes([texel[i * 2], texel[i * 2 + 1]]));
+    let float = |i: usize| {
+        f32::from_le_bytes([
+            texel[i * 4],
+            texel[i * 4 + 1],
+            texel[i * 4 + 2],
+            texel[i * 4 + 3],
+        ])
+    };
+
+    Some(match format {
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => {
+            [unorm8(0), unorm8(1), unorm8(2), unorm8(3)]
+        }
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => {
+            [unorm8(2), unorm8(1), unorm8(0), unorm8(3)]
+        }
+        TextureFormat::R8Unorm => [unorm8(0), unorm8(0), unorm8(0), 1.0],
+        TextureFormat::Rg8Unorm => [unorm8(0), unorm8(1), 0.0, 1.0],
+        TextureFormat::R16Unorm => [unorm16(0), unorm16(0), unorm16(0), 1.0],
+        TextureFormat::Rg16Unorm => [unorm16(0), unorm16(1), 0.0, 1.0],
+        TextureFormat::Rgba16Unorm => [unorm16(0), unorm16(1), unorm16(2), unorm16(3)],
+        TextureFormat::R16Float => [half(0), half(0), half(0), 1.0],
+        TextureFormat::Rg16Float => [half(0), half(1), 0.0, 1.0],
+        TextureFormat::Rgba16Float => [half(0), half(1), half(2), half(3)],
+        TextureFormat::R32Float => [float(0), float(0), float(0), 1.0],
+        TextureFormat::Rg32Float => [float(0), float(1), 0.0, 1.0],
+        TextureFormat::Rgba32Float => [float(0), float(1), float(2), float(3)],
+        TextureFormat::Depth32Float | TextureFormat::Depth32FloatStencil8 => {
+            [float(0), float(0), float(0), 1.0]
+        }
+        _ => return None,
+    })
+}
+
+/// spread the depth values in the texture over 0..1 so the depth is visible
+fn stretch_depth(mut texels: Vec<[f32; 4]>) -> Vec<[f32; 4]> {
+    let (min, max) = texels
+        .iter()
+        .map(|texel| texel[0])
+        .filter(|depth| depth.is_finite())
+        .fold((f32::MAX, f32::MIN), |(min, max), depth| {
+            (min.min(depth), max.max(depth))
+        });
+
+    let range = (max - min).max(f32::EPSILON);
+    for texel in &mut texels {
+        let depth = (texel[0] - min) / range;
+        *texel = [depth, depth, depth, 1.0];
+    }
+    texels
+}
+
+fn f16_to_f32(bits: u16) -> f32 {
+    let negative = bits & 0x8000 != 0;
+    let exponent = ((bits >> 10) & 0x1f) as u32;
+    let fraction = (bits & 0x3ff) as u32;
+
+    let value = match exponent {
+        // subnormals
+        0 => fraction as f32 * 2f32.powi(-24),
+        0x1f => f32::from_bits(0x7f80_0000 | (fraction << 13)),
+        _ => f32::from_bits(((exponent + 112) << 23) | (fraction << 13)),
+    };
+
+    match negative {
+        true => -value,
+        false => value,
+    }
+}
@@ -7,8 +7,14 @@ use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
 
 use crate::{
     core::RenderContext,
-    render_graph::graph::{GraphBuilder, RenderGraph},
-    types::{Dimensions, render_config::RenderConfig},
+    render_graph::{
+        description::GraphDescription,
+        graph::{GraphBuilder, RenderGraph},
+    },
+    types::{
+        Dimensions, post_effects::PostEffects, quality::QualitySettings,
+        render_config::RenderConfig,
+    },
 };
 
 // TODO create a render context to avoid passing itself to the graph
@@ -18,6 +24,10 @@ use crate::{
 pub struct Renderer {
     pub context: RenderContext,
     pub render_graph: RenderGraph,
+    graph_description: Option<GraphDescription>,
+    /// where to save the next frame, see [`Renderer::screenshot`]
+    #[cfg(not(target_arch = "wasm32"))]
+    screenshots: Vec<std::path::PathBuf>,
 }
 
 impl Renderer {
@@ -31,6 +41,8 @@ impl Renderer {
         Ok(Renderer {
             context,
             render_graph: RenderGraph::default(),
+            graph_description: None,
+            screenshots: Vec::new(),
         })
     }
 
@@ -38,12 +50,13 @@ impl Renderer {
     #[cfg(target_arch = "wasm32")]
     pub async fn init_async<T>(window: Arc<T>, config: RenderConfig) -> Result<Self>
     where
-        T: HasWindowHandle + HasDisplayHandle + 'static,
+        T: HasWindowHandle + HasDisplayHandle + SendSync + 'static,
     {
         let context = RenderContext::init(window, config).await?;
         Ok(Renderer {
             context,
             render_graph: RenderGraph::default(),
+            graph_description: None,
         })
     }
 
@@ -54,6 +67,8 @@ impl Renderer {
         Ok(Renderer {
             context,
             render_graph: RenderGraph::default(),
+            graph_description: None,
+            screenshots: Vec::new(),
         })
     }
 
@@ -64,6 +79,7 @@ impl Renderer {
         Ok(Renderer {
             context,
             render_graph: RenderGraph::default(),
+            graph_description: None,
         })
     }
 
@@ -74,22 +90,138 @@ impl Renderer {
         self.context.attach_surface(window, dimensions)
     }
 
+    /// drop the window surface while the app is in the background, see
+    /// [`RenderContext::detach_surface`]. nothing is drawn until a surface is attached again
+    pub fn detach_surface(&mut self) {
+        self.context.detach_surface();
+    }
+
     /// resize the surface as well as render_passes that might need that
     pub fn resize(&mut self, dimensions: Dimensions) {
         self.context.resize(dimensions);
         self.render_graph.resize(&self.context, dimensions);
     }
 
+    /// the post processing applied after the main pass and before ui, changes show up the next
+    /// frame
+    pub fn post_effects(&mut self) -> &mut PostEffects {
+        &mut self.context.post_effects
+    }
+
+    /// render with `quality` from the next frame on, scene targets are recreated when the render
+    /// scale changes and the post effects are replaced by the ones in `quality`
+    ///
+    /// pipelines are built for the msaa samples so they can't change once a node was added to the
+    /// graph, the new count is used the next time the game starts
+    pub fn set_quality(&mut self, mut quality: QualitySettings) {
+        if !self.render_graph.is_empty() {
+            quality.msaa_samples = self.context.quality.msaa_samples;
+        }
+        let rescale = quality.render_scale != self.context.quality.render_scale;
+        self.context.post_effects = quality.post_effects;
+        self.context.quality = quality;
+        if rescale {
+            self.render_graph
+                .resize(&self.context, self.context.surface_size());
+        }
+    }
+
+    /// the graph plugins should build instead of their own, `None` if the app wasn't given one
+    pub fn graph_description(&self) -> Option<&GraphDescription> {
+        self.graph_description.as_ref()
+    }
+
+    /// set the graph plugins build when the app starts, see
+    /// [`crate::render_graph::description`]
+    pub fn set_graph_description(&mut self, description: Option<GraphDescription>) {
+        self.graph_description = description;
+    }
+
     pub fn graph(&mut self) -> GraphBuilder<'_> {
         GraphBuilder::create(self)
     }
 
+    /// acquire the surface texture of the next frame ahead of time
+    ///
+    /// this waits for the swapchain so calling it at the end of a frame moves that wait before
+    /// the input of the next frame is handled. [`Renderer::begin_draw`] then renders into the
+    /// texture acquired here
+    pub fn prepare_next_frame(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.context.can_present() {
+            self.context.acquire_surface_texture()?;
+        }
+        Ok(())
+    }
+
+    /// save every texture target in the render graph as a png, see [`RenderGraph::dump_targets`]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn dump_targets(
+        &self,
+        dir: impl AsRef<std::path::Path>,
+    ) -> Result<Vec<std::path::PathBuf>> {
+        self.render_graph.dump_targets(&self.context, dir)
+    }
+
+    /// save the next frame drawn to the surface as an image at `path`, the format is picked from
+    /// the extension. the image is read after the render graph ran and before it is presented so
+    /// it is exactly what is shown
+    ///
+    /// ```rust,ignore
+    /// if input.key_just_pressed.contains(&KeyCode::F12) {
+    ///     renderer.screenshot("screenshots/latest.png");
+    /// }
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn screenshot(&mut self, path: impl Into<std::path::PathBuf>) {
+        self.screenshots.push(path.into());
+    }
+
+    /// save the surface texture for the screenshots asked for, a failed screenshot is logged
+    /// and the frame is still presented
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_screenshots(&mut self) {
+        if self.screenshots.is_empty() {
+            return;
+        }
+        let image = match self.context.read_surface() {
+            Ok(image) => image,
+            Err(err) => {
+                log::error!("failed to read the surface for a screenshot: {err}");
+                self.screenshots.clear();
+                return;
+            }
+        };
+        for path in self.screenshots.drain(..) {
+            if let Some(dir) = path.parent() {
+                let _ = std::fs::create_dir_all(dir);
+            }
+            match image.save(&path) {
+                Ok(()) => log::info!("saved screenshot to {}", path.display()),
+                Err(err) => log::error!("failed to save screenshot {}: {err}", path.display()),
+            }
+        }
+    }
+
+    /// run the render graph once without a surface, for headless renderers whose nodes only draw
+    /// into textures
+    pub fn draw_offscreen(&mut self, ctx: &GameContext) -> Result<()> {
+        self.render_graph.render(&self.context, ctx)
+    }
+
     /// begins the render passes within the render graph patent pending
+    ///
+    /// does nothing without a surface to draw to
     pub fn begin_draw(&mut self, ctx: &GameContext) -> Result<(), Box<dyn Error>> {
+        if !self.context.can_present() {
+            return Ok(());
+        }
         self.context.acquire_surface_texture()?;
 
         self.render_graph.render(&self.context, ctx)?;
 
+        #[cfg(not(target_arch = "wasm32"))]
+        self.save_screenshots();
+
         self.context.present_surface()?;
 
         Ok(())
@@ -0,0 +1,167 @@
+//! space for per object data that changes size every frame
+//!
+//! passes drawing a different number of objects each frame would otherwise need a buffer big
+//! enough for each object and a descriptor set to go with it. a [`RingBuffer`] is one buffer
+//! bound once with a dynamic offset, each object's data is pushed into the next free space and
+//! the offset it returns is given when the set is bound:
+//!
+//! ```rust,ignore
+//! // setup, the layout uses DescriptorBindingType::DynamicUniformBuffer
+//! let objects = rcx.device().create_ring_buffer("objects", 1 << 20);
+//! let set = rcx.device().build_descriptor_set(
+//!     DescriptorSet::builder(&layout).ring(0, &objects, size_of::<ObjectData>() as u64),
+//! );
+//!
+//! // draw
+//! objects.reset();
+//! for object in &visible {
+//!     let Some(offset) = objects.push(&object.data()) else { break };
+//!     pass.bind_descriptor_set_with_offset(1, &set, &[offset]).draw_indexed(0..1);
+//! }
+//! ```
+//!
+//! the data is written with the queue so it reaches the gpu before the frame's commands run,
+//! which makes it safe to start again from the beginning every frame while the last frame is
+//! still being drawn.
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+
+use bytemuck::Pod;
+use wgpu::{BufferUsages, COPY_BUFFER_ALIGNMENT, Device, Queue};
+
+use crate::core::{RenderDevice, buffer::Buffer};
+
+/// a buffer handed out in aligned pieces once a frame, see the [module docs](self)
+#[derive(Debug)]
+pub struct RingBuffer {
+    buffer: Buffer<[u8]>,
+    queue: Arc<Queue>,
+    label: &'static str,
+    /// the offsets given must be a multiple of this for uniform and storage bindings
+    alignment: u64,
+    /// where the next push starts
+    cursor: AtomicU64,
+    /// the most bytes a descriptor set binds at a pushed offset, every push leaves room for it
+    binding_size: AtomicU64,
+}
+
+impl RingBuffer {
+    pub(crate) fn new(
+        device: &Device,
+        queue: Arc<Queue>,
+        label: &'static str,
+        capacity: u64,
+    ) -> Self {
+        let limits = device.limits();
+        let alignment = limits
+            .min_uniform_buffer_offset_alignment
+            .max(limits.min_storage_buffer_offset_alignment)
+            .max(COPY_BUFFER_ALIGNMENT as u32) as u64;
+
+        Self {
+            buffer: Self::create_buffer(device, label, capacity.max(alignment)),
+            queue,
+            label,
+            alignment,
+            cursor: AtomicU64::new(0),
+            binding_size: AtomicU64::new(0),
+        }
+    }
+
+    fn create_buffer(device: &Device, label: &str, capacity: u64) -> Buffer<[u8]> {
+        Buffer::from_size(
+            device,
+            capacity as usize,
+            BufferUsages::UNIFORM | BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            label,
+        )
+    }
+
+    /// the buffer the pieces are in, for binding it in a descriptor set
+    pub fn buffer(&self) -> &Buffer<[u8]> {
+        &self.buffer
+    }
+
+    /// the offsets [`RingBuffer::push`] returns are a multiple of this
+    pub fn alignment(&self) -> u64 {
+        self.alignment
+    }
+
+    pub fn capacity(&self) -> u64 {
+        self.buffer.byte_size()
+    }
+
+    /// the bytes pushed since the last [`RingBuffer::reset`]
+    pub fn used(&self) -> u64 {
+        self.cursor.load(Ordering::Relaxed).min(self.capacity())
+    }
+
+    /// the bytes a descriptor set binds at each offset, see [`RingBuffer::set_binding_size`]
+    pub fn binding_size(&self) -> u64 {
+        self.binding_size.load(Ordering::Relaxed)
+    }
+
+    /// refuse pushes that leave less than `size` bytes from their offset to the end of the
+    /// buffer. the gpu reads the whole binding at an offset even when less was pushed, this is
+    /// set by [`crate::core::descriptor_set::DescriptorSetBuilder::ring`] and only grows
+    pub fn set_binding_size(&self, size: u64) {
+        self.binding_size.fetch_max(size, Ordering::Relaxed);
+    }
+
+    /// start again from the beginning, call once a frame before the first push
+    pub fn reset(&self) {
+        self.cursor.store(0, Ordering::Relaxed);
+    }
+
+    /// write `value` into the next free space and return its offset, `None` if it or the
+    /// [`RingBuffer::binding_size`] at its offset doesn't fit
+    pub fn push<T: Pod>(&self, value: &T) -> Option<u32> {
+        self.push_bytes(bytemuck::bytes_of(value))
+    }
+
+    /// write `data` into the next free space and return its offset, `None` if it doesn't fit.
+    /// for storage bindings of a variable size
+    pub fn push_slice<T: Pod>(&self, data: &[T]) -> Option<u32> {
+        self.push_bytes(bytemuck::cast_slice(data))
+    }
+
+    fn push_bytes(&self, bytes: &[u8]) -> Option<u32> {
+        if bytes.is_empty() {
+            return None;
+        }
+        // padded so the next piece starts aligned, pushes can come from several threads
+        let size = (bytes.len() as u64).next_multiple_of(self.alignment);
+        let offset = self.cursor.fetch_add(size, Ordering::Relaxed);
+        // the queue only copies whole words
+        let written = (bytes.len() as u64).next_multiple_of(COPY_BUFFER_ALIGNMENT);
+        if offset + written.max(self.binding_size()) > self.capacity() {
+            return None;
+        }
+
+        match written == bytes.len() as u64 {
+            true => self.queue.write_buffer(&self.buffer.buffer, offset, bytes),
+            false => {
+                let mut padded = bytes.to_vec();
+                padded.resize(written as usize, 0);
+                self.queue
+                    .write_buffer(&self.buffer.buffer, offset, &padded);
+            }
+        }
+        Some(offset as u32)
+    }
+
+    /// make room for at least `capacity` bytes, returns true if the buffer was replaced and the
+    /// descriptor sets using it have to be built again. what was pushed this frame is lost
+    pub fn reserve(&mut self, device: &RenderDevice, capacity: u64) -> bool {
+        if capacity <= self.capacity() {
+            return false;
+        }
+        let capacity = capacity.next_power_of_two();
+        self.buffer = Self::create_buffer(&device.device, self.label, capacity);
+        self.reset();
+        true
+    }
+}
@@ -1,6 +1,6 @@
 use wgpu::{Device, ShaderModule, ShaderStages};
 
-use crate::shader_asset::Shader;
+use crate::{shader_asset::Shader, shader_include::resolve_includes};
 
 // #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 // pub struct GraphicsShader {
@@ -66,10 +66,14 @@ impl ComputeShader {
         source: ComputeShaderSource<'_>,
         label: Option<&str>,
     ) -> Self {
+        // there is no error to return so a missing include panics like an invalid shader does
+        let resolve = |code| {
+            resolve_includes(code).unwrap_or_else(|err| panic!("compute shader include: {err}"))
+        };
         let shader_source = match source {
-            ComputeShaderSource::Wgsl(code) => wgpu::ShaderSource::Wgsl(code.into()),
+            ComputeShaderSource::Wgsl(code) => wgpu::ShaderSource::Wgsl(resolve(code)),
             ComputeShaderSource::Glsl(code) => wgpu::ShaderSource::Glsl {
-                shader: code.into(),
+                shader: resolve(code),
                 stage: wgpu::naga::ShaderStage::Compute,
                 defines: &[],
             },
@@ -0,0 +1,650 @@
+//! checks that a rust struct is laid out the way a shader reads it
+//!
+//! uniform and storage blocks follow std140/std430 style rules where a `vec3` is aligned like a
+//! `vec4` and arrays in uniforms are padded to 16 bytes. a rust struct that doesn't add the same
+//! padding by hand uploads fine but the shader reads garbage. derive [`ShaderParams`] with
+//! `maple::derive::ShaderParams` and check it against the block the shader declares:
+//!
+//! ```rust,ignore
+//! use maple::renderer::core::{ShaderLayout, ShaderParams};
+//!
+//! #[repr(C)]
+//! #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, ShaderParams)]
+//! struct Params {
+//!     color: [f32; 3],
+//!     _padding: f32,
+//!     time: f32,
+//! }
+//!
+//! let layout = ShaderLayout::from_source(include_str!("params.wgsl"))?;
+//! layout.check_binding::<Params>(0, 0)?;
+//! ```
+//!
+//! fields are matched to shader members by name. fields and members that start with `_` or are
+//! `pad` followed by digits (`pad`, `pad0`, ..) are treated as padding and only have to exist on
+//! one side
+
+use thiserror::Error;
+use wgpu::naga;
+
+use crate::{
+    shader_asset::{EmbeddedSource, ShaderSource},
+    shader_include::{IncludeError, resolve_includes},
+};
+
+/// a field of a [`ShaderParams`] struct
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParamField {
+    pub name: &'static str,
+    /// the rust type as it was written
+    pub ty: &'static str,
+    pub offset: usize,
+    pub size: usize,
+}
+
+impl ParamField {
+    /// true if the field only exists to pad the struct
+    pub fn is_padding(&self) -> bool {
+        is_padding(self.name)
+    }
+}
+
+fn is_padding(name: &str) -> bool {
+    name.starts_with('_')
+        || name
+            .strip_prefix("pad")
+            .is_some_and(|rest| rest.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// a `#[repr(C)]` struct that is uploaded to a shader. derive it with `maple::derive::ShaderParams`
+pub trait ShaderParams: bytemuck::Pod {
+    const FIELDS: &'static [ParamField];
+}
+
+#[derive(Debug, Error)]
+pub enum LayoutError {
+    #[error("shader failed to parse: {details}")]
+    Parse { details: String },
+    #[error("shader include failed: {0}")]
+    Include(#[from] IncludeError),
+    #[error("shader failed to validate: {details}")]
+    Validation { details: String },
+    #[error("no struct named `{name}` in the shader")]
+    MissingStruct { name: String },
+    #[error("nothing is bound at group {group} binding {binding} in the shader")]
+    MissingBinding { group: u32, binding: u32 },
+    #[error("the `{name}` block isn't a struct")]
+    NotAStruct { name: String },
+    #[error("`{block}` has a `{member}` member the rust struct doesn't have")]
+    MissingField { block: String, member: String },
+    #[error(
+        "`{field}` isn't in `{block}`, name it `_{field}` if it's padding or remove it if it's unused"
+    )]
+    ExtraField { block: String, field: String },
+    #[error("`{field}` is at byte {rust} in rust but the shader reads it at byte {shader}")]
+    Offset {
+        field: String,
+        rust: usize,
+        shader: usize,
+    },
+    #[error("`{field}` is {rust} bytes in rust but {shader} bytes in the shader")]
+    Size {
+        field: String,
+        rust: usize,
+        shader: usize,
+    },
+    #[error("the rust struct is {rust} bytes but `{block}` is {shader} bytes")]
+    StructSize {
+        block: String,
+        rust: usize,
+        shader: usize,
+    },
+}
+
+/// the type of a [`UniformMember`] that can be read and written as a [`UniformValue`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum UniformKind {
+    F32,
+    Vec2,
+    Vec3,
+    Vec4,
+    I32,
+    U32,
+}
+
+impl UniformKind {
+    pub fn size(self) -> usize {
+        match self {
+            UniformKind::F32 | UniformKind::I32 | UniformKind::U32 => 4,
+            UniformKind::Vec2 => 8,
+            UniformKind::Vec3 => 12,
+            UniformKind::Vec4 => 16,
+        }
+    }
+}
+
+/// a value of one of the [`UniformKind`]s
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UniformValue {
+    F32(f32),
+    Vec2([f32; 2]),
+    Vec3([f32; 3]),
+    Vec4([f32; 4]),
+    I32(i32),
+    U32(u32),
+}
+
+impl UniformValue {
+    pub fn kind(&self) -> UniformKind {
+        match self {
+            UniformValue::F32(_) => UniformKind::F32,
+            UniformValue::Vec2(_) => UniformKind::Vec2,
+            UniformValue::Vec3(_) => UniformKind::Vec3,
+            UniformValue::Vec4(_) => UniformKind::Vec4,
+            UniformValue::I32(_) => UniformKind::I32,
+            UniformValue::U32(_) => UniformKind::U32,
+        }
+    }
+
+    /// read a value of `kind` from the start of `bytes`, `None` if there aren't enough bytes
+    pub fn read(kind: UniformKind, bytes: &[u8]) -> Option<Self> {
+        let bytes = bytes.get(..kind.size())?;
+        Some(match kind {
+            UniformKind::F32 => UniformValue::F32(bytemuck::pod_read_unaligned(bytes)),
+            UniformKind::Vec2 => UniformValue::Vec2(bytemuck::pod_read_unaligned(bytes)),
+            UniformKind::Vec3 => UniformValue::Vec3(bytemuck::pod_read_unaligned(bytes)),
+            UniformKind::Vec4 => UniformValue::Vec4(bytemuck::pod_read_unaligned(bytes)),
+            UniformKind::I32 => UniformValue::I32(bytemuck::pod_read_unaligned(bytes)),
+            UniformKind::U32 => UniformValue::U32(bytemuck::pod_read_unaligned(bytes)),
+        })
+    }
+
+    /// the bytes the shader reads for the value
+    pub fn bytes(&self) -> Vec<u8> {
+        match self {
+            UniformValue::F32(value) => bytemuck::bytes_of(value).to_vec(),
+            UniformValue::Vec2(value) => bytemuck::bytes_of(value).to_vec(),
+            UniformValue::Vec3(value) => bytemuck::bytes_of(value).to_vec(),
+            UniformValue::Vec4(value) => bytemuck::bytes_of(value).to_vec(),
+            UniformValue::I32(value) => bytemuck::bytes_of(value).to_vec(),
+            UniformValue::U32(value) => bytemuck::bytes_of(value).to_vec(),
+        }
+    }
+}
+
+/// a member of a [`UniformBlock`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UniformMember {
+    pub name: String,
+    pub offset: usize,
+    pub size: usize,
+    /// `None` for matrices, arrays and structs which can't be edited as a single value
+    pub kind: Option<UniformKind>,
+}
+
+impl UniformMember {
+    /// true if the member only exists to pad the block
+    pub fn is_padding(&self) -> bool {
+        is_padding(&self.name)
+    }
+}
+
+/// a `var<uniform>` struct or GLSL uniform block found by [`ShaderLayout::uniform_blocks`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UniformBlock {
+    /// the name of the struct, or of the variable if the struct has none
+    pub name: String,
+    pub group: u32,
+    pub binding: u32,
+    pub size: usize,
+    pub members: Vec<UniformMember>,
+}
+
+impl UniformBlock {
+    pub fn member(&self, name: &str) -> Option<&UniformMember> {
+        self.members.iter().find(|member| member.name == name)
+    }
+}
+
+/// the structs and bindings a shader declares, parsed once so several structs can be checked
+pub struct ShaderLayout {
+    module: naga::Module,
+}
+
+impl ShaderLayout {
+    /// parse and validate a shader
+    ///
+    /// validation catches uniform rules that can't be matched from rust at all, like an
+    /// `array<f32, N>` in a uniform which needs a 16 byte stride
+    pub fn from_source(source: impl Into<ShaderSource>) -> Result<Self, LayoutError> {
+        let parse_error = |details: String| LayoutError::Parse { details };
+
+        let (module, text) = match source.into().source {
+            EmbeddedSource::Wgsl(code) => {
+                let code = resolve_includes(code)?;
+                let module = naga::front::wgsl::parse_str(&code)
+                    .map_err(|err| parse_error(err.emit_to_string(&code)))?;
+                (module, code)
+            }
+            EmbeddedSource::Glsl { source, stage } => {
+                let source = resolve_includes(source)?;
+                let stage: naga::ShaderStage = stage.into();
+                let module = naga::front::glsl::Frontend::default()
+                    .parse(&stage.into(), &source)
+                    .map_err(|err| parse_error(err.emit_to_string(&source)))?;
+                (module, source)
+            }
+            EmbeddedSource::Spirv(bytes) => (
+                naga::front::spv::parse_u8_slice(bytes, &Default::default())
+                    .map_err(|err| parse_error(err.to_string()))?,
+                "".into(),
+            ),
+        };
+
+        naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::all(),
+        )
+        .validate(&module)
+        .map_err(|err| LayoutError::Validation {
+            details: err.emit_to_string(&text),
+        })?;
+
+        Ok(Self { module })
+    }
+
+    /// check `T` against the struct or GLSL block named `name`
+    pub fn check_struct<T: ShaderParams>(&self, name: &str) -> Result<(), LayoutError> {
+        let ty = self
+            .module
+            .types
+            .iter()
+            .find(|(_, ty)| ty.name.as_deref() == Some(name))
+            .map(|(handle, _)| handle)
+            .ok_or_else(|| LayoutError::MissingStruct { name: name.into() })?;
+
+        self.check::<T>(ty, name)
+    }
+
+    /// check `T` against the struct bound at `@group(group) @binding(binding)`
+    pub fn check_binding<T: ShaderParams>(
+        &self,
+        group: u32,
+        binding: u32,
+    ) -> Result<(), LayoutError> {
+        let variable = self
+            .module
+            .global_variables
+            .iter()
+            .map(|(_, variable)| variable)
+            .find(|variable| variable.binding == Some(naga::ResourceBinding { group, binding }))
+            .ok_or(LayoutError::MissingBinding { group, binding })?;
+
+        let name = self.module.types[variable.ty]
+            .name
+            .clone()
+            .or_else(|| variable.name.clone())
+            .unwrap_or_else(|| format!("@group({group}) @binding({binding})"));
+
+        self.check::<T>(variable.ty, &name)
+    }
+
+    /// every uniform struct the shader binds, ordered by group and binding
+    pub fn uniform_blocks(&self) -> Vec<UniformBlock> {
+        let mut blocks: Vec<UniformBlock> = self
+            .module
+            .global_variables
+            .iter()
+            .filter(|(_, variable)| variable.space == naga::AddressSpace::Uniform)
+            .filter_map(|(_, variable)| {
+                let binding = variable.binding.as_ref()?;
+                let ty = &self.module.types[variable.ty];
+                let naga::TypeInner::Struct { members, span } = &ty.inner else {
+                    return None;
+                };
+
+                let members = members
+                    .iter()
+                    .map(|member| {
+                        let inner = &self.module.types[member.ty].inner;
+                        UniformMember {
+                            name: member.name.clone().unwrap_or_default(),
+                            offset: member.offset as usize,
+                            size: inner.size(self.module.to_ctx()) as usize,
+                            kind: uniform_kind(inner),
+                        }
+                    })
+                    .collect();
+
+                Some(UniformBlock {
+                    name: ty
+                        .name
+                        .clone()
+                        .or_else(|| variable.name.clone())
+                        .unwrap_or_default(),
+                    group: binding.group,
+                    binding: binding.binding,
+                    size: *span as usize,
+                    members,
+                })
+            })
+            .collect();
+        blocks.sort_by_key(|block| (block.group, block.binding));
+        blocks
+    }
+
+    /// the uniform struct bound at `@group(group) @binding(binding)`
+    pub fn uniform_block(&self, group: u32, binding: u32) -> Result<UniformBlock, LayoutError> {
+        self.uniform_blocks()
+            .into_iter()
+            .find(|block| block.group == group && block.binding == binding)
+            .ok_or(LayoutError::MissingBinding { group, binding })
+    }
+
+    fn check<T: ShaderParams>(
+        &self,
+        ty: naga::Handle<naga::Type>,
+        block: &str,
+    ) -> Result<(), LayoutError> {
+        let naga::TypeInner::Struct { members, span } = &self.module.types[ty].inner else {
+            return Err(LayoutError::NotAStruct { name: block.into() });
+        };
+
+        for member in members {
+            let name = member.name.clone().unwrap_or_default();
+            let Some(field) = T::FIELDS.iter().find(|field| field.name == name) else {
+                if is_padding(&name) {
+                    continue;
+                }
+                return Err(LayoutError::MissingField {
+                    block: block.into(),
+                    member: name,
+                });
+            };
+
+            let offset = member.offset as usize;
+            if field.offset != offset {
+                return Err(LayoutError::Offset {
+                    field: name,
+                    rust: field.offset,
+                    shader: offset,
+                });
+            }
+
+            let size = self.module.types[member.ty]
+                .inner
+                .size(self.module.to_ctx()) as usize;
+            if field.size != size {
+                return Err(LayoutError::Size {
+                    field: name,
+                    rust: field.size,
+                    shader: size,
+                });
+            }
+        }
+
+        if let Some(field) = T::FIELDS.iter().find(|field| {
+            !field.is_padding()
+                && !members
+                    .iter()
+                    .any(|member| member.name.as_deref() == Some(field.name))
+        }) {
+            return Err(LayoutError::ExtraField {
+                block: block.into(),
+                field: field.name.into(),
+            });
+        }
+
+        let rust_size = std::mem::size_of::<T>();
+        if rust_size != *span as usize {
+            return Err(LayoutError::StructSize {
+                block: block.into(),
+                rust: rust_size,
+                shader: *span as usize,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+fn uniform_kind(inner: &naga::TypeInner) -> Option<UniformKind> {
+    use naga::{ScalarKind, TypeInner, VectorSize};
+
+    match inner {
+        TypeInner::Scalar(scalar) if scalar.width == 4 => match scalar.kind {
+            ScalarKind::Float => Some(UniformKind::F32),
+            ScalarKind::Sint => Some(UniformKind::I32),
+            ScalarKind::Uint => Some(UniformKind::U32),
+            _ => None,
+        },
+        TypeInner::Vector { size, scalar }
+            if scalar.kind == ScalarKind::Float && scalar.width == 4 =>
+        {
+            Some(match size {
+                VectorSize::Bi => UniformKind::Vec2,
+                VectorSize::Tri => UniformKind::Vec3,
+                VectorSize::Quad => UniformKind::Vec4,
+            })
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::{offset_of, size_of};
+
+    use bytemuck::{Pod, Zeroable};
+
+    use super::*;
+
+    const SHADER: &str = r#"
+struct Params {
+    color: vec3<f32>,
+    _pad: f32,
+    time: f32,
+}
+
+struct Light {
+    position: vec3<f32>,
+    intensity: f32,
+}
+
+struct Scene {
+    light: Light,
+    time: f32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<uniform> scene: Scene;
+"#;
+
+    /// what the derive generates for a field, the type name isn't checked
+    const fn field<T>(name: &'static str, offset: usize) -> ParamField {
+        ParamField {
+            name,
+            ty: "",
+            offset,
+            size: size_of::<T>(),
+        }
+    }
+
+    /// the same block as `Params` with its own padding
+    #[repr(C)]
+    #[derive(Clone, Copy, Pod, Zeroable)]
+    struct Padded {
+        color: [f32; 3],
+        _padding: f32,
+        time: f32,
+        pad0: [f32; 3],
+    }
+
+    impl ShaderParams for Padded {
+        const FIELDS: &'static [ParamField] = &[
+            field::<[f32; 3]>("color", offset_of!(Padded, color)),
+            field::<f32>("_padding", offset_of!(Padded, _padding)),
+            field::<f32>("time", offset_of!(Padded, time)),
+            field::<[f32; 3]>("pad0", offset_of!(Padded, pad0)),
+        ];
+    }
+
+    /// `time` follows the `vec3` directly instead of its 16 byte alignment
+    #[repr(C)]
+    #[derive(Clone, Copy, Pod, Zeroable)]
+    struct Packed {
+        color: [f32; 3],
+        time: f32,
+    }
+
+    impl ShaderParams for Packed {
+        const FIELDS: &'static [ParamField] = &[
+            field::<[f32; 3]>("color", offset_of!(Packed, color)),
+            field::<f32>("time", offset_of!(Packed, time)),
+        ];
+    }
+
+    /// the names only look like padding
+    #[repr(C)]
+    #[derive(Clone, Copy, Pod, Zeroable)]
+    struct Keypad {
+        color: [f32; 3],
+        keypad: f32,
+        time: f32,
+        padding: [f32; 3],
+    }
+
+    impl ShaderParams for Keypad {
+        const FIELDS: &'static [ParamField] = &[
+            field::<[f32; 3]>("color", offset_of!(Keypad, color)),
+            field::<f32>("keypad", offset_of!(Keypad, keypad)),
+            field::<f32>("time", offset_of!(Keypad, time)),
+            field::<[f32; 3]>("padding", offset_of!(Keypad, padding)),
+        ];
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Pod, Zeroable)]
+    struct LightParams {
+        position: [f32; 3],
+        intensity: f32,
+    }
+
+    impl ShaderParams for LightParams {
+        const FIELDS: &'static [ParamField] = &[
+            field::<[f32; 3]>("position", offset_of!(LightParams, position)),
+            field::<f32>("intensity", offset_of!(LightParams, intensity)),
+        ];
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Pod, Zeroable)]
+    struct SceneParams {
+        light: LightParams,
+        time: f32,
+        _pad: [f32; 3],
+    }
+
+    impl ShaderParams for SceneParams {
+        const FIELDS: &'static [ParamField] = &[
+            field::<LightParams>("light", offset_of!(SceneParams, light)),
+            field::<f32>("time", offset_of!(SceneParams, time)),
+            field::<[f32; 3]>("_pad", offset_of!(SceneParams, _pad)),
+        ];
+    }
+
+    /// a nested struct declared as its position only
+    #[repr(C)]
+    #[derive(Clone, Copy, Pod, Zeroable)]
+    struct ShortScene {
+        light: [f32; 3],
+        time: f32,
+        _pad: [f32; 4],
+    }
+
+    impl ShaderParams for ShortScene {
+        const FIELDS: &'static [ParamField] = &[
+            field::<[f32; 3]>("light", offset_of!(ShortScene, light)),
+            field::<f32>("time", offset_of!(ShortScene, time)),
+            field::<[f32; 4]>("_pad", offset_of!(ShortScene, _pad)),
+        ];
+    }
+
+    #[test]
+    fn test_padding_names() {
+        for name in ["_pad", "_padding", "_", "pad", "pad0", "pad12"] {
+            assert!(is_padding(name), "{name}");
+        }
+        for name in ["padding", "keypad", "spread", "pad_x", "pads"] {
+            assert!(!is_padding(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_padding_only_has_to_exist_on_one_side() {
+        let layout = ShaderLayout::from_source(SHADER).unwrap();
+        layout.check_struct::<Padded>("Params").unwrap();
+        layout.check_binding::<Padded>(0, 0).unwrap();
+    }
+
+    #[test]
+    fn test_layout_mismatches() {
+        let layout = ShaderLayout::from_source(SHADER).unwrap();
+
+        assert!(matches!(
+            layout.check_struct::<Packed>("Params"),
+            Err(LayoutError::Offset {
+                rust: 12,
+                shader: 16,
+                ..
+            })
+        ));
+        // `keypad` sits where the shader has `_pad` but isn't padding itself
+        assert!(matches!(
+            layout.check_struct::<Keypad>("Params"),
+            Err(LayoutError::ExtraField { ref field, .. }) if field == "keypad"
+        ));
+        assert!(matches!(
+            layout.check_struct::<Padded>("Missing"),
+            Err(LayoutError::MissingStruct { .. })
+        ));
+        assert!(matches!(
+            layout.check_binding::<Padded>(1, 0),
+            Err(LayoutError::MissingBinding {
+                group: 1,
+                binding: 0
+            })
+        ));
+    }
+
+    #[test]
+    fn test_fields_named_like_padding_must_be_in_the_shader() {
+        // same offsets as `Padded` but `keypad` and `padding` aren't padding names
+        let layout = ShaderLayout::from_source(
+            r#"
+struct Params {
+    color: vec3<f32>,
+    keypad: f32,
+    time: f32,
+}
+"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            layout.check_struct::<Keypad>("Params"),
+            Err(LayoutError::ExtraField { ref field, .. }) if field == "padding"
+        ));
+    }
+
+    #[test]
+    fn test_nested_structs() {
+        let layout = ShaderLayout::from_source(SHADER).unwrap();
+        layout.check_struct::<LightParams>("Light").unwrap();
+        layout.check_binding::<SceneParams>(0, 1).unwrap();
+
+        assert!(matches!(
+            layout.check_binding::<ShortScene>(0, 1),
+            Err(LayoutError::Size { ref field, rust: 12, shader: 16 }) if field == "light"
+        ));
+    }
+}
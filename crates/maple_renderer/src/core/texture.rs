@@ -10,6 +10,7 @@ use wgpu::{
 
 use crate::{
     core::{DepthCompare, RenderContext, mipmap_generator::MipmapGenerator},
+    ktx2::Ktx2,
     render_graph::graph::GraphResource,
 };
 
@@ -17,6 +18,80 @@ pub struct TextureView {
     pub(crate) inner: wgpu::TextureView,
 }
 
+/// how the layers of a view are read in shaders
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextureViewDimension {
+    D2,
+    D2Array,
+    Cube,
+    CubeArray,
+}
+
+impl From<TextureViewDimension> for wgpu::TextureViewDimension {
+    fn from(value: TextureViewDimension) -> Self {
+        match value {
+            TextureViewDimension::D2 => Self::D2,
+            TextureViewDimension::D2Array => Self::D2Array,
+            TextureViewDimension::Cube => Self::Cube,
+            TextureViewDimension::CubeArray => Self::CubeArray,
+        }
+    }
+}
+
+/// the mips and layers a view sees, e.g. one mip of a cube face to render a prefiltered
+/// environment into
+///
+/// ```rust,ignore
+/// let level = cube.create_view_with(TextureViewOptions {
+///     dimension: Some(TextureViewDimension::D2),
+///     base_layer: CubeFace::PositiveY as u32,
+///     layer_count: Some(1),
+///     ..TextureViewOptions::mip(2)
+/// });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TextureViewOptions {
+    /// `None` uses the view of [`Texture::create_view`] and the others
+    ///
+    /// Default: `None`
+    pub dimension: Option<TextureViewDimension>,
+    /// Default: `0`
+    pub base_mip_level: u32,
+    /// `None` for every mip from [`TextureViewOptions::base_mip_level`]
+    ///
+    /// Default: `None`
+    pub mip_level_count: Option<u32>,
+    /// Default: `0`
+    pub base_layer: u32,
+    /// `None` for every layer from [`TextureViewOptions::base_layer`]
+    ///
+    /// Default: `None`
+    pub layer_count: Option<u32>,
+}
+
+impl TextureViewOptions {
+    /// only the mip `level`
+    pub fn mip(level: u32) -> Self {
+        Self {
+            base_mip_level: level,
+            mip_level_count: Some(1),
+            ..Default::default()
+        }
+    }
+
+    fn create(&self, texture: &wgpu::Texture, dimension: TextureViewDimension) -> TextureView {
+        let view = texture.create_view(&TextureViewDescriptor {
+            dimension: Some(self.dimension.unwrap_or(dimension).into()),
+            base_mip_level: self.base_mip_level,
+            mip_level_count: self.mip_level_count,
+            base_array_layer: self.base_layer,
+            array_layer_count: self.layer_count,
+            ..Default::default()
+        });
+        TextureView { inner: view }
+    }
+}
+
 #[derive(Clone)]
 pub struct Sampler {
     pub(crate) inner: wgpu::Sampler,
@@ -47,7 +122,68 @@ impl From<SamplerOptions> for wgpu::SamplerDescriptor<'static> {
     }
 }
 
+/// how a texture wants to be sampled by the materials using it. gltf samplers are mapped onto
+/// this when a model is loaded
+///
+/// ```rust,ignore
+/// // crisp pixel art
+/// let texture = texture.with_sampling(TextureSampling::pixelated());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextureSampling {
+    pub mode_u: TextureMode,
+    pub mode_v: TextureMode,
+    pub mag_filter: FilterMode,
+    pub min_filter: FilterMode,
+    /// how mip levels are blended, `None` only samples the full size level
+    pub mipmap_filter: Option<FilterMode>,
+}
+
+impl Default for TextureSampling {
+    /// repeating trilinear filtering
+    fn default() -> Self {
+        Self {
+            mode_u: TextureMode::Repeat,
+            mode_v: TextureMode::Repeat,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: Some(FilterMode::Linear),
+        }
+    }
+}
+
+impl TextureSampling {
+    /// nearest filtering without mips
+    pub fn pixelated() -> Self {
+        Self {
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Nearest,
+            mipmap_filter: None,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<TextureSampling> for wgpu::SamplerDescriptor<'static> {
+    fn from(value: TextureSampling) -> Self {
+        Self {
+            label: Some("texture sampler"),
+            address_mode_u: value.mode_u.into(),
+            address_mode_v: value.mode_v.into(),
+            mag_filter: value.mag_filter.into(),
+            min_filter: value.min_filter.into(),
+            mipmap_filter: value.mipmap_filter.unwrap_or(FilterMode::Nearest).into(),
+            lod_max_clamp: match value.mipmap_filter {
+                Some(_) => 32.0,
+                None => 0.0,
+            },
+            ..Default::default()
+        }
+    }
+}
+
 /// how its sampled when uv is outside of texture
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum TextureMode {
     ClampToEdge,
     Repeat,
@@ -65,6 +201,7 @@ impl From<TextureMode> for AddressMode {
 }
 
 /// how its sampled when uv is between 2 texels
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum FilterMode {
     Linear,
     Nearest,
@@ -79,7 +216,8 @@ impl From<FilterMode> for wgpu::FilterMode {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// formats are named like the variants in [`crate::render_graph::description`] files
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum TextureFormat {
     RGB8,
     RGB16,
@@ -95,6 +233,16 @@ pub enum TextureFormat {
     RG16,
     RG32Float,
     RGBA32Float,
+    // block compressed formats, these need a device with bc support
+    BC1,
+    BC1Srgb,
+    BC3,
+    BC3Srgb,
+    BC4,
+    BC5,
+    BC6HFloat,
+    BC7,
+    BC7Srgb,
     // depth format
     Depth32,
     Depth24,
@@ -119,8 +267,63 @@ impl TextureFormat {
             Self::RGBA8Srgb => 4,
             Self::RGBA32Float => 16,
             Self::Depth32 | Self::Depth24 | Self::Depth24PlusStencil8 => 0,
+            // compressed formats are laid out in blocks, see [`Self::block_bytes`]
+            Self::BC1
+            | Self::BC1Srgb
+            | Self::BC3
+            | Self::BC3Srgb
+            | Self::BC4
+            | Self::BC5
+            | Self::BC6HFloat
+            | Self::BC7
+            | Self::BC7Srgb => 0,
+        }
+    }
+
+    /// the bytes of a 4x4 block of a compressed format, `None` for uncompressed formats
+    pub fn block_bytes(&self) -> Option<u32> {
+        match self {
+            Self::BC1 | Self::BC1Srgb | Self::BC4 => Some(8),
+            Self::BC3 | Self::BC3Srgb | Self::BC5 | Self::BC6HFloat | Self::BC7 | Self::BC7Srgb => {
+                Some(16)
+            }
+            _ => None,
         }
     }
+
+    pub fn is_compressed(&self) -> bool {
+        self.block_bytes().is_some()
+    }
+
+    /// the bytes a `width` by `height` image with `mip_levels` mips takes on the gpu
+    pub fn image_bytes(&self, width: u32, height: u32, mip_levels: u32) -> u64 {
+        (0..mip_levels.max(1))
+            .map(|level| {
+                let width = (width >> level).max(1) as u64;
+                let height = (height >> level).max(1) as u64;
+                match self.block_bytes() {
+                    Some(block) => width.div_ceil(4) * height.div_ceil(4) * block as u64,
+                    None => width * height * self.texel_bytes() as u64,
+                }
+            })
+            .sum()
+    }
+
+    /// [`Self::byte_offset`] with the depth formats filled in
+    fn texel_bytes(&self) -> u32 {
+        match self {
+            Self::Depth32 | Self::Depth24 | Self::Depth24PlusStencil8 => 4,
+            format => format.byte_offset(),
+        }
+    }
+
+    /// true if the gpu converts between srgb and linear when sampling or rendering to the format
+    pub fn is_srgb(&self) -> bool {
+        matches!(
+            self,
+            Self::BGRA8Srgb | Self::RGBA8Srgb | Self::BC1Srgb | Self::BC3Srgb | Self::BC7Srgb
+        )
+    }
 }
 
 impl From<TextureFormat> for wgpu::TextureFormat {
@@ -143,6 +346,15 @@ impl From<TextureFormat> for wgpu::TextureFormat {
             TextureFormat::Depth24 => Self::Depth24Plus,
             TextureFormat::Depth24PlusStencil8 => Self::Depth32FloatStencil8,
             TextureFormat::RG32Float => Self::Rg32Float,
+            TextureFormat::BC1 => Self::Bc1RgbaUnorm,
+            TextureFormat::BC1Srgb => Self::Bc1RgbaUnormSrgb,
+            TextureFormat::BC3 => Self::Bc3RgbaUnorm,
+            TextureFormat::BC3Srgb => Self::Bc3RgbaUnormSrgb,
+            TextureFormat::BC4 => Self::Bc4RUnorm,
+            TextureFormat::BC5 => Self::Bc5RgUnorm,
+            TextureFormat::BC6HFloat => Self::Bc6hRgbUfloat,
+            TextureFormat::BC7 => Self::Bc7RgbaUnorm,
+            TextureFormat::BC7Srgb => Self::Bc7RgbaUnormSrgb,
         }
     }
 }
@@ -164,6 +376,15 @@ impl From<wgpu::TextureFormat> for TextureFormat {
             wgpu::TextureFormat::Depth24Plus => Self::Depth24,
             wgpu::TextureFormat::Depth32FloatStencil8 => Self::Depth24PlusStencil8,
             wgpu::TextureFormat::Rg32Float => Self::RG32Float,
+            wgpu::TextureFormat::Bc1RgbaUnorm => Self::BC1,
+            wgpu::TextureFormat::Bc1RgbaUnormSrgb => Self::BC1Srgb,
+            wgpu::TextureFormat::Bc3RgbaUnorm => Self::BC3,
+            wgpu::TextureFormat::Bc3RgbaUnormSrgb => Self::BC3Srgb,
+            wgpu::TextureFormat::Bc4RUnorm => Self::BC4,
+            wgpu::TextureFormat::Bc5RgUnorm => Self::BC5,
+            wgpu::TextureFormat::Bc6hRgbUfloat => Self::BC6HFloat,
+            wgpu::TextureFormat::Bc7RgbaUnorm => Self::BC7,
+            wgpu::TextureFormat::Bc7RgbaUnormSrgb => Self::BC7Srgb,
             _ => panic!("Unsupported wgpu::TextureFormat: {:?}", value),
         }
     }
@@ -201,6 +422,27 @@ impl From<TextureUsage> for wgpu::TextureUsages {
     }
 }
 
+/// a rectangle of a texture in pixels, see [`RenderContext::write_texture`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl TextureRegion {
+    /// all of the first mip of `texture`
+    pub fn full(texture: &Texture) -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            width: texture.width(),
+            height: texture.height(),
+        }
+    }
+}
+
 pub struct TextureCreateInfo {
     pub label: Option<&'static str>,
     pub width: u32,
@@ -220,13 +462,19 @@ pub struct Texture {
     sample_count: u32,
     /// Optional array layer to use when creating views (for rendering to specific layers)
     array_layer: Option<u32>,
+    sampling: TextureSampling,
 }
 
 impl GraphResource for Texture {}
 
+/// how many mip levels a texture of this size has down to 1x1, at most 10
+pub fn mip_level_count(width: u32, height: u32) -> u32 {
+    (width.max(height).max(1).ilog2() + 1).min(10)
+}
+
 impl Texture {
     /// Check if a format supports storage binding for mipmap generation
-    fn supports_mipmap_generation(format: TextureFormat) -> bool {
+    pub(crate) fn supports_mipmap_generation(format: TextureFormat) -> bool {
         matches!(
             format,
             TextureFormat::RGBA8
@@ -250,6 +498,12 @@ impl Texture {
             usage |= TextureUsage::STORAGE_BINDING;
         }
 
+        // lets the render graph read targets back while debugging, multisampled textures can't
+        // be copied from
+        if info.sample_count == 1 {
+            usage |= TextureUsage::COPY_SRC;
+        }
+
         let texture = device.create_texture(&TextureDescriptor {
             label: info.label,
             size: texture_size,
@@ -268,6 +522,7 @@ impl Texture {
             format: info.format,
             sample_count: info.sample_count,
             array_layer: None,
+            sampling: TextureSampling::default(),
         }
     }
 
@@ -275,10 +530,31 @@ impl Texture {
         self.width
     }
 
+    pub fn mip_level_count(&self) -> u32 {
+        self.inner.mip_level_count()
+    }
+
+    /// how materials sample this texture
+    pub fn sampling(&self) -> TextureSampling {
+        self.sampling
+    }
+
+    /// the same texture sampled differently, the texture data is shared
+    pub fn with_sampling(mut self, sampling: TextureSampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
     pub fn height(&self) -> u32 {
         self.height
     }
 
+    /// the array layer views and copies of the texture use
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn layer(&self) -> u32 {
+        self.array_layer.unwrap_or(0)
+    }
+
     pub(crate) fn write<T: bytemuck::Pod>(&self, queue: &Queue, data: &[T]) {
         let size = wgpu::Extent3d {
             width: self.width,
@@ -319,7 +595,11 @@ impl Texture {
             TexelCopyTextureInfo {
                 texture: &self.inner,
                 mip_level: 0,
-                origin: Origin3d::ZERO,
+                // the layer of textures made with TextureArray::create_layer_texture
+                origin: Origin3d {
+                    z: self.array_layer.unwrap_or(0),
+                    ..Origin3d::ZERO
+                },
                 aspect: TextureAspect::All,
             },
             final_data,
@@ -332,6 +612,49 @@ impl Texture {
         );
     }
 
+    /// write one mip level, compressed formats are written as whole 4x4 blocks
+    pub(crate) fn write_mip(&self, queue: &Queue, level: u32, data: &[u8]) {
+        let width = (self.width >> level).max(1);
+        let height = (self.height >> level).max(1);
+
+        let (size, bytes_per_row, rows) = match self.format.block_bytes() {
+            // the smallest mips still take up a whole block
+            Some(block_bytes) => {
+                let (blocks_x, blocks_y) = (width.div_ceil(4), height.div_ceil(4));
+                let size = wgpu::Extent3d {
+                    width: blocks_x * 4,
+                    height: blocks_y * 4,
+                    depth_or_array_layers: 1,
+                };
+                (size, blocks_x * block_bytes, blocks_y)
+            }
+            None => {
+                let size = wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                };
+                (size, self.format.byte_offset() * width, height)
+            }
+        };
+
+        queue.write_texture(
+            TexelCopyTextureInfo {
+                texture: &self.inner,
+                mip_level: level,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            data,
+            TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row),
+                rows_per_image: Some(rows),
+            },
+            size,
+        );
+    }
+
     pub(crate) fn write_region<T: bytemuck::Pod>(
         &self,
         queue: &Queue,
@@ -378,7 +701,11 @@ impl Texture {
             TexelCopyTextureInfo {
                 texture: &self.inner,
                 mip_level: 0,
-                origin: Origin3d { x, y, z: 0 },
+                origin: Origin3d {
+                    x,
+                    y,
+                    z: self.array_layer.unwrap_or(0),
+                },
                 aspect: TextureAspect::All,
             },
             final_data,
@@ -407,6 +734,20 @@ impl Texture {
         TextureView { inner: view }
     }
 
+    /// a view of some of the mips, the layer of textures made with
+    /// [`TextureArray::create_layer_texture`] is only used when `options` don't pick layers
+    pub fn create_view_with(&self, options: TextureViewOptions) -> TextureView {
+        let options = match (self.array_layer, options.layer_count) {
+            (Some(layer), None) => TextureViewOptions {
+                base_layer: layer,
+                layer_count: Some(1),
+                ..options
+            },
+            _ => options,
+        };
+        options.create(&self.inner, TextureViewDimension::D2)
+    }
+
     pub(crate) fn create_sampler(device: &Device, options: SamplerOptions) -> Sampler {
         let sampler = device.create_sampler(&options.into());
         Sampler { inner: sampler }
@@ -420,26 +761,38 @@ impl Texture {
         self.sample_count
     }
 
+    /// the bytes the texture and its mips take on the gpu
+    pub fn gpu_bytes(&self) -> u64 {
+        self.format
+            .image_bytes(self.width, self.height, self.mip_level_count())
+            * self.sample_count as u64
+    }
+
     /// Load a texture from bytes (PNG, JPEG, etc.)
+    ///
+    /// with `mipmaps` the texture has room for a full mip chain that is left for
+    /// [`MipmapGenerator::generate_mipmaps`] to fill
     pub(crate) fn from_bytes(
         device: &Device,
         queue: &Queue,
         bytes: &[u8],
         label: Option<&'static str>,
+        mipmaps: bool,
     ) -> Result<Self, ImageError> {
         let img = image::load_from_memory(bytes)?;
-        Ok(Self::from_image(device, queue, &img, label))
+        Ok(Self::from_image(device, queue, &img, label, mipmaps))
     }
 
-    /// Load a texture from a file path
+    /// Load a texture from a file path, see [`Self::from_bytes`]
     pub(crate) fn from_file(
         device: &Device,
         queue: &Queue,
         path: impl AsRef<std::path::Path>,
         label: Option<&'static str>,
+        mipmaps: bool,
     ) -> Result<Self, ImageError> {
         let img = image::open(path)?;
-        Ok(Self::from_image(device, queue, &img, label))
+        Ok(Self::from_image(device, queue, &img, label, mipmaps))
     }
 
     pub fn new_hdri_from_file(
@@ -449,6 +802,26 @@ impl Texture {
         label: Option<&'static str>,
     ) -> Result<Self, ImageError> {
         let img = image::open(file)?;
+        Ok(Self::new_hdri_from_image(device, queue, &img, label))
+    }
+
+    /// load an hdr or exr image from its file contents
+    pub fn new_hdri_from_bytes(
+        device: &Device,
+        queue: &Queue,
+        bytes: &[u8],
+        label: Option<&'static str>,
+    ) -> Result<Self, ImageError> {
+        let img = image::load_from_memory(bytes)?;
+        Ok(Self::new_hdri_from_image(device, queue, &img, label))
+    }
+
+    fn new_hdri_from_image(
+        device: &Device,
+        queue: &Queue,
+        img: &DynamicImage,
+        label: Option<&'static str>,
+    ) -> Self {
         let rgba = img.to_rgba32f();
         let dimensions = rgba.dimensions();
 
@@ -470,7 +843,33 @@ impl Texture {
         );
 
         texture.write(queue, &rgba.into_raw());
-        Ok(texture)
+        texture
+    }
+
+    /// upload a texture read from a ktx2 file with its mip levels
+    pub(crate) fn from_ktx2(
+        device: &Device,
+        queue: &Queue,
+        ktx2: &Ktx2,
+        label: Option<&'static str>,
+    ) -> Self {
+        let texture = Self::create(
+            device,
+            &TextureCreateInfo {
+                label,
+                width: ktx2.width,
+                height: ktx2.height,
+                format: ktx2.format,
+                usage: TextureUsage::TEXTURE_BINDING | TextureUsage::COPY_DST,
+                sample_count: 1,
+                mip_level: ktx2.levels.len() as u32,
+            },
+        );
+
+        for (level, data) in ktx2.levels.iter().enumerate() {
+            texture.write_mip(queue, level as u32, data);
+        }
+        texture
     }
 
     /// Create a texture from a DynamicImage
@@ -479,9 +878,14 @@ impl Texture {
         queue: &Queue,
         img: &DynamicImage,
         label: Option<&'static str>,
+        mipmaps: bool,
     ) -> Self {
         let rgba = img.to_rgba8();
         let dimensions = rgba.dimensions();
+        let mip_level = match mipmaps {
+            true => mip_level_count(dimensions.0, dimensions.1),
+            false => 1,
+        };
 
         let texture = Self::create(
             device,
@@ -492,7 +896,7 @@ impl Texture {
                 format: TextureFormat::RGBA8,
                 usage: TextureUsage::TEXTURE_BINDING | TextureUsage::COPY_DST,
                 sample_count: 1,
-                mip_level: 1,
+                mip_level,
             },
         );
 
@@ -508,10 +912,14 @@ pub struct TextureArrayCreateInfo {
     pub array_layers: u32,
     pub format: TextureFormat,
     pub usage: TextureUsage,
+    /// every layer gets the same mips, see [`RenderContext::generate_array_mipmaps`]
+    pub mip_level: u32,
 }
 
 /// A 2D texture array
-#[derive(Clone, Debug)]
+///
+/// two arrays are equal if they are the same gpu texture
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct TextureArray {
     pub(crate) inner: wgpu::Texture,
     width: u32,
@@ -530,13 +938,18 @@ impl TextureArray {
             depth_or_array_layers: info.array_layers,
         };
 
+        let mut usage = info.usage | TextureUsage::COPY_SRC;
+        if info.mip_level > 1 && Texture::supports_mipmap_generation(info.format) {
+            usage |= TextureUsage::STORAGE_BINDING;
+        }
+
         let texture = device.create_texture(&TextureDescriptor {
             label: info.label,
             size: texture_size,
             format: info.format.into(),
-            usage: info.usage.into(),
+            usage: usage.into(),
             dimension: TextureDimension::D2,
-            mip_level_count: 1,
+            mip_level_count: info.mip_level.max(1),
             sample_count: 1,
             view_formats: &[],
         });
@@ -566,10 +979,19 @@ impl TextureArray {
         self.array_layers
     }
 
+    pub fn mip_level_count(&self) -> u32 {
+        self.inner.mip_level_count()
+    }
+
     pub fn format(&self) -> TextureFormat {
         self.format
     }
 
+    /// a view of some of the layers and mips, read as an array unless `options` say otherwise
+    pub fn create_view_with(&self, options: TextureViewOptions) -> TextureView {
+        options.create(&self.inner, TextureViewDimension::D2Array)
+    }
+
     /// Create a view of the entire array
     pub fn create_view(&self) -> TextureView {
         let view = self.inner.create_view(&TextureViewDescriptor {
@@ -601,6 +1023,7 @@ impl TextureArray {
             format: self.format,
             sample_count: 1,
             array_layer: Some(layer),
+            sampling: TextureSampling::default(),
         }
     }
 }
@@ -660,7 +1083,7 @@ impl TextureCube {
             label: info.label,
             size: texture_size,
             format: info.format.into(),
-            usage: info.usage.into(),
+            usage: (info.usage | TextureUsage::COPY_SRC).into(),
             dimension: TextureDimension::D2,
             mip_level_count: info.mip_level,
             sample_count: 1,
@@ -678,10 +1101,19 @@ impl TextureCube {
         self.size
     }
 
+    pub fn mip_level_count(&self) -> u32 {
+        self.inner.mip_level_count()
+    }
+
     pub fn format(&self) -> TextureFormat {
         self.format
     }
 
+    /// a view of some of the faces and mips, read as a cube unless `options` say otherwise
+    pub fn create_view_with(&self, options: TextureViewOptions) -> TextureView {
+        options.create(&self.inner, TextureViewDimension::Cube)
+    }
+
     pub fn create_view(&self) -> TextureView {
         let view = self.inner.create_view(&TextureViewDescriptor {
             dimension: Some(wgpu::TextureViewDimension::Cube),
@@ -713,6 +1145,7 @@ impl TextureCube {
             format: self.format,
             sample_count: 1,
             array_layer: Some(face as u32),
+            sampling: TextureSampling::default(),
         }
     }
 }
@@ -727,7 +1160,9 @@ pub struct TextureCubeArrayCreateInfo {
 }
 
 /// A cube texture array - useful for point light shadow maps
-#[derive(Clone, Debug)]
+///
+/// two arrays are equal if they are the same gpu texture
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct TextureCubeArray {
     pub(crate) inner: wgpu::Texture,
     size: u32,
@@ -749,7 +1184,7 @@ impl TextureCubeArray {
             label: info.label,
             size: texture_size,
             format: info.format.into(),
-            usage: info.usage.into(),
+            usage: (info.usage | TextureUsage::COPY_SRC).into(),
             dimension: TextureDimension::D2,
             mip_level_count: 1,
             sample_count: 1,
@@ -776,6 +1211,12 @@ impl TextureCubeArray {
         self.format
     }
 
+    /// a view of some of the faces, read as a cube array unless `options` say otherwise. the
+    /// layers count faces so cube `i` starts at layer `i * 6`
+    pub fn create_view_with(&self, options: TextureViewOptions) -> TextureView {
+        options.create(&self.inner, TextureViewDimension::CubeArray)
+    }
+
     /// Create a view of the entire cube array
     pub fn create_view(&self) -> TextureView {
         let view = self.inner.create_view(&TextureViewDescriptor {
@@ -817,6 +1258,7 @@ impl TextureCubeArray {
             format: self.format,
             sample_count: 1,
             array_layer: Some(cube_index * 6 + face),
+            sampling: TextureSampling::default(),
         }
     }
 }
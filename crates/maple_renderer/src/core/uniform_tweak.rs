@@ -0,0 +1,185 @@
+//! live editing of uniform blocks for tuning shader parameters without recompiling
+//!
+//! a pass makes a [`UniformTweak`] from the [`UniformBlock`] its shader declares and overlays the
+//! edited values onto the data it uploads every frame. every tweak that is alive can be found with
+//! [`uniform_tweaks`], which is how the egui tweak panel lists them.
+//!
+//! ```rust,ignore
+//! let layout = ShaderLayout::from_source(include_str!("blit.frag.wgsl").into())?;
+//! let tweak = UniformTweak::new("Composite", layout.uniform_block(0, 3)?, &uniforms);
+//!
+//! // in draw
+//! tweak.apply(&mut uniforms);
+//! rcx.queue().write_buffer(&buffer, &uniforms);
+//! ```
+
+use std::sync::{Arc, LazyLock, Weak};
+
+use bytemuck::Pod;
+use maple_engine::platform::SendSync;
+use parking_lot::Mutex;
+
+use crate::core::{
+    Buffer, RenderQueue,
+    shader_layout::{UniformBlock, UniformValue},
+};
+
+static TWEAKS: LazyLock<Mutex<Vec<Weak<Mutex<TweakState>>>>> =
+    LazyLock::new(|| Mutex::new(Vec::new()));
+
+struct TweakState {
+    label: String,
+    block: UniformBlock,
+    /// the values the tweak was made with, for resetting
+    original: Vec<u8>,
+    bytes: Vec<u8>,
+    /// the members changed with [`UniformTweak::set`] by index
+    edited: Vec<bool>,
+    /// set when a value changed since the last [`UniformTweak::write`]
+    changed: bool,
+}
+
+/// an editable copy of a uniform block, see the [module docs](self)
+///
+/// clones share the same values. the tweak is listed by [`uniform_tweaks`] until every clone is
+/// dropped
+#[derive(Clone)]
+pub struct UniformTweak {
+    state: Arc<Mutex<TweakState>>,
+}
+
+impl UniformTweak {
+    /// a tweak of `block` starting from the values in `initial`
+    ///
+    /// `label` names the tweak in the panel, usually the label of the pass
+    pub fn new<T: Pod>(label: impl Into<String>, block: UniformBlock, initial: &T) -> Self {
+        let mut bytes = bytemuck::bytes_of(initial).to_vec();
+        bytes.resize(block.size, 0);
+
+        let state = Arc::new(Mutex::new(TweakState {
+            label: label.into(),
+            edited: vec![false; block.members.len()],
+            block,
+            original: bytes.clone(),
+            bytes,
+            changed: false,
+        }));
+
+        let mut tweaks = TWEAKS.lock();
+        tweaks.retain(|tweak| tweak.strong_count() > 0);
+        tweaks.push(Arc::downgrade(&state));
+
+        Self { state }
+    }
+
+    pub fn label(&self) -> String {
+        self.state.lock().label.clone()
+    }
+
+    pub fn block(&self) -> UniformBlock {
+        self.state.lock().block.clone()
+    }
+
+    /// the current value of `member`, `None` if there is no such member or it can't be edited
+    pub fn get(&self, member: &str) -> Option<UniformValue> {
+        let state = self.state.lock();
+        let member = state.block.member(member)?;
+        UniformValue::read(member.kind?, state.bytes.get(member.offset..)?)
+    }
+
+    /// the value `member` had when the tweak was made
+    pub fn original(&self, member: &str) -> Option<UniformValue> {
+        let state = self.state.lock();
+        let member = state.block.member(member)?;
+        UniformValue::read(member.kind?, state.original.get(member.offset..)?)
+    }
+
+    /// change `member`, false if there is no such member or it's a different kind
+    pub fn set(&self, member: &str, value: UniformValue) -> bool {
+        let mut state = self.state.lock();
+        let Some(index) = state
+            .block
+            .members
+            .iter()
+            .position(|block_member| block_member.name == member)
+        else {
+            return false;
+        };
+        let member = &state.block.members[index];
+        if member.kind != Some(value.kind()) {
+            return false;
+        }
+
+        let range = member.offset..member.offset + member.size;
+        let Some(bytes) = state.bytes.get_mut(range) else {
+            return false;
+        };
+        bytes.copy_from_slice(&value.bytes());
+        state.edited[index] = true;
+        state.changed = true;
+        true
+    }
+
+    /// true if `member` was changed with [`UniformTweak::set`] since the last reset
+    pub fn is_edited(&self, member: &str) -> bool {
+        let state = self.state.lock();
+        state
+            .block
+            .members
+            .iter()
+            .zip(&state.edited)
+            .any(|(block_member, edited)| block_member.name == member && *edited)
+    }
+
+    /// go back to the values the tweak was made with and stop overriding the pass
+    pub fn reset(&self) {
+        let mut state = self.state.lock();
+        state.bytes = state.original.clone();
+        state.edited.fill(false);
+        state.changed = true;
+    }
+
+    /// copy the edited members over `value`, members that weren't edited are left alone so
+    /// the pass keeps control of them
+    pub fn apply<T: Pod>(&self, value: &mut T) {
+        let state = self.state.lock();
+        let target = bytemuck::bytes_of_mut(value);
+        for (member, _) in state
+            .block
+            .members
+            .iter()
+            .zip(&state.edited)
+            .filter(|(_, edited)| **edited)
+        {
+            let range = member.offset..member.offset + member.size;
+            if let (Some(target), Some(source)) =
+                (target.get_mut(range.clone()), state.bytes.get(range))
+            {
+                target.copy_from_slice(source);
+            }
+        }
+    }
+
+    /// upload every value to `buffer` if one changed since the last write, for blocks the pass
+    /// doesn't upload itself
+    ///
+    /// nothing is written if `T` isn't the size of the block
+    pub fn write<T: Pod + SendSync>(&self, queue: &RenderQueue, buffer: &Buffer<T>) {
+        let mut state = self.state.lock();
+        if !state.changed || state.bytes.len() != size_of::<T>() {
+            return;
+        }
+        queue.write_buffer(buffer, &bytemuck::pod_read_unaligned::<T>(&state.bytes));
+        state.changed = false;
+    }
+}
+
+/// every [`UniformTweak`] that is still alive, in the order they were made
+pub fn uniform_tweaks() -> Vec<UniformTweak> {
+    TWEAKS
+        .lock()
+        .iter()
+        .filter_map(Weak::upgrade)
+        .map(|state| UniformTweak { state })
+        .collect()
+}
@@ -0,0 +1,146 @@
+//! reading textures out of `.ktx2` containers
+//!
+//! ktx2 files hold textures in the format the gpu samples them in together with their mip chain
+//! so nothing has to be decoded or generated at load. block compressed bc1 to bc7 textures take
+//! a quarter to an eighth of the memory of rgba8 ones. they can be made with the ktx tools:
+//!
+//! ```text
+//! ktx create --format BC7_SRGB_BLOCK --generate-mipmap albedo.png albedo.ktx2
+//! ```
+//!
+//! basis universal files have to be transcoded to a bc format first, e.g. with
+//! `ktx transcode --target bc7 albedo.ktx2 albedo_bc7.ktx2`. supercompressed files aren't
+//! supported either
+
+use maple_engine::asset::LoadErr;
+
+use crate::core::texture::TextureFormat;
+
+const IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+
+/// the size of the header and the index before the level index
+const HEADER_LEN: usize = 80;
+
+/// a texture read from a `.ktx2` file
+#[derive(Debug, Clone)]
+pub struct Ktx2 {
+    pub format: TextureFormat,
+    pub width: u32,
+    pub height: u32,
+    /// the data of each mip level from the full size one down
+    pub levels: Vec<Vec<u8>>,
+}
+
+/// true if the bytes start like a ktx2 file
+pub fn is_ktx2(bytes: &[u8]) -> bool {
+    bytes.starts_with(&IDENTIFIER)
+}
+
+/// read a 2d texture out of a ktx2 file
+pub fn parse(bytes: &[u8]) -> Result<Ktx2, LoadErr> {
+    let err = |message: &str| LoadErr::Import(format!("invalid ktx2 file: {message}"));
+    if !is_ktx2(bytes) || bytes.len() < HEADER_LEN {
+        return Err(err("missing the ktx2 header"));
+    }
+
+    let u32_at = |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+    let u64_at = |offset: usize| u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap());
+
+    let vk_format = u32_at(12);
+    let width = u32_at(20);
+    let height = u32_at(24);
+    let depth = u32_at(28);
+    let layers = u32_at(32);
+    let faces = u32_at(36);
+    let level_count = u32_at(40).max(1) as usize;
+    let supercompression = u32_at(44);
+
+    if supercompression != 0 {
+        return Err(LoadErr::Import(format!(
+            "supercompressed ktx2 textures aren't supported (scheme {supercompression}), \
+             transcode it to a bc format first"
+        )));
+    }
+    let format = match vk_format {
+        0 => {
+            return Err(LoadErr::Import(
+                "basis universal ktx2 textures aren't supported, transcode it to a bc format first"
+                    .into(),
+            ));
+        }
+        37 => TextureFormat::RGBA8,
+        43 => TextureFormat::RGBA8Srgb,
+        // bc1 rgb and rgba
+        131 | 133 => TextureFormat::BC1,
+        132 | 134 => TextureFormat::BC1Srgb,
+        137 => TextureFormat::BC3,
+        138 => TextureFormat::BC3Srgb,
+        139 => TextureFormat::BC4,
+        141 => TextureFormat::BC5,
+        143 => TextureFormat::BC6HFloat,
+        145 => TextureFormat::BC7,
+        146 => TextureFormat::BC7Srgb,
+        other => {
+            return Err(LoadErr::Import(format!(
+                "ktx2 textures with vulkan format {other} aren't supported"
+            )));
+        }
+    };
+
+    if width == 0 || height == 0 || depth > 1 || layers > 1 || faces != 1 {
+        return Err(err("only 2d textures can be loaded"));
+    }
+    if format.is_compressed() && (!width.is_multiple_of(4) || !height.is_multiple_of(4)) {
+        return Err(err(
+            "compressed textures need a size that is a multiple of 4",
+        ));
+    }
+
+    if level_count as u32 > width.max(height).ilog2() + 1 {
+        return Err(err("it has more mip levels than its size allows"));
+    }
+
+    let index_end = HEADER_LEN + level_count * 24;
+    if bytes.len() < index_end {
+        return Err(err("the level index is cut off"));
+    }
+
+    let levels = (0..level_count)
+        .map(|level| {
+            let entry = HEADER_LEN + level * 24;
+            let offset = u64_at(entry) as usize;
+            let length = u64_at(entry + 8) as usize;
+
+            let expected = level_size(format, width, height, level as u32);
+            if length != expected {
+                return Err(err(&format!(
+                    "level {level} is {length} bytes instead of {expected}"
+                )));
+            }
+            offset
+                .checked_add(length)
+                .and_then(|end| bytes.get(offset..end))
+                .map(<[u8]>::to_vec)
+                .ok_or_else(|| err(&format!("level {level} is cut off")))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Ktx2 {
+        format,
+        width,
+        height,
+        levels,
+    })
+}
+
+/// the bytes of a mip level, compressed levels are padded to whole blocks
+fn level_size(format: TextureFormat, width: u32, height: u32, level: u32) -> usize {
+    let width = (width >> level).max(1);
+    let height = (height >> level).max(1);
+    match format.block_bytes() {
+        Some(block_bytes) => (width.div_ceil(4) * height.div_ceil(4) * block_bytes) as usize,
+        None => (width * height * format.byte_offset()) as usize,
+    }
+}
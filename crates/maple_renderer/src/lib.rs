@@ -2,14 +2,23 @@
 //!
 //! implements the render graph [`render_graph::graph::RenderGraph`], assets like
 //! [`core::texture::Texture`], and other conveniences like typed buffers with [`core::buffer::Buffer`]
+//!
+//! [`GraphicsBackend::Null`](types::render_config::GraphicsBackend::Null) draws nothing and needs
+//! no gpu, it logs what nodes do instead so they can be unit tested, see [`core::command_log`].
 
 pub mod core;
+pub mod ktx2;
 pub mod platform;
 pub mod render_graph;
 pub mod shader_asset;
+pub mod shader_include;
 pub mod texture_asset;
 pub mod types;
 
+/// the version of this crate
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
 pub mod prelude {
     pub use crate::core::texture::Texture;
+    pub use crate::types::quality::{Quality, QualityPreset};
 }
@@ -1,13 +1,8 @@
 //! provides platform specific traits like send sync for both wasm and standard
+//!
+//! the web build turns on wgpu's `fragile-send-sync-non-atomic-wasm` so gpu types are send and
+//! sync there too and [`SendSync`] means the same thing on every platform
 
-#[cfg(not(target_arch = "wasm32"))]
 pub trait SendSync: Send + Sync {}
 
-#[cfg(target_arch = "wasm32")]
-pub trait SendSync {}
-
-#[cfg(not(target_arch = "wasm32"))]
 impl<T: Send + Sync + ?Sized> SendSync for T {}
-
-#[cfg(target_arch = "wasm32")]
-impl<T: ?Sized> SendSync for T {}
@@ -0,0 +1,128 @@
+//! the render graph written as data so pipeline variants can be switched without code changes
+//!
+//! plugins register the nodes they provide by their [`RenderNode::label`] with
+//! [`GraphBuilder::register_node`]. a description then picks nodes by that name, orders them
+//! with edges from one node's output to another's input (stages still order nodes on their own),
+//! declares extra render targets and passes parameters the nodes read with
+//! [`RenderGraphContext::parameter`]. nodes without parameters can be given as just their name:
+//!
+//! ```json
+//! {
+//!     "targets": [{ "name": "half_res_color", "format": "RGBA16Float", "scale": 0.5 }],
+//!     "nodes": ["Scene Textures", "Main", { "name": "Bloom", "parameters": { "mips": 3 } }],
+//!     "edges": [["Main", "Bloom"]]
+//! }
+//! ```
+//!
+//! targets are made the size of the surface times `scale` and shared with the graph under their
+//! name before any node is set up, they are made again when the surface is resized. the app loads
+//! a description from [`Renderer::graph_description`] at startup, plugins build it in their ready:
+//!
+//! ```rust,ignore
+//! let description = app.renderer().graph_description().cloned();
+//! let mut graph = app.renderer_mut().graph();
+//! graph.register_node::<BlurPass>();
+//! graph.build(&description.unwrap_or_else(default_graph))?;
+//! ```
+//!
+//! [`RenderNode::label`]: crate::render_graph::node::RenderNode::label
+//! [`GraphBuilder::register_node`]: crate::render_graph::graph::GraphBuilder::register_node
+//! [`RenderGraphContext::parameter`]: crate::render_graph::graph::RenderGraphContext::parameter
+//! [`Renderer::graph_description`]: crate::core::renderer::Renderer::graph_description
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::core::texture::TextureFormat;
+
+/// a whole render graph, see the [module docs](self)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GraphDescription {
+    /// set up in this order, by the name they were registered with
+    pub nodes: Vec<NodeDescription>,
+    /// `[output, input]` pairs, the output node is drawn before the input node
+    #[serde(default)]
+    pub edges: Vec<[String; 2]>,
+    #[serde(default)]
+    pub targets: Vec<TargetDescription>,
+}
+
+impl GraphDescription {
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).context("invalid render graph description")
+    }
+
+    /// read a description from a json file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        Self::from_json(&json).with_context(|| format!("in {}", path.display()))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("the description to serialize")
+    }
+}
+
+/// one node of the graph
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "NodeEntry")]
+pub struct NodeDescription {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub parameters: Map<String, Value>,
+}
+
+impl NodeDescription {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            parameters: Map::new(),
+        }
+    }
+
+    /// set a parameter the node reads in setup or draw
+    pub fn parameter(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.parameters.insert(name.into(), value.into());
+        self
+    }
+}
+
+/// nodes are either a name or a name with parameters
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NodeEntry {
+    Name(String),
+    Node {
+        name: String,
+        #[serde(default)]
+        parameters: Map<String, Value>,
+    },
+}
+
+impl From<NodeEntry> for NodeDescription {
+    fn from(entry: NodeEntry) -> Self {
+        match entry {
+            NodeEntry::Name(name) => Self::new(name),
+            NodeEntry::Node { name, parameters } => Self { name, parameters },
+        }
+    }
+}
+
+/// a texture the graph makes and shares for nodes to render to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TargetDescription {
+    pub name: String,
+    pub format: TextureFormat,
+    /// the size of the target compared to the surface
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
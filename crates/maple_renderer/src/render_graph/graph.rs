@@ -1,17 +1,36 @@
 use std::{
     any::{Any, TypeId},
     collections::HashMap,
+    sync::Arc,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    path::{Path, PathBuf},
     time::{Duration, Instant},
 };
+#[cfg(target_arch = "wasm32")]
+use web_time::{Duration, Instant};
 
 use crate::{platform::SendSync, types::Dimensions};
 use anyhow::{Result, anyhow};
-use maple_engine::GameContext;
+use maple_engine::{GameContext, prelude::Frame as GameFrame};
 use parking_lot::RwLock;
-
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::core::{
+    readback,
+    texture::{CubeFace, TextureArray, TextureCube, TextureCubeArray},
+};
 use crate::{
-    core::{RenderContext, Renderer},
-    render_graph::node::RenderNode,
+    core::{
+        RenderContext, Renderer,
+        texture::{Texture, TextureCreateInfo, TextureFormat, TextureUsage},
+    },
+    render_graph::{description::GraphDescription, node::RenderNode, stats::RenderStats},
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -34,19 +53,76 @@ pub struct RenderGraph {
     nodes: HashMap<TypeId, (String, RwLock<Box<dyn RenderNode>>)>,
     edges: HashMap<TypeId, Vec<TypeId>>,
     pub context: RwLock<RenderGraphContext>,
+    /// nodes a [`GraphDescription`] can use by name
+    registry: HashMap<String, RegisteredNode>,
+    /// targets made for a [`GraphDescription`], made again on resize
+    targets: Vec<GraphTarget>,
+}
+
+type SetupNode = fn(&RenderContext, &mut RenderGraphContext) -> (String, Box<dyn RenderNode>);
+
+struct RegisteredNode {
+    id: TypeId,
+    setup: SetupNode,
+}
+
+fn setup_boxed<T: RenderNode + 'static>(
+    rcx: &RenderContext,
+    graph_ctx: &mut RenderGraphContext,
+) -> (String, Box<dyn RenderNode>) {
+    (T::label().into(), Box::new(T::setup(rcx, graph_ctx)))
+}
+
+struct GraphTarget {
+    name: &'static str,
+    format: TextureFormat,
+    scale: f32,
+}
+
+impl GraphTarget {
+    fn create(&self, rcx: &RenderContext, dimensions: Dimensions) -> Texture {
+        let size = |side: u32| ((side as f32 * self.scale) as u32).max(1);
+        rcx.device().create_texture(TextureCreateInfo {
+            label: Some(self.name),
+            width: size(dimensions.width),
+            height: size(dimensions.height),
+            format: self.format,
+            usage: TextureUsage::RENDER_ATTACHMENT | TextureUsage::TEXTURE_BINDING,
+            sample_count: 1,
+            mip_level: 1,
+        })
+    }
 }
 
 pub trait GraphResource: Any + SendSync {}
 
+/// timing and size of the frame being drawn, the same for every node in a frame
+///
+/// use this instead of timing passes with their own clock so shader time follows game time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameInfo {
+    /// seconds of game time since the first frame, the sum of every `delta`
+    pub time: f32,
+    /// seconds of game time since the last frame
+    pub delta: f32,
+    /// counts up by one every frame starting at 0
+    pub index: u64,
+    /// the size of the surface being drawn to
+    pub dimensions: Dimensions,
+}
+
 /// the context contains shared resources within the render graph
 ///
-/// these resources are not error checked so be sure to add edges to properly order the nodes
+/// these resources are not error checked so be sure to add edges to properly order the nodes.
+/// nodes without an edge between them can be drawn at the same time on different threads, each
+/// with its own copy of the context, so a resource shared by one of them is only seen by the
+/// nodes after it
 #[derive(Default)]
 pub struct RenderGraphContext {
-    #[cfg(not(target_arch = "wasm32"))]
-    resources: HashMap<&'static str, Box<dyn Any + Send + Sync>>,
-    #[cfg(target_arch = "wasm32")]
-    resources: HashMap<&'static str, Box<dyn Any>>,
+    resources: HashMap<&'static str, Arc<dyn Any + Send + Sync>>,
+    frame: Option<FrameInfo>,
+    /// parameters of each node by label from the [`GraphDescription`]
+    parameters: Arc<HashMap<String, Map<String, Value>>>,
 }
 
 pub struct GraphBuilder<'a> {
@@ -79,16 +155,140 @@ impl<'a> GraphBuilder<'a> {
     pub fn add_edge<Output: RenderNode + 'static, Input: RenderNode + 'static>(&mut self) {
         self.renderer.render_graph.add_edge::<Output, Input>();
     }
+
+    /// let [`GraphDescription`]s add the node by its [`RenderNode::label`]
+    pub fn register_node<T>(&mut self)
+    where
+        T: RenderNode + 'static,
+    {
+        self.renderer.render_graph.registry.insert(
+            T::label().into(),
+            RegisteredNode {
+                id: TypeId::of::<T>(),
+                setup: setup_boxed::<T>,
+            },
+        );
+    }
+
+    /// the description the app was configured with, see [`Renderer::graph_description`]
+    pub fn description(&self) -> Option<&GraphDescription> {
+        self.renderer.graph_description()
+    }
+
+    /// set up and add every node of the description with its targets, parameters and edges
+    ///
+    /// nothing is added if the description names a node that wasn't registered
+    pub fn build(&mut self, description: &GraphDescription) -> Result<()> {
+        let graph = &mut self.renderer.render_graph;
+        let registered = |name: &str| {
+            graph
+                .registry
+                .get(name)
+                .ok_or_else(|| anyhow!("render graph description uses unknown node: {name}"))
+        };
+        let nodes = description
+            .nodes
+            .iter()
+            .map(|node| registered(&node.name).map(|registered| (node, registered.setup)))
+            .collect::<Result<Vec<_>>>()?;
+        let edges = description
+            .edges
+            .iter()
+            .map(|[output, input]| Ok((registered(output)?.id, registered(input)?.id)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let rcx = &self.renderer.context;
+        let mut graph_ctx = graph.context.write();
+        for target in &description.targets {
+            // shared resources are named for the life of the graph
+            let target = GraphTarget {
+                name: Box::leak(target.name.clone().into_boxed_str()),
+                format: target.format,
+                scale: target.scale,
+            };
+            graph_ctx.add_shared_resource(target.name, target.create(rcx, rcx.surface_size()));
+            graph.targets.push(target);
+        }
+
+        for (node, setup) in nodes {
+            Arc::make_mut(&mut graph_ctx.parameters)
+                .insert(node.name.clone(), node.parameters.clone());
+            let (label, node) = setup(rcx, &mut graph_ctx);
+            let id = graph.registry[&label].id;
+            graph.nodes.insert(id, (label, RwLock::new(node)));
+        }
+        drop(graph_ctx);
+
+        for (output, input) in edges {
+            graph.edges.entry(output).or_default().push(input);
+        }
+        Ok(())
+    }
 }
 
 impl RenderGraphContext {
     pub fn add_shared_resource<T: GraphResource>(&mut self, name: &'static str, res: T) {
-        self.resources.insert(name, Box::new(res));
+        self.resources.insert(name, Arc::new(res));
     }
 
     pub fn get_shared_resource<T: GraphResource>(&self, name: &'static str) -> Option<&T> {
         self.resources.get(name)?.downcast_ref()
     }
+
+    /// a parameter the [`GraphDescription`] gave the node labelled `node`
+    ///
+    /// `None` if it wasn't given or can't be read as a `T`
+    pub fn parameter<T: DeserializeOwned>(&self, node: &str, name: &str) -> Option<T> {
+        let value = self.parameters.get(node)?.get(name)?;
+        serde_json::from_value(value.clone())
+            .inspect_err(|e| log::warn!("ignoring parameter {name} of {node}: {e}"))
+            .ok()
+    }
+
+    /// the timing and size of the frame being drawn
+    pub fn frame_info(&self) -> FrameInfo {
+        self.frame.unwrap_or_default()
+    }
+
+    /// a copy for a node drawn alongside others, see [`RenderGraphContext::merge`]
+    fn fork(&self) -> Self {
+        Self {
+            resources: self.resources.clone(),
+            frame: self.frame,
+            parameters: self.parameters.clone(),
+        }
+    }
+
+    /// keep the resources a forked copy shared
+    fn merge(&mut self, fork: Self) {
+        for (name, resource) in fork.resources {
+            let shared = self
+                .resources
+                .get(name)
+                .is_some_and(|current| Arc::ptr_eq(current, &resource));
+            if !shared {
+                self.resources.insert(name, resource);
+            }
+        }
+    }
+
+    /// move the frame info on to the next frame
+    fn advance_frame(&mut self, delta: f32, dimensions: Dimensions) {
+        let frame = match self.frame {
+            Some(last) => FrameInfo {
+                time: last.time + delta,
+                delta,
+                index: last.index + 1,
+                dimensions,
+            },
+            // the first frame has nothing to be a delta from
+            None => FrameInfo {
+                dimensions,
+                ..Default::default()
+            },
+        };
+        self.frame = Some(frame);
+    }
 }
 
 impl RenderGraph {
@@ -110,53 +310,180 @@ impl RenderGraph {
     pub(crate) fn render(&mut self, rcx: &RenderContext, game_ctx: &GameContext) -> Result<()> {
         let layers = self.order_nodes_layered()?;
 
+        let delta = match game_ctx.has_resource::<GameFrame>() {
+            true => game_ctx.get_resource::<GameFrame>().time_delta_f32,
+            false => 0.0,
+        };
+        self.context
+            .write()
+            .advance_frame(delta, rcx.surface_size());
+
+        let stats = game_ctx.has_resource::<RenderStats>();
+        if stats {
+            game_ctx.get_resource_mut::<RenderStats>().begin_frame();
+        }
+
         let mut frame = rcx.create_frame();
 
         let mut timings: HashMap<String, Duration> = HashMap::new();
 
+        // the command log has to be in graph order
+        let parallel = rcx.device().log.is_none();
+
         for layer in layers {
-            #[cfg(not(target_arch = "wasm32"))]
-            layer.iter().try_for_each(|&node_id| -> Result<()> {
-                let (name, node) = self
-                    .nodes
-                    .get(&node_id)
-                    .ok_or(anyhow!("failed to get node: {node_id:?}"))?;
+            let nodes = layer
+                .iter()
+                .map(|node_id| {
+                    self.nodes
+                        .get(node_id)
+                        .ok_or(anyhow!("failed to get node: {node_id:?}"))
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            if !parallel || nodes.len() < 2 {
+                for (name, node) in nodes {
+                    let mut node_guard = node.write();
+                    let mut ctx_guard = self.context.write();
+
+                    frame.set_scope(name);
+                    let start = Instant::now();
+                    node_guard.draw(rcx, &mut frame, &mut ctx_guard, game_ctx);
+                    timings.insert(name.clone(), start.elapsed());
+                }
+                continue;
+            }
 
-                let mut node_guard = node.write();
-                let mut ctx_guard = self.context.write();
+            // nodes in a layer don't depend on each other so each records its own commands on
+            // the worker pool, they are submitted in the layer's order
+            let recorded: Vec<_> = {
+                let ctx_guard = self.context.read();
+                let frame = &frame;
+                nodes
+                    .into_par_iter()
+                    .map(|(name, node)| {
+                        let mut node_frame = frame.split();
+                        let mut node_ctx = ctx_guard.fork();
+
+                        node_frame.set_scope(name);
+                        let start = Instant::now();
+                        node.write()
+                            .draw(rcx, &mut node_frame, &mut node_ctx, game_ctx);
+                        (name, node_frame, node_ctx, start.elapsed())
+                    })
+                    .collect()
+            };
+
+            let mut ctx_guard = self.context.write();
+            let mut frames = Vec::with_capacity(recorded.len());
+            for (name, node_frame, node_ctx, elapsed) in recorded {
+                ctx_guard.merge(node_ctx);
+                timings.insert(name.clone(), elapsed);
+                frames.push(node_frame);
+            }
+            frame.join(frames);
+        }
 
-                let start = Instant::now();
-                node_guard.draw(rcx, &mut frame, &mut ctx_guard, game_ctx);
-                let elapsed = start.elapsed();
+        rcx.submit_frame(frame);
 
-                let entry = timings.entry(name.clone()).or_insert(elapsed);
+        if stats {
+            game_ctx
+                .get_resource_mut::<RenderStats>()
+                .end_frame(rcx.gpu_times());
+        }
 
-                *entry = elapsed;
+        Ok(())
+    }
+
+    /// save every texture in the graph as a png in `dir` and return the paths written
+    ///
+    /// shared textures are named after the name they were shared with and textures from
+    /// [`RenderNode::debug_targets`] after their node. array layers and cube faces are saved as
+    /// separate images. call this outside of a frame to capture what the last frame left in the
+    /// targets
+    ///
+    /// textures that can't be read back such as multisampled ones are skipped with a warning.
+    /// float targets are clamped to 0..1 and depth is stretched between its closest and furthest
+    /// values
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn dump_targets(&self, rcx: &RenderContext, dir: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+
+        let mut targets: Vec<(String, wgpu::Texture, u32)> = Vec::new();
+
+        for (name, resource) in &self.context.read().resources {
+            if let Some(texture) = resource.downcast_ref::<Texture>() {
+                targets.push((name.to_string(), texture.inner.clone(), texture.layer()));
+            } else if let Some(array) = resource.downcast_ref::<TextureArray>() {
+                for layer in 0..array.array_layers() {
+                    targets.push((format!("{name}_{layer}"), array.inner.clone(), layer));
+                }
+            } else if let Some(cube) = resource.downcast_ref::<TextureCube>() {
+                for face in CubeFace::iter() {
+                    targets.push((format!("{name}_{face:?}"), cube.inner.clone(), face as u32));
+                }
+            } else if let Some(cubes) = resource.downcast_ref::<TextureCubeArray>() {
+                for cube in 0..cubes.array_layers() {
+                    for face in CubeFace::iter() {
+                        let layer = cube * 6 + face as u32;
+                        targets.push((
+                            format!("{name}_{cube}_{face:?}"),
+                            cubes.inner.clone(),
+                            layer,
+                        ));
+                    }
+                }
+            }
+        }
 
-                Ok(())
-            })?;
+        for (node_name, node) in self.nodes.values() {
+            for (label, texture) in node.read().debug_targets() {
+                let name = match node_name.is_empty() {
+                    true => label,
+                    false => format!("{node_name}_{label}"),
+                };
+                targets.push((name, texture.inner.clone(), texture.layer()));
+            }
+        }
 
-            #[cfg(target_arch = "wasm32")]
-            for &node_id in layer.iter() {
-                let node = self
-                    .nodes
-                    .get(&node_id)
-                    .ok_or(anyhow!("failed to get node: {node_id:?}"))?;
+        targets.sort_by(|a, b| a.0.cmp(&b.0));
 
-                let mut node_guard = node.write();
-                let mut ctx_guard = self.context.write();
+        let mut written = Vec::new();
+        for (name, texture, layer) in targets {
+            let file_name: String = name
+                .chars()
+                .map(|c| match c.is_alphanumeric() || c == '-' {
+                    true => c,
+                    false => '_',
+                })
+                .collect();
 
-                node_guard.draw(rcx, &mut ctx_guard, scene);
+            match readback::read_layer(rcx.device(), &texture, layer) {
+                Ok(image) => {
+                    let path = dir.join(format!("{file_name}.png"));
+                    image.save(&path)?;
+                    written.push(path);
+                }
+                Err(e) => log::warn!("skipped render target {name}: {e}"),
             }
         }
 
-        rcx.submit_frame(frame);
-
-        Ok(())
+        Ok(written)
     }
 
     /// calls resize for all the nodes
+    /// true before any node was added
+    pub(crate) fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
     pub(crate) fn resize(&mut self, render_ctx: &RenderContext, dimensions: Dimensions) {
+        let mut graph_ctx = self.context.write();
+        for target in &self.targets {
+            graph_ctx.add_shared_resource(target.name, target.create(render_ctx, dimensions));
+        }
+        drop(graph_ctx);
+
         for (_, node_lock) in self.nodes.values_mut() {
             let mut node = node_lock.write();
             node.resize(render_ctx, dimensions);
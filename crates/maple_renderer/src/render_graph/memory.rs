@@ -0,0 +1,319 @@
+//! gpu memory taken by textures, meshes and other assets, grouped by the directory they came from
+//!
+//! the app measures every loaded texture once a second, crates with gpu assets of their own add
+//! them with [`GpuMemoryStats::track`]. assets loaded from a file are grouped by its directory and
+//! anything made at runtime falls under `generated`, so a scene can be checked against a budget
+//! folder by folder. [`GpuMemoryStats::to_json`] dumps the whole report for tools outside the game.
+//!
+//! the app sets the texture budget from the
+//! [`QualitySettings`](crate::types::quality::QualitySettings) and a warning is logged when the
+//! loaded textures go over it.
+//!
+//! ```rust,ignore
+//! let memory = ctx.get_resource::<GpuMemoryStats>();
+//! for category in memory.categories() {
+//!     println!("{}: {} bytes of textures", category.name, category.texture_bytes);
+//! }
+//! std::fs::write("memory.json", memory.to_json())?;
+//! ```
+
+use std::collections::HashMap;
+
+use maple_engine::{
+    asset::{Asset, AssetId, AssetLibrary},
+    prelude::Resource,
+};
+use serde_json::json;
+
+use crate::core::texture::{Texture, TextureFormat};
+
+/// how often [`GpuMemoryStats::refresh`] measures the assets again in seconds
+const REFRESH_EVERY: f32 = 1.0;
+
+/// category of assets that weren't loaded from a file
+pub const GENERATED: &str = "generated";
+
+/// what takes up the memory of an asset
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MemoryKind {
+    Texture {
+        format: TextureFormat,
+        width: u32,
+        height: u32,
+        mip_levels: u32,
+    },
+    Mesh {
+        vertex_bytes: u64,
+        index_bytes: u64,
+    },
+}
+
+impl MemoryKind {
+    pub fn texture(texture: &Texture) -> Self {
+        Self::Texture {
+            format: texture.format(),
+            width: texture.width(),
+            height: texture.height(),
+            mip_levels: texture.mip_level_count(),
+        }
+    }
+
+    pub fn bytes(&self) -> u64 {
+        match *self {
+            Self::Texture {
+                format,
+                width,
+                height,
+                mip_levels,
+            } => format.image_bytes(width, height, mip_levels),
+            Self::Mesh {
+                vertex_bytes,
+                index_bytes,
+            } => vertex_bytes + index_bytes,
+        }
+    }
+}
+
+/// the gpu memory of one asset
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssetMemory {
+    pub id: AssetId,
+    pub kind: MemoryKind,
+}
+
+impl AssetMemory {
+    pub fn bytes(&self) -> u64 {
+        self.kind.bytes()
+    }
+
+    /// the directory the asset was loaded from or [`GENERATED`]
+    pub fn category(&self) -> String {
+        match &self.id {
+            AssetId::Path(path) => path
+                .parent()
+                .map(|dir| dir.display().to_string())
+                .filter(|dir| !dir.is_empty())
+                .unwrap_or_else(|| ".".to_string()),
+            AssetId::Id(_) => GENERATED.to_string(),
+        }
+    }
+
+    /// the file the asset was loaded from or its id
+    pub fn name(&self) -> String {
+        match &self.id {
+            AssetId::Path(path) => path.display().to_string(),
+            AssetId::Id(id) => format!("#{id}"),
+        }
+    }
+}
+
+/// the assets of one directory added up, see [`GpuMemoryStats::categories`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryCategory {
+    pub name: String,
+    pub textures: usize,
+    pub texture_bytes: u64,
+    pub meshes: usize,
+    pub mesh_bytes: u64,
+}
+
+impl MemoryCategory {
+    pub fn bytes(&self) -> u64 {
+        self.texture_bytes + self.mesh_bytes
+    }
+}
+
+type Measure = Box<dyn Fn(&AssetLibrary, &mut Vec<AssetMemory>) + Send + Sync>;
+
+/// gpu memory of the loaded assets, see the [module docs](self)
+///
+/// the app inserts it as a resource and refreshes it once a second
+pub struct GpuMemoryStats {
+    measures: Vec<Measure>,
+    /// largest first
+    assets: Vec<AssetMemory>,
+    since_refresh: f32,
+    texture_budget: Option<u64>,
+    over_budget: bool,
+}
+
+impl Resource for GpuMemoryStats {}
+
+impl Default for GpuMemoryStats {
+    fn default() -> Self {
+        Self {
+            measures: Vec::new(),
+            assets: Vec::new(),
+            // the first refresh measures straight away
+            since_refresh: REFRESH_EVERY,
+            texture_budget: None,
+            over_budget: false,
+        }
+    }
+}
+
+impl GpuMemoryStats {
+    /// measure every loaded asset of type `T` with `measure` from the next refresh on
+    pub fn track<T: Asset>(&mut self, measure: impl Fn(&T) -> MemoryKind + Send + Sync + 'static) {
+        self.measures.push(Box::new(move |library, assets| {
+            library.for_each::<T>(|id, asset| {
+                assets.push(AssetMemory {
+                    id: id.clone(),
+                    kind: measure(asset),
+                });
+            });
+        }));
+    }
+
+    /// measure the assets again once a second has passed since the last time
+    pub fn refresh(&mut self, library: &AssetLibrary, dt: f32) {
+        self.since_refresh += dt;
+        if self.since_refresh >= REFRESH_EVERY {
+            self.refresh_now(library);
+        }
+    }
+
+    /// measure the assets again straight away
+    pub fn refresh_now(&mut self, library: &AssetLibrary) {
+        self.since_refresh = 0.0;
+        self.assets.clear();
+        for measure in &self.measures {
+            measure(library, &mut self.assets);
+        }
+        self.assets
+            .sort_by(|a, b| b.bytes().cmp(&a.bytes()).then_with(|| a.id.cmp(&b.id)));
+
+        // only warn when going over so a scene that stays over doesn't log every second
+        let over_budget = self.is_over_budget();
+        if over_budget && !self.over_budget {
+            log::warn!(
+                "textures take {} bytes, over the budget of {} bytes",
+                self.texture_bytes(),
+                self.texture_budget.unwrap_or_default()
+            );
+        }
+        self.over_budget = over_budget;
+    }
+
+    /// how many bytes of textures the scene should stay under, `None` for no budget
+    pub fn set_texture_budget(&mut self, bytes: Option<u64>) {
+        self.texture_budget = bytes;
+    }
+
+    pub fn texture_budget(&self) -> Option<u64> {
+        self.texture_budget
+    }
+
+    /// true if the measured textures take more than the texture budget
+    pub fn is_over_budget(&self) -> bool {
+        self.texture_budget
+            .is_some_and(|budget| self.texture_bytes() > budget)
+    }
+
+    /// every measured asset, largest first
+    pub fn assets(&self) -> &[AssetMemory] {
+        &self.assets
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.assets.iter().map(AssetMemory::bytes).sum()
+    }
+
+    pub fn texture_bytes(&self) -> u64 {
+        self.assets
+            .iter()
+            .filter(|asset| matches!(asset.kind, MemoryKind::Texture { .. }))
+            .map(AssetMemory::bytes)
+            .sum()
+    }
+
+    /// the assets added up by directory, largest first
+    pub fn categories(&self) -> Vec<MemoryCategory> {
+        let mut categories: HashMap<String, MemoryCategory> = HashMap::new();
+        for asset in &self.assets {
+            let name = asset.category();
+            let category = categories
+                .entry(name.clone())
+                .or_insert_with(|| MemoryCategory {
+                    name,
+                    ..Default::default()
+                });
+            match asset.kind {
+                MemoryKind::Texture { .. } => {
+                    category.textures += 1;
+                    category.texture_bytes += asset.bytes();
+                }
+                MemoryKind::Mesh { .. } => {
+                    category.meshes += 1;
+                    category.mesh_bytes += asset.bytes();
+                }
+            }
+        }
+
+        let mut categories: Vec<MemoryCategory> = categories.into_values().collect();
+        categories.sort_by(|a, b| b.bytes().cmp(&a.bytes()).then_with(|| a.name.cmp(&b.name)));
+        categories
+    }
+
+    /// the report as pretty printed json with the totals, categories and every asset
+    pub fn to_json(&self) -> String {
+        let categories: Vec<_> = self
+            .categories()
+            .iter()
+            .map(|category| {
+                json!({
+                    "name": category.name,
+                    "bytes": category.bytes(),
+                    "textures": category.textures,
+                    "texture_bytes": category.texture_bytes,
+                    "meshes": category.meshes,
+                    "mesh_bytes": category.mesh_bytes,
+                })
+            })
+            .collect();
+
+        let assets: Vec<_> = self
+            .assets
+            .iter()
+            .map(|asset| {
+                let mut value = json!({
+                    "name": asset.name(),
+                    "category": asset.category(),
+                    "bytes": asset.bytes(),
+                });
+                match asset.kind {
+                    MemoryKind::Texture {
+                        format,
+                        width,
+                        height,
+                        mip_levels,
+                    } => {
+                        value["kind"] = json!("texture");
+                        value["format"] = json!(format!("{format:?}"));
+                        value["width"] = json!(width);
+                        value["height"] = json!(height);
+                        value["mip_levels"] = json!(mip_levels);
+                    }
+                    MemoryKind::Mesh {
+                        vertex_bytes,
+                        index_bytes,
+                    } => {
+                        value["kind"] = json!("mesh");
+                        value["vertex_bytes"] = json!(vertex_bytes);
+                        value["index_bytes"] = json!(index_bytes);
+                    }
+                }
+                value
+            })
+            .collect();
+
+        let report = json!({
+            "total_bytes": self.total_bytes(),
+            "texture_bytes": self.texture_bytes(),
+            "texture_budget": self.texture_budget,
+            "categories": categories,
+            "assets": assets,
+        });
+        serde_json::to_string_pretty(&report).expect("the report to serialize")
+    }
+}
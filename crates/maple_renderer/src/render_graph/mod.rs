@@ -1,2 +1,5 @@
+pub mod description;
 pub mod graph;
+pub mod memory;
 pub mod node;
+pub mod stats;
@@ -3,7 +3,7 @@ use maple_engine::GameContext;
 use crate::{
     core::{
         DepthCompare, DepthStencilOptions, Frame, RenderContext,
-        texture::{Texture, TextureView},
+        texture::{Texture, TextureFormat, TextureView},
     },
     platform::SendSync,
     render_graph::graph::{RenderGraphContext, Stage},
@@ -18,6 +18,33 @@ pub enum RenderTarget {
         texture: TextureView,
         resolve: TextureView,
     },
+    /// draw into a multisampled texture the size of the surface that is resolved into the
+    /// surface at the end of the pass, `1` sample draws straight to the surface. pipelines and
+    /// depth buffers drawn with it need the same sample count, see
+    /// [`RenderContext::surface_msaa`]
+    MultiSampledSurface {
+        samples: u32,
+    },
+}
+
+/// a depth buffer the size of the surface, see [`RenderContext::surface_depth`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SurfaceDepth {
+    /// Default: `TextureFormat::Depth32`
+    pub format: TextureFormat,
+    /// must match the sample count of the color targets drawn with it
+    ///
+    /// Default: `1`
+    pub sample_count: u32,
+}
+
+impl Default for SurfaceDepth {
+    fn default() -> Self {
+        Self {
+            format: TextureFormat::Depth32,
+            sample_count: 1,
+        }
+    }
 }
 
 pub enum DepthTarget {
@@ -66,6 +93,9 @@ pub trait RenderNode: SendSync {
         Self: Sized;
 
     /// called every frame here is where you put logic to draw stuff
+    ///
+    /// nodes of the same stage without an edge between them are drawn at the same time on the
+    /// worker pool, so a resource shared here is only seen by the nodes after this one
     fn draw(
         &mut self,
         renderer_ctx: &RenderContext,
@@ -77,4 +107,10 @@ pub trait RenderNode: SendSync {
     /// called when the window resizes if that is relavent to the pass
     #[allow(unused)]
     fn resize(&mut self, render_ctx: &RenderContext, dimensions: Dimensions) {}
+
+    /// textures the node keeps to itself that [`crate::render_graph::graph::RenderGraph::dump_targets`]
+    /// should save along with the shared resources
+    fn debug_targets(&self) -> Vec<(String, Texture)> {
+        Vec::new()
+    }
 }
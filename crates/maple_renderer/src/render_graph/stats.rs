@@ -0,0 +1,182 @@
+//! draw calls, triangles and gpu time of the last frame, per render graph node and per scene node
+//!
+//! passes that draw scene nodes call [`RenderStats::record`] for every node they draw. the gpu
+//! time of a pass is shared between the nodes it drew by how many of its triangles each one was,
+//! so a node that makes up half the triangles of the main pass is given half of its time. gpu
+//! times are only there on devices that can time passes.
+//!
+//! ```rust,ignore
+//! let stats = ctx.get_resource::<RenderStats>();
+//! for (pass, node) in stats.node(selected).passes {
+//!     println!("{pass}: {} draws {} triangles {:?}", node.draw_calls, node.triangles, node.gpu_time);
+//! }
+//! ```
+
+use std::{collections::HashMap, time::Duration};
+
+use maple_engine::{prelude::Resource, scene::NodeId};
+
+/// draw calls and triangles of one node or pass
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DrawStats {
+    /// a node drawn instanced together with other nodes counts the shared draw call too
+    pub draw_calls: u32,
+    pub triangles: u64,
+}
+
+impl std::ops::AddAssign for DrawStats {
+    fn add_assign(&mut self, other: Self) {
+        self.draw_calls += other.draw_calls;
+        self.triangles += other.triangles;
+    }
+}
+
+/// what one render graph node drew in the last frame
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PassStats {
+    /// every node recorded by the pass together
+    pub draws: DrawStats,
+    /// `None` if the device can't time passes or the times aren't back from the gpu yet
+    pub gpu_time: Option<Duration>,
+}
+
+/// what drew one scene node in one pass
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NodePassStats {
+    pub draw_calls: u32,
+    pub triangles: u64,
+    /// the node's share of the pass's gpu time by triangles
+    pub gpu_time: Option<Duration>,
+}
+
+/// what it cost to draw a scene node in the last frame, see [`RenderStats::node`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NodeRenderStats {
+    /// by render graph node label, sorted by label
+    pub passes: Vec<(String, NodePassStats)>,
+}
+
+impl NodeRenderStats {
+    pub fn draw_calls(&self) -> u32 {
+        self.passes.iter().map(|(_, pass)| pass.draw_calls).sum()
+    }
+
+    pub fn triangles(&self) -> u64 {
+        self.passes.iter().map(|(_, pass)| pass.triangles).sum()
+    }
+
+    /// `None` if none of the passes were timed
+    pub fn gpu_time(&self) -> Option<Duration> {
+        self.passes
+            .iter()
+            .filter_map(|(_, pass)| pass.gpu_time)
+            .reduce(|total, time| total + time)
+    }
+}
+
+/// render statistics of the last frame, see the [module docs](self)
+///
+/// the app inserts it as a resource and the render graph clears it at the start of every frame
+#[derive(Debug, Default)]
+pub struct RenderStats {
+    passes: HashMap<String, PassStats>,
+    nodes: HashMap<NodeId, HashMap<String, DrawStats>>,
+    /// the stats being recorded, swapped in once the frame is done so readers see a whole frame
+    recording: HashMap<NodeId, HashMap<String, DrawStats>>,
+    recording_passes: HashMap<String, DrawStats>,
+}
+
+impl Resource for RenderStats {}
+
+impl RenderStats {
+    /// add what `pass` drew of `node` this frame, `pass` is the label of the render graph node
+    pub fn record(&mut self, pass: &str, node: NodeId, draw_calls: u32, triangles: u64) {
+        let draws = DrawStats {
+            draw_calls,
+            triangles,
+        };
+        *self
+            .recording
+            .entry(node)
+            .or_default()
+            .entry(pass.to_string())
+            .or_default() += draws;
+        *self.recording_passes.entry(pass.to_string()).or_default() += draws;
+    }
+
+    /// the stats of the render graph node labelled `pass`
+    pub fn pass(&self, pass: &str) -> Option<PassStats> {
+        self.passes.get(pass).copied()
+    }
+
+    /// every render graph node that drew or was timed in the last frame
+    pub fn passes(&self) -> impl Iterator<Item = (&str, &PassStats)> {
+        self.passes
+            .iter()
+            .map(|(label, stats)| (label.as_str(), stats))
+    }
+
+    /// every pass `node` was drawn in with its share of the gpu time
+    pub fn node(&self, node: NodeId) -> NodeRenderStats {
+        let Some(passes) = self.nodes.get(&node) else {
+            return NodeRenderStats::default();
+        };
+
+        let mut passes: Vec<(String, NodePassStats)> = passes
+            .iter()
+            .map(|(label, draws)| {
+                let gpu_time = self.passes.get(label).and_then(|pass| {
+                    let time = pass.gpu_time?;
+                    let share = match pass.draws.triangles {
+                        0 => 1.0 / self.draws_in(label).max(1) as f64,
+                        total => draws.triangles as f64 / total as f64,
+                    };
+                    Some(time.mul_f64(share))
+                });
+                let stats = NodePassStats {
+                    draw_calls: draws.draw_calls,
+                    triangles: draws.triangles,
+                    gpu_time,
+                };
+                (label.clone(), stats)
+            })
+            .collect();
+        passes.sort_by(|a, b| a.0.cmp(&b.0));
+
+        NodeRenderStats { passes }
+    }
+
+    /// the scene nodes recorded by `pass`
+    fn draws_in(&self, pass: &str) -> usize {
+        self.nodes
+            .values()
+            .filter(|passes| passes.contains_key(pass))
+            .count()
+    }
+
+    /// drop anything left from a frame that wasn't finished
+    pub(crate) fn begin_frame(&mut self) {
+        self.recording.clear();
+        self.recording_passes.clear();
+    }
+
+    /// finish the frame being recorded, `gpu_times` are the latest times of each graph node
+    pub(crate) fn end_frame(&mut self, gpu_times: HashMap<String, Duration>) {
+        self.nodes = std::mem::take(&mut self.recording);
+
+        let mut passes: HashMap<String, PassStats> = std::mem::take(&mut self.recording_passes)
+            .into_iter()
+            .map(|(label, draws)| {
+                let stats = PassStats {
+                    draws,
+                    gpu_time: None,
+                };
+                (label, stats)
+            })
+            .collect();
+        for (label, time) in gpu_times {
+            passes.entry(label).or_default().gpu_time = Some(time);
+        }
+        self.passes = passes;
+    }
+}
@@ -1,6 +1,9 @@
 use maple_engine::asset::{Asset, AssetLoader, IntoAsset, LoadErr};
 
-use crate::core::{RenderDevice, ShaderStage};
+use crate::{
+    core::{RenderDevice, ShaderStage},
+    shader_include::{IncludeError, resolve_includes},
+};
 
 #[derive(Debug, Clone)]
 pub struct Shader {
@@ -26,10 +29,13 @@ impl Shader {
     }
 
     pub(crate) fn compile(device: &RenderDevice, shader: ShaderSource) -> Result<Self, LoadErr> {
+        let include_err = |err: IncludeError| LoadErr::Import(err.to_string());
         let source = match shader.source {
-            EmbeddedSource::Wgsl(code) => wgpu::ShaderSource::Wgsl(code.into()),
+            EmbeddedSource::Wgsl(code) => {
+                wgpu::ShaderSource::Wgsl(resolve_includes(code).map_err(include_err)?)
+            }
             EmbeddedSource::Glsl { source, stage } => wgpu::ShaderSource::Glsl {
-                shader: source.into(),
+                shader: resolve_includes(source).map_err(include_err)?,
                 stage: stage.into(),
                 defines: &[],
             },
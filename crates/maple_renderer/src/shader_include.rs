@@ -0,0 +1,158 @@
+//! `#include` for WGSL and GLSL shaders
+//!
+//! a line like `#include "maple/brdf.wgsl"` is replaced with the source registered at that
+//! virtual path before a shader is compiled, so shaders can share code instead of copying it.
+//! each path is only pasted once per shader, later includes of it are skipped, so chunks can
+//! include what they use without breaking shaders that include it too.
+//!
+//! the engine ships these chunks, each one in WGSL and GLSL:
+//! - `maple/common` constants like `PI` and `TAU`
+//! - `maple/brdf` the cook torrance terms and GGX importance sampling
+//! - `maple/shadow` filtered shadow map lookups
+//! - `maple/noise` seeded perlin, simplex and fractal noise, the same as
+//!   [`maple_engine::utils::noise`]
+//! - `maple/tonemap` ACES, reinhard and luminance
+//!
+//! add `.wgsl` or `.glsl` to the name to pick the language. games can add their own chunks with
+//! [`register_include`].
+//!
+//! # Example
+//! ```rust,ignore
+//! register_include("game/wind.wgsl", include_str!("wind.wgsl"));
+//!
+//! // in a shader
+//! #include "maple/brdf.wgsl"
+//! #include "game/wind.wgsl"
+//! ```
+
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    sync::LazyLock,
+};
+
+use parking_lot::RwLock;
+use thiserror::Error;
+
+/// the chunks that come with the engine
+const BUILTIN: &[(&str, &str)] = &[
+    ("maple/common.wgsl", include_str!("shaders/common.wgsl")),
+    ("maple/brdf.wgsl", include_str!("shaders/brdf.wgsl")),
+    ("maple/shadow.wgsl", include_str!("shaders/shadow.wgsl")),
+    ("maple/noise.wgsl", maple_engine::utils::noise::NOISE_WGSL),
+    ("maple/tonemap.wgsl", include_str!("shaders/tonemap.wgsl")),
+    ("maple/common.glsl", include_str!("shaders/common.glsl")),
+    ("maple/brdf.glsl", include_str!("shaders/brdf.glsl")),
+    ("maple/shadow.glsl", include_str!("shaders/shadow.glsl")),
+    ("maple/noise.glsl", include_str!("shaders/noise.glsl")),
+    ("maple/tonemap.glsl", include_str!("shaders/tonemap.glsl")),
+];
+
+static INCLUDES: LazyLock<RwLock<HashMap<String, Cow<'static, str>>>> = LazyLock::new(|| {
+    RwLock::new(
+        BUILTIN
+            .iter()
+            .map(|(path, source)| (path.to_string(), Cow::Borrowed(*source)))
+            .collect(),
+    )
+});
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum IncludeError {
+    #[error("{file} line {line}: nothing is registered at `{path}`")]
+    NotFound {
+        path: String,
+        file: String,
+        line: usize,
+    },
+    #[error("{file} line {line}: expected `#include \"path\"`")]
+    Malformed { file: String, line: usize },
+}
+
+/// make `source` includable at `path`, replacing whatever was there
+///
+/// registering over a `maple/` path changes the chunk for every shader compiled afterwards,
+/// including the engine's own
+pub fn register_include(path: impl Into<String>, source: impl Into<Cow<'static, str>>) {
+    INCLUDES.write().insert(path.into(), source.into());
+}
+
+/// true if something is registered at `path`
+pub fn has_include(path: &str) -> bool {
+    INCLUDES.read().contains_key(path)
+}
+
+/// paste every included chunk into a shader, see the [module docs](self)
+///
+/// shaders without an `#include` are returned as they are
+pub fn resolve_includes(source: &str) -> Result<Cow<'_, str>, IncludeError> {
+    if !source
+        .lines()
+        .any(|line| line.trim_start().starts_with("#include"))
+    {
+        return Ok(Cow::Borrowed(source));
+    }
+
+    let includes = INCLUDES.read();
+    let mut resolved = String::with_capacity(source.len());
+    expand(
+        source,
+        "shader",
+        &includes,
+        &mut HashSet::new(),
+        &mut resolved,
+    )?;
+    Ok(Cow::Owned(resolved))
+}
+
+fn expand<'a>(
+    source: &str,
+    file: &str,
+    includes: &'a HashMap<String, Cow<'static, str>>,
+    included: &mut HashSet<&'a str>,
+    resolved: &mut String,
+) -> Result<(), IncludeError> {
+    for (index, line) in source.lines().enumerate() {
+        let Some(rest) = line.trim_start().strip_prefix("#include") else {
+            resolved.push_str(line);
+            resolved.push('\n');
+            continue;
+        };
+
+        let path = include_path(rest).ok_or_else(|| IncludeError::Malformed {
+            file: file.to_string(),
+            line: index + 1,
+        })?;
+        let Some((path, chunk)) = includes.get_key_value(path) else {
+            return Err(IncludeError::NotFound {
+                path: path.to_string(),
+                file: file.to_string(),
+                line: index + 1,
+            });
+        };
+
+        // marking the path before expanding it also stops chunks that include each other
+        if included.insert(path) {
+            expand(chunk, path, includes, included, resolved)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// the path in `"path"` or `<path>`
+fn include_path(rest: &str) -> Option<&str> {
+    let rest = rest.trim();
+    let (open, close) = match rest.chars().next()? {
+        '"' => ('"', '"'),
+        '<' => ('<', '>'),
+        _ => return None,
+    };
+    let inner = rest.strip_prefix(open)?;
+    let end = inner.find(close)?;
+    let trailing = inner[end + 1..].trim_start();
+    match trailing.is_empty() || trailing.starts_with("//") {
+        true => Some(&inner[..end]).filter(|path| !path.is_empty()),
+        false => None,
+    }
+}
@@ -0,0 +1,5 @@
+// constants shared by the other maple chunks
+
+const float PI = 3.14159265359;
+const float TAU = 6.28318530718;
+const float INV_PI = 0.31830988618;
@@ -0,0 +1,5 @@
+// constants shared by the other maple chunks
+
+const PI: f32 = 3.14159265359;
+const TAU: f32 = 6.28318530718;
+const INV_PI: f32 = 0.31830988618;
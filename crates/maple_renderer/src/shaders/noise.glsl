@@ -0,0 +1,210 @@
+// seeded gradient noise, the same as maple/noise.wgsl and maple_engine::utils::noise
+
+uint noise_hash(uint value) {
+    uint state = value * 747796405u + 2891336453u;
+    uint word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+uint noise_hash2(ivec2 cell, uint seed) {
+    return noise_hash(uint(cell.x) ^ noise_hash(uint(cell.y) ^ noise_hash(seed)));
+}
+
+uint noise_hash3(ivec3 cell, uint seed) {
+    return noise_hash(uint(cell.x) ^ noise_hash(uint(cell.y) ^ noise_hash(uint(cell.z) ^ noise_hash(seed))));
+}
+
+const float NOISE_DIAGONAL = 0.70710678;
+
+vec2 noise_gradient2(ivec2 cell, uint seed) {
+    vec2 gradients[8] = vec2[8](
+        vec2(1.0, 0.0),
+        vec2(-1.0, 0.0),
+        vec2(0.0, 1.0),
+        vec2(0.0, -1.0),
+        vec2(NOISE_DIAGONAL, NOISE_DIAGONAL),
+        vec2(-NOISE_DIAGONAL, NOISE_DIAGONAL),
+        vec2(NOISE_DIAGONAL, -NOISE_DIAGONAL),
+        vec2(-NOISE_DIAGONAL, -NOISE_DIAGONAL)
+    );
+    return gradients[noise_hash2(cell, seed) & 7u];
+}
+
+vec3 noise_gradient3(ivec3 cell, uint seed) {
+    vec3 gradients[12] = vec3[12](
+        vec3(1.0, 1.0, 0.0),
+        vec3(-1.0, 1.0, 0.0),
+        vec3(1.0, -1.0, 0.0),
+        vec3(-1.0, -1.0, 0.0),
+        vec3(1.0, 0.0, 1.0),
+        vec3(-1.0, 0.0, 1.0),
+        vec3(1.0, 0.0, -1.0),
+        vec3(-1.0, 0.0, -1.0),
+        vec3(0.0, 1.0, 1.0),
+        vec3(0.0, -1.0, 1.0),
+        vec3(0.0, 1.0, -1.0),
+        vec3(0.0, -1.0, -1.0)
+    );
+    return gradients[noise_hash3(cell, seed) % 12u];
+}
+
+float noise_fade(float t) {
+    return t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+}
+
+float noise_perlin2(vec2 point, uint seed) {
+    vec2 floored = floor(point);
+    vec2 local_point = point - floored;
+    ivec2 cell = ivec2(floored);
+
+    float c00 = dot(noise_gradient2(cell, seed), local_point);
+    float c10 = dot(noise_gradient2(cell + ivec2(1, 0), seed), local_point - vec2(1.0, 0.0));
+    float c01 = dot(noise_gradient2(cell + ivec2(0, 1), seed), local_point - vec2(0.0, 1.0));
+    float c11 = dot(noise_gradient2(cell + ivec2(1, 1), seed), local_point - vec2(1.0, 1.0));
+
+    vec2 u = vec2(noise_fade(local_point.x), noise_fade(local_point.y));
+    return mix(mix(c00, c10, u.x), mix(c01, c11, u.x), u.y) * 1.41421356;
+}
+
+float noise_perlin3(vec3 point, uint seed) {
+    vec3 floored = floor(point);
+    vec3 local_point = point - floored;
+    ivec3 cell = ivec3(floored);
+
+    float c000 = dot(noise_gradient3(cell, seed), local_point);
+    float c100 = dot(noise_gradient3(cell + ivec3(1, 0, 0), seed), local_point - vec3(1.0, 0.0, 0.0));
+    float c010 = dot(noise_gradient3(cell + ivec3(0, 1, 0), seed), local_point - vec3(0.0, 1.0, 0.0));
+    float c110 = dot(noise_gradient3(cell + ivec3(1, 1, 0), seed), local_point - vec3(1.0, 1.0, 0.0));
+    float c001 = dot(noise_gradient3(cell + ivec3(0, 0, 1), seed), local_point - vec3(0.0, 0.0, 1.0));
+    float c101 = dot(noise_gradient3(cell + ivec3(1, 0, 1), seed), local_point - vec3(1.0, 0.0, 1.0));
+    float c011 = dot(noise_gradient3(cell + ivec3(0, 1, 1), seed), local_point - vec3(0.0, 1.0, 1.0));
+    float c111 = dot(noise_gradient3(cell + ivec3(1, 1, 1), seed), local_point - vec3(1.0, 1.0, 1.0));
+
+    vec3 u = vec3(noise_fade(local_point.x), noise_fade(local_point.y), noise_fade(local_point.z));
+    float near = mix(mix(c000, c100, u.x), mix(c010, c110, u.x), u.y);
+    float far = mix(mix(c001, c101, u.x), mix(c011, c111, u.x), u.y);
+    return mix(near, far, u.z);
+}
+
+float noise_simplex2_corner(ivec2 cell, vec2 distance_to, uint seed) {
+    float t = 0.5 - dot(distance_to, distance_to);
+    if (t <= 0.0) {
+        return 0.0;
+    }
+    float t2 = t * t;
+    return t2 * t2 * dot(noise_gradient2(cell, seed), distance_to);
+}
+
+float noise_simplex2(vec2 point, uint seed) {
+    const float skew = 0.36602542;
+    const float unskew = 0.21132487;
+
+    vec2 floored = floor(point + vec2((point.x + point.y) * skew));
+    vec2 origin = point - (floored - vec2((floored.x + floored.y) * unskew));
+    ivec2 cell = ivec2(floored);
+
+    ivec2 middle = ivec2(0, 1);
+    if (origin.x > origin.y) {
+        middle = ivec2(1, 0);
+    }
+
+    float total = noise_simplex2_corner(cell, origin, seed);
+    total += noise_simplex2_corner(cell + middle, origin - vec2(middle) + vec2(unskew), seed);
+    total += noise_simplex2_corner(cell + ivec2(1, 1), origin - vec2(1.0) + vec2(2.0 * unskew), seed);
+    return total * 99.0;
+}
+
+float noise_simplex3_corner(ivec3 cell, vec3 distance_to, uint seed) {
+    float t = 0.6 - dot(distance_to, distance_to);
+    if (t <= 0.0) {
+        return 0.0;
+    }
+    float t2 = t * t;
+    return t2 * t2 * dot(noise_gradient3(cell, seed), distance_to);
+}
+
+float noise_simplex3(vec3 point, uint seed) {
+    const float skew = 1.0 / 3.0;
+    const float unskew = 1.0 / 6.0;
+
+    vec3 floored = floor(point + vec3((point.x + point.y + point.z) * skew));
+    vec3 origin = point - (floored - vec3((floored.x + floored.y + floored.z) * unskew));
+    ivec3 cell = ivec3(floored);
+
+    ivec3 first;
+    ivec3 second;
+    if (origin.x >= origin.y) {
+        if (origin.y >= origin.z) {
+            first = ivec3(1, 0, 0);
+            second = ivec3(1, 1, 0);
+        } else if (origin.x >= origin.z) {
+            first = ivec3(1, 0, 0);
+            second = ivec3(1, 0, 1);
+        } else {
+            first = ivec3(0, 0, 1);
+            second = ivec3(1, 0, 1);
+        }
+    } else {
+        if (origin.y < origin.z) {
+            first = ivec3(0, 0, 1);
+            second = ivec3(0, 1, 1);
+        } else if (origin.x < origin.z) {
+            first = ivec3(0, 1, 0);
+            second = ivec3(0, 1, 1);
+        } else {
+            first = ivec3(0, 1, 0);
+            second = ivec3(1, 1, 0);
+        }
+    }
+
+    float total = noise_simplex3_corner(cell, origin, seed);
+    total += noise_simplex3_corner(cell + first, origin - vec3(first) + vec3(unskew), seed);
+    total += noise_simplex3_corner(cell + second, origin - vec3(second) + vec3(2.0 * unskew), seed);
+    total += noise_simplex3_corner(cell + ivec3(1, 1, 1), origin - vec3(1.0) + vec3(3.0 * unskew), seed);
+    return total * 32.0;
+}
+
+// kind is 0 for perlin and 1 for simplex, like NoiseKind
+float noise_fbm2(vec2 point, uint seed, uint kind, uint octaves, float frequency, float lacunarity, float gain) {
+    float freq = frequency;
+    float amplitude = 1.0;
+    float total = 0.0;
+    float max_total = 0.0;
+
+    for (uint octave = 0u; octave < max(octaves, 1u); octave++) {
+        float value;
+        if (kind == 0u) {
+            value = noise_perlin2(point * freq, seed + octave);
+        } else {
+            value = noise_simplex2(point * freq, seed + octave);
+        }
+        total += value * amplitude;
+        max_total += amplitude;
+        freq *= lacunarity;
+        amplitude *= gain;
+    }
+
+    return total / max_total;
+}
+
+float noise_fbm3(vec3 point, uint seed, uint kind, uint octaves, float frequency, float lacunarity, float gain) {
+    float freq = frequency;
+    float amplitude = 1.0;
+    float total = 0.0;
+    float max_total = 0.0;
+
+    for (uint octave = 0u; octave < max(octaves, 1u); octave++) {
+        float value;
+        if (kind == 0u) {
+            value = noise_perlin3(point * freq, seed + octave);
+        } else {
+            value = noise_simplex3(point * freq, seed + octave);
+        }
+        total += value * amplitude;
+        max_total += amplitude;
+        freq *= lacunarity;
+        amplitude *= gain;
+    }
+
+    return total / max_total;
+}
@@ -0,0 +1,154 @@
+// filtered shadow map lookups
+//
+// the maps and samplers are passed in so these work with any bindings. every lookup returns
+// how lit the point is, 0 in full shadow and 1 fully lit
+
+// the filters a light can pick, see maple_3d's ShadowFilter
+const uint SHADOW_FILTER_HARD = 0u;
+const uint SHADOW_FILTER_PCF_3X3 = 1u;
+const uint SHADOW_FILTER_PCF_5X5 = 2u;
+const uint SHADOW_FILTER_PCSS = 3u;
+
+// 16 well spread points around the origin about 1 from it
+const vec2 POISSON_DISK[16] = vec2[16](
+    vec2(-0.94201624, -0.39906216),
+    vec2(0.94558609, -0.76890725),
+    vec2(-0.09418410, -0.92938870),
+    vec2(0.34495938, 0.29387760),
+    vec2(-0.91588581, 0.45771432),
+    vec2(-0.81544232, -0.87912464),
+    vec2(-0.38277543, 0.27676845),
+    vec2(0.97484398, 0.75648379),
+    vec2(0.44323325, -0.97511554),
+    vec2(0.53742981, -0.47373420),
+    vec2(-0.26496911, -0.41893023),
+    vec2(0.79197514, 0.19090188),
+    vec2(-0.24188840, 0.99706507),
+    vec2(-0.81409955, 0.91437590),
+    vec2(0.19984126, 0.78641367),
+    vec2(0.14383161, -0.14100790)
+);
+
+// a dithering value from 0 to 1 that changes every pixel and every frame
+float interleaved_gradient_noise(vec2 pixel_coordinates, uint frame) {
+    vec2 xy = pixel_coordinates + 5.588238 * float(frame % 64u);
+    return fract(52.9829189 * fract(0.06711056 * xy.x + 0.00583715 * xy.y));
+}
+
+// a single hardware filtered compare
+float sample_shadow_hardware(texture2DArray shadow_map, samplerShadow shadow_sampler, vec2 uv, float depth, int layer) {
+    return texture(sampler2DArrayShadow(shadow_map, shadow_sampler), vec4(uv, float(layer), depth));
+}
+
+// an evenly weighted 3x3 grid of compares spaced `radius` texels apart
+float sample_shadow_pcf(texture2DArray shadow_map, samplerShadow shadow_sampler, vec2 uv, float depth, int layer, float radius) {
+    vec2 texel = radius / vec2(textureSize(sampler2DArrayShadow(shadow_map, shadow_sampler), 0).xy);
+
+    float sum = 0.0;
+    for (int x = -1; x <= 1; x++) {
+        for (int y = -1; y <= 1; y++) {
+            vec2 offset = vec2(float(x), float(y)) * texel;
+            sum += texture(sampler2DArrayShadow(shadow_map, shadow_sampler), vec4(uv + offset, float(layer), depth));
+        }
+    }
+    return sum / 9.0;
+}
+
+// a random rotation for every pixel so the poisson disk turns into noise instead of banding
+mat2 poisson_rotation(vec2 pixel_coordinates) {
+    float angle = 6.28318530 * interleaved_gradient_noise(pixel_coordinates, 0u);
+    float s = sin(angle);
+    float c = cos(angle);
+    return mat2(c, s, -s, c);
+}
+
+// the average of compares over the rotated poisson disk scaled to `radius` in uv
+float sample_shadow_poisson(texture2DArray shadow_map, samplerShadow shadow_sampler, vec2 uv, float depth, int layer, float radius, mat2 rotation) {
+    float sum = 0.0;
+    for (int i = 0; i < 16; i++) {
+        vec2 offset = rotation * POISSON_DISK[i] * radius;
+        sum += texture(sampler2DArrayShadow(shadow_map, shadow_sampler), vec4(uv + offset, float(layer), depth));
+    }
+    return sum / 16.0;
+}
+
+// the average depth of what is closer to the light than `depth` in the rotated poisson disk
+// scaled to `radius` in uv, -1 if nothing is
+float find_shadow_blockers(texture2DArray shadow_map, sampler depth_sampler, vec2 uv, float depth, int layer, float radius, mat2 rotation) {
+    float sum = 0.0;
+    float count = 0.0;
+    for (int i = 0; i < 16; i++) {
+        vec2 offset = rotation * POISSON_DISK[i] * radius;
+        float blocker = textureLod(sampler2DArray(shadow_map, depth_sampler), vec3(uv + offset, float(layer)), 0.0).r;
+        if (blocker < depth) {
+            sum += blocker;
+            count += 1.0;
+        }
+    }
+    return count > 0.0 ? sum / count : -1.0;
+}
+
+// a 13 tap tent filter made from 9 hardware compares (castano 2013), taken from bevy
+float sample_shadow_castano_thirteen(texture2DArray shadow_map, samplerShadow shadow_sampler, vec2 uv, float depth, int layer) {
+    vec2 shadow_map_size = vec2(textureSize(sampler2DArrayShadow(shadow_map, shadow_sampler), 0).xy);
+    vec2 inv_shadow_map_size = 1.0 / shadow_map_size;
+    vec2 texel_uv = uv * shadow_map_size;
+    vec2 base_uv = floor(texel_uv + 0.5);
+    float s = texel_uv.x + 0.5 - base_uv.x;
+    float t = texel_uv.y + 0.5 - base_uv.y;
+    base_uv -= 0.5;
+    base_uv *= inv_shadow_map_size;
+
+    float uw0 = 4.0 - 3.0 * s;
+    float uw1 = 7.0;
+    float uw2 = 1.0 + 3.0 * s;
+    float u0 = (3.0 - 2.0 * s) / uw0 - 2.0;
+    float u1 = (3.0 + s) / uw1;
+    float u2 = s / uw2 + 2.0;
+
+    float vw0 = 4.0 - 3.0 * t;
+    float vw1 = 7.0;
+    float vw2 = 1.0 + 3.0 * t;
+    float v0 = (3.0 - 2.0 * t) / vw0 - 2.0;
+    float v1 = (3.0 + t) / vw1;
+    float v2 = t / vw2 + 2.0;
+
+    float sum = 0.0;
+    sum += uw0 * vw0 * texture(sampler2DArrayShadow(shadow_map, shadow_sampler), vec4(base_uv + vec2(u0, v0) * inv_shadow_map_size, float(layer), depth));
+    sum += uw1 * vw0 * texture(sampler2DArrayShadow(shadow_map, shadow_sampler), vec4(base_uv + vec2(u1, v0) * inv_shadow_map_size, float(layer), depth));
+    sum += uw2 * vw0 * texture(sampler2DArrayShadow(shadow_map, shadow_sampler), vec4(base_uv + vec2(u2, v0) * inv_shadow_map_size, float(layer), depth));
+    sum += uw0 * vw1 * texture(sampler2DArrayShadow(shadow_map, shadow_sampler), vec4(base_uv + vec2(u0, v1) * inv_shadow_map_size, float(layer), depth));
+    sum += uw1 * vw1 * texture(sampler2DArrayShadow(shadow_map, shadow_sampler), vec4(base_uv + vec2(u1, v1) * inv_shadow_map_size, float(layer), depth));
+    sum += uw2 * vw1 * texture(sampler2DArrayShadow(shadow_map, shadow_sampler), vec4(base_uv + vec2(u2, v1) * inv_shadow_map_size, float(layer), depth));
+    sum += uw0 * vw2 * texture(sampler2DArrayShadow(shadow_map, shadow_sampler), vec4(base_uv + vec2(u0, v2) * inv_shadow_map_size, float(layer), depth));
+    sum += uw1 * vw2 * texture(sampler2DArrayShadow(shadow_map, shadow_sampler), vec4(base_uv + vec2(u1, v2) * inv_shadow_map_size, float(layer), depth));
+    sum += uw2 * vw2 * texture(sampler2DArrayShadow(shadow_map, shadow_sampler), vec4(base_uv + vec2(u2, v2) * inv_shadow_map_size, float(layer), depth));
+
+    return sum * (1.0 / 144.0);
+}
+
+// a compare in a cube shadow map, `depth` is the distance to the light mapped the way the map
+// was rendered
+float sample_point_shadow(textureCubeArray shadow_map, samplerShadow shadow_sampler, vec3 direction, float depth, int layer) {
+    return texture(samplerCubeArrayShadow(shadow_map, shadow_sampler), vec4(direction, float(layer)), depth);
+}
+
+// two directions perpendicular to `direction` and each other, as long as it is
+mat2x3 point_shadow_tangents(vec3 direction) {
+    vec3 up = abs(direction.y) > 0.99 * length(direction) ? vec3(1.0, 0.0, 0.0) : vec3(0.0, 1.0, 0.0);
+    vec3 tangent = normalize(cross(up, direction)) * length(direction);
+    vec3 bitangent = normalize(cross(direction, tangent)) * length(direction);
+    return mat2x3(tangent, bitangent);
+}
+
+// the average of compares in a cube shadow map over the rotated poisson disk scaled to `radius`
+// times the length of `direction`
+float sample_point_shadow_poisson(textureCubeArray shadow_map, samplerShadow shadow_sampler, vec3 direction, float depth, int layer, float radius, mat2 rotation) {
+    mat2x3 tangents = point_shadow_tangents(direction);
+    float sum = 0.0;
+    for (int i = 0; i < 16; i++) {
+        vec3 offset = tangents * (rotation * POISSON_DISK[i] * radius);
+        sum += texture(samplerCubeArrayShadow(shadow_map, shadow_sampler), vec4(direction + offset, float(layer)), depth);
+    }
+    return sum / 16.0;
+}
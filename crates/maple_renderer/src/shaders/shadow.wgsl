@@ -0,0 +1,270 @@
+// filtered shadow map lookups
+//
+// the maps and samplers are passed in so these work with any bindings. every lookup returns
+// how lit the point is, 0 in full shadow and 1 fully lit
+
+// the filters a light can pick, see maple_3d's ShadowFilter
+const SHADOW_FILTER_HARD: u32 = 0u;
+const SHADOW_FILTER_PCF_3X3: u32 = 1u;
+const SHADOW_FILTER_PCF_5X5: u32 = 2u;
+const SHADOW_FILTER_PCSS: u32 = 3u;
+
+// 16 well spread points around the origin about 1 from it
+const POISSON_DISK: array<vec2<f32>, 16> = array(
+    vec2<f32>(-0.94201624, -0.39906216),
+    vec2<f32>(0.94558609, -0.76890725),
+    vec2<f32>(-0.09418410, -0.92938870),
+    vec2<f32>(0.34495938, 0.29387760),
+    vec2<f32>(-0.91588581, 0.45771432),
+    vec2<f32>(-0.81544232, -0.87912464),
+    vec2<f32>(-0.38277543, 0.27676845),
+    vec2<f32>(0.97484398, 0.75648379),
+    vec2<f32>(0.44323325, -0.97511554),
+    vec2<f32>(0.53742981, -0.47373420),
+    vec2<f32>(-0.26496911, -0.41893023),
+    vec2<f32>(0.79197514, 0.19090188),
+    vec2<f32>(-0.24188840, 0.99706507),
+    vec2<f32>(-0.81409955, 0.91437590),
+    vec2<f32>(0.19984126, 0.78641367),
+    vec2<f32>(0.14383161, -0.14100790),
+);
+
+// a dithering value from 0 to 1 that changes every pixel and every frame
+fn interleaved_gradient_noise(pixel_coordinates: vec2<f32>, frame: u32) -> f32 {
+    let xy = pixel_coordinates + 5.588238 * f32(frame % 64u);
+    return fract(52.9829189 * fract(0.06711056 * xy.x + 0.00583715 * xy.y));
+}
+
+// a single hardware filtered compare
+fn sample_shadow_hardware(
+    shadow_map: texture_depth_2d_array,
+    shadow_sampler: sampler_comparison,
+    uv: vec2<f32>,
+    depth: f32,
+    layer: i32,
+) -> f32 {
+    return textureSampleCompareLevel(shadow_map, shadow_sampler, uv, layer, depth);
+}
+
+// an evenly weighted 3x3 grid of compares spaced `radius` texels apart
+fn sample_shadow_pcf(
+    shadow_map: texture_depth_2d_array,
+    shadow_sampler: sampler_comparison,
+    uv: vec2<f32>,
+    depth: f32,
+    layer: i32,
+    radius: f32,
+) -> f32 {
+    let texel = radius / vec2<f32>(textureDimensions(shadow_map));
+
+    var sum = 0.0;
+    for (var x = -1; x <= 1; x++) {
+        for (var y = -1; y <= 1; y++) {
+            let offset = vec2<f32>(f32(x), f32(y)) * texel;
+            sum += textureSampleCompareLevel(shadow_map, shadow_sampler, uv + offset, layer, depth);
+        }
+    }
+    return sum / 9.0;
+}
+
+// a random rotation for every pixel so the poisson disk turns into noise instead of banding
+fn poisson_rotation(pixel_coordinates: vec2<f32>) -> mat2x2<f32> {
+    let angle = 6.28318530 * interleaved_gradient_noise(pixel_coordinates, 0u);
+    let s = sin(angle);
+    let c = cos(angle);
+    return mat2x2<f32>(c, s, -s, c);
+}
+
+// the average of compares over the rotated poisson disk scaled to `radius` in uv
+fn sample_shadow_poisson(
+    shadow_map: texture_depth_2d_array,
+    shadow_sampler: sampler_comparison,
+    uv: vec2<f32>,
+    depth: f32,
+    layer: i32,
+    radius: f32,
+    rotation: mat2x2<f32>,
+) -> f32 {
+    var sum = 0.0;
+    for (var i = 0; i < 16; i++) {
+        let offset = rotation * POISSON_DISK[i] * radius;
+        sum += textureSampleCompareLevel(shadow_map, shadow_sampler, uv + offset, layer, depth);
+    }
+    return sum / 16.0;
+}
+
+// the average depth of what is closer to the light than `depth` in the rotated poisson disk
+// scaled to `radius` in uv, -1 if nothing is
+fn find_shadow_blockers(
+    shadow_map: texture_depth_2d_array,
+    depth_sampler: sampler,
+    uv: vec2<f32>,
+    depth: f32,
+    layer: i32,
+    radius: f32,
+    rotation: mat2x2<f32>,
+) -> f32 {
+    var sum = 0.0;
+    var count = 0.0;
+    for (var i = 0; i < 16; i++) {
+        let offset = rotation * POISSON_DISK[i] * radius;
+        let blocker = textureSampleLevel(shadow_map, depth_sampler, uv + offset, layer, 0);
+        if blocker < depth {
+            sum += blocker;
+            count += 1.0;
+        }
+    }
+    return select(-1.0, sum / max(count, 1.0), count > 0.0);
+}
+
+// a 13 tap tent filter made from 9 hardware compares (castano 2013), taken from bevy
+fn sample_shadow_castano_thirteen(
+    shadow_map: texture_depth_2d_array,
+    shadow_sampler: sampler_comparison,
+    uv: vec2<f32>,
+    depth: f32,
+    layer: i32,
+) -> f32 {
+    let shadow_map_size = vec2<f32>(textureDimensions(shadow_map));
+    let inv_shadow_map_size = 1.0 / shadow_map_size;
+    let texel_uv = uv * shadow_map_size;
+    var base_uv = floor(texel_uv + 0.5);
+    let s = (texel_uv.x + 0.5 - base_uv.x);
+    let t = (texel_uv.y + 0.5 - base_uv.y);
+    base_uv -= 0.5;
+    base_uv *= inv_shadow_map_size;
+
+    let uw0 = (4.0 - 3.0 * s);
+    let uw1 = 7.0;
+    let uw2 = (1.0 + 3.0 * s);
+    let u0 = (3.0 - 2.0 * s) / uw0 - 2.0;
+    let u1 = (3.0 + s) / uw1;
+    let u2 = s / uw2 + 2.0;
+
+    let vw0 = (4.0 - 3.0 * t);
+    let vw1 = 7.0;
+    let vw2 = (1.0 + 3.0 * t);
+    let v0 = (3.0 - 2.0 * t) / vw0 - 2.0;
+    let v1 = (3.0 + t) / vw1;
+    let v2 = t / vw2 + 2.0;
+
+    var sum = 0.0;
+    sum += uw0 * vw0 * textureSampleCompareLevel(shadow_map, shadow_sampler, base_uv + (vec2(u0, v0) * inv_shadow_map_size), layer, depth);
+    sum += uw1 * vw0 * textureSampleCompareLevel(shadow_map, shadow_sampler, base_uv + (vec2(u1, v0) * inv_shadow_map_size), layer, depth);
+    sum += uw2 * vw0 * textureSampleCompareLevel(shadow_map, shadow_sampler, base_uv + (vec2(u2, v0) * inv_shadow_map_size), layer, depth);
+    sum += uw0 * vw1 * textureSampleCompareLevel(shadow_map, shadow_sampler, base_uv + (vec2(u0, v1) * inv_shadow_map_size), layer, depth);
+    sum += uw1 * vw1 * textureSampleCompareLevel(shadow_map, shadow_sampler, base_uv + (vec2(u1, v1) * inv_shadow_map_size), layer, depth);
+    sum += uw2 * vw1 * textureSampleCompareLevel(shadow_map, shadow_sampler, base_uv + (vec2(u2, v1) * inv_shadow_map_size), layer, depth);
+    sum += uw0 * vw2 * textureSampleCompareLevel(shadow_map, shadow_sampler, base_uv + (vec2(u0, v2) * inv_shadow_map_size), layer, depth);
+    sum += uw1 * vw2 * textureSampleCompareLevel(shadow_map, shadow_sampler, base_uv + (vec2(u1, v2) * inv_shadow_map_size), layer, depth);
+    sum += uw2 * vw2 * textureSampleCompareLevel(shadow_map, shadow_sampler, base_uv + (vec2(u2, v2) * inv_shadow_map_size), layer, depth);
+
+    return sum * (1.0 / 144.0);
+}
+
+// a compare in a cube shadow map, `depth` is the distance to the light mapped the way the map
+// was rendered
+fn sample_point_shadow(
+    shadow_map: texture_depth_cube_array,
+    shadow_sampler: sampler_comparison,
+    direction: vec3<f32>,
+    depth: f32,
+    layer: i32,
+) -> f32 {
+    return textureSampleCompareLevel(shadow_map, shadow_sampler, direction, layer, depth);
+}
+
+// two directions perpendicular to `direction` and each other, as long as it is
+fn point_shadow_tangents(direction: vec3<f32>) -> mat2x3<f32> {
+    let up = select(vec3<f32>(0.0, 1.0, 0.0), vec3<f32>(1.0, 0.0, 0.0), abs(direction.y) > 0.99 * length(direction));
+    let tangent = normalize(cross(up, direction)) * length(direction);
+    let bitangent = normalize(cross(direction, tangent)) * length(direction);
+    return mat2x3<f32>(tangent, bitangent);
+}
+
+// the average of compares in a cube shadow map over the rotated poisson disk scaled to `radius`
+// times the length of `direction`. `scale` and `face_size` are passed to
+// scale_point_shadow_direction for every tap
+fn sample_point_shadow_poisson(
+    shadow_map: texture_depth_cube_array,
+    shadow_sampler: sampler_comparison,
+    direction: vec3<f32>,
+    depth: f32,
+    layer: i32,
+    radius: f32,
+    rotation: mat2x2<f32>,
+    scale: f32,
+    face_size: f32,
+) -> f32 {
+    let tangents = point_shadow_tangents(direction);
+    var sum = 0.0;
+    for (var i = 0; i < 16; i++) {
+        let offset = tangents * (rotation * POISSON_DISK[i] * radius);
+        let tap = scale_point_shadow_direction(direction + offset, scale, face_size);
+        sum += textureSampleCompareLevel(shadow_map, shadow_sampler, tap, layer, depth);
+    }
+    return sum / 16.0;
+}
+
+// the average depth of what is closer to the light than `depth` in a cube shadow map, see
+// sample_point_shadow_poisson for the arguments. -1 if nothing is
+fn find_point_shadow_blockers(
+    shadow_map: texture_depth_cube_array,
+    depth_sampler: sampler,
+    direction: vec3<f32>,
+    depth: f32,
+    layer: i32,
+    radius: f32,
+    rotation: mat2x2<f32>,
+    scale: f32,
+    face_size: f32,
+) -> f32 {
+    let tangents = point_shadow_tangents(direction);
+    var sum = 0.0;
+    var count = 0.0;
+    for (var i = 0; i < 16; i++) {
+        let offset = tangents * (rotation * POISSON_DISK[i] * radius);
+        let tap = scale_point_shadow_direction(direction + offset, scale, face_size);
+        let blocker = textureSampleLevel(shadow_map, depth_sampler, tap, layer, 0);
+        if blocker < depth {
+            sum += blocker;
+            count += 1.0;
+        }
+    }
+    return select(-1.0, sum / max(count, 1.0), count > 0.0);
+}
+
+// point a cube map direction at the same spot of a face whose shadow was only rendered into the
+// top left `scale` of it. `face_size` is the width of a face in texels
+fn scale_point_shadow_direction(direction: vec3<f32>, scale: f32, face_size: f32) -> vec3<f32> {
+    if scale >= 1.0 {
+        return direction;
+    }
+
+    // the face and its coordinates the way the gpu picks them
+    let a = abs(direction);
+    var major: f32;
+    var st: vec2<f32>;
+    if a.x >= a.y && a.x >= a.z {
+        major = a.x;
+        st = vec2<f32>(select(direction.z, -direction.z, direction.x > 0.0), -direction.y);
+    } else if a.y >= a.z {
+        major = a.y;
+        st = vec2<f32>(direction.x, select(-direction.z, direction.z, direction.y > 0.0));
+    } else {
+        major = a.z;
+        st = vec2<f32>(select(-direction.x, direction.x, direction.z > 0.0), -direction.y);
+    }
+
+    // half a texel in from the edge so filtering doesn't read what wasn't rendered
+    let half_texel = 0.5 / face_size;
+    let uv = clamp((st / major + 1.0) * 0.5 * scale, vec2<f32>(half_texel), vec2<f32>(scale - half_texel));
+    let scaled = (uv * 2.0 - 1.0) * major;
+
+    if a.x >= a.y && a.x >= a.z {
+        return vec3<f32>(direction.x, -scaled.y, select(scaled.x, -scaled.x, direction.x > 0.0));
+    } else if a.y >= a.z {
+        return vec3<f32>(scaled.x, direction.y, select(-scaled.y, scaled.y, direction.y > 0.0));
+    }
+    return vec3<f32>(select(-scaled.x, scaled.x, direction.z > 0.0), -scaled.y, direction.z);
+}
@@ -0,0 +1,38 @@
+// mapping hdr color down to the 0..1 range of the screen
+
+// the perceived brightness of a linear color
+float luminance(vec3 color) {
+    return dot(color, vec3(0.2126, 0.7152, 0.0722));
+}
+
+// ACES fitted curve (Krzysztof Narkowicz approximation)
+vec3 aces_tonemap(vec3 x) {
+    const float a = 2.51;
+    const float b = 0.03;
+    const float c = 2.43;
+    const float d = 0.59;
+    const float e = 0.14;
+    return clamp((x * (a * x + b)) / (x * (c * x + d) + e), 0.0, 1.0);
+}
+
+vec3 reinhard_tonemap(vec3 x) {
+    return x / (1.0 + x);
+}
+
+// reinhard on the luminance so bright colors keep their hue
+vec3 reinhard_luminance_tonemap(vec3 x) {
+    float l = luminance(x);
+    return x * (1.0 / (1.0 + l));
+}
+
+vec3 linear_to_srgb(vec3 color) {
+    vec3 low = color * 12.92;
+    vec3 high = 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055;
+    return mix(high, low, lessThanEqual(color, vec3(0.0031308)));
+}
+
+vec3 srgb_to_linear(vec3 color) {
+    vec3 low = color / 12.92;
+    vec3 high = pow((color + 0.055) / 1.055, vec3(2.4));
+    return mix(high, low, lessThanEqual(color, vec3(0.04045)));
+}
@@ -0,0 +1,38 @@
+// mapping hdr color down to the 0..1 range of the screen
+
+// the perceived brightness of a linear color
+fn luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+// ACES fitted curve (Krzysztof Narkowicz approximation)
+fn aces_tonemap(x: vec3<f32>) -> vec3<f32> {
+    let a = 2.51;
+    let b = 0.03;
+    let c = 2.43;
+    let d = 0.59;
+    let e = 0.14;
+    return saturate((x * (a * x + b)) / (x * (c * x + d) + e));
+}
+
+fn reinhard_tonemap(x: vec3<f32>) -> vec3<f32> {
+    return x / (1.0 + x);
+}
+
+// reinhard on the luminance so bright colors keep their hue
+fn reinhard_luminance_tonemap(x: vec3<f32>) -> vec3<f32> {
+    let l = luminance(x);
+    return x * (1.0 / (1.0 + l));
+}
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, color <= vec3<f32>(0.04045));
+}
@@ -3,7 +3,10 @@ use std::path::Path;
 use image::ImageError;
 use maple_engine::asset::{Asset, AssetLibrary, AssetLoader, FileLoader, LoadErr};
 
-use crate::core::{RenderDevice, RenderQueue, texture::Texture};
+use crate::{
+    core::{RenderDevice, RenderQueue, mipmap_generator::MipmapGenerator, texture::Texture},
+    ktx2,
+};
 
 /// Texture asset that can be loaded through the asset system
 /// Supports HDR, EXR, PNG, JPG, and other image formats
@@ -13,14 +16,26 @@ impl Asset for Texture {
 
 /// Loader for texture assets
 /// Automatically detects HDR formats (.hdr, .exr) and loads them appropriately
+///
+/// images get a full mip chain when they are loaded. `.ktx2` files are uploaded as they are with
+/// their own mips, see [`crate::ktx2`]
 pub struct TextureAssetLoader {
     device: RenderDevice,
     queue: RenderQueue,
+    mipmap_generator: MipmapGenerator,
 }
 
 impl TextureAssetLoader {
-    pub fn new(device: RenderDevice, queue: RenderQueue) -> Self {
-        Self { device, queue }
+    pub fn new(
+        device: RenderDevice,
+        queue: RenderQueue,
+        mipmap_generator: MipmapGenerator,
+    ) -> Self {
+        Self {
+            device,
+            queue,
+            mipmap_generator,
+        }
     }
 }
 
@@ -28,37 +43,73 @@ impl AssetLoader for TextureAssetLoader {
     type Asset = Texture;
 }
 
-impl FileLoader for TextureAssetLoader {
-    fn load_path(&self, path: &Path, _library: &AssetLibrary) -> Result<Self::Asset, LoadErr> {
+/// where the image of a texture comes from
+enum Source<'a> {
+    File,
+    Bytes(&'a [u8]),
+}
+
+impl TextureAssetLoader {
+    fn load(&self, path: &Path, source: Source) -> Result<Texture, LoadErr> {
         // Check file extension to determine if it's HDR
         let extension = path
             .extension()
             .and_then(|s| s.to_str())
             .map(|s| s.to_lowercase());
 
+        let (device, queue) = (&self.device.device, &self.queue.queue);
         let texture = match extension.as_deref() {
             Some("hdr") | Some("exr") => {
                 log::info!("loading hdri texture from: {:?}", path);
                 // Load as HDR texture with RGBA32Float format
-                let texture =
-                    Texture::new_hdri_from_file(&self.device.device, &self.queue.queue, path, None)
-                        .map_err(|e: ImageError| {
-                            LoadErr::Import(format!("Failed to load HDR texture: {}", e))
-                        })?;
+                let texture = match source {
+                    Source::File => Texture::new_hdri_from_file(device, queue, path, None),
+                    Source::Bytes(bytes) => {
+                        Texture::new_hdri_from_bytes(device, queue, bytes, None)
+                    }
+                }
+                .map_err(|e: ImageError| {
+                    LoadErr::Import(format!("Failed to load HDR texture: {}", e))
+                })?;
+                self.mipmap_generator.generate_mipmaps(&texture);
                 log::info!("Finished loading hdri Texture: {:?}", path);
                 texture
             }
             Some("png") | Some("jpg") | Some("jpeg") | Some("bmp") | Some("tga") | Some("webp") => {
                 log::info!("loading texture from: {:?}", path);
                 // Load as standard texture
-                let texture =
-                    Texture::from_file(&self.device.device, &self.queue.queue, path, None)
-                        .map_err(|e: ImageError| {
-                            LoadErr::Import(format!("Failed to load texture: {}", e))
-                        })?;
+                let texture = match source {
+                    Source::File => Texture::from_file(device, queue, path, None, true),
+                    Source::Bytes(bytes) => Texture::from_bytes(device, queue, bytes, None, true),
+                }
+                .map_err(|e: ImageError| {
+                    LoadErr::Import(format!("Failed to load texture: {}", e))
+                })?;
+                self.mipmap_generator.generate_mipmaps(&texture);
                 log::info!("Finished loading Texture: {:?}", path);
                 texture
             }
+            Some("ktx2") => {
+                log::info!("loading ktx2 texture from: {:?}", path);
+                let bytes = match source {
+                    Source::File => std::fs::read(path)
+                        .map_err(|e| LoadErr::Import(format!("Failed to read texture: {e}")))?,
+                    Source::Bytes(bytes) => bytes.to_vec(),
+                };
+                let ktx2 = ktx2::parse(&bytes)?;
+
+                let bc = wgpu::Features::TEXTURE_COMPRESSION_BC;
+                if ktx2.format.is_compressed() && !device.features().contains(bc) {
+                    return Err(LoadErr::Import(format!(
+                        "this gpu can't sample {:?} compressed textures",
+                        ktx2.format
+                    )));
+                }
+
+                let texture = Texture::from_ktx2(device, queue, &ktx2, None);
+                log::info!("Finished loading ktx2 Texture: {:?}", path);
+                texture
+            }
             _ => {
                 return Err(LoadErr::Import(format!(
                     "Unsupported texture format: {:?}",
@@ -70,3 +121,18 @@ impl FileLoader for TextureAssetLoader {
         Ok(texture)
     }
 }
+
+impl FileLoader for TextureAssetLoader {
+    fn load_path(&self, path: &Path, _library: &AssetLibrary) -> Result<Self::Asset, LoadErr> {
+        self.load(path, Source::File)
+    }
+
+    fn load_bytes(
+        &self,
+        path: &Path,
+        bytes: Vec<u8>,
+        _library: &AssetLibrary,
+    ) -> Result<Self::Asset, LoadErr> {
+        self.load(path, Source::Bytes(&bytes))
+    }
+}
@@ -0,0 +1,253 @@
+//! utility textures shared by every pass instead of each pass making its own copy
+//!
+//! the generated textures are only made the first time they are asked for with
+//! [`crate::core::RenderContext::builtin_texture`]
+
+use std::sync::OnceLock;
+
+use maple_engine::utils::noise::hash;
+use wgpu::{Device, Queue};
+
+use crate::{
+    core::texture::{Texture, TextureCreateInfo, TextureFormat, TextureUsage},
+    types::default_texture::DefaultTexture,
+};
+
+/// the width and height of [`Builtin::BlueNoise`]
+pub const BLUE_NOISE_SIZE: u32 = 64;
+/// the width and height of [`Builtin::BrdfLut`]
+pub const BRDF_LUT_SIZE: u32 = 64;
+
+/// a texture the renderer provides out of the box
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Builtin {
+    /// 1x1 opaque white
+    White,
+    /// 1x1 opaque black
+    Black,
+    /// 1x1 flat tangent space normal
+    Normal,
+    /// 2x2 magenta and black checkerboard for missing textures
+    Error,
+    /// tileable `R8` blue noise, every value from 0 to 1 appears equally often and neighbouring
+    /// pixels are far apart in value. use it with a repeating sampler for dithering and to rotate
+    /// sample kernels in SSAO or TAA
+    BlueNoise,
+    /// `RG32Float` split sum BRDF for image based lighting. X is `NdotV`, Y is roughness, red is
+    /// the scale and green the bias applied to F0
+    BrdfLut,
+}
+
+/// the generated builtin textures, each made on first use
+#[derive(Default)]
+pub(crate) struct BuiltinTextures {
+    black: OnceLock<Texture>,
+    blue_noise: OnceLock<Texture>,
+    brdf_lut: OnceLock<Texture>,
+}
+
+impl BuiltinTextures {
+    pub(crate) fn get<'a>(
+        &'a self,
+        builtin: Builtin,
+        defaults: &'a DefaultTexture,
+        device: &Device,
+        queue: &Queue,
+    ) -> &'a Texture {
+        match builtin {
+            Builtin::White => &defaults.white,
+            Builtin::Normal => &defaults.normal,
+            Builtin::Error => &defaults.error,
+            Builtin::Black => self.black.get_or_init(|| {
+                create(
+                    device,
+                    queue,
+                    "Builtin Black",
+                    1,
+                    TextureFormat::RGBA8,
+                    &[0u8, 0, 0, 255],
+                )
+            }),
+            Builtin::BlueNoise => self.blue_noise.get_or_init(|| {
+                create(
+                    device,
+                    queue,
+                    "Builtin Blue Noise",
+                    BLUE_NOISE_SIZE,
+                    TextureFormat::R8,
+                    &blue_noise(BLUE_NOISE_SIZE as usize, 0),
+                )
+            }),
+            Builtin::BrdfLut => self.brdf_lut.get_or_init(|| {
+                create(
+                    device,
+                    queue,
+                    "Builtin BRDF LUT",
+                    BRDF_LUT_SIZE,
+                    TextureFormat::RG32Float,
+                    &brdf_lut(BRDF_LUT_SIZE as usize),
+                )
+            }),
+        }
+    }
+}
+
+fn create<T: bytemuck::Pod>(
+    device: &Device,
+    queue: &Queue,
+    label: &'static str,
+    size: u32,
+    format: TextureFormat,
+    data: &[T],
+) -> Texture {
+    let texture = Texture::create(
+        device,
+        &TextureCreateInfo {
+            label: Some(label),
+            width: size,
+            height: size,
+            format,
+            usage: TextureUsage::TEXTURE_BINDING | TextureUsage::COPY_DST,
+            sample_count: 1,
+            mip_level: 1,
+        },
+    );
+    texture.write(queue, data);
+    texture
+}
+
+/// a tileable blue noise texture made with the void and cluster method
+///
+/// pixels are added one at a time wherever the pattern so far has the biggest gap, so the order
+/// they were added in is the noise value
+fn blue_noise(size: usize, seed: u32) -> Vec<u8> {
+    let count = size * size;
+
+    // how much a pixel at each wrapped offset crowds another pixel
+    let sigma = 1.5f32;
+    let kernel: Vec<f32> = (0..count)
+        .map(|i| {
+            let wrap = |d: usize| d.min(size - d) as f32;
+            let (dx, dy) = (wrap(i % size), wrap(i / size));
+            (-(dx * dx + dy * dy) / (2.0 * sigma * sigma)).exp()
+        })
+        .collect();
+
+    let mut energy = vec![0.0f32; count];
+    let mut filled = vec![false; count];
+    let toggle = |energy: &mut [f32], filled: &mut [bool], pixel: usize, on: bool| {
+        filled[pixel] = on;
+        let sign = if on { 1.0 } else { -1.0 };
+        let (px, py) = (pixel % size, pixel / size);
+        for (i, energy) in energy.iter_mut().enumerate() {
+            let dx = (i % size + size - px) % size;
+            let dy = (i / size + size - py) % size;
+            *energy += sign * kernel[dy * size + dx];
+        }
+    };
+
+    // the tightest cluster is the filled pixel with the most energy, the largest void the empty
+    // pixel with the least
+    let extreme = |energy: &[f32], filled: &[bool], want: bool, most: bool| {
+        (0..count)
+            .filter(|i| filled[*i] == want)
+            .max_by(|a, b| match most {
+                true => energy[*a].total_cmp(&energy[*b]),
+                false => energy[*b].total_cmp(&energy[*a]),
+            })
+    };
+
+    // start from random pixels and move them out of clusters into voids until they're spread out
+    let initial = count / 10;
+    let mut placed = 0;
+    let mut i = 0;
+    while placed < initial {
+        let pixel = hash(i ^ hash(seed)) as usize % count;
+        i += 1;
+        if !filled[pixel] {
+            toggle(&mut energy, &mut filled, pixel, true);
+            placed += 1;
+        }
+    }
+    for _ in 0..count {
+        let cluster = extreme(&energy, &filled, true, true).unwrap();
+        toggle(&mut energy, &mut filled, cluster, false);
+        let void = extreme(&energy, &filled, false, false).unwrap();
+        toggle(&mut energy, &mut filled, void, true);
+        if cluster == void {
+            break;
+        }
+    }
+    let (prototype_energy, prototype_filled) = (energy.clone(), filled.clone());
+
+    let mut rank = vec![0usize; count];
+
+    // the starting pixels are ranked by removing the tightest cluster one at a time
+    for order in (0..initial).rev() {
+        let cluster = extreme(&energy, &filled, true, true).unwrap();
+        toggle(&mut energy, &mut filled, cluster, false);
+        rank[cluster] = order;
+    }
+
+    // and the rest by filling the largest void one at a time
+    let (mut energy, mut filled) = (prototype_energy, prototype_filled);
+    for order in initial..count {
+        let void = extreme(&energy, &filled, false, false).unwrap();
+        toggle(&mut energy, &mut filled, void, true);
+        rank[void] = order;
+    }
+
+    rank.into_iter()
+        .map(|order| ((order * 256) / count) as u8)
+        .collect()
+}
+
+/// the split sum BRDF integrated on the CPU, the same as the GPU version the environment uses
+fn brdf_lut(size: usize) -> Vec<[f32; 2]> {
+    const SAMPLES: u32 = 256;
+
+    let mut lut = Vec::with_capacity(size * size);
+    for y in 0..size {
+        for x in 0..size {
+            let n_dot_v = ((x as f32 + 0.5) / size as f32).max(1e-4);
+            let roughness = (y as f32 + 0.5) / size as f32;
+            lut.push(integrate_brdf(n_dot_v, roughness, SAMPLES));
+        }
+    }
+    lut
+}
+
+fn integrate_brdf(n_dot_v: f32, roughness: f32, samples: u32) -> [f32; 2] {
+    // the normal is +Z and the view is in the XZ plane so only a few components are needed
+    let view = ((1.0 - n_dot_v * n_dot_v).sqrt(), n_dot_v);
+    let alpha = roughness * roughness;
+    let k = alpha / 2.0;
+    let geometry = |n_dot: f32| n_dot / (n_dot * (1.0 - k) + k);
+
+    let (mut scale, mut bias) = (0.0, 0.0);
+    for i in 0..samples {
+        // hammersley point importance sampled around the GGX lobe
+        let xi = (
+            i as f32 / samples as f32,
+            i.reverse_bits() as f32 * 2.328_306_4e-10,
+        );
+        let phi = std::f32::consts::TAU * xi.0;
+        let cos_theta = ((1.0 - xi.1) / (1.0 + (alpha * alpha - 1.0) * xi.1)).sqrt();
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let half_x = phi.cos() * sin_theta;
+        let v_dot_half = view.0 * half_x + view.1 * cos_theta;
+        // the reflected light direction is unit length so its z is `NdotL`
+        let n_dot_l = (2.0 * v_dot_half * cos_theta - view.1).max(0.0);
+        let n_dot_h = cos_theta.max(0.0);
+        let v_dot_h = v_dot_half.max(0.0);
+
+        if n_dot_l > 0.0 {
+            let visibility = geometry(n_dot_v) * geometry(n_dot_l) * v_dot_h / (n_dot_h * n_dot_v);
+            let fresnel = (1.0 - v_dot_h).powi(5);
+            scale += (1.0 - fresnel) * visibility;
+            bias += fresnel * visibility;
+        }
+    }
+
+    [scale / samples as f32, bias / samples as f32]
+}
@@ -1,8 +1,12 @@
+pub mod builtin_texture;
 pub mod default_texture;
 pub mod dimensions;
 pub mod error;
+pub mod post_effects;
+pub mod quality;
 pub mod render_config;
 pub mod setup_render;
 pub mod vertex;
 
+pub use builtin_texture::Builtin;
 pub use dimensions::Dimensions;
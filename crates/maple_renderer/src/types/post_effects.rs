@@ -0,0 +1,48 @@
+//! effects applied to the rendered image on its way to the screen
+//!
+//! scenes are drawn into an hdr texture first. the composite pass then adds bloom, tonemaps the
+//! image with the active camera's exposure and applies the other effects while writing it to the
+//! surface. ui is drawn after the composite pass so it isn't affected.
+//!
+//! ```rust,ignore
+//! let effects = app.renderer_mut().post_effects();
+//! effects.fxaa = true;
+//! effects.vignette = 0.3;
+//! ```
+
+/// the post processing applied after the main pass, see the [module docs](self)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PostEffects {
+    /// add a glow around the brightest parts of the image
+    ///
+    /// Default: `true`
+    pub bloom: bool,
+    /// how much of the bloom is added to the image
+    ///
+    /// Default: `0.04`
+    pub bloom_intensity: f32,
+    /// map the hdr image to the screen with aces, colors brighter than white are clipped when off
+    ///
+    /// Default: `true`
+    pub tonemapping: bool,
+    /// smooth jagged edges left by the main pass, softens the image a bit
+    ///
+    /// Default: `false`
+    pub fxaa: bool,
+    /// how much the corners of the image are darkened from `0.0` to `1.0`
+    ///
+    /// Default: `0.0`
+    pub vignette: f32,
+}
+
+impl Default for PostEffects {
+    fn default() -> Self {
+        Self {
+            bloom: true,
+            bloom_intensity: 0.04,
+            tonemapping: true,
+            fxaa: false,
+            vignette: 0.0,
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! graphics quality presets that trade how the scene looks for how fast it draws
+//!
+//! a [`QualityPreset`] maps to the [`QualitySettings`] the renderer and the 3d passes read: the
+//! largest shadow map a light gets, msaa, the [`PostEffects`], how much texture memory the scene
+//! should stay under and the resolution the scene is drawn at relative to the window.
+//!
+//! the app picks a preset from the gpu the first time the game runs with
+//! [`QualityPreset::detect`] and keeps the user's choice in the
+//! [`Settings`](maple_engine::resources::Settings) store after that. games change it through the
+//! [`Quality`] resource, usually from a settings menu:
+//!
+//! ```rust,ignore
+//! ctx.game
+//!     .get_resource_mut::<Quality>()
+//!     .set_preset(QualityPreset::Low);
+//! ```
+//!
+//! msaa is part of every pipeline the graph builds so a new sample count is used the next time
+//! the game starts, everything else changes the next frame.
+
+use maple_engine::prelude::Resource;
+use serde::{Deserialize, Serialize};
+
+use crate::types::{
+    Dimensions,
+    post_effects::PostEffects,
+    render_config::{AdapterInfo, DeviceType, GraphicsBackend},
+};
+
+const MIB: u64 = 1024 * 1024;
+
+/// a named set of [`QualitySettings`], see the [module docs](self)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum QualityPreset {
+    /// for integrated and mobile gpus, webgl and software renderers
+    Low,
+    Medium,
+    /// what the renderer draws with when nothing else is picked
+    #[default]
+    High,
+    /// bigger shadows and texture budget than high, never picked by [`QualityPreset::detect`]
+    Ultra,
+}
+
+impl QualityPreset {
+    pub const ALL: [QualityPreset; 4] = [Self::Low, Self::Medium, Self::High, Self::Ultra];
+
+    /// the key the chosen preset is kept under in the settings store
+    pub const SETTINGS_KEY: &'static str = "graphics_quality";
+
+    /// a default for the gpu the game runs on. software renderers and gl get
+    /// [`QualityPreset::Low`], integrated and mobile gpus [`QualityPreset::Medium`] and discrete
+    /// gpus [`QualityPreset::High`]
+    pub fn detect(adapter: &AdapterInfo) -> Self {
+        if adapter.device_type == DeviceType::Cpu || adapter.backend == GraphicsBackend::Gl {
+            return Self::Low;
+        }
+
+        let mobile = cfg!(any(target_os = "android", target_os = "ios"));
+        match adapter.device_type {
+            DeviceType::DiscreteGpu if !mobile => Self::High,
+            _ => Self::Medium,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Low => "Low",
+            Self::Medium => "Medium",
+            Self::High => "High",
+            Self::Ultra => "Ultra",
+        }
+    }
+
+    /// what this preset renders with
+    pub fn settings(self) -> QualitySettings {
+        match self {
+            Self::Low => QualitySettings {
+                max_shadow_resolution: 512,
+                msaa_samples: 1,
+                post_effects: PostEffects {
+                    bloom: false,
+                    ..Default::default()
+                },
+                texture_budget: 256 * MIB,
+                render_scale: 0.75,
+            },
+            Self::Medium => QualitySettings {
+                max_shadow_resolution: 1024,
+                msaa_samples: 1,
+                post_effects: PostEffects {
+                    fxaa: true,
+                    ..Default::default()
+                },
+                texture_budget: 512 * MIB,
+                render_scale: 1.0,
+            },
+            Self::High => QualitySettings::default(),
+            Self::Ultra => QualitySettings {
+                max_shadow_resolution: 4096,
+                texture_budget: 2048 * MIB,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// what the renderer trades for speed, see the [module docs](self)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QualitySettings {
+    /// the largest shadow map a light is rendered into, lights asking for more are drawn at this
+    /// size
+    ///
+    /// Default: `2048`
+    pub max_shadow_resolution: u32,
+    /// samples per pixel of the main scene targets, `1` turns msaa off. `1` and `4` work on every
+    /// gpu. read when the render graph is set up
+    ///
+    /// Default: `4`
+    pub msaa_samples: u32,
+    /// the effects the composite pass starts with
+    ///
+    /// Default: [`PostEffects::default`]
+    pub post_effects: PostEffects,
+    /// how many bytes of textures the loaded scene should stay under, the
+    /// [memory report](crate::render_graph::memory) warns when it doesn't
+    ///
+    /// Default: 1 GiB
+    pub texture_budget: u64,
+    /// the size the scene is drawn at relative to the window, the composite pass scales it to fit.
+    /// ui is always drawn at the window's size
+    ///
+    /// Default: `1.0`
+    pub render_scale: f32,
+}
+
+impl Default for QualitySettings {
+    fn default() -> Self {
+        Self {
+            max_shadow_resolution: 2048,
+            msaa_samples: 4,
+            post_effects: PostEffects::default(),
+            texture_budget: 1024 * MIB,
+            render_scale: 1.0,
+        }
+    }
+}
+
+impl QualitySettings {
+    /// the size of the scene targets for a window of `surface`, never smaller than a pixel
+    pub fn render_size(&self, surface: Dimensions) -> Dimensions {
+        let scale = |size: u32| ((size as f32 * self.render_scale).round() as u32).max(1);
+        Dimensions {
+            width: scale(surface.width),
+            height: scale(surface.height),
+        }
+    }
+}
+
+/// the quality the game renders with, see the [module docs](self)
+///
+/// the app inserts it as a resource, applies changes to the renderer at the start of the next
+/// frame and saves the preset in the settings store
+#[derive(Debug, Clone)]
+pub struct Quality {
+    preset: QualityPreset,
+    settings: QualitySettings,
+    changed: bool,
+}
+
+impl Resource for Quality {}
+
+impl Quality {
+    pub fn new(preset: QualityPreset) -> Self {
+        Self {
+            preset,
+            settings: preset.settings(),
+            changed: false,
+        }
+    }
+
+    pub fn preset(&self) -> QualityPreset {
+        self.preset
+    }
+
+    pub fn settings(&self) -> &QualitySettings {
+        &self.settings
+    }
+
+    /// switch to `preset`, replacing any settings changed through [`Quality::settings_mut`]
+    pub fn set_preset(&mut self, preset: QualityPreset) {
+        self.preset = preset;
+        self.settings = preset.settings();
+        self.changed = true;
+    }
+
+    /// change single settings on top of the preset. only the preset is saved so these are back
+    /// to the preset's the next time the game starts
+    pub fn settings_mut(&mut self) -> &mut QualitySettings {
+        self.changed = true;
+        &mut self.settings
+    }
+
+    /// the preset and settings if they changed since the last call
+    pub fn take_changed(&mut self) -> Option<(QualityPreset, QualitySettings)> {
+        std::mem::take(&mut self.changed).then_some((self.preset, self.settings))
+    }
+}
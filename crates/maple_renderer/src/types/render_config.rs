@@ -1,6 +1,22 @@
-#[derive(Default, Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
 pub struct RenderConfig {
     pub vsync: VsyncMode,
+    /// how many frames can be queued for presentation before the cpu waits on the gpu
+    ///
+    /// lower values reduce input latency at the cost of throughput. Default: 2
+    pub frames_in_flight: u32,
+    /// the graphics api to render with, `None` picks the best one the platform has. Default: None
+    pub backend: Option<GraphicsBackend>,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self {
+            vsync: VsyncMode::default(),
+            frames_in_flight: 2,
+            backend: None,
+        }
+    }
 }
 
 #[derive(Default, Debug, Clone, Copy)]
@@ -9,3 +25,102 @@ pub enum VsyncMode {
     Off,
     On,
 }
+
+/// how the 3d scene is lit, see [`RenderPath::Deferred`]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderPath {
+    /// every mesh is lit by every light while it is drawn
+    #[default]
+    Forward,
+    /// opaque meshes are drawn into a g-buffer first and a fullscreen pass lights every pixel
+    /// once, so the cost of a light no longer grows with the meshes it touches. transparent
+    /// meshes and materials without a g-buffer shader are still drawn forward afterwards
+    Deferred,
+}
+
+/// a graphics api the renderer can draw with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GraphicsBackend {
+    Vulkan,
+    Metal,
+    Dx12,
+    /// opengl es and webgl
+    Gl,
+    /// webgpu in the browser
+    BrowserWebGpu,
+    /// doesn't draw anything and works without a gpu, buffers keep what is written to them and
+    /// every other call succeeds without doing anything. the renderer keeps a
+    /// [log](crate::core::command_log) of what was created and encoded for testing render nodes
+    Null,
+}
+
+impl GraphicsBackend {
+    /// every backend that draws, so not [`GraphicsBackend::Null`]
+    pub const ALL: [GraphicsBackend; 5] = [
+        Self::Vulkan,
+        Self::Metal,
+        Self::Dx12,
+        Self::Gl,
+        Self::BrowserWebGpu,
+    ];
+}
+
+impl From<GraphicsBackend> for wgpu::Backends {
+    fn from(value: GraphicsBackend) -> Self {
+        match value {
+            GraphicsBackend::Vulkan => wgpu::Backends::VULKAN,
+            GraphicsBackend::Metal => wgpu::Backends::METAL,
+            GraphicsBackend::Dx12 => wgpu::Backends::DX12,
+            GraphicsBackend::Gl => wgpu::Backends::GL,
+            GraphicsBackend::BrowserWebGpu => wgpu::Backends::BROWSER_WEBGPU,
+            GraphicsBackend::Null => wgpu::Backends::NOOP,
+        }
+    }
+}
+
+impl GraphicsBackend {
+    pub(crate) fn from_wgpu(backend: wgpu::Backend) -> Self {
+        match backend {
+            wgpu::Backend::Vulkan => Self::Vulkan,
+            wgpu::Backend::Metal => Self::Metal,
+            wgpu::Backend::Dx12 => Self::Dx12,
+            wgpu::Backend::Gl => Self::Gl,
+            wgpu::Backend::BrowserWebGpu => Self::BrowserWebGpu,
+            wgpu::Backend::Noop => Self::Null,
+        }
+    }
+}
+
+/// the kind of gpu an adapter is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeviceType {
+    IntegratedGpu,
+    DiscreteGpu,
+    VirtualGpu,
+    /// a software renderer
+    Cpu,
+    Other,
+}
+
+impl DeviceType {
+    pub(crate) fn from_wgpu(device_type: wgpu::DeviceType) -> Self {
+        match device_type {
+            wgpu::DeviceType::IntegratedGpu => Self::IntegratedGpu,
+            wgpu::DeviceType::DiscreteGpu => Self::DiscreteGpu,
+            wgpu::DeviceType::VirtualGpu => Self::VirtualGpu,
+            wgpu::DeviceType::Cpu => Self::Cpu,
+            wgpu::DeviceType::Other => Self::Other,
+        }
+    }
+}
+
+/// the gpu the renderer draws with, see [`crate::core::RenderContext::adapter_info`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdapterInfo {
+    /// the name the driver gives the gpu
+    pub name: String,
+    pub device_type: DeviceType,
+    /// the driver name and version, empty if the backend doesn't say
+    pub driver: String,
+    pub backend: GraphicsBackend,
+}
@@ -0,0 +1,699 @@
+//! backend conformance suite
+//!
+//! every case is a small render graph that is drawn on each [`GraphicsBackend`] this machine can
+//! create a device for. the images are read back and checked against the colors the case should
+//! draw and against each other, so a backend that lands has to draw the same thing as the ones
+//! before it. backends without an adapter are skipped and a case fails if none could draw it.
+//!
+//! the cases need a gpu so they are ignored by default, run them with
+//! `cargo test -p maple_renderer --test conformance -- --ignored`
+
+use image::{Rgba, RgbaImage};
+use maple_engine::GameContext;
+use maple_renderer::{
+    core::{
+        Buffer, CullMode, DepthCompare, DepthStencilOptions, DescriptorSetLayout, Frame,
+        GraphicsShader, RenderContext, Renderer, StageFlags,
+        context::RenderOptions,
+        descriptor_set::{DescriptorBindingType, DescriptorSet, DescriptorSetLayoutDescriptor},
+        pipeline::{AlphaMode, PipelineCreateInfo, RenderPipeline},
+        texture::{
+            FilterMode, SamplerOptions, Texture, TextureCreateInfo, TextureFormat, TextureMode,
+            TextureUsage, TextureView,
+        },
+    },
+    render_graph::{
+        graph::{RenderGraphContext, Stage},
+        node::{DepthMode, RenderNode, RenderTarget},
+    },
+    types::render_config::{GraphicsBackend, RenderConfig},
+};
+
+const SIZE: u32 = 32;
+
+/// how far a channel can be off between backends from rasterization and filtering differences
+const TOLERANCE: u8 = 2;
+
+const RED: [u8; 4] = [255, 0, 0, 255];
+const GREEN: [u8; 4] = [0, 255, 0, 255];
+const BLUE: [u8; 4] = [0, 0, 255, 255];
+const WHITE: [u8; 4] = [255, 255, 255, 255];
+const BLACK: [u8; 4] = [0, 0, 0, 255];
+
+/// a fullscreen triangle with uvs, the top left of the screen is uv 0, 0
+const FULLSCREEN_VERT: &str = r#"
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+"#;
+
+const TRIANGLE_VERT: &str = r#"
+@vertex
+fn main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    var positions = array<vec2<f32>, 3>(
+        vec2<f32>(-0.5, -0.5),
+        vec2<f32>(0.5, -0.5),
+        vec2<f32>(0.0, 0.5),
+    );
+    return vec4<f32>(positions[index], 0.0, 1.0);
+}
+"#;
+
+const RED_FRAG: &str = r#"
+@fragment
+fn main() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0, 0.0, 0.0, 1.0);
+}
+"#;
+
+const TEXTURED_FRAG: &str = r#"
+@group(0) @binding(0) var image: texture_2d<f32>;
+@group(0) @binding(1) var image_sampler: sampler;
+
+@fragment
+fn main(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
+    return textureSample(image, image_sampler, uv);
+}
+"#;
+
+const UNIFORM_FRAG: &str = r#"
+struct Tint {
+    color: vec4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> tint: Tint;
+
+@fragment
+fn main() -> @location(0) vec4<f32> {
+    return tint.color;
+}
+"#;
+
+const MRT_FRAG: &str = r#"
+struct Targets {
+    @location(0) first: vec4<f32>,
+    @location(1) second: vec4<f32>,
+};
+
+@fragment
+fn main() -> Targets {
+    var out: Targets;
+    out.first = vec4<f32>(1.0, 0.0, 0.0, 1.0);
+    out.second = vec4<f32>(0.0, 0.0, 1.0, 1.0);
+    return out;
+}
+"#;
+
+/// four quads, each instance picks its half of the screen, depth and color. the left half draws
+/// the near quad first and the right half draws it last
+const DEPTH_VERT: &str = r#"
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+@vertex
+fn main(
+    @builtin(vertex_index) index: u32,
+    @builtin(instance_index) instance: u32,
+) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+    );
+    let right = instance >= 2u;
+    let near = instance == 0u || instance == 3u;
+
+    let corner = corners[index];
+    let x = select(-1.0, 0.0, right) + corner.x;
+    let y = corner.y * 2.0 - 1.0;
+
+    var out: VertexOutput;
+    out.position = vec4<f32>(x, y, select(0.75, 0.25, near), 1.0);
+    out.color = select(vec4<f32>(1.0, 0.0, 0.0, 1.0), vec4<f32>(0.0, 1.0, 0.0, 1.0), near);
+    return out;
+}
+"#;
+
+const COLOR_FRAG: &str = r#"
+@fragment
+fn main(@location(0) color: vec4<f32>) -> @location(0) vec4<f32> {
+    return color;
+}
+"#;
+
+fn compile(rcx: &RenderContext, vertex: &'static str, fragment: &'static str) -> GraphicsShader {
+    let compile = |source: &'static str| {
+        rcx.device()
+            .compile_shader(source.into())
+            .expect("conformance shader to compile")
+    };
+    GraphicsShader {
+        vertex: compile(vertex),
+        fragment: compile(fragment),
+    }
+}
+
+fn pipeline(
+    rcx: &RenderContext,
+    shader: GraphicsShader,
+    layouts: &[DescriptorSetLayout],
+    color_formats: &[TextureFormat],
+    depth: DepthMode,
+) -> RenderPipeline {
+    rcx.device().create_pipeline(PipelineCreateInfo {
+        label: Some("Conformance"),
+        layout: rcx.device().create_pipeline_layout(layouts),
+        shader,
+        color_formats,
+        depth,
+        cull_mode: CullMode::None,
+        alpha_mode: AlphaMode::Opaque,
+        sample_count: 1,
+        vertex_buffer_layout: None,
+    })
+}
+
+/// a color target shared with the graph under `name` so the test can read it back
+fn color_target(
+    rcx: &RenderContext,
+    graph_ctx: &mut RenderGraphContext,
+    name: &'static str,
+) -> TextureView {
+    let texture = rcx.device().create_texture(TextureCreateInfo {
+        label: Some(name),
+        width: SIZE,
+        height: SIZE,
+        format: TextureFormat::RGBA8,
+        usage: TextureUsage::RENDER_ATTACHMENT,
+        sample_count: 1,
+        mip_level: 1,
+    });
+    let view = texture.create_view();
+    graph_ctx.add_shared_resource(name, texture);
+    view
+}
+
+fn clear<'a>(label: &'static str, color_targets: &'a [RenderTarget]) -> RenderOptions<'a> {
+    RenderOptions {
+        label: Some(label),
+        color_targets,
+        depth_target: None,
+        clear_color: Some([0.0, 0.0, 0.0, 1.0]),
+        clear_depth: None,
+        discard_depth: false,
+    }
+}
+
+/// a red triangle in the middle of a black target
+struct TriangleCase {
+    target: [RenderTarget; 1],
+    pipeline: RenderPipeline,
+}
+
+impl RenderNode for TriangleCase {
+    fn stage(&self) -> Stage {
+        Stage::Opaque
+    }
+
+    fn setup(rcx: &RenderContext, graph_ctx: &mut RenderGraphContext) -> Self {
+        let shader = compile(rcx, TRIANGLE_VERT, RED_FRAG);
+        Self {
+            target: [RenderTarget::Texture(color_target(
+                rcx, graph_ctx, "triangle",
+            ))],
+            pipeline: pipeline(rcx, shader, &[], &[TextureFormat::RGBA8], DepthMode::None),
+        }
+    }
+
+    fn draw(
+        &mut self,
+        _: &RenderContext,
+        frame: &mut Frame,
+        _: &mut RenderGraphContext,
+        _: &GameContext,
+    ) {
+        frame
+            .render(clear("Triangle", &self.target), |mut fb| {
+                fb.use_pipeline(&self.pipeline).draw(0..3, 0);
+            })
+            .expect("failed to render the triangle");
+    }
+}
+
+/// a 2 by 2 texture sampled with nearest filtering across the whole target
+struct TexturedQuadCase {
+    target: [RenderTarget; 1],
+    pipeline: RenderPipeline,
+    descriptor: DescriptorSet,
+}
+
+impl RenderNode for TexturedQuadCase {
+    fn stage(&self) -> Stage {
+        Stage::Opaque
+    }
+
+    fn setup(rcx: &RenderContext, graph_ctx: &mut RenderGraphContext) -> Self {
+        let layout = rcx
+            .device()
+            .create_descriptor_set_layout(DescriptorSetLayoutDescriptor {
+                label: Some("Conformance_Texture"),
+                visibility: StageFlags::FRAGMENT,
+                layout: &[
+                    DescriptorBindingType::TextureView { filterable: true },
+                    DescriptorBindingType::Sampler { filtering: true },
+                ],
+            });
+
+        let image = rcx.device().create_texture(TextureCreateInfo {
+            label: Some("Conformance_Image"),
+            width: 2,
+            height: 2,
+            format: TextureFormat::RGBA8,
+            usage: TextureUsage::TEXTURE_BINDING | TextureUsage::COPY_DST,
+            sample_count: 1,
+            mip_level: 1,
+        });
+        rcx.queue()
+            .write_texture(&image, &[RED, GREEN, BLUE, WHITE].concat());
+
+        let sampler = rcx.device().create_sampler(SamplerOptions {
+            mode_u: TextureMode::ClampToEdge,
+            mode_v: TextureMode::ClampToEdge,
+            mode_w: TextureMode::ClampToEdge,
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Nearest,
+            compare: None,
+        });
+        let view = image.create_view();
+        let descriptor = rcx.device().build_descriptor_set(
+            DescriptorSet::builder(&layout)
+                .texture_view(0, &view)
+                .sampler(1, &sampler),
+        );
+
+        let shader = compile(rcx, FULLSCREEN_VERT, TEXTURED_FRAG);
+        Self {
+            target: [RenderTarget::Texture(color_target(
+                rcx,
+                graph_ctx,
+                "textured_quad",
+            ))],
+            pipeline: pipeline(
+                rcx,
+                shader,
+                &[layout],
+                &[TextureFormat::RGBA8],
+                DepthMode::None,
+            ),
+            descriptor,
+        }
+    }
+
+    fn draw(
+        &mut self,
+        _: &RenderContext,
+        frame: &mut Frame,
+        _: &mut RenderGraphContext,
+        _: &GameContext,
+    ) {
+        frame
+            .render(clear("Textured Quad", &self.target), |mut fb| {
+                fb.use_pipeline(&self.pipeline)
+                    .bind_descriptor_set(0, &self.descriptor)
+                    .draw(0..3, 0);
+            })
+            .expect("failed to render the textured quad");
+    }
+}
+
+/// fills the target with a uniform color that changes every frame
+struct UniformUpdateCase {
+    target: [RenderTarget; 1],
+    pipeline: RenderPipeline,
+    tint: Buffer<[f32; 4]>,
+    descriptor: DescriptorSet,
+    frame: usize,
+}
+
+impl UniformUpdateCase {
+    const COLORS: [[f32; 4]; 2] = [[0.0, 1.0, 0.0, 1.0], [0.0, 0.0, 1.0, 1.0]];
+}
+
+impl RenderNode for UniformUpdateCase {
+    fn stage(&self) -> Stage {
+        Stage::Opaque
+    }
+
+    fn setup(rcx: &RenderContext, graph_ctx: &mut RenderGraphContext) -> Self {
+        let layout = rcx
+            .device()
+            .create_descriptor_set_layout(DescriptorSetLayoutDescriptor {
+                label: Some("Conformance_Tint"),
+                visibility: StageFlags::FRAGMENT,
+                layout: &[DescriptorBindingType::UniformBuffer],
+            });
+        let tint = rcx.device().create_uniform_buffer(&[1.0f32, 0.0, 0.0, 1.0]);
+        let descriptor = rcx
+            .device()
+            .build_descriptor_set(DescriptorSet::builder(&layout).uniform(0, &tint));
+
+        let shader = compile(rcx, FULLSCREEN_VERT, UNIFORM_FRAG);
+        Self {
+            target: [RenderTarget::Texture(color_target(
+                rcx,
+                graph_ctx,
+                "uniform_update",
+            ))],
+            pipeline: pipeline(
+                rcx,
+                shader,
+                &[layout],
+                &[TextureFormat::RGBA8],
+                DepthMode::None,
+            ),
+            tint,
+            descriptor,
+            frame: 0,
+        }
+    }
+
+    fn draw(
+        &mut self,
+        rcx: &RenderContext,
+        frame: &mut Frame,
+        _: &mut RenderGraphContext,
+        _: &GameContext,
+    ) {
+        let color = Self::COLORS[self.frame % Self::COLORS.len()];
+        self.frame += 1;
+        rcx.queue().write_buffer(&self.tint, &color);
+
+        frame
+            .render(clear("Uniform Update", &self.target), |mut fb| {
+                fb.use_pipeline(&self.pipeline)
+                    .bind_descriptor_set(0, &self.descriptor)
+                    .draw(0..3, 0);
+            })
+            .expect("failed to render the uniform update");
+    }
+}
+
+/// one draw writing red to the first target and blue to the second
+struct MultipleTargetsCase {
+    targets: [RenderTarget; 2],
+    pipeline: RenderPipeline,
+}
+
+impl RenderNode for MultipleTargetsCase {
+    fn stage(&self) -> Stage {
+        Stage::Opaque
+    }
+
+    fn setup(rcx: &RenderContext, graph_ctx: &mut RenderGraphContext) -> Self {
+        let shader = compile(rcx, FULLSCREEN_VERT, MRT_FRAG);
+        Self {
+            targets: [
+                RenderTarget::Texture(color_target(rcx, graph_ctx, "mrt_first")),
+                RenderTarget::Texture(color_target(rcx, graph_ctx, "mrt_second")),
+            ],
+            pipeline: pipeline(
+                rcx,
+                shader,
+                &[],
+                &[TextureFormat::RGBA8, TextureFormat::RGBA8],
+                DepthMode::None,
+            ),
+        }
+    }
+
+    fn draw(
+        &mut self,
+        _: &RenderContext,
+        frame: &mut Frame,
+        _: &mut RenderGraphContext,
+        _: &GameContext,
+    ) {
+        frame
+            .render(clear("Multiple Targets", &self.targets), |mut fb| {
+                fb.use_pipeline(&self.pipeline).draw(0..3, 0);
+            })
+            .expect("failed to render multiple targets");
+    }
+}
+
+/// a green near quad and a red far quad over each half, drawn in opposite orders
+struct DepthTestCase {
+    target: [RenderTarget; 1],
+    depth: TextureView,
+    pipeline: RenderPipeline,
+}
+
+impl RenderNode for DepthTestCase {
+    fn stage(&self) -> Stage {
+        Stage::Opaque
+    }
+
+    fn setup(rcx: &RenderContext, graph_ctx: &mut RenderGraphContext) -> Self {
+        let depth = rcx.device().create_texture(TextureCreateInfo {
+            label: Some("Conformance_Depth"),
+            width: SIZE,
+            height: SIZE,
+            format: TextureFormat::Depth32,
+            usage: TextureUsage::RENDER_ATTACHMENT,
+            sample_count: 1,
+            mip_level: 1,
+        });
+
+        let depth_mode = DepthMode::Texture(DepthStencilOptions {
+            format: TextureFormat::Depth32,
+            compare: DepthCompare::Less,
+            write_enabled: true,
+            depth_bias: None,
+        });
+        let shader = compile(rcx, DEPTH_VERT, COLOR_FRAG);
+        Self {
+            target: [RenderTarget::Texture(color_target(
+                rcx,
+                graph_ctx,
+                "depth_test",
+            ))],
+            depth: depth.create_view(),
+            pipeline: pipeline(rcx, shader, &[], &[TextureFormat::RGBA8], depth_mode),
+        }
+    }
+
+    fn draw(
+        &mut self,
+        _: &RenderContext,
+        frame: &mut Frame,
+        _: &mut RenderGraphContext,
+        _: &GameContext,
+    ) {
+        let options = RenderOptions {
+            depth_target: Some(&self.depth),
+            clear_depth: Some(1.0),
+            ..clear("Depth Test", &self.target)
+        };
+        frame
+            .render(options, |mut fb| {
+                fb.use_pipeline(&self.pipeline);
+                for instance in 0..4 {
+                    fb.draw(0..6, instance);
+                }
+            })
+            .expect("failed to render the depth test");
+    }
+}
+
+/// what one backend drew, the targets in order for every frame
+struct BackendImages {
+    backend: GraphicsBackend,
+    frames: Vec<Vec<RgbaImage>>,
+}
+
+/// draw `T` for `frames` frames on every available backend and read `targets` back after each
+///
+/// the images of every backend are compared with the first one
+fn run_case<T: RenderNode + 'static>(
+    frames: usize,
+    targets: &[&'static str],
+) -> Vec<BackendImages> {
+    let ctx = GameContext::new();
+    let mut results: Vec<BackendImages> = Vec::new();
+    let mut skipped: Vec<String> = Vec::new();
+
+    for backend in GraphicsBackend::ALL {
+        let config = RenderConfig {
+            backend: Some(backend),
+            ..Default::default()
+        };
+        let mut renderer = match Renderer::init_headless(config) {
+            Ok(renderer) => renderer,
+            Err(err) => {
+                skipped.push(format!("{backend:?}: {err}"));
+                continue;
+            }
+        };
+        assert_eq!(renderer.context.graphics_backend(), backend);
+
+        renderer.graph().setup_and_add_node::<T>();
+        let textures: Vec<Texture> = targets
+            .iter()
+            .map(|name| {
+                renderer
+                    .render_graph
+                    .context
+                    .read()
+                    .get_shared_resource::<Texture>(name)
+                    .cloned()
+                    .unwrap_or_else(|| panic!("the case didn't share target {name}"))
+            })
+            .collect();
+
+        let frames = (0..frames)
+            .map(|_| {
+                renderer.draw_offscreen(&ctx).expect("the graph to render");
+                textures
+                    .iter()
+                    .map(|texture| renderer.context.read_texture(texture, 0).unwrap())
+                    .collect()
+            })
+            .collect();
+
+        results.push(BackendImages { backend, frames });
+    }
+
+    let Some((reference, others)) = results.split_first() else {
+        panic!(
+            "no graphics backend could draw the case, nothing was tested: {}",
+            skipped.join(", ")
+        );
+    };
+    for other in others {
+        assert_images_match(reference, other);
+    }
+
+    results
+}
+
+fn assert_images_match(reference: &BackendImages, other: &BackendImages) {
+    let pairs = reference
+        .frames
+        .iter()
+        .flatten()
+        .zip(other.frames.iter().flatten());
+    for (index, (expected, actual)) in pairs.enumerate() {
+        let mismatched = expected
+            .pixels()
+            .zip(actual.pixels())
+            .filter(|(a, b)| a.0.iter().zip(b.0).any(|(a, b)| a.abs_diff(b) > TOLERANCE))
+            .count();
+        assert_eq!(
+            mismatched, 0,
+            "{:?} and {:?} differ in {mismatched} pixels of image {index}",
+            reference.backend, other.backend
+        );
+    }
+}
+
+fn assert_pixel(image: &RgbaImage, x: u32, y: u32, expected: [u8; 4], backend: GraphicsBackend) {
+    let Rgba(actual) = *image.get_pixel(x, y);
+    assert!(
+        actual
+            .iter()
+            .zip(expected)
+            .all(|(a, b)| a.abs_diff(b) <= TOLERANCE),
+        "{backend:?} drew {actual:?} at {x}, {y} instead of {expected:?}"
+    );
+}
+
+#[test]
+#[ignore = "needs a gpu, run with `cargo test --test conformance -- --ignored`"]
+fn triangle() {
+    for result in run_case::<TriangleCase>(1, &["triangle"]) {
+        let image = &result.frames[0][0];
+        assert_pixel(image, SIZE / 2, SIZE / 2, RED, result.backend);
+        assert_pixel(image, 1, 1, BLACK, result.backend);
+        assert_pixel(image, SIZE - 2, 1, BLACK, result.backend);
+    }
+}
+
+#[test]
+#[ignore = "needs a gpu, run with `cargo test --test conformance -- --ignored`"]
+fn textured_quad() {
+    let quarter = SIZE / 4;
+    for result in run_case::<TexturedQuadCase>(1, &["textured_quad"]) {
+        let image = &result.frames[0][0];
+        assert_pixel(image, quarter, quarter, RED, result.backend);
+        assert_pixel(image, SIZE - quarter, quarter, GREEN, result.backend);
+        assert_pixel(image, quarter, SIZE - quarter, BLUE, result.backend);
+        assert_pixel(image, SIZE - quarter, SIZE - quarter, WHITE, result.backend);
+    }
+}
+
+#[test]
+#[ignore = "needs a gpu, run with `cargo test --test conformance -- --ignored`"]
+fn uniform_update() {
+    for result in run_case::<UniformUpdateCase>(2, &["uniform_update"]) {
+        assert_pixel(
+            &result.frames[0][0],
+            SIZE / 2,
+            SIZE / 2,
+            GREEN,
+            result.backend,
+        );
+        assert_pixel(
+            &result.frames[1][0],
+            SIZE / 2,
+            SIZE / 2,
+            BLUE,
+            result.backend,
+        );
+    }
+}
+
+#[test]
+#[ignore = "needs a gpu, run with `cargo test --test conformance -- --ignored`"]
+fn multiple_render_targets() {
+    for result in run_case::<MultipleTargetsCase>(1, &["mrt_first", "mrt_second"]) {
+        assert_pixel(
+            &result.frames[0][0],
+            SIZE / 2,
+            SIZE / 2,
+            RED,
+            result.backend,
+        );
+        assert_pixel(
+            &result.frames[0][1],
+            SIZE / 2,
+            SIZE / 2,
+            BLUE,
+            result.backend,
+        );
+    }
+}
+
+#[test]
+#[ignore = "needs a gpu, run with `cargo test --test conformance -- --ignored`"]
+fn depth_test() {
+    for result in run_case::<DepthTestCase>(1, &["depth_test"]) {
+        let image = &result.frames[0][0];
+        assert_pixel(image, SIZE / 4, SIZE / 2, GREEN, result.backend);
+        assert_pixel(image, SIZE - SIZE / 4, SIZE / 2, GREEN, result.backend);
+    }
+}
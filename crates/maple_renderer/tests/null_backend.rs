@@ -0,0 +1,479 @@
+//! render nodes tested on the null backend through the command log, these run without a gpu
+
+use maple_engine::GameContext;
+use maple_renderer::{
+    core::{
+        CullMode, Frame, GraphicsShader, RenderContext, Renderer, StageFlags,
+        command_log::RecordedCommand,
+        context::RenderOptions,
+        descriptor_set::{DescriptorBindingType, DescriptorSet, DescriptorSetLayoutDescriptor},
+        pipeline::{AlphaMode, PipelineCreateInfo, PushConstantRange, RenderPipeline},
+        texture::{
+            Texture, TextureArrayCreateInfo, TextureCreateInfo, TextureFormat, TextureRegion,
+            TextureUsage, TextureViewDimension, TextureViewOptions,
+        },
+    },
+    render_graph::{
+        description::GraphDescription,
+        graph::{RenderGraphContext, Stage},
+        node::{DepthMode, RenderNode, RenderTarget, SurfaceDepth},
+    },
+    types::{
+        Dimensions,
+        quality::QualityPreset,
+        render_config::{GraphicsBackend, RenderConfig},
+    },
+};
+
+const TINT_VERT: &str = r#"
+@vertex
+fn main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+"#;
+
+const TINT_FRAG: &str = r#"
+@group(0) @binding(0) var<uniform> tint: vec4<f32>;
+
+@fragment
+fn main() -> @location(0) vec4<f32> {
+    return tint;
+}
+"#;
+
+/// fills a target with a uniform color
+struct TintPass {
+    target: [RenderTarget; 1],
+    pipeline: RenderPipeline,
+    descriptor: DescriptorSet,
+}
+
+impl RenderNode for TintPass {
+    fn label() -> &'static str {
+        "Tint"
+    }
+
+    fn stage(&self) -> Stage {
+        Stage::PostProcess
+    }
+
+    fn setup(rcx: &RenderContext, _: &mut RenderGraphContext) -> Self {
+        let target = rcx.device().create_texture(TextureCreateInfo {
+            label: Some("Tint_Target"),
+            width: 16,
+            height: 16,
+            format: TextureFormat::RGBA8,
+            usage: TextureUsage::RENDER_ATTACHMENT,
+            sample_count: 1,
+            mip_level: 1,
+        });
+
+        let layout = rcx
+            .device()
+            .create_descriptor_set_layout(DescriptorSetLayoutDescriptor {
+                label: Some("Tint"),
+                visibility: StageFlags::FRAGMENT,
+                layout: &[DescriptorBindingType::UniformBuffer],
+            });
+        let tint = rcx.device().create_uniform_buffer(&[1.0f32, 0.5, 0.0, 1.0]);
+        let descriptor = rcx
+            .device()
+            .build_descriptor_set(DescriptorSet::builder(&layout).uniform(0, &tint));
+
+        let shader = GraphicsShader {
+            vertex: rcx.device().compile_shader(TINT_VERT.into()).unwrap(),
+            fragment: rcx.device().compile_shader(TINT_FRAG.into()).unwrap(),
+        };
+        let pipeline = rcx.device().create_pipeline(PipelineCreateInfo {
+            label: Some("Tint"),
+            layout: rcx.device().create_pipeline_layout(&[layout]),
+            shader,
+            color_formats: &[TextureFormat::RGBA8],
+            depth: DepthMode::None,
+            cull_mode: CullMode::None,
+            alpha_mode: AlphaMode::Opaque,
+            sample_count: 1,
+            vertex_buffer_layout: None,
+        });
+
+        Self {
+            target: [RenderTarget::Texture(target.create_view())],
+            pipeline,
+            descriptor,
+        }
+    }
+
+    fn draw(
+        &mut self,
+        _: &RenderContext,
+        frame: &mut Frame,
+        _: &mut RenderGraphContext,
+        _: &GameContext,
+    ) {
+        frame
+            .render(
+                RenderOptions {
+                    label: Some("Tint"),
+                    color_targets: &self.target,
+                    depth_target: None,
+                    clear_color: None,
+                    clear_depth: None,
+                    discard_depth: false,
+                },
+                |mut fb| {
+                    fb.use_pipeline(&self.pipeline)
+                        .bind_descriptor_set(0, &self.descriptor)
+                        .draw(0..3, 0);
+                },
+            )
+            .expect("failed to render the tint");
+    }
+}
+
+#[test]
+fn test_node_setup_and_draw_are_recorded() {
+    let mut renderer = Renderer::init_headless(RenderConfig {
+        backend: Some(GraphicsBackend::Null),
+        ..Default::default()
+    })
+    .expect("the null backend to need no gpu");
+    assert_eq!(renderer.context.graphics_backend(), GraphicsBackend::Null);
+    renderer.context.take_commands();
+
+    renderer.graph().setup_and_add_node::<TintPass>();
+    let setup = renderer.context.take_commands();
+    let [
+        RecordedCommand::CreateTexture {
+            label: Some("Tint_Target"),
+        },
+        RecordedCommand::CreateDescriptorSet(descriptor_set),
+        RecordedCommand::CreatePipeline {
+            label: Some("Tint"),
+            id: pipeline,
+        },
+    ] = setup.as_slice()
+    else {
+        panic!("unexpected setup {setup:#?}");
+    };
+
+    renderer.draw_offscreen(&GameContext::new()).unwrap();
+    assert_eq!(
+        renderer.context.take_commands(),
+        vec![
+            RecordedCommand::BeginNode("Tint".into()),
+            RecordedCommand::BeginRender {
+                label: Some("Tint".into()),
+                color_targets: 1,
+                depth_target: false,
+            },
+            RecordedCommand::UsePipeline(pipeline.clone()),
+            RecordedCommand::BindDescriptorSet {
+                set: 0,
+                descriptor_set: descriptor_set.clone(),
+            },
+            RecordedCommand::Draw {
+                vertices: 0..3,
+                instances: 0..1,
+            },
+        ]
+    );
+
+    // taking the log empties it
+    assert!(renderer.context.take_commands().is_empty());
+}
+
+#[test]
+fn test_graph_is_built_from_a_description() {
+    let mut renderer = Renderer::init_headless(RenderConfig {
+        backend: Some(GraphicsBackend::Null),
+        ..Default::default()
+    })
+    .expect("the null backend to need no gpu");
+
+    let description = GraphDescription::from_json(
+        r#"{
+            "targets": [{ "name": "half_res", "format": "RGBA8", "scale": 0.5 }],
+            "nodes": [{ "name": "Tint", "parameters": { "strength": 2.0 } }]
+        }"#,
+    )
+    .unwrap();
+
+    let mut graph = renderer.graph();
+    assert!(graph.build(&description).is_err(), "tint isn't registered");
+    graph.register_node::<TintPass>();
+    graph.build(&description).unwrap();
+
+    {
+        let graph_ctx = renderer.render_graph.context.read();
+        let half_res = graph_ctx
+            .get_shared_resource::<Texture>("half_res")
+            .unwrap();
+        let surface = renderer.context.surface_size();
+        assert_eq!(half_res.width(), (surface.width / 2).max(1));
+        assert_eq!(half_res.format(), TextureFormat::RGBA8);
+        assert_eq!(graph_ctx.parameter::<f32>("Tint", "strength"), Some(2.0));
+        assert_eq!(graph_ctx.parameter::<String>("Tint", "strength"), None);
+    }
+
+    renderer.context.take_commands();
+    renderer.draw_offscreen(&GameContext::new()).unwrap();
+    assert!(
+        renderer
+            .context
+            .take_commands()
+            .contains(&RecordedCommand::BeginNode("Tint".into()))
+    );
+}
+
+#[test]
+fn test_quality_settings_apply_to_the_context() {
+    let mut renderer = Renderer::init_headless(RenderConfig {
+        backend: Some(GraphicsBackend::Null),
+        ..Default::default()
+    })
+    .expect("the null backend to need no gpu");
+
+    let adapter = renderer.context.adapter_info();
+    assert_eq!(adapter.backend, GraphicsBackend::Null);
+    assert_ne!(QualityPreset::detect(&adapter), QualityPreset::Ultra);
+
+    let low = QualityPreset::Low.settings();
+    let render = low.render_size(Dimensions {
+        width: 1920,
+        height: 1080,
+    });
+    assert_eq!((render.width, render.height), (1440, 810));
+
+    renderer.set_quality(low);
+    assert_eq!(renderer.context.quality().msaa_samples, 1);
+    assert!(!renderer.context.post_effects().bloom);
+
+    // pipelines in the graph were built for one sample so the count stays
+    renderer.graph().setup_and_add_node::<TintPass>();
+    renderer.set_quality(QualityPreset::High.settings());
+    assert_eq!(renderer.context.quality().msaa_samples, 1);
+    assert_eq!(renderer.context.quality().render_scale, 1.0);
+    assert!(renderer.context.post_effects().bloom);
+}
+
+#[test]
+fn test_ring_buffer_hands_out_aligned_space() {
+    let renderer = Renderer::init_headless(RenderConfig {
+        backend: Some(GraphicsBackend::Null),
+        ..Default::default()
+    })
+    .expect("the null backend to need no gpu");
+
+    let device = renderer.context.device();
+    let ring = device.create_ring_buffer("objects", 1024);
+    let align = ring.alignment() as u32;
+
+    assert_eq!(ring.push(&[1.0f32; 3]), Some(0));
+    assert_eq!(ring.push(&7u32), Some(align));
+    assert_eq!(ring.push_slice(&[0u8; 5]), Some(align * 2));
+
+    // pieces that don't fit are refused until the next frame
+    assert_eq!(ring.push_slice(&vec![0u8; 2048]), None);
+    ring.reset();
+    assert_eq!(ring.used(), 0);
+    assert_eq!(ring.push(&7u32), Some(0));
+
+    let layout = device.create_descriptor_set_layout(DescriptorSetLayoutDescriptor {
+        label: Some("objects"),
+        visibility: StageFlags::VERTEX,
+        layout: &[DescriptorBindingType::DynamicUniformBuffer { min_size: None }],
+    });
+    let set = device.build_descriptor_set(DescriptorSet::builder(&layout).ring(0, &ring, 16));
+    assert!(
+        renderer
+            .context
+            .take_commands()
+            .contains(&RecordedCommand::CreateDescriptorSet(set))
+    );
+}
+
+#[test]
+fn test_ring_buffer_leaves_room_for_the_bound_size() {
+    let renderer = Renderer::init_headless(RenderConfig {
+        backend: Some(GraphicsBackend::Null),
+        ..Default::default()
+    })
+    .expect("the null backend to need no gpu");
+
+    let device = renderer.context.device();
+    let align = device.create_ring_buffer("probe", 0).alignment();
+    let ring = device.create_ring_buffer("objects", align * 4);
+    let layout = device.create_descriptor_set_layout(DescriptorSetLayoutDescriptor {
+        label: Some("objects"),
+        visibility: StageFlags::VERTEX,
+        layout: &[DescriptorBindingType::DynamicUniformBuffer { min_size: None }],
+    });
+    device.build_descriptor_set(DescriptorSet::builder(&layout).ring(0, &ring, align * 2));
+    assert_eq!(ring.binding_size(), align * 2);
+
+    // the last offsets have room for the value but not for the bound window
+    assert_eq!(ring.push(&7u32), Some(0));
+    assert_eq!(ring.push(&7u32), Some(align as u32));
+    assert_eq!(ring.push(&7u32), Some(align as u32 * 2));
+    assert_eq!(ring.push(&7u32), None);
+}
+
+#[test]
+fn test_push_constant_layouts_are_checked() {
+    let renderer = Renderer::init_headless(RenderConfig {
+        backend: Some(GraphicsBackend::Null),
+        ..Default::default()
+    })
+    .expect("the null backend to need no gpu");
+
+    let device = renderer.context.device();
+    assert!(device.supports_push_constants());
+    let max = device.max_push_constant_size();
+    assert!(max >= 64);
+
+    let stages = StageFlags::VERTEX | StageFlags::FRAGMENT;
+    assert!(
+        device
+            .create_pipeline_layout_with_push_constants(&[], &[PushConstantRange::new(stages, 64)])
+            .is_ok()
+    );
+    assert!(
+        device
+            .create_pipeline_layout_with_push_constants(
+                &[],
+                &[PushConstantRange::new(stages, max + 4)]
+            )
+            .is_err()
+    );
+    assert!(
+        device
+            .create_pipeline_layout_with_push_constants(&[], &[PushConstantRange::new(stages, 6)])
+            .is_err()
+    );
+}
+
+#[test]
+fn test_surface_targets_follow_the_surface_size() {
+    let mut renderer = Renderer::init_headless(RenderConfig {
+        backend: Some(GraphicsBackend::Null),
+        ..Default::default()
+    })
+    .expect("the null backend to need no gpu");
+
+    let depth = renderer.context.surface_depth(SurfaceDepth::default());
+    assert_eq!(
+        depth,
+        renderer.context.surface_depth(SurfaceDepth::default())
+    );
+    let color = renderer.context.surface_msaa(1);
+    assert_eq!(color.format(), renderer.context.surface_format());
+    assert!(
+        renderer
+            .context
+            .supports_sample_count(TextureFormat::Depth32, 1)
+    );
+
+    renderer.resize(Dimensions {
+        width: 64,
+        height: 32,
+    });
+    let resized = renderer.context.surface_depth(SurfaceDepth::default());
+    assert_ne!(depth, resized);
+    assert_eq!((resized.width(), resized.height()), (64, 32));
+    assert_eq!(resized.format(), TextureFormat::Depth32);
+    assert_eq!(renderer.context.surface_msaa(1).width(), 64);
+}
+
+#[test]
+fn test_texture_arrays_have_mips_and_views() {
+    let renderer = Renderer::init_headless(RenderConfig {
+        backend: Some(GraphicsBackend::Null),
+        ..Default::default()
+    })
+    .expect("the null backend to need no gpu");
+
+    let array = renderer
+        .context
+        .device()
+        .create_texture_array(TextureArrayCreateInfo {
+            label: Some("terrain layers"),
+            width: 16,
+            height: 8,
+            array_layers: 3,
+            format: TextureFormat::RGBA8,
+            usage: TextureUsage::TEXTURE_BINDING | TextureUsage::COPY_DST,
+            mip_level: 4,
+        });
+    assert_eq!(array.mip_level_count(), 4);
+
+    for layer in 0..array.array_layers() {
+        renderer
+            .context
+            .queue()
+            .write_texture(&array.create_layer_texture(layer), &[255u8; 16 * 8 * 4]);
+    }
+    renderer.context.generate_array_mipmaps(&array);
+
+    // one mip of one layer, read as a plain 2d texture
+    let _ = array.create_view_with(TextureViewOptions {
+        dimension: Some(TextureViewDimension::D2),
+        base_layer: 2,
+        layer_count: Some(1),
+        ..TextureViewOptions::mip(3)
+    });
+    let _ = array
+        .create_layer_texture(1)
+        .create_view_with(TextureViewOptions::mip(1));
+}
+
+#[test]
+fn test_textures_are_created_from_pixels_and_images() {
+    let renderer = Renderer::init_headless(RenderConfig {
+        backend: Some(GraphicsBackend::Null),
+        ..Default::default()
+    })
+    .expect("the null backend to need no gpu");
+    let rcx = &renderer.context;
+
+    let texture = rcx.create_texture_with_data(
+        TextureCreateInfo {
+            label: Some("checker"),
+            width: 8,
+            height: 8,
+            format: TextureFormat::RGBA8,
+            usage: TextureUsage::TEXTURE_BINDING,
+            sample_count: 1,
+            mip_level: 4,
+        },
+        &[128u8; 8 * 8 * 4],
+    );
+    assert_eq!(texture.mip_level_count(), 4);
+    rcx.write_texture(
+        &texture,
+        TextureRegion {
+            x: 2,
+            y: 2,
+            width: 4,
+            height: 4,
+        },
+        &[255u8; 4 * 4 * 4],
+    );
+    rcx.write_texture(&texture, TextureRegion::full(&texture), &[0u8; 8 * 8 * 4]);
+
+    let mut png = Vec::new();
+    image::RgbaImage::from_pixel(16, 4, image::Rgba([255, 0, 0, 255]))
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .unwrap();
+    let loaded = rcx.load_texture_from_bytes(&png, Some("red")).unwrap();
+    assert_eq!((loaded.width(), loaded.height()), (16, 4));
+    assert_eq!(loaded.format(), TextureFormat::RGBA8);
+    assert_eq!(loaded.mip_level_count(), 5);
+    assert!(rcx.load_texture_from_bytes(&[1, 2, 3], None).is_err());
+
+    // rows come back without the copy padding
+    let bytes = rcx.read_texture_bytes(&loaded, 0).unwrap();
+    assert_eq!(bytes.len(), 16 * 4 * 4);
+    // headless renderers have nothing to screenshot
+    assert!(rcx.read_surface().is_err());
+}
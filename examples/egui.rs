@@ -140,6 +140,19 @@ fn scene(assets: &AssetLibrary) -> Scene {
             });
         });
 
+    // tune the post processing uniforms live
+    scene
+        .spawn(Empty::default())
+        .on::<EguiUpdate>(uniform_tweak_panel());
+
+    // select nodes to see what they cost to draw
+    scene.spawn(Empty::default()).on::<EguiUpdate>(inspector());
+
+    // see how much gpu memory the loaded assets take
+    scene
+        .spawn(Empty::default())
+        .on::<EguiUpdate>(gpu_memory_panel());
+
     scene
         .spawn(Empty::default())
         .on::<Update>(|ctx| {
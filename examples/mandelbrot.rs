@@ -1,4 +1,4 @@
-use std::{slice, time::Instant};
+use std::slice;
 
 use bytemuck::{Pod, Zeroable};
 use maple::maple_3d::math::Vertex;
@@ -69,6 +69,7 @@ impl RenderNode for ShowPass {
                 tex_uv: [0.0, 0.0],
                 tangent: [1.0, 0.0, 0.0],
                 bitangent: [0.0, 1.0, 0.0],
+                ..Default::default()
             },
             Vertex {
                 position: [3.0, -1.0, 0.0],
@@ -76,6 +77,7 @@ impl RenderNode for ShowPass {
                 tex_uv: [2.0, 0.0],
                 tangent: [1.0, 0.0, 0.0],
                 bitangent: [0.0, 1.0, 0.0],
+                ..Default::default()
             },
             Vertex {
                 position: [-1.0, 3.0, 0.0],
@@ -83,6 +85,7 @@ impl RenderNode for ShowPass {
                 tex_uv: [0.0, 2.0],
                 tangent: [1.0, 0.0, 0.0],
                 bitangent: [0.0, 1.0, 0.0],
+                ..Default::default()
             },
         ];
 
@@ -162,6 +165,7 @@ impl RenderNode for ShowPass {
                     depth_target: None,
                     clear_color: Some([0.0, 0.0, 0.0, 1.0]),
                     clear_depth: None,
+                    discard_depth: false,
                 },
                 |mut fb| {
                     fb.use_pipeline(pipeline)
@@ -182,7 +186,6 @@ struct MainPass {
     params: Params,
     param_buffer: Buffer<Params>,
     descriptor_set: DescriptorSet,
-    time: Instant,
 }
 
 impl RenderNode for MainPass {
@@ -197,6 +200,7 @@ impl RenderNode for MainPass {
                 tex_uv: [0.0, 0.0],
                 tangent: [1.0, 0.0, 0.0],
                 bitangent: [0.0, 1.0, 0.0],
+                ..Default::default()
             },
             Vertex {
                 position: [3.0, -1.0, 0.0],
@@ -204,6 +208,7 @@ impl RenderNode for MainPass {
                 tex_uv: [2.0, 0.0],
                 tangent: [1.0, 0.0, 0.0],
                 bitangent: [0.0, 1.0, 0.0],
+                ..Default::default()
             },
             Vertex {
                 position: [-1.0, 3.0, 0.0],
@@ -211,6 +216,7 @@ impl RenderNode for MainPass {
                 tex_uv: [0.0, 2.0],
                 tangent: [1.0, 0.0, 0.0],
                 bitangent: [0.0, 1.0, 0.0],
+                ..Default::default()
             },
         ];
         let params = Params {
@@ -328,22 +334,20 @@ impl RenderNode for MainPass {
             pipeline,
             descriptor_set,
             params,
-            time: Instant::now(),
         }
     }
     fn draw(
         &mut self,
         rcx: &RenderContext,
         frame: &mut Frame,
-        _graph_ctx: &mut maple_renderer::render_graph::graph::RenderGraphContext,
+        graph_ctx: &mut maple_renderer::render_graph::graph::RenderGraphContext,
         _scene: &GameContext,
     ) {
-        let dt = self.time.elapsed().as_secs_f32();
+        let dt = graph_ctx.frame_info().delta;
 
         let fps = 1.0 / dt;
 
         println!("fps: {fps}");
-        self.time = Instant::now();
 
         self.params.zoom *= 0.99_f32.powf(dt * 60.0);
         println!("zoom: {}", self.params.zoom);
@@ -362,6 +366,7 @@ impl RenderNode for MainPass {
                     depth_target: None,
                     clear_color: None,
                     clear_depth: None,
+                    discard_depth: false,
                 },
                 |mut fb| {
                     fb.use_pipeline(pipeline)
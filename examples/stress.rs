@@ -0,0 +1,60 @@
+use maple::engine::utils::{random::weighted_choice, stress::StressScene};
+use maple::prelude::*;
+
+fn main() {
+    App::new(Config::default())
+        .add_plugin(Core3D)
+        .load_scene(MainScene)
+        .run();
+}
+
+pub struct MainScene;
+
+impl SceneBuilder for MainScene {
+    fn build(self, assets: &AssetLibrary) -> Scene {
+        let scene = Scene::default();
+
+        scene
+            .spawn(
+                Camera3D::builder()
+                    .position((-150.0, 120.0, -150.0))
+                    .looking_at((0.0, 0.0, 0.0))
+                    .build(),
+            )
+            .on::<FixedUpdate>(|ctx| {
+                println!("fps: {}", ctx.get_resource::<Frame>().fps);
+            })
+            .on::<Update>(Camera3D::free_fly(20.0, 0.5));
+
+        scene.spawn(
+            DirectionalLight::builder()
+                .direction((0.1, -1.0, 0.1))
+                .intensity(10.0)
+                .build(),
+        );
+
+        let meshes = [
+            (assets.add::<Mesh3D>(Cuboid::default()), 1.0),
+            (assets.add::<Mesh3D>(Sphere::default()), 1.0),
+            (assets.add::<Mesh3D>(Torus::default()), 1.0),
+        ];
+        let materials = [
+            (assets.add::<Material>(Color::RED), 1.0),
+            (assets.add::<Material>(Color::GREEN), 1.0),
+            (assets.add::<Material>(Color::CYAN), 1.0),
+        ];
+
+        // thousands of meshes in random hierarchies that keep spawning, despawning and moving
+        StressScene::new(5_000)
+            .seed(42)
+            .random_events(true)
+            .populate_with(&scene, |rng| {
+                MeshInstance3D::builder()
+                    .mesh(weighted_choice(rng, &meshes).unwrap().clone())
+                    .material(weighted_choice(rng, &materials).unwrap().clone())
+                    .build()
+            });
+
+        scene
+    }
+}
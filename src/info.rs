@@ -0,0 +1,200 @@
+use std::fmt;
+
+use maple_renderer::{core::RenderContext, types::render_config::AdapterInfo};
+
+/// the name and version of one of the engine's crates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrateInfo {
+    pub name: &'static str,
+    pub version: &'static str,
+}
+
+/// what the engine was built with and is running on, see [`engine_info`]
+#[derive(Debug, Clone)]
+pub struct EngineInfo {
+    /// the version of the `maple` crate
+    pub version: &'static str,
+    /// every engine crate that was built in
+    pub crates: Vec<CrateInfo>,
+    /// the enabled cargo features, features of the sub crates are prefixed with the crate such as
+    /// `audio/physics`
+    pub features: Vec<String>,
+    /// the target os and architecture, like `linux x86_64`
+    pub target: String,
+    /// true for debug builds
+    pub debug: bool,
+    /// the gpu and graphics api being drawn with, `None` before the app has started
+    pub adapter: Option<AdapterInfo>,
+}
+
+impl EngineInfo {
+    /// true if the cargo feature `feature` is enabled, see [`EngineInfo::features`]
+    pub fn has_feature(&self, feature: &str) -> bool {
+        self.features.iter().any(|enabled| enabled == feature)
+    }
+
+    /// the version of the engine crate called `name`, like `maple_3d`
+    pub fn crate_version(&self, name: &str) -> Option<&'static str> {
+        self.crates
+            .iter()
+            .find(|info| info.name == name)
+            .map(|info| info.version)
+    }
+}
+
+impl fmt::Display for EngineInfo {
+    /// a report with everything on its own line for bug reports and logs
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let build = match self.debug {
+            true => "debug",
+            false => "release",
+        };
+        writeln!(f, "maple {} ({build}, {})", self.version, self.target)?;
+
+        let crates: Vec<String> = self
+            .crates
+            .iter()
+            .map(|info| format!("{} {}", info.name, info.version))
+            .collect();
+        writeln!(f, "crates: {}", crates.join(", "))?;
+
+        match self.features.is_empty() {
+            true => writeln!(f, "features: none")?,
+            false => writeln!(f, "features: {}", self.features.join(", "))?,
+        }
+
+        match &self.adapter {
+            Some(adapter) => {
+                writeln!(f, "backend: {:?}", adapter.backend)?;
+                write!(f, "adapter: {} ({:?})", adapter.name, adapter.device_type)?;
+                if !adapter.driver.is_empty() {
+                    write!(f, ", driver {}", adapter.driver)?;
+                }
+                Ok(())
+            }
+            None => write!(f, "backend: not started"),
+        }
+    }
+}
+
+/// the versions, features and gpu of the running engine, for bug reports, about screens and
+/// plugins that change what they do with the features that are enabled
+///
+/// ```rust,ignore
+/// let info = maple::engine_info();
+/// log::info!("{info}");
+///
+/// if info.has_feature("physics") {
+///     // ...
+/// }
+/// ```
+pub fn engine_info() -> EngineInfo {
+    #[allow(unused_mut, reason = "the optional crates add to them")]
+    let mut crates = vec![
+        CrateInfo {
+            name: "maple",
+            version: env!("CARGO_PKG_VERSION"),
+        },
+        CrateInfo {
+            name: "maple_app",
+            version: maple_app::VERSION,
+        },
+        CrateInfo {
+            name: "maple_engine",
+            version: maple_engine::VERSION,
+        },
+        CrateInfo {
+            name: "maple_renderer",
+            version: maple_renderer::VERSION,
+        },
+    ];
+    #[allow(unused_mut, reason = "the optional crates add to them")]
+    let mut features: Vec<String> = Vec::new();
+
+    #[cfg(feature = "3d")]
+    {
+        crates.push(CrateInfo {
+            name: "maple_3d",
+            version: maple_3d::VERSION,
+        });
+        features.push("3d".into());
+    }
+    #[cfg(feature = "physics")]
+    {
+        crates.push(CrateInfo {
+            name: "maple_physics",
+            version: maple_physics::VERSION,
+        });
+        features.push("physics".into());
+    }
+    #[cfg(feature = "audio")]
+    {
+        crates.push(CrateInfo {
+            name: "maple_audio",
+            version: maple_audio::VERSION,
+        });
+        features.push("audio".into());
+        features.extend(
+            maple_audio::FEATURES
+                .iter()
+                .map(|feature| format!("audio/{feature}")),
+        );
+    }
+    #[cfg(feature = "gameplay")]
+    {
+        crates.push(CrateInfo {
+            name: "maple_gameplay",
+            version: maple_gameplay::VERSION,
+        });
+        features.push("gameplay".into());
+        features.extend(
+            maple_gameplay::FEATURES
+                .iter()
+                .map(|feature| format!("gameplay/{feature}")),
+        );
+    }
+
+    EngineInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        crates,
+        features,
+        target: format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+        debug: cfg!(debug_assertions),
+        adapter: RenderContext::current_adapter(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_engine_info_reports_the_build() {
+        let info = engine_info();
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(
+            info.crate_version("maple_engine"),
+            Some(maple_engine::VERSION)
+        );
+        assert_eq!(info.crate_version("maple_unknown"), None);
+
+        assert_eq!(info.has_feature("3d"), cfg!(feature = "3d"));
+        assert_eq!(info.has_feature("physics"), cfg!(feature = "physics"));
+        assert_eq!(info.has_feature("audio"), cfg!(feature = "audio"));
+        assert_eq!(info.has_feature("gameplay"), cfg!(feature = "gameplay"));
+        assert_eq!(
+            info.crate_version("maple_3d").is_some(),
+            cfg!(feature = "3d")
+        );
+    }
+
+    #[test]
+    fn test_engine_info_before_the_renderer_started() {
+        let info = engine_info();
+        assert!(info.adapter.is_none());
+
+        let report = info.to_string();
+        assert!(report.starts_with(&format!("maple {}", info.version)));
+        assert!(report.ends_with("backend: not started"));
+    }
+}
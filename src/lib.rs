@@ -13,12 +13,19 @@ pub use maple_app as app;
 #[cfg(feature = "audio")]
 pub use maple_audio as audio;
 
+/// common gameplay systems such as dialogue
+#[cfg(feature = "gameplay")]
+pub use maple_gameplay as gameplay;
+
 /// derive macros
 pub use maple_derive as derive;
 
 /// core engine implementation
 pub use maple_engine as engine;
 
+/// save and cache folders and safe file writes
+pub use maple_engine::fs;
+
 /// physics with [`rapier3d`]
 #[cfg(feature = "physics")]
 pub use maple_physics as physics;
@@ -26,10 +33,13 @@ pub use maple_physics as physics;
 /// core renderer implementation
 pub use maple_renderer as renderer;
 
+mod info;
+pub use info::{CrateInfo, EngineInfo, engine_info};
+
 /// the prelude exposes almost everything you need to get started
 pub mod prelude {
     pub use crate::app::prelude::*;
-    pub use crate::derive::Node;
+    pub use crate::derive::{Node, ShaderParams};
     pub use crate::engine::prelude::*;
     pub use crate::renderer::prelude::*;
 
@@ -42,6 +52,9 @@ pub mod prelude {
     #[cfg(feature = "audio")]
     pub use crate::audio::prelude::*;
 
+    #[cfg(feature = "gameplay")]
+    pub use crate::gameplay::prelude::*;
+
     /// re-export glam as math
     use glam as math;
     pub use math::{Mat4, Quat, Vec2, Vec3, Vec4};
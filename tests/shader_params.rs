@@ -0,0 +1,99 @@
+//! the `ShaderParams` derive checked against shaders with `ShaderLayout`
+
+use maple::derive::ShaderParams;
+use maple::renderer::core::{LayoutError, ParamField, ShaderLayout, ShaderParams};
+
+const SHADER: &str = r#"
+struct Light {
+    position: vec3<f32>,
+    intensity: f32,
+}
+
+struct Params {
+    color: vec3<f32>,
+    _pad: f32,
+    light: Light,
+    time: f32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+"#;
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, ShaderParams)]
+struct Light {
+    position: [f32; 3],
+    intensity: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, ShaderParams)]
+struct Params {
+    color: [f32; 3],
+    _padding: f32,
+    light: Light,
+    time: f32,
+    pad0: [f32; 3],
+}
+
+/// forgets the padding after `color`
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, ShaderParams)]
+struct Unpadded {
+    color: [f32; 3],
+    light: Light,
+    time: f32,
+    pad0: [f32; 3],
+}
+
+#[test]
+fn test_derived_fields() {
+    assert_eq!(
+        Params::FIELDS,
+        &[
+            ParamField {
+                name: "color",
+                ty: "[f32;3]",
+                offset: 0,
+                size: 12,
+            },
+            ParamField {
+                name: "_padding",
+                ty: "f32",
+                offset: 12,
+                size: 4,
+            },
+            ParamField {
+                name: "light",
+                ty: "Light",
+                offset: 16,
+                size: 16,
+            },
+            ParamField {
+                name: "time",
+                ty: "f32",
+                offset: 32,
+                size: 4,
+            },
+            ParamField {
+                name: "pad0",
+                ty: "[f32;3]",
+                offset: 36,
+                size: 12,
+            },
+        ]
+    );
+    assert!(Params::FIELDS[1].is_padding() && Params::FIELDS[4].is_padding());
+}
+
+#[test]
+fn test_derived_structs_match_the_shader() {
+    let layout = ShaderLayout::from_source(SHADER).unwrap();
+    layout.check_binding::<Params>(0, 0).unwrap();
+    layout.check_struct::<Light>("Light").unwrap();
+
+    assert!(matches!(
+        layout.check_binding::<Unpadded>(0, 0),
+        Err(LayoutError::Offset { ref field, rust: 12, shader: 16 }) if field == "light"
+    ));
+}